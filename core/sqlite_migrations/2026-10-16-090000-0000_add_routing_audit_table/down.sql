@@ -0,0 +1,4 @@
+DROP INDEX IF EXISTS idx_routing_audit_project_time;
+DROP INDEX IF EXISTS idx_routing_audit_trace_id;
+
+DROP TABLE IF EXISTS routing_audit;
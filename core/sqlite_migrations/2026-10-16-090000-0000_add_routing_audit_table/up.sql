@@ -0,0 +1,16 @@
+-- Append-only record of routing and interceptor decisions
+CREATE TABLE routing_audit (
+    id TEXT PRIMARY KEY NOT NULL,
+    timestamp_us BIGINT NOT NULL,
+    project_id TEXT,
+    trace_id TEXT,
+    router_name TEXT NOT NULL,
+    strategy TEXT NOT NULL,
+    matched_route TEXT,
+    targets TEXT NOT NULL, -- JSON stored as text
+    interceptors TEXT NOT NULL, -- JSON stored as text
+    final_model TEXT
+);
+
+CREATE INDEX idx_routing_audit_trace_id ON routing_audit(trace_id);
+CREATE INDEX idx_routing_audit_project_time ON routing_audit(project_id, timestamp_us);
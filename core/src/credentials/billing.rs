@@ -0,0 +1,19 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Billing entities usage of own credentials is charged back to, by project slug
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BillingLabelsConfig {
+    /// Applies to projects without their own label
+    #[serde(default)]
+    pub default: Option<String>,
+    #[serde(default)]
+    pub projects: HashMap<String, String>,
+}
+
+impl BillingLabelsConfig {
+    pub fn for_project(&self, project_slug: &str) -> Option<&String> {
+        self.projects.get(project_slug).or(self.default.as_ref())
+    }
+}
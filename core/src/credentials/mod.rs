@@ -1,3 +1,4 @@
+pub mod billing;
 mod storage;
 
 use async_trait::async_trait;
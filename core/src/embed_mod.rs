@@ -132,6 +132,7 @@ impl OpenAIEmbed {
                     finish_reason: ModelFinishReason::Stop,
                     tool_calls: vec![],
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             )))
             .await
@@ -1,11 +1,11 @@
 use crate::http::status::GuardValidationFailed;
 use crate::types::guardrails::GuardError;
-use actix_web::http::header::ContentType;
+use actix_web::http::header::{ContentType, RETRY_AFTER};
 use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
 use serde_json::json;
 use thiserror::Error;
-use vllora_llm::client::error::ModelError;
+use vllora_llm::client::error::{ModelError, ProviderErrorClass};
 use vllora_llm::error::LLMError;
 use vllora_llm::mcp::McpServerError;
 use vllora_llm::types::ModelEvent;
@@ -45,12 +45,146 @@ pub enum GatewayError {
     #[error("Unsupported provider: {0}")]
     UnsupportedProvider(String),
     #[error(transparent)]
-    LLMError(#[from] LLMError),
+    LLMError(LLMError),
+    #[error("Rate limit exceeded: {message}")]
+    RateLimited {
+        message: String,
+        retry_after: Option<u64>,
+    },
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+    #[error("Invalid request: {param} {message}")]
+    InvalidParameter { param: String, message: String },
+    #[error("Provider unavailable: {message}")]
+    ProviderUnavailable {
+        message: String,
+        retry_after: Option<u64>,
+    },
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+    #[error("Policy denied: {0}")]
+    PolicyDenied(String),
+    #[error("Gateway overloaded: {0}")]
+    Overloaded(String),
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
+    #[error("Replayed request rejected: {0}")]
+    ReplayRejected(String),
+    #[error("Idempotency key reused: {0}")]
+    IdempotencyKeyReused(String),
+}
+
+impl GatewayError {
+    pub fn from_provider_error(class: ProviderErrorClass, message: String) -> Self {
+        match class {
+            ProviderErrorClass::RateLimit { retry_after } => GatewayError::RateLimited {
+                message,
+                retry_after,
+            },
+            ProviderErrorClass::InvalidRequest => GatewayError::InvalidRequest(message),
+            ProviderErrorClass::ProviderUnavailable { retry_after } => {
+                GatewayError::ProviderUnavailable {
+                    message,
+                    retry_after,
+                }
+            }
+            ProviderErrorClass::ContextLengthExceeded => {
+                GatewayError::ContextLengthExceeded(message)
+            }
+        }
+    }
+
+    /// Machine readable `error.type` for classified provider errors
+    pub fn error_type(&self) -> Option<&'static str> {
+        match self {
+            GatewayError::RateLimited { .. } => Some("rate_limit"),
+            GatewayError::InvalidRequest(_) | GatewayError::InvalidParameter { .. } => {
+                Some("invalid_request")
+            }
+            GatewayError::ProviderUnavailable { .. } => Some("provider_unavailable"),
+            GatewayError::ContextLengthExceeded(_) => Some("context_length_exceeded"),
+            GatewayError::PolicyDenied(_) => Some("policy_denied"),
+            GatewayError::Overloaded(_) => Some("overloaded"),
+            GatewayError::PayloadTooLarge(_) => Some("payload_too_large"),
+            GatewayError::ReplayRejected(_) => Some("replay_rejected"),
+            GatewayError::IdempotencyKeyReused(_) => Some("idempotency_key_reused"),
+            _ => None,
+        }
+    }
+
+    /// Request parameter the error is about, e.g. `messages[2].role`
+    pub fn param(&self) -> Option<&str> {
+        match self {
+            GatewayError::InvalidParameter { param, .. } => Some(param),
+            _ => None,
+        }
+    }
+
+    /// Copy of errors made of plain values, which covers every classified error
+    pub fn try_clone(&self) -> Option<GatewayError> {
+        Some(match self {
+            GatewayError::MissingVariable(v) => GatewayError::MissingVariable(v.clone()),
+            GatewayError::CustomError(message) => GatewayError::CustomError(message.clone()),
+            GatewayError::UnsupportedProvider(p) => GatewayError::UnsupportedProvider(p.clone()),
+            GatewayError::RateLimited {
+                message,
+                retry_after,
+            } => GatewayError::RateLimited {
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
+            GatewayError::InvalidRequest(message) => GatewayError::InvalidRequest(message.clone()),
+            GatewayError::InvalidParameter { param, message } => GatewayError::InvalidParameter {
+                param: param.clone(),
+                message: message.clone(),
+            },
+            GatewayError::ProviderUnavailable {
+                message,
+                retry_after,
+            } => GatewayError::ProviderUnavailable {
+                message: message.clone(),
+                retry_after: *retry_after,
+            },
+            GatewayError::ContextLengthExceeded(message) => {
+                GatewayError::ContextLengthExceeded(message.clone())
+            }
+            GatewayError::PolicyDenied(message) => GatewayError::PolicyDenied(message.clone()),
+            GatewayError::Overloaded(message) => GatewayError::Overloaded(message.clone()),
+            GatewayError::PayloadTooLarge(message) => {
+                GatewayError::PayloadTooLarge(message.clone())
+            }
+            GatewayError::ReplayRejected(message) => GatewayError::ReplayRejected(message.clone()),
+            GatewayError::IdempotencyKeyReused(message) => {
+                GatewayError::IdempotencyKeyReused(message.clone())
+            }
+            _ => return None,
+        })
+    }
+
+    fn retry_after(&self) -> Option<u64> {
+        match self {
+            GatewayError::RateLimited { retry_after, .. }
+            | GatewayError::ProviderUnavailable { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
 }
 
 impl From<ModelError> for GatewayError {
     fn from(value: ModelError) -> Self {
-        GatewayError::ModelError(Box::new(value))
+        match value.classify() {
+            Some(class) => GatewayError::from_provider_error(class, value.to_string()),
+            None => GatewayError::ModelError(Box::new(value)),
+        }
+    }
+}
+
+impl From<LLMError> for GatewayError {
+    fn from(value: LLMError) -> Self {
+        match value.classify() {
+            Some(class) => GatewayError::from_provider_error(class, value.to_string()),
+            None => GatewayError::LLMError(value),
+        }
     }
 }
 
@@ -71,6 +205,22 @@ impl actix_web::error::ResponseError for GatewayError {
         tracing::error!("API error: {:?}", self);
         match self {
             GatewayError::GuardError(e) => e.error_response(),
+            e if e.error_type().is_some() => {
+                let json_error = json!({
+                    "error": {
+                        "message": e.to_string(),
+                        "type": e.error_type(),
+                        "param": e.param(),
+                    }
+                });
+
+                let mut response = HttpResponse::build(e.status_code());
+                response.insert_header(ContentType::json());
+                if let Some(retry_after) = e.retry_after() {
+                    response.insert_header((RETRY_AFTER, retry_after.to_string()));
+                }
+                response.json(json_error)
+            }
             e => {
                 let json_error = json!({
                     "error": e.to_string(),
@@ -88,7 +238,76 @@ impl actix_web::error::ResponseError for GatewayError {
             GatewayError::GuardError(GuardError::GuardNotPassed(_, _)) => {
                 GuardValidationFailed::status_code()
             }
+            GatewayError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+            GatewayError::InvalidRequest(_)
+            | GatewayError::InvalidParameter { .. }
+            | GatewayError::ContextLengthExceeded(_) => StatusCode::BAD_REQUEST,
+            GatewayError::ProviderUnavailable { .. } | GatewayError::Overloaded(_) => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            GatewayError::PolicyDenied(_) => StatusCode::FORBIDDEN,
+            GatewayError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            GatewayError::ReplayRejected(_) => StatusCode::UNAUTHORIZED,
+            GatewayError::IdempotencyKeyReused(_) => StatusCode::UNPROCESSABLE_ENTITY,
             _ => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::ResponseError;
+    use async_openai::error::{ApiError, OpenAIError};
+    use vllora_llm::client::error::BedrockError;
+
+    #[test]
+    fn test_openai_rate_limit_maps_to_rate_limited() {
+        let error = GatewayError::from(ModelError::from(OpenAIError::ApiError(ApiError {
+            message: "Rate limit reached for requests".to_string(),
+            r#type: Some("requests".to_string()),
+            param: None,
+            code: Some("rate_limit_exceeded".to_string()),
+        })));
+
+        assert!(matches!(error, GatewayError::RateLimited { .. }));
+        assert_eq!(error.status_code(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(error.error_type(), Some("rate_limit"));
+    }
+
+    #[test]
+    fn test_bedrock_validation_maps_to_context_length() {
+        let error = GatewayError::from(LLMError::from(ModelError::from(
+            BedrockError::ValidationError("Input is too long for requested model.".to_string()),
+        )));
+
+        assert!(matches!(error, GatewayError::ContextLengthExceeded(_)));
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_unclassified_model_error_is_kept() {
+        let error = GatewayError::from(ModelError::ModelNotFound("unknown".to_string()));
+
+        assert!(matches!(error, GatewayError::ModelError(_)));
+        assert_eq!(error.error_type(), None);
+        assert_eq!(error.status_code(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn test_error_response_sets_type_and_retry_after() {
+        let error = GatewayError::ProviderUnavailable {
+            message: "overloaded".to_string(),
+            retry_after: Some(30),
+        };
+
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "30");
+
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["type"], "provider_unavailable");
+    }
+}
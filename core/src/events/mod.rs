@@ -109,6 +109,25 @@ pub fn map_cloud_event_to_agui_events(value: &GatewayEvent) -> Vec<Event> {
                     });
                     events
                 }
+                ModelEventType::LlmReasoning(reasoning_event) => {
+                    vec![Event::Custom {
+                        run_context: value.clone().into(),
+                        custom_event: CustomEventType::LlmReasoning {
+                            delta: reasoning_event.content.clone(),
+                        },
+                        timestamp: event_info.timestamp.timestamp_millis() as u64,
+                    }]
+                }
+                ModelEventType::LlmInterimUsage(usage_event) => {
+                    vec![Event::Custom {
+                        run_context: value.clone().into(),
+                        custom_event: CustomEventType::LlmInterimUsage {
+                            output_tokens: usage_event.output_tokens,
+                            estimated: usage_event.estimated,
+                        },
+                        timestamp: event_info.timestamp.timestamp_millis() as u64,
+                    }]
+                }
                 ModelEventType::ToolStart(tool_start) => {
                     vec![Event::ToolCallStart {
                         run_context: value.clone().into(),
@@ -83,17 +83,11 @@ pub async fn execute(
     } else {
         (None, None)
     };
+    let rate_limit = u.as_ref().and_then(|u| u.rate_limit.clone());
     let model_usage = u.and_then(|u| u.usage);
     let is_cache_used = model_usage.as_ref().map(|u| u.is_cache_used);
     let usage: ChatCompletionUsage = match model_usage {
-        Some(u) => ChatCompletionUsage {
-            prompt_tokens: u.input_tokens as i32,
-            completion_tokens: u.output_tokens as i32,
-            total_tokens: u.total_tokens as i32,
-            prompt_tokens_details: u.prompt_tokens_details.clone(),
-            completion_tokens_details: u.completion_tokens_details.clone(),
-            cost: 0.0,
-        },
+        Some(u) => ChatCompletionUsage::from(&u),
         None => ChatCompletionUsage {
             ..Default::default()
         },
@@ -110,9 +104,11 @@ pub async fn execute(
             index: 0,
             message: response.message().clone(),
             finish_reason: Some(finish_reason.clone()),
+            redaction: None,
         }],
         usage,
         is_cache_used,
+        rate_limit,
     };
 
     Ok(response)
@@ -0,0 +1,264 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use tokio::sync::watch;
+use vllora_llm::types::gateway::ChatCompletionResponse;
+
+use crate::GatewayApiError;
+
+/// Response header set on responses shared from another in-flight identical request
+pub const COALESCED_HEADER: &str = "x-vllora-coalesced";
+
+/// Outcome of joining the in-flight call for a request
+pub enum Flight<'a, T> {
+    /// An identical request was already in flight, this is its result
+    Shared(T),
+    /// The caller is the first with this key and must call the provider
+    Lead(FlightGuard<'a, T>),
+}
+
+/// Single-flight calls by request key.
+///
+/// Identical requests arriving while the first one is in flight wait for it and share its
+/// result. When the first request is dropped before completing, one of the waiting requests
+/// takes over.
+pub struct RequestCoalescer<T> {
+    in_flight: Mutex<HashMap<String, watch::Receiver<Option<T>>>>,
+}
+
+impl<T> Default for RequestCoalescer<T> {
+    fn default() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl<T: Clone> RequestCoalescer<T> {
+    pub async fn join(&self, key: &str) -> Flight<'_, T> {
+        loop {
+            let mut in_flight = {
+                let mut calls = self.in_flight.lock().unwrap_or_else(|e| e.into_inner());
+                match calls.get(key) {
+                    Some(rx) => rx.clone(),
+                    None => {
+                        let (tx, rx) = watch::channel(None);
+                        calls.insert(key.to_string(), rx);
+                        return Flight::Lead(FlightGuard {
+                            coalescer: self,
+                            key: key.to_string(),
+                            tx,
+                        });
+                    }
+                }
+            };
+
+            // Fails when the leader is dropped without a result, then the key is joined again
+            if let Ok(result) = in_flight.wait_for(Option::is_some).await {
+                if let Some(result) = result.as_ref() {
+                    return Flight::Shared(result.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Held by the request calling the provider for a key. Dropping it without
+/// [`FlightGuard::complete`] hands the call over to a waiting request.
+pub struct FlightGuard<'a, T> {
+    coalescer: &'a RequestCoalescer<T>,
+    key: String,
+    tx: watch::Sender<Option<T>>,
+}
+
+impl<T> FlightGuard<'_, T> {
+    /// Shares `result` with the waiting requests and returns how many there were
+    pub fn complete(self, result: T) -> usize {
+        self.release();
+        let shared_with = self.tx.receiver_count();
+        self.tx.send_replace(Some(result));
+        shared_with
+    }
+
+    fn release(&self) {
+        let mut calls = self
+            .coalescer
+            .in_flight
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if calls
+            .get(&self.key)
+            .is_some_and(|rx| rx.same_channel(&self.tx.subscribe()))
+        {
+            calls.remove(&self.key);
+        }
+    }
+}
+
+impl<T> Drop for FlightGuard<'_, T> {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+/// Error of a coalesced call, cloned for every request sharing it with
+/// [`GatewayApiError::to_shared`] so they fail with the same status and `Retry-After`
+#[derive(Debug)]
+pub struct SharedError(pub GatewayApiError);
+
+impl Clone for SharedError {
+    fn clone(&self) -> Self {
+        SharedError(self.0.to_shared())
+    }
+}
+
+impl From<SharedError> for GatewayApiError {
+    fn from(value: SharedError) -> Self {
+        value.0
+    }
+}
+
+/// Process-wide in-flight chat completions
+pub fn request_coalescer() -> &'static RequestCoalescer<Result<ChatCompletionResponse, SharedError>>
+{
+    static COALESCER: OnceLock<RequestCoalescer<Result<ChatCompletionResponse, SharedError>>> =
+        OnceLock::new();
+    COALESCER.get_or_init(RequestCoalescer::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::chat_completion::response_cache::{
+        is_coalescable, request_hash, ResponseCacheConfig,
+    };
+    use crate::routing::RoutingStrategy;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use vllora_llm::types::gateway::ChatCompletionRequestWithTools;
+
+    fn request(temperature: f32) -> ChatCompletionRequestWithTools<RoutingStrategy> {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o-mini",
+            "messages": [{"role": "user", "content": "What is 2 + 2?"}],
+            "temperature": temperature,
+        }))
+        .unwrap()
+    }
+
+    async fn call_provider(
+        coalescer: &RequestCoalescer<String>,
+        calls: &AtomicUsize,
+        shared: &AtomicUsize,
+        key: &str,
+    ) -> String {
+        match coalescer.join(key).await {
+            Flight::Shared(result) => result,
+            Flight::Lead(guard) => {
+                calls.fetch_add(1, Ordering::SeqCst);
+                // Keep the call in flight while the identical requests arrive
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                let result = "4".to_string();
+                shared.fetch_add(guard.complete(result.clone()), Ordering::SeqCst);
+                result
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_identical_concurrent_requests_share_one_provider_call() {
+        // Coalescing is opt-in
+        assert!(!is_coalescable(
+            &request(0.0),
+            &ResponseCacheConfig::default()
+        ));
+
+        let config = ResponseCacheConfig {
+            coalesce: true,
+            ..Default::default()
+        };
+        assert!(is_coalescable(&request(0.0), &config));
+        assert!(!is_coalescable(&request(0.7), &config));
+
+        let coalescer = RequestCoalescer::default();
+        let calls = AtomicUsize::new(0);
+        let shared = AtomicUsize::new(0);
+        let key = request_hash(&request(0.0)).unwrap();
+
+        let results = futures::future::join_all(
+            (0..10).map(|_| call_provider(&coalescer, &calls, &shared, &key)),
+        )
+        .await;
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(shared.load(Ordering::SeqCst), 9);
+        assert!(results.iter().all(|result| result == "4"));
+
+        // Once completed, the next identical request calls the provider again
+        call_provider(&coalescer, &calls, &shared, &key).await;
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_waiting_request_takes_over_dropped_call() {
+        let coalescer = RequestCoalescer::<String>::default();
+        let Flight::Lead(first) = coalescer.join("key").await else {
+            panic!("first request must lead");
+        };
+
+        let (waiting, _) = tokio::join!(coalescer.join("key"), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            drop(first);
+        });
+
+        let Flight::Lead(second) = waiting else {
+            panic!("waiting request must take over");
+        };
+        assert_eq!(second.complete("4".to_string()), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shared_errors_keep_status_and_retry_after() {
+        use crate::error::GatewayError;
+        use actix_web::http::header::RETRY_AFTER;
+        use actix_web::http::StatusCode;
+        use actix_web::ResponseError;
+
+        let coalescer = RequestCoalescer::<Result<String, SharedError>>::default();
+        let Flight::Lead(lead) = coalescer.join("key").await else {
+            panic!("first request must lead");
+        };
+
+        let (waiting, _) = tokio::join!(coalescer.join("key"), async {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            lead.complete(Err(SharedError(GatewayApiError::GatewayError(
+                GatewayError::RateLimited {
+                    message: "Rate limit reached".to_string(),
+                    retry_after: Some(30),
+                },
+            ))));
+        });
+
+        let Flight::Shared(Err(error)) = waiting else {
+            panic!("waiting request must share the error");
+        };
+        let error = GatewayApiError::from(error);
+        assert!(matches!(
+            error,
+            GatewayApiError::GatewayError(GatewayError::RateLimited {
+                retry_after: Some(30),
+                ..
+            })
+        ));
+        let response = error.error_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "30");
+
+        // Errors that can't be copied keep their status
+        let shared = SharedError(GatewayApiError::JsonParseError(
+            serde_json::from_str::<u32>("x").unwrap_err(),
+        ))
+        .clone();
+        assert_eq!(shared.0.status_code(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -9,6 +9,7 @@ use crate::model::ResponseCacheState;
 use crate::GatewayApiError;
 use vllora_llm::client::completions::response_stream::ResultStream;
 use vllora_llm::client::message_mapper::MessageMapper;
+use vllora_llm::client::tools::validation::validate_definition;
 use vllora_llm::error::LLMError;
 use vllora_llm::mcp::get_tools;
 use vllora_llm::types::credentials::Credentials;
@@ -30,6 +31,7 @@ use vllora_llm::types::ModelEvent;
 use vllora_llm::types::ModelEventType;
 
 use either::Either::{self, Left, Right};
+use futures::StreamExt;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::collections::HashMap;
@@ -42,9 +44,15 @@ use vllora_llm::types::credentials_ident::CredentialsIdent;
 
 use super::context::ExecutorContext;
 use crate::executor::chat_completion::breakpoint::{wait_for_breakpoint_action, BreakpointManager};
+use crate::executor::concurrency::provider_concurrency;
+use crate::executor::parameter_schema::validate_parameters;
+use crate::executor::queue::{request_queues, PRIORITY_HEADER};
 
 pub mod basic_executor;
 pub mod breakpoint;
+pub mod coalesce;
+pub mod post_processing;
+pub mod response_cache;
 pub mod routed_executor;
 pub mod stream_executor;
 pub mod stream_wrapper;
@@ -116,6 +124,7 @@ pub async fn execute<T: Serialize + DeserializeOwned + Debug + Clone>(
     let mut modified_request_with_tools = request_with_tools.clone();
     modified_request_with_tools.request = request_to_use.clone();
 
+    let is_cache_hit = matches!(cache_state, Some(ResponseCacheState::Hit));
     let resolved_model_context = resolve_model_instance(
         executor_context,
         &modified_request_with_tools,
@@ -218,20 +227,58 @@ pub async fn execute<T: Serialize + DeserializeOwned + Debug + Clone>(
         .as_ref()
         .and_then(|e| e.variables.clone())
         .unwrap_or_default();
+
+    let concurrency = &executor_context.concurrency;
+    let provider = llm_model.inference_provider.provider.to_string();
+    let request_queue = &executor_context.request_queue;
+    let queue_permit = if request_queue.enabled && !is_cache_hit {
+        let priority = request_queue.priority(
+            executor_context
+                .plugin_context
+                .request_header(PRIORITY_HEADER),
+            request_with_tools.extra.as_ref(),
+        );
+        span.record("priority", priority.to_string());
+        let capacity = if concurrency.enabled {
+            provider_concurrency().current_limit(&provider, concurrency)
+        } else {
+            request_queue.max_in_flight
+        };
+        let queue_permit = request_queues()
+            .enqueue(&provider, priority, capacity, request_queue)
+            .await
+            .map_err(|e| GatewayError::Overloaded(e.to_string()))?;
+        span.record("queue_wait_ms", queue_permit.waited.as_millis() as u64);
+        Some(queue_permit)
+    } else {
+        None
+    };
+    let permit = if concurrency.enabled && !is_cache_hit {
+        Some(provider_concurrency().acquire(&provider, concurrency).await)
+    } else {
+        None
+    };
+
     if is_stream {
-        Ok(Left(
-            stream_chunks(
-                resolved_model_context.completion_model_definition,
-                resolved_model_context.model_instance,
-                messages.clone(),
-                executor_context.callbackhandler.clone().into(),
-                executor_context.tags.clone(),
-                input_vars,
-                stream_cache_context,
-            )
-            .instrument(span)
-            .await,
-        ))
+        let stream = stream_chunks(
+            resolved_model_context.completion_model_definition,
+            resolved_model_context.model_instance,
+            messages.clone(),
+            executor_context.callbackhandler.clone().into(),
+            executor_context.tags.clone(),
+            input_vars,
+            stream_cache_context,
+        )
+        .instrument(span)
+        .await;
+
+        // The queue and provider slots stay taken until the stream is consumed or dropped
+        Ok(Left(stream.map(|stream| {
+            ResultStream::new(Box::pin(stream.map(move |chunk| {
+                let _ = (&queue_permit, &permit);
+                chunk
+            })))
+        })))
     } else {
         let result = basic_executor::execute(
             request,
@@ -247,6 +294,14 @@ pub async fn execute<T: Serialize + DeserializeOwned + Debug + Clone>(
         )
         .instrument(span)
         .await;
+        drop(permit);
+        drop(queue_permit);
+
+        if concurrency.enabled {
+            if let Some(rate_limit) = result.as_ref().ok().and_then(|r| r.rate_limit.as_ref()) {
+                provider_concurrency().observe_rate_limit(&provider, rate_limit, concurrency);
+            }
+        }
 
         // if let Ok(completion_response) = &result {
         //     let ChatCompletionResponse { choices, .. } = completion_response;
@@ -282,19 +337,53 @@ pub async fn resolve_model_instance<T: Serialize + DeserializeOwned + Debug + Cl
     key: Option<&Credentials>,
 ) -> Result<ResolvedModelContext, GatewayApiError> {
     let provider_specific = request.provider_specific.clone();
-    let execution_options = request
-        .max_retries
-        .map(|retries| ExecutionOptions {
-            max_retries: Some(retries),
-        })
-        .unwrap_or_default();
+    let provider = llm_model.inference_provider.provider.to_string();
+    let propagated = executor_context
+        .metadata_propagation
+        .propagate(&provider, extra);
+    if !propagated.is_empty() {
+        router_span.record("propagated_metadata", propagated.keys.join(","));
+    }
+    // Configured headers first, so those of the request take precedence
+    let mut headers = executor_context.provider_headers.for_provider(&provider);
+    if !headers.is_empty() {
+        router_span.record(
+            "provider_headers",
+            serde_json::to_string(&executor_context.provider_headers.redacted(&provider))?,
+        );
+    }
+    headers.extend(propagated.headers);
+    headers.extend(
+        executor_context
+            .trace_context
+            .outbound_headers(&provider, &router_span),
+    );
+    let execution_options = ExecutionOptions {
+        max_retries: request.max_retries,
+        max_tool_iterations: request.max_tool_iterations,
+        role_policy: extra.and_then(|extra| extra.role_policy),
+        headers,
+    };
 
-    let request = request.request.clone();
+    let mut request = request.request.clone();
+    if request.user.is_none() {
+        request.user = propagated.user;
+    }
+    let clamped = executor_context
+        .parameter_ranges
+        .apply(&provider, llm_model.parameters.as_ref(), &mut request)
+        .map_err(|e| GatewayError::InvalidRequest(e.to_string()))?;
+    if !clamped.is_empty() {
+        router_span.record("clamped_params", clamped.join(","));
+    }
+    validate_parameters(&llm_model.model, llm_model.parameters.as_ref(), &request)?;
 
     let mut builder =
         CompletionEngineParamsBuilder::new().with_provider(llm_model.inference_provider.clone());
 
-    builder = builder.with_model_name(llm_model.inference_provider.model_name.clone());
+    builder = builder
+        .with_model_name(llm_model.inference_provider.model_name.clone())
+        .with_capabilities(llm_model.capabilities.clone());
 
     if let Some(credentials) = key {
         builder = builder.with_credentials(credentials.clone());
@@ -308,7 +397,17 @@ pub async fn resolve_model_instance<T: Serialize + DeserializeOwned + Debug + Cl
         builder = builder.with_execution_options(execution_options.clone());
     }
 
-    let engine = builder.build(&request)?;
+    let adjustments = builder.param_adjustments(&request);
+    let mut engine = builder.build(&request)?;
+    if let Some(endpoint) = extra.and_then(|extra| extra.endpoint.as_ref()) {
+        executor_context.endpoint_overrides.validate(endpoint)?;
+        engine = engine
+            .with_endpoint(endpoint.clone())
+            .map_err(|e| GatewayError::InvalidRequest(e.to_string()))?;
+    }
+    if let Some(endpoint) = engine.endpoint() {
+        router_span.record("endpoint", endpoint);
+    }
 
     let credentials_ident = if llm_model.inference_provider.provider
         == InferenceModelProvider::Proxy("vllora".to_string())
@@ -316,7 +415,8 @@ pub async fn resolve_model_instance<T: Serialize + DeserializeOwned + Debug + Cl
         CredentialsIdent::Vllora
     } else {
         CredentialsIdent::Own
-    };
+    }
+    .with_billing_label(executor_context.billing_label.clone());
 
     let db_model = Model {
         name: llm_model.model.clone(),
@@ -335,6 +435,7 @@ pub async fn resolve_model_instance<T: Serialize + DeserializeOwned + Debug + Cl
         model_params: CompletionModelParams {
             engine: engine.clone(),
             provider_name: llm_model.model_provider.to_string(),
+            adjustments,
         },
         tools,
         db_model: db_model.clone(),
@@ -376,9 +477,12 @@ pub async fn resolve_mcp_tools<T: Serialize + DeserializeOwned + Debug + Clone>(
                 passed_args: vec![],
             });
 
+            let gateway_tool = GatewayTool { def: tool.clone() };
+            validate_definition(&gateway_tool)
+                .map_err(|e| GatewayError::InvalidRequest(e.to_string()))?;
             tools_map.insert(
                 tool.function.name.clone(),
-                Arc::new(Box::new(GatewayTool { def: tool.clone() }) as Box<dyn Tool>),
+                Arc::new(Box::new(gateway_tool) as Box<dyn Tool>),
             );
         }
     }
@@ -392,6 +496,10 @@ pub async fn resolve_mcp_tools<T: Serialize + DeserializeOwned + Debug + Clone>(
 
     for server_tools in mcp_tools {
         for tool in server_tools.tools {
+            if let Err(e) = validate_definition(&tool) {
+                tracing::warn!("Skipping MCP tool: {e}");
+                continue;
+            }
             tools_map.insert(
                 tool.name(),
                 Arc::new(Box::new(tool.clone()) as Box<dyn Tool>),
@@ -408,6 +516,10 @@ pub async fn resolve_mcp_tools<T: Serialize + DeserializeOwned + Debug + Clone>(
                 .map_err(|e| GatewayError::McpServerError(Box::new(e)))?;
             for server_tools in tools {
                 for tool in server_tools.tools {
+                    if let Err(e) = validate_definition(&tool) {
+                        tracing::warn!("Skipping MCP tool: {e}");
+                        continue;
+                    }
                     tools_map.insert(
                         tool.name(),
                         Arc::new(Box::new(tool.clone()) as Box<dyn Tool>),
@@ -418,6 +530,27 @@ pub async fn resolve_mcp_tools<T: Serialize + DeserializeOwned + Debug + Clone>(
         }
     }
 
+    if let Some(allowed_tools) = request
+        .extra
+        .as_ref()
+        .and_then(|extra| extra.allowed_tools.as_ref())
+    {
+        let forced_tool = request
+            .request
+            .tool_choice
+            .as_ref()
+            .and_then(|choice| choice["function"]["name"].as_str());
+        if let Some(tool) = forced_tool.filter(|tool| !allowed_tools.iter().any(|t| t == tool)) {
+            return Err(GatewayError::InvalidRequest(format!(
+                "tool {tool} is not in the allowed tools of this request"
+            ))
+            .into());
+        }
+
+        request_tools.retain(|tool| allowed_tools.contains(&tool.name));
+        tools_map.retain(|name, _| allowed_tools.contains(name));
+    }
+
     Ok((ModelTools(request_tools), tools_map))
 }
 
@@ -430,9 +563,93 @@ pub struct ResolvedModelContext {
 
 #[cfg(test)]
 mod tests {
+    use super::resolve_mcp_tools;
+    use crate::routing::RoutingStrategy;
     use crate::types::mcp::McpServerType;
     use crate::types::mcp::{McpConfig, McpServerConfig};
+    use crate::GatewayApiError;
     use vllora_llm::mcp::get_tools;
+    use vllora_llm::types::gateway::ChatCompletionRequestWithTools;
+
+    fn request_with_tool(
+        parameters: serde_json::Value,
+    ) -> ChatCompletionRequestWithTools<RoutingStrategy> {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o-mini",
+            "messages": [{"role": "user", "content": "What's the weather?"}],
+            "tools": [{
+                "type": "function",
+                "function": {"name": "get_weather", "parameters": parameters}
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_invalid_tool_schema_is_rejected_before_execution() {
+        let request = request_with_tool(serde_json::json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city", "days"]
+        }));
+        let error = resolve_mcp_tools(None, &request).await.unwrap_err();
+
+        assert!(matches!(error, GatewayApiError::GatewayError(_)));
+        let message = error.to_string();
+        assert!(message.contains("get_weather"), "{message}");
+        assert!(message.contains("days"), "{message}");
+
+        let request = request_with_tool(serde_json::json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city"]
+        }));
+        let (tools, tools_map) = resolve_mcp_tools(None, &request).await.unwrap();
+        assert!(tools.contains(&"get_weather".to_string()));
+        assert!(tools_map.contains_key("get_weather"));
+    }
+
+    #[tokio::test]
+    async fn test_only_allowed_tools_are_sent_to_the_provider() {
+        let tool = |name: &str| {
+            serde_json::json!({
+                "type": "function",
+                "function": {"name": name, "parameters": {"type": "object", "properties": {}}}
+            })
+        };
+        let request = |tool_choice: serde_json::Value| {
+            serde_json::from_value::<ChatCompletionRequestWithTools<RoutingStrategy>>(
+                serde_json::json!({
+                    "model": "openai/gpt-4o-mini",
+                    "messages": [{"role": "user", "content": "What's the weather?"}],
+                    "tools": [tool("get_weather"), tool("send_email"), tool("shell_exec")],
+                    "tool_choice": tool_choice,
+                    "extra": {"allowed_tools": ["get_weather", "send_email"]}
+                }),
+            )
+            .unwrap()
+        };
+
+        let (tools, tools_map) = resolve_mcp_tools(None, &request(serde_json::json!("auto")))
+            .await
+            .unwrap();
+        let mut names = tools.names().cloned().collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, vec!["get_weather", "send_email"]);
+        assert!(!tools_map.contains_key("shell_exec"));
+        assert_eq!(tools_map.len(), 2);
+
+        let forced = |name: &str| {
+            request(serde_json::json!({"type": "function", "function": {"name": name}}))
+        };
+        assert!(resolve_mcp_tools(None, &forced("get_weather"))
+            .await
+            .is_ok());
+        let error = resolve_mcp_tools(None, &forced("shell_exec"))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("shell_exec"), "{error}");
+    }
 
     #[tokio::test]
     async fn test_resolve_mcp_tools_integration() {
@@ -0,0 +1,573 @@
+use std::collections::HashMap;
+
+use futures::{stream, StreamExt};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use vllora_llm::client::completions::response_stream::ResultStream;
+use vllora_llm::types::gateway::{
+    ChatCompletionChunk, ChatCompletionContent, ChatCompletionResponse, OutputRedaction,
+};
+
+/// Post-processors run on the output text, by route: the model or router name requested
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PostProcessingConfig {
+    pub routes: HashMap<String, Vec<PostProcessor>>,
+}
+
+impl PostProcessingConfig {
+    /// Pipeline of the post-processors configured for `route`, `None` when there are none
+    pub fn pipeline(&self, route: &str) -> Result<Option<OutputPipeline>, regex::Error> {
+        match self.routes.get(route) {
+            Some(processors) if !processors.is_empty() => OutputPipeline::new(processors).map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PostProcessor {
+    /// Removes emphasis, inline code and heading markers and code fence lines
+    StripMarkdown,
+    /// Drops the output after `max_chars` characters
+    MaxLength { max_chars: usize },
+    /// Replaces matches of `pattern` with `replacement`. Matches can't span whitespace.
+    /// Choices with a match are flagged as redacted with `category`, e.g. `pii` or `secret`.
+    Mask {
+        pattern: String,
+        #[serde(default = "default_mask_replacement")]
+        replacement: String,
+        #[serde(default = "default_mask_category")]
+        category: String,
+    },
+}
+
+fn default_mask_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_mask_category() -> String {
+    "sensitive".to_string()
+}
+
+impl PostProcessor {
+    fn transform(&self) -> Result<Box<dyn OutputTransform>, regex::Error> {
+        Ok(match self {
+            PostProcessor::StripMarkdown => Box::new(StripMarkdown::default()),
+            PostProcessor::MaxLength { max_chars } => Box::new(MaxLength {
+                max_chars: *max_chars,
+                emitted: 0,
+            }),
+            PostProcessor::Mask {
+                pattern,
+                replacement,
+                category,
+            } => Box::new(Mask {
+                pattern: Regex::new(pattern)?,
+                replacement: replacement.clone(),
+                category: category.clone(),
+                pending: String::new(),
+                matched: false,
+            }),
+        })
+    }
+}
+
+/// Deterministic transform of output text that may arrive in pieces. Text that could still
+/// change with the next piece, e.g. half of a word being masked, is held back until then.
+pub trait OutputTransform: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Takes the next piece of output and returns the transformed output that is final
+    fn push(&mut self, text: &str) -> String;
+
+    /// Returns the transformed output held back, once the output is complete
+    fn finish(&mut self) -> String;
+
+    /// Transform with the same settings and no output seen yet
+    fn fresh(&self) -> Box<dyn OutputTransform>;
+
+    /// Category of the content redacted from the output seen so far, if any
+    fn redacted(&self) -> Option<&str> {
+        None
+    }
+}
+
+struct MaxLength {
+    max_chars: usize,
+    emitted: usize,
+}
+
+impl OutputTransform for MaxLength {
+    fn name(&self) -> &'static str {
+        "max_length"
+    }
+
+    fn push(&mut self, text: &str) -> String {
+        let kept: String = text
+            .chars()
+            .take(self.max_chars.saturating_sub(self.emitted))
+            .collect();
+        self.emitted += kept.chars().count();
+        kept
+    }
+
+    fn finish(&mut self) -> String {
+        String::new()
+    }
+
+    fn fresh(&self) -> Box<dyn OutputTransform> {
+        Box::new(MaxLength {
+            max_chars: self.max_chars,
+            emitted: 0,
+        })
+    }
+}
+
+struct Mask {
+    pattern: Regex,
+    replacement: String,
+    category: String,
+    pending: String,
+    matched: bool,
+}
+
+impl Mask {
+    fn mask(&mut self, text: &str) -> String {
+        self.matched |= self.pattern.is_match(text);
+        self.pattern
+            .replace_all(text, self.replacement.as_str())
+            .into_owned()
+    }
+}
+
+impl OutputTransform for Mask {
+    fn name(&self) -> &'static str {
+        "mask"
+    }
+
+    fn push(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+        // The last word may continue in the next piece, so only complete words are masked
+        let Some((start, whitespace)) = self
+            .pending
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace())
+        else {
+            return String::new();
+        };
+        let rest = self.pending.split_off(start + whitespace.len_utf8());
+        let ready = std::mem::replace(&mut self.pending, rest);
+        self.mask(&ready)
+    }
+
+    fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        self.mask(&rest)
+    }
+
+    fn fresh(&self) -> Box<dyn OutputTransform> {
+        Box::new(Mask {
+            pattern: self.pattern.clone(),
+            replacement: self.replacement.clone(),
+            category: self.category.clone(),
+            pending: String::new(),
+            matched: false,
+        })
+    }
+
+    fn redacted(&self) -> Option<&str> {
+        self.matched.then_some(self.category.as_str())
+    }
+}
+
+/// Works line by line, since fences and headings depend on where a line starts
+#[derive(Default)]
+struct StripMarkdown {
+    pending: String,
+    in_fence: bool,
+}
+
+impl StripMarkdown {
+    fn strip_line(&mut self, line: &str) -> String {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            self.in_fence = !self.in_fence;
+            return String::new();
+        }
+        if self.in_fence {
+            return line.to_string();
+        }
+
+        let line = match trimmed.trim_start_matches('#') {
+            heading if heading.len() < trimmed.len() && heading.starts_with(' ') => &heading[1..],
+            _ => line,
+        };
+        ["**", "__", "~~", "`"]
+            .iter()
+            .fold(line.to_string(), |line, marker| line.replace(marker, ""))
+    }
+}
+
+impl OutputTransform for StripMarkdown {
+    fn name(&self) -> &'static str {
+        "strip_markdown"
+    }
+
+    fn push(&mut self, text: &str) -> String {
+        self.pending.push_str(text);
+        let mut output = String::new();
+        while let Some(end) = self.pending.find('\n') {
+            let rest = self.pending.split_off(end + 1);
+            let line = std::mem::replace(&mut self.pending, rest);
+            output.push_str(&self.strip_line(&line));
+        }
+        output
+    }
+
+    fn finish(&mut self) -> String {
+        let rest = std::mem::take(&mut self.pending);
+        self.strip_line(&rest)
+    }
+
+    fn fresh(&self) -> Box<dyn OutputTransform> {
+        Box::new(StripMarkdown::default())
+    }
+}
+
+/// Post-processors applied in order, each to the output of the previous one
+pub struct OutputPipeline {
+    transforms: Vec<Box<dyn OutputTransform>>,
+}
+
+impl OutputPipeline {
+    pub fn new(processors: &[PostProcessor]) -> Result<Self, regex::Error> {
+        Ok(Self {
+            transforms: processors
+                .iter()
+                .map(PostProcessor::transform)
+                .collect::<Result<_, _>>()?,
+        })
+    }
+
+    pub fn names(&self) -> Vec<&'static str> {
+        self.transforms.iter().map(|t| t.name()).collect()
+    }
+
+    pub fn fresh(&self) -> Self {
+        Self {
+            transforms: self.transforms.iter().map(|t| t.fresh()).collect(),
+        }
+    }
+
+    pub fn push(&mut self, text: &str) -> String {
+        self.transforms
+            .iter_mut()
+            .fold(text.to_string(), |text, transform| transform.push(&text))
+    }
+
+    pub fn finish(&mut self) -> String {
+        self.transforms
+            .iter_mut()
+            .fold(String::new(), |held_back, transform| {
+                let mut output = transform.push(&held_back);
+                output.push_str(&transform.finish());
+                output
+            })
+    }
+
+    /// Categories of the content redacted from the output seen so far, `None` when nothing was
+    pub fn redaction(&self) -> Option<OutputRedaction> {
+        let mut categories: Vec<String> = vec![];
+        for category in self.transforms.iter().filter_map(|t| t.redacted()) {
+            if !categories.iter().any(|c| c == category) {
+                categories.push(category.to_string());
+            }
+        }
+        (!categories.is_empty()).then_some(OutputRedaction { categories })
+    }
+
+    /// Transforms a complete output
+    pub fn apply(&self, text: &str) -> String {
+        let mut pipeline = self.fresh();
+        let mut output = pipeline.push(text);
+        output.push_str(&pipeline.finish());
+        output
+    }
+
+    /// Transforms the text content of every choice of a buffered response, flagging the
+    /// choices with redacted content. Returns the categories redacted from any choice.
+    pub fn apply_to_response(&self, response: &mut ChatCompletionResponse) -> Vec<String> {
+        let mut redacted: Vec<String> = vec![];
+        for choice in response.choices.iter_mut() {
+            if let Some(ChatCompletionContent::Text(text)) = choice.message.content.as_mut() {
+                let mut pipeline = self.fresh();
+                let mut output = pipeline.push(text);
+                output.push_str(&pipeline.finish());
+                *text = output;
+                choice.redaction = pipeline.redaction();
+            }
+            for category in choice.redaction.iter().flat_map(|r| &r.categories) {
+                if !redacted.contains(category) {
+                    redacted.push(category.clone());
+                }
+            }
+        }
+        redacted
+    }
+
+    /// Transforms the content deltas of a stream, keeping a separate state per choice.
+    /// Categories redacted from finished choices are recorded as `redactions` on `span`.
+    pub fn apply_to_stream(self, inner: ResultStream, span: tracing::Span) -> ResultStream {
+        struct State {
+            inner: ResultStream,
+            template: OutputPipeline,
+            choices: HashMap<i32, OutputPipeline>,
+            last: Option<ChatCompletionChunk>,
+            done: bool,
+            span: tracing::Span,
+            redacted: Vec<String>,
+        }
+
+        impl State {
+            fn record_redaction(&mut self, pipeline: &OutputPipeline) {
+                let categories = pipeline.redaction().map(|r| r.categories);
+                let mut added = false;
+                for category in categories.into_iter().flatten() {
+                    if !self.redacted.contains(&category) {
+                        self.redacted.push(category);
+                        added = true;
+                    }
+                }
+                if added {
+                    self.span.record("redactions", self.redacted.join(","));
+                }
+            }
+        }
+
+        let state = State {
+            inner,
+            template: self,
+            choices: HashMap::new(),
+            last: None,
+            done: false,
+            span,
+            redacted: vec![],
+        };
+
+        ResultStream::new(Box::pin(stream::unfold(state, |mut state| async move {
+            if state.done {
+                return None;
+            }
+
+            match state.inner.next().await {
+                Some(Ok(chunk)) if chunk.tool_event.is_some() => Some((Ok(chunk), state)),
+                Some(Ok(mut chunk)) => {
+                    for choice in chunk.choices.iter_mut() {
+                        let pipeline = state
+                            .choices
+                            .entry(choice.index)
+                            .or_insert_with(|| state.template.fresh());
+                        let mut content =
+                            pipeline.push(choice.delta.content.as_deref().unwrap_or_default());
+                        if choice.finish_reason.is_some() {
+                            content.push_str(&pipeline.finish());
+                            if let Some(pipeline) = state.choices.remove(&choice.index) {
+                                state.record_redaction(&pipeline);
+                            }
+                        }
+                        if choice.delta.content.is_some() || !content.is_empty() {
+                            choice.delta.content = Some(content);
+                        }
+                    }
+                    state.last = Some(chunk.clone());
+                    Some((Ok(chunk), state))
+                }
+                Some(Err(e)) => Some((Err(e), state)),
+                None => {
+                    // Output of choices that ended without a finish reason
+                    state.done = true;
+                    let mut chunk = state.last.take()?;
+                    chunk.usage = None;
+                    chunk
+                        .choices
+                        .retain(|choice| state.choices.contains_key(&choice.index));
+                    for choice in chunk.choices.iter_mut() {
+                        if let Some(mut pipeline) = state.choices.remove(&choice.index) {
+                            choice.delta = Default::default();
+                            choice.delta.content = Some(pipeline.finish());
+                            state.record_redaction(&pipeline);
+                        }
+                    }
+                    chunk.choices.retain(|choice| {
+                        !choice
+                            .delta
+                            .content
+                            .as_deref()
+                            .unwrap_or_default()
+                            .is_empty()
+                    });
+                    (!chunk.choices.is_empty()).then_some((Ok(chunk), state))
+                }
+            }
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_llm::error::LLMError;
+    use vllora_llm::types::gateway::{ChatCompletionChoice, ChatCompletionMessage};
+
+    fn chunk(content: &str, finish_reason: Option<&str>) -> Result<ChatCompletionChunk, LLMError> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "id": "chunk",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "delta": {"content": content},
+                "finish_reason": finish_reason,
+                "logprobs": null
+            }]
+        }))
+        .unwrap())
+    }
+
+    async fn streamed(pipeline: OutputPipeline, pieces: &[&str]) -> Vec<String> {
+        let last = pieces.len() - 1;
+        let upstream = ResultStream::new(Box::pin(stream::iter(
+            pieces
+                .iter()
+                .enumerate()
+                .map(|(i, piece)| chunk(piece, (i == last).then_some("stop")))
+                .collect::<Vec<_>>(),
+        )));
+        pipeline
+            .apply_to_stream(upstream, tracing::Span::none())
+            .map(|chunk| {
+                chunk.unwrap().choices[0]
+                    .delta
+                    .content
+                    .clone()
+                    .unwrap_or_default()
+            })
+            .collect()
+            .await
+    }
+
+    fn max_length(max_chars: usize) -> OutputPipeline {
+        OutputPipeline::new(&[PostProcessor::MaxLength { max_chars }]).unwrap()
+    }
+
+    fn response(content: &str) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: "chatcmpl-1".to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "gpt-4o-mini".to_string(),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatCompletionMessage {
+                    role: "assistant".to_string(),
+                    content: Some(ChatCompletionContent::Text(content.to_string())),
+                    ..Default::default()
+                },
+                finish_reason: Some("stop".to_string()),
+                redaction: None,
+            }],
+            usage: Default::default(),
+            is_cache_used: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_max_length_truncates_response() {
+        let mut response = response("Paris is the capital of France.");
+
+        let pipeline = max_length(9);
+        pipeline.apply_to_response(&mut response);
+        assert_eq!(
+            response.choices[0].message.content,
+            Some(ChatCompletionContent::Text("Paris is ".to_string()))
+        );
+        assert_eq!(pipeline.names(), vec!["max_length"]);
+        assert_eq!(response.choices[0].redaction, None);
+    }
+
+    #[test]
+    fn test_masked_response_is_flagged_as_redacted() {
+        let pipeline = OutputPipeline::new(&[
+            PostProcessor::Mask {
+                pattern: r"[\w.]+@[\w.]+".to_string(),
+                replacement: "[EMAIL]".to_string(),
+                category: "pii".to_string(),
+            },
+            PostProcessor::Mask {
+                pattern: r"sk-[A-Za-z0-9]+".to_string(),
+                replacement: default_mask_replacement(),
+                category: "secret".to_string(),
+            },
+        ])
+        .unwrap();
+
+        let mut redacted = response("Write to jane@example.com for access.");
+        assert_eq!(pipeline.apply_to_response(&mut redacted), vec!["pii"]);
+        let choice = &redacted.choices[0];
+        assert_eq!(
+            choice.message.content,
+            Some(ChatCompletionContent::Text(
+                "Write to [EMAIL] for access.".to_string()
+            ))
+        );
+        assert_eq!(choice.finish_reason.as_deref(), Some("stop"));
+        assert_eq!(
+            serde_json::to_value(choice).unwrap()["redaction"],
+            serde_json::json!({"categories": ["pii"]})
+        );
+
+        let mut clean = response("Nothing to hide here.");
+        assert!(pipeline.apply_to_response(&mut clean).is_empty());
+        assert!(serde_json::to_value(&clean.choices[0])
+            .unwrap()
+            .get("redaction")
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_max_length_truncates_stream() {
+        let pieces = streamed(max_length(9), &["Paris ", "is the ", "capital", "."]).await;
+        assert_eq!(pieces, vec!["Paris ", "is ", "", ""]);
+    }
+
+    #[tokio::test]
+    async fn test_mask_holds_back_split_words() {
+        let pipeline = OutputPipeline::new(&[
+            PostProcessor::Mask {
+                pattern: r"sk-[A-Za-z0-9]+".to_string(),
+                replacement: default_mask_replacement(),
+                category: default_mask_category(),
+            },
+            PostProcessor::MaxLength { max_chars: 23 },
+        ])
+        .unwrap();
+
+        let pieces = streamed(pipeline, &["Your key is sk-", "abc", "123 keep it safe"]).await;
+        assert_eq!(pieces, vec!["Your key is ", "", "[REDACTED] ", ""]);
+        assert_eq!(pieces.concat(), "Your key is [REDACTED] ");
+    }
+
+    #[test]
+    fn test_strip_markdown() {
+        let pipeline = OutputPipeline::new(&[PostProcessor::StripMarkdown]).unwrap();
+        assert_eq!(
+            pipeline.apply("## Answer\nUse **bold** and `code`:\n```rust\nlet x = 1;\n```\n"),
+            "Answer\nUse bold and code:\nlet x = 1;\n"
+        );
+    }
+}
@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use vllora_llm::types::cache::ResponseCacheAdapter;
+use vllora_llm::types::gateway::{
+    ChatCompletionRequest, ChatCompletionRequestWithTools, ChatCompletionResponse, Extra,
+};
+
+/// Response header telling whether the response was served from the exact-match cache.
+pub const CACHE_HEADER: &str = "x-vllora-cache";
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct ResponseCacheConfig {
+    /// Cache responses of identical non-streaming requests without a per-request opt-in
+    #[serde(default)]
+    pub enabled: bool,
+    /// How long cached responses are served, in seconds
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Only cache requests with `temperature: 0` or a `seed`, whose responses are repeatable
+    #[serde(default = "default_deterministic_only")]
+    pub deterministic_only: bool,
+    /// Let concurrent identical cacheable or deterministic requests share one provider call.
+    /// Opt-in, waiting requests get the response of another request.
+    #[serde(default)]
+    pub coalesce: bool,
+}
+
+fn default_ttl_secs() -> u64 {
+    60 * 60
+}
+
+fn default_deterministic_only() -> bool {
+    true
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_ttl_secs(),
+            deterministic_only: default_deterministic_only(),
+            coalesce: false,
+        }
+    }
+}
+
+/// Whether repeating `request` is expected to produce the same response
+pub fn is_deterministic(request: &ChatCompletionRequest) -> bool {
+    request.temperature == Some(0.0) || request.seed.is_some()
+}
+
+/// How long the response to `request` may be cached, or `None` when it must not be.
+///
+/// `extra.cache` with the exact adapter opts a single request in, with its own expiration.
+/// Distance-based caching is left to the semantic cache.
+pub fn cache_ttl<T>(
+    request: &ChatCompletionRequestWithTools<T>,
+    config: &ResponseCacheConfig,
+) -> Option<Duration> {
+    if request.request.stream.unwrap_or(false) {
+        return None;
+    }
+
+    match &request.extra {
+        Some(Extra {
+            cache: Some(options),
+            ..
+        }) => match options.adapter {
+            ResponseCacheAdapter::Exact => Some(Duration::from_secs(
+                options
+                    .expiration_time
+                    .map(u64::from)
+                    .unwrap_or(config.ttl_secs),
+            )),
+            ResponseCacheAdapter::Distance(_) => None,
+        },
+        _ if config.enabled
+            && (!config.deterministic_only || is_deterministic(&request.request)) =>
+        {
+            Some(Duration::from_secs(config.ttl_secs))
+        }
+        _ => None,
+    }
+}
+
+/// Whether concurrent identical copies of `request` may wait for the first one and share its
+/// response instead of each calling the provider
+pub fn is_coalescable<T>(
+    request: &ChatCompletionRequestWithTools<T>,
+    config: &ResponseCacheConfig,
+) -> bool {
+    config.coalesce
+        && !request.request.stream.unwrap_or(false)
+        && (cache_ttl(request, config).is_some() || is_deterministic(&request.request))
+}
+
+/// Sha256 of the canonicalized request. Object keys are sorted, so two requests with the
+/// same model, messages and parameters hash the same.
+pub fn request_hash<T: Serialize>(
+    request: &ChatCompletionRequestWithTools<T>,
+) -> Result<String, serde_json::Error> {
+    let canonical = serde_json::to_string(&canonicalize(serde_json::to_value(request)?))?;
+    Ok(format!("{:x}", Sha256::digest(canonical.as_bytes())))
+}
+
+/// Sorts object keys recursively. Maps keep their insertion order when serialized, which
+/// differs between equal `HashMap`s such as `extra.variables` or `logit_bias`.
+fn canonicalize(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, canonicalize(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(canonicalize).collect()),
+        value => value,
+    }
+}
+
+/// Identifies a cached response: the project it belongs to and the request hash
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResponseCacheKey {
+    pub project_slug: String,
+    pub request_hash: String,
+}
+
+impl ResponseCacheKey {
+    pub fn new<T: Serialize>(
+        project_slug: &str,
+        request: &ChatCompletionRequestWithTools<T>,
+    ) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            project_slug: project_slug.to_string(),
+            request_hash: request_hash(request)?,
+        })
+    }
+}
+
+/// In-memory exact-match cache of full chat completion responses
+#[derive(Default)]
+pub struct ResponseCache {
+    entries: Mutex<HashMap<ResponseCacheKey, (Instant, ChatCompletionResponse)>>,
+}
+
+impl ResponseCache {
+    pub fn get(&self, key: &ResponseCacheKey) -> Option<ChatCompletionResponse> {
+        let mut entries = self.entries.lock().ok()?;
+        match entries.get(key) {
+            Some((expires_at, response)) if *expires_at > Instant::now() => Some(response.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: ResponseCacheKey, response: ChatCompletionResponse, ttl: Duration) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let now = Instant::now();
+            entries.retain(|_, (expires_at, _)| *expires_at > now);
+            entries.insert(key, (now + ttl, response));
+        }
+    }
+}
+
+/// Process-wide response cache shared by all requests
+pub fn response_cache() -> &'static ResponseCache {
+    static CACHE: OnceLock<ResponseCache> = OnceLock::new();
+    CACHE.get_or_init(ResponseCache::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::RoutingStrategy;
+    use vllora_llm::types::gateway::{
+        ChatCompletionChoice, ChatCompletionContent, ChatCompletionMessage, ChatCompletionUsage,
+    };
+
+    fn request(temperature: f32) -> ChatCompletionRequestWithTools<RoutingStrategy> {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o-mini",
+            "messages": [{"role": "user", "content": "What is 2 + 2?"}],
+            "temperature": temperature,
+        }))
+        .unwrap()
+    }
+
+    fn response(content: &str) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: "chatcmpl-1".to_string(),
+            object: "chat.completion".to_string(),
+            created: 0,
+            model: "gpt-4o-mini".to_string(),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatCompletionMessage {
+                    role: "assistant".to_string(),
+                    content: Some(ChatCompletionContent::Text(content.to_string())),
+                    ..Default::default()
+                },
+                finish_reason: Some("stop".to_string()),
+                redaction: None,
+            }],
+            usage: ChatCompletionUsage::default(),
+            is_cache_used: None,
+            rate_limit: None,
+        }
+    }
+
+    #[test]
+    fn test_only_deterministic_requests_are_cached_by_default() {
+        let config = ResponseCacheConfig {
+            enabled: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            cache_ttl(&request(0.0), &config),
+            Some(Duration::from_secs(default_ttl_secs()))
+        );
+        assert_eq!(cache_ttl(&request(0.7), &config), None);
+        assert_eq!(
+            cache_ttl(&request(0.0), &ResponseCacheConfig::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_identical_request_is_served_from_cache() {
+        let cache = ResponseCache::default();
+        let ttl = cache_ttl(
+            &request(0.0),
+            &ResponseCacheConfig {
+                enabled: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
+
+        let first = ResponseCacheKey::new("default", &request(0.0)).unwrap();
+        assert!(cache.get(&first).is_none());
+        cache.insert(first, response("4"), ttl);
+
+        let repeat = ResponseCacheKey::new("default", &request(0.0)).unwrap();
+        let cached = cache.get(&repeat).unwrap();
+        assert_eq!(
+            cached.choices[0].message.content,
+            Some(ChatCompletionContent::Text("4".to_string()))
+        );
+
+        let other_project = ResponseCacheKey::new("other", &request(0.0)).unwrap();
+        assert!(cache.get(&other_project).is_none());
+    }
+
+    #[test]
+    fn test_request_hash_ignores_map_order() {
+        let keys = [
+            "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf",
+        ];
+        let hashes: Vec<String> = (0..16)
+            .map(|i| {
+                // Every request fills its maps in a different order
+                let mut keys = keys.to_vec();
+                keys.rotate_left(i % keys.len());
+                if i % 2 == 1 {
+                    keys.reverse();
+                }
+                let variables: serde_json::Map<String, Value> = keys
+                    .iter()
+                    .map(|key| (key.to_string(), serde_json::json!({"b": 1, "a": key})))
+                    .collect();
+                let metadata: serde_json::Map<String, Value> = keys
+                    .iter()
+                    .map(|key| (key.to_string(), Value::from(key.to_uppercase())))
+                    .collect();
+
+                let mut request = request(0.0);
+                request.request.logit_bias =
+                    Some(keys.iter().map(|key| (key.to_string(), 1)).collect());
+                request.extra = Some(
+                    serde_json::from_value(serde_json::json!({
+                        "variables": variables,
+                        "metadata": metadata,
+                    }))
+                    .unwrap(),
+                );
+                request_hash(&request).unwrap()
+            })
+            .collect();
+
+        assert!(hashes.iter().all(|hash| *hash == hashes[0]));
+    }
+}
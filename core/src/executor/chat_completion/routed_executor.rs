@@ -1,15 +1,23 @@
 use crate::credentials::GatewayCredentials;
 use crate::executor::chat_completion::basic_executor::BasicCacheContext;
 use crate::executor::chat_completion::breakpoint::BreakpointManager;
+use crate::executor::chat_completion::coalesce::{
+    request_coalescer, Flight, SharedError, COALESCED_HEADER,
+};
+use crate::executor::chat_completion::response_cache::{self, ResponseCacheKey, CACHE_HEADER};
 use crate::executor::context::ExecutorContext;
-use crate::routing::metrics::InMemoryMetricsRepository;
+use crate::model::ResponseCacheState;
+use crate::routing::interceptor::rate_limiter::record_rate_limit_cost;
+use crate::routing::metrics::{metrics_cache, CachedMetricsRepository, StorageMetricsRepository};
+use crate::routing::RoutingConfig;
 use crate::routing::RoutingStrategy;
 use crate::usage::InMemoryStorage;
-use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 use vllora_llm::types::credentials_ident::CredentialsIdent;
 use vllora_llm::types::gateway::ChatCompletionChunk;
+use vllora_llm::types::gateway::ChatCompletionResponse;
 use vllora_llm::types::gateway::GatewayModelUsage;
 use vllora_llm::types::gateway::Usage;
 use vllora_llm::types::models::InferenceProvider;
@@ -20,9 +28,9 @@ use crate::executor::chat_completion::execute;
 use crate::routing::RouteStrategy;
 use vllora_llm::types::gateway::ChatCompletionRequestWithTools;
 
+use crate::handler::stream_format;
 use crate::GatewayError;
 use actix_web::HttpResponse;
-use bytes::Bytes;
 use either::Either::{Left, Right};
 use futures::StreamExt;
 
@@ -34,7 +42,10 @@ use tokio::sync::Mutex;
 use tracing::Span;
 use tracing_futures::Instrument;
 use tracing_opentelemetry::OpenTelemetrySpanExt as _;
+use vllora_llm::client::completions::stream_usage::with_stream_usage;
 
+use crate::routing::audit::{self, RoutingAuditRecord};
+use crate::routing::resolution::{ResolutionSnapshot, ResolvedRouter, RESOLUTION_HEADER};
 use crate::routing::LlmRouter;
 use crate::GatewayApiError;
 use vllora_telemetry::trace_id_uuid;
@@ -74,13 +85,67 @@ impl RoutedExecutor {
         breakpoint_manager: Option<&BreakpointManager>,
         project_slug: &str,
         tenant_name: &str,
+    ) -> Result<HttpResponse, GatewayApiError> {
+        let result = self
+            .execute_targets(
+                executor_context,
+                memory_storage,
+                project_id,
+                thread_id,
+                breakpoint_manager,
+                project_slug,
+                tenant_name,
+            )
+            .await;
+
+        Self::or_fallback_response(
+            &self.request,
+            &executor_context.routing_config,
+            executor_context.plugin_context.request_header("accept"),
+            result,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn execute_targets(
+        &self,
+        executor_context: &ExecutorContext,
+        memory_storage: Option<Arc<Mutex<InMemoryStorage>>>,
+        project_id: Option<&uuid::Uuid>,
+        thread_id: Option<&String>,
+        breakpoint_manager: Option<&BreakpointManager>,
+        project_slug: &str,
+        tenant_name: &str,
     ) -> Result<HttpResponse, GatewayApiError> {
         let span = Span::current();
 
-        let mut targets = vec![(self.request.clone(), None)];
+        if let Some(request) = Self::bypass_routing(
+            &self.request,
+            executor_context.forced_model.as_ref(),
+            &executor_context.routing_config,
+        ) {
+            span.record("routing_bypassed", true);
+            return Self::execute_request(
+                &request,
+                executor_context,
+                project_id,
+                thread_id,
+                breakpoint_manager,
+                project_slug,
+                tenant_name,
+                &self.request.request.model,
+                None,
+            )
+            .instrument(span.clone())
+            .await;
+        }
+
+        // Each target carries the router that picked it, for the resolution snapshot
+        let mut targets: Vec<(_, _, Option<ResolvedRouter>)> =
+            vec![(self.request.clone(), None, None)];
 
         let mut depth = 0;
-        while let Some((mut request, target)) = targets.pop() {
+        while let Some((mut request, target, resolved_router)) = targets.pop() {
             depth += 1;
             if depth > MAX_DEPTH {
                 return Err(GatewayApiError::GatewayError(GatewayError::CustomError(
@@ -109,6 +174,7 @@ impl RoutedExecutor {
                     router_name = router_name,
                     before = JsonValue(&serde_json::to_value(&request.request)?).as_value(),
                     router_resolution = field::Empty,
+                    router.metric_resolution = field::Empty,
                     after = field::Empty
                 );
 
@@ -117,18 +183,17 @@ impl RoutedExecutor {
                     strategy: router.strategy.clone(),
                     targets: router.targets.clone(),
                     metrics_duration: None,
+                    project_id: Some(executor_context.project_id),
+                    decision_log: executor_context.routing_config.decision_log.clone(),
                 };
 
-                let metrics = match &memory_storage {
-                    Some(storage) => {
-                        let guard = storage.lock().await;
-                        guard.get_all_counters().await
-                    }
-                    None => BTreeMap::new(),
-                };
-
-                // Create metrics repository from the fetched metrics
-                let metrics_repository = InMemoryMetricsRepository::new(metrics);
+                // Counters are only read by metric based routes, and reused for the cache TTL
+                let storage_metrics = StorageMetricsRepository::new(memory_storage.clone());
+                let metrics_repository = CachedMetricsRepository::new(
+                    &storage_metrics,
+                    metrics_cache(),
+                    &executor_context.routing_config.metrics_cache,
+                );
 
                 let interceptor_factory = executor_context.get_interceptor_factory();
                 let executor_result = llm_router
@@ -145,8 +210,26 @@ impl RoutedExecutor {
 
                 match executor_result {
                     Ok(routing_result) => {
+                        if let Some(sink) = &executor_context.routing_audit_sink {
+                            let trace_id = span.context().span().span_context().trace_id();
+                            let record = RoutingAuditRecord::new(
+                                &llm_router.name,
+                                &llm_router.strategy,
+                                &routing_result,
+                            )
+                            .with_project_id(executor_context.project_id.to_string())
+                            .with_trace_id(trace_id_uuid(trace_id).to_string());
+                            audit::record(sink.clone(), record);
+                        }
+
+                        let resolved_router =
+                            ResolvedRouter::new(&llm_router.name, &llm_router.strategy);
                         for t in routing_result.targets.iter().rev() {
-                            targets.push((request.clone(), Some(t.clone())));
+                            targets.push((
+                                request.clone(),
+                                Some(t.clone()),
+                                Some(resolved_router.clone()),
+                            ));
                         }
                     }
                     Err(e) => {
@@ -154,14 +237,26 @@ impl RoutedExecutor {
                     }
                 }
             } else {
-                let result = Self::execute_request(
-                    &request,
-                    executor_context,
-                    project_id,
-                    thread_id,
-                    breakpoint_manager,
-                    project_slug,
-                    tenant_name,
+                let result = Self::with_context_upgrades(
+                    request,
+                    &executor_context.routing_config,
+                    |request| {
+                        let resolved_router = resolved_router.clone();
+                        async move {
+                            Self::execute_request(
+                                &request,
+                                executor_context,
+                                project_id,
+                                thread_id,
+                                breakpoint_manager,
+                                project_slug,
+                                tenant_name,
+                                &self.request.request.model,
+                                resolved_router.as_ref(),
+                            )
+                            .await
+                        }
+                    },
                 )
                 .instrument(span.clone())
                 .await;
@@ -169,7 +264,12 @@ impl RoutedExecutor {
                 match result {
                     Ok(response) => return Ok(response),
                     Err(err) => {
-                        if targets.is_empty() {
+                        if targets.is_empty()
+                            || !executor_context
+                                .routing_config
+                                .fallback_mode
+                                .falls_back_on(&err)
+                        {
                             return Err(err);
                         } else {
                             tracing::warn!(
@@ -185,6 +285,49 @@ impl RoutedExecutor {
         unreachable!()
     }
 
+    /// Replaces the error of a route whose targets all failed with the fallback response
+    /// configured for it. Only provider failures are replaced, errors of the request and
+    /// policy or guard denials are returned as is whatever the fallback mode.
+    fn or_fallback_response(
+        request: &ChatCompletionRequestWithTools<RoutingStrategy>,
+        routing_config: &RoutingConfig,
+        accept: Option<&str>,
+        result: Result<HttpResponse, GatewayApiError>,
+    ) -> Result<HttpResponse, GatewayApiError> {
+        let route = &request.request.model;
+        let err = match result {
+            Ok(response) => return Ok(response),
+            Err(err) => err,
+        };
+        let Some(fallback) = routing_config.fallback_responses.get(route) else {
+            return Err(err);
+        };
+        if !err.is_provider_failure() {
+            return Err(err);
+        }
+
+        tracing::warn!("All targets of {route} failed, returning its fallback response: {err}");
+        Span::current().record("fallback_response", err.to_string());
+
+        let mut builder = HttpResponse::Ok();
+        builder.insert_header(("X-Model-Name", route.clone()));
+        if !request.request.stream.unwrap_or(false) {
+            return Ok(builder.json(fallback.response(route)));
+        }
+
+        let format = stream_format::negotiate(request.request.stream_format, accept);
+        let chunk = serde_json::to_string(&fallback.chunk(route))?;
+        Ok(builder
+            .content_type(format.content_type())
+            .streaming(stream_format::encode(
+                format,
+                futures::stream::iter([Ok(chunk)]),
+            )))
+    }
+
+    /// Executes `request` on its model. `route` is the model or router originally requested,
+    /// `resolved_router` the router that picked the model, if any.
+    #[allow(clippy::too_many_arguments)]
     async fn execute_request(
         request: &ChatCompletionRequestWithTools<RoutingStrategy>,
         executor_context: &ExecutorContext,
@@ -193,6 +336,8 @@ impl RoutedExecutor {
         breakpoint_manager: Option<&BreakpointManager>,
         project_slug: &str,
         tenant_name: &str,
+        route: &str,
+        resolved_router: Option<&ResolvedRouter>,
     ) -> Result<HttpResponse, GatewayApiError> {
         let span = tracing::Span::current();
         span.record("request", &serde_json::to_string(&request)?);
@@ -200,6 +345,82 @@ impl RoutedExecutor {
 
         let model_name = request.request.model.clone();
 
+        // Applied to what is returned only, cached and shared responses stay unprocessed
+        let post_processing = executor_context
+            .post_processing
+            .pipeline(route)
+            .map_err(|e| GatewayApiError::CustomError(format!("Invalid post-processor: {e}")))?;
+        if let Some(pipeline) = &post_processing {
+            span.record("post_processors", pipeline.names().join(","));
+        }
+        let post_process = |response: &mut ChatCompletionResponse| {
+            if let Some(pipeline) = &post_processing {
+                let redacted = pipeline.apply_to_response(response);
+                if !redacted.is_empty() {
+                    span.record("redactions", redacted.join(","));
+                }
+            }
+        };
+
+        let cache_ttl = response_cache::cache_ttl(request, &executor_context.response_cache_config);
+        let cache_key = match cache_ttl {
+            Some(_) => Some(ResponseCacheKey::new(project_slug, request)?),
+            None => None,
+        };
+        if let Some(cached) = cache_key
+            .as_ref()
+            .and_then(|key| response_cache::response_cache().get(key))
+        {
+            span.record("cache", ResponseCacheState::Hit.to_string());
+            let mut cached = cached;
+            post_process(&mut cached);
+            executor_context
+                .plugins
+                .on_response(&executor_context.plugin_context, &mut cached)
+                .await;
+            let mut builder = HttpResponse::Ok();
+            builder
+                .insert_header(("X-Trace-Id", trace_id_uuid(trace_id).to_string()))
+                .insert_header(("X-Model-Name", model_name))
+                .insert_header((CACHE_HEADER, ResponseCacheState::Hit.to_string()));
+            executor_context
+                .plugin_context
+                .apply_response_headers(&mut builder);
+            return Ok(builder.json(cached));
+        }
+
+        // Identical requests in flight share one provider call, and its cost, with the first
+        let flight =
+            if response_cache::is_coalescable(request, &executor_context.response_cache_config) {
+                let key = format!(
+                    "{tenant_name}:{project_slug}:{}",
+                    response_cache::request_hash(request)?
+                );
+                match request_coalescer().join(&key).await {
+                    Flight::Shared(result) => {
+                        span.record("coalesced", true);
+                        let mut shared = result?;
+                        post_process(&mut shared);
+                        executor_context
+                            .plugins
+                            .on_response(&executor_context.plugin_context, &mut shared)
+                            .await;
+                        let mut builder = HttpResponse::Ok();
+                        builder
+                            .insert_header(("X-Trace-Id", trace_id_uuid(trace_id).to_string()))
+                            .insert_header(("X-Model-Name", model_name))
+                            .insert_header((COALESCED_HEADER, "true"));
+                        executor_context
+                            .plugin_context
+                            .apply_response_headers(&mut builder);
+                        return Ok(builder.json(shared));
+                    }
+                    Flight::Lead(guard) => Some(guard),
+                }
+            } else {
+                None
+            };
+
         let llm_model = match executor_context
             .model_metadata_factory
             .get_model_metadata(&request.request.model, false, false, project_id)
@@ -228,6 +449,25 @@ impl RoutedExecutor {
                 return Err(e);
             }
         };
+        let qualified_model_name = llm_model.qualified_model_name();
+        executor_context
+            .access_policy
+            .for_project(project_slug)
+            .check(
+                &request.request,
+                &[
+                    qualified_model_name.as_str(),
+                    request.request.model.as_str(),
+                ],
+            )
+            .inspect_err(|e| {
+                span.record("policy_denied", e.to_string());
+            })?;
+
+        let resolution = ResolutionSnapshot::new(resolved_router, &request.request, &llm_model)?;
+        let resolution = serde_json::to_string(&resolution)?;
+        span.record("resolution", resolution.as_str());
+
         let key = GatewayCredentials::extract_key_from_model(
             &llm_model,
             project_slug,
@@ -263,10 +503,28 @@ impl RoutedExecutor {
         if let Some(thread_id) = thread_id {
             builder.insert_header(("X-Thread-Id", thread_id.to_string()));
         }
+        if ResolutionSnapshot::requested(
+            executor_context
+                .plugin_context
+                .request_header(RESOLUTION_HEADER),
+        ) {
+            builder.insert_header((RESOLUTION_HEADER, resolution));
+        }
+        executor_context
+            .plugin_context
+            .apply_response_headers(builder);
 
         match response {
             Left(result_stream) => {
-                let stream = result_stream?;
+                let result_stream = match post_processing {
+                    Some(pipeline) => pipeline.apply_to_stream(result_stream?, span.clone()),
+                    None => result_stream?,
+                };
+                let stream = with_stream_usage(
+                    result_stream,
+                    request.request.include_usage(),
+                    &request.request.messages,
+                );
 
                 // Pin the stream to heap
                 let mut stream = Box::pin(stream);
@@ -288,12 +546,18 @@ impl RoutedExecutor {
                 let price = llm_model.price.clone();
                 let cost_calculator = executor_context.cost_calculator.clone();
                 let model_name = llm_model.model.clone();
+                let plugins = executor_context.plugins.clone();
+                let plugin_context = executor_context.plugin_context.clone();
+                let rate_limiter_service = executor_context.rate_limiter_service.clone();
                 let result = futures::stream::once(async { Ok(first) })
                     .chain(stream)
                     .then(move |delta| {
                         let price = price.clone();
                         let cost_calculator = cost_calculator.clone();
                         let model_name = model_name.clone();
+                        let plugins = plugins.clone();
+                        let plugin_context = plugin_context.clone();
+                        let rate_limiter_service = rate_limiter_service.clone();
                         async move {
                             let r = match delta {
                                 Ok(delta) => {
@@ -312,6 +576,8 @@ impl RoutedExecutor {
                                                 .clone(),
                                             ..Default::default()
                                         };
+                                        usage.cached_input_tokens = u.cached_input_tokens();
+                                        usage.cache_write_tokens = u.cache_write_tokens();
                                         usage.cost = cost_calculator
                                             .calculate_cost(
                                                 &price,
@@ -320,36 +586,158 @@ impl RoutedExecutor {
                                             )
                                             .await?
                                             .cost;
+                                        record_rate_limit_cost(
+                                            rate_limiter_service.as_ref(),
+                                            usage.cost,
+                                        )
+                                        .await;
                                     }
-                                    let json_str = serde_json::to_string(&delta).unwrap();
-                                    format!("data: {json_str}\n\n")
-                                }
-                                Err(e) => {
-                                    let result = serde_json::to_string(&HashMap::from([(
-                                        "error",
-                                        e.to_string(),
-                                    )]))
-                                    .unwrap_or_else(|e| {
-                                        format!("{{\"error\": \"Failed to serialize chunk: {e}\"}}")
-                                    });
-
-                                    format!("data: {result}\n\n")
+                                    plugins.on_stream_chunk(&plugin_context, &mut delta).await;
+                                    serde_json::to_string(&delta).unwrap()
                                 }
+                                Err(e) => return Err(GatewayApiError::from(e)),
                             };
-                            Ok(Bytes::from(r))
+                            Ok::<_, GatewayApiError>(r)
+                        }
+                    });
+                let format = stream_format::negotiate(
+                    request.request.stream_format,
+                    executor_context.plugin_context.request_header("accept"),
+                );
+                let result = stream_format::encode(format, result).instrument(span.clone());
+
+                Ok(builder
+                    .content_type(format.content_type())
+                    .streaming(result))
+            }
+            Right(completions_response) => {
+                if let Some(flight) = flight {
+                    let shared_with = flight.complete(
+                        completions_response
+                            .as_ref()
+                            .map(Clone::clone)
+                            .map_err(|e| SharedError(e.to_shared())),
+                    );
+                    if shared_with > 0 {
+                        span.record("coalesced_requests", shared_with);
+                    }
+                }
+                let mut completions_response = completions_response?;
+                record_rate_limit_cost(
+                    executor_context.rate_limiter_service.as_ref(),
+                    completions_response.usage.cost,
+                )
+                .await;
+                executor_context
+                    .rate_limit_headers
+                    .apply(builder, completions_response.rate_limit.as_ref());
+                if let (Some(key), Some(ttl)) = (cache_key, cache_ttl) {
+                    span.record("cache", ResponseCacheState::Miss.to_string());
+                    builder.insert_header((CACHE_HEADER, ResponseCacheState::Miss.to_string()));
+                    response_cache::response_cache().insert(key, completions_response.clone(), ttl);
+                }
+                post_process(&mut completions_response);
+                executor_context
+                    .plugins
+                    .on_response(&executor_context.plugin_context, &mut completions_response)
+                    .await;
+                Ok(builder.json(completions_response))
+            }
+        }
+    }
+
+    /// Runs `execute`, retrying on the configured larger-context models whenever the provider
+    /// rejects the request for exceeding the context window. Each upgrade is tried at most once.
+    async fn with_context_upgrades<F, Fut, T>(
+        request: ChatCompletionRequestWithTools<RoutingStrategy>,
+        routing_config: &RoutingConfig,
+        execute: F,
+    ) -> Result<T, GatewayApiError>
+    where
+        F: Fn(ChatCompletionRequestWithTools<RoutingStrategy>) -> Fut,
+        Fut: Future<Output = Result<T, GatewayApiError>>,
+    {
+        let span = Span::current();
+        let original_model = request.request.model.clone();
+        let mut attempted = vec![original_model.clone()];
+        let mut request = request;
+
+        loop {
+            match execute(request.clone()).await {
+                Err(GatewayApiError::GatewayError(GatewayError::ContextLengthExceeded(
+                    message,
+                ))) => {
+                    let Some(upgrade) =
+                        routing_config.next_context_upgrade(&original_model, &attempted)
+                    else {
+                        if attempted.len() == 1 {
+                            return Err(GatewayApiError::GatewayError(
+                                GatewayError::ContextLengthExceeded(message),
+                            ));
                         }
-                    })
-                    .chain(futures::stream::once(async {
-                        Ok::<_, GatewayApiError>(Bytes::from("data: [DONE]\n\n"))
-                    }))
-                    .instrument(span.clone());
 
-                Ok(builder.content_type("text/event-stream").streaming(result))
+                        return Err(GatewayApiError::GatewayError(
+                            GatewayError::ContextLengthExceeded(format!(
+                                "no upgrade of {original_model} fits (tried {}): {message}",
+                                attempted.join(", ")
+                            )),
+                        ));
+                    };
+
+                    tracing::warn!(
+                        "Context length exceeded on {}, upgrading to {upgrade}",
+                        request.request.model
+                    );
+                    span.record(
+                        "context_upgrade",
+                        JsonValue(&serde_json::json!({
+                            "from": request.request.model,
+                            "to": upgrade,
+                        }))
+                        .as_value(),
+                    );
+
+                    attempted.push(upgrade.clone());
+                    request.request.model = upgrade.clone();
+                }
+                result => return result,
             }
-            Right(completions_response) => Ok(builder.json(completions_response?)),
         }
     }
 
+    /// Returns the request pinned to the forced model, if one was requested via header or
+    /// `extra.force_model` and the gateway permits it. Fallbacks and routers are dropped.
+    fn bypass_routing(
+        request: &ChatCompletionRequestWithTools<RoutingStrategy>,
+        forced_model: Option<&String>,
+        routing_config: &RoutingConfig,
+    ) -> Option<ChatCompletionRequestWithTools<RoutingStrategy>> {
+        let forced_model = forced_model.cloned().or_else(|| {
+            request
+                .extra
+                .as_ref()
+                .and_then(|extra| extra.force_model.clone())
+        })?;
+
+        if !routing_config.allow_force_model {
+            tracing::warn!("Forcing model {forced_model} is disabled, routing request normally");
+            return None;
+        }
+
+        Some(Self::force_model(request, forced_model))
+    }
+
+    fn force_model(
+        request: &ChatCompletionRequestWithTools<RoutingStrategy>,
+        model: String,
+    ) -> ChatCompletionRequestWithTools<RoutingStrategy> {
+        let mut request = request.clone();
+        request.router = None;
+        request.fallbacks = None;
+        request.request.model = model;
+        request
+    }
+
     fn merge_request_with_target(
         request: &ChatCompletionRequestWithTools<RoutingStrategy>,
         target: &HashMap<String, serde_json::Value>,
@@ -370,3 +758,279 @@ impl RoutedExecutor {
             .map_err(RoutedExecutorError::FailedToDeserializeRequestResult)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::fallback_response::{FallbackResponse, FALLBACK_FINISH_REASON};
+    use crate::routing::FallbackMode;
+    use crate::types::guardrails::{GuardError, GuardResult};
+    use vllora_llm::types::gateway::ChatCompletionContent;
+
+    fn routed_request() -> ChatCompletionRequestWithTools<RoutingStrategy> {
+        serde_json::from_value(serde_json::json!({
+            "model": "router/dynamic",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "router": {
+                "type": "percentage",
+                "targets_percentages": [1.0],
+                "targets": [{"model": "openai/gpt-4o"}],
+            },
+            "extra": {"force_model": "anthropic/claude-3-5-haiku"},
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_force_model_is_ignored_unless_allowed() {
+        let request = routed_request();
+        let forced_header = "openai/gpt-4o-mini".to_string();
+
+        // Operators haven't opted in, the request keeps its router
+        let config: RoutingConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(!config.allow_force_model);
+        assert!(RoutedExecutor::bypass_routing(&request, None, &config).is_none());
+        assert!(RoutedExecutor::bypass_routing(&request, Some(&forced_header), &config).is_none());
+        assert!(
+            RoutedExecutor::bypass_routing(&request, None, &RoutingConfig::default()).is_none()
+        );
+    }
+
+    #[test]
+    fn test_force_model_skips_router_when_allowed() {
+        let request = routed_request();
+        let config = RoutingConfig {
+            allow_force_model: true,
+            ..Default::default()
+        };
+
+        let forced = RoutedExecutor::bypass_routing(&request, None, &config).unwrap();
+        assert!(forced.router.is_none());
+        assert_eq!(forced.request.model, "anthropic/claude-3-5-haiku");
+
+        // The header takes precedence over `extra.force_model`
+        let forced_header = "openai/gpt-4o-mini".to_string();
+        let forced =
+            RoutedExecutor::bypass_routing(&request, Some(&forced_header), &config).unwrap();
+        assert!(forced.router.is_none());
+        assert_eq!(forced.request.model, "openai/gpt-4o-mini");
+    }
+
+    #[tokio::test]
+    async fn test_context_length_upgrades_to_larger_model() {
+        let request: ChatCompletionRequestWithTools<RoutingStrategy> =
+            serde_json::from_value(serde_json::json!({
+                "model": "openai/small",
+                "messages": [{"role": "user", "content": "A very long prompt"}],
+            }))
+            .unwrap();
+        let routing_config = RoutingConfig {
+            context_upgrades: HashMap::from([(
+                "openai/small".to_string(),
+                vec!["openai/medium".to_string(), "openai/large".to_string()],
+            )]),
+            ..Default::default()
+        };
+
+        let execute = |request: ChatCompletionRequestWithTools<RoutingStrategy>| async move {
+            match request.request.model.as_str() {
+                "openai/large" => Ok(request.request.model),
+                _ => Err(GatewayApiError::GatewayError(
+                    GatewayError::ContextLengthExceeded("prompt is too long".to_string()),
+                )),
+            }
+        };
+
+        let model =
+            RoutedExecutor::with_context_upgrades(request.clone(), &routing_config, execute)
+                .await
+                .unwrap();
+        assert_eq!(model, "openai/large");
+
+        let result =
+            RoutedExecutor::with_context_upgrades(request, &RoutingConfig::default(), execute)
+                .await;
+        assert!(matches!(
+            result,
+            Err(GatewayApiError::GatewayError(
+                GatewayError::ContextLengthExceeded(_)
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_context_length_upgrade_gives_up_when_nothing_fits() {
+        let request: ChatCompletionRequestWithTools<RoutingStrategy> =
+            serde_json::from_value(serde_json::json!({
+                "model": "openai/small",
+                "messages": [{"role": "user", "content": "A very long prompt"}],
+            }))
+            .unwrap();
+        let routing_config = RoutingConfig {
+            context_upgrades: HashMap::from([(
+                "openai/small".to_string(),
+                vec!["openai/large".to_string(), "openai/small".to_string()],
+            )]),
+            ..Default::default()
+        };
+
+        let result = RoutedExecutor::with_context_upgrades(
+            request,
+            &routing_config,
+            |_request| async move {
+                Err::<(), _>(GatewayApiError::GatewayError(
+                    GatewayError::ContextLengthExceeded("prompt is too long".to_string()),
+                ))
+            },
+        )
+        .await;
+
+        match result {
+            Err(GatewayApiError::GatewayError(GatewayError::ContextLengthExceeded(message))) => {
+                assert!(message.contains("openai/large"));
+            }
+            _ => panic!("expected context length error"),
+        }
+    }
+
+    fn provider_unavailable() -> GatewayApiError {
+        GatewayApiError::GatewayError(GatewayError::ProviderUnavailable {
+            message: "upstream returned 503".to_string(),
+            retry_after: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_fallback_response_when_all_targets_fail() {
+        let request: ChatCompletionRequestWithTools<RoutingStrategy> =
+            serde_json::from_value(serde_json::json!({
+                "model": "router/support",
+                "messages": [{"role": "user", "content": "Hello"}],
+            }))
+            .unwrap();
+        let routing_config = RoutingConfig {
+            fallback_responses: HashMap::from([(
+                "router/support".to_string(),
+                FallbackResponse {
+                    content: Some("Support is unavailable right now".to_string()),
+                },
+            )]),
+            ..Default::default()
+        };
+
+        let response = RoutedExecutor::or_fallback_response(
+            &request,
+            &routing_config,
+            None,
+            Err(provider_unavailable()),
+        )
+        .unwrap();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let response: ChatCompletionResponse = serde_json::from_slice(&body).unwrap();
+        assert_eq!(
+            response.choices[0].finish_reason.as_deref(),
+            Some(FALLBACK_FINISH_REASON)
+        );
+        assert_eq!(
+            response.choices[0].message.content,
+            Some(ChatCompletionContent::Text(
+                "Support is unavailable right now".to_string()
+            ))
+        );
+        assert_eq!(response.usage.cost, 0.0);
+
+        // Request errors are not masked
+        let result = RoutedExecutor::or_fallback_response(
+            &request,
+            &routing_config,
+            None,
+            Err(GatewayApiError::GatewayError(GatewayError::InvalidRequest(
+                "bad request".to_string(),
+            ))),
+        );
+        assert!(result.is_err());
+
+        // Nor are request errors of the provider, nor denials, even on a best effort route
+        let routing_config = RoutingConfig {
+            fallback_mode: FallbackMode::BestEffort,
+            ..routing_config
+        };
+        for error in [
+            GatewayError::ContextLengthExceeded("prompt is too long".to_string()),
+            GatewayError::PolicyDenied("model is not allowed".to_string()),
+            GatewayError::GuardError(GuardError::GuardNotPassed(
+                "toxicity".to_string(),
+                GuardResult::Boolean {
+                    passed: false,
+                    confidence: None,
+                },
+            )),
+        ] {
+            let result = RoutedExecutor::or_fallback_response(
+                &request,
+                &routing_config,
+                None,
+                Err(GatewayApiError::GatewayError(error)),
+            );
+            assert!(result.is_err());
+        }
+
+        // Routes without a fallback response keep returning the error
+        let result = RoutedExecutor::or_fallback_response(
+            &request,
+            &RoutingConfig::default(),
+            None,
+            Err(provider_unavailable()),
+        );
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_streamed_fallback_response() {
+        let request: ChatCompletionRequestWithTools<RoutingStrategy> =
+            serde_json::from_value(serde_json::json!({
+                "model": "openai/gpt-4o-mini",
+                "messages": [{"role": "user", "content": "Hello"}],
+                "stream": true,
+            }))
+            .unwrap();
+        let routing_config = RoutingConfig {
+            fallback_responses: HashMap::from([(
+                "openai/gpt-4o-mini".to_string(),
+                FallbackResponse::default(),
+            )]),
+            ..Default::default()
+        };
+
+        let response = RoutedExecutor::or_fallback_response(
+            &request,
+            &routing_config,
+            None,
+            Err(provider_unavailable()),
+        )
+        .unwrap();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let events: Vec<&str> = body
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| event.strip_prefix("data: ").unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[1], "[DONE]");
+
+        let chunk: ChatCompletionChunk = serde_json::from_str(events[0]).unwrap();
+        assert_eq!(
+            chunk.choices[0].finish_reason.as_deref(),
+            Some(FALLBACK_FINISH_REASON)
+        );
+        assert_eq!(
+            chunk.choices[0].delta.content,
+            Some(FallbackResponse::default().content())
+        );
+    }
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tracing::Span;
 use tracing_futures::Instrument;
+use vllora_llm::client::completions::event_stream::{forward_events, with_tool_events};
 use vllora_llm::client::completions::response_stream::ResultStream;
 use vllora_llm::types::engine::CompletionModelDefinition;
 use vllora_llm::types::engine::ParentCompletionOptions;
@@ -36,7 +37,8 @@ pub async fn stream_chunks(
     };
 
     let db_model = model_options.definition.get_db_model();
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<Option<ModelEvent>>(10000);
+    let (tx, events) = tokio::sync::mpsc::channel::<Option<ModelEvent>>(10000);
+    let (forward, mut rx) = tokio::sync::mpsc::channel::<Option<ModelEvent>>(10000);
 
     tokio::spawn(async move {
         let mut assistant_msg = String::new();
@@ -55,9 +57,16 @@ pub async fn stream_chunks(
         span.record("response", assistant_msg.clone());
     });
 
-    model
+    let stream = model
         .stream(input_vars, tx, messages, tags)
         .instrument(Span::current())
-        .await
-        .map_err(GatewayApiError::LLMError)
+        .await;
+    match stream {
+        // Tools the gateway runs are streamed to the client along the model output
+        Ok(stream) => Ok(with_tool_events(stream, events, forward)),
+        Err(e) => {
+            tokio::spawn(forward_events(events, forward));
+            Err(GatewayApiError::from(e))
+        }
+    }
 }
@@ -0,0 +1,445 @@
+use crate::routing::metrics::MetricsRepository;
+use crate::routing::RouterError;
+use crate::usage::{Metrics, ProviderMetrics};
+use dashmap::DashMap;
+use opentelemetry::metrics::Gauge;
+use opentelemetry::KeyValue;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use tokio::sync::Notify;
+use vllora_llm::types::rate_limit::ProviderRateLimit;
+
+/// Per provider concurrency limits that adapt to provider latency and error rate (AIMD).
+/// The limit grows by one while latency stays within `latency_tolerance` of the best latency
+/// seen and the error rate is below `max_error_rate`, and is multiplied by `backoff_ratio`
+/// otherwise. Rate limits reported by the provider also back the limit off while less than
+/// `min_remaining_quota` of the request or token quota is left, and cap it at the requests
+/// left. It always stays within `min_limit..=max_limit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AdaptiveConcurrencyConfig {
+    pub enabled: bool,
+    pub min_limit: usize,
+    pub max_limit: usize,
+    pub initial_limit: usize,
+    pub latency_tolerance: f64,
+    pub max_error_rate: f64,
+    pub backoff_ratio: f64,
+    pub min_remaining_quota: f64,
+    /// How often limits are recomputed from provider metrics
+    pub refresh_interval_secs: u64,
+}
+
+impl Default for AdaptiveConcurrencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_limit: 1,
+            max_limit: 64,
+            initial_limit: 8,
+            latency_tolerance: 1.5,
+            max_error_rate: 0.05,
+            backoff_ratio: 0.7,
+            min_remaining_quota: 0.1,
+            refresh_interval_secs: 5,
+        }
+    }
+}
+
+impl AdaptiveConcurrencyConfig {
+    fn clamp(&self, limit: usize) -> usize {
+        limit.clamp(
+            self.min_limit.max(1),
+            self.max_limit.max(self.min_limit.max(1)),
+        )
+    }
+}
+
+/// Latency and error rate of a provider over the recent window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProviderSample {
+    pub latency: Option<f64>,
+    pub error_rate: Option<f64>,
+}
+
+impl ProviderSample {
+    /// Averages latency and takes the worst error rate across the provider's models
+    pub fn from_metrics(metrics: &ProviderMetrics) -> Self {
+        let windows: Vec<&Metrics> = metrics
+            .models
+            .values()
+            .map(|m| {
+                if m.metrics.last_15_minutes.requests.is_some() {
+                    &m.metrics.last_15_minutes
+                } else {
+                    &m.metrics.total
+                }
+            })
+            .collect();
+
+        let latencies: Vec<f64> = windows.iter().filter_map(|m| m.latency).collect();
+        let latency =
+            (!latencies.is_empty()).then(|| latencies.iter().sum::<f64>() / latencies.len() as f64);
+        let error_rate = windows.iter().filter_map(|m| m.error_rate).reduce(f64::max);
+
+        Self {
+            latency,
+            error_rate,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AimdController {
+    limit: usize,
+    baseline_latency: Option<f64>,
+}
+
+impl AimdController {
+    pub fn new(config: &AdaptiveConcurrencyConfig) -> Self {
+        Self {
+            limit: config.clamp(config.initial_limit),
+            baseline_latency: None,
+        }
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Updates and returns the limit for a new sample. Samples without latency or error
+    /// rate carry no signal and leave the limit unchanged.
+    pub fn observe(&mut self, sample: ProviderSample, config: &AdaptiveConcurrencyConfig) -> usize {
+        let errors_high = sample
+            .error_rate
+            .is_some_and(|rate| rate > config.max_error_rate);
+
+        let latency_high = match sample.latency {
+            Some(latency) => {
+                let baseline = *self.baseline_latency.get_or_insert(latency);
+                let high = latency > baseline * config.latency_tolerance;
+                // Follow improvements immediately and regressions slowly, so a lasting
+                // shift in provider latency eventually becomes the new baseline
+                self.baseline_latency = Some(if latency < baseline {
+                    latency
+                } else {
+                    baseline + (latency - baseline) * 0.1
+                });
+                Some(high)
+            }
+            None => None,
+        };
+
+        if errors_high || latency_high == Some(true) {
+            self.limit = config.clamp((self.limit as f64 * config.backoff_ratio).floor() as usize);
+        } else if latency_high.is_some() || sample.error_rate.is_some() {
+            self.limit = config.clamp(self.limit + 1);
+        }
+        self.limit
+    }
+
+    /// Updates and returns the limit for the rate limits reported with a provider response
+    pub fn observe_rate_limit(
+        &mut self,
+        rate_limit: &ProviderRateLimit,
+        config: &AdaptiveConcurrencyConfig,
+    ) -> usize {
+        if rate_limit
+            .remaining_ratio()
+            .is_some_and(|ratio| ratio < config.min_remaining_quota)
+        {
+            self.limit = config.clamp((self.limit as f64 * config.backoff_ratio).floor() as usize);
+        }
+        if let Some(remaining) = rate_limit.remaining_requests {
+            self.limit = config.clamp(self.limit.min(remaining as usize));
+        }
+        self.limit
+    }
+}
+
+struct ProviderLimiter {
+    controller: Mutex<AimdController>,
+    limit: AtomicUsize,
+    in_flight: AtomicUsize,
+    notify: Notify,
+}
+
+impl ProviderLimiter {
+    fn new(config: &AdaptiveConcurrencyConfig) -> Self {
+        let controller = AimdController::new(config);
+        Self {
+            limit: AtomicUsize::new(controller.limit()),
+            controller: Mutex::new(controller),
+            in_flight: AtomicUsize::new(0),
+            notify: Notify::new(),
+        }
+    }
+
+    fn update(&self, observe: impl FnOnce(&mut AimdController) -> usize) -> usize {
+        let limit = observe(&mut self.controller.lock());
+        if self.limit.swap(limit, Ordering::SeqCst) < limit {
+            self.notify.notify_waiters();
+        }
+        limit
+    }
+
+    async fn acquire(self: Arc<Self>) -> ConcurrencyPermit {
+        loop {
+            let notified = self.notify.notified();
+            tokio::pin!(notified);
+            notified.as_mut().enable();
+
+            let in_flight = self.in_flight.load(Ordering::SeqCst);
+            if in_flight < self.limit.load(Ordering::SeqCst)
+                && self
+                    .in_flight
+                    .compare_exchange(in_flight, in_flight + 1, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_ok()
+            {
+                return ConcurrencyPermit { limiter: self };
+            }
+
+            notified.await;
+        }
+    }
+}
+
+/// Slot for one in-flight provider request, released on drop
+pub struct ConcurrencyPermit {
+    limiter: Arc<ProviderLimiter>,
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        self.limiter.in_flight.fetch_sub(1, Ordering::SeqCst);
+        self.limiter.notify.notify_one();
+    }
+}
+
+pub struct ProviderConcurrency {
+    limiters: DashMap<String, Arc<ProviderLimiter>>,
+    limit_gauge: Gauge<u64>,
+}
+
+impl Default for ProviderConcurrency {
+    fn default() -> Self {
+        Self {
+            limiters: DashMap::new(),
+            limit_gauge: opentelemetry::global::meter("vllora")
+                .u64_gauge("provider_concurrency_limit")
+                .with_description("Current adaptive concurrency limit per provider")
+                .build(),
+        }
+    }
+}
+
+impl ProviderConcurrency {
+    fn limiter(&self, provider: &str, config: &AdaptiveConcurrencyConfig) -> Arc<ProviderLimiter> {
+        self.limiters
+            .entry(provider.to_string())
+            .or_insert_with(|| Arc::new(ProviderLimiter::new(config)))
+            .clone()
+    }
+
+    /// Waits until `provider` has a free slot under its current limit
+    pub async fn acquire(
+        &self,
+        provider: &str,
+        config: &AdaptiveConcurrencyConfig,
+    ) -> ConcurrencyPermit {
+        self.limiter(provider, config).acquire().await
+    }
+
+    /// Current limit of `provider`, starting its limiter if it has none yet
+    pub fn current_limit(&self, provider: &str, config: &AdaptiveConcurrencyConfig) -> usize {
+        self.limiter(provider, config).limit.load(Ordering::SeqCst)
+    }
+
+    pub fn limit(&self, provider: &str) -> Option<usize> {
+        self.limiters
+            .get(provider)
+            .map(|limiter| limiter.limit.load(Ordering::SeqCst))
+    }
+
+    /// Adapts `provider`'s limit to the rate limits reported with one of its responses
+    pub fn observe_rate_limit(
+        &self,
+        provider: &str,
+        rate_limit: &ProviderRateLimit,
+        config: &AdaptiveConcurrencyConfig,
+    ) {
+        let limit = self
+            .limiter(provider, config)
+            .update(|controller| controller.observe_rate_limit(rate_limit, config));
+        self.limit_gauge.record(
+            limit as u64,
+            &[KeyValue::new("provider", provider.to_string())],
+        );
+    }
+
+    /// Recomputes every provider's limit from the latest metrics
+    pub async fn refresh<M: MetricsRepository + Send + Sync>(
+        &self,
+        metrics_repository: &M,
+        config: &AdaptiveConcurrencyConfig,
+    ) -> Result<(), RouterError> {
+        for (provider, metrics) in metrics_repository.get_metrics().await? {
+            let sample = ProviderSample::from_metrics(&metrics);
+            let limit = self
+                .limiter(&provider, config)
+                .update(|controller| controller.observe(sample, config));
+            self.limit_gauge
+                .record(limit as u64, &[KeyValue::new("provider", provider)]);
+        }
+        Ok(())
+    }
+}
+
+/// Process-wide concurrency limits shared by all requests
+pub fn provider_concurrency() -> &'static ProviderConcurrency {
+    static LIMITS: OnceLock<ProviderConcurrency> = OnceLock::new();
+    LIMITS.get_or_init(ProviderConcurrency::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::metrics::InMemoryMetricsRepository;
+    use crate::usage::ModelMetrics;
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn config() -> AdaptiveConcurrencyConfig {
+        AdaptiveConcurrencyConfig {
+            enabled: true,
+            min_limit: 2,
+            max_limit: 20,
+            initial_limit: 10,
+            ..Default::default()
+        }
+    }
+
+    fn repository(latency: f64, error_rate: f64) -> InMemoryMetricsRepository {
+        let mut model_metrics = ModelMetrics::default();
+        model_metrics.metrics.last_15_minutes = Metrics {
+            requests: Some(100.0),
+            latency: Some(latency),
+            error_rate: Some(error_rate),
+            ..Default::default()
+        };
+        InMemoryMetricsRepository::new(BTreeMap::from([(
+            "openai".to_string(),
+            ProviderMetrics {
+                models: BTreeMap::from([("gpt-4o-mini".to_string(), model_metrics)]),
+            },
+        )]))
+    }
+
+    #[tokio::test]
+    async fn test_limit_decreases_as_latency_grows() {
+        let config = config();
+        let concurrency = ProviderConcurrency::default();
+
+        for _ in 0..5 {
+            concurrency
+                .refresh(&repository(200.0, 0.0), &config)
+                .await
+                .unwrap();
+        }
+        let stable_limit = concurrency.limit("openai").unwrap();
+        assert_eq!(stable_limit, 15);
+
+        let mut previous = stable_limit;
+        for latency in [400.0, 800.0, 1600.0] {
+            concurrency
+                .refresh(&repository(latency, 0.0), &config)
+                .await
+                .unwrap();
+            let limit = concurrency.limit("openai").unwrap();
+            assert!(limit < previous, "{limit} should be below {previous}");
+            previous = limit;
+        }
+
+        for _ in 0..10 {
+            concurrency
+                .refresh(&repository(5000.0, 0.5), &config)
+                .await
+                .unwrap();
+        }
+        assert_eq!(concurrency.limit("openai"), Some(config.min_limit));
+    }
+
+    #[test]
+    fn test_limit_stays_within_bounds() {
+        let config = config();
+        let mut controller = AimdController::new(&config);
+        let stable = ProviderSample {
+            latency: Some(100.0),
+            error_rate: Some(0.0),
+        };
+        for _ in 0..50 {
+            controller.observe(stable, &config);
+        }
+        assert_eq!(controller.limit(), config.max_limit);
+
+        assert_eq!(
+            controller.observe(ProviderSample::default(), &config),
+            config.max_limit
+        );
+    }
+
+    #[test]
+    fn test_limit_follows_reported_rate_limits() {
+        let config = config();
+        let concurrency = ProviderConcurrency::default();
+        let rate_limit = |remaining_requests, remaining_tokens| ProviderRateLimit {
+            limit_requests: Some(500),
+            remaining_requests: Some(remaining_requests),
+            limit_tokens: Some(100_000),
+            remaining_tokens: Some(remaining_tokens),
+            ..Default::default()
+        };
+
+        concurrency.observe_rate_limit("openai", &rate_limit(450, 90_000), &config);
+        assert_eq!(concurrency.limit("openai"), Some(config.initial_limit));
+
+        // Token quota nearly used up
+        concurrency.observe_rate_limit("openai", &rate_limit(450, 5_000), &config);
+        assert_eq!(concurrency.limit("openai"), Some(7));
+
+        // Never more in flight than requests left in the window
+        concurrency.observe_rate_limit("openai", &rate_limit(3, 90_000), &config);
+        assert_eq!(concurrency.limit("openai"), Some(3));
+        concurrency.observe_rate_limit("openai", &rate_limit(0, 0), &config);
+        assert_eq!(concurrency.limit("openai"), Some(config.min_limit));
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_for_a_free_slot() {
+        let config = AdaptiveConcurrencyConfig {
+            min_limit: 1,
+            initial_limit: 1,
+            ..config()
+        };
+        let concurrency = Arc::new(ProviderConcurrency::default());
+        let permit = concurrency.acquire("openai", &config).await;
+
+        let waiting = {
+            let concurrency = concurrency.clone();
+            let config = config.clone();
+            tokio::spawn(async move {
+                let _permit = concurrency.acquire("openai", &config).await;
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(!waiting.is_finished());
+
+        drop(permit);
+        tokio::time::timeout(Duration::from_secs(1), waiting)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+}
@@ -1,19 +1,42 @@
+use crate::credentials::billing::BillingLabelsConfig;
 use crate::credentials::KeyStorage;
 use crate::mcp::McpConfig;
+use crate::model::first_token_timeout::FirstTokenTimeoutConfig;
+use crate::model::stream_channel::StreamChannelConfig;
+use crate::model::stream_coalescing::StreamCoalescingConfig;
 use crate::model::ModelMetadataFactory;
+use crate::plugins::{PluginContext, PluginRegistry};
 use crate::routing::interceptor::rate_limiter::RateLimiterService;
+use crate::telemetry::trace_context::TraceContextConfig;
+use crate::types::guardrails::defaults::DefaultGuardsConfig;
+use crate::types::guardrails::json_repair::JsonRepairConfig;
+use crate::types::guardrails::language::LanguageGuardConfig;
 use crate::types::guardrails::service::GuardrailsEvaluator;
+use crate::types::guardrails::streaming::StreamingGuardConfig;
+use crate::types::metadata::project::Project;
 use crate::{
     error::GatewayError,
     handler::{extract_tags, CallbackHandlerFn},
 };
-use actix_web::HttpRequest;
+use actix_web::{HttpMessage, HttpRequest};
 use std::{collections::HashMap, sync::Arc};
-use vllora_llm::types::gateway::CostCalculator;
+use vllora_llm::types::gateway::{CostCalculator, GuardOrName};
 
+use super::chat_completion::post_processing::PostProcessingConfig;
+use super::chat_completion::response_cache::ResponseCacheConfig;
+use super::concurrency::AdaptiveConcurrencyConfig;
+use super::endpoint::EndpointOverrideConfig;
+use super::parameter_ranges::ParameterRangesConfig;
+use super::policy::AccessPolicyConfig;
+use super::propagation::MetadataPropagationConfig;
+use super::provider_headers::ProviderHeadersConfig;
+use super::queue::RequestQueueConfig;
+use super::rate_limit_headers::RateLimitHeadersConfig;
 use super::ProvidersConfig;
+use crate::routing::audit::RoutingAuditSink;
 use crate::routing::interceptor::InterceptorFactory;
 use crate::routing::interceptor::RouterInterceptorFactory;
+use crate::routing::{RoutingConfig, FORCE_MODEL_HEADER};
 
 #[derive(Clone)]
 pub struct ExecutorContext {
@@ -28,6 +51,32 @@ pub struct ExecutorContext {
     pub project_id: uuid::Uuid,
     pub key_storage: Arc<Box<dyn KeyStorage>>,
     pub mcp_config: Option<McpConfig>,
+    pub routing_config: RoutingConfig,
+    pub forced_model: Option<String>,
+    pub routing_audit_sink: Option<Arc<dyn RoutingAuditSink>>,
+    pub response_cache_config: ResponseCacheConfig,
+    pub access_policy: AccessPolicyConfig,
+    /// Billing entity the project's own credentials usage is charged back to
+    pub billing_label: Option<String>,
+    pub endpoint_overrides: EndpointOverrideConfig,
+    pub concurrency: AdaptiveConcurrencyConfig,
+    pub metadata_propagation: MetadataPropagationConfig,
+    pub provider_headers: ProviderHeadersConfig,
+    pub parameter_ranges: ParameterRangesConfig,
+    pub streaming_guard: StreamingGuardConfig,
+    /// Guards of the project applied to every request, see [`DefaultGuardsConfig`]
+    pub default_guards: Vec<GuardOrName>,
+    pub stream_channel: StreamChannelConfig,
+    pub stream_coalescing: StreamCoalescingConfig,
+    pub language_guard: LanguageGuardConfig,
+    pub json_repair: JsonRepairConfig,
+    pub first_token_timeout: FirstTokenTimeoutConfig,
+    pub request_queue: RequestQueueConfig,
+    pub trace_context: TraceContextConfig,
+    pub post_processing: PostProcessingConfig,
+    pub rate_limit_headers: RateLimitHeadersConfig,
+    pub plugins: PluginRegistry,
+    pub plugin_context: PluginContext,
 }
 
 // Implement Send + Sync since all fields are Send + Sync
@@ -51,6 +100,101 @@ impl ExecutorContext {
         let tags = extract_tags(req)?;
 
         let providers_config = req.app_data::<ProvidersConfig>().cloned();
+        let project_slug = req.extensions().get::<Project>().map(|p| p.slug.clone());
+        let routing_config = req
+            .app_data::<RoutingConfig>()
+            .map(|config| config.for_project(project_slug.as_deref()))
+            .unwrap_or_default();
+        let forced_model = req
+            .headers()
+            .get(FORCE_MODEL_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let routing_audit_sink = req
+            .app_data::<Option<Arc<dyn RoutingAuditSink>>>()
+            .cloned()
+            .flatten();
+        let response_cache_config = req
+            .app_data::<ResponseCacheConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let access_policy = req
+            .app_data::<AccessPolicyConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let endpoint_overrides = req
+            .app_data::<EndpointOverrideConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let concurrency = req
+            .app_data::<AdaptiveConcurrencyConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let metadata_propagation = req
+            .app_data::<MetadataPropagationConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let provider_headers = req
+            .app_data::<ProviderHeadersConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let parameter_ranges = req
+            .app_data::<ParameterRangesConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let streaming_guard = req
+            .app_data::<StreamingGuardConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let stream_channel = req
+            .app_data::<StreamChannelConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let stream_coalescing = req
+            .app_data::<StreamCoalescingConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let language_guard = req
+            .app_data::<LanguageGuardConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let json_repair = req
+            .app_data::<JsonRepairConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let first_token_timeout = req
+            .app_data::<FirstTokenTimeoutConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let request_queue = req
+            .app_data::<RequestQueueConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let trace_context = req
+            .app_data::<TraceContextConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let post_processing = req
+            .app_data::<PostProcessingConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let rate_limit_headers = req
+            .app_data::<RateLimitHeadersConfig>()
+            .cloned()
+            .unwrap_or_default();
+        let plugins = req
+            .app_data::<PluginRegistry>()
+            .cloned()
+            .unwrap_or_default();
+        let plugin_context = PluginContext::from_request(req);
+        let billing_label = req
+            .app_data::<BillingLabelsConfig>()
+            .zip(project_slug.as_ref())
+            .and_then(|(labels, project_slug)| labels.for_project(project_slug).cloned());
+        let default_guards = req
+            .app_data::<DefaultGuardsConfig>()
+            .map(|config| config.for_project(project_slug.as_deref()))
+            .unwrap_or_default();
 
         Ok(Self {
             callbackhandler,
@@ -64,6 +208,30 @@ impl ExecutorContext {
             project_id,
             key_storage,
             mcp_config,
+            routing_config,
+            forced_model,
+            routing_audit_sink,
+            response_cache_config,
+            access_policy,
+            billing_label,
+            endpoint_overrides,
+            plugins,
+            plugin_context,
+            concurrency,
+            metadata_propagation,
+            provider_headers,
+            parameter_ranges,
+            streaming_guard,
+            default_guards,
+            stream_channel,
+            stream_coalescing,
+            language_guard,
+            json_repair,
+            first_token_timeout,
+            request_queue,
+            trace_context,
+            post_processing,
+            rate_limit_headers,
         })
     }
 
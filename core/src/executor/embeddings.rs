@@ -12,10 +12,12 @@ use tracing::Span;
 use tracing_futures::Instrument;
 use vllora_llm::types::credentials::Credentials;
 use vllora_llm::types::credentials_ident::CredentialsIdent;
+use vllora_llm::types::engine::EmbeddingsEngineParams;
 use vllora_llm::types::engine::EmbeddingsModelDefinition;
 use vllora_llm::types::engine::Model;
 use vllora_llm::types::gateway::CostCalculator;
 use vllora_llm::types::gateway::CreateEmbeddingRequest;
+use vllora_llm::types::gateway::Input;
 use vllora_llm::types::models::ModelMetadata;
 use vllora_llm::types::models::ModelType;
 use vllora_llm::types::provider::InferenceModelProvider;
@@ -25,6 +27,40 @@ use vllora_llm::types::ModelEventType;
 use super::get_key_credentials;
 use super::ProvidersConfig;
 
+/// Most inputs embedded in one provider request. Gemini and Titan embed a single text per
+/// request, multiple parts would be merged into one embedding.
+fn max_batch_size(engine: &EmbeddingsEngineParams) -> usize {
+    match engine {
+        EmbeddingsEngineParams::OpenAi { .. } => 2048,
+        EmbeddingsEngineParams::Bedrock { model_name, .. }
+            if model_name
+                .rsplit('/')
+                .next()
+                .is_some_and(|name| name.starts_with("cohere.")) =>
+        {
+            96
+        }
+        EmbeddingsEngineParams::Gemini { .. } | EmbeddingsEngineParams::Bedrock { .. } => 1,
+    }
+}
+
+/// Splits the inputs of `request` into requests of at most `batch_size` inputs
+fn batch_requests(
+    request: &CreateEmbeddingRequest,
+    batch_size: usize,
+) -> Vec<CreateEmbeddingRequest> {
+    match &request.input {
+        Input::Array(inputs) if inputs.len() > batch_size => inputs
+            .chunks(batch_size)
+            .map(|chunk| CreateEmbeddingRequest {
+                input: Input::Array(chunk.to_vec()),
+                ..request.clone()
+            })
+            .collect(),
+        _ => vec![request.clone()],
+    }
+}
+
 pub async fn handle_embeddings(
     mut request: CreateEmbeddingRequest,
     callback_handler: &CallbackHandlerFn,
@@ -62,6 +98,7 @@ pub async fn handle_embeddings(
         },
     };
 
+    let batch_size = max_batch_size(&engine);
     let embeddings_model_definition = EmbeddingsModelDefinition {
         name: llm_model.model.clone(),
         engine,
@@ -98,12 +135,62 @@ pub async fn handle_embeddings(
     .await
     .map_err(|e| GatewayError::CustomError(e.to_string()))?;
 
-    let result = model
-        .embed(&request, tx, tags.clone())
-        .instrument(span.clone())
-        .await?;
+    let mut result: Option<EmbeddingResult> = None;
+    for batch in batch_requests(&request, batch_size) {
+        let batch_result = model
+            .embed(&batch, tx.clone(), tags.clone())
+            .instrument(span.clone())
+            .await?;
+        match result.as_mut() {
+            Some(result) => result.extend(batch_result)?,
+            None => result = Some(batch_result),
+        }
+    }
+    drop(tx);
 
     let _stop_event = handle.await.unwrap();
 
-    Ok(result)
+    result.ok_or_else(|| GatewayError::CustomError("No embeddings returned".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(inputs: usize) -> CreateEmbeddingRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "text-embedding-3-small",
+            "input": (0..inputs).map(|i| format!("input {i}")).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_inputs_are_split_into_provider_batches() {
+        let openai = EmbeddingsEngineParams::OpenAi {
+            credentials: None,
+            endpoint: None,
+            model_name: "text-embedding-3-small".to_string(),
+        };
+        let cohere = EmbeddingsEngineParams::Bedrock {
+            credentials: None,
+            model_name: "cohere.embed-english-v3".to_string(),
+        };
+        let titan = EmbeddingsEngineParams::Bedrock {
+            credentials: None,
+            model_name: "amazon.titan-embed-text-v2:0".to_string(),
+        };
+
+        assert_eq!(
+            batch_requests(&request(200), max_batch_size(&openai)).len(),
+            1
+        );
+
+        let batches = batch_requests(&request(200), max_batch_size(&cohere));
+        assert_eq!(batches.len(), 3);
+        assert!(matches!(&batches[2].input, Input::Array(inputs) if inputs.len() == 8));
+        assert!(matches!(&batches[1].input, Input::Array(inputs) if inputs[0] == "input 96"));
+
+        assert_eq!(batch_requests(&request(3), max_batch_size(&titan)).len(), 3);
+    }
 }
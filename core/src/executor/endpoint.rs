@@ -0,0 +1,93 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+
+use crate::error::GatewayError;
+
+/// Hosts requests may send their provider calls to through `extra.endpoint`
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct EndpointOverrideConfig {
+    /// Exact hosts or `*.`-prefixed domains. Overrides are rejected when empty.
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
+}
+
+impl EndpointOverrideConfig {
+    fn is_allowed_host(&self, host: &str) -> bool {
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| match allowed.strip_prefix("*.") {
+                Some(domain) => host
+                    .strip_suffix(domain)
+                    .is_some_and(|subdomain| subdomain.ends_with('.')),
+                None => allowed == host,
+            })
+    }
+
+    /// Checks that `endpoint` is an http(s) URL on an allowed host
+    pub fn validate(&self, endpoint: &str) -> Result<(), GatewayError> {
+        let url = Url::parse(endpoint).map_err(|e| {
+            GatewayError::InvalidRequest(format!("Invalid endpoint {endpoint}: {e}"))
+        })?;
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err(GatewayError::InvalidRequest(format!(
+                "Invalid endpoint {endpoint}: only http and https are supported"
+            )));
+        }
+
+        match url.host_str() {
+            Some(host) if self.is_allowed_host(host) => Ok(()),
+            _ => Err(GatewayError::PolicyDenied(format!(
+                "endpoint {endpoint} is not allowed"
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> EndpointOverrideConfig {
+        serde_json::from_value(serde_json::json!({
+            "allowed_hosts": ["mirror.internal", "*.openai.azure.com"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_allowed_endpoints() {
+        let config = config();
+
+        assert!(config.validate("https://mirror.internal/v1").is_ok());
+        assert!(config.validate("http://mirror.internal:8080").is_ok());
+        assert!(config
+            .validate("https://eu.openai.azure.com/openai/deployments")
+            .is_ok());
+    }
+
+    #[test]
+    fn test_rejected_endpoints() {
+        let config = config();
+
+        assert!(matches!(
+            config.validate("https://attacker.example.com"),
+            Err(GatewayError::PolicyDenied(_))
+        ));
+        assert!(matches!(
+            config.validate("https://evilopenai.azure.com"),
+            Err(GatewayError::PolicyDenied(_))
+        ));
+        assert!(matches!(
+            config.validate("file:///etc/passwd"),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+        assert!(matches!(
+            config.validate("not a url"),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+        // Overrides are disabled unless hosts are configured
+        assert!(EndpointOverrideConfig::default()
+            .validate("https://mirror.internal")
+            .is_err());
+    }
+}
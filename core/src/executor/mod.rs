@@ -5,10 +5,20 @@ use serde::{Deserialize, Serialize};
 use vllora_llm::types::credentials::{ApiKeyCredentials, Credentials};
 
 pub mod chat_completion;
+pub mod concurrency;
 pub mod context;
 pub mod embeddings;
+pub mod endpoint;
 pub mod image_generation;
+pub mod parameter_ranges;
+pub mod parameter_schema;
+pub mod policy;
+pub mod propagation;
+pub mod provider_headers;
+pub mod queue;
+pub mod rate_limit_headers;
 pub mod responses;
+pub mod warm_up;
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct ProvidersConfig(pub HashMap<String, ApiKeyCredentials>);
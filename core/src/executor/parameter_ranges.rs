@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+use vllora_llm::types::gateway::ChatCompletionRequest;
+
+/// Valid range of a sampling parameter
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ParameterRange {
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Keeps `temperature` and `top_p` within the range a provider accepts. Ranges come from the
+/// model's metadata, and can be overridden per provider. Out of range values are clamped and
+/// recorded as `clamped_params`, or rejected in `strict` mode.
+///
+/// ```yaml
+/// parameter_ranges:
+///   strict: false
+///   providers:
+///     anthropic:
+///       temperature: { min: 0, max: 1 }
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParameterRangesConfig {
+    /// Reject out of range values instead of clamping them
+    pub strict: bool,
+    /// Ranges per provider, mapping parameter name to range. Take precedence over metadata.
+    pub providers: HashMap<String, HashMap<String, ParameterRange>>,
+}
+
+impl Default for ParameterRangesConfig {
+    fn default() -> Self {
+        Self {
+            strict: false,
+            // Model metadata lists 0-2 for every provider, Anthropic only accepts 0-1
+            providers: HashMap::from([(
+                "anthropic".to_string(),
+                HashMap::from([(
+                    "temperature".to_string(),
+                    ParameterRange { min: 0.0, max: 1.0 },
+                )]),
+            )]),
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ParameterRangeError {
+    #[error("{name} must be between {min} and {max} for {provider}, got {value}")]
+    OutOfRange {
+        provider: String,
+        name: String,
+        value: f32,
+        min: f32,
+        max: f32,
+    },
+}
+
+impl ParameterRangesConfig {
+    /// Range of `name` for `provider`, from the config or from the model's `parameters`
+    /// metadata
+    fn range(
+        &self,
+        provider: &str,
+        parameters: Option<&serde_json::Value>,
+        name: &str,
+    ) -> Option<ParameterRange> {
+        if let Some(range) = self.providers.get(provider).and_then(|p| p.get(name)) {
+            return Some(*range);
+        }
+        let metadata = parameters?.get(name)?;
+        Some(ParameterRange {
+            min: metadata.get("min")?.as_f64()? as f32,
+            max: metadata.get("max")?.as_f64()? as f32,
+        })
+    }
+
+    /// Clamps the sampling parameters of `request` to the ranges of `provider`. Returns the
+    /// clamped parameters as `name=value->clamped`.
+    pub fn apply(
+        &self,
+        provider: &str,
+        parameters: Option<&serde_json::Value>,
+        request: &mut ChatCompletionRequest,
+    ) -> Result<Vec<String>, ParameterRangeError> {
+        let mut clamped = vec![];
+        for (name, value) in [
+            ("temperature", &mut request.temperature),
+            ("top_p", &mut request.top_p),
+        ] {
+            let (Some(current), Some(range)) = (*value, self.range(provider, parameters, name))
+            else {
+                continue;
+            };
+            if (range.min..=range.max).contains(&current) {
+                continue;
+            }
+            if self.strict {
+                return Err(ParameterRangeError::OutOfRange {
+                    provider: provider.to_string(),
+                    name: name.to_string(),
+                    value: current,
+                    min: range.min,
+                    max: range.max,
+                });
+            }
+            let new_value = current.clamp(range.min, range.max);
+            clamped.push(format!("{name}={current}->{new_value}"));
+            *value = Some(new_value);
+        }
+        Ok(clamped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(temperature: f32, top_p: f32) -> ChatCompletionRequest {
+        serde_json::from_value(json!({
+            "model": "anthropic/claude-3-5-sonnet",
+            "messages": [],
+            "temperature": temperature,
+            "top_p": top_p,
+        }))
+        .unwrap()
+    }
+
+    fn metadata_parameters() -> serde_json::Value {
+        json!({
+            "temperature": {"min": 0, "max": 2, "default": 1},
+            "top_p": {"min": 0, "max": 1, "default": 1}
+        })
+    }
+
+    #[test]
+    fn test_temperature_is_clamped_to_provider_range() {
+        let config = ParameterRangesConfig::default();
+        let parameters = metadata_parameters();
+
+        let mut anthropic = request(1.8, 0.5);
+        let clamped = config
+            .apply("anthropic", Some(&parameters), &mut anthropic)
+            .unwrap();
+        assert_eq!(anthropic.temperature, Some(1.0));
+        assert_eq!(anthropic.top_p, Some(0.5));
+        assert_eq!(clamped, vec!["temperature=1.8->1"]);
+
+        // Within the 0-2 range of the metadata
+        let mut openai = request(1.8, 0.5);
+        let clamped = config
+            .apply("openai", Some(&parameters), &mut openai)
+            .unwrap();
+        assert_eq!(openai.temperature, Some(1.8));
+        assert!(clamped.is_empty());
+
+        let mut openai = request(1.0, 1.5);
+        config
+            .apply("openai", Some(&parameters), &mut openai)
+            .unwrap();
+        assert_eq!(openai.top_p, Some(1.0));
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_out_of_range_values() {
+        let config = ParameterRangesConfig {
+            strict: true,
+            ..Default::default()
+        };
+        let mut anthropic = request(1.8, 0.5);
+        assert_eq!(
+            config.apply("anthropic", None, &mut anthropic),
+            Err(ParameterRangeError::OutOfRange {
+                provider: "anthropic".to_string(),
+                name: "temperature".to_string(),
+                value: 1.8,
+                min: 0.0,
+                max: 1.0,
+            })
+        );
+        assert_eq!(anthropic.temperature, Some(1.8));
+    }
+}
@@ -0,0 +1,182 @@
+use serde_json::Value;
+use thiserror::Error;
+use vllora_llm::types::gateway::ChatCompletionRequest;
+
+use crate::error::GatewayError;
+
+/// Request fields described under a different path of the model's `parameters` metadata
+const METADATA_PATHS: [(&str, &str); 1] = [("reasoning_effort", "reasoning.effort")];
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ParameterSchemaError {
+    #[error("must be between {min} and {max} for {model}, got {value}")]
+    OutOfRange {
+        model: String,
+        name: String,
+        value: f64,
+        min: String,
+        max: String,
+    },
+
+    #[error("must be one of {allowed} for {model}, got {value}")]
+    NotAllowed {
+        model: String,
+        name: String,
+        value: Value,
+        allowed: String,
+    },
+}
+
+impl ParameterSchemaError {
+    pub fn name(&self) -> &str {
+        match self {
+            ParameterSchemaError::OutOfRange { name, .. }
+            | ParameterSchemaError::NotAllowed { name, .. } => name,
+        }
+    }
+}
+
+impl From<ParameterSchemaError> for GatewayError {
+    fn from(e: ParameterSchemaError) -> Self {
+        GatewayError::InvalidParameter {
+            param: e.name().to_string(),
+            message: e.to_string(),
+        }
+    }
+}
+
+/// Schema of a request field in the model's `parameters` metadata, like
+/// `{"type": "float", "min": 0, "max": 2}` or `{"type": "string", "enum": ["low", "high"]}`
+fn schema<'a>(parameters: &'a Value, name: &str) -> Option<&'a Value> {
+    let path = METADATA_PATHS
+        .iter()
+        .find(|(field, _)| *field == name)
+        .map_or(name, |(_, path)| *path);
+    path.split('.')
+        .try_fold(parameters, |schema, key| schema.get(key))
+}
+
+fn bound(schema: &Value, key: &str) -> Option<f64> {
+    schema.get(key)?.as_f64()
+}
+
+/// Checks the parameters of `request` against the ranges and allowed values the model
+/// declares in its `parameters` metadata, so invalid values are rejected before the
+/// provider is called. Parameters the metadata doesn't describe are left to the provider.
+pub fn validate_parameters(
+    model: &str,
+    parameters: Option<&Value>,
+    request: &ChatCompletionRequest,
+) -> Result<(), ParameterSchemaError> {
+    let Some(parameters) = parameters else {
+        return Ok(());
+    };
+    let Ok(Value::Object(fields)) = serde_json::to_value(request) else {
+        return Ok(());
+    };
+
+    for (name, value) in fields.iter().filter(|(_, value)| !value.is_null()) {
+        let Some(schema) = schema(parameters, name) else {
+            continue;
+        };
+
+        if let Some(number) = value.as_f64() {
+            let (min, max) = (bound(schema, "min"), bound(schema, "max"));
+            if min.is_some_and(|min| number < min) || max.is_some_and(|max| number > max) {
+                let format_bound =
+                    |bound: Option<f64>| bound.map_or("any".to_string(), |b| b.to_string());
+                return Err(ParameterSchemaError::OutOfRange {
+                    model: model.to_string(),
+                    name: name.clone(),
+                    value: number,
+                    min: format_bound(min),
+                    max: format_bound(max),
+                });
+            }
+        }
+
+        if let Some(allowed) = schema.get("enum").and_then(Value::as_array) {
+            if !allowed.contains(value) {
+                return Err(ParameterSchemaError::NotAllowed {
+                    model: model.to_string(),
+                    name: name.clone(),
+                    value: value.clone(),
+                    allowed: allowed
+                        .iter()
+                        .map(|v| v.as_str().map_or(v.to_string(), str::to_string))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(parameters: Value) -> ChatCompletionRequest {
+        let mut request = json!({
+            "model": "openai/o4-mini",
+            "messages": [{"role": "user", "content": "Hi"}],
+        });
+        request
+            .as_object_mut()
+            .unwrap()
+            .extend(parameters.as_object().unwrap().clone());
+        serde_json::from_value(request).unwrap()
+    }
+
+    fn metadata_parameters() -> Value {
+        json!({
+            "presence_penalty": {"type": "float", "min": -2, "max": 2, "default": 0},
+            "max_tokens": {"type": "int", "min": null, "max": null, "default": 1000},
+            "reasoning": {
+                "effort": {"type": "string", "enum": ["high", "medium"], "default": null}
+            }
+        })
+    }
+
+    #[test]
+    fn test_out_of_range_parameter_is_rejected() {
+        let parameters = metadata_parameters();
+        assert!(validate_parameters(
+            "o4-mini",
+            Some(&parameters),
+            &request(json!({"presence_penalty": -1.5, "max_tokens": 100000}))
+        )
+        .is_ok());
+
+        let error = validate_parameters(
+            "o4-mini",
+            Some(&parameters),
+            &request(json!({"presence_penalty": 3})),
+        )
+        .unwrap_err();
+        assert_eq!(error.name(), "presence_penalty");
+        assert_eq!(
+            GatewayError::from(error).to_string(),
+            "Invalid request: presence_penalty must be between -2 and 2 for o4-mini, got 3"
+        );
+
+        let error = validate_parameters(
+            "o4-mini",
+            Some(&parameters),
+            &request(json!({"reasoning_effort": "low"})),
+        )
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "must be one of high, medium for o4-mini, got \"low\""
+        );
+
+        // Without metadata the provider validates
+        assert!(
+            validate_parameters("o4-mini", None, &request(json!({"presence_penalty": 3}))).is_ok()
+        );
+    }
+}
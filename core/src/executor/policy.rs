@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use vllora_llm::types::gateway::ChatCompletionRequest;
+
+use crate::error::GatewayError;
+
+/// Models and tools a project may use.
+///
+/// Entries are exact names or prefixes ending in `*`, e.g. `openai/*`. Deny entries win over
+/// allow entries, and an allow list restricts usage to its entries.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct AccessPolicy {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_models: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_models: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub denied_tools: Vec<String>,
+}
+
+/// Access policies, by project slug
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AccessPolicyConfig {
+    /// Applies to projects without their own policy
+    #[serde(default)]
+    pub default: AccessPolicy,
+    #[serde(default)]
+    pub projects: HashMap<String, AccessPolicy>,
+}
+
+impl AccessPolicyConfig {
+    pub fn for_project(&self, project_slug: &str) -> &AccessPolicy {
+        self.projects.get(project_slug).unwrap_or(&self.default)
+    }
+}
+
+fn matches(pattern: &str, name: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => pattern == name,
+    }
+}
+
+/// Whether an item known by `names` is allowed. It is denied when any of its names is.
+fn is_allowed(allowed: Option<&Vec<String>>, denied: &[String], names: &[&str]) -> bool {
+    let any_matches = |patterns: &[String]| {
+        names
+            .iter()
+            .any(|name| patterns.iter().any(|p| matches(p, name)))
+    };
+    !any_matches(denied) && allowed.is_none_or(|allowed| any_matches(allowed))
+}
+
+impl AccessPolicy {
+    /// Rejects `request` when its model or one of its tools is not allowed. `models` are the
+    /// names the resolved model is known by, the first one is reported when it is denied.
+    pub fn check(
+        &self,
+        request: &ChatCompletionRequest,
+        models: &[&str],
+    ) -> Result<(), GatewayError> {
+        if !is_allowed(self.allowed_models.as_ref(), &self.denied_models, models) {
+            return Err(GatewayError::PolicyDenied(format!(
+                "model {} is not allowed",
+                models.first().unwrap_or(&request.model.as_str())
+            )));
+        }
+
+        let tools = request
+            .tools
+            .iter()
+            .flatten()
+            .map(|tool| &tool.function.name)
+            .chain(request.functions.iter().flatten().map(|f| &f.name));
+        for tool in tools {
+            if !is_allowed(self.allowed_tools.as_ref(), &self.denied_tools, &[tool]) {
+                return Err(GatewayError::PolicyDenied(format!(
+                    "tool {tool} is not allowed"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::ResponseError;
+
+    fn request(tools: &[&str]) -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o",
+            "messages": [{"role": "user", "content": "What's the weather in Paris?"}],
+            "tools": tools.iter().map(|name| serde_json::json!({
+                "type": "function",
+                "function": {"name": name, "description": null, "parameters": null}
+            })).collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    fn config() -> AccessPolicyConfig {
+        serde_json::from_value(serde_json::json!({
+            "projects": {
+                "restricted": {
+                    "allowed_models": ["anthropic/*"],
+                    "denied_models": ["anthropic/claude-3-opus"],
+                    "denied_tools": ["shell_exec"]
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_denied_model_is_rejected() {
+        let policy = config();
+        let policy = policy.for_project("restricted");
+
+        let error = policy.check(&request(&[]), &["openai/gpt-4o"]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Policy denied: model openai/gpt-4o is not allowed"
+        );
+        assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+
+        assert!(policy
+            .check(&request(&[]), &["anthropic/claude-3-opus"])
+            .is_err());
+    }
+
+    #[test]
+    fn test_allowed_model_proceeds() {
+        let policy = config();
+
+        assert!(policy
+            .for_project("restricted")
+            .check(&request(&["get_weather"]), &["anthropic/claude-3-5-sonnet"])
+            .is_ok());
+        // Projects without a policy fall back to the default, which allows everything
+        assert!(policy
+            .for_project("other")
+            .check(&request(&["shell_exec"]), &["openai/gpt-4o"])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_denied_tool_is_rejected() {
+        let policy = config();
+
+        let error = policy
+            .for_project("restricted")
+            .check(
+                &request(&["get_weather", "shell_exec"]),
+                &["anthropic/claude-3-5-sonnet"],
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            GatewayError::PolicyDenied(message) if message == "tool shell_exec is not allowed"
+        ));
+    }
+}
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use vllora_llm::types::gateway::Extra;
+
+/// Forwards request metadata (`extra.metadata` and `extra.user`) to providers for their own
+/// analytics. User fields are available as `user_id`, `user_name` and `user_email`.
+///
+/// ```yaml
+/// metadata_propagation:
+///   user_key: user_id
+///   headers:
+///     openai:
+///       X-Team: team
+///   exclude: [user_email]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MetadataPropagationConfig {
+    /// Metadata key sent as the OpenAI `user` field, unless the request sets one
+    pub user_key: Option<String>,
+    /// Headers forwarded per provider, mapping header name to metadata key
+    pub headers: HashMap<String, HashMap<String, String>>,
+    /// Metadata keys that are never forwarded
+    pub exclude: Vec<String>,
+}
+
+/// Metadata forwarded to a provider
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropagatedMetadata {
+    pub user: Option<String>,
+    pub headers: HashMap<String, String>,
+    /// Metadata keys that were forwarded, for tracing without recording their values
+    pub keys: Vec<String>,
+}
+
+impl PropagatedMetadata {
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl MetadataPropagationConfig {
+    pub fn propagate(&self, provider: &str, extra: Option<&Extra>) -> PropagatedMetadata {
+        let metadata = request_metadata(extra);
+        let lookup = |key: &String| {
+            (!self.exclude.contains(key))
+                .then(|| metadata.get(key))
+                .flatten()
+                .cloned()
+        };
+
+        let mut propagated = PropagatedMetadata::default();
+        if let Some(key) = &self.user_key {
+            if let Some(user) = lookup(key) {
+                propagated.user = Some(user);
+                propagated.keys.push(key.clone());
+            }
+        }
+
+        for (header, key) in self.headers.get(provider).into_iter().flatten() {
+            if let Some(value) = lookup(key) {
+                propagated.headers.insert(header.clone(), value);
+                propagated.keys.push(key.clone());
+            }
+        }
+
+        propagated.keys.sort();
+        propagated.keys.dedup();
+        propagated
+    }
+}
+
+fn request_metadata(extra: Option<&Extra>) -> HashMap<String, String> {
+    let mut metadata = extra
+        .and_then(|extra| extra.metadata.clone())
+        .unwrap_or_default();
+
+    if let Some(user) = extra.and_then(|extra| extra.user.as_ref()) {
+        for (key, value) in [
+            ("user_id", &user.id),
+            ("user_name", &user.name),
+            ("user_email", &user.email),
+        ] {
+            if let Some(value) = value {
+                metadata
+                    .entry(key.to_string())
+                    .or_insert_with(|| value.clone());
+            }
+        }
+    }
+    metadata
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_llm::types::credentials::{ApiKeyCredentials, Credentials};
+    use vllora_llm::types::engine::{CompletionEngineParams, CompletionEngineParamsBuilder};
+    use vllora_llm::types::gateway::ChatCompletionRequest;
+    use vllora_llm::types::models::InferenceProvider;
+    use vllora_llm::types::provider::InferenceModelProvider;
+
+    fn extra() -> Extra {
+        serde_json::from_value(serde_json::json!({
+            "user": {"id": "u-42", "email": "jane@example.com"},
+            "metadata": {"team": "search", "session": "s-1"}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_metadata_key_reaches_openai_user() {
+        let config = MetadataPropagationConfig {
+            user_key: Some("team".to_string()),
+            ..Default::default()
+        };
+        let propagated = config.propagate("openai", Some(&extra()));
+        assert_eq!(propagated.user.as_deref(), Some("search"));
+
+        let request = ChatCompletionRequest {
+            model: "gpt-4o-mini".to_string(),
+            user: propagated.user,
+            ..Default::default()
+        };
+        let engine = CompletionEngineParamsBuilder::new()
+            .with_provider(InferenceProvider {
+                provider: InferenceModelProvider::OpenAI,
+                model_name: "gpt-4o-mini".to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            })
+            .with_credentials(Credentials::ApiKey(ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }))
+            .build(&request)
+            .unwrap();
+
+        match engine {
+            CompletionEngineParams::OpenAi { params, .. } => {
+                assert_eq!(params.user.as_deref(), Some("search"));
+            }
+            _ => panic!("expected OpenAI engine"),
+        }
+    }
+
+    #[test]
+    fn test_headers_are_per_provider_and_skip_excluded_keys() {
+        let config = MetadataPropagationConfig {
+            user_key: Some("user_email".to_string()),
+            headers: HashMap::from([(
+                "openai".to_string(),
+                HashMap::from([
+                    ("X-Team".to_string(), "team".to_string()),
+                    ("X-User".to_string(), "user_id".to_string()),
+                    ("X-Email".to_string(), "user_email".to_string()),
+                ]),
+            )]),
+            exclude: vec!["user_email".to_string()],
+        };
+
+        let propagated = config.propagate("openai", Some(&extra()));
+        assert_eq!(propagated.user, None);
+        assert_eq!(
+            propagated.headers,
+            HashMap::from([
+                ("X-Team".to_string(), "search".to_string()),
+                ("X-User".to_string(), "u-42".to_string()),
+            ])
+        );
+        assert_eq!(propagated.keys, vec!["team", "user_id"]);
+
+        assert!(config.propagate("anthropic", Some(&extra())).is_empty());
+        assert!(config.propagate("openai", None).is_empty());
+    }
+}
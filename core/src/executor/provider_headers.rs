@@ -0,0 +1,136 @@
+use actix_web::http::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use thiserror::Error;
+
+const REDACTED: &str = "[REDACTED]";
+
+/// Static headers sent with every request to a provider, e.g. beta flags or an organization
+/// id. Values can use the `{{ ENV_VAR }}` templating of the config file. Values of
+/// `sensitive` headers are redacted from traces.
+///
+/// ```yaml
+/// provider_headers:
+///   headers:
+///     anthropic:
+///       anthropic-beta: prompt-caching-2024-07-31
+///     openai:
+///       OpenAI-Organization: "{{ OPENAI_ORG_ID }}"
+///   sensitive: [OpenAI-Organization]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProviderHeadersConfig {
+    /// Headers per provider, mapping header name to value
+    pub headers: HashMap<String, HashMap<String, String>>,
+    /// Header names, in any case, whose values are never recorded
+    pub sensitive: Vec<String>,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ProviderHeadersError {
+    #[error("Invalid header name {name} for provider {provider}")]
+    InvalidName { provider: String, name: String },
+    #[error("Invalid value for header {name} of provider {provider}")]
+    InvalidValue { provider: String, name: String },
+}
+
+impl ProviderHeadersConfig {
+    /// Checks that every configured header can be sent
+    pub fn validate(&self) -> Result<(), ProviderHeadersError> {
+        for (provider, headers) in &self.headers {
+            for (name, value) in headers {
+                if HeaderName::from_bytes(name.as_bytes()).is_err() {
+                    return Err(ProviderHeadersError::InvalidName {
+                        provider: provider.clone(),
+                        name: name.clone(),
+                    });
+                }
+                if HeaderValue::from_str(value).is_err() {
+                    return Err(ProviderHeadersError::InvalidValue {
+                        provider: provider.clone(),
+                        name: name.clone(),
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Headers configured for `provider`
+    pub fn for_provider(&self, provider: &str) -> HashMap<String, String> {
+        self.headers.get(provider).cloned().unwrap_or_default()
+    }
+
+    /// Headers configured for `provider` with the values of sensitive ones redacted, for traces
+    pub fn redacted(&self, provider: &str) -> HashMap<String, String> {
+        self.for_provider(provider)
+            .into_iter()
+            .map(|(name, value)| {
+                let sensitive = self
+                    .sensitive
+                    .iter()
+                    .any(|sensitive| sensitive.eq_ignore_ascii_case(&name));
+                (
+                    name,
+                    if sensitive {
+                        REDACTED.to_string()
+                    } else {
+                        value
+                    },
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProviderHeadersConfig {
+        serde_json::from_value(serde_json::json!({
+            "headers": {
+                "anthropic": {"anthropic-beta": "prompt-caching-2024-07-31"},
+                "openai": {"OpenAI-Organization": "org-123"}
+            },
+            "sensitive": ["openai-organization"]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_headers_are_validated_and_redacted() {
+        let config = config();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            config.for_provider("anthropic")["anthropic-beta"],
+            "prompt-caching-2024-07-31"
+        );
+        assert!(config.for_provider("gemini").is_empty());
+
+        assert_eq!(
+            config.redacted("openai")["OpenAI-Organization"],
+            "[REDACTED]"
+        );
+        assert_eq!(
+            config.redacted("anthropic")["anthropic-beta"],
+            "prompt-caching-2024-07-31"
+        );
+
+        let invalid = ProviderHeadersConfig {
+            headers: HashMap::from([(
+                "openai".to_string(),
+                HashMap::from([("X-Team".to_string(), "search\nteam".to_string())]),
+            )]),
+            ..Default::default()
+        };
+        assert_eq!(
+            invalid.validate(),
+            Err(ProviderHeadersError::InvalidValue {
+                provider: "openai".to_string(),
+                name: "X-Team".to_string(),
+            })
+        );
+    }
+}
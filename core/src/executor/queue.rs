@@ -0,0 +1,446 @@
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::oneshot;
+use vllora_llm::types::gateway::Extra;
+
+pub const PRIORITY_HEADER: &str = "X-Priority";
+
+/// Dispatch order of queued requests, highest first
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPriority {
+    #[serde(alias = "best_effort")]
+    Low,
+    #[default]
+    Normal,
+    #[serde(alias = "premium")]
+    High,
+}
+
+impl FromStr for RequestPriority {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "low" | "best_effort" => Ok(Self::Low),
+            "normal" => Ok(Self::Normal),
+            "high" | "premium" => Ok(Self::High),
+            other => Err(format!("Unknown request priority: {other}")),
+        }
+    }
+}
+
+impl std::fmt::Display for RequestPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestPriority::Low => write!(f, "low"),
+            RequestPriority::Normal => write!(f, "normal"),
+            RequestPriority::High => write!(f, "high"),
+        }
+    }
+}
+
+/// Per provider queue in front of provider calls. When more requests are in flight than the
+/// provider allows, new ones wait and are dispatched by priority, then arrival order.
+/// With adaptive concurrency enabled the provider's current adaptive limit is used instead
+/// of `max_in_flight`, so the queue decides which waiting request gets each freed slot.
+///
+/// ```yaml
+/// request_queue:
+///   enabled: true
+///   max_queue_size: 100
+///   max_wait_ms: 10000
+///   tiers:
+///     enterprise: high
+///     free: low
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestQueueConfig {
+    pub enabled: bool,
+    pub max_in_flight: usize,
+    /// Waiting requests per provider before new ones are rejected
+    pub max_queue_size: usize,
+    /// Longest time a request waits before it is rejected
+    pub max_wait_ms: u64,
+    /// Key of `extra.metadata` holding the priority, used when the header is missing
+    pub metadata_key: String,
+    /// Priority of user tiers, used when neither header nor metadata set one
+    pub tiers: HashMap<String, RequestPriority>,
+}
+
+impl Default for RequestQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_in_flight: 32,
+            max_queue_size: 256,
+            max_wait_ms: 30_000,
+            metadata_key: "priority".to_string(),
+            tiers: HashMap::new(),
+        }
+    }
+}
+
+impl RequestQueueConfig {
+    /// Priority from the `X-Priority` header, then request metadata, then the user tier
+    pub fn priority(&self, header: Option<&str>, extra: Option<&Extra>) -> RequestPriority {
+        let from_metadata = || {
+            extra
+                .and_then(|extra| extra.metadata.as_ref())
+                .and_then(|metadata| metadata.get(&self.metadata_key))
+                .and_then(|value| value.parse().ok())
+        };
+        let from_tier = || {
+            extra
+                .and_then(|extra| extra.user.as_ref())
+                .and_then(|user| user.tiers.as_ref())
+                .into_iter()
+                .flatten()
+                .filter_map(|tier| self.tiers.get(tier).copied())
+                .max()
+        };
+
+        header
+            .and_then(|value| value.parse().ok())
+            .or_else(from_metadata)
+            .or_else(from_tier)
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum QueueError {
+    #[error("Request queue for {0} is full")]
+    Full(String),
+    #[error("Request waited {waited_ms}ms in the queue for {provider}")]
+    Timeout { provider: String, waited_ms: u64 },
+}
+
+struct Waiter {
+    priority: RequestPriority,
+    seq: u64,
+    dispatch: oneshot::Sender<()>,
+}
+
+impl PartialEq for Waiter {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Waiter {}
+
+impl PartialOrd for Waiter {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Waiter {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.priority
+            .cmp(&other.priority)
+            .then_with(|| other.seq.cmp(&self.seq))
+    }
+}
+
+#[derive(Default)]
+struct QueueState {
+    in_flight: usize,
+    capacity: usize,
+    next_seq: u64,
+    waiting: BinaryHeap<Waiter>,
+}
+
+#[derive(Default)]
+pub struct PriorityQueue {
+    state: Mutex<QueueState>,
+}
+
+impl PriorityQueue {
+    /// Waits for a dispatch slot. At most `capacity` requests hold a slot at once.
+    pub async fn enqueue(
+        self: Arc<Self>,
+        provider: &str,
+        priority: RequestPriority,
+        capacity: usize,
+        config: &RequestQueueConfig,
+    ) -> Result<QueuePermit, QueueError> {
+        let started = Instant::now();
+        let mut pending = {
+            let mut state = self.state.lock();
+            state.capacity = capacity.max(1);
+            state.waiting.retain(|waiter| !waiter.dispatch.is_closed());
+            // The capacity may have grown since the waiters were queued
+            while state.in_flight < state.capacity {
+                let Some(waiter) = state.waiting.pop() else {
+                    break;
+                };
+                if waiter.dispatch.send(()).is_ok() {
+                    state.in_flight += 1;
+                }
+            }
+
+            if state.waiting.is_empty() && state.in_flight < state.capacity {
+                state.in_flight += 1;
+                return Ok(QueuePermit::new(self.clone(), started));
+            }
+            if state.waiting.len() >= config.max_queue_size {
+                return Err(QueueError::Full(provider.to_string()));
+            }
+
+            let (dispatch, dispatched) = oneshot::channel();
+            let seq = state.next_seq;
+            state.next_seq += 1;
+            state.waiting.push(Waiter {
+                priority,
+                seq,
+                dispatch,
+            });
+            Pending {
+                queue: self.clone(),
+                seq,
+                dispatched,
+                dispatched_ok: false,
+            }
+        };
+
+        let max_wait = Duration::from_millis(config.max_wait_ms);
+        if let Ok(Ok(())) = tokio::time::timeout(max_wait, &mut pending.dispatched).await {
+            pending.dispatched_ok = true;
+            return Ok(QueuePermit::new(self.clone(), started));
+        }
+
+        drop(pending);
+        Err(QueueError::Timeout {
+            provider: provider.to_string(),
+            waited_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+
+    /// Hands the freed slot to the highest priority waiter still waiting
+    fn release(&self) {
+        let mut state = self.state.lock();
+        if state.in_flight <= state.capacity {
+            while let Some(waiter) = state.waiting.pop() {
+                if waiter.dispatch.send(()).is_ok() {
+                    return;
+                }
+            }
+        }
+        state.in_flight -= 1;
+    }
+
+    pub fn waiting(&self) -> usize {
+        self.state.lock().waiting.len()
+    }
+}
+
+/// Request waiting for a slot. Leaves the queue when the wait times out or is cancelled,
+/// passing on a slot that was handed over in the meantime.
+struct Pending {
+    queue: Arc<PriorityQueue>,
+    seq: u64,
+    dispatched: oneshot::Receiver<()>,
+    dispatched_ok: bool,
+}
+
+impl Drop for Pending {
+    fn drop(&mut self) {
+        if self.dispatched_ok {
+            return;
+        }
+        let handed_over = {
+            let mut state = self.queue.state.lock();
+            state.waiting.retain(|waiter| waiter.seq != self.seq);
+            self.dispatched.close();
+            self.dispatched.try_recv().is_ok()
+        };
+        if handed_over {
+            self.queue.release();
+        }
+    }
+}
+
+/// Dispatch slot of one request, handed to the next waiter on drop
+pub struct QueuePermit {
+    queue: Arc<PriorityQueue>,
+    pub waited: Duration,
+}
+
+impl QueuePermit {
+    fn new(queue: Arc<PriorityQueue>, started: Instant) -> Self {
+        Self {
+            queue,
+            waited: started.elapsed(),
+        }
+    }
+}
+
+impl Drop for QueuePermit {
+    fn drop(&mut self) {
+        self.queue.release();
+    }
+}
+
+#[derive(Default)]
+pub struct RequestQueues {
+    queues: DashMap<String, Arc<PriorityQueue>>,
+}
+
+impl RequestQueues {
+    pub async fn enqueue(
+        &self,
+        provider: &str,
+        priority: RequestPriority,
+        capacity: usize,
+        config: &RequestQueueConfig,
+    ) -> Result<QueuePermit, QueueError> {
+        let queue = self.queues.entry(provider.to_string()).or_default().clone();
+        queue.enqueue(provider, priority, capacity, config).await
+    }
+}
+
+/// Process-wide request queues shared by all requests
+pub fn request_queues() -> &'static RequestQueues {
+    static QUEUES: OnceLock<RequestQueues> = OnceLock::new();
+    QUEUES.get_or_init(RequestQueues::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RequestQueueConfig {
+        RequestQueueConfig {
+            enabled: true,
+            max_queue_size: 10,
+            max_wait_ms: 1_000,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_high_priority_request_jumps_ahead_of_queued_low_priority() {
+        let queue = Arc::new(PriorityQueue::default());
+        let config = config();
+        let running = queue
+            .clone()
+            .enqueue("openai", RequestPriority::Normal, 1, &config)
+            .await
+            .unwrap();
+
+        let (order_tx, mut order_rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut tasks = vec![];
+        for (name, priority) in [
+            ("low-1", RequestPriority::Low),
+            ("low-2", RequestPriority::Low),
+            ("high", RequestPriority::High),
+        ] {
+            let queue = queue.clone();
+            let config = config.clone();
+            let order_tx = order_tx.clone();
+            tasks.push(tokio::spawn(async move {
+                let _permit = queue.enqueue("openai", priority, 1, &config).await.unwrap();
+                order_tx.send(name).unwrap();
+            }));
+            // Arrive in a known order
+            while queue.waiting() < tasks.len() {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        drop(running);
+        for task in tasks {
+            task.await.unwrap();
+        }
+        drop(order_tx);
+
+        let mut order = vec![];
+        while let Some(name) = order_rx.recv().await {
+            order.push(name);
+        }
+        assert_eq!(order, vec!["high", "low-1", "low-2"]);
+    }
+
+    #[tokio::test]
+    async fn test_full_queue_and_max_wait_are_rejected() {
+        let queue = Arc::new(PriorityQueue::default());
+        let config = RequestQueueConfig {
+            max_queue_size: 1,
+            max_wait_ms: 20,
+            ..config()
+        };
+        let _running = queue
+            .clone()
+            .enqueue("openai", RequestPriority::Normal, 1, &config)
+            .await
+            .unwrap();
+
+        let waiting = tokio::spawn({
+            let queue = queue.clone();
+            let config = config.clone();
+            async move {
+                queue
+                    .enqueue("openai", RequestPriority::High, 1, &config)
+                    .await
+                    .map(|_| ())
+            }
+        });
+        while queue.waiting() < 1 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(
+            queue
+                .clone()
+                .enqueue("openai", RequestPriority::High, 1, &config)
+                .await
+                .err(),
+            Some(QueueError::Full("openai".to_string()))
+        );
+        assert!(matches!(
+            waiting.await.unwrap(),
+            Err(QueueError::Timeout { .. })
+        ));
+        assert_eq!(queue.waiting(), 0);
+    }
+
+    #[test]
+    fn test_priority_from_header_metadata_and_tier() {
+        let config = RequestQueueConfig {
+            tiers: HashMap::from([("enterprise".to_string(), RequestPriority::High)]),
+            ..config()
+        };
+        let extra: Extra = serde_json::from_value(serde_json::json!({
+            "user": {"tiers": ["free", "enterprise"]},
+            "metadata": {"priority": "best_effort"}
+        }))
+        .unwrap();
+        let tier_only: Extra = serde_json::from_value(serde_json::json!({
+            "user": {"tiers": ["enterprise"]}
+        }))
+        .unwrap();
+
+        assert_eq!(
+            config.priority(Some("premium"), Some(&extra)),
+            RequestPriority::High
+        );
+        assert_eq!(config.priority(None, Some(&extra)), RequestPriority::Low);
+        assert_eq!(
+            config.priority(Some("urgent"), Some(&tier_only)),
+            RequestPriority::High
+        );
+        assert_eq!(config.priority(None, None), RequestPriority::Normal);
+    }
+}
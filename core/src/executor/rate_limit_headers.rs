@@ -0,0 +1,30 @@
+use actix_web::HttpResponseBuilder;
+use serde::{Deserialize, Serialize};
+use vllora_llm::types::rate_limit::ProviderRateLimit;
+
+/// Surfaces the rate limits a provider reported as normalized `x-vllora-ratelimit-*`
+/// headers on the gateway response, so clients can see the quota left before they hit it.
+///
+/// ```yaml
+/// rate_limit_headers:
+///   enabled: true
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitHeadersConfig {
+    pub enabled: bool,
+}
+
+impl RateLimitHeadersConfig {
+    pub fn apply(&self, builder: &mut HttpResponseBuilder, rate_limit: Option<&ProviderRateLimit>) {
+        if !self.enabled {
+            return;
+        }
+        for header in rate_limit
+            .into_iter()
+            .flat_map(ProviderRateLimit::response_headers)
+        {
+            builder.insert_header(header);
+        }
+    }
+}
@@ -15,6 +15,7 @@ use tracing_opentelemetry::OpenTelemetrySpanExt;
 pub use vllora_llm::async_openai::types::responses as ResponsesTypes;
 use vllora_llm::async_openai::types::responses::CreateResponse;
 use vllora_llm::client::responses::Responses;
+use vllora_llm::provider::openai::responses::hosted_tool_types;
 use vllora_llm::types::credentials::Credentials;
 use vllora_llm::types::credentials_ident::CredentialsIdent;
 use vllora_llm::types::engine::Model;
@@ -75,6 +76,19 @@ pub async fn handle_create_response(
         }
     };
 
+    let hosted_tools = hosted_tool_types(request);
+    if !hosted_tools.is_empty()
+        && llm_model.inference_provider.provider != InferenceModelProvider::OpenAI
+    {
+        return Err(GatewayApiError::GatewayError(GatewayError::InvalidRequest(
+            format!(
+                "Hosted tools {} are not supported by provider {}",
+                hosted_tools.join(", "),
+                llm_model.inference_provider.provider
+            ),
+        )));
+    }
+
     let key = GatewayCredentials::extract_key_from_model(
         &llm_model,
         &executor_context.project_id.to_string(),
@@ -199,7 +213,8 @@ async fn resolve_model_instance(
         CredentialsIdent::Vllora
     } else {
         CredentialsIdent::Own
-    };
+    }
+    .with_billing_label(executor_context.billing_label.clone());
 
     let db_model = Model {
         name: llm_model.model.clone(),
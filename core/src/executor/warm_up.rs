@@ -0,0 +1,228 @@
+use crate::credentials::{GatewayCredentials, KeyStorage};
+use crate::error::GatewayError;
+use crate::model::ModelMetadataFactory;
+use crate::types::metadata::project::Project;
+use crate::GatewayApiError;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use vllora_llm::types::credentials::Credentials;
+use vllora_llm::types::engine::{CompletionEngineParamsBuilder, ExecutionOptions};
+use vllora_llm::types::gateway::ChatCompletionRequest;
+use vllora_llm::types::instance::init_model_instance;
+use vllora_llm::types::message::{Message, MessageContentType, MessageType};
+use vllora_llm::types::models::ModelMetadata;
+
+/// Models called with a one token completion at startup, so their credentials are checked and
+/// the first request to them doesn't pay for opening the provider connection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarmUpConfig {
+    pub enabled: bool,
+    /// Qualified model names, e.g. `openai/gpt-4o-mini`
+    pub models: Vec<String>,
+    /// Limit for warming up a single model
+    pub timeout_secs: u64,
+}
+
+impl Default for WarmUpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            models: vec![],
+            timeout_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WarmUpReport {
+    pub warmed: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+/// Sends a one token completion to `llm_model`, which builds its provider client and opens the
+/// connection. Fails when the provider can't be reached or rejects the credentials.
+pub async fn warm_up_model(
+    llm_model: &ModelMetadata,
+    key: Option<&Credentials>,
+) -> Result<(), GatewayApiError> {
+    let mut builder = CompletionEngineParamsBuilder::new()
+        .with_provider(llm_model.inference_provider.clone())
+        .with_model_name(llm_model.inference_provider.model_name.clone())
+        .with_capabilities(llm_model.capabilities.clone())
+        .with_execution_options(ExecutionOptions {
+            max_retries: Some(0),
+            ..Default::default()
+        });
+    if let Some(credentials) = key {
+        builder = builder.with_credentials(credentials.clone());
+    }
+
+    let request = ChatCompletionRequest {
+        model: llm_model.qualified_model_name(),
+        max_tokens: Some(1),
+        ..Default::default()
+    };
+    let engine = builder.build(&request)?;
+    let instance = init_model_instance(engine, HashMap::new())
+        .await
+        .map_err(GatewayError::from)?;
+
+    let ping = Message {
+        model_name: llm_model.qualified_model_name(),
+        thread_id: None,
+        user_id: String::new(),
+        content_type: MessageContentType::Text,
+        content: Some("ping".to_string()),
+        content_array: vec![],
+        r#type: MessageType::HumanMessage,
+        tool_call_id: None,
+        tool_calls: None,
+        created_at: None,
+        name: None,
+    };
+    let (tx, _rx) = tokio::sync::mpsc::channel(100);
+    instance
+        .invoke(HashMap::new(), tx, vec![ping], HashMap::new())
+        .await
+        .map_err(GatewayError::from)?;
+    Ok(())
+}
+
+/// Runs `warm` for every model concurrently, each bounded by `timeout`. Failures are logged
+/// and reported, never returned as errors.
+pub async fn warm_up_with<F, Fut>(models: &[String], timeout: Duration, warm: F) -> WarmUpReport
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<(), String>>,
+{
+    let results = join_all(models.iter().map(|model| {
+        let warming = warm(model.clone());
+        async move {
+            let result = tokio::time::timeout(timeout, warming)
+                .await
+                .unwrap_or_else(|_| Err(format!("timed out after {timeout:?}")));
+            (model.clone(), result)
+        }
+    }))
+    .await;
+
+    let mut report = WarmUpReport::default();
+    for (model, result) in results {
+        match result {
+            Ok(()) => {
+                tracing::info!("Warmed up {model}");
+                report.warmed.push(model);
+            }
+            Err(e) => {
+                tracing::warn!("Failed to warm up {model}: {e}");
+                report.failed.push((model, e));
+            }
+        }
+    }
+    report
+}
+
+/// Checks credentials and opens provider connections for the configured models of a project
+pub async fn warm_up(
+    config: &WarmUpConfig,
+    model_metadata_factory: &dyn ModelMetadataFactory,
+    key_storage: &dyn KeyStorage,
+    project: &Project,
+) -> WarmUpReport {
+    warm_up_with(
+        &config.models,
+        Duration::from_secs(config.timeout_secs),
+        |model| async move {
+            let llm_model = model_metadata_factory
+                .get_model_metadata(&model, false, false, Some(&project.id))
+                .await
+                .map_err(|e| e.to_string())?;
+            let key = GatewayCredentials::extract_key_from_model(
+                &llm_model,
+                &project.slug,
+                "default",
+                key_storage,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            warm_up_model(&llm_model, key.as_ref())
+                .await
+                .map_err(|e| e.to_string())
+        },
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use vllora_llm::types::models::InferenceProvider;
+    use vllora_llm::types::provider::InferenceModelProvider;
+
+    fn openai_model(model: &str) -> ModelMetadata {
+        ModelMetadata {
+            model: model.to_string(),
+            inference_provider: InferenceProvider {
+                provider: InferenceModelProvider::OpenAI,
+                model_name: model.to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_initializes_clients_for_each_model() {
+        let initialized = Mutex::new(vec![]);
+        let models = vec![
+            "openai/gpt-4o-mini".to_string(),
+            "anthropic/claude-3-5-haiku".to_string(),
+            "gemini/slow".to_string(),
+            "bedrock/broken".to_string(),
+        ];
+
+        let report = warm_up_with(&models, Duration::from_millis(50), |model| {
+            let initialized = &initialized;
+            async move {
+                match model.as_str() {
+                    "gemini/slow" => {
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                        Ok(())
+                    }
+                    "bedrock/broken" => Err("missing credentials".to_string()),
+                    _ => {
+                        initialized.lock().unwrap().push(model);
+                        Ok(())
+                    }
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(report.warmed, models[..2].to_vec());
+        assert_eq!(*initialized.lock().unwrap(), models[..2].to_vec());
+        assert_eq!(report.failed.len(), 2);
+        assert!(report.failed[0].1.contains("timed out"));
+        assert_eq!(report.failed[1].1, "missing credentials");
+    }
+
+    #[tokio::test]
+    async fn test_warm_up_model_fails_when_provider_is_unreachable() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let endpoint = format!("http://{}/v1", listener.local_addr().unwrap());
+        drop(listener);
+
+        let key = Credentials::ApiKeyWithEndpoint {
+            api_key: "sk-test".to_string(),
+            endpoint,
+        };
+        let result = warm_up_model(&openai_model("gpt-4o-mini"), Some(&key)).await;
+        assert!(result.is_err());
+    }
+}
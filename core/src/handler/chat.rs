@@ -8,8 +8,8 @@ use crate::executor::context::ExecutorContext;
 use crate::metadata::pool::DbPool;
 use crate::metadata::services::project::ProjectServiceImpl;
 use crate::model::DefaultModelMetadataFactory;
-use crate::routing::interceptor::rate_limiter::InMemoryRateLimiterService;
-use crate::routing::RoutingStrategy;
+use crate::routing::interceptor::rate_limit_store::rate_limiter_service;
+use crate::routing::{RoutingConfig, RoutingStrategy};
 use crate::types::guardrails::service::GuardrailsEvaluator;
 use crate::types::metadata::services::project::ProjectService;
 use crate::usage::InMemoryStorage;
@@ -36,7 +36,14 @@ use vllora_llm::types::gateway::{ChatCompletionDelta, CostCalculator};
 
 use crate::credentials::KeyStorage;
 use crate::executor::chat_completion::breakpoint::BreakpointManager;
+use crate::executor::chat_completion::response_cache::request_hash;
 use crate::executor::chat_completion::routed_executor::RoutedExecutor;
+use crate::handler::idempotency::{
+    idempotency_store, with_idempotency, IdempotencyConfig, IDEMPOTENCY_KEY_HEADER,
+};
+use crate::handler::prompts::StoredPromptsConfig;
+use crate::handler::request_limits::RequestLimitsConfig;
+use crate::handler::validation::validate_chat_request;
 
 pub type SSOChatEvent = (
     Option<ChatCompletionDelta>,
@@ -129,6 +136,26 @@ pub async fn create_chat_completion(
         title = tracing::field::Empty,
         cost = tracing::field::Empty,
         usage = tracing::field::Empty,
+        routing_bypassed = tracing::field::Empty,
+        context_upgrade = tracing::field::Empty,
+        cache = tracing::field::Empty,
+        idempotent_replay = tracing::field::Empty,
+        policy_denied = tracing::field::Empty,
+        endpoint = tracing::field::Empty,
+        model_defaulted = tracing::field::Empty,
+        correlation_id = tracing::field::Empty,
+        propagated_metadata = tracing::field::Empty,
+        provider_headers = tracing::field::Empty,
+        clamped_params = tracing::field::Empty,
+        priority = tracing::field::Empty,
+        queue_wait_ms = tracing::field::Empty,
+        coalesced = tracing::field::Empty,
+        coalesced_requests = tracing::field::Empty,
+        post_processors = tracing::field::Empty,
+        redactions = tracing::field::Empty,
+        fallback_response = tracing::field::Empty,
+        resolution = tracing::field::Empty,
+        prompt_mode = tracing::field::Empty,
     ));
 
     let thread_title = req.headers().get("X-Thread-Title").map_or_else(
@@ -157,13 +184,34 @@ pub async fn create_chat_completion(
     }
 
     let memory_storage = req.app_data::<Arc<Mutex<InMemoryStorage>>>().cloned();
-    let rate_limiter_service = InMemoryRateLimiterService::new();
+    let rate_limiter_service = rate_limiter_service();
     let guardrails_evaluator_service = evaluator_service.clone().into_inner();
 
     let thread_id = thread_id.value();
 
     let cost_calculator = cost_calculator.into_inner();
-    let request = request.into_inner();
+    let mut request = request.into_inner();
+    req.app_data::<RequestLimitsConfig>()
+        .cloned()
+        .unwrap_or_default()
+        .check_media(&request.request)?;
+    let prompt_mode = req
+        .app_data::<StoredPromptsConfig>()
+        .cloned()
+        .unwrap_or_default()
+        .apply(&mut request.request)?;
+    if let Some(mode) = prompt_mode {
+        span.record("prompt_mode", mode.as_str());
+    }
+    validate_chat_request(&request.request)?;
+    let model_defaulted = req
+        .app_data::<RoutingConfig>()
+        .map(|config| config.for_project(Some(&project.slug)))
+        .unwrap_or_default()
+        .apply_default_model(&mut request.request)?;
+    if model_defaulted {
+        span.record("model_defaulted", true);
+    }
     let (_handle, callback_handler_fn) = prepare_request(
         &callback_handler.get_ref().clone(),
         "vllora",
@@ -190,7 +238,7 @@ pub async fn create_chat_completion(
     };
 
     let db_pool = db_pool.into_inner();
-    let executor_context = ExecutorContext::new(
+    let mut executor_context = ExecutorContext::new(
         callback_handler_fn,
         cost_calculator,
         Arc::new(Box::new(
@@ -205,20 +253,49 @@ pub async fn create_chat_completion(
         None,
     )?;
 
-    let executor = RoutedExecutor::new(request.clone());
-    executor
-        .execute(
-            &executor_context,
-            memory_storage,
-            None,
-            Some(&thread_id),
-            Some(&breakpoint_manager.into_inner()),
-            &project_slug,
-            "default",
-        )
+    let plugins = executor_context.plugins.clone();
+    plugins
+        .on_request(&mut executor_context.plugin_context, &mut request)
         .instrument(span.clone())
-        .await
-        .inspect_err(|e| {
-            span.record("error", e.to_string());
-        })
+        .await;
+
+    // Streamed responses can't be stored for replay, so only buffered requests are idempotent
+    let idempotency_key = req
+        .headers()
+        .get(IDEMPOTENCY_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .filter(|_| !request.request.stream.unwrap_or(false))
+        .map(|key| format!("{project_slug}:{key}"));
+    let idempotency_config = req
+        .app_data::<IdempotencyConfig>()
+        .cloned()
+        .unwrap_or_default();
+    let request_fingerprint = match &idempotency_key {
+        Some(_) => request_hash(&request)?,
+        None => String::new(),
+    };
+
+    let executor = RoutedExecutor::new(request.clone());
+    with_idempotency(
+        idempotency_store(),
+        idempotency_key.as_deref(),
+        &request_fingerprint,
+        &idempotency_config,
+        || {
+            executor.execute(
+                &executor_context,
+                memory_storage,
+                Some(&executor_context.project_id),
+                Some(&thread_id),
+                Some(&breakpoint_manager.into_inner()),
+                &project_slug,
+                "default",
+            )
+        },
+    )
+    .instrument(span.clone())
+    .await
+    .inspect_err(|e| {
+        span.record("error", e.to_string());
+    })
 }
@@ -1,14 +1,17 @@
 use crate::executor::embeddings::handle_embeddings;
 use crate::types::embed::EmbeddingResult;
+use crate::types::metadata::project::Project;
 use crate::types::metadata::services::model::ModelService;
 use actix_web::{web, HttpResponse};
 use actix_web::{HttpMessage, HttpRequest};
 use tracing::Span;
 use tracing_futures::Instrument;
 use vllora_llm::types::credentials::Credentials;
+use vllora_llm::types::credentials_ident::CredentialsIdent;
 
 use vllora_llm::types::gateway::{
     CostCalculator, CreateEmbeddingRequest, CreateEmbeddingResponse, EmbeddingData, EmbeddingUsage,
+    GatewayModelUsage, Usage,
 };
 
 use crate::handler::extract_tags;
@@ -17,6 +20,45 @@ use crate::GatewayApiError;
 
 use super::{can_execute_llm_for_request, find_model_by_full_name};
 
+/// Maps the embeddings returned by the provider to the OpenAI response shape
+fn map_embedding_response(
+    result: &EmbeddingResult,
+    model: &str,
+    cost: f64,
+) -> CreateEmbeddingResponse {
+    let data = match result {
+        EmbeddingResult::Float(response) => response
+            .data
+            .iter()
+            .map(|v| EmbeddingData {
+                object: v.object.clone(),
+                embedding: v.embedding.clone().into(),
+                index: v.index,
+            })
+            .collect(),
+        EmbeddingResult::Base64(response) => response
+            .data
+            .iter()
+            .map(|v| EmbeddingData {
+                object: v.object.clone(),
+                embedding: v.embedding.clone().into(),
+                index: v.index,
+            })
+            .collect(),
+    };
+
+    CreateEmbeddingResponse {
+        object: "list".into(),
+        data,
+        model: model.to_string(),
+        usage: EmbeddingUsage {
+            prompt_tokens: result.usage().prompt_tokens,
+            total_tokens: result.usage().total_tokens,
+            cost,
+        },
+    }
+}
+
 pub async fn embeddings_handler(
     request: web::Json<CreateEmbeddingRequest>,
     models_service: web::Data<Box<dyn ModelService>>,
@@ -26,8 +68,13 @@ pub async fn embeddings_handler(
 ) -> Result<HttpResponse, GatewayApiError> {
     can_execute_llm_for_request(&req).await?;
     let request = request.into_inner();
-    let llm_model =
-        find_model_by_full_name(&request.model, models_service.as_ref().as_ref(), None)?;
+    let project_id = req.extensions().get::<Project>().map(|p| p.id);
+    let llm_model = find_model_by_full_name(
+        &request.model,
+        models_service.as_ref().as_ref(),
+        None,
+        project_id.as_ref(),
+    )?;
     let key_credentials = req.extensions().get::<Credentials>().cloned();
 
     let span = Span::or_current(tracing::info_span!(
@@ -37,43 +84,53 @@ pub async fn embeddings_handler(
         response = tracing::field::Empty,
         error = tracing::field::Empty,
         message_id = tracing::field::Empty,
+        usage = tracing::field::Empty,
+        cost = tracing::field::Empty,
     ));
     span.record("request", &serde_json::to_string(&request)?);
 
     let tags = extract_tags(&req)?;
+    let cost_calculator = cost_calculator.into_inner();
+    let credentials_ident = match key_credentials {
+        Some(_) => CredentialsIdent::Own,
+        None => CredentialsIdent::Vllora,
+    };
 
     let result = handle_embeddings(
         request,
         callback_handler.get_ref(),
         &llm_model,
         key_credentials.as_ref(),
-        cost_calculator.into_inner(),
+        cost_calculator.clone(),
         tags,
         req,
     )
-    .instrument(span)
+    .instrument(span.clone())
     .await?;
 
-    let data = match &result {
-        EmbeddingResult::Float(response) => response
-            .data
-            .iter()
-            .map(|v| EmbeddingData {
-                object: v.object.clone(),
-                embedding: v.embedding.clone().into(),
-                index: v.index,
-            })
-            .collect(),
-        EmbeddingResult::Base64(response) => response
-            .data
-            .iter()
-            .map(|v| EmbeddingData {
-                object: v.object.clone(),
-                embedding: v.embedding.clone().into(),
-                index: v.index,
-            })
-            .collect(),
+    let usage = GatewayModelUsage {
+        input_tokens: result.usage().prompt_tokens,
+        total_tokens: result.usage().total_tokens,
+        ..Default::default()
     };
+    let cost = match cost_calculator
+        .calculate_cost(
+            &llm_model.price,
+            &Usage::CompletionModelUsage(usage.clone()),
+            &credentials_ident,
+        )
+        .await
+    {
+        Ok(cost) => {
+            span.record("cost", serde_json::to_string(&cost)?);
+            cost.cost
+        }
+        Err(e) => {
+            tracing::error!("Error calculating cost: {:?}", e);
+            0.0
+        }
+    };
+    span.record("usage", serde_json::to_string(&usage)?);
 
     Ok(HttpResponse::Ok()
         .append_header(("X-Model-Name", llm_model.model.clone()))
@@ -81,14 +138,67 @@ pub async fn embeddings_handler(
             "X-Provider-Name",
             llm_model.inference_provider.provider.to_string(),
         ))
-        .json(CreateEmbeddingResponse {
-            object: "list".into(),
-            data,
-            model: llm_model.model.clone(),
-            usage: EmbeddingUsage {
-                prompt_tokens: result.usage().prompt_tokens,
-                total_tokens: result.usage().total_tokens,
-                cost: 0.0,
-            },
+        .json(map_embedding_response(&result, &llm_model.model, cost)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_llm::async_openai::types::embeddings::{
+        CreateEmbeddingResponse as OpenAiEmbeddingResponse, Embedding,
+        EmbeddingUsage as OpenAiEmbeddingUsage,
+    };
+    use vllora_llm::types::gateway::{EncodingFormat, Input};
+
+    #[test]
+    fn test_openai_embedding_request_and_response_shape() {
+        let request: CreateEmbeddingRequest = serde_json::from_value(serde_json::json!({
+            "model": "openai/text-embedding-3-small",
+            "input": ["first", "second"],
+            "encoding_format": "float"
         }))
+        .unwrap();
+        assert!(matches!(request.input, Input::Array(ref inputs) if inputs.len() == 2));
+        assert!(matches!(request.encoding_format, EncodingFormat::Float));
+
+        let request: CreateEmbeddingRequest = serde_json::from_value(serde_json::json!({
+            "model": "openai/text-embedding-3-small",
+            "input": "single"
+        }))
+        .unwrap();
+        assert!(matches!(request.input, Input::String(ref input) if input == "single"));
+
+        let result = EmbeddingResult::Float(OpenAiEmbeddingResponse {
+            object: "list".to_string(),
+            model: "text-embedding-3-small".to_string(),
+            data: (0..2)
+                .map(|index| Embedding {
+                    index,
+                    object: "embedding".to_string(),
+                    embedding: vec![0.5, -0.25],
+                })
+                .collect(),
+            usage: OpenAiEmbeddingUsage {
+                prompt_tokens: 4,
+                total_tokens: 4,
+            },
+        });
+
+        let response = serde_json::to_value(map_embedding_response(
+            &result,
+            "openai/text-embedding-3-small",
+            0.00008,
+        ))
+        .unwrap();
+        assert_eq!(response["object"], "list");
+        assert_eq!(response["data"][1]["index"], 1);
+        assert_eq!(response["data"][1]["object"], "embedding");
+        assert_eq!(response["usage"]["cost"], 0.00008);
+
+        // OpenAI clients can read the response
+        let response: OpenAiEmbeddingResponse = serde_json::from_value(response).unwrap();
+        assert_eq!(response.data.len(), 2);
+        assert_eq!(response.data[0].embedding, vec![0.5, -0.25]);
+        assert_eq!(response.usage.prompt_tokens, 4);
+    }
 }
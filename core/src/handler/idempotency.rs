@@ -0,0 +1,436 @@
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::http::StatusCode;
+use actix_web::HttpResponse;
+use bytes::Bytes;
+use tokio::sync::watch;
+
+use crate::error::GatewayError;
+use crate::GatewayApiError;
+
+/// Request header carrying the client chosen idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Response header set when a response is replayed for a repeated idempotency key
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "Idempotent-Replayed";
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct IdempotencyConfig {
+    /// How long the response to an idempotency key is replayed, in seconds
+    #[serde(default = "default_ttl_secs")]
+    pub ttl_secs: u64,
+    /// Responses kept for replay. The oldest are evicted first once the limit is reached.
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_ttl_secs() -> u64 {
+    60 * 60
+}
+
+fn default_max_entries() -> usize {
+    10_000
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: default_ttl_secs(),
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+enum Entry<T> {
+    /// The first request with the key is still executing. Its sender is dropped once it
+    /// completes or fails, which wakes the duplicates waiting on it.
+    InProgress {
+        fingerprint: String,
+        done: watch::Receiver<()>,
+    },
+    Completed {
+        fingerprint: String,
+        expires_at: Instant,
+        result: T,
+    },
+}
+
+impl<T> Entry<T> {
+    fn fingerprint(&self) -> &str {
+        match self {
+            Entry::InProgress { fingerprint, .. } | Entry::Completed { fingerprint, .. } => {
+                fingerprint
+            }
+        }
+    }
+}
+
+/// Outcome of claiming an idempotency key
+pub enum Claim<'a, T> {
+    /// The key already completed, this is its stored result
+    Replay(T),
+    /// The caller is the first with this key and must execute the request
+    Execute(IdempotencyGuard<'a, T>),
+    /// The key was used by a request with a different body
+    Mismatch,
+}
+
+struct Entries<T> {
+    by_key: HashMap<String, Entry<T>>,
+    /// Completed keys in the order they expire, as every key is kept for the same TTL
+    expiry: VecDeque<(Instant, String)>,
+}
+
+impl<T> Entries<T> {
+    /// Drops expired results, then the oldest ones above `max_entries`. Only walks the
+    /// entries it removes.
+    fn evict(&mut self, now: Instant, max_entries: usize) {
+        while let Some((expires_at, key)) = self.expiry.front() {
+            if *expires_at > now && self.expiry.len() <= max_entries {
+                break;
+            }
+            // The key may have expired and been completed again since
+            if matches!(
+                self.by_key.get(key),
+                Some(Entry::Completed { expires_at: current, .. }) if current == expires_at
+            ) {
+                self.by_key.remove(key);
+            }
+            self.expiry.pop_front();
+        }
+    }
+}
+
+/// Results of requests keyed by idempotency key.
+///
+/// Concurrent requests with the same key wait for the first one. When it fails the key is
+/// released, so a retry executes again. A key is bound to the fingerprint of the request
+/// that first used it, requests with another fingerprint are rejected.
+pub struct IdempotencyStore<T> {
+    entries: Mutex<Entries<T>>,
+}
+
+impl<T> Default for IdempotencyStore<T> {
+    fn default() -> Self {
+        Self {
+            entries: Mutex::new(Entries {
+                by_key: HashMap::new(),
+                expiry: VecDeque::new(),
+            }),
+        }
+    }
+}
+
+impl<T: Clone> IdempotencyStore<T> {
+    pub async fn claim(
+        &self,
+        key: &str,
+        fingerprint: &str,
+        config: &IdempotencyConfig,
+    ) -> Claim<'_, T> {
+        loop {
+            let mut in_progress = {
+                let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+                let now = Instant::now();
+
+                let running = match entries.by_key.get(key) {
+                    // Expired results are left for `Entries::evict` and treated as absent
+                    Some(Entry::Completed { expires_at, .. }) if *expires_at <= now => None,
+                    Some(entry) if entry.fingerprint() != fingerprint => return Claim::Mismatch,
+                    Some(Entry::Completed { result, .. }) => return Claim::Replay(result.clone()),
+                    Some(Entry::InProgress { done, .. }) => Some(done.clone()),
+                    None => None,
+                };
+
+                match running {
+                    Some(done) => done,
+                    None => {
+                        let (tx, rx) = watch::channel(());
+                        entries.by_key.insert(
+                            key.to_string(),
+                            Entry::InProgress {
+                                fingerprint: fingerprint.to_string(),
+                                done: rx,
+                            },
+                        );
+                        return Claim::Execute(IdempotencyGuard {
+                            store: self,
+                            key: key.to_string(),
+                            fingerprint: fingerprint.to_string(),
+                            ttl: Duration::from_secs(config.ttl_secs),
+                            max_entries: config.max_entries,
+                            completed: false,
+                            _tx: tx,
+                        });
+                    }
+                }
+            };
+
+            // Resolves once the first request drops its sender, then the key is claimed again
+            let _ = in_progress.changed().await;
+        }
+    }
+}
+
+/// Held by the request executing for an idempotency key. Dropping it without
+/// [`IdempotencyGuard::complete`] releases the key.
+pub struct IdempotencyGuard<'a, T> {
+    store: &'a IdempotencyStore<T>,
+    key: String,
+    fingerprint: String,
+    ttl: Duration,
+    max_entries: usize,
+    completed: bool,
+    _tx: watch::Sender<()>,
+}
+
+impl<T> IdempotencyGuard<'_, T> {
+    pub fn complete(mut self, result: T) {
+        let mut entries = self.store.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let expires_at = now + self.ttl;
+        entries.by_key.insert(
+            self.key.clone(),
+            Entry::Completed {
+                fingerprint: std::mem::take(&mut self.fingerprint),
+                expires_at,
+                result,
+            },
+        );
+        entries.expiry.push_back((expires_at, self.key.clone()));
+        entries.evict(now, self.max_entries);
+        self.completed = true;
+    }
+}
+
+impl<T> Drop for IdempotencyGuard<'_, T> {
+    fn drop(&mut self) {
+        if !self.completed {
+            let mut entries = self.store.entries.lock().unwrap_or_else(|e| e.into_inner());
+            entries.by_key.remove(&self.key);
+        }
+    }
+}
+
+/// Buffered copy of a response that can be replayed
+#[derive(Debug, Clone)]
+pub struct StoredResponse {
+    status: StatusCode,
+    headers: Vec<(HeaderName, HeaderValue)>,
+    body: Bytes,
+}
+
+impl StoredResponse {
+    async fn from_response(response: HttpResponse) -> Result<Self, GatewayApiError> {
+        let status = response.status();
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .map_err(|e| GatewayApiError::CustomError(e.to_string()))?;
+
+        Ok(Self {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    fn to_response(&self, replayed: bool) -> HttpResponse {
+        let mut builder = HttpResponse::build(self.status);
+        for header in &self.headers {
+            builder.insert_header(header.clone());
+        }
+        if replayed {
+            builder.insert_header((IDEMPOTENT_REPLAYED_HEADER, "true"));
+        }
+        builder.body(self.body.clone())
+    }
+}
+
+/// Process-wide store of responses by idempotency key
+pub fn idempotency_store() -> &'static IdempotencyStore<StoredResponse> {
+    static STORE: OnceLock<IdempotencyStore<StoredResponse>> = OnceLock::new();
+    STORE.get_or_init(IdempotencyStore::default)
+}
+
+/// Runs `execute` at most once per `key` within the configured TTL, replaying its response to
+/// repeated requests. `fingerprint` identifies the request body, reusing a key for a different
+/// body is rejected. Only successful responses are stored, any other releases the key. Without
+/// a key the request always executes. Only buffered responses can be stored, so streaming
+/// requests must not be given a key.
+pub async fn with_idempotency<F, Fut>(
+    store: &IdempotencyStore<StoredResponse>,
+    key: Option<&str>,
+    fingerprint: &str,
+    config: &IdempotencyConfig,
+    execute: F,
+) -> Result<HttpResponse, GatewayApiError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<HttpResponse, GatewayApiError>>,
+{
+    let Some(key) = key else {
+        return execute().await;
+    };
+
+    match store.claim(key, fingerprint, config).await {
+        Claim::Replay(stored) => {
+            tracing::Span::current().record("idempotent_replay", true);
+            Ok(stored.to_response(true))
+        }
+        Claim::Execute(guard) => {
+            let response = execute().await?;
+            if !response.status().is_success() {
+                return Ok(response);
+            }
+            let stored = StoredResponse::from_response(response).await?;
+            guard.complete(stored.clone());
+            Ok(stored.to_response(false))
+        }
+        Claim::Mismatch => Err(GatewayApiError::GatewayError(
+            GatewayError::IdempotencyKeyReused(
+                "the idempotency key was already used with a different request body".to_string(),
+            ),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::ResponseError;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn config() -> IdempotencyConfig {
+        IdempotencyConfig {
+            ttl_secs: 60,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_same_key_executes_once() {
+        let store = IdempotencyStore::default();
+        let counter = AtomicUsize::new(0);
+        let executions = &counter;
+        let config = config();
+
+        let execute = move || async move {
+            executions.fetch_add(1, Ordering::SeqCst);
+            // Keep the first request in flight while the duplicate arrives
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            Ok(HttpResponse::Ok().body("charged once"))
+        };
+
+        let (first, second) = tokio::join!(
+            with_idempotency(&store, Some("project:order-42"), "body", &config, execute),
+            with_idempotency(&store, Some("project:order-42"), "body", &config, execute),
+        );
+        let third =
+            with_idempotency(&store, Some("project:order-42"), "body", &config, execute).await;
+
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+        for response in [first.unwrap(), second.unwrap(), third.unwrap()] {
+            let body = actix_web::body::to_bytes(response.into_body())
+                .await
+                .unwrap();
+            assert_eq!(body, Bytes::from("charged once"));
+        }
+
+        with_idempotency(&store, Some("project:order-43"), "body", &config, execute)
+            .await
+            .unwrap();
+        assert_eq!(executions.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_request_releases_key() {
+        let store = IdempotencyStore::default();
+        let config = config();
+
+        let failed = with_idempotency(&store, Some("key"), "body", &config, || async {
+            Err(GatewayApiError::CustomError(
+                "provider unavailable".to_string(),
+            ))
+        })
+        .await;
+        assert!(failed.is_err());
+
+        let retried = with_idempotency(&store, Some("key"), "body", &config, || async {
+            Ok(HttpResponse::Ok().finish())
+        })
+        .await
+        .unwrap();
+        assert!(retried.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_are_not_stored() {
+        let store = IdempotencyStore::default();
+        let config = config();
+
+        let unavailable = with_idempotency(&store, Some("key"), "body", &config, || async {
+            Ok(HttpResponse::ServiceUnavailable().finish())
+        })
+        .await
+        .unwrap();
+        assert_eq!(unavailable.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let retried = with_idempotency(&store, Some("key"), "body", &config, || async {
+            Ok(HttpResponse::Ok().finish())
+        })
+        .await
+        .unwrap();
+        assert_eq!(retried.status(), StatusCode::OK);
+        assert!(retried.headers().get(IDEMPOTENT_REPLAYED_HEADER).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_key_reused_with_different_body_is_rejected() {
+        let store = IdempotencyStore::default();
+        let config = config();
+
+        with_idempotency(&store, Some("key"), "first body", &config, || async {
+            Ok(HttpResponse::Ok().body("first"))
+        })
+        .await
+        .unwrap();
+
+        let reused = with_idempotency(&store, Some("key"), "second body", &config, || async {
+            Ok(HttpResponse::Ok().body("second"))
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(reused.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_oldest_responses_are_evicted_above_max_entries() {
+        let store = IdempotencyStore::default();
+        let config = IdempotencyConfig {
+            ttl_secs: 60,
+            max_entries: 2,
+        };
+
+        for key in ["a", "b", "c"] {
+            with_idempotency(&store, Some(key), "body", &config, || async {
+                Ok(HttpResponse::Ok().finish())
+            })
+            .await
+            .unwrap();
+        }
+
+        let entries = store.entries.lock().unwrap();
+        assert_eq!(entries.by_key.len(), 2);
+        assert!(!entries.by_key.contains_key("a"));
+        assert_eq!(entries.expiry.len(), 2);
+    }
+}
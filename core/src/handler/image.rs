@@ -1,6 +1,7 @@
 use crate::executor::image_generation::handle_image_generation;
 use crate::handler::record_map_err;
 use crate::handler::CallbackHandlerFn;
+use crate::types::metadata::project::Project;
 use crate::types::metadata::services::model::ModelService;
 use crate::GatewayApiError;
 use actix_web::HttpMessage;
@@ -25,8 +26,13 @@ pub async fn create_image(
     can_execute_llm_for_request(&req).await?;
 
     let request = request.into_inner();
-    let llm_model =
-        find_model_by_full_name(&request.model, models_service.as_ref().as_ref(), None)?;
+    let project_id = req.extensions().get::<Project>().map(|p| p.id);
+    let llm_model = find_model_by_full_name(
+        &request.model,
+        models_service.as_ref().as_ref(),
+        None,
+        project_id.as_ref(),
+    )?;
 
     let span = Span::or_current(tracing::info_span!(
         target: "vllora::user_tracing::api_invoke",
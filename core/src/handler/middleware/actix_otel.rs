@@ -1,4 +1,5 @@
 use crate::events::ui_broadcaster::EventsUIBroadcaster;
+use crate::telemetry::trace_context::{continue_remote_trace, remote_parent_context};
 use crate::types::metadata::project::Project;
 use crate::types::threads::{CompletionsRunId, CompletionsThreadId};
 use actix_web::body::{BodySize, BoxBody, MessageBody};
@@ -111,6 +112,7 @@ where
             let method = req.method();
             let should_create_run_span = method == "POST"
                 && (path.ends_with("/chat/completions")
+                    || path.ends_with("/v1/completions")
                     || path.ends_with("/embeddings")
                     || path.ends_with("/images/generations")
                     || path.ends_with("/responses"));
@@ -247,6 +249,11 @@ where
             let thread_id = req.extensions().get::<CompletionsThreadId>().cloned();
             let broadcaster: Option<web::Data<EventsUIBroadcaster>> = req.app_data().cloned();
             let project = req.extensions().get::<Project>().cloned();
+            let request_context = req
+                .extensions()
+                .get::<opentelemetry::Context>()
+                .cloned()
+                .or_else(|| remote_parent_context(req.headers()));
 
             // Get the run span from request extensions (set by RunSpanMiddleware)
             let run_span = req
@@ -306,6 +313,12 @@ where
                 )
             };
 
+            // No run span is created for requests carrying the caller's trace context, so
+            // this span continues their trace
+            if run_span.is_none() {
+                continue_remote_trace(&span, request_context.as_ref());
+            }
+
             if let (Some(run_id), Some(thread_id)) = (run_id, thread_id) {
                 let event = Event::Custom {
                     run_context: EventRunContext {
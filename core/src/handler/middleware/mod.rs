@@ -1,4 +1,67 @@
 pub mod actix_otel;
 pub mod rate_limit;
+pub mod replay_protection;
 pub mod run_id;
 pub mod thread_id;
+
+/// Routes that run a completion and so get a run and a thread: chat completions, legacy
+/// text completions and responses
+pub(crate) fn is_completions_path(path: &str) -> bool {
+    path.contains("chat/completion") || path.ends_with("/completions") || path.contains("responses")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run_id::RunId;
+    use super::thread_id::ThreadId;
+    use crate::types::threads::{CompletionsRunId, CompletionsThreadId};
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+
+    /// Takes the run and thread like the completion handlers do
+    async fn run_and_thread(
+        run_id: web::ReqData<CompletionsRunId>,
+        thread_id: web::ReqData<CompletionsThreadId>,
+    ) -> HttpResponse {
+        HttpResponse::Ok().json(serde_json::json!({
+            "run_id": run_id.value(),
+            "thread_id": thread_id.value(),
+        }))
+    }
+
+    #[actix_web::test]
+    async fn test_text_completions_get_a_run_and_a_thread() {
+        let app = test::init_service(
+            App::new()
+                .wrap(ThreadId)
+                .wrap(RunId)
+                .route("/v1/completions", web::post().to(run_and_thread))
+                .route("/v1/chat/completions", web::post().to(run_and_thread))
+                .route("/v1/models", web::post().to(run_and_thread)),
+        )
+        .await;
+
+        for uri in ["/v1/completions", "/v1/chat/completions"] {
+            let response = test::call_service(
+                &app,
+                test::TestRequest::post()
+                    .uri(uri)
+                    .insert_header(("X-Thread-Id", "thread-1"))
+                    .set_json(serde_json::json!({"model": "gpt-4o-mini", "prompt": "Hi"}))
+                    .to_request(),
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::OK, "{uri}");
+            let body: serde_json::Value = test::read_body_json(response).await;
+            assert_eq!(body["thread_id"], "thread-1");
+            assert!(body["run_id"].is_string());
+        }
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post().uri("/v1/models").to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,334 @@
+use actix_web::dev::forward_ready;
+use actix_web::{
+    dev::{Service, ServiceRequest, ServiceResponse, Transform},
+    Error, HttpMessage,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::{Mutex, OnceLock};
+use thiserror::Error;
+
+use crate::error::GatewayError;
+use crate::types::metadata::project::Project;
+
+/// Request header carrying a client chosen nonce, unique per request
+pub const NONCE_HEADER: &str = "X-Vllora-Nonce";
+/// Request header carrying when the request was sent, in unix seconds
+pub const TIMESTAMP_HEADER: &str = "X-Vllora-Timestamp";
+
+/// Rejects replayed requests. Every request must carry a nonce and a timestamp. Requests
+/// whose timestamp is more than `window_secs` away from now, or whose nonce was already used
+/// by the same project within the window, are rejected. Unlike idempotency keys, a repeated
+/// request is an error, not a replay of the first response. A project can have at most
+/// `max_nonces` nonces in the window, further requests are rate limited until the oldest
+/// expire.
+///
+/// The headers aren't authenticated here, a client able to rewrite them can pass this check
+/// with a fresh nonce. Deployments signing requests must verify the signature, covering both
+/// headers, before the gateway.
+///
+/// ```yaml
+/// replay_protection:
+///   enabled: true
+///   window_secs: 300
+///   max_nonces: 100000
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReplayProtectionConfig {
+    pub enabled: bool,
+    pub window_secs: u64,
+    pub max_nonces: usize,
+}
+
+impl Default for ReplayProtectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_secs: 300,
+            max_nonces: 100_000,
+        }
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ReplayError {
+    #[error("missing {0} header")]
+    MissingHeader(&'static str),
+    #[error("invalid {TIMESTAMP_HEADER} header, expected unix seconds")]
+    InvalidTimestamp,
+    #[error("request timestamp is outside the accepted window")]
+    StaleTimestamp,
+    #[error("nonce was already used")]
+    ReusedNonce,
+    #[error("too many requests within the replay window")]
+    TooManyNonces,
+}
+
+#[derive(Default)]
+struct Nonces {
+    /// Nonces seen within the window, by project
+    seen: HashMap<String, HashSet<String>>,
+    /// `(expires_at, project, nonce)` in insertion order. Every nonce is kept for the same
+    /// time after it was seen, so they expire in this order too.
+    expiry: VecDeque<(u64, String, String)>,
+}
+
+#[derive(Default)]
+pub struct NonceStore {
+    nonces: Mutex<Nonces>,
+}
+
+impl NonceStore {
+    /// Accepts a request of `scope` with `nonce` sent at `timestamp` and remembers the
+    /// nonce. A request is accepted until its timestamp is `window_secs` in the past, which
+    /// is at most twice the window after it is first seen, so nonces are forgotten then.
+    pub fn check(
+        &self,
+        scope: &str,
+        nonce: &str,
+        timestamp: u64,
+        now: u64,
+        config: &ReplayProtectionConfig,
+    ) -> Result<(), ReplayError> {
+        if timestamp.abs_diff(now) > config.window_secs {
+            return Err(ReplayError::StaleTimestamp);
+        }
+
+        let mut nonces = self.nonces.lock().unwrap_or_else(|e| e.into_inner());
+        while nonces
+            .expiry
+            .front()
+            .is_some_and(|(expires_at, _, _)| *expires_at < now)
+        {
+            let Some((_, scope, nonce)) = nonces.expiry.pop_front() else {
+                break;
+            };
+            if let Some(seen) = nonces.seen.get_mut(&scope) {
+                seen.remove(&nonce);
+                if seen.is_empty() {
+                    nonces.seen.remove(&scope);
+                }
+            }
+        }
+
+        let seen = nonces.seen.entry(scope.to_string()).or_default();
+        if seen.contains(nonce) {
+            return Err(ReplayError::ReusedNonce);
+        }
+        if seen.len() >= config.max_nonces {
+            return Err(ReplayError::TooManyNonces);
+        }
+        seen.insert(nonce.to_string());
+        nonces.expiry.push_back((
+            now + 2 * config.window_secs,
+            scope.to_string(),
+            nonce.to_string(),
+        ));
+        Ok(())
+    }
+}
+
+pub fn nonce_store() -> &'static NonceStore {
+    static STORE: OnceLock<NonceStore> = OnceLock::new();
+    STORE.get_or_init(NonceStore::default)
+}
+
+fn check_request(
+    req: &ServiceRequest,
+    config: &ReplayProtectionConfig,
+    store: &NonceStore,
+) -> Result<(), ReplayError> {
+    let header = |name: &'static str| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(ReplayError::MissingHeader(name))
+    };
+    let nonce = header(NONCE_HEADER)?;
+    let timestamp = header(TIMESTAMP_HEADER)?
+        .parse::<u64>()
+        .map_err(|_| ReplayError::InvalidTimestamp)?;
+    let now = chrono::Utc::now().timestamp().max(0) as u64;
+    let scope = req
+        .extensions()
+        .get::<Project>()
+        .map(|project| project.id.to_string())
+        .unwrap_or_default();
+
+    store.check(&scope, nonce, timestamp, now, config)
+}
+
+pub struct ReplayProtectionMiddleware;
+
+impl<S, B> Transform<S, ServiceRequest> for ReplayProtectionMiddleware
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = ReplayProtectionMiddlewareService<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(ReplayProtectionMiddlewareService {
+            service: service.into(),
+        }))
+    }
+}
+
+pub struct ReplayProtectionMiddlewareService<S> {
+    service: Rc<S>,
+}
+
+type LocalBoxFuture<T> = Pin<Box<dyn Future<Output = T> + 'static>>;
+
+impl<S, B> Service<ServiceRequest> for ReplayProtectionMiddlewareService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = LocalBoxFuture<Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+
+        Box::pin(async move {
+            if let Some(config) = req
+                .app_data::<ReplayProtectionConfig>()
+                .filter(|config| config.enabled)
+            {
+                check_request(&req, config, nonce_store()).map_err(|e| match e {
+                    ReplayError::TooManyNonces => GatewayError::RateLimited {
+                        message: e.to_string(),
+                        retry_after: None,
+                    },
+                    e => GatewayError::ReplayRejected(e.to_string()),
+                })?;
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse, ResponseError};
+
+    #[test]
+    fn test_reused_nonce_is_rejected() {
+        let store = NonceStore::default();
+        let config = ReplayProtectionConfig::default();
+
+        assert_eq!(store.check("p", "a", 1000, 1000, &config), Ok(()));
+        assert_eq!(
+            store.check("p", "a", 1010, 1010, &config),
+            Err(ReplayError::ReusedNonce)
+        );
+        assert_eq!(store.check("p", "b", 1010, 1010, &config), Ok(()));
+
+        assert_eq!(
+            store.check("p", "c", 600, 1010, &config),
+            Err(ReplayError::StaleTimestamp)
+        );
+        // Once out of the window, the nonce would be rejected as stale
+        assert_eq!(
+            store.check("p", "a", 1000, 1400, &config),
+            Err(ReplayError::StaleTimestamp)
+        );
+    }
+
+    #[test]
+    fn test_nonces_are_scoped_by_project() {
+        let store = NonceStore::default();
+        let config = ReplayProtectionConfig::default();
+
+        assert_eq!(store.check("project-a", "n", 1000, 1000, &config), Ok(()));
+        assert_eq!(store.check("project-b", "n", 1000, 1000, &config), Ok(()));
+        assert_eq!(
+            store.check("project-a", "n", 1000, 1000, &config),
+            Err(ReplayError::ReusedNonce)
+        );
+    }
+
+    #[test]
+    fn test_nonces_are_capped_and_expire_in_order() {
+        let store = NonceStore::default();
+        let config = ReplayProtectionConfig {
+            enabled: true,
+            window_secs: 300,
+            max_nonces: 2,
+        };
+
+        assert_eq!(store.check("p", "a", 1000, 1000, &config), Ok(()));
+        assert_eq!(store.check("p", "b", 1100, 1100, &config), Ok(()));
+        assert_eq!(
+            store.check("p", "c", 1100, 1100, &config),
+            Err(ReplayError::TooManyNonces)
+        );
+        // Other projects have their own limit
+        assert_eq!(store.check("q", "c", 1100, 1100, &config), Ok(()));
+
+        // "a" is forgotten after twice the window, "b" is still remembered
+        assert_eq!(store.check("p", "c", 1601, 1601, &config), Ok(()));
+        let nonces = store.nonces.lock().unwrap();
+        assert_eq!(
+            nonces.seen["p"],
+            HashSet::from(["b".to_string(), "c".to_string()])
+        );
+        assert_eq!(nonces.expiry.len(), 3);
+    }
+
+    #[actix_web::test]
+    async fn test_replayed_request_is_rejected() {
+        let app = test::init_service(
+            App::new()
+                .app_data(ReplayProtectionConfig {
+                    enabled: true,
+                    ..Default::default()
+                })
+                .wrap(ReplayProtectionMiddleware)
+                .route("/", web::post().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let nonce = uuid::Uuid::new_v4().to_string();
+        let now = chrono::Utc::now().timestamp().to_string();
+        let request = || {
+            test::TestRequest::post()
+                .uri("/")
+                .insert_header((NONCE_HEADER, nonce.as_str()))
+                .insert_header((TIMESTAMP_HEADER, now.as_str()))
+                .to_request()
+        };
+
+        let response = test::call_service(&app, request()).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = test::try_call_service(&app, request()).await;
+        let error = response.err().unwrap();
+        assert_eq!(
+            error.as_response_error().status_code(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        let without_nonce = test::TestRequest::post().uri("/").to_request();
+        let error = test::try_call_service(&app, without_nonce)
+            .await
+            .err()
+            .unwrap();
+        assert!(error.to_string().contains(NONCE_HEADER));
+    }
+}
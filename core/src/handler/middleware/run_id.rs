@@ -1,6 +1,10 @@
+use crate::error::GatewayError;
+use crate::handler::middleware::is_completions_path;
+use crate::handler::request_limits::RequestLimitsConfig;
 use crate::types::threads::CompletionsRunId;
 use actix_http::h1::Payload;
 use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::CONTENT_LENGTH;
 use actix_web::web::BytesMut;
 use actix_web::HttpMessage;
 use futures::TryStreamExt;
@@ -13,6 +17,10 @@ use tokio_stream::StreamExt;
 use uuid::Uuid;
 use vllora_telemetry::AdditionalContext;
 
+/// Header naming the run that spawned this request's run, e.g. the run of an agent
+/// delegating to a sub-agent. Also read from `extra.metadata.parent_run_id`.
+pub const PARENT_RUN_ID_HEADER: &str = "X-Parent-Run-Id";
+
 #[derive(Deserialize, Debug)]
 struct Extra {
     #[serde(alias = "extra_body")]
@@ -21,8 +29,20 @@ struct Extra {
 
 #[derive(Deserialize, Debug)]
 struct SessionPayload {
-    session_id: Uuid,
+    session_id: Option<Uuid>,
+    #[serde(default)]
+    metadata: HashMap<String, serde_json::Value>,
+}
+
+impl SessionPayload {
+    fn parent_run_id(&self) -> Option<String> {
+        self.metadata
+            .get("parent_run_id")
+            .and_then(|v| v.as_str())
+            .map(ToString::to_string)
+    }
 }
+
 pub struct RunId;
 
 impl Default for RunId {
@@ -78,34 +98,50 @@ where
         let service = Rc::clone(&self.service);
 
         Box::pin(async move {
-            if req.path().contains("chat/completion") || req.path().contains("responses") {
-                let run_id = match req.headers().get("X-Run-Id") {
-                    Some(value) => match value.to_str() {
-                        Ok(v) => v.to_string(),
-                        Err(_) => Uuid::new_v4().to_string(),
-                    },
-                    None => {
-                        // Try to extract session_id from the request body without consuming the payload
-                        let session_id = match extract_session_id_from_request(&mut req).await {
-                            Ok(session_id) => session_id.to_string(),
+            if is_completions_path(req.path()) {
+                let run_id_header =
+                    req.headers()
+                        .get("X-Run-Id")
+                        .map(|value| match value.to_str() {
+                            Ok(v) => v.to_string(),
                             Err(_) => Uuid::new_v4().to_string(),
-                        };
-                        session_id
+                        });
+                let parent_run_id_header = req
+                    .headers()
+                    .get(PARENT_RUN_ID_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .map(ToString::to_string);
+
+                // Try to extract the session and metadata from the request body without consuming the payload
+                let payload = match (&run_id_header, &parent_run_id_header) {
+                    (Some(_), Some(_)) => None,
+                    _ => {
+                        let max_body_bytes = req
+                            .app_data::<RequestLimitsConfig>()
+                            .cloned()
+                            .unwrap_or_default()
+                            .max_body_bytes;
+                        extract_session_from_request(&mut req, max_body_bytes).await?
                     }
                 };
+                let run_id = run_id_header
+                    .or_else(|| Some(payload.as_ref()?.session_id?.to_string()))
+                    .unwrap_or_else(|| Uuid::new_v4().to_string());
+                let parent_run_id =
+                    parent_run_id_header.or_else(|| payload.as_ref()?.parent_run_id());
 
                 req.extensions_mut()
                     .insert(CompletionsRunId::new(run_id.clone()));
+                let mut context = HashMap::from([("vllora.run_id".to_string(), run_id)]);
+                if let Some(parent_run_id) = parent_run_id {
+                    context.insert("vllora.parent_run_id".to_string(), parent_run_id);
+                }
                 let mut extensions_mut = req.extensions_mut();
                 let additional_context = extensions_mut.get_mut::<AdditionalContext>();
                 if let Some(additional_context) = additional_context {
-                    additional_context
-                        .0
-                        .insert("vllora.run_id".to_string(), run_id);
+                    additional_context.0.extend(context);
                 } else {
-                    let mut additional_context = HashMap::new();
-                    additional_context.insert("vllora.run_id".to_string(), run_id);
-                    extensions_mut.insert(AdditionalContext::new(additional_context));
+                    extensions_mut.insert(AdditionalContext::new(context));
                 }
             }
 
@@ -117,10 +153,26 @@ where
     }
 }
 
-/// Extract session_id from request body without consuming the payload
-async fn extract_session_id_from_request(
+/// Extract session_id and metadata from request body without consuming the payload. Bodies
+/// over `max_body_bytes` are rejected with a 413 before they are read in full.
+async fn extract_session_from_request(
     req: &mut ServiceRequest,
-) -> Result<Uuid, Box<dyn std::error::Error>> {
+    max_body_bytes: usize,
+) -> Result<Option<SessionPayload>, actix_web::Error> {
+    let too_large = || {
+        GatewayError::PayloadTooLarge(format!(
+            "request body exceeds the limit of {max_body_bytes} bytes"
+        ))
+    };
+    let content_length = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<usize>().ok());
+    if content_length.is_some_and(|length| length > max_body_bytes) {
+        return Err(too_large().into());
+    }
+
     // Clone the payload to avoid consuming the original
     let payload = req.take_payload();
     let mut request_body = BytesMut::new();
@@ -129,6 +181,9 @@ async fn extract_session_id_from_request(
     let mut payload_stream = payload.into_stream();
     while let Some(chunk) = payload_stream.next().await {
         let chunk = chunk?;
+        if request_body.len() + chunk.len() > max_body_bytes {
+            return Err(too_large().into());
+        }
         request_body.extend_from_slice(&chunk);
     }
 
@@ -142,8 +197,57 @@ async fn extract_session_id_from_request(
         payload: new_payload,
     });
 
-    match json_result {
-        Ok(payload) => Ok(payload.extra.session_id),
-        Err(e) => Err(Box::new(e)),
+    Ok(json_result.ok().map(|payload| payload.extra))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+
+    async fn run_id(
+        run_id: web::ReqData<CompletionsRunId>,
+        _request: web::Json<serde_json::Value>,
+    ) -> HttpResponse {
+        HttpResponse::Ok().body(run_id.value())
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_body_is_rejected_before_it_is_buffered() {
+        let config = RequestLimitsConfig {
+            max_body_bytes: 128,
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .wrap(RunId)
+                .app_data(config.json_config())
+                .app_data(config)
+                .route("/v1/chat/completions", web::post().to(run_id)),
+        )
+        .await;
+
+        let session_id = Uuid::new_v4();
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/v1/chat/completions")
+                .set_json(serde_json::json!({"extra": {"session_id": session_id}}))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(test::read_body(response).await, session_id.to_string());
+
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/v1/chat/completions")
+                .set_json(serde_json::json!({"messages": "a".repeat(1024)}))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
     }
 }
@@ -1,3 +1,4 @@
+use crate::handler::middleware::is_completions_path;
 use crate::types::threads::CompletionsThreadId;
 use actix_web::dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform};
 use actix_web::HttpMessage;
@@ -55,7 +56,7 @@ where
     forward_ready!(service);
 
     fn call(&self, req: ServiceRequest) -> Self::Future {
-        if req.path().contains("chat/completion") || req.path().contains("responses") {
+        if is_completions_path(req.path()) {
             let thread_id = match req
                 .headers()
                 .get("X-Thread-Id")
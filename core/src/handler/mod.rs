@@ -2,17 +2,24 @@ pub mod chat;
 pub mod embedding;
 pub mod events;
 pub mod group;
+pub mod idempotency;
 pub mod image;
 pub mod labels;
 pub mod mcp_configs;
 pub mod middleware;
 pub mod models;
+pub mod prompts;
 pub mod providers;
+pub mod request_limits;
 pub mod responses;
+pub mod routing;
 pub mod runs;
 pub mod spans;
+pub mod stream_format;
+pub mod text_completions;
 pub mod threads;
 pub mod traces;
+pub mod validation;
 
 use crate::metadata::models::model::DbModel;
 use crate::metadata::pool::DbPool;
@@ -21,6 +28,7 @@ use crate::types::metadata::services::model::ModelService;
 use crate::types::metadata::services::provider::ProviderService;
 use crate::GatewayApiError;
 use crate::GatewayError;
+use actix_web::http::header::HeaderValue;
 use actix_web::HttpRequest;
 use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
@@ -53,19 +61,21 @@ pub fn find_model_by_full_name(
     model_name: &str,
     model_service: &dyn ModelService,
     db_pool: Option<&DbPool>,
+    project_id: Option<&uuid::Uuid>,
 ) -> Result<ModelMetadata, GatewayApiError> {
-    find_model_by_full_name_with_provider_info(model_name, model_service, db_pool)
+    find_model_by_full_name_with_provider_info(model_name, model_service, db_pool, project_id)
 }
 
 pub fn find_model_by_full_name_with_provider_info(
     model_name: &str,
     model_service: &dyn ModelService,
     db_pool: Option<&DbPool>,
+    project_id: Option<&uuid::Uuid>,
 ) -> Result<ModelMetadata, GatewayApiError> {
     let model_parts = model_name.split('/').collect::<Vec<&str>>();
     let (llm_model, provider_name) = if model_parts.len() == 1 {
         let model = model_service
-            .get_by_name(model_name, None)
+            .get_by_name(model_name, project_id.copied())
             .map_err(|e| GatewayApiError::CustomError(e.to_string()))?
             .first()
             .cloned();
@@ -82,7 +92,7 @@ pub fn find_model_by_full_name_with_provider_info(
             .get_by_provider_and_name(
                 &model_name_part.to_lowercase(),
                 provider_name.as_ref().unwrap(),
-                None,
+                project_id.copied(),
             )
             .map_err(|e| GatewayApiError::CustomError(e.to_string()))?;
         (model, provider_name)
@@ -115,28 +125,73 @@ pub fn find_model_by_full_name_with_provider_info(
     }
 }
 
-// extract vllora-tags from headers, shoule be sth like this: tag1=value1&tag2=value2 => result should be a Map<String, String>
+/// Prefix of headers attaching a single tag, e.g. `x-vllora-tag-team: payments`
+pub const TAG_HEADER_PREFIX: &str = "x-vllora-tag-";
+/// Most tags a request can attach
+pub const MAX_TAGS: usize = 32;
+pub const MAX_TAG_KEY_LENGTH: usize = 64;
+pub const MAX_TAG_VALUE_LENGTH: usize = 256;
+
+fn header_str<'a>(name: &str, value: &'a HeaderValue) -> Result<&'a str, GatewayError> {
+    value.to_str().map_err(|_| GatewayError::InvalidParameter {
+        param: name.to_string(),
+        message: "must be visible ASCII".to_string(),
+    })
+}
+
+/// Tags of the request, recorded on its spans. Tags are attached with one
+/// `x-vllora-tag-<key>: <value>` header each, or all at once with
+/// `x-tags: tag1=value1&tag2=value2`. Header tags take precedence over `x-tags`.
+///
+/// Header tags over the limits are rejected. `x-tags` predates the limits, so its tags are
+/// kept within them instead: empty or long keys are dropped, long values truncated, and tags
+/// beyond [`MAX_TAGS`] ignored.
 pub fn extract_tags(req: &HttpRequest) -> Result<HashMap<String, String>, GatewayError> {
-    Ok(match req.headers().get("x-tags") {
-        Some(value) => {
-            let tags_str = value
-                .to_str()
-                .map_err(|e| LLMError::CustomError(e.to_string()))?
-                .to_string();
-            let tags: HashMap<String, String> = tags_str
-                .split('&')
-                .map(|tag| {
-                    tag.split_once('=')
-                        .map_or((tag.to_string(), "-".to_string()), |(k, v)| {
-                            (k.to_string(), v.to_string())
-                        })
-                })
-                .collect();
-            Some(tags)
+    let mut tags = HashMap::new();
+    for (name, value) in req.headers() {
+        let Some(key) = name.as_str().strip_prefix(TAG_HEADER_PREFIX) else {
+            continue;
+        };
+        let value = header_str(name.as_str(), value)?;
+        if key.is_empty() || key.len() > MAX_TAG_KEY_LENGTH {
+            return Err(GatewayError::InvalidParameter {
+                param: "tags".to_string(),
+                message: format!(
+                    "keys must be between 1 and {MAX_TAG_KEY_LENGTH} characters, got '{key}'"
+                ),
+            });
+        }
+        if value.len() > MAX_TAG_VALUE_LENGTH {
+            return Err(GatewayError::InvalidParameter {
+                param: format!("tags.{key}"),
+                message: format!("must not exceed {MAX_TAG_VALUE_LENGTH} characters"),
+            });
+        }
+        tags.insert(key.to_string(), value.to_string());
+    }
+    if tags.len() > MAX_TAGS {
+        return Err(GatewayError::InvalidParameter {
+            param: "tags".to_string(),
+            message: format!("must not exceed {MAX_TAGS} tags, got {}", tags.len()),
+        });
+    }
+
+    if let Some(value) = req.headers().get("x-tags") {
+        for tag in header_str("x-tags", value)?.split('&') {
+            let (key, value) = tag.split_once('=').unwrap_or((tag, "-"));
+            if key.is_empty() || key.len() > MAX_TAG_KEY_LENGTH || tags.contains_key(key) {
+                continue;
+            }
+            if tags.len() == MAX_TAGS {
+                break;
+            }
+            // Header values are ASCII, so any byte index is a char boundary
+            let value = &value[..value.len().min(MAX_TAG_VALUE_LENGTH)];
+            tags.insert(key.to_string(), value.to_string());
         }
-        None => None,
     }
-    .unwrap_or_default())
+
+    Ok(tags)
 }
 
 pub fn record_map_err(
@@ -253,3 +308,102 @@ pub(crate) async fn can_execute_llm_for_request(req: &HttpRequest) -> Result<(),
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+    use valuable::Valuable;
+    use vllora_telemetry::test_utils::RecordingLayer;
+
+    #[test]
+    fn test_header_tags_are_recorded_on_model_span() {
+        let req = TestRequest::default()
+            .insert_header(("x-tags", "team=search&env=prod"))
+            .insert_header(("x-vllora-tag-team", "payments"))
+            .insert_header(("x-vllora-tag-feature", "checkout"))
+            .to_http_request();
+        let tags = extract_tags(&req).unwrap();
+        assert_eq!(
+            tags,
+            HashMap::from([
+                ("team".to_string(), "payments".to_string()),
+                ("env".to_string(), "prod".to_string()),
+                ("feature".to_string(), "checkout".to_string()),
+            ])
+        );
+
+        let recorded = RecordingLayer::default();
+        {
+            let _guard = recorded.set_default();
+            let _span = vllora_telemetry::create_model_invoke_span!(
+                "{}",
+                "{}",
+                "openai",
+                "gpt-4o-mini",
+                "gpt-4o-mini",
+                "vllora",
+                tags
+            );
+        }
+        let recorded = recorded.values("tags");
+        assert_eq!(recorded.len(), 1);
+        assert!(recorded[0].contains(r#""team": "payments""#));
+        assert!(recorded[0].contains(r#""feature": "checkout""#));
+    }
+
+    #[test]
+    fn test_header_tags_over_the_limits_are_rejected() {
+        let long_value = TestRequest::default()
+            .insert_header(("x-vllora-tag-team", "a".repeat(MAX_TAG_VALUE_LENGTH + 1)))
+            .to_http_request();
+        assert!(matches!(
+            extract_tags(&long_value),
+            Err(GatewayError::InvalidParameter { param, .. }) if param == "tags.team"
+        ));
+
+        let mut too_many = TestRequest::default();
+        for i in 0..=MAX_TAGS {
+            too_many = too_many.insert_header((format!("x-vllora-tag-tag{i}"), "value"));
+        }
+        assert!(matches!(
+            extract_tags(&too_many.to_http_request()),
+            Err(GatewayError::InvalidParameter { param, .. }) if param == "tags"
+        ));
+    }
+
+    #[test]
+    fn test_legacy_tags_are_kept_within_the_limits() {
+        let trailing_separator = TestRequest::default()
+            .insert_header(("x-tags", "team=search&"))
+            .to_http_request();
+        assert_eq!(
+            extract_tags(&trailing_separator).unwrap(),
+            HashMap::from([("team".to_string(), "search".to_string())])
+        );
+
+        let long_value = TestRequest::default()
+            .insert_header((
+                "x-tags",
+                format!("team={}&env=prod", "a".repeat(MAX_TAG_VALUE_LENGTH + 1)),
+            ))
+            .to_http_request();
+        let tags = extract_tags(&long_value).unwrap();
+        assert_eq!(tags["team"], "a".repeat(MAX_TAG_VALUE_LENGTH));
+        assert_eq!(tags["env"], "prod");
+
+        let too_many = TestRequest::default()
+            .insert_header((
+                "x-tags",
+                (0..=MAX_TAGS)
+                    .map(|i| format!("tag{i}=value"))
+                    .collect::<Vec<_>>()
+                    .join("&"),
+            ))
+            .insert_header(("x-vllora-tag-team", "payments"))
+            .to_http_request();
+        let tags = extract_tags(&too_many).unwrap();
+        assert_eq!(tags.len(), MAX_TAGS);
+        assert_eq!(tags["team"], "payments");
+    }
+}
@@ -1,13 +1,14 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 
 use actix_web::{web, HttpResponse, Result};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveTime};
 use serde::{Deserialize, Serialize};
 use vllora_llm::types::gateway::ChatModel;
 use vllora_llm::types::models::{ModelCapability, ModelIOFormats, ModelMetadata, ModelType};
 use vllora_llm::types::provider::{CompletionModelPrice, ModelPrice};
 
 use crate::metadata::models::model::DbNewModel;
+use crate::types::metadata::project::Project;
 use crate::types::metadata::services::model::ModelService;
 use crate::GatewayApiError;
 
@@ -40,6 +41,99 @@ pub async fn list_gateway_models(
     Ok(HttpResponse::Ok().json(response))
 }
 
+/// Builds an OpenAI-compatible models list from model metadata.
+///
+/// Models are listed under their qualified `provider/model` name, which is what
+/// chat completions resolve. Duplicate ids (e.g. a project-scoped model that
+/// shadows a global one) are listed once, keeping the first occurrence.
+pub fn openai_models_list(models: &[ModelMetadata]) -> ChatModelsResponse {
+    let mut seen = HashSet::new();
+    let data = models
+        .iter()
+        .filter(|m| seen.insert(m.qualified_model_name()))
+        .map(|m| ChatModel {
+            id: m.qualified_model_name(),
+            object: "model".to_string(),
+            created: m
+                .release_date
+                .unwrap_or(chrono::Utc::now().date_naive())
+                .and_time(NaiveTime::from_hms_opt(0, 0, 0).expect("Invalid time"))
+                .and_utc()
+                .timestamp(),
+            owned_by: m.model_provider.to_string(),
+        })
+        .collect();
+
+    ChatModelsResponse {
+        object: "list".to_string(),
+        data,
+    }
+}
+
+/// Filters of a models list, e.g. `?capability=tools&type=completions`. A model is listed
+/// when it matches every filter given.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ModelsFilter {
+    pub capability: Option<ModelCapability>,
+    #[serde(rename = "type")]
+    pub model_type: Option<ModelType>,
+    pub input_format: Option<ModelIOFormats>,
+    pub output_format: Option<ModelIOFormats>,
+    /// Provider the model is owned by or served through
+    pub provider: Option<String>,
+}
+
+impl ModelsFilter {
+    pub fn matches(&self, model: &ModelMetadata) -> bool {
+        self.capability
+            .as_ref()
+            .is_none_or(|c| model.capabilities.contains(c))
+            && self.model_type.as_ref().is_none_or(|t| &model.r#type == t)
+            && self
+                .input_format
+                .as_ref()
+                .is_none_or(|f| model.input_formats.contains(f))
+            && self
+                .output_format
+                .as_ref()
+                .is_none_or(|f| model.output_formats.contains(f))
+            && self.provider.as_ref().is_none_or(|p| {
+                model.model_provider.eq_ignore_ascii_case(p)
+                    || model
+                        .inference_provider
+                        .provider
+                        .to_string()
+                        .eq_ignore_ascii_case(p)
+            })
+    }
+
+    pub fn apply(&self, models: Vec<ModelMetadata>) -> Vec<ModelMetadata> {
+        models.into_iter().filter(|m| self.matches(m)).collect()
+    }
+}
+
+/// OpenAI-compatible `/v1/models` handler.
+///
+/// Lists global models plus the private models of the resolved project, using the
+/// same inventory as `vllora list`, narrowed down by the [`ModelsFilter`] query.
+pub async fn list_project_models(
+    project: Option<web::ReqData<Project>>,
+    model_service: web::Data<Box<dyn ModelService>>,
+    filter: web::Query<ModelsFilter>,
+) -> Result<HttpResponse, GatewayApiError> {
+    let project_id = project.map(|p| p.id);
+
+    let db_models = model_service
+        .list(project_id)
+        .map_err(|e| GatewayApiError::CustomError(format!("Failed to fetch models: {}", e)))?;
+
+    // Project-scoped models first so they win over global models with the same name
+    let mut models = filter.apply(db_models.into_iter().map(|m| m.into()).collect());
+    models.sort_by_key(|m| !m.is_private);
+
+    Ok(HttpResponse::Ok().json(openai_models_list(&models)))
+}
+
 pub async fn list_gateway_models_capabilities(
     models: web::Data<AvailableModels>,
 ) -> Result<HttpResponse, GatewayApiError> {
@@ -159,6 +253,9 @@ pub async fn create_model<T: ModelService>(
             per_cached_input_token: req.cached_input_token_price,
             per_cached_input_write_token: req.cached_input_write_token_price,
             valid_from: None,
+            per_batch_input_token: None,
+            per_batch_output_token: None,
+            per_service_tier: None,
         }),
         input_formats: req.input_types.clone().unwrap_or_default(),
         output_formats: req.output_types.clone().unwrap_or_default(),
@@ -371,3 +468,97 @@ pub async fn delete_custom_model_by_name<T: ModelService>(
         }))),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model(provider: &str, name: &str, is_private: bool) -> ModelMetadata {
+        let default = ModelMetadata::default();
+        ModelMetadata {
+            model: name.to_string(),
+            model_provider: provider.to_string(),
+            inference_provider: vllora_llm::types::models::InferenceProvider {
+                provider: vllora_llm::types::provider::InferenceModelProvider::from(
+                    provider.to_string(),
+                ),
+                ..default.inference_provider.clone()
+            },
+            release_date: NaiveDate::from_ymd_opt(2024, 5, 13),
+            is_private,
+            ..default
+        }
+    }
+
+    #[test]
+    fn test_openai_models_list_shape() {
+        let models = vec![
+            model("openai", "gpt-4o", false),
+            model("anthropic", "claude-3-5-sonnet", false),
+        ];
+
+        let value = serde_json::to_value(openai_models_list(&models)).unwrap();
+
+        assert_eq!(value["object"], "list");
+        let data = value["data"].as_array().unwrap();
+        assert_eq!(data.len(), 2);
+        for entry in data {
+            let entry = entry.as_object().unwrap();
+            assert_eq!(entry.len(), 4);
+            assert_eq!(entry["object"], "model");
+            assert!(entry["id"].is_string());
+            assert!(entry["owned_by"].is_string());
+            assert!(entry["created"].is_i64());
+        }
+        assert_eq!(data[0]["id"], "openai/gpt-4o");
+        assert_eq!(data[0]["owned_by"], "openai");
+        assert_eq!(data[0]["created"], 1715558400);
+    }
+
+    #[test]
+    fn test_openai_models_list_dedups_ids() {
+        let models = vec![
+            model("openai", "my-model", true),
+            model("openai", "my-model", false),
+        ];
+
+        let response = openai_models_list(&models);
+
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0].id, "openai/my-model");
+    }
+
+    #[test]
+    fn test_filter_by_tools_excludes_text_only_models() {
+        let tools_model = ModelMetadata {
+            capabilities: vec![ModelCapability::Tools],
+            input_formats: vec![ModelIOFormats::Text, ModelIOFormats::Image],
+            ..model("openai", "gpt-4o", false)
+        };
+        let text_only = ModelMetadata {
+            capabilities: vec![],
+            input_formats: vec![ModelIOFormats::Text],
+            ..model("openai", "text-model", false)
+        };
+        let models = vec![tools_model, text_only];
+
+        let filter = web::Query::<ModelsFilter>::from_query("capability=tools&type=completions")
+            .unwrap()
+            .into_inner();
+        let filtered = filter.apply(models.clone());
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].model, "gpt-4o");
+
+        let filter = web::Query::<ModelsFilter>::from_query("input_format=image")
+            .unwrap()
+            .into_inner();
+        assert_eq!(filter.apply(models.clone()).len(), 1);
+
+        let filter = web::Query::<ModelsFilter>::from_query("provider=anthropic")
+            .unwrap()
+            .into_inner();
+        assert!(filter.apply(models.clone()).is_empty());
+
+        assert_eq!(ModelsFilter::default().apply(models).len(), 2);
+    }
+}
@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use vllora_llm::async_openai::types::responses::CreateResponse;
+use vllora_llm::types::gateway::ChatCompletionRequest;
+use vllora_llm::types::prompts::{Prompt, PromptMode};
+
+use crate::error::GatewayError;
+
+/// Prompt templates stored prompt references are rendered from, by prompt id.
+///
+/// ```yaml
+/// prompts:
+///   support-agent:
+///     version: "2"
+///     messages:
+///       - role: system
+///         content: "You help customers of {{ product }}. Answer in {{ language }}."
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct StoredPromptsConfig {
+    pub prompts: HashMap<String, Prompt>,
+}
+
+impl StoredPromptsConfig {
+    /// Resolves the stored prompt a chat completion request references. Chat completion
+    /// APIs can't resolve stored prompts, so the prompt is rendered locally and its
+    /// messages put before the request messages.
+    pub fn apply(
+        &self,
+        request: &mut ChatCompletionRequest,
+    ) -> Result<Option<PromptMode>, GatewayError> {
+        let Some(reference) = request.prompt.take() else {
+            return Ok(None);
+        };
+        let prompt =
+            self.prompts
+                .get(&reference.id)
+                .ok_or_else(|| GatewayError::InvalidParameter {
+                    param: "prompt.id".to_string(),
+                    message: format!("references unknown prompt {}", reference.id),
+                })?;
+        if let Some(version) = &reference.version {
+            if prompt.version.as_ref() != Some(version) {
+                return Err(GatewayError::InvalidParameter {
+                    param: "prompt.version".to_string(),
+                    message: format!("prompt {} has no version {version}", reference.id),
+                });
+            }
+        }
+
+        let mut messages = prompt.render(&reference.variables);
+        messages.append(&mut request.messages);
+        request.messages = messages;
+
+        Ok(Some(PromptMode::Local))
+    }
+}
+
+/// Responses requests are only sent to OpenAI, which resolves the stored prompts it keeps
+pub fn responses_prompt_mode(request: &CreateResponse) -> Option<PromptMode> {
+    serde_json::to_value(request)
+        .ok()?
+        .get("prompt")
+        .filter(|prompt| !prompt.is_null())
+        .map(|_| PromptMode::Provider)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use vllora_llm::types::gateway::ChatCompletionContent;
+
+    fn text(request: &ChatCompletionRequest) -> Vec<(String, String)> {
+        request
+            .messages
+            .iter()
+            .map(|m| {
+                let content = match &m.content {
+                    Some(ChatCompletionContent::Text(text)) => text.clone(),
+                    _ => String::new(),
+                };
+                (m.role.clone(), content)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stored_prompt_is_rendered_locally() {
+        let prompts: StoredPromptsConfig = serde_json::from_value(json!({
+            "support-agent": {
+                "messages": [{"role": "system", "content": "You help customers of {{ product }}."}]
+            }
+        }))
+        .unwrap();
+        let mut request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "anthropic/claude-sonnet-4",
+            "prompt": {"id": "support-agent", "variables": {"product": "vLLora"}},
+            "messages": [{"role": "user", "content": "How do I add a provider?"}]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            prompts.apply(&mut request).unwrap(),
+            Some(PromptMode::Local)
+        );
+        assert!(request.prompt.is_none());
+        assert_eq!(
+            text(&request),
+            vec![
+                (
+                    "system".to_string(),
+                    "You help customers of vLLora.".to_string()
+                ),
+                ("user".to_string(), "How do I add a provider?".to_string()),
+            ]
+        );
+
+        let mut unknown: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "anthropic/claude-sonnet-4",
+            "prompt": {"id": "missing"},
+            "messages": []
+        }))
+        .unwrap();
+        let error = prompts.apply(&mut unknown).unwrap_err();
+        assert_eq!(error.param(), Some("prompt.id"));
+    }
+
+    #[test]
+    fn test_stored_prompt_version_must_match() {
+        let prompts: StoredPromptsConfig = serde_json::from_value(json!({
+            "support-agent": {
+                "version": "2",
+                "messages": [{"role": "system", "content": "You help customers."}]
+            }
+        }))
+        .unwrap();
+        let request = |version: &str| -> ChatCompletionRequest {
+            serde_json::from_value(json!({
+                "model": "anthropic/claude-sonnet-4",
+                "prompt": {"id": "support-agent", "version": version},
+                "messages": []
+            }))
+            .unwrap()
+        };
+
+        let mut current = request("2");
+        assert_eq!(
+            prompts.apply(&mut current).unwrap(),
+            Some(PromptMode::Local)
+        );
+        assert_eq!(current.messages.len(), 1);
+
+        let error = prompts.apply(&mut request("1")).unwrap_err();
+        assert_eq!(error.param(), Some("prompt.version"));
+    }
+}
@@ -0,0 +1,195 @@
+use actix_web::error::JsonPayloadError;
+use actix_web::web::JsonConfig;
+use serde::{Deserialize, Serialize};
+use vllora_llm::types::gateway::{ChatCompletionContent, ChatCompletionRequest, Content};
+
+use crate::error::GatewayError;
+
+/// Size limits of incoming requests, enforced before anything is decoded.
+///
+/// ```yaml
+/// request_limits:
+///   max_body_bytes: 8388608    # 8 MiB of JSON
+///   max_media_bytes: 20971520  # 20 MiB of images, audio and files once base64 decoded
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestLimitsConfig {
+    /// Largest accepted request body, in bytes
+    pub max_body_bytes: usize,
+    /// Largest total of inline image, audio and file data per request, in decoded bytes
+    pub max_media_bytes: usize,
+}
+
+impl Default for RequestLimitsConfig {
+    fn default() -> Self {
+        Self {
+            max_body_bytes: 8 * 1024 * 1024,
+            max_media_bytes: 20 * 1024 * 1024,
+        }
+    }
+}
+
+impl RequestLimitsConfig {
+    /// JSON extractor config rejecting bodies over `max_body_bytes` with a 413
+    pub fn json_config(&self) -> JsonConfig {
+        JsonConfig::default()
+            .limit(self.max_body_bytes)
+            .error_handler(|err, _| match err {
+                JsonPayloadError::OverflowKnownLength { length, limit } => {
+                    GatewayError::PayloadTooLarge(format!(
+                        "request body of {length} bytes exceeds the limit of {limit} bytes"
+                    ))
+                    .into()
+                }
+                JsonPayloadError::Overflow { limit } => GatewayError::PayloadTooLarge(format!(
+                    "request body exceeds the limit of {limit} bytes"
+                ))
+                .into(),
+                err => err.into(),
+            })
+    }
+
+    /// Rejects requests whose inline media would decode to more than `max_media_bytes`.
+    /// Sizes are computed from the base64 length, nothing is decoded.
+    pub fn check_media(&self, request: &ChatCompletionRequest) -> Result<(), GatewayError> {
+        let media_bytes: usize = request
+            .messages
+            .iter()
+            .filter_map(|message| match &message.content {
+                Some(ChatCompletionContent::Content(parts)) => Some(parts),
+                _ => None,
+            })
+            .flatten()
+            .map(content_media_bytes)
+            .sum();
+
+        if media_bytes > self.max_media_bytes {
+            return Err(GatewayError::PayloadTooLarge(format!(
+                "images, audio and files total {media_bytes} bytes, over the limit of {} bytes",
+                self.max_media_bytes
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+fn content_media_bytes(content: &Content) -> usize {
+    let image = content
+        .image_url
+        .as_ref()
+        .and_then(|image| data_url_payload(&image.url));
+    let audio = content.audio.as_ref().map(|audio| audio.data.as_str());
+    let file = content
+        .file
+        .as_ref()
+        .and_then(|file| file.data.as_deref())
+        .map(|data| data_url_payload(data).unwrap_or(data));
+
+    [image, audio, file]
+        .into_iter()
+        .flatten()
+        .map(decoded_len)
+        .sum()
+}
+
+/// Base64 payload of a `data:` URL. Remote URLs are fetched by the provider, not decoded here.
+fn data_url_payload(url: &str) -> Option<&str> {
+    url.strip_prefix("data:")
+        .and_then(|rest| rest.split_once(";base64,"))
+        .map(|(_, payload)| payload)
+}
+
+/// Decoded size of base64 `data`
+fn decoded_len(data: &str) -> usize {
+    let symbols = data
+        .bytes()
+        .filter(|b| !b.is_ascii_whitespace() && *b != b'=')
+        .count();
+    symbols * 3 / 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, web, App, HttpResponse};
+
+    async fn echo_length(request: web::Json<serde_json::Value>) -> HttpResponse {
+        HttpResponse::Ok().body(request.to_string().len().to_string())
+    }
+
+    fn request_with_image(data: &str) -> ChatCompletionRequest {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o",
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "What is in this image?"},
+                    {"type": "image_url", "image_url": {"url": format!("data:image/png;base64,{data}")}}
+                ]
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn test_oversized_body_is_rejected_with_413() {
+        let config = RequestLimitsConfig {
+            max_body_bytes: 64,
+            ..Default::default()
+        };
+        let app = test::init_service(
+            App::new()
+                .app_data(config.json_config())
+                .route("/", web::post().to(echo_length)),
+        )
+        .await;
+
+        // `{"text":"…"}` is 11 bytes around the text
+        let borderline = serde_json::json!({"text": "a".repeat(64 - 11)});
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/")
+                .set_json(&borderline)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let oversized = serde_json::json!({"text": "a".repeat(64)});
+        let response = test::call_service(
+            &app,
+            test::TestRequest::post()
+                .uri("/")
+                .set_json(&oversized)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert!(body["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("limit of 64 bytes"));
+    }
+
+    #[test]
+    fn test_media_is_capped_by_decoded_size() {
+        let config = RequestLimitsConfig {
+            max_media_bytes: 6,
+            ..Default::default()
+        };
+
+        // 8 base64 symbols decode to exactly 6 bytes
+        assert!(config.check_media(&request_with_image("AAAAAAAA")).is_ok());
+
+        let err = config
+            .check_media(&request_with_image("AAAAAAAAAAAA"))
+            .unwrap_err();
+        assert!(matches!(err, GatewayError::PayloadTooLarge(_)));
+        assert!(err.to_string().contains("limit of 6 bytes"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::{collections::HashMap, sync::Arc};
 
 use crate::executor::responses::handle_create_response;
+use crate::handler::prompts::responses_prompt_mode;
 use crate::GatewayApiError;
 use crate::{
     credentials::KeyStorage,
@@ -10,7 +11,10 @@ use crate::{
     executor::context::ExecutorContext,
     handler::{CallbackHandlerFn, ModelEventWithDetails},
     model::{DefaultModelMetadataFactory, ModelMetadataFactory},
-    routing::interceptor::rate_limiter::InMemoryRateLimiterService,
+    routing::interceptor::{
+        rate_limit_store::rate_limiter_service,
+        rate_limiter::{record_rate_limit_cost, RateLimiterService},
+    },
     types::{
         guardrails::service::GuardrailsEvaluator,
         metadata::{project::Project, services::model::ModelService},
@@ -38,6 +42,7 @@ async fn prepare_request(
     run_id: Option<String>,
     thread_id: Option<String>,
     cost_calculator: Arc<Box<dyn CostCalculator>>,
+    rate_limiter_service: Arc<dyn RateLimiterService>,
     span: Span,
 ) -> Result<(JoinHandle<()>, CallbackHandlerFn), GatewayApiError> {
     let (tx, mut rx) = tokio::sync::broadcast::channel(10000);
@@ -99,6 +104,7 @@ async fn prepare_request(
                                     );
                                 }
 
+                                record_rate_limit_cost(rate_limiter_service.as_ref(), cost.0).await;
                                 cost
                             }
                             None => {
@@ -203,7 +209,11 @@ pub async fn create(
         title = tracing::field::Empty,
         cost = tracing::field::Empty,
         usage = tracing::field::Empty,
+        prompt_mode = tracing::field::Empty,
     ));
+    if let Some(mode) = responses_prompt_mode(&request) {
+        span.record("prompt_mode", mode.as_str());
+    }
 
     let thread_title = req.headers().get("X-Thread-Title").map_or_else(
         || get_thread_title(&request),
@@ -215,6 +225,7 @@ pub async fn create(
     }
 
     let cost_calculator = cost_calculator.into_inner();
+    let rate_limiter_service: Arc<dyn RateLimiterService> = Arc::new(rate_limiter_service());
     let (_handle, callback_handler) = prepare_request(
         &callback_handler.get_ref().clone(),
         &request,
@@ -224,11 +235,11 @@ pub async fn create(
         Some(run_id.value()),
         Some(thread_id.value()),
         cost_calculator.clone(),
+        rate_limiter_service.clone(),
         span.clone(),
     )
     .await?;
 
-    let rate_limiter_service = InMemoryRateLimiterService::new();
     let guardrails_evaluator_service = evaluator_service.clone().into_inner();
 
     let executor_context = ExecutorContext::new(
@@ -240,7 +251,7 @@ pub async fn create(
         &req,
         HashMap::new(),
         guardrails_evaluator_service,
-        Arc::new(rate_limiter_service),
+        rate_limiter_service,
         project.id,
         key_storage.into_inner(),
         None,
@@ -250,3 +261,121 @@ pub async fn create(
         .instrument(span.clone())
         .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::interceptor::rate_limiter::{RateLimitResult, RateLimiterConfig};
+    use crate::routing::interceptor::InterceptorError;
+    use serde_json::json;
+    use std::sync::Mutex;
+    use vllora_llm::types::credentials_ident::CredentialsIdent;
+    use vllora_llm::types::engine::Model;
+    use vllora_llm::types::gateway::{
+        CostCalculationResult, CostCalculatorError, GatewayModelUsage,
+    };
+    use vllora_llm::types::models::ModelType;
+    use vllora_llm::types::provider::ModelPrice;
+    use vllora_llm::types::{LLMFinishEvent, ModelFinishReason};
+
+    struct FixedCost(f64);
+
+    #[async_trait::async_trait]
+    impl CostCalculator for FixedCost {
+        async fn calculate_cost(
+            &self,
+            _model_price: &ModelPrice,
+            _usage: &Usage,
+            _credentials_ident: &CredentialsIdent,
+        ) -> Result<CostCalculationResult, CostCalculatorError> {
+            Ok(serde_json::from_value(json!({
+                "cost": self.0,
+                "per_input_token": 0.0,
+                "per_output_token": 0.0,
+                "is_cache_used": false
+            }))
+            .unwrap())
+        }
+    }
+
+    #[derive(Default)]
+    struct RecordedCosts(Mutex<Vec<f64>>);
+
+    #[async_trait::async_trait]
+    impl RateLimiterService for RecordedCosts {
+        async fn check_rate_limit(
+            &self,
+            _entity_id: &str,
+            _config: &RateLimiterConfig,
+        ) -> Result<RateLimitResult, InterceptorError> {
+            unimplemented!()
+        }
+
+        async fn record_cost(&self, cost: f64) -> Result<(), InterceptorError> {
+            self.0.lock().unwrap().push(cost);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_response_cost_is_recorded_against_rate_limits() {
+        let request: CreateResponse = serde_json::from_value(json!({
+            "model": "openai/gpt-4o-mini",
+            "input": "Hi"
+        }))
+        .unwrap();
+        let rate_limiter_service = Arc::new(RecordedCosts::default());
+        let (handle, callback_handler) = prepare_request(
+            &GatewayCallbackHandlerFn::default(),
+            &request,
+            "vllora",
+            "default",
+            vec![],
+            None,
+            None,
+            Arc::new(Box::new(FixedCost(0.25)) as Box<dyn CostCalculator>),
+            rate_limiter_service.clone(),
+            Span::none(),
+        )
+        .await
+        .unwrap();
+
+        let model = Model {
+            name: "gpt-4o-mini".to_string(),
+            inference_model_name: "gpt-4o-mini".to_string(),
+            provider_name: "openai".to_string(),
+            model_type: ModelType::Responses,
+            price: serde_json::from_value(json!({
+                "per_input_token": 0.15,
+                "per_output_token": 0.6
+            }))
+            .unwrap(),
+            credentials_ident: CredentialsIdent::Own,
+        };
+        callback_handler.on_message(ModelEventWithDetails::new(
+            ModelEvent::new(
+                &Span::none(),
+                ModelEventType::LlmStop(LLMFinishEvent {
+                    provider_name: "openai".to_string(),
+                    model_name: "gpt-4o-mini".to_string(),
+                    output: Some("Hello".to_string()),
+                    usage: Some(GatewayModelUsage {
+                        input_tokens: 10,
+                        output_tokens: 5,
+                        total_tokens: 15,
+                        ..Default::default()
+                    }),
+                    finish_reason: ModelFinishReason::Stop,
+                    tool_calls: vec![],
+                    credentials_ident: CredentialsIdent::Own,
+                    rate_limit: None,
+                }),
+            ),
+            Some(model),
+        ));
+        drop(callback_handler);
+        handle.await.unwrap();
+
+        assert_eq!(*rate_limiter_service.0.lock().unwrap(), vec![0.25]);
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use tokio::sync::Mutex;
+use vllora_llm::types::gateway::{ChatCompletionRequestWithTools, CostCalculator};
+
+use crate::credentials::KeyStorage;
+use crate::error::GatewayError;
+use crate::executor::context::ExecutorContext;
+use crate::handler::CallbackHandlerFn;
+use crate::metadata::pool::DbPool;
+use crate::model::{DefaultModelMetadataFactory, ModelMetadataFactory};
+use crate::routing::interceptor::rate_limiter::InMemoryRateLimiterService;
+use crate::routing::metrics::InMemoryMetricsRepository;
+use crate::routing::{LlmRouter, RoutingStrategy};
+use crate::types::guardrails::service::GuardrailsEvaluator;
+use crate::types::metadata::project::Project;
+use crate::types::metadata::services::model::ModelService;
+use crate::usage::InMemoryStorage;
+use crate::GatewayApiError;
+
+/// Explains which targets the router of a chat completion request would pick, and why,
+/// without executing the request.
+#[allow(clippy::too_many_arguments)]
+pub async fn explain_routing(
+    request: web::Json<ChatCompletionRequestWithTools<RoutingStrategy>>,
+    req: HttpRequest,
+    cost_calculator: web::Data<Box<dyn CostCalculator>>,
+    evaluator_service: web::Data<Box<dyn GuardrailsEvaluator>>,
+    project: web::ReqData<Project>,
+    key_storage: web::Data<Box<dyn KeyStorage>>,
+    models_service: web::Data<Box<dyn ModelService>>,
+    db_pool: web::Data<DbPool>,
+) -> Result<HttpResponse, GatewayApiError> {
+    let request = request.into_inner();
+    let Some(router) = &request.router else {
+        return Err(
+            GatewayError::InvalidRequest("Request has no router to explain".to_string()).into(),
+        );
+    };
+
+    let db_pool = db_pool.into_inner();
+    let executor_context = ExecutorContext::new(
+        CallbackHandlerFn(None),
+        cost_calculator.into_inner(),
+        Arc::new(Box::new(
+            DefaultModelMetadataFactory::new(models_service.into_inner()).with_db_pool(&db_pool),
+        ) as Box<dyn ModelMetadataFactory>),
+        &req,
+        HashMap::new(),
+        evaluator_service.into_inner(),
+        Arc::new(InMemoryRateLimiterService::new()),
+        project.id,
+        key_storage.into_inner(),
+        None,
+    )?;
+
+    let llm_router = LlmRouter::new(
+        router.name.clone().unwrap_or("dynamic".to_string()),
+        router.strategy.clone(),
+    )
+    .with_targets(router.targets.clone())
+    .with_project_id(project.id);
+
+    let metrics = match req.app_data::<Arc<Mutex<InMemoryStorage>>>() {
+        Some(storage) => storage.lock().await.get_all_counters().await,
+        None => BTreeMap::new(),
+    };
+    let metrics_repository = InMemoryMetricsRepository::new(metrics);
+
+    let explanation = llm_router
+        .explain(
+            request.request.clone(),
+            request.extra.as_ref(),
+            Arc::clone(&executor_context.model_metadata_factory),
+            executor_context.metadata.clone(),
+            &metrics_repository,
+            executor_context.get_interceptor_factory(),
+        )
+        .await?;
+
+    Ok(HttpResponse::Ok().json(explanation))
+}
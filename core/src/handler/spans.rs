@@ -205,6 +205,7 @@ pub async fn list_spans<T: TraceService + DatabaseServiceTrait>(
         sort_by: None,
         sort_order: None,
         labels,
+        after: None,
     };
 
     Ok(trace_service.list(list_query.clone()).map(|traces| {
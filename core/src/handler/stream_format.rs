@@ -0,0 +1,235 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use vllora_llm::types::gateway::StreamFormat;
+
+use crate::GatewayApiError;
+
+/// Format of a streamed response: `stream_format` from the request, else the first
+/// streaming format named in `Accept`, else server-sent events.
+///
+/// `application/json` in `Accept` doesn't select [`StreamFormat::JsonArray`], OpenAI SDKs
+/// send it with every request, streamed or not.
+pub fn negotiate(requested: Option<StreamFormat>, accept: Option<&str>) -> StreamFormat {
+    requested
+        .or_else(|| {
+            accept?.split(',').find_map(|media_type| {
+                match media_type.split(';').next().unwrap_or_default().trim() {
+                    "text/event-stream" => Some(StreamFormat::Sse),
+                    "application/x-ndjson" | "application/ndjson" | "application/jsonl" => {
+                        Some(StreamFormat::Ndjson)
+                    }
+                    _ => None,
+                }
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// OpenAI style error chunk for an error raised while streaming
+pub fn error_chunk(error: &GatewayApiError) -> String {
+    let error_type = match error {
+        GatewayApiError::GatewayError(e) => e.error_type(),
+        _ => None,
+    };
+    serde_json::json!({
+        "error": {
+            "message": error.to_string(),
+            "type": error_type.unwrap_or("server_error"),
+            "code": null,
+        }
+    })
+    .to_string()
+}
+
+/// Frames `chunks`, each one serialized JSON document, in `stream_format`.
+///
+/// An error ends the stream with an [`error_chunk`] instead of cutting the connection, and
+/// is recorded as `error` on the current span. SSE streams ending with an error have no
+/// `[DONE]` event.
+pub fn encode<S>(
+    stream_format: StreamFormat,
+    chunks: S,
+) -> impl Stream<Item = Result<Bytes, GatewayApiError>>
+where
+    S: Stream<Item = Result<String, GatewayApiError>>,
+{
+    let (open, close): (&'static [u8], &'static [u8]) = match stream_format {
+        StreamFormat::Sse => (b"", b"data: [DONE]\n\n"),
+        StreamFormat::Ndjson => (b"", b""),
+        StreamFormat::JsonArray => (b"[", b"]"),
+    };
+    let failed = Arc::new(AtomicBool::new(false));
+
+    let chunks = chunks
+        .enumerate()
+        .take_while({
+            let failed = failed.clone();
+            move |_| futures::future::ready(!failed.load(Ordering::Relaxed))
+        })
+        .map({
+            let failed = failed.clone();
+            move |(index, chunk)| {
+                let json = chunk.unwrap_or_else(|e| {
+                    tracing::Span::current().record("error", e.to_string());
+                    failed.store(true, Ordering::Relaxed);
+                    error_chunk(&e)
+                });
+                Ok(Bytes::from(match stream_format {
+                    StreamFormat::Sse => format!("data: {json}\n\n"),
+                    StreamFormat::Ndjson => format!("{json}\n"),
+                    StreamFormat::JsonArray if index == 0 => json,
+                    StreamFormat::JsonArray => format!(",{json}"),
+                }))
+            }
+        });
+    let close = futures::stream::once(async move {
+        match stream_format {
+            StreamFormat::Sse if failed.load(Ordering::Relaxed) => Ok(Bytes::new()),
+            _ => Ok(Bytes::from_static(close)),
+        }
+    });
+
+    futures::stream::iter([open])
+        .map(|open| Ok(Bytes::from_static(open)))
+        .chain(chunks)
+        .chain(close)
+        .filter(|bytes| futures::future::ready(!matches!(bytes, Ok(b) if b.is_empty())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::HttpResponse;
+    use vllora_llm::types::gateway::ChatCompletionChunk;
+
+    fn chunk(content: &str) -> String {
+        serde_json::json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion.chunk",
+            "created": 1,
+            "model": "openai/gpt-4o-mini",
+            "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": null}]
+        })
+        .to_string()
+    }
+
+    async fn stream_body(stream_format: StreamFormat) -> (String, String) {
+        let chunks = futures::stream::iter(["Hel", "lo"].map(|content| Ok(chunk(content))));
+        let response = HttpResponse::Ok()
+            .content_type(stream_format.content_type())
+            .streaming(encode(stream_format, chunks));
+        let content_type = response.headers()["content-type"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        (content_type, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    fn content(chunks: &[ChatCompletionChunk]) -> String {
+        chunks
+            .iter()
+            .filter_map(|chunk| chunk.choices[0].delta.content.clone())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sse_stream() {
+        let (content_type, body) = stream_body(StreamFormat::Sse).await;
+        assert_eq!(content_type, "text/event-stream");
+
+        let events: Vec<&str> = body
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| event.strip_prefix("data: ").unwrap())
+            .collect();
+        assert_eq!(events.last(), Some(&"[DONE]"));
+        let chunks: Vec<ChatCompletionChunk> = events[..events.len() - 1]
+            .iter()
+            .map(|event| serde_json::from_str(event).unwrap())
+            .collect();
+        assert_eq!(content(&chunks), "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_ndjson_stream() {
+        let (content_type, body) = stream_body(StreamFormat::Ndjson).await;
+        assert_eq!(content_type, "application/x-ndjson");
+
+        let chunks: Vec<ChatCompletionChunk> = body
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(content(&chunks), "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_json_array_stream() {
+        let (content_type, body) = stream_body(StreamFormat::JsonArray).await;
+        assert_eq!(content_type, "application/json");
+
+        let chunks: Vec<ChatCompletionChunk> = serde_json::from_str(&body).unwrap();
+        assert_eq!(content(&chunks), "Hello");
+
+        let empty: Vec<Bytes> = encode(StreamFormat::JsonArray, futures::stream::empty())
+            .map(|bytes| bytes.unwrap())
+            .collect()
+            .await;
+        assert_eq!(empty.concat(), b"[]");
+    }
+
+    #[test]
+    fn test_negotiate_format() {
+        assert_eq!(negotiate(None, None), StreamFormat::Sse);
+        assert_eq!(
+            negotiate(None, Some("application/x-ndjson, text/event-stream")),
+            StreamFormat::Ndjson
+        );
+        assert_eq!(negotiate(None, Some("application/json")), StreamFormat::Sse);
+        assert_eq!(
+            negotiate(Some(StreamFormat::JsonArray), Some("text/event-stream")),
+            StreamFormat::JsonArray
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mid_stream_error_ends_sse_stream() {
+        let chunks = futures::stream::iter([
+            Ok(chunk("Hel")),
+            Err(GatewayApiError::GatewayError(
+                crate::error::GatewayError::ProviderUnavailable {
+                    message: "connection reset".to_string(),
+                    retry_after: None,
+                },
+            )),
+            Ok(chunk("lo")),
+        ]);
+        let body: Vec<Bytes> = encode(StreamFormat::Sse, chunks)
+            .map(|bytes| bytes.unwrap())
+            .collect()
+            .await;
+        let body = String::from_utf8(body.concat()).unwrap();
+
+        let events: Vec<&str> = body
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| event.strip_prefix("data: ").unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        let chunk: ChatCompletionChunk = serde_json::from_str(events[0]).unwrap();
+        assert_eq!(content(&[chunk]), "Hel");
+
+        let error: serde_json::Value = serde_json::from_str(events[1]).unwrap();
+        assert_eq!(error["error"]["type"], "provider_unavailable");
+        assert!(error["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("connection reset"));
+    }
+}
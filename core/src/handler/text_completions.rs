@@ -0,0 +1,332 @@
+use std::pin::Pin;
+
+use actix_web::body::{BodyStream, MessageBody};
+use actix_web::{web, HttpRequest, HttpResponse};
+use bytes::{Bytes, BytesMut};
+use futures::StreamExt;
+use serde_json::{json, Map, Value};
+use vllora_llm::types::gateway::{ChatCompletionRequestWithTools, CostCalculator};
+
+use crate::credentials::KeyStorage;
+use crate::error::GatewayError;
+use crate::events::callback_handler::GatewayCallbackHandlerFn;
+use crate::executor::chat_completion::breakpoint::BreakpointManager;
+use crate::handler::chat::create_chat_completion;
+use crate::metadata::pool::DbPool;
+use crate::routing::RoutingStrategy;
+use crate::types::guardrails::service::GuardrailsEvaluator;
+use crate::types::metadata::project::Project;
+use crate::types::metadata::services::model::ModelService;
+use crate::types::threads::{CompletionsRunId, CompletionsThreadId};
+use crate::GatewayApiError;
+
+/// Legacy fields without a chat completions counterpart, `echo` is applied to the response
+const UNSUPPORTED_FIELDS: [&str; 4] = ["suffix", "best_of", "logprobs", "echo"];
+
+/// Maps a legacy `/completions` request to a chat completion with the prompt as the single
+/// user message. Other fields, including gateway extensions like `extra` or `router`, are
+/// kept as they are.
+pub fn to_chat_request(
+    legacy: Value,
+) -> Result<ChatCompletionRequestWithTools<RoutingStrategy>, GatewayError> {
+    let Value::Object(mut fields) = legacy else {
+        return Err(GatewayError::InvalidRequest(
+            "Request must be a JSON object".to_string(),
+        ));
+    };
+
+    let prompt = match fields.remove("prompt") {
+        Some(Value::String(prompt)) => prompt,
+        Some(Value::Array(prompts)) if prompts.len() == 1 && prompts[0].is_string() => {
+            prompts[0].as_str().unwrap_or_default().to_string()
+        }
+        Some(Value::Array(_)) => {
+            return Err(GatewayError::InvalidRequest(
+                "Only a single prompt string is supported".to_string(),
+            ))
+        }
+        _ => {
+            return Err(GatewayError::InvalidRequest(
+                "`prompt` must be a string".to_string(),
+            ))
+        }
+    };
+    for field in UNSUPPORTED_FIELDS {
+        fields.remove(field);
+    }
+    if let Some(Value::String(stop)) = fields.get("stop").cloned() {
+        fields.insert("stop".to_string(), json!([stop]));
+    }
+    // The legacy stream is only defined for server-sent events
+    fields.insert("stream_format".to_string(), json!("sse"));
+    fields.insert(
+        "messages".to_string(),
+        json!([{"role": "user", "content": prompt}]),
+    );
+
+    serde_json::from_value(Value::Object(fields))
+        .map_err(|e| GatewayError::InvalidRequest(e.to_string()))
+}
+
+/// Legacy `text_completion` object for a chat completion or chat completion chunk
+fn to_legacy_object(chat: &Value, echo: Option<&str>) -> Value {
+    let choices: Vec<Value> = chat["choices"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|choice| {
+            let content = choice["message"]["content"]
+                .as_str()
+                .or_else(|| choice["delta"]["content"].as_str())
+                .unwrap_or_default();
+            json!({
+                "text": format!("{}{content}", echo.unwrap_or_default()),
+                "index": choice["index"],
+                "logprobs": Value::Null,
+                "finish_reason": choice["finish_reason"],
+            })
+        })
+        .collect();
+
+    let mut legacy = Map::new();
+    for field in ["id", "created", "model", "system_fingerprint"] {
+        if let Some(value) = chat.get(field) {
+            legacy.insert(field.to_string(), value.clone());
+        }
+    }
+    legacy.insert("object".to_string(), json!("text_completion"));
+    legacy.insert("choices".to_string(), json!(choices));
+    if let Some(usage) = chat.get("usage").filter(|usage| !usage.is_null()) {
+        legacy.insert("usage".to_string(), usage.clone());
+    }
+    Value::Object(legacy)
+}
+
+/// Legacy server-sent event for a chat completions event, `[DONE]` is kept as is
+fn to_legacy_event(event: &[u8], echo: Option<&str>) -> Bytes {
+    let data = std::str::from_utf8(event)
+        .ok()
+        .and_then(|event| event.strip_prefix("data: "));
+    match data.and_then(|data| serde_json::from_str::<Value>(data).ok()) {
+        Some(chunk) => Bytes::from(format!("data: {}\n\n", to_legacy_object(&chunk, echo))),
+        None => Bytes::from([event, &b"\n\n"[..]].concat()),
+    }
+}
+
+/// Rewrites a chat completions response in the legacy format. Error responses are returned
+/// as they are. With `echo` the prompt is prepended to the text of the first event, or of
+/// the buffered response.
+pub async fn to_legacy_response(
+    response: HttpResponse,
+    stream: bool,
+    echo: Option<String>,
+) -> Result<HttpResponse, GatewayApiError> {
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+    let (response, body) = response.into_parts();
+
+    if !stream {
+        let body = actix_web::body::to_bytes(body)
+            .await
+            .map_err(|e| GatewayApiError::CustomError(e.to_string()))?;
+        let chat: Value = serde_json::from_slice(&body)?;
+        let legacy = to_legacy_object(&chat, echo.as_deref());
+        return Ok(response
+            .set_body(serde_json::to_vec(&legacy)?)
+            .map_into_boxed_body());
+    }
+
+    let mut body = body;
+    let chunks = futures::stream::poll_fn(move |cx| Pin::new(&mut body).poll_next(cx));
+    let events = futures::stream::unfold(
+        (chunks.boxed_local(), BytesMut::new(), echo, false),
+        |(mut chunks, mut buffer, mut echo, mut done)| async move {
+            loop {
+                if let Some(end) = buffer.windows(2).position(|w| w == b"\n\n") {
+                    let event = buffer.split_to(end + 2);
+                    let event = to_legacy_event(&event[..end], echo.as_deref());
+                    if event.starts_with(b"data: {") {
+                        echo = None;
+                    }
+                    return Some((Ok(event), (chunks, buffer, echo, done)));
+                }
+                if done {
+                    return None;
+                }
+                match chunks.next().await {
+                    Some(Ok(bytes)) => buffer.extend_from_slice(&bytes),
+                    Some(Err(e)) => {
+                        done = true;
+                        return Some((Err(e), (chunks, buffer, echo, done)));
+                    }
+                    None => {
+                        done = true;
+                        if buffer.is_empty() {
+                            return None;
+                        }
+                        // Last event without its separator
+                        buffer.extend_from_slice(b"\n\n");
+                    }
+                }
+            }
+        },
+    );
+
+    Ok(response
+        .set_body(BodyStream::new(events))
+        .map_into_boxed_body())
+}
+
+/// OpenAI-compatible legacy `/completions` handler. The prompt is sent as a chat
+/// completion, so it's routed, traced and billed like any chat request.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_text_completion(
+    request: web::Json<Value>,
+    callback_handler: web::Data<GatewayCallbackHandlerFn>,
+    req: HttpRequest,
+    cost_calculator: web::Data<Box<dyn CostCalculator>>,
+    evaluator_service: web::Data<Box<dyn GuardrailsEvaluator>>,
+    run_id: web::ReqData<CompletionsRunId>,
+    thread_id: web::ReqData<CompletionsThreadId>,
+    project: web::ReqData<Project>,
+    key_storage: web::Data<Box<dyn KeyStorage>>,
+    models_service: web::Data<Box<dyn ModelService>>,
+    breakpoint_manager: web::Data<BreakpointManager>,
+    db_pool: web::Data<DbPool>,
+) -> Result<HttpResponse, GatewayApiError> {
+    let legacy = request.into_inner();
+    let echo = legacy["echo"]
+        .as_bool()
+        .unwrap_or(false)
+        .then(|| legacy["prompt"].as_str().map(ToString::to_string))
+        .flatten();
+    let chat_request = to_chat_request(legacy)?;
+    let stream = chat_request.request.stream.unwrap_or(false);
+
+    let response = create_chat_completion(
+        web::Json(chat_request),
+        callback_handler,
+        req,
+        cost_calculator,
+        evaluator_service,
+        run_id,
+        thread_id,
+        project,
+        key_storage,
+        models_service,
+        breakpoint_manager,
+        db_pool,
+    )
+    .await?;
+
+    to_legacy_response(response, stream, echo).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_llm::types::gateway::ChatCompletionContent;
+
+    #[test]
+    fn test_legacy_request_maps_to_chat_request() {
+        let request = to_chat_request(json!({
+            "model": "openai/gpt-4o-mini",
+            "prompt": "Say this is a test",
+            "max_tokens": 7,
+            "stop": "\n",
+            "logprobs": 2,
+            "echo": true,
+        }))
+        .unwrap();
+
+        assert_eq!(request.request.model, "openai/gpt-4o-mini");
+        assert_eq!(request.request.max_tokens, Some(7));
+        assert_eq!(request.request.stop, Some(vec!["\n".to_string()]));
+        assert_eq!(request.request.messages.len(), 1);
+        assert_eq!(request.request.messages[0].role, "user");
+        assert_eq!(
+            request.request.messages[0].content,
+            Some(ChatCompletionContent::Text(
+                "Say this is a test".to_string()
+            ))
+        );
+
+        assert!(to_chat_request(json!({"model": "m", "prompt": ["a", "b"]})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chat_response_maps_to_legacy_response() {
+        let chat = HttpResponse::Ok().json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "created": 1,
+            "model": "openai/gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "This is a test."},
+                "finish_reason": "stop"
+            }],
+            "usage": {"prompt_tokens": 5, "completion_tokens": 5, "total_tokens": 10}
+        }));
+
+        let response = to_legacy_response(chat, false, None).await.unwrap();
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let legacy: Value = serde_json::from_slice(&body).unwrap();
+
+        assert_eq!(legacy["object"], "text_completion");
+        assert_eq!(legacy["id"], "chatcmpl-1");
+        assert_eq!(legacy["choices"][0]["text"], "This is a test.");
+        assert_eq!(legacy["choices"][0]["finish_reason"], "stop");
+        assert_eq!(legacy["usage"]["total_tokens"], 10);
+    }
+
+    #[tokio::test]
+    async fn test_chat_stream_maps_to_legacy_stream() {
+        let chunk = |content: &str, finish_reason: Option<&str>| {
+            json!({
+                "id": "chatcmpl-1",
+                "object": "chat.completion.chunk",
+                "created": 1,
+                "model": "openai/gpt-4o-mini",
+                "choices": [{"index": 0, "delta": {"content": content}, "finish_reason": finish_reason}]
+            })
+            .to_string()
+        };
+        let chunks = futures::stream::iter(
+            [chunk("This is", None), chunk(" a test.", Some("stop"))].map(Ok),
+        );
+        let chat = HttpResponse::Ok()
+            .content_type("text/event-stream")
+            .streaming(crate::handler::stream_format::encode(
+                vllora_llm::types::gateway::StreamFormat::Sse,
+                chunks,
+            ));
+
+        let response = to_legacy_response(chat, true, Some("Say: ".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["content-type"], "text/event-stream");
+        let body = actix_web::body::to_bytes(response.into_body())
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+
+        let events: Vec<&str> = body
+            .split("\n\n")
+            .filter(|event| !event.is_empty())
+            .map(|event| event.strip_prefix("data: ").unwrap())
+            .collect();
+        assert_eq!(events.last(), Some(&"[DONE]"));
+        let texts: Vec<Value> = events[..events.len() - 1]
+            .iter()
+            .map(|event| serde_json::from_str(event).unwrap())
+            .collect();
+        assert!(texts.iter().all(|t| t["object"] == "text_completion"));
+        assert_eq!(texts[0]["choices"][0]["text"], "Say: This is");
+        assert_eq!(texts[1]["choices"][0]["text"], " a test.");
+        assert_eq!(texts[1]["choices"][0]["finish_reason"], "stop");
+    }
+}
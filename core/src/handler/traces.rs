@@ -69,6 +69,7 @@ pub async fn list_traces<T: TraceService + DatabaseServiceTrait>(
         sort_by: None,
         sort_order: None,
         labels,
+        after: None,
     };
 
     Ok(trace_service
@@ -165,6 +166,7 @@ pub async fn get_spans_by_run<T: TraceService + DatabaseServiceTrait>(
                 sort_by: None,
                 sort_order: None,
                 labels: None,
+                after: None,
             };
             let total = trace_service.count(count_query).unwrap_or(0);
 
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+
+use vllora_llm::client::tools::validation::validate_definition;
+use vllora_llm::types::gateway::ChatCompletionRequest;
+
+use crate::error::GatewayError;
+use crate::model::tools::GatewayTool;
+
+const ROLES: [&str; 6] = [
+    "system",
+    "developer",
+    "user",
+    "assistant",
+    "tool",
+    "function",
+];
+
+/// Most tokens a `logit_bias` can adjust, larger maps are rejected
+const MAX_LOGIT_BIAS_TOKENS: usize = 300;
+
+fn invalid(param: impl Into<String>, message: impl Into<String>) -> GatewayError {
+    GatewayError::InvalidParameter {
+        param: param.into(),
+        message: message.into(),
+    }
+}
+
+/// Checks a chat completion request for mistakes providers would reject, so they are
+/// reported as a 400 naming the offending parameter before any provider is called.
+pub fn validate_chat_request(request: &ChatCompletionRequest) -> Result<(), GatewayError> {
+    if request.messages.is_empty() {
+        return Err(invalid("messages", "must contain at least one message"));
+    }
+
+    for (i, message) in request.messages.iter().enumerate() {
+        if !ROLES.contains(&message.role.as_str()) {
+            return Err(invalid(
+                format!("messages[{i}].role"),
+                format!(
+                    "has unknown role {}, expected one of {}",
+                    message.role,
+                    ROLES.join(", ")
+                ),
+            ));
+        }
+        if message.role == "tool" && message.tool_call_id.is_none() {
+            return Err(invalid(
+                format!("messages[{i}].tool_call_id"),
+                "is required for tool messages",
+            ));
+        }
+    }
+
+    let mut tool_names = HashSet::new();
+    for (i, tool) in request.tools.iter().flatten().enumerate() {
+        if !tool_names.insert(tool.function.name.as_str()) {
+            return Err(invalid(
+                format!("tools[{i}].function.name"),
+                format!("duplicates tool {}", tool.function.name),
+            ));
+        }
+        validate_definition(&GatewayTool { def: tool.clone() })
+            .map_err(|e| invalid(format!("tools[{i}].function.parameters"), e.message))?;
+    }
+
+    if request.functions.is_some() && request.tools.is_some() {
+        return Err(invalid("functions", "can't be combined with tools"));
+    }
+    if let (Some(max_tokens), Some(max_completion_tokens)) =
+        (request.max_tokens, request.max_completion_tokens)
+    {
+        if max_tokens != max_completion_tokens {
+            return Err(invalid(
+                "max_completion_tokens",
+                format!(
+                    "{max_completion_tokens} conflicts with max_tokens {max_tokens}, set only one"
+                ),
+            ));
+        }
+    }
+    if let Some(logit_bias) = &request.logit_bias {
+        if logit_bias.len() > MAX_LOGIT_BIAS_TOKENS {
+            return Err(invalid(
+                "logit_bias",
+                format!(
+                    "can adjust at most {MAX_LOGIT_BIAS_TOKENS} tokens, got {}",
+                    logit_bias.len()
+                ),
+            ));
+        }
+        for (token, bias) in logit_bias {
+            if token.parse::<u32>().is_err() {
+                return Err(invalid(
+                    format!("logit_bias.{token}"),
+                    "must be keyed by a token id",
+                ));
+            }
+            if !(-100..=100).contains(bias) {
+                return Err(invalid(
+                    format!("logit_bias.{token}"),
+                    format!("must be between -100 and 100, got {bias}"),
+                ));
+            }
+        }
+    }
+    if request.n == Some(0) {
+        return Err(invalid("n", "must be at least 1"));
+    }
+    if request.stream_options.is_some() && request.stream != Some(true) {
+        return Err(invalid(
+            "stream_options",
+            "is only allowed when stream is true",
+        ));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::ResponseError;
+    use std::collections::HashMap;
+    use vllora_llm::types::gateway::{ChatCompletionContent, ChatCompletionMessage, StreamOptions};
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            model: "openai/gpt-4o-mini".to_string(),
+            messages: vec![ChatCompletionMessage {
+                role: "user".to_string(),
+                content: Some(ChatCompletionContent::Text("Hi".to_string())),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn param(result: Result<(), GatewayError>) -> String {
+        result.unwrap_err().param().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_empty_messages_are_rejected() {
+        assert!(validate_chat_request(&request()).is_ok());
+
+        let error = validate_chat_request(&ChatCompletionRequest {
+            messages: vec![],
+            ..request()
+        })
+        .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid request: messages must contain at least one message"
+        );
+
+        let response = error.error_response();
+        assert_eq!(response.status(), 400);
+        let body = to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["error"]["type"], "invalid_request");
+        assert_eq!(body["error"]["param"], "messages");
+    }
+
+    #[test]
+    fn test_conflicting_parameters_are_rejected() {
+        assert_eq!(
+            param(validate_chat_request(&ChatCompletionRequest {
+                max_tokens: Some(100),
+                max_completion_tokens: Some(200),
+                ..request()
+            })),
+            "max_completion_tokens"
+        );
+        assert_eq!(
+            param(validate_chat_request(&ChatCompletionRequest {
+                stream: Some(false),
+                stream_options: Some(StreamOptions {
+                    include_usage: true,
+                }),
+                ..request()
+            })),
+            "stream_options"
+        );
+
+        assert_eq!(
+            param(validate_chat_request(&ChatCompletionRequest {
+                logit_bias: Some(HashMap::from([("50256".to_string(), 120)])),
+                ..request()
+            })),
+            "logit_bias.50256"
+        );
+        // Biases beyond an i8 are parsed, and rejected as out of range
+        let out_of_range: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o-mini",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "logit_bias": {"50256": 300},
+        }))
+        .unwrap();
+        assert_eq!(
+            param(validate_chat_request(&out_of_range)),
+            "logit_bias.50256"
+        );
+        assert_eq!(
+            param(validate_chat_request(&ChatCompletionRequest {
+                logit_bias: Some(HashMap::from([("hello".to_string(), 1)])),
+                ..request()
+            })),
+            "logit_bias.hello"
+        );
+        assert!(validate_chat_request(&ChatCompletionRequest {
+            logit_bias: Some(HashMap::from([("50256".to_string(), -100)])),
+            ..request()
+        })
+        .is_ok());
+
+        let mut tool_message = request();
+        tool_message.messages.push(ChatCompletionMessage {
+            role: "tool".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(
+            param(validate_chat_request(&tool_message)),
+            "messages[1].tool_call_id"
+        );
+    }
+}
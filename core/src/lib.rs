@@ -9,6 +9,7 @@ pub mod llm_gateway;
 pub mod mcp;
 pub mod metadata;
 pub mod model;
+pub mod plugins;
 pub mod pricing;
 pub mod routing;
 pub mod telemetry;
@@ -19,10 +20,12 @@ use crate::error::GatewayError;
 use actix_web::http::header::ContentType;
 use actix_web::http::StatusCode;
 use actix_web::HttpResponse;
+use actix_web::ResponseError;
 use executor::chat_completion::routed_executor::RoutedExecutorError;
 use serde_json::json;
 use thiserror::Error;
 use tracing::Span;
+use vllora_llm::client::error::ModelError;
 use vllora_llm::error::LLMError;
 use vllora_llm::types::gateway::CostCalculatorError;
 
@@ -47,7 +50,7 @@ pub enum GatewayApiError {
     GatewayError(#[from] GatewayError),
 
     #[error(transparent)]
-    LLMError(#[from] LLMError),
+    LLMError(LLMError),
 
     #[error("{0}")]
     CustomError(String),
@@ -66,6 +69,22 @@ pub enum GatewayApiError {
 
     #[error(transparent)]
     KeyStorageError(#[from] KeyStorageError),
+
+    /// Error of another request, see [`GatewayApiError::to_shared`]
+    #[error("{message}")]
+    Shared { status: StatusCode, message: String },
+}
+
+impl From<LLMError> for GatewayApiError {
+    fn from(value: LLMError) -> Self {
+        match value.classify() {
+            Some(class) => GatewayApiError::GatewayError(GatewayError::from_provider_error(
+                class,
+                value.to_string(),
+            )),
+            None => GatewayApiError::LLMError(value),
+        }
+    }
 }
 
 impl GatewayApiError {
@@ -78,9 +97,93 @@ impl GatewayApiError {
             )))
         )
     }
+
+    /// Copy of the error for other requests sharing the call that failed with it. Classified
+    /// errors are copied as is, keeping their status and `Retry-After`; others keep their
+    /// status and message.
+    pub fn to_shared(&self) -> GatewayApiError {
+        match self {
+            GatewayApiError::GatewayError(e) => match e.try_clone() {
+                Some(e) => GatewayApiError::GatewayError(e),
+                None => GatewayApiError::Shared {
+                    status: self.status_code(),
+                    message: self.to_string(),
+                },
+            },
+            GatewayApiError::CustomError(message) => GatewayApiError::CustomError(message.clone()),
+            GatewayApiError::TokenUsageLimit => GatewayApiError::TokenUsageLimit,
+            GatewayApiError::Shared { status, message } => GatewayApiError::Shared {
+                status: *status,
+                message: message.clone(),
+            },
+            e => GatewayApiError::Shared {
+                status: e.status_code(),
+                message: e.to_string(),
+            },
+        }
+    }
+
+    /// Errors caused by the request itself, which every fallback target would reject too.
+    /// Provider authentication failures are not included, each target has its own credentials.
+    pub fn is_request_error(&self) -> bool {
+        match self {
+            GatewayApiError::TokenUsageLimit => true,
+            GatewayApiError::GatewayError(e) => matches!(
+                e,
+                GatewayError::InvalidRequest(_)
+                    | GatewayError::InvalidParameter { .. }
+                    | GatewayError::PolicyDenied(_)
+                    | GatewayError::PayloadTooLarge(_)
+                    | GatewayError::GuardError(GuardError::GuardNotPassed(_, _))
+            ),
+            _ => false,
+        }
+    }
+
+    /// Failures of a provider or of the connection to it. Unlike errors of the request or
+    /// of the gateway's own checks, another provider could have answered.
+    pub fn is_provider_failure(&self) -> bool {
+        match self {
+            GatewayApiError::GatewayError(e) => match e {
+                GatewayError::RateLimited { .. }
+                | GatewayError::ProviderUnavailable { .. }
+                | GatewayError::Overloaded(_) => true,
+                GatewayError::ReqwestError(_) => true,
+                GatewayError::ModelError(e) => is_provider_model_failure(e),
+                GatewayError::LLMError(e) => is_provider_failure(e),
+                _ => false,
+            },
+            GatewayApiError::LLMError(e) => is_provider_failure(e),
+            GatewayApiError::Shared { status, .. } => {
+                *status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Unclassified provider errors, see [`GatewayApiError::is_provider_failure`]
+fn is_provider_failure(error: &LLMError) -> bool {
+    match error {
+        LLMError::ReqwestError(_) | LLMError::FirstTokenTimeout(_) => true,
+        LLMError::ModelError(e) => is_provider_model_failure(e),
+        _ => false,
+    }
+}
+
+fn is_provider_model_failure(error: &ModelError) -> bool {
+    matches!(
+        error,
+        ModelError::OpenAIApi(_)
+            | ModelError::Bedrock(_)
+            | ModelError::Anthropic(_)
+            | ModelError::Gemini(_)
+            | ModelError::StreamError(_)
+            | ModelError::MaxRetriesReached
+    )
 }
 
-impl actix_web::error::ResponseError for GatewayApiError {
+impl ResponseError for GatewayApiError {
     fn error_response(&self) -> HttpResponse {
         tracing::error!("API error: {:?}", self);
 
@@ -112,6 +215,7 @@ impl actix_web::error::ResponseError for GatewayApiError {
             GatewayApiError::RoutedExecutorError(_) => StatusCode::INTERNAL_SERVER_ERROR,
             GatewayApiError::TokenUsageLimit => StatusCode::BAD_REQUEST,
             GatewayApiError::KeyStorageError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            GatewayApiError::Shared { status, .. } => *status,
         }
     }
 }
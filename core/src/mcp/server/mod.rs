@@ -10,16 +10,20 @@ pub use rmcp::transport::streamable_http_server::session::local::LocalSessionMan
 
 use crate::mcp::server::prompts::Prompts;
 use crate::mcp::server::tools::{
-    ErrorBreadcrumb, GetLlmCallInclude, GetLlmCallParams, GetLlmCallResponse,
-    GetRecentOverviewParams, GetRecentOverviewResponse, GetRunOverviewParams,
+    DescribeToolsResponse, ErrorBreadcrumb, GetLlmCallInclude, GetLlmCallParams,
+    GetLlmCallResponse, GetRecentOverviewParams, GetRecentOverviewResponse, GetRunOverviewParams,
     GetRunOverviewResponse, LlmModelStats, LlmRequest, LlmResponse, LlmSummary, Redaction,
     RunOverviewRun, RunOverviewSpan, SearchTraceItem, SearchTracesInclude,
     SearchTracesOperationKind, SearchTracesParams, SearchTracesResponse, SearchTracesSortOrder,
-    SearchTracesStatus, ToolCallStats, ToolSummary, UnsafeText,
+    SearchTracesStatus, ToolCallStats, ToolDescription, ToolSummary, UnsafeText,
 };
+use crate::metadata::pool::DbPool;
+use crate::metadata::services::mcp_config::McpConfigService;
+use crate::pricing::currency::{currency_converter, CurrencyConverter};
 use crate::rmcp::model::ListResourceTemplatesResult;
 use crate::types::handlers::pagination::PaginatedResult;
 use crate::types::metadata::services::trace::ListTracesQuery;
+use crate::types::metadata::services::trace::TraceCursor;
 use crate::types::metadata::services::trace::TraceService;
 use crate::types::traces::LangdbSpan;
 use rmcp::handler::server::wrapper::Parameters;
@@ -40,7 +44,12 @@ use rmcp_macros::prompt_handler;
 use rmcp_macros::prompt_router;
 use serde_json::{json, Value as JsonValue};
 use std::collections::HashMap;
-use vllora_llm::types::gateway::{CostCalculationResult, GatewayModelUsage};
+use std::sync::Arc;
+use vllora_llm::types::gateway::{CostCalculationResult, GatewayModelUsage, McpTool};
+use vllora_llm::types::tools::Tool;
+
+/// Tenant the gateway stores MCP configurations under
+const MCP_CONFIGS_TENANT: &str = "default";
 
 #[derive(Clone)]
 pub struct VlloraMcp<T: TraceService + Send + Sync + 'static> {
@@ -52,6 +61,10 @@ pub struct VlloraMcp<T: TraceService + Send + Sync + 'static> {
     prompts: Prompts,
     /// Default project slug for filtering traces
     project_slug: Option<String>,
+    /// Currency costs are shown in
+    currency: Arc<CurrencyConverter>,
+    /// MCP configurations whose tools are offered to models
+    mcp_configs: Option<Arc<McpConfigService>>,
 }
 
 #[tool_router]
@@ -65,9 +78,21 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             trace_service,
             prompts: Prompts::new(),
             project_slug,
+            currency: currency_converter(),
+            mcp_configs: None,
         }
     }
 
+    pub fn with_mcp_configs(mut self, db_pool: DbPool) -> Self {
+        self.mcp_configs = Some(Arc::new(McpConfigService::new(db_pool)));
+        self
+    }
+
+    pub fn with_currency(mut self, currency: Arc<CurrencyConverter>) -> Self {
+        self.currency = currency;
+        self
+    }
+
     #[tool(description = "Get Vllora version")]
     async fn get_version(&self) -> Result<CallToolResult, McpError> {
         Ok(CallToolResult::success(vec![Content::text(env!(
@@ -75,6 +100,66 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
         ))]))
     }
 
+    /// Lists the tools of the registered MCP servers, with the parameter schemas models are
+    /// given for them.
+    #[tool(
+        name = "describe_tools",
+        description = "List the tools registered with the gateway with their descriptions and parameter schemas"
+    )]
+    pub async fn describe_tools(&self) -> Result<Json<DescribeToolsResponse>, String> {
+        let tools = self.registered_tools()?;
+
+        let mut descriptions = vec![];
+        for (name, tool) in tools.iter() {
+            let parameters = tool
+                .get_function_parameters()
+                .map(serde_json::to_value)
+                .transpose()
+                .map_err(|e| e.to_string())?
+                .unwrap_or_else(|| json!({}));
+            let description = tool.description();
+            descriptions.push(ToolDescription {
+                name: name.clone(),
+                description: (!description.is_empty()).then_some(description),
+                parameters,
+            });
+        }
+        descriptions.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(Json(DescribeToolsResponse {
+            tools: descriptions,
+        }))
+    }
+
+    /// Tools fetched from the MCP servers of the stored configurations
+    fn registered_tools(&self) -> Result<HashMap<String, Arc<Box<dyn Tool>>>, String> {
+        let mut tools_map = HashMap::new();
+        let Some(service) = &self.mcp_configs else {
+            return Ok(tools_map);
+        };
+
+        let configs = service
+            .get_all(MCP_CONFIGS_TENANT)
+            .map_err(|e| e.to_string())?;
+        for db_config in configs {
+            let config = db_config.to_mcp_config().map_err(|e| e.to_string())?;
+            let server_tools: HashMap<String, Vec<rmcp::model::Tool>> =
+                serde_json::from_str(&db_config.tools).unwrap_or_default();
+            for (server, tools) in server_tools {
+                let Some(server_config) = config.get_server(&server) else {
+                    continue;
+                };
+                let definition = server_config.to_mcp_definition();
+                for tool in tools {
+                    let tool = McpTool(tool, definition.clone());
+                    tools_map.insert(tool.name(), Arc::new(Box::new(tool) as Box<dyn Tool>));
+                }
+            }
+        }
+
+        Ok(tools_map)
+    }
+
     /// High-level MCP tool that wraps `get_traces` into the `search_traces` shape
     /// documented in DOC_v2.md.
     #[tool(name = "search_traces", description = "Search traces for analysis")]
@@ -91,6 +176,9 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
         if let Some(page) = &params.page {
             list_query.limit = page.limit;
             list_query.offset = page.offset.unwrap_or(0);
+            if let Some(cursor) = &page.cursor {
+                list_query = list_query.with_cursor(cursor)?;
+            }
         }
 
         // Apply filters
@@ -168,10 +256,16 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             });
         }
 
+        let keyset_cursor = list_query.sorts_by_start_time();
         let paginated: PaginatedResult<LangdbSpan> = self
             .trace_service
             .list_paginated(list_query)
             .map_err(|e| e.to_string())?;
+        let page_len = paginated.data.len() as i64;
+        let last_position = paginated
+            .data
+            .last()
+            .map(|span| TraceCursor::new(span.start_time_us, span.span_id.clone()));
 
         // Determine which optional fields should be populated based on `include`.
         let include = params.include.unwrap_or(SearchTracesInclude {
@@ -182,6 +276,13 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             output: false,
         });
 
+        // Costs are stored in USD and shown in the configured currency
+        let cost_converter = if include.costs {
+            Some(self.currency.converter().await)
+        } else {
+            None
+        };
+
         // Map PaginatedResult<LangdbSpan> into SearchTracesResponse, enriching
         // with labels, metrics, tokens and costs from the span attributes.
         let items: Vec<SearchTraceItem> = paginated
@@ -203,7 +304,6 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
                 // ----- metrics, tokens, costs -----
                 let mut metrics: HashMap<String, i64> = HashMap::new();
                 let mut tokens: Option<JsonValue> = None;
-                let mut costs: Option<JsonValue> = None;
 
                 // ttft metric (typically present on openai spans)
                 if include.metrics {
@@ -247,17 +347,10 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
                 }
 
                 // cost metric (commonly a string in "cost")
-                if include.costs {
-                    if let Some(raw_cost) = span.attribute.get("cost") {
-                        let cost_value: Option<JsonValue> = match raw_cost {
-                            JsonValue::String(s) => {
-                                s.parse::<f64>().ok().map(|v| serde_json::json!(v))
-                            }
-                            other => Some(other.clone()),
-                        };
-                        costs = cost_value;
-                    }
-                }
+                let costs = match (&cost_converter, span_cost(&span)) {
+                    (Some(converter), Some(cost)) => Some(converter.convert(cost)),
+                    _ => None,
+                };
 
                 // ----- attributes -----
                 let attributes: Option<HashMap<String, JsonValue>> = if include.attributes {
@@ -320,12 +413,17 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             })
             .collect();
 
+        // The total counts the spans after the cursor, but ignores the offset
         let pagination = paginated.pagination;
-        let next_offset = pagination.offset + pagination.limit;
-        let next_cursor = if next_offset < pagination.total {
-            Some(next_offset.to_string())
-        } else {
-            None
+        let next_cursor = match last_position {
+            Some(position) if pagination.offset + page_len < pagination.total => {
+                if keyset_cursor {
+                    Some(position.encode())
+                } else {
+                    Some((pagination.offset + pagination.limit).to_string())
+                }
+            }
+            _ => None,
         };
 
         Ok(Json(SearchTracesResponse { items, next_cursor }))
@@ -464,9 +562,12 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             None
         };
 
-        // Extract tokens and costs
+        // Extract tokens and costs, shown in the configured currency
         let tokens = span.attribute.get("usage").cloned();
-        let costs = span.attribute.get("cost").cloned();
+        let costs = match span_cost(&span) {
+            Some(cost) => Some(self.currency.convert(cost).await),
+            None => None,
+        };
 
         let raw_request = if include.raw_request {
             span.attribute.get("request").cloned()
@@ -480,8 +581,21 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             None
         };
 
-        // Build redactions list (placeholder - would need to track redactions in attributes)
-        let redactions: Option<Vec<Redaction>> = None;
+        // Categories of output content redacted by the gateway, recorded comma separated
+        let redactions: Option<Vec<Redaction>> = span
+            .attribute
+            .get("redactions")
+            .and_then(|value| value.as_str())
+            .map(|categories| {
+                categories
+                    .split(',')
+                    .filter(|category| !category.is_empty())
+                    .map(|category| Redaction {
+                        path: "response.choices.message.content".to_string(),
+                        r#type: category.to_string(),
+                    })
+                    .collect()
+            });
 
         // Calculate duration in milliseconds
         let duration_ms = if span.finish_time_us > span.start_time_us {
@@ -511,7 +625,7 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
     /// High-level MCP tool that provides an overview of a single run and its spans.
     #[tool(
         name = "get_run_overview",
-        description = "Get high-level overview of a run and its spans"
+        description = "Get high-level overview of a run and its spans, with its parent and child runs"
     )]
     pub async fn get_run_overview(
         &self,
@@ -543,6 +657,7 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             sort_by: None,
             sort_order: None,
             labels: None,
+            after: None,
         };
 
         let paginated: PaginatedResult<LangdbSpan> = self
@@ -644,11 +759,8 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
                 crate::types::traces::Operation::ModelCall
             ) {
                 // Extract and deserialize cost
-                if let Some(cost_str) = span.attribute.get("cost").and_then(|v| v.as_str()) {
-                    if let Ok(cost_result) = serde_json::from_str::<CostCalculationResult>(cost_str)
-                    {
-                        total_cost += cost_result.cost;
-                    }
+                if let Some(cost) = span_cost(span) {
+                    total_cost += cost;
                 }
 
                 // Extract and deserialize usage
@@ -691,6 +803,13 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             }
         }
 
+        // Costs are stored in USD and shown in the configured currency
+        let total_cost = if total_cost > 0.0 {
+            Some(self.currency.convert(total_cost).await)
+        } else {
+            None
+        };
+
         let run_overview = RunOverviewRun {
             run_id: params.run_id.clone(),
             status: run_status,
@@ -698,13 +817,14 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             duration_ms,
             label: run_label,
             root_span_id: root_span_id.clone(),
-            total_cost: if total_cost > 0.0 {
-                Some(total_cost)
-            } else {
-                None
-            },
+            currency: total_cost.as_ref().map(|cost| cost.currency.clone()),
+            total_cost: total_cost.map(|cost| cost.amount),
             usage: aggregated_usage,
             total_llm_calls,
+            parent_run_id: spans
+                .iter()
+                .find_map(|s| s.attribute.get("parent_run_id").and_then(|v| v.as_str()))
+                .map(|s| s.to_string()),
         };
 
         // Build span tree entries and derive "kind" per span.
@@ -866,6 +986,11 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             });
         }
 
+        let child_run_ids = self
+            .trace_service
+            .list_child_run_ids(&params.run_id, self.project_slug.as_deref())
+            .map_err(|e| e.to_string())?;
+
         Ok(Json(GetRunOverviewResponse {
             run: run_overview,
             span_tree,
@@ -873,6 +998,7 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
             error_breadcrumbs,
             llm_summaries,
             tool_summaries,
+            child_run_ids,
         }))
     }
 
@@ -943,6 +1069,7 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
                 sort_by: Some("start_time".to_string()),
                 sort_order: Some("desc".to_string()),
                 labels: None,
+                after: None,
             };
 
             let page: PaginatedResult<LangdbSpan> = self
@@ -1014,6 +1141,7 @@ impl<T: TraceService + Send + Sync + 'static> VlloraMcp<T> {
                 sort_by: Some("start_time".to_string()),
                 sort_order: Some("desc".to_string()),
                 labels: None,
+                after: None,
             };
 
             let page: PaginatedResult<LangdbSpan> = self
@@ -1224,3 +1352,193 @@ impl<T: TraceService + Send + Sync + 'static> ServerHandler for VlloraMcp<T> {
         }
     }
 }
+
+/// USD cost recorded on a span, either a [`CostCalculationResult`] or a plain number
+fn span_cost(span: &LangdbSpan) -> Option<f64> {
+    match span.attribute.get("cost")? {
+        JsonValue::Number(cost) => cost.as_f64(),
+        JsonValue::String(cost) => cost.parse::<f64>().ok().or_else(|| {
+            serde_json::from_str::<CostCalculationResult>(cost)
+                .ok()
+                .map(|result| result.cost)
+        }),
+        other => serde_json::from_value::<CostCalculationResult>(other.clone())
+            .ok()
+            .map(|result| result.cost),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::models::trace::DbNewTrace;
+    use crate::metadata::services::trace::TraceServiceImpl;
+    use crate::metadata::test_utils::setup_test_database;
+    use crate::metadata::DatabaseServiceTrait;
+    use crate::pricing::currency::{ConvertedCost, CurrencyConfig};
+    use crate::types::mcp::{McpConfig, McpServerConfig, McpServerType};
+
+    fn run_span(span_id: &str, run_id: &str, parent_run_id: Option<&str>) -> DbNewTrace {
+        let attribute = parent_run_id
+            .map(|parent| HashMap::from([("parent_run_id".to_string(), json!(parent))]))
+            .unwrap_or_default();
+        DbNewTrace::new(
+            format!("trace-{run_id}"),
+            span_id.to_string(),
+            None,
+            None,
+            "run".to_string(),
+            1,
+            2,
+            attribute,
+            Some(run_id.to_string()),
+            Some("default".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_child_runs_are_discoverable_from_parent() {
+        let trace_service = TraceServiceImpl::init(setup_test_database());
+        trace_service
+            .insert_many(vec![
+                run_span("1", "planner", None),
+                run_span("2", "researcher", Some("planner")),
+                run_span("3", "researcher", Some("planner")),
+                run_span("4", "writer", Some("planner")),
+                run_span("5", "fact-checker", Some("writer")),
+            ])
+            .unwrap();
+        let mcp = VlloraMcp::new(trace_service, Some("default".to_string()));
+
+        let overview = |run_id: &str| {
+            mcp.get_run_overview(Parameters(GetRunOverviewParams {
+                run_id: run_id.to_string(),
+            }))
+        };
+
+        let planner = overview("planner").await.unwrap().0;
+        let mut children = planner.child_run_ids.clone();
+        children.sort();
+        assert_eq!(children, vec!["researcher", "writer"]);
+        assert_eq!(planner.run.parent_run_id, None);
+
+        let writer = overview("writer").await.unwrap().0;
+        assert_eq!(writer.run.parent_run_id.as_deref(), Some("planner"));
+        assert_eq!(writer.child_run_ids, vec!["fact-checker"]);
+    }
+
+    #[tokio::test]
+    async fn test_describe_tools_lists_registered_tools_with_schemas() {
+        let db_pool = setup_test_database();
+        let mut config = McpConfig::new();
+        config.add_server(
+            "weather".to_string(),
+            McpServerConfig::new("http://localhost:3000/mcp".to_string(), McpServerType::Http),
+        );
+        let schema = json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city"]
+        });
+        let tools = HashMap::from([(
+            "weather".to_string(),
+            vec![rmcp::model::Tool::new(
+                "get_weather",
+                "Current weather in a city",
+                schema.as_object().unwrap().clone(),
+            )],
+        )]);
+        McpConfigService::new(db_pool.clone())
+            .create_with_tools(MCP_CONFIGS_TENANT.to_string(), &config, &tools)
+            .unwrap();
+
+        let mcp =
+            VlloraMcp::new(TraceServiceImpl::init(db_pool.clone()), None).with_mcp_configs(db_pool);
+        let tools = mcp.describe_tools().await.unwrap().0.tools;
+
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name, "get_weather");
+        assert_eq!(
+            tools[0].description.as_deref(),
+            Some("Current weather in a city")
+        );
+        assert_eq!(tools[0].parameters["type"], json!("object"));
+        assert_eq!(
+            tools[0].parameters["properties"]["city"],
+            json!({"type": "string"})
+        );
+        assert_eq!(tools[0].parameters["required"], json!(["city"]));
+    }
+
+    #[tokio::test]
+    async fn test_costs_are_shown_in_configured_currency() {
+        let trace_service = TraceServiceImpl::init(setup_test_database());
+        let cost = json!({
+            "cost": 2.0,
+            "per_input_token": 0.1,
+            "per_output_token": 0.2,
+            "is_cache_used": false
+        });
+        trace_service
+            .insert_many(vec![DbNewTrace::new(
+                "trace-1".to_string(),
+                "call".to_string(),
+                None,
+                None,
+                "model_call".to_string(),
+                1,
+                2,
+                HashMap::from([("cost".to_string(), json!(cost.to_string()))]),
+                Some("run".to_string()),
+                Some("default".to_string()),
+            )
+            .unwrap()])
+            .unwrap();
+        let currency = CurrencyConverter::from_config(&CurrencyConfig {
+            code: "EUR".to_string(),
+            rates: HashMap::from([("EUR".to_string(), 0.5)]),
+            ..Default::default()
+        });
+        let mcp = VlloraMcp::new(trace_service, Some("default".to_string()))
+            .with_currency(Arc::new(currency));
+        let expected = ConvertedCost {
+            amount: 1.0,
+            currency: "EUR".to_string(),
+        };
+
+        let search = mcp
+            .search_traces(Parameters(
+                serde_json::from_value(json!({
+                    "page": {"limit": 10},
+                    "include": {"metrics": false, "tokens": false, "costs": true}
+                }))
+                .unwrap(),
+            ))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(search.items[0].costs, Some(expected.clone()));
+
+        let call = mcp
+            .get_llm_call(Parameters(GetLlmCallParams {
+                span_id: "call".to_string(),
+                allow_unsafe_text: false,
+                include: None,
+            }))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(call.costs, Some(expected.clone()));
+
+        let overview = mcp
+            .get_run_overview(Parameters(GetRunOverviewParams {
+                run_id: "run".to_string(),
+            }))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(overview.run.total_cost, Some(expected.amount));
+        assert_eq!(overview.run.currency, Some(expected.currency));
+    }
+}
@@ -3,6 +3,7 @@ use rmcp_actix_web::transport::StreamableHttpService;
 use rmcp::transport::streamable_http_server::session::local::LocalSessionManager;
 
 use crate::mcp::server::VlloraMcp;
+use crate::metadata::pool::DbPool;
 use crate::metadata::services::project::ProjectServiceImpl;
 use crate::metadata::{DatabaseService, DatabaseServiceTrait};
 use crate::types::metadata::services::project::ProjectService;
@@ -16,8 +17,9 @@ fn create_http_service<T: TraceService + Clone + Send + Sync + 'static>(
     session_manager: Arc<LocalSessionManager>,
     trace_service: T,
     project_slug: Option<String>,
+    db_pool: DbPool,
 ) -> StreamableHttpService<VlloraMcp<T>> {
-    let vllora_mcp = VlloraMcp::new(trace_service, project_slug);
+    let vllora_mcp = VlloraMcp::new(trace_service, project_slug).with_mcp_configs(db_pool);
     StreamableHttpService::builder()
         .service_factory(Arc::new(move || Ok(vllora_mcp.clone())))
         .session_manager(session_manager) // Session management
@@ -40,7 +42,12 @@ pub fn attach_vllora_mcp<T: TraceService + DatabaseServiceTrait + Clone + Send +
         .ok()
         .map(|p| p.slug);
 
-    let http_service = create_http_service(session_manager, trace_service, project_slug);
+    let http_service = create_http_service(
+        session_manager,
+        trace_service,
+        project_slug,
+        database_service.db_pool().clone(),
+    );
 
     scope.service(http_service.clone().scope())
 }
@@ -3,6 +3,7 @@ use serde::{Deserialize, Serialize};
 
 use std::collections::HashMap;
 
+use crate::pricing::currency::ConvertedCost;
 use crate::types::traces::Operation;
 
 const MAX_LIMIT: i64 = 1000;
@@ -303,6 +304,12 @@ pub struct SearchTracesPage {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Offset into the result set, for classic offset-based pagination.")]
     pub offset: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        description = "`next_cursor` of the previous page. Takes precedence over `offset`."
+    )]
+    pub cursor: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
@@ -314,7 +321,9 @@ pub struct SearchTracesInclude {
     #[schemars(description = "If true, include token usage details, if available for the trace.")]
     pub tokens: bool,
 
-    #[schemars(description = "If true, include cost breakdowns, if available for the trace.")]
+    #[schemars(
+        description = "If true, include the cost in the configured currency, if available for the trace."
+    )]
     pub costs: bool,
 
     #[serde(default)]
@@ -404,8 +413,10 @@ pub struct SearchTraceItem {
     pub tokens: Option<serde_json::Value>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Optional cost information, if available for the trace.")]
-    pub costs: Option<serde_json::Value>,
+    #[schemars(
+        description = "Optional cost of the trace in the configured currency, if available."
+    )]
+    pub costs: Option<ConvertedCost>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Raw span attributes, if include.attributes is true.")]
@@ -581,8 +592,8 @@ pub struct GetLlmCallResponse {
     pub tokens: Option<serde_json::Value>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Cost information, if available.")]
-    pub costs: Option<serde_json::Value>,
+    #[schemars(description = "Cost in the configured currency, if available.")]
+    pub costs: Option<ConvertedCost>,
 
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "List of redactions applied to the data.")]
@@ -633,15 +644,25 @@ pub struct RunOverviewRun {
     pub root_span_id: String,
 
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[schemars(description = "Total cost across all model calls in the run.")]
+    #[schemars(
+        description = "Total cost across all model calls in the run, in the configured currency."
+    )]
     pub total_cost: Option<f64>,
 
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "ISO 4217 code of the currency total_cost is in, e.g. USD.")]
+    pub currency: Option<String>,
+
     #[serde(skip_serializing_if = "Option::is_none")]
     #[schemars(description = "Aggregated usage across all model calls in the run.")]
     pub usage: Option<vllora_llm::types::gateway::GatewayModelUsage>,
 
     #[schemars(description = "Total number of LLM calls in the run.")]
     pub total_llm_calls: u32,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Run that spawned this run, e.g. the delegating agent's run.")]
+    pub parent_run_id: Option<String>,
 }
 
 /// A single span entry in the span tree.
@@ -742,6 +763,11 @@ pub struct GetRunOverviewResponse {
 
     #[schemars(description = "Summaries for tool spans in this run.")]
     pub tool_summaries: Vec<ToolSummary>,
+
+    #[schemars(
+        description = "Runs spawned by this run, e.g. sub-agents it delegated to. Use get_run_overview on them to descend the run hierarchy."
+    )]
+    pub child_run_ids: Vec<String>,
 }
 
 /// ---------------------------------------------------------------------------
@@ -821,6 +847,32 @@ pub struct GetRecentOverviewResponse {
     pub tool_calls: Vec<ToolCallStats>,
 }
 
+/// ---------------------------------------------------------------------------
+/// MCP tool shapes for `describe_tools`
+/// ---------------------------------------------------------------------------
+/// A tool of a registered MCP server, as offered to models.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(description = "A tool of an MCP server registered with the gateway.")]
+pub struct ToolDescription {
+    #[schemars(description = "Name the tool is called with.")]
+    pub name: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "What the tool does.")]
+    pub description: Option<String>,
+
+    #[schemars(description = "JSON schema of the tool parameters.")]
+    pub parameters: serde_json::Value,
+}
+
+/// Response for the describe_tools MCP tool.
+#[derive(Debug, Clone, Serialize, Deserialize, schemars::JsonSchema)]
+#[schemars(description = "Tools of the MCP servers registered with the gateway.")]
+pub struct DescribeToolsResponse {
+    #[schemars(description = "Registered tools, sorted by name.")]
+    pub tools: Vec<ToolDescription>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
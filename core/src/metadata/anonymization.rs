@@ -0,0 +1,288 @@
+use opentelemetry::KeyValue;
+use opentelemetry_sdk::error::OTelSdkResult;
+use opentelemetry_sdk::trace::{SpanData, SpanExporter};
+use opentelemetry_sdk::Resource;
+use ring::hmac;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use thiserror::Error;
+
+const PSEUDONYM_PREFIX: &str = "anon:";
+
+#[derive(Debug, Error)]
+pub enum TraceAnonymizationError {
+    #[error("Trace anonymization key must not be empty")]
+    EmptyKey,
+}
+
+/// Pseudonymizes user identifiers before spans are stored or exported. Each configured field is
+/// replaced with a keyed HMAC-SHA256 of its value, so the same identifier always maps to
+/// the same pseudonym and spans stay joinable by user without the raw value being kept.
+/// Fields are dotted paths into span attributes; attributes holding JSON encoded strings,
+/// like `request`, are searched as well. The key can come from the environment through
+/// config templating, e.g. `key: "{{ TRACE_ANONYMIZATION_KEY }}"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceAnonymizationConfig {
+    pub key: Option<String>,
+    pub fields: Vec<String>,
+}
+
+impl Default for TraceAnonymizationConfig {
+    fn default() -> Self {
+        Self {
+            key: None,
+            fields: vec![
+                "user.id".to_string(),
+                "user.email".to_string(),
+                "request.user".to_string(),
+                "request.extra.user.id".to_string(),
+                "request.extra.user.email".to_string(),
+            ],
+        }
+    }
+}
+
+pub struct TraceAnonymizer {
+    key: hmac::Key,
+    fields: Vec<Vec<String>>,
+}
+
+impl TraceAnonymizer {
+    /// Returns `None` when no key is configured
+    pub fn from_config(
+        config: &TraceAnonymizationConfig,
+    ) -> Result<Option<Self>, TraceAnonymizationError> {
+        let Some(key) = config.key.as_deref() else {
+            return Ok(None);
+        };
+        if key.trim().is_empty() {
+            return Err(TraceAnonymizationError::EmptyKey);
+        }
+
+        Ok(Some(Self {
+            key: hmac::Key::new(hmac::HMAC_SHA256, key.trim().as_bytes()),
+            fields: config
+                .fields
+                .iter()
+                .map(|field| field.split('.').map(str::to_string).collect())
+                .collect(),
+        }))
+    }
+
+    /// Replaces every configured identifier found in the attributes with its pseudonym
+    pub fn anonymize_attributes(&self, attributes: &mut HashMap<String, Value>) {
+        for path in &self.fields {
+            let Some((name, rest)) = path.split_first() else {
+                continue;
+            };
+            if let Some(value) = attributes.get_mut(name) {
+                self.anonymize_path(value, rest);
+            }
+        }
+    }
+
+    /// Replaces every configured identifier found in the attributes of an exported span
+    pub fn anonymize_span_attributes(&self, attributes: &mut [KeyValue]) {
+        for attribute in attributes {
+            let mut value = match &attribute.value {
+                opentelemetry::Value::String(value) => Value::String(value.to_string()),
+                opentelemetry::Value::I64(value) => Value::from(*value),
+                _ => continue,
+            };
+            let before = value.clone();
+            for path in &self.fields {
+                if let Some((name, rest)) = path.split_first() {
+                    if name == attribute.key.as_str() {
+                        self.anonymize_path(&mut value, rest);
+                    }
+                }
+            }
+            match value {
+                Value::String(value) if value != before => attribute.value = value.into(),
+                _ => {}
+            }
+        }
+    }
+
+    /// Keyed hash of an identifier, stable for a given key
+    pub fn pseudonym(&self, value: &str) -> String {
+        let tag = hmac::sign(&self.key, value.as_bytes());
+        let mut pseudonym = PSEUDONYM_PREFIX.to_string();
+        for byte in &tag.as_ref()[..16] {
+            let _ = write!(pseudonym, "{byte:02x}");
+        }
+        pseudonym
+    }
+
+    fn anonymize_path(&self, value: &mut Value, path: &[String]) {
+        let Some((segment, rest)) = path.split_first() else {
+            self.anonymize_value(value);
+            return;
+        };
+
+        match value {
+            Value::Object(map) => {
+                if let Some(value) = map.get_mut(segment) {
+                    self.anonymize_path(value, rest);
+                }
+            }
+            // Attributes such as `request` are recorded as JSON encoded strings
+            Value::String(encoded) => {
+                if let Ok(mut decoded @ Value::Object(_)) = serde_json::from_str::<Value>(encoded) {
+                    self.anonymize_path(&mut decoded, path);
+                    *encoded = decoded.to_string();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn anonymize_value(&self, value: &mut Value) {
+        let identifier = match value {
+            Value::String(s) if s.starts_with(PSEUDONYM_PREFIX) => return,
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => return,
+        };
+        *value = Value::String(self.pseudonym(&identifier));
+    }
+}
+
+static TRACE_ANONYMIZER: OnceLock<Option<Arc<TraceAnonymizer>>> = OnceLock::new();
+
+/// Sets the process-wide trace anonymization. Only the first call takes effect.
+pub fn init_trace_anonymization(
+    config: &TraceAnonymizationConfig,
+) -> Result<(), TraceAnonymizationError> {
+    let anonymizer = TraceAnonymizer::from_config(config)?.map(Arc::new);
+    let _ = TRACE_ANONYMIZER.set(anonymizer);
+    Ok(())
+}
+
+/// Process-wide trace anonymization, if configured
+pub fn trace_anonymizer() -> Option<Arc<TraceAnonymizer>> {
+    TRACE_ANONYMIZER.get().cloned().flatten()
+}
+
+/// Pseudonymizes user identifiers, see [`TraceAnonymizer`], before spans reach `exporter`.
+/// The anonymizer is looked up on every export, it is set up after tracing.
+#[derive(Debug)]
+pub struct AnonymizingSpanExporter<E> {
+    exporter: E,
+}
+
+impl<E> AnonymizingSpanExporter<E> {
+    pub fn new(exporter: E) -> Self {
+        Self { exporter }
+    }
+}
+
+impl<E: SpanExporter> SpanExporter for AnonymizingSpanExporter<E> {
+    async fn export(&self, mut batch: Vec<SpanData>) -> OTelSdkResult {
+        if let Some(anonymizer) = trace_anonymizer() {
+            for span in &mut batch {
+                anonymizer.anonymize_span_attributes(&mut span.attributes);
+            }
+        }
+        self.exporter.export(batch).await
+    }
+
+    fn shutdown_with_timeout(&mut self, timeout: Duration) -> OTelSdkResult {
+        self.exporter.shutdown_with_timeout(timeout)
+    }
+
+    fn force_flush(&mut self) -> OTelSdkResult {
+        self.exporter.force_flush()
+    }
+
+    fn set_resource(&mut self, resource: &Resource) {
+        self.exporter.set_resource(resource)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn anonymizer(key: &str) -> TraceAnonymizer {
+        TraceAnonymizer::from_config(&TraceAnonymizationConfig {
+            key: Some(key.to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_configured_fields_are_pseudonymized() {
+        let anonymizer = anonymizer("secret");
+        let request = json!({
+            "model": "openai/gpt-4o-mini",
+            "user": "user-1",
+            "extra": {"user": {"id": "user-1", "email": "a@example.com", "tiers": ["free"]}},
+        });
+        let mut attributes: HashMap<String, Value> = serde_json::from_value(json!({
+            "request": request.to_string(),
+            "model_name": "openai/gpt-4o-mini",
+        }))
+        .unwrap();
+
+        anonymizer.anonymize_attributes(&mut attributes);
+        let request: Value = serde_json::from_str(attributes["request"].as_str().unwrap()).unwrap();
+        let pseudonym = anonymizer.pseudonym("user-1");
+        assert!(pseudonym.starts_with(PSEUDONYM_PREFIX));
+        assert_eq!(request["user"], json!(pseudonym));
+        assert_eq!(request["extra"]["user"]["id"], json!(pseudonym));
+        assert_eq!(
+            request["extra"]["user"]["email"],
+            json!(anonymizer.pseudonym("a@example.com"))
+        );
+        assert_eq!(request["extra"]["user"]["tiers"], json!(["free"]));
+        assert_eq!(attributes["model_name"], json!("openai/gpt-4o-mini"));
+
+        // Already pseudonymized values are left alone
+        let before = attributes.clone();
+        anonymizer.anonymize_attributes(&mut attributes);
+        assert_eq!(attributes, before);
+    }
+
+    #[test]
+    fn test_exported_span_attributes_are_pseudonymized() {
+        let anonymizer = anonymizer("secret");
+        let user = json!({"id": "user-1", "email": "a@example.com", "name": "Alice"});
+        let mut attributes = vec![
+            KeyValue::new("user", user.to_string()),
+            KeyValue::new("model_name", "openai/gpt-4o-mini"),
+        ];
+
+        anonymizer.anonymize_span_attributes(&mut attributes);
+        let user: Value = serde_json::from_str(&attributes[0].value.as_str()).unwrap();
+        assert_eq!(user["id"], json!(anonymizer.pseudonym("user-1")));
+        assert_eq!(user["email"], json!(anonymizer.pseudonym("a@example.com")));
+        assert_eq!(user["name"], json!("Alice"));
+        assert_eq!(attributes[1].value.as_str(), "openai/gpt-4o-mini");
+    }
+
+    #[test]
+    fn test_pseudonyms_depend_on_key() {
+        assert_eq!(
+            anonymizer("k1").pseudonym("user-1"),
+            anonymizer("k1").pseudonym("user-1")
+        );
+        assert_ne!(
+            anonymizer("k1").pseudonym("user-1"),
+            anonymizer("k2").pseudonym("user-1")
+        );
+        assert!(
+            TraceAnonymizer::from_config(&TraceAnonymizationConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
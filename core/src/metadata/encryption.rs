@@ -0,0 +1,259 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+use thiserror::Error;
+
+/// Span attributes that may carry prompt or completion content
+pub const SENSITIVE_ATTRIBUTES: [&str; 4] = ["input", "output", "request", "response"];
+
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+
+#[derive(Debug, Error)]
+pub enum TraceEncryptionError {
+    #[error("Active trace encryption key {0} is not configured")]
+    MissingActiveKey(String),
+    #[error("Trace encryption key {0} must be 32 base64 encoded bytes")]
+    InvalidKey(String),
+    #[error("Unknown trace encryption key {0}")]
+    UnknownKey(String),
+    #[error("Malformed encrypted attribute")]
+    Malformed,
+    #[error("Failed to encrypt attribute")]
+    Encrypt,
+    #[error("Failed to decrypt attribute with key {0}")]
+    Decrypt(String),
+    #[error(transparent)]
+    Serde(#[from] serde_json::Error),
+}
+
+/// Encryption at rest for sensitive span attributes. Keys are base64 encoded 32 byte
+/// AES-256-GCM keys by id; new spans are written with `active_key` while every listed key
+/// stays available for reading, so keys can be rotated without rewriting stored spans.
+/// Keys can come from the environment through config templating, e.g. `k1: "{{ TRACE_KEY }}"`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceEncryptionConfig {
+    pub active_key: Option<String>,
+    pub keys: HashMap<String, String>,
+}
+
+pub struct TraceEncryption {
+    active_key: String,
+    keys: HashMap<String, LessSafeKey>,
+    rng: SystemRandom,
+}
+
+impl TraceEncryption {
+    /// Returns `None` when no active key is configured
+    pub fn from_config(
+        config: &TraceEncryptionConfig,
+    ) -> Result<Option<Self>, TraceEncryptionError> {
+        let Some(active_key) = config.active_key.clone() else {
+            return Ok(None);
+        };
+        if !config.keys.contains_key(&active_key) {
+            return Err(TraceEncryptionError::MissingActiveKey(active_key));
+        }
+
+        let keys = config
+            .keys
+            .iter()
+            .map(|(id, key)| {
+                let bytes = STANDARD
+                    .decode(key.trim())
+                    .map_err(|_| TraceEncryptionError::InvalidKey(id.clone()))?;
+                let key = UnboundKey::new(&AES_256_GCM, &bytes)
+                    .map_err(|_| TraceEncryptionError::InvalidKey(id.clone()))?;
+                Ok((id.clone(), LessSafeKey::new(key)))
+            })
+            .collect::<Result<HashMap<_, _>, TraceEncryptionError>>()?;
+
+        Ok(Some(Self {
+            active_key,
+            keys,
+            rng: SystemRandom::new(),
+        }))
+    }
+
+    /// Replaces each sensitive attribute with its ciphertext, tagged with the id of the key used
+    pub fn encrypt_attributes(
+        &self,
+        attributes: &mut HashMap<String, Value>,
+    ) -> Result<(), TraceEncryptionError> {
+        for name in SENSITIVE_ATTRIBUTES {
+            if let Some(value) = attributes.get_mut(name) {
+                if !value.is_null() && !is_encrypted(value) {
+                    *value = Value::String(self.encrypt(value)?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Restores sensitive attributes encrypted with any configured key. Values that were
+    /// never encrypted are left as they are.
+    pub fn decrypt_attributes(
+        &self,
+        attributes: &mut HashMap<String, Value>,
+    ) -> Result<(), TraceEncryptionError> {
+        for name in SENSITIVE_ATTRIBUTES {
+            if let Some(value) = attributes.get_mut(name) {
+                if let Some(encrypted) = value
+                    .as_str()
+                    .and_then(|v| v.strip_prefix(ENCRYPTED_PREFIX))
+                {
+                    *value = self.decrypt(encrypted)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Decrypts a JSON encoded attribute map, as stored in the `traces.attribute` column
+    pub fn decrypt_attribute_json(&self, attribute: &str) -> Result<String, TraceEncryptionError> {
+        if !attribute.contains(ENCRYPTED_PREFIX) {
+            return Ok(attribute.to_string());
+        }
+        let mut attributes: HashMap<String, Value> = serde_json::from_str(attribute)?;
+        self.decrypt_attributes(&mut attributes)?;
+        Ok(serde_json::to_string(&attributes)?)
+    }
+
+    fn encrypt(&self, value: &Value) -> Result<String, TraceEncryptionError> {
+        let key = self
+            .keys
+            .get(&self.active_key)
+            .ok_or_else(|| TraceEncryptionError::UnknownKey(self.active_key.clone()))?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng
+            .fill(&mut nonce)
+            .map_err(|_| TraceEncryptionError::Encrypt)?;
+
+        let mut data = serde_json::to_vec(value)?;
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(self.active_key.as_bytes()),
+            &mut data,
+        )
+        .map_err(|_| TraceEncryptionError::Encrypt)?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend(data);
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{}:{}",
+            self.active_key,
+            STANDARD.encode(payload)
+        ))
+    }
+
+    fn decrypt(&self, encrypted: &str) -> Result<Value, TraceEncryptionError> {
+        let (key_id, payload) = encrypted
+            .rsplit_once(':')
+            .ok_or(TraceEncryptionError::Malformed)?;
+        let key = self
+            .keys
+            .get(key_id)
+            .ok_or_else(|| TraceEncryptionError::UnknownKey(key_id.to_string()))?;
+
+        let payload = STANDARD
+            .decode(payload)
+            .map_err(|_| TraceEncryptionError::Malformed)?;
+        if payload.len() < NONCE_LEN {
+            return Err(TraceEncryptionError::Malformed);
+        }
+        let (nonce, data) = payload.split_at(NONCE_LEN);
+        let nonce =
+            Nonce::try_assume_unique_for_key(nonce).map_err(|_| TraceEncryptionError::Malformed)?;
+
+        let mut data = data.to_vec();
+        let plaintext = key
+            .open_in_place(nonce, Aad::from(key_id.as_bytes()), &mut data)
+            .map_err(|_| TraceEncryptionError::Decrypt(key_id.to_string()))?;
+        Ok(serde_json::from_slice(plaintext)?)
+    }
+}
+
+fn is_encrypted(value: &Value) -> bool {
+    value
+        .as_str()
+        .is_some_and(|v| v.starts_with(ENCRYPTED_PREFIX))
+}
+
+static TRACE_ENCRYPTION: OnceLock<Option<Arc<TraceEncryption>>> = OnceLock::new();
+
+/// Sets the process-wide trace encryption. Only the first call takes effect.
+pub fn init_trace_encryption(config: &TraceEncryptionConfig) -> Result<(), TraceEncryptionError> {
+    let encryption = TraceEncryption::from_config(config)?.map(Arc::new);
+    let _ = TRACE_ENCRYPTION.set(encryption);
+    Ok(())
+}
+
+/// Process-wide trace encryption, if configured
+pub fn trace_encryption() -> Option<Arc<TraceEncryption>> {
+    TRACE_ENCRYPTION.get().cloned().flatten()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn config(active_key: &str, keys: &[(&str, u8)]) -> TraceEncryptionConfig {
+        TraceEncryptionConfig {
+            active_key: Some(active_key.to_string()),
+            keys: keys
+                .iter()
+                .map(|(id, byte)| (id.to_string(), STANDARD.encode([*byte; 32])))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_only_sensitive_attributes_are_encrypted() {
+        let encryption = TraceEncryption::from_config(&config("k1", &[("k1", 1)]))
+            .unwrap()
+            .unwrap();
+        let original: HashMap<String, Value> = serde_json::from_value(json!({
+            "input": "{\"messages\":[]}",
+            "response": {"content": "secret"},
+            "model_name": "gpt-4o-mini",
+        }))
+        .unwrap();
+
+        let mut attributes = original.clone();
+        encryption.encrypt_attributes(&mut attributes).unwrap();
+        assert!(is_encrypted(&attributes["input"]));
+        assert!(is_encrypted(&attributes["response"]));
+        assert_eq!(attributes["model_name"], json!("gpt-4o-mini"));
+
+        encryption.decrypt_attributes(&mut attributes).unwrap();
+        assert_eq!(attributes, original);
+    }
+
+    #[test]
+    fn test_invalid_keys_are_rejected() {
+        let mut invalid = config("k1", &[("k1", 1)]);
+        invalid
+            .keys
+            .insert("k1".to_string(), STANDARD.encode([1u8; 16]));
+        assert!(matches!(
+            TraceEncryption::from_config(&invalid),
+            Err(TraceEncryptionError::InvalidKey(_))
+        ));
+        assert!(matches!(
+            TraceEncryption::from_config(&config("k2", &[("k1", 1)])),
+            Err(TraceEncryptionError::MissingActiveKey(_))
+        ));
+        assert!(
+            TraceEncryption::from_config(&TraceEncryptionConfig::default())
+                .unwrap()
+                .is_none()
+        );
+    }
+}
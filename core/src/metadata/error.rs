@@ -20,6 +20,9 @@ pub enum DatabaseError {
 
     #[error("Invalid UUID: {0:?}")]
     InvalidUuid(#[from] uuid::Error),
+
+    #[error("Trace encryption error: {0}")]
+    EncryptionError(#[from] crate::metadata::encryption::TraceEncryptionError),
 }
 
 impl ResponseError for DatabaseError {
@@ -1,3 +1,5 @@
+pub mod anonymization;
+pub mod encryption;
 pub mod error;
 pub mod models;
 pub mod pool;
@@ -4,6 +4,7 @@ pub mod model;
 pub mod project;
 pub mod provider;
 pub mod provider_credential;
+pub mod routing_audit;
 pub mod run;
 pub mod session;
 pub mod trace;
@@ -215,6 +215,9 @@ impl DbModel {
             per_cached_input_token: val.cached_input_token_price.map(|p| p as f64),
             per_cached_input_write_token: val.cached_input_write_token_price.map(|p| p as f64),
             valid_from: None,
+            per_batch_input_token: None,
+            per_batch_output_token: None,
+            per_service_tier: None,
         });
 
         ModelMetadata {
@@ -0,0 +1,35 @@
+use crate::metadata::schema::routing_audit;
+use diesel::{Insertable, Queryable, Selectable};
+use serde::{Deserialize, Serialize};
+
+#[derive(Queryable, Selectable, PartialEq, Debug, Clone, Serialize, Deserialize)]
+#[serde(crate = "serde")]
+#[diesel(table_name = routing_audit)]
+pub struct DbRoutingAudit {
+    pub id: String,
+    pub timestamp_us: i64,
+    pub project_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub router_name: String,
+    pub strategy: String,
+    pub matched_route: Option<String>,
+    pub targets: String,      // JSON stored as text
+    pub interceptors: String, // JSON stored as text
+    pub final_model: Option<String>,
+}
+
+#[derive(Insertable, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(crate = "serde")]
+#[diesel(table_name = routing_audit)]
+pub struct DbNewRoutingAudit {
+    pub id: String,
+    pub timestamp_us: i64,
+    pub project_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub router_name: String,
+    pub strategy: String,
+    pub matched_route: Option<String>,
+    pub targets: String,
+    pub interceptors: String,
+    pub final_model: Option<String>,
+}
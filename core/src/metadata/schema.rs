@@ -97,6 +97,21 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    routing_audit (id) {
+        id -> Text,
+        timestamp_us -> BigInt,
+        project_id -> Nullable<Text>,
+        trace_id -> Nullable<Text>,
+        router_name -> Text,
+        strategy -> Text,
+        matched_route -> Nullable<Text>,
+        targets -> Text,
+        interceptors -> Text,
+        final_model -> Nullable<Text>,
+    }
+}
+
 diesel::table! {
     traces (trace_id, span_id) {
         trace_id -> Text,
@@ -151,6 +166,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     projects,
     provider_credentials,
     providers,
+    routing_audit,
     sessions,
     traces,
 );
@@ -5,6 +5,7 @@ pub mod model;
 pub mod project;
 pub mod provider;
 pub mod provider_credential;
+pub mod routing_audit;
 pub mod run;
 pub mod thread;
 pub mod trace;
@@ -53,8 +53,10 @@ impl ModelService for ModelServiceImpl {
         match project_id {
             Some(pid) => {
                 let project_id_str = pid.to_string();
+                // Project models shadow global ones with the same name
                 Ok(DbModel::for_project(project_id_str)
                     .filter(crate::metadata::schema::models::model_name.eq(model_name))
+                    .order(crate::metadata::schema::models::project_id.is_null())
                     .load(&mut conn)?)
             }
             None => Ok(DbModel::global_only()
@@ -77,6 +79,7 @@ impl ModelService for ModelServiceImpl {
                 DbModel::for_project(project_id_str)
                     .filter(lower(crate::metadata::schema::models::model_name).eq(model_name))
                     .filter(lower(crate::metadata::schema::models::provider_name).eq(provider_name))
+                    .order(crate::metadata::schema::models::project_id.is_null())
                     .first(&mut conn)
                     .optional()?
             }
@@ -101,12 +104,18 @@ impl ModelService for ModelServiceImpl {
     fn upsert(&self, model: DbNewModel) -> Result<(), DatabaseError> {
         let mut conn = self.db_pool.get()?;
 
-        // Try to find existing model by model_name and provider_info_id
-        let existing = DbModel::not_deleted()
+        // Try to find existing model by model_name and provider_info_id within the same project
+        let query = DbModel::not_deleted()
             .filter(crate::metadata::schema::models::model_name.eq(&model.model_name))
             .filter(crate::metadata::schema::models::provider_name.eq(&model.provider_name))
-            .first::<DbModel>(&mut conn)
-            .optional()?;
+            .into_boxed();
+        let query = match &model.project_id {
+            Some(project_id) => {
+                query.filter(crate::metadata::schema::models::project_id.eq(project_id))
+            }
+            None => query.filter(crate::metadata::schema::models::project_id.is_null()),
+        };
+        let existing = query.first::<DbModel>(&mut conn).optional()?;
 
         if let Some(existing_model) = existing {
             // Update existing model with current timestamp
@@ -34,7 +34,10 @@ impl ProviderCredentialsService for ProviderCredentialsServiceImpl {
             .into_boxed();
 
         if let Some(project_slug) = project_slug_param {
-            query = query.filter(p::slug.eq(project_slug).or(pc::project_id.is_null()));
+            // The project's own credentials take precedence over global ones
+            query = query
+                .filter(p::slug.eq(project_slug).or(pc::project_id.is_null()))
+                .order(pc::project_id.is_null());
         } else {
             query = query.filter(pc::project_id.is_null());
         }
@@ -498,4 +501,54 @@ mod tests {
         assert_eq!(project_providers[0].name, "openai");
         assert_eq!(project_providers[1].name, "anthropic");
     }
+
+    #[test]
+    fn test_project_credentials_are_isolated() {
+        let service = create_test_provider_service();
+        let project_service = ProjectServiceImpl::new(service.db_pool.clone());
+        let create_project = |name: &str| {
+            project_service
+                .create(
+                    NewProjectDTO {
+                        name: name.to_string(),
+                        description: None,
+                        settings: None,
+                        private_model_prices: None,
+                        usage_limit: None,
+                    },
+                    Uuid::new_v4(),
+                )
+                .unwrap()
+        };
+        let project_a = create_project("Project A");
+        let project_b = create_project("Project B");
+
+        let save = |api_key: &str, project_id: Option<String>| {
+            let dto = NewProviderCredentialsDTO {
+                provider_name: "openai".to_string(),
+                provider_type: "api_key".to_string(),
+                credentials: Credentials::ApiKey(ApiKeyCredentials {
+                    api_key: api_key.to_string(),
+                }),
+                project_id,
+            };
+            service.save_provider(dto.to_db_insert().unwrap()).unwrap();
+        };
+        save("sk-global", None);
+        save("sk-project-a", Some(project_a.id.to_string()));
+
+        let api_key = |project_slug: &str| match service
+            .get_provider_credentials("openai", Some(project_slug))
+            .unwrap()
+            .unwrap()
+            .parse_credentials()
+            .unwrap()
+        {
+            Credentials::ApiKey(creds) => creds.api_key,
+            _ => panic!("Expected ApiKey credentials"),
+        };
+
+        assert_eq!(api_key(&project_a.slug.to_string()), "sk-project-a");
+        assert_eq!(api_key(&project_b.slug.to_string()), "sk-global");
+    }
 }
@@ -0,0 +1,36 @@
+use crate::metadata::error::DatabaseError;
+use crate::metadata::models::routing_audit::{DbNewRoutingAudit, DbRoutingAudit};
+use crate::metadata::pool::DbPool;
+use crate::metadata::schema::routing_audit;
+use crate::metadata::DatabaseServiceTrait;
+use diesel::prelude::*;
+
+#[derive(Clone)]
+pub struct RoutingAuditServiceImpl {
+    db_pool: DbPool,
+}
+
+impl DatabaseServiceTrait for RoutingAuditServiceImpl {
+    fn init(db_pool: DbPool) -> Self {
+        Self { db_pool }
+    }
+}
+
+impl RoutingAuditServiceImpl {
+    pub fn insert(&self, record: DbNewRoutingAudit) -> Result<(), DatabaseError> {
+        let mut conn = self.db_pool.get()?;
+        diesel::insert_into(routing_audit::table)
+            .values(&record)
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
+    pub fn list_by_trace(&self, trace_id: &str) -> Result<Vec<DbRoutingAudit>, DatabaseError> {
+        let mut conn = self.db_pool.get()?;
+        Ok(routing_audit::table
+            .filter(routing_audit::trace_id.eq(trace_id))
+            .order(routing_audit::timestamp_us.asc())
+            .select(DbRoutingAudit::as_select())
+            .load(&mut conn)?)
+    }
+}
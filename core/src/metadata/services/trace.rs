@@ -1,3 +1,5 @@
+use crate::metadata::anonymization::{trace_anonymizer, TraceAnonymizer};
+use crate::metadata::encryption::{trace_encryption, TraceEncryption};
 use crate::metadata::error::DatabaseError;
 use crate::metadata::models::trace::{DbNewTrace, DbTrace};
 use crate::metadata::pool::DbPool;
@@ -9,7 +11,9 @@ use crate::types::metadata::services::trace::BatchGroupSpansQuery;
 use crate::types::metadata::services::trace::BatchGroupSpansResponse;
 use crate::types::metadata::services::trace::GroupIdentifier;
 use crate::types::metadata::services::trace::GroupSpansData;
-use crate::types::metadata::services::trace::{GetGroupSpansQuery, ListTracesQuery, TraceService};
+use crate::types::metadata::services::trace::{
+    GetGroupSpansQuery, ListTracesQuery, TraceCursor, TraceService,
+};
 use crate::types::traces::{LangdbSpan, Operation};
 use diesel::prelude::*;
 use diesel::sql_types::{Nullable, Text};
@@ -19,11 +23,83 @@ use std::sync::Arc;
 #[derive(Clone)]
 pub struct TraceServiceImpl {
     db_pool: DbPool,
+    encryption: Option<Arc<TraceEncryption>>,
+    anonymizer: Option<Arc<TraceAnonymizer>>,
 }
 
 impl DatabaseServiceTrait for TraceServiceImpl {
     fn init(db_pool: DbPool) -> Self {
-        Self { db_pool }
+        Self {
+            db_pool,
+            encryption: trace_encryption(),
+            anonymizer: trace_anonymizer(),
+        }
+    }
+}
+
+impl TraceServiceImpl {
+    pub fn with_encryption(mut self, encryption: Option<Arc<TraceEncryption>>) -> Self {
+        self.encryption = encryption;
+        self
+    }
+
+    pub fn with_anonymizer(mut self, anonymizer: Option<Arc<TraceAnonymizer>>) -> Self {
+        self.anonymizer = anonymizer;
+        self
+    }
+
+    /// Decrypts sensitive attributes so callers always see plaintext. Attributes that can't
+    /// be decrypted, e.g. because their key was removed, are returned as stored.
+    fn decrypt_attribute(&self, attribute: String) -> String {
+        match &self.encryption {
+            Some(encryption) => encryption
+                .decrypt_attribute_json(&attribute)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to decrypt span attributes: {e}");
+                    attribute
+                }),
+            None => attribute,
+        }
+    }
+
+    fn decrypt_traces(&self, traces: Vec<DbTrace>) -> Vec<DbTrace> {
+        if self.encryption.is_none() {
+            return traces;
+        }
+        traces
+            .into_iter()
+            .map(|mut trace| {
+                trace.attribute = self.decrypt_attribute(trace.attribute);
+                trace
+            })
+            .collect()
+    }
+
+    /// Keeps the spans that come after `after` in start time order, ties broken by span id
+    fn filter_after<'a>(
+        db_query: traces::BoxedQuery<'a, diesel::sqlite::Sqlite>,
+        after: &TraceCursor,
+        is_desc: bool,
+    ) -> traces::BoxedQuery<'a, diesel::sqlite::Sqlite> {
+        let start_time_us = after.start_time_us;
+        let span_id = after.span_id.clone();
+        if is_desc {
+            db_query.filter(
+                traces::start_time_us
+                    .lt(start_time_us)
+                    .or(traces::start_time_us
+                        .eq(start_time_us)
+                        .and(traces::span_id.lt(span_id))),
+            )
+        } else {
+            db_query.filter(
+                traces::start_time_us
+                    .gt(start_time_us)
+                    .or(traces::start_time_us
+                        .eq(start_time_us)
+                        .and(traces::span_id.gt(span_id))),
+            )
+        }
     }
 }
 
@@ -133,15 +209,19 @@ impl TraceService for TraceServiceImpl {
         let sort_order = query.sort_order.as_deref().unwrap_or("desc");
         let is_desc = sort_order == "desc";
 
+        if let Some(after) = query.after.as_ref().filter(|_| query.sorts_by_start_time()) {
+            db_query = Self::filter_after(db_query, after, is_desc);
+        }
+
         let results = match (sort_by, is_desc) {
             ("start_time", true) => db_query
-                .order(traces::start_time_us.desc())
+                .order((traces::start_time_us.desc(), traces::span_id.desc()))
                 .limit(query.limit)
                 .offset(query.offset)
                 .load::<DbTrace>(&mut conn)
                 .map_err(DatabaseError::QueryError)?,
             ("start_time", false) => db_query
-                .order(traces::start_time_us.asc())
+                .order((traces::start_time_us.asc(), traces::span_id.asc()))
                 .limit(query.limit)
                 .offset(query.offset)
                 .load::<DbTrace>(&mut conn)
@@ -160,14 +240,14 @@ impl TraceService for TraceServiceImpl {
                 .map_err(DatabaseError::QueryError)?,
             // Default: start_time descending
             _ => db_query
-                .order(traces::start_time_us.desc())
+                .order((traces::start_time_us.desc(), traces::span_id.desc()))
                 .limit(query.limit)
                 .offset(query.offset)
                 .load::<DbTrace>(&mut conn)
                 .map_err(DatabaseError::QueryError)?,
         };
 
-        Ok(results)
+        Ok(self.decrypt_traces(results))
     }
 
     fn list_paginated(
@@ -269,7 +349,7 @@ impl TraceService for TraceServiceImpl {
             .load::<DbTrace>(&mut conn)
             .map_err(DatabaseError::QueryError)?;
 
-        Ok(results)
+        Ok(self.decrypt_traces(results))
     }
 
     fn count(&self, query: ListTracesQuery) -> Result<i64, DatabaseError> {
@@ -372,6 +452,11 @@ impl TraceService for TraceServiceImpl {
             }
         }
 
+        if let Some(after) = query.after.as_ref().filter(|_| query.sorts_by_start_time()) {
+            let is_desc = query.sort_order.as_deref().unwrap_or("desc") == "desc";
+            db_query = Self::filter_after(db_query, after, is_desc);
+        }
+
         let count = db_query
             .count()
             .get_result::<i64>(&mut conn)
@@ -444,7 +529,7 @@ impl TraceService for TraceServiceImpl {
                 result.parent_span_id,
                 result
                     .child_attribute
-                    .map(|attr| serde_json::from_str(&attr).unwrap()),
+                    .map(|attr| serde_json::from_str(&self.decrypt_attribute(attr)).unwrap()),
             );
         }
         Ok(map)
@@ -517,6 +602,7 @@ impl TraceService for TraceServiceImpl {
             .limit(limit)
             .offset(offset)
             .load::<DbTrace>(&mut conn)?;
+        let traces = self.decrypt_traces(traces);
 
         // Get child attributes
         let trace_ids: Vec<String> = traces.iter().map(|t| t.trace_id.clone()).collect();
@@ -664,6 +750,7 @@ impl TraceService for TraceServiceImpl {
                 .offset(offset)
                 .load::<DbTrace>(&mut conn)
                 .unwrap_or_default();
+            let traces = self.decrypt_traces(traces);
 
             // Get child attributes
             let trace_ids: Vec<String> = traces.iter().map(|t| t.trace_id.clone()).collect();
@@ -712,6 +799,36 @@ impl TraceService for TraceServiceImpl {
 
         Ok(BatchGroupSpansResponse { data: result_map })
     }
+
+    fn list_child_run_ids(
+        &self,
+        parent_run_id: &str,
+        project_id: Option<&str>,
+    ) -> Result<Vec<String>, DatabaseError> {
+        let mut conn = self.db_pool.get()?;
+
+        let mut query = traces::table
+            .select(traces::run_id)
+            .filter(traces::run_id.is_not_null())
+            .filter(
+                diesel::dsl::sql::<diesel::sql_types::Bool>(
+                    "json_extract(attribute, '$.parent_run_id') = ",
+                )
+                .bind::<Text, _>(parent_run_id),
+            )
+            .distinct()
+            .into_boxed();
+
+        if let Some(project_id) = project_id {
+            query = query.filter(traces::project_id.eq(project_id));
+        }
+
+        let run_ids = query
+            .load::<Option<String>>(&mut conn)
+            .map_err(DatabaseError::QueryError)?;
+
+        Ok(run_ids.into_iter().flatten().collect())
+    }
 }
 
 impl TraceServiceImpl {
@@ -723,9 +840,21 @@ impl TraceServiceImpl {
         let mut conn = self.db_pool.get()?;
         let mut inserted_count = 0;
 
-        for trace in &trace_list {
+        for mut trace in trace_list {
+            if self.encryption.is_some() || self.anonymizer.is_some() {
+                let mut attributes: HashMap<String, serde_json::Value> =
+                    serde_json::from_str(&trace.attribute)?;
+                // Pseudonymize first so identifiers inside encrypted attributes are covered too
+                if let Some(anonymizer) = &self.anonymizer {
+                    anonymizer.anonymize_attributes(&mut attributes);
+                }
+                if let Some(encryption) = &self.encryption {
+                    encryption.encrypt_attributes(&mut attributes)?;
+                }
+                trace.attribute = serde_json::to_string(&attributes)?;
+            }
             diesel::insert_into(traces::table)
-                .values(trace)
+                .values(&trace)
                 .execute(&mut conn)?;
             inserted_count += 1;
         }
@@ -733,3 +862,295 @@ impl TraceServiceImpl {
         Ok(inserted_count)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::test_utils::setup_test_database;
+
+    fn trace(span_id: &str, run_id: &str, project_slug: &str) -> DbNewTrace {
+        DbNewTrace::new(
+            "trace".to_string(),
+            span_id.to_string(),
+            None,
+            None,
+            "run".to_string(),
+            1,
+            2,
+            HashMap::new(),
+            Some(run_id.to_string()),
+            Some(project_slug.to_string()),
+        )
+        .unwrap()
+    }
+
+    fn list_query(project_slug: &str) -> ListTracesQuery {
+        ListTracesQuery {
+            project_slug: Some(project_slug.to_string()),
+            span_id: None,
+            span_ids: None,
+            run_ids: None,
+            thread_ids: None,
+            operation_names: None,
+            parent_span_ids: None,
+            filter_null_thread: false,
+            filter_null_run: false,
+            filter_null_operation: false,
+            filter_null_parent: false,
+            filter_not_null_thread: false,
+            filter_not_null_run: false,
+            filter_not_null_operation: false,
+            filter_not_null_parent: false,
+            start_time_min: None,
+            start_time_max: None,
+            limit: 10,
+            offset: 0,
+            text_search: None,
+            sort_by: None,
+            sort_order: None,
+            labels: None,
+            after: None,
+        }
+    }
+
+    #[test]
+    fn test_traces_are_scoped_to_project() {
+        let service = TraceServiceImpl::init(setup_test_database());
+        service
+            .insert_many(vec![
+                trace("span-a", "run-a", "project-a"),
+                trace("span-b", "run-b", "project-b"),
+            ])
+            .unwrap();
+
+        let traces = service.list(list_query("project-a")).unwrap();
+        assert_eq!(traces.len(), 1);
+        assert_eq!(traces[0].span_id, "span-a");
+        assert_eq!(service.count(list_query("project-a")).unwrap(), 1);
+
+        let run_span_buffer = Arc::new(RunSpanBuffer::new(std::time::Duration::from_secs(20)));
+        let spans = service
+            .get_by_run_id("run-a", Some("project-b"), 10, 0, run_span_buffer)
+            .unwrap();
+        assert!(spans.is_empty());
+    }
+
+    fn trace_at(span_id: &str, start_time_us: i64) -> DbNewTrace {
+        DbNewTrace::new(
+            "trace".to_string(),
+            span_id.to_string(),
+            None,
+            None,
+            "run".to_string(),
+            start_time_us,
+            start_time_us + 1,
+            HashMap::new(),
+            None,
+            Some("project-a".to_string()),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_cursor_pagination_is_stable_under_inserts() {
+        let service = TraceServiceImpl::init(setup_test_database());
+        service
+            .insert_many(vec![
+                trace_at("span-1", 10),
+                trace_at("span-2", 20),
+                trace_at("span-3", 30),
+                trace_at("span-3b", 30),
+                trace_at("span-4", 40),
+                trace_at("span-5", 50),
+            ])
+            .unwrap();
+
+        let page_query = |cursor: Option<&TraceCursor>| {
+            let query = ListTracesQuery {
+                limit: 2,
+                ..list_query("project-a")
+            };
+            match cursor {
+                Some(cursor) => query.with_cursor(&cursor.encode()).unwrap(),
+                None => query,
+            }
+        };
+
+        let mut seen = vec![];
+        let mut cursor: Option<TraceCursor> = None;
+        loop {
+            let page = service.list(page_query(cursor.as_ref())).unwrap();
+            let Some(last) = page.last() else {
+                break;
+            };
+            cursor = Some(TraceCursor::new(last.start_time_us, last.span_id.clone()));
+            seen.extend(page.into_iter().map(|t| t.span_id));
+
+            if seen.len() == 2 {
+                // Newer spans land before the cursor and would shift an offset by one
+                service
+                    .insert_many(vec![trace_at("span-6", 60), trace_at("span-7", 70)])
+                    .unwrap();
+                assert_eq!(service.count(page_query(cursor.as_ref())).unwrap(), 4);
+            }
+        }
+
+        assert_eq!(
+            seen,
+            vec!["span-5", "span-4", "span-3b", "span-3", "span-2", "span-1"]
+        );
+    }
+
+    #[test]
+    fn test_numeric_cursor_is_an_offset() {
+        let query = list_query("project-a").with_cursor("40").unwrap();
+        assert_eq!(query.offset, 40);
+        assert!(query.after.is_none());
+
+        let cursor = TraceCursor::new(1_700_000_000_000_000, "4bf92f3577b34da6");
+        let query = list_query("project-a")
+            .with_cursor(&cursor.encode())
+            .unwrap();
+        assert_eq!(query.after, Some(cursor));
+
+        assert!(list_query("project-a").with_cursor("not a cursor").is_err());
+    }
+
+    #[test]
+    fn test_sensitive_attributes_round_trip_encrypted() {
+        use crate::metadata::encryption::TraceEncryptionConfig;
+        use base64::Engine;
+
+        let key = |byte: u8| base64::engine::general_purpose::STANDARD.encode([byte; 32]);
+        let encryption = |active_key: &str, keys: &[(&str, u8)]| {
+            let config = TraceEncryptionConfig {
+                active_key: Some(active_key.to_string()),
+                keys: keys
+                    .iter()
+                    .map(|(id, byte)| (id.to_string(), key(*byte)))
+                    .collect(),
+            };
+            TraceEncryption::from_config(&config).unwrap().map(Arc::new)
+        };
+        let span = |span_id: &str, start_time_us: i64| {
+            let attribute = serde_json::from_value(serde_json::json!({
+                "request": {"messages": [{"role": "user", "content": "my secret"}]},
+                "output": "the answer",
+                "model_name": "openai/gpt-4o-mini",
+            }))
+            .unwrap();
+            DbNewTrace::new(
+                "trace".to_string(),
+                span_id.to_string(),
+                None,
+                None,
+                "model_call".to_string(),
+                start_time_us,
+                start_time_us + 1,
+                attribute,
+                None,
+                Some("project-a".to_string()),
+            )
+            .unwrap()
+        };
+
+        let db_pool = setup_test_database();
+        TraceServiceImpl::init(db_pool.clone())
+            .with_encryption(encryption("k1", &[("k1", 1)]))
+            .insert_many(vec![span("span-1", 10)])
+            .unwrap();
+        // Rotating keeps the old key for reading only
+        let service = TraceServiceImpl::init(db_pool.clone())
+            .with_encryption(encryption("k2", &[("k1", 1), ("k2", 2)]));
+        service.insert_many(vec![span("span-2", 20)]).unwrap();
+
+        let stored = TraceServiceImpl::init(db_pool)
+            .with_encryption(None)
+            .list(list_query("project-a"))
+            .unwrap();
+        for trace in &stored {
+            assert!(!trace.attribute.contains("my secret"));
+            assert!(!trace.attribute.contains("the answer"));
+            assert!(trace.attribute.contains("openai/gpt-4o-mini"));
+        }
+        assert!(stored[0].attribute.contains("enc:v1:k2:"));
+        assert!(stored[1].attribute.contains("enc:v1:k1:"));
+
+        let traces = service.list(list_query("project-a")).unwrap();
+        assert_eq!(traces.len(), 2);
+        for trace in traces {
+            let attribute = trace.parse_attribute().unwrap();
+            assert_eq!(
+                attribute["request"]["messages"][0]["content"],
+                serde_json::json!("my secret")
+            );
+            assert_eq!(attribute["output"], serde_json::json!("the answer"));
+        }
+    }
+
+    #[test]
+    fn test_user_ids_are_pseudonymized_in_stored_spans() {
+        use crate::metadata::anonymization::TraceAnonymizationConfig;
+
+        let anonymizer = TraceAnonymizer::from_config(&TraceAnonymizationConfig {
+            key: Some("pseudonym-key".to_string()),
+            ..Default::default()
+        })
+        .unwrap()
+        .map(Arc::new);
+        let span = |span_id: &str, start_time_us: i64, user_id: &str| {
+            let request = serde_json::json!({
+                "model": "openai/gpt-4o-mini",
+                "extra": {"user": {"id": user_id, "email": format!("{user_id}@example.com")}},
+            });
+            let attribute = serde_json::from_value(serde_json::json!({
+                "request": request.to_string(),
+            }))
+            .unwrap();
+            DbNewTrace::new(
+                "trace".to_string(),
+                span_id.to_string(),
+                None,
+                None,
+                "api_invoke".to_string(),
+                start_time_us,
+                start_time_us + 1,
+                attribute,
+                None,
+                Some("project-a".to_string()),
+            )
+            .unwrap()
+        };
+
+        let service = TraceServiceImpl::init(setup_test_database())
+            .with_encryption(None)
+            .with_anonymizer(anonymizer);
+        service
+            .insert_many(vec![
+                span("span-1", 10, "alice"),
+                span("span-2", 20, "alice"),
+                span("span-3", 30, "bob"),
+            ])
+            .unwrap();
+
+        let mut user_ids = service
+            .list(list_query("project-a"))
+            .unwrap()
+            .into_iter()
+            .map(|trace| {
+                assert!(!trace.attribute.contains("alice"));
+                assert!(!trace.attribute.contains("bob"));
+                let attribute = trace.parse_attribute().unwrap();
+                let request: serde_json::Value =
+                    serde_json::from_str(attribute["request"].as_str().unwrap()).unwrap();
+                request["extra"]["user"]["id"].as_str().unwrap().to_string()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(user_ids.len(), 3);
+        assert!(user_ids.iter().all(|id| id.starts_with("anon:")));
+        // Both alice spans share one pseudonym, bob gets another
+        user_ids.sort();
+        user_ids.dedup();
+        assert_eq!(user_ids.len(), 2);
+    }
+}
@@ -202,6 +202,9 @@ impl AzureModelProvider {
             per_cached_input_token: None,
             per_cached_input_write_token: None,
             valid_from: None,
+            per_batch_input_token: None,
+            per_batch_output_token: None,
+            per_service_tier: None,
         })
     }
 
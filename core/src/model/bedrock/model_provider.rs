@@ -6,6 +6,7 @@ use aws_sdk_bedrock::Client as BedrockClient;
 use vllora_llm::client::error::ModelError;
 use vllora_llm::error::LLMError;
 use vllora_llm::provider::bedrock::get_sdk_config;
+use vllora_llm::provider::bedrock::inference_profile::region_prefix;
 use vllora_llm::types::credentials::BedrockCredentials;
 use vllora_llm::types::models::InferenceProvider;
 use vllora_llm::types::models::Limits;
@@ -47,20 +48,12 @@ impl ModelProviderInstance for BedrockModelProvider {
 
         let mut models = Vec::new();
 
-        let mut region_prefix = "";
-
-        if let Some(region) = self.client.config().region() {
-            let region = region.to_string();
-            region_prefix = if region.starts_with("us") {
-                "us."
-            } else if region.starts_with("ap") {
-                "apac."
-            } else if region.starts_with("eu") {
-                "eu."
-            } else {
-                ""
-            };
-        }
+        let region_prefix = self
+            .client
+            .config()
+            .region()
+            .and_then(|region| region_prefix(&region.to_string()))
+            .unwrap_or("");
 
         let prices = pricing::fetch_pricing().await?;
 
@@ -195,6 +188,9 @@ impl ModelProviderInstance for BedrockModelProvider {
                         per_cached_input_token: None,
                         per_cached_input_write_token: None,
                         valid_from: None,
+                        per_batch_input_token: None,
+                        per_batch_output_token: None,
+                        per_service_tier: None,
                     }),
                     input_formats,
                     output_formats,
@@ -139,6 +139,7 @@ impl BedrockEmbeddings {
                     finish_reason: ModelFinishReason::Stop,
                     tool_calls: vec![],
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             )))
             .await;
@@ -98,6 +98,7 @@ impl GeminiEmbeddings {
                     finish_reason: ModelFinishReason::Stop,
                     tool_calls: vec![],
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             )))
             .await;
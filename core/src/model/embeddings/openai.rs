@@ -130,6 +130,7 @@ impl<C: Config> OpenAIEmbeddings<C> {
                     finish_reason: ModelFinishReason::Stop,
                     tool_calls: vec![],
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             )))
             .map_err(|e| LLMError::CustomError(e.to_string()))?;
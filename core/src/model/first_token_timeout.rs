@@ -0,0 +1,167 @@
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+use vllora_llm::client::completions::response_stream::ResultStream;
+use vllora_llm::error::{LLMError, LLMResult};
+use vllora_llm::types::gateway::ChatCompletionChunk;
+
+/// Time a streamed model call has to produce its first token, counted from the request to
+/// the provider. Calls that miss it fail with [`LLMError::FirstTokenTimeout`], recorded as
+/// the `error` of the model call span, however long the rest of the generation may take.
+/// Disabled unless `timeout_ms` is set.
+///
+/// ```yaml
+/// first_token_timeout:
+///   timeout_ms: 10000
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FirstTokenTimeoutConfig {
+    pub timeout_ms: Option<u64>,
+}
+
+impl FirstTokenTimeoutConfig {
+    /// Starts the stream with `start` and fails it when no token arrives in time
+    pub async fn apply<F>(&self, start: F, span: tracing::Span) -> LLMResult<ResultStream>
+    where
+        F: Future<Output = LLMResult<ResultStream>>,
+    {
+        let Some(timeout) = self.timeout_ms.map(Duration::from_millis) else {
+            return start.await;
+        };
+        let deadline = Instant::now() + timeout;
+        match tokio::time::timeout_at(deadline, start).await {
+            Ok(stream) => {
+                stream.map(|stream| first_token_deadline(stream, deadline, timeout, span))
+            }
+            Err(_) => Err(timed_out(timeout, &span)),
+        }
+    }
+}
+
+fn timed_out(timeout: Duration, span: &tracing::Span) -> LLMError {
+    let error = LLMError::FirstTokenTimeout(timeout);
+    span.record("error", error.to_string());
+    error
+}
+
+/// Chunks carrying output, as opposed to the role-only chunk some providers start with
+fn has_token(chunk: &ChatCompletionChunk) -> bool {
+    chunk.choices.iter().any(|choice| {
+        choice.delta.content.as_ref().is_some_and(|c| !c.is_empty())
+            || choice
+                .delta
+                .reasoning_content
+                .as_ref()
+                .is_some_and(|r| !r.is_empty())
+            || choice.delta.tool_calls.is_some()
+            || choice.finish_reason.is_some()
+    })
+}
+
+struct DeadlineStream {
+    inner: ResultStream,
+    deadline: Option<Instant>,
+    timeout: Duration,
+    span: tracing::Span,
+    finished: bool,
+}
+
+/// Passes `inner` through, ending it with [`LLMError::FirstTokenTimeout`] when no token
+/// arrived by `deadline`. The inner stream is dropped on timeout, which stops the provider.
+pub fn first_token_deadline(
+    inner: ResultStream,
+    deadline: Instant,
+    timeout: Duration,
+    span: tracing::Span,
+) -> ResultStream {
+    let state = DeadlineStream {
+        inner,
+        deadline: Some(deadline),
+        timeout,
+        span,
+        finished: false,
+    };
+
+    ResultStream::new(Box::pin(stream::unfold(state, |mut state| async move {
+        if state.finished {
+            return None;
+        }
+        let next = match state.deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, state.inner.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    state.finished = true;
+                    return Some((Err(timed_out(state.timeout, &state.span)), state));
+                }
+            },
+            None => state.inner.next().await,
+        };
+        if let Some(Ok(chunk)) = &next {
+            if has_token(chunk) {
+                state.deadline = None;
+            }
+        }
+        next.map(|item| (item, state))
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chunk(delta: serde_json::Value) -> ChatCompletionChunk {
+        serde_json::from_value(json!({
+            "id": "chunk",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4o-mini",
+            "choices": [{"index": 0, "delta": delta, "finish_reason": null, "logprobs": null}]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_stream_without_first_token_times_out() {
+        let config = FirstTokenTimeoutConfig {
+            timeout_ms: Some(50),
+        };
+
+        // The provider only sends the role, then never a token
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        tx.send(Ok(chunk(json!({"role": "assistant", "content": ""}))))
+            .await
+            .unwrap();
+        let mut stream = config
+            .apply(
+                async { Ok(ResultStream::create(rx)) },
+                tracing::Span::none(),
+            )
+            .await
+            .unwrap();
+
+        assert!(stream.next().await.unwrap().is_ok());
+        let error = stream.next().await.unwrap().unwrap_err();
+        assert!(matches!(error, LLMError::FirstTokenTimeout(timeout) if timeout.as_millis() == 50));
+        assert!(stream.next().await.is_none());
+        drop(tx);
+
+        // Once the first token arrived, the rest of the generation may take longer
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        let mut stream = config
+            .apply(
+                async { Ok(ResultStream::create(rx)) },
+                tracing::Span::none(),
+            )
+            .await
+            .unwrap();
+        tx.send(Ok(chunk(json!({"content": "Hi"})))).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        tx.send(Ok(chunk(json!({"content": "!"})))).await.unwrap();
+        assert!(stream.next().await.unwrap().is_ok());
+    }
+}
@@ -176,6 +176,9 @@ impl ModelProviderInstance for GoogleVertexModelProvider {
                             per_cached_input_token: None,
                             per_cached_input_write_token: None,
                             valid_from: None,
+                            per_batch_input_token: None,
+                            per_batch_output_token: None,
+                            per_service_tier: None,
                         }),
                         input_formats,
                         output_formats,
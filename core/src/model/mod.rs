@@ -2,7 +2,10 @@ use crate::executor::context::ExecutorContext;
 use crate::handler::find_model_by_full_name;
 use crate::metadata::pool::DbPool;
 use crate::model::cached::CachedModel;
+use crate::model::ranking::{rank_models, BenchmarkRankingSource, RankingSource};
+use crate::types::guardrails::defaults::merge_guards;
 use crate::types::guardrails::service::GuardrailsEvaluator;
+use crate::types::guardrails::streaming::guard_stream;
 use crate::types::guardrails::{GuardError, GuardResult, GuardStage};
 use crate::types::metadata::services::model::ModelService;
 use crate::GatewayApiError;
@@ -11,9 +14,11 @@ use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::sync::Arc;
-use tokio::sync::mpsc::{self, channel};
+use tokio::sync::mpsc;
 use tracing::Instrument;
 use valuable::Valuable;
+use vllora_llm::client::completions::interim_usage::InterimUsageTracker;
+use vllora_llm::client::completions::language::detect_language;
 use vllora_llm::client::completions::response_stream::ResultStream;
 use vllora_llm::client::completions::CompletionsClient;
 use vllora_llm::client::error::ModelError;
@@ -25,8 +30,8 @@ use vllora_llm::types::engine::{CompletionEngineParamsBuilder, CompletionModelDe
 use vllora_llm::types::events::CustomEventType;
 use vllora_llm::types::gateway::{
     ChatCompletionContent, ChatCompletionMessage, ChatCompletionMessageWithFinishReason,
-    ChatCompletionRequest, ContentType, Extra, GatewayModelUsage, GuardOrName, GuardWithParameters,
-    Usage,
+    ChatCompletionRequest, ContentType, CostBreakdown, Extra, GatewayModelUsage, GuardOrName,
+    GuardWithParameters, Usage,
 };
 use vllora_llm::types::instance::init_model_instance;
 use vllora_llm::types::message::Message;
@@ -43,9 +48,15 @@ pub mod azure;
 pub mod bedrock;
 pub mod cached;
 pub mod embeddings;
+pub mod first_token_timeout;
 pub mod google_vertex;
 pub mod image_generation;
+pub mod openapi_tools;
+pub mod openrouter;
+pub mod ranking;
 pub mod responses;
+pub mod stream_channel;
+pub mod stream_coalescing;
 pub mod tools;
 
 #[async_trait::async_trait]
@@ -190,6 +201,38 @@ impl TracedModel {
         let str = serde_json::to_string(&json!(input_vars))?;
         Ok(str)
     }
+
+    /// Runs `streaming` stage guards over the output while it streams
+    fn guard_stream(&self, stream: ResultStream, span: tracing::Span) -> ResultStream {
+        if self.executor_context.default_guards.is_empty()
+            && self
+                .extra
+                .as_ref()
+                .is_none_or(|extra| extra.guards.is_empty())
+        {
+            return stream;
+        }
+
+        let extra = self.extra.clone();
+        let executor_context = self.executor_context.clone();
+        let check_every_chars = executor_context.streaming_guard.check_every_chars;
+        guard_stream(stream, check_every_chars, move |output| {
+            let extra = extra.clone();
+            let executor_context = executor_context.clone();
+            async move {
+                let message = ChatCompletionMessage::new_text("assistant".to_string(), output);
+                apply_guardrails(
+                    std::slice::from_ref(&message),
+                    extra.as_ref(),
+                    executor_context.evaluator_service.as_ref().as_ref(),
+                    &executor_context,
+                    GuardStage::Streaming,
+                )
+                .await
+            }
+            .instrument(span.clone())
+        })
+    }
 }
 
 #[async_trait::async_trait]
@@ -201,7 +244,8 @@ impl ModelInstance for TracedModel {
         _previous_messages: Vec<Message>,
         tags: HashMap<String, String>,
     ) -> LLMResult<ChatCompletionMessageWithFinishReason> {
-        let credentials_ident = credentials_identifier(&self.definition.model_params);
+        let credentials_ident = credentials_identifier(&self.definition.model_params)
+            .with_billing_label(self.executor_context.billing_label.clone());
         let traced_model: TraceModelDefinition = self.definition.clone().into();
         let model = traced_model.sanitize_json()?;
         let model_str = serde_json::to_string(&model)?;
@@ -209,7 +253,10 @@ impl ModelInstance for TracedModel {
         let input_str = self.clean_input_trace(&input_vars)?;
         let model_name = self.definition.name.clone();
         let provider_name = self.definition.db_model.provider_name.clone();
-        let (tx, mut rx) = channel::<Option<ModelEvent>>(outer_tx.max_capacity());
+        let (tx, mut rx) = self
+            .executor_context
+            .stream_channel
+            .channel_for::<Option<ModelEvent>, _>(&outer_tx);
 
         let span = create_model_invoke_span!(
             &input_str,
@@ -225,6 +272,11 @@ impl ModelInstance for TracedModel {
         if let Some(state) = &self.response_cache_state {
             span.record("cache", state.to_string());
         }
+        let service_tier = self.definition.model_params.engine.service_tier();
+        if let Some(service_tier) = service_tier {
+            span.record("service_tier", service_tier.as_str());
+        }
+        self.definition.model_params.adjustments.record(&span);
 
         apply_guardrails(
             &self.initial_messages,
@@ -248,7 +300,11 @@ impl ModelInstance for TracedModel {
             .await?;
 
         let cost_calculator = self.executor_context.cost_calculator.clone();
-        let price = self.definition.db_model.price.clone();
+        let price = self
+            .definition
+            .db_model
+            .price
+            .for_service_tier(service_tier);
         let _model_name_clone = model_name.clone();
         let _provider_name_clone = provider_name.clone();
         tokio::spawn(
@@ -256,6 +312,7 @@ impl ModelInstance for TracedModel {
                 let mut start_time = None;
                 let mut usage = GatewayModelUsage::default();
                 let mut total_cost = 0.0;
+                let mut total_breakdown = CostBreakdown::default();
                 while let Some(Some(msg)) = rx.recv().await {
                     match &msg.event {
                         ModelEventType::LlmStart(_) => {
@@ -279,6 +336,10 @@ impl ModelInstance for TracedModel {
                                     Ok(mut c) => {
                                         total_cost += c.cost;
                                         c.cost = total_cost;
+                                        if let Some(breakdown) = &mut c.breakdown {
+                                            total_breakdown.add(breakdown);
+                                            *breakdown = total_breakdown;
+                                        }
                                         current_span
                                             .record("cost", serde_json::to_string(&c).unwrap());
                                     }
@@ -320,6 +381,11 @@ impl ModelInstance for TracedModel {
         async {
             let instance =
                 init_model_instance(self.definition.model_params.engine.clone(), tools).await?;
+            let instance = self
+                .executor_context
+                .json_repair
+                .apply(instance, &self.request);
+            let instance = self.executor_context.language_guard.apply(instance);
             let vllora_llm_client = CompletionsClient::new(CompletionEngineParamsBuilder::new())
                 .with_instance(instance);
             let result = vllora_llm_client
@@ -338,7 +404,7 @@ impl ModelInstance for TracedModel {
                             .map(|a| match a.r#type {
                                 ContentType::Text => a.text.clone().unwrap_or_default(),
                                 ContentType::ImageUrl => "".to_string(),
-                                ContentType::InputAudio => "".to_string(),
+                                ContentType::InputAudio | ContentType::Audio => "".to_string(),
                                 ContentType::File => "".to_string(),
                             })
                             .collect::<Vec<String>>()
@@ -374,7 +440,8 @@ impl ModelInstance for TracedModel {
         _previous_messages: Vec<Message>,
         tags: HashMap<String, String>,
     ) -> LLMResult<ResultStream> {
-        let credentials_ident = credentials_identifier(&self.definition.model_params);
+        let credentials_ident = credentials_identifier(&self.definition.model_params)
+            .with_billing_label(self.executor_context.billing_label.clone());
         let traced_model: TraceModelDefinition = self.definition.clone().into();
         let model = traced_model.sanitize_json()?;
         let model_str = serde_json::to_string(&model)?;
@@ -399,6 +466,11 @@ impl ModelInstance for TracedModel {
         if let Some(state) = &self.response_cache_state {
             span.record("cache", state.to_string());
         }
+        let service_tier = self.definition.model_params.engine.service_tier();
+        if let Some(service_tier) = service_tier {
+            span.record("service_tier", service_tier.as_str());
+        }
+        self.definition.model_params.adjustments.record(&span);
 
         apply_guardrails(
             &self.initial_messages,
@@ -421,7 +493,7 @@ impl ModelInstance for TracedModel {
             )))
             .await?;
 
-        let (tx, mut rx) = channel(outer_tx.max_capacity());
+        let (tx, mut rx) = self.executor_context.stream_channel.channel_for(&outer_tx);
         let mut start_time = None;
         let tools = self.tools.clone();
 
@@ -430,22 +502,44 @@ impl ModelInstance for TracedModel {
         let completions_client =
             CompletionsClient::new(CompletionEngineParamsBuilder::new()).with_instance(instance);
 
-        let result = execute_stream(
-            completions_client,
-            self.request.clone(),
-            input_vars.clone(),
-            tx.clone(),
-            tags.clone(),
-        )
-        .instrument(span.clone())
-        .await;
+        let result = self
+            .executor_context
+            .first_token_timeout
+            .apply(
+                execute_stream(
+                    completions_client,
+                    self.request.clone(),
+                    input_vars.clone(),
+                    tx.clone(),
+                    tags.clone(),
+                )
+                .instrument(span.clone()),
+                span.clone(),
+            )
+            .await
+            .map(|stream| self.guard_stream(stream, span.clone()))
+            .map(|stream| self.executor_context.stream_coalescing.apply(stream));
 
         span.record(
             "tags",
             JsonValue(&serde_json::to_value(tags.clone())?).as_value(),
         );
 
-        let price = self.definition.db_model.price.clone();
+        let price = self
+            .definition
+            .db_model
+            .price
+            .for_service_tier(service_tier);
+        let required_language = self
+            .executor_context
+            .language_guard
+            .required_language
+            .clone();
+        let mut interim_usage = self
+            .extra
+            .as_ref()
+            .and_then(|extra| extra.interim_usage_every)
+            .map(InterimUsageTracker::new);
         tokio::spawn(
             async move {
                 let mut output = String::new();
@@ -486,10 +580,23 @@ impl ModelInstance for TracedModel {
                                 s.record("usage", serde_json::to_string(u).unwrap());
                             }
                             s.record("output", output.clone());
+                            if let Some(required_language) = &required_language {
+                                s.record("required_language", required_language.as_str());
+                                if let Some(detected) = detect_language(&output) {
+                                    s.record("detected_language", detected);
+                                }
+                            }
                         }
                         _ => {}
                     }
-                    outer_tx.send(Some(msg)).await.unwrap();
+                    match interim_usage.as_mut() {
+                        Some(tracker) => {
+                            for event in tracker.process(msg) {
+                                outer_tx.send(Some(event)).await.unwrap();
+                            }
+                        }
+                        None => outer_tx.send(Some(msg)).await.unwrap(),
+                    }
                 }
             }
             .instrument(span.clone()),
@@ -539,11 +646,12 @@ pub async fn apply_guardrails(
     executor_context: &ExecutorContext,
     guard_stage: GuardStage,
 ) -> Result<(), GuardError> {
-    let Some(Extra { guards, .. }) = extra else {
-        return Ok(());
-    };
+    let requested = extra
+        .map(|extra| extra.guards.as_slice())
+        .unwrap_or_default();
+    let guards = merge_guards(&executor_context.default_guards, requested);
 
-    for guard in guards {
+    for guard in &guards {
         let (guard_id, parameters) = match guard {
             GuardOrName::GuardId(guard_id) => (guard_id, None),
             GuardOrName::GuardWithParameters(GuardWithParameters { id, parameters }) => {
@@ -608,6 +716,7 @@ pub trait ModelMetadataFactory: Send + Sync {
 pub struct DefaultModelMetadataFactory {
     service: Arc<Box<dyn ModelService>>,
     db_pool: Option<DbPool>,
+    ranking_source: Arc<dyn RankingSource>,
 }
 
 impl DefaultModelMetadataFactory {
@@ -615,6 +724,7 @@ impl DefaultModelMetadataFactory {
         Self {
             service,
             db_pool: None,
+            ranking_source: Arc::new(BenchmarkRankingSource),
         }
     }
 
@@ -622,6 +732,12 @@ impl DefaultModelMetadataFactory {
         self.db_pool = Some(db_pool.clone());
         self
     }
+
+    /// Ranks models with `ranking_source` instead of their `benchmark_info`
+    pub fn with_ranking_source(mut self, ranking_source: Arc<dyn RankingSource>) -> Self {
+        self.ranking_source = ranking_source;
+        self
+    }
 }
 
 #[async_trait::async_trait]
@@ -633,12 +749,13 @@ impl ModelMetadataFactory for DefaultModelMetadataFactory {
         model_name: &str,
         _include_parameters: bool,
         _include_benchmark: bool,
-        _project_id: Option<&uuid::Uuid>,
+        project_id: Option<&uuid::Uuid>,
     ) -> Result<ModelMetadata, GatewayApiError> {
         find_model_by_full_name(
             model_name,
             self.service.as_ref().as_ref(),
             self.db_pool.as_ref(),
+            project_id,
         )
     }
 
@@ -659,10 +776,18 @@ impl ModelMetadataFactory for DefaultModelMetadataFactory {
 
     async fn get_top_by_ranking(
         &self,
-        _ranking_name: &str,
-        _top: u8,
+        ranking_name: &str,
+        top: u8,
     ) -> Result<Vec<ModelMetadata>, GatewayApiError> {
-        unimplemented!()
+        let models = self
+            .service
+            .list(None)
+            .map_err(|e| GatewayApiError::CustomError(e.to_string()))?
+            .into_iter()
+            .map(|m| m.into())
+            .collect();
+
+        rank_models(self.ranking_source.as_ref(), ranking_name, models, top).await
     }
 }
 
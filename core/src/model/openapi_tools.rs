@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde_json::{json, Map, Value};
+use thiserror::Error;
+use vllora_llm::client::tools::validation::{validate_definition, InvalidToolDefinition};
+use vllora_llm::types::gateway::{ChatCompletionFunction, ChatCompletionTool};
+use vllora_llm::types::tools::{ModelTool, Tool};
+
+use crate::model::tools::GatewayTool;
+
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
+/// Nesting of `$ref`s followed before giving up, in case of cyclic schemas
+const MAX_REF_DEPTH: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum OpenApiImportError {
+    #[error("Document has neither OpenAPI paths nor JSON Schema definitions")]
+    NoDefinitions,
+    #[error("Unresolved schema reference {0}")]
+    UnresolvedRef(String),
+    #[error(transparent)]
+    InvalidTool(#[from] InvalidToolDefinition),
+}
+
+/// Tool names are limited to letters, digits, `_` and `-`, up to 64 characters
+fn tool_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    name.split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_")
+        .chars()
+        .take(64)
+        .collect()
+}
+
+/// Replaces local `$ref`s, e.g. `#/components/schemas/Pet`, with the schema they point to
+fn resolve_refs(
+    document: &Value,
+    schema: &Value,
+    depth: usize,
+) -> Result<Value, OpenApiImportError> {
+    match schema {
+        Value::Object(object) => {
+            if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+                let target = reference
+                    .strip_prefix('#')
+                    .and_then(|pointer| document.pointer(pointer))
+                    .filter(|_| depth < MAX_REF_DEPTH)
+                    .ok_or_else(|| OpenApiImportError::UnresolvedRef(reference.to_string()))?;
+                return resolve_refs(document, target, depth + 1);
+            }
+            object
+                .iter()
+                .map(|(key, value)| Ok((key.clone(), resolve_refs(document, value, depth)?)))
+                .collect::<Result<Map<_, _>, _>>()
+                .map(Value::Object)
+        }
+        Value::Array(items) => items
+            .iter()
+            .map(|item| resolve_refs(document, item, depth))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Array),
+        value => Ok(value.clone()),
+    }
+}
+
+fn tool(name: String, description: Option<&str>, parameters: Value) -> ChatCompletionTool {
+    ChatCompletionTool {
+        tool_type: "function".to_string(),
+        function: ChatCompletionFunction {
+            name,
+            description: description.map(ToString::to_string),
+            parameters: Some(parameters),
+            strict: None,
+        },
+    }
+}
+
+/// Parameters schema of an operation: its path and query parameters, and the properties of
+/// a JSON request body. Bodies that aren't objects are passed as `body`.
+fn operation_parameters(document: &Value, operation: &Value) -> Result<Value, OpenApiImportError> {
+    let mut properties = Map::new();
+    let mut required = vec![];
+
+    for parameter in operation
+        .get("parameters")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let parameter = resolve_refs(document, parameter, 0)?;
+        let (Some(name), Some("path" | "query")) = (
+            parameter.get("name").and_then(Value::as_str),
+            parameter.get("in").and_then(Value::as_str),
+        ) else {
+            continue;
+        };
+        let mut schema = parameter
+            .get("schema")
+            .cloned()
+            .unwrap_or_else(|| json!({"type": "string"}));
+        if let (Some(description), Value::Object(schema)) =
+            (parameter.get("description"), &mut schema)
+        {
+            schema
+                .entry("description")
+                .or_insert_with(|| description.clone());
+        }
+        properties.insert(name.to_string(), schema);
+        if parameter.get("required").and_then(Value::as_bool) == Some(true) {
+            required.push(Value::String(name.to_string()));
+        }
+    }
+
+    if let Some(body) = operation.get("requestBody") {
+        let body = resolve_refs(document, body, 0)?;
+        if let Some(schema) = body.pointer("/content/application~1json/schema") {
+            match schema.get("properties").and_then(Value::as_object) {
+                Some(body_properties) => {
+                    properties.extend(body_properties.clone());
+                    required.extend(
+                        schema
+                            .get("required")
+                            .and_then(Value::as_array)
+                            .into_iter()
+                            .flatten()
+                            .cloned(),
+                    );
+                }
+                None => {
+                    properties.insert("body".to_string(), schema.clone());
+                    if body.get("required").and_then(Value::as_bool) == Some(true) {
+                        required.push(Value::String("body".to_string()));
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(json!({"type": "object", "properties": properties, "required": required}))
+}
+
+/// Tool definitions from an OpenAPI document, one per operation, or from a JSON Schema
+/// document, one per schema in `$defs` or `definitions`. Operations are named by their
+/// `operationId`, or by method and path.
+pub fn import_tools(document: &Value) -> Result<Vec<ChatCompletionTool>, OpenApiImportError> {
+    let mut tools = vec![];
+
+    if let Some(paths) = document.get("paths").and_then(Value::as_object) {
+        for (path, item) in paths {
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let name = match operation.get("operationId").and_then(Value::as_str) {
+                    Some(operation_id) => tool_name(operation_id),
+                    None => tool_name(&format!("{method}_{path}")),
+                };
+                let description = operation
+                    .get("summary")
+                    .or_else(|| operation.get("description"))
+                    .and_then(Value::as_str);
+                tools.push(tool(
+                    name,
+                    description,
+                    operation_parameters(document, operation)?,
+                ));
+            }
+        }
+        return Ok(tools);
+    }
+
+    let definitions = document
+        .get("$defs")
+        .or_else(|| document.get("definitions"))
+        .and_then(Value::as_object)
+        .ok_or(OpenApiImportError::NoDefinitions)?;
+    for (name, schema) in definitions {
+        let schema = resolve_refs(document, schema, 0)?;
+        let description = schema
+            .get("description")
+            .and_then(Value::as_str)
+            .map(ToString::to_string);
+        tools.push(tool(tool_name(name), description.as_deref(), schema));
+    }
+    Ok(tools)
+}
+
+/// Validates imported `tools` and registers them into `tools_map`. Like tools sent with a
+/// request, they're returned to the caller to run.
+pub fn register_tools(
+    tools: Vec<ChatCompletionTool>,
+    tools_map: &mut HashMap<String, Arc<Box<dyn Tool>>>,
+) -> Result<Vec<ModelTool>, OpenApiImportError> {
+    let mut model_tools = vec![];
+    for def in tools {
+        let tool = GatewayTool { def };
+        validate_definition(&tool)?;
+        model_tools.push(ModelTool {
+            name: tool.name(),
+            description: Some(tool.description()),
+            passed_args: vec![],
+        });
+        tools_map.insert(tool.name(), Arc::new(Box::new(tool) as Box<dyn Tool>));
+    }
+    Ok(model_tools)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn petstore() -> Value {
+        json!({
+            "openapi": "3.0.0",
+            "info": {"title": "Petstore", "version": "1.0.0"},
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "operationId": "listPets",
+                        "summary": "List all pets",
+                        "parameters": [{
+                            "name": "limit",
+                            "in": "query",
+                            "description": "How many pets to return",
+                            "schema": {"type": "integer"}
+                        }]
+                    },
+                    "post": {
+                        "summary": "Create a pet",
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": {"$ref": "#/components/schemas/NewPet"}
+                                }
+                            }
+                        }
+                    }
+                },
+                "/pets/{petId}": {
+                    "get": {
+                        "operationId": "showPetById",
+                        "parameters": [{
+                            "name": "petId",
+                            "in": "path",
+                            "required": true,
+                            "schema": {"type": "string"}
+                        }, {
+                            "name": "X-Request-Id",
+                            "in": "header",
+                            "schema": {"type": "string"}
+                        }]
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "NewPet": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string"},
+                            "tag": {"type": "string"}
+                        },
+                        "required": ["name"]
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_import_openapi_operations_as_tools() {
+        let tools = import_tools(&petstore()).unwrap();
+        let by_name: HashMap<&str, &ChatCompletionFunction> = tools
+            .iter()
+            .map(|tool| (tool.function.name.as_str(), &tool.function))
+            .collect();
+        assert_eq!(by_name.len(), 3);
+
+        let list = by_name["listPets"];
+        assert_eq!(list.description.as_deref(), Some("List all pets"));
+        assert_eq!(
+            list.parameters,
+            Some(json!({
+                "type": "object",
+                "properties": {
+                    "limit": {"type": "integer", "description": "How many pets to return"}
+                },
+                "required": []
+            }))
+        );
+
+        let create = by_name["post_pets"];
+        assert_eq!(
+            create.parameters.as_ref().unwrap()["required"],
+            json!(["name"])
+        );
+        assert_eq!(
+            create.parameters.as_ref().unwrap()["properties"]["tag"],
+            json!({"type": "string"})
+        );
+
+        // Header parameters are left to the caller running the tool
+        let show = by_name["showPetById"];
+        assert_eq!(
+            show.parameters,
+            Some(json!({
+                "type": "object",
+                "properties": {"petId": {"type": "string"}},
+                "required": ["petId"]
+            }))
+        );
+
+        let mut tools_map = HashMap::new();
+        let model_tools = register_tools(tools, &mut tools_map).unwrap();
+        assert_eq!(model_tools.len(), 3);
+        assert!(tools_map.contains_key("showPetById"));
+    }
+
+    #[test]
+    fn test_invalid_imported_schema_is_rejected() {
+        let document = json!({
+            "$defs": {
+                "lookup": {
+                    "type": "object",
+                    "properties": {"id": {"type": "string"}},
+                    "required": ["id", "region"]
+                }
+            }
+        });
+        let tools = import_tools(&document).unwrap();
+        assert!(matches!(
+            register_tools(tools, &mut HashMap::new()),
+            Err(OpenApiImportError::InvalidTool(_))
+        ));
+
+        assert!(matches!(
+            import_tools(&json!({"openapi": "3.0.0"})),
+            Err(OpenApiImportError::NoDefinitions)
+        ));
+    }
+}
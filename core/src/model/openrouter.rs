@@ -0,0 +1,312 @@
+use crate::model::ModelProviderInstance;
+use crate::GatewayApiError;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use std::str::FromStr;
+use vllora_llm::types::credentials::Credentials;
+use vllora_llm::types::engine::CustomInferenceApiType;
+use vllora_llm::types::models::InferenceProvider;
+use vllora_llm::types::models::Limits;
+use vllora_llm::types::models::ModelCapability;
+use vllora_llm::types::models::ModelIOFormats;
+use vllora_llm::types::models::ModelMetadata;
+use vllora_llm::types::models::ModelType;
+use vllora_llm::types::provider::{CompletionModelPrice, InferenceModelProvider, ModelPrice};
+
+pub const OPENROUTER_PROVIDER_NAME: &str = "openrouter";
+pub const OPENROUTER_ENDPOINT: &str = "https://openrouter.ai/api/v1";
+
+/// Catalog prices are in USD per token, model prices per million tokens
+const TOKENS_PER_PRICE_UNIT: f64 = 1_000_000.0;
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModelsResponse {
+    data: Vec<OpenRouterModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterModel {
+    id: String,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    context_length: Option<u32>,
+    #[serde(default)]
+    created: Option<i64>,
+    pricing: OpenRouterPricing,
+    #[serde(default)]
+    architecture: Option<OpenRouterArchitecture>,
+    #[serde(default)]
+    supported_parameters: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterPricing {
+    prompt: String,
+    completion: String,
+    #[serde(default)]
+    input_cache_read: Option<String>,
+    #[serde(default)]
+    input_cache_write: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenRouterArchitecture {
+    #[serde(default)]
+    input_modalities: Vec<String>,
+    #[serde(default)]
+    output_modalities: Vec<String>,
+}
+
+fn price(value: &str) -> Option<f64> {
+    value
+        .parse::<f64>()
+        .ok()
+        .filter(|price| *price >= 0.0)
+        .map(|price| price * TOKENS_PER_PRICE_UNIT)
+}
+
+fn formats(modalities: &[String]) -> Vec<ModelIOFormats> {
+    let formats: Vec<ModelIOFormats> = modalities
+        .iter()
+        .filter_map(|modality| ModelIOFormats::from_str(modality).ok())
+        .collect();
+    if formats.is_empty() {
+        vec![ModelIOFormats::Text]
+    } else {
+        formats
+    }
+}
+
+impl OpenRouterModel {
+    /// Models with a negative or missing price, like OpenRouter's auto router, can't be
+    /// priced and are skipped
+    fn into_metadata(self) -> Option<ModelMetadata> {
+        let price = ModelPrice::Completion(CompletionModelPrice {
+            per_input_token: price(&self.pricing.prompt)?,
+            per_output_token: price(&self.pricing.completion)?,
+            per_cached_input_token: self.pricing.input_cache_read.as_deref().and_then(price),
+            per_cached_input_write_token: self.pricing.input_cache_write.as_deref().and_then(price),
+            valid_from: None,
+            per_batch_input_token: None,
+            per_batch_output_token: None,
+            per_service_tier: None,
+        });
+
+        let mut capabilities = vec![];
+        if self.supported_parameters.iter().any(|p| p == "tools") {
+            capabilities.push(ModelCapability::Tools);
+        }
+        if self.supported_parameters.iter().any(|p| p == "reasoning") {
+            capabilities.push(ModelCapability::Reasoning);
+        }
+        let (input_formats, output_formats) = match &self.architecture {
+            Some(architecture) => (
+                formats(&architecture.input_modalities),
+                formats(&architecture.output_modalities),
+            ),
+            None => (vec![ModelIOFormats::Text], vec![ModelIOFormats::Text]),
+        };
+        // Ids are `<author>/<model>`, e.g. `anthropic/claude-sonnet-4`
+        let model_provider = self
+            .id
+            .split_once('/')
+            .map_or(OPENROUTER_PROVIDER_NAME, |(author, _)| author)
+            .to_string();
+
+        Some(ModelMetadata {
+            model: self.id.clone(),
+            model_provider,
+            inference_provider: InferenceProvider {
+                provider: InferenceModelProvider::Proxy(OPENROUTER_PROVIDER_NAME.to_string()),
+                model_name: self.id.clone(),
+                endpoint: None,
+                custom_inference_api_type: Some(CustomInferenceApiType::OpenAI),
+            },
+            price,
+            input_formats,
+            output_formats,
+            capabilities,
+            r#type: ModelType::Completions,
+            limits: Limits::new(self.context_length.unwrap_or_default()),
+            description: self
+                .description
+                .unwrap_or_else(|| format!("{} through OpenRouter", self.id)),
+            release_date: self
+                .created
+                .and_then(|created| chrono::DateTime::from_timestamp(created, 0))
+                .map(|created| created.date_naive()),
+            is_private: true,
+            ..Default::default()
+        })
+    }
+}
+
+/// Lists the models of OpenRouter's catalog, priced with the catalog prices. Requests to
+/// them go through the OpenAI compatible `openrouter` provider.
+pub struct OpenRouterModelProvider {
+    api_key: String,
+    endpoint: String,
+    client: Client,
+}
+
+impl OpenRouterModelProvider {
+    pub fn new(credentials: Credentials) -> Result<Self, GatewayApiError> {
+        let (api_key, endpoint) = match credentials {
+            Credentials::ApiKey(credentials) => (credentials.api_key, OPENROUTER_ENDPOINT.into()),
+            Credentials::ApiKeyWithEndpoint { api_key, endpoint } => (api_key, endpoint),
+            _ => {
+                return Err(GatewayApiError::CustomError(
+                    "OpenRouter requires an API key".to_string(),
+                ))
+            }
+        };
+        Ok(Self {
+            api_key,
+            endpoint,
+            client: Client::new(),
+        })
+    }
+
+    fn models_from_catalog(catalog: OpenRouterModelsResponse) -> Vec<ModelMetadata> {
+        catalog
+            .data
+            .into_iter()
+            .filter_map(OpenRouterModel::into_metadata)
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ModelProviderInstance for OpenRouterModelProvider {
+    async fn get_private_models(&self) -> Result<Vec<ModelMetadata>, GatewayApiError> {
+        let response = self
+            .client
+            .get(format!("{}/models", self.endpoint.trim_end_matches('/')))
+            .bearer_auth(&self.api_key)
+            .send()
+            .await
+            .map_err(|e| {
+                GatewayApiError::CustomError(format!("Failed to fetch OpenRouter models: {e}"))
+            })?;
+
+        if !response.status().is_success() {
+            return Err(GatewayApiError::CustomError(format!(
+                "OpenRouter API returned error status: {}",
+                response.status()
+            )));
+        }
+
+        let catalog: OpenRouterModelsResponse = response.json().await.map_err(|e| {
+            GatewayApiError::CustomError(format!("Failed to parse OpenRouter models: {e}"))
+        })?;
+
+        Ok(Self::models_from_catalog(catalog))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use vllora_llm::provider::tests::MockStreamServer;
+
+    #[test]
+    fn test_catalog_models_are_listed_with_catalog_prices() {
+        let catalog: OpenRouterModelsResponse = serde_json::from_value(json!({
+            "data": [
+                {
+                    "id": "anthropic/claude-sonnet-4",
+                    "name": "Anthropic: Claude Sonnet 4",
+                    "created": 1747930371,
+                    "description": "Claude Sonnet 4",
+                    "context_length": 200000,
+                    "architecture": {
+                        "input_modalities": ["image", "text", "file"],
+                        "output_modalities": ["text"]
+                    },
+                    "pricing": {
+                        "prompt": "0.000003",
+                        "completion": "0.000015",
+                        "input_cache_read": "0.0000003"
+                    },
+                    "supported_parameters": ["max_tokens", "tools", "reasoning"]
+                },
+                {
+                    "id": "openrouter/auto",
+                    "context_length": 2000000,
+                    "pricing": {"prompt": "-1", "completion": "-1"}
+                }
+            ]
+        }))
+        .unwrap();
+
+        let models = OpenRouterModelProvider::models_from_catalog(catalog);
+        assert_eq!(models.len(), 1);
+        let model = &models[0];
+        assert_eq!(model.model, "anthropic/claude-sonnet-4");
+        assert_eq!(model.model_provider, "anthropic");
+        assert_eq!(
+            model.inference_provider.provider,
+            InferenceModelProvider::Proxy("openrouter".to_string())
+        );
+        assert_eq!(
+            model.inference_provider.model_name,
+            "anthropic/claude-sonnet-4"
+        );
+        assert_eq!(
+            model.input_formats,
+            vec![ModelIOFormats::Image, ModelIOFormats::Text]
+        );
+        assert_eq!(
+            model.capabilities,
+            vec![ModelCapability::Tools, ModelCapability::Reasoning]
+        );
+        assert_eq!(model.limits.max_context_size, 200000);
+        assert!(model.is_private);
+
+        let ModelPrice::Completion(price) = &model.price else {
+            panic!("expected a completion price");
+        };
+        assert!((price.per_input_token - 3.0).abs() < 1e-9);
+        assert!((price.per_output_token - 15.0).abs() < 1e-9);
+        assert!((price.per_cached_input_token.unwrap() - 0.3).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_private_models_are_fetched_from_catalog() {
+        let server = MockStreamServer::start().await.unwrap();
+        let catalog = json!({
+            "data": [
+                {
+                    "id": "openai/gpt-4o-mini",
+                    "context_length": 128000,
+                    "pricing": {"prompt": "0.00000015", "completion": "0.0000006"}
+                }
+            ]
+        });
+        server
+            .set_payload("application/json", catalog.to_string().into_bytes())
+            .await;
+
+        let provider = OpenRouterModelProvider::new(Credentials::ApiKeyWithEndpoint {
+            api_key: "sk-or-test".to_string(),
+            endpoint: format!("{}/", server.url()),
+        })
+        .unwrap();
+        let models = provider.get_private_models().await.unwrap();
+
+        let requests = server.requests().await;
+        assert_eq!(requests.len(), 1);
+        let request = &requests[0];
+        assert!(request.starts_with("GET /models HTTP/1.1"));
+        assert!(request
+            .to_lowercase()
+            .contains("authorization: bearer sk-or-test"));
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].model, "openai/gpt-4o-mini");
+        assert_eq!(models[0].model_provider, "openai");
+        assert_eq!(models[0].limits.max_context_size, 128000);
+    }
+}
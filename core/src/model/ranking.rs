@@ -0,0 +1,242 @@
+use crate::error::GatewayError;
+use crate::GatewayApiError;
+use async_trait::async_trait;
+use parking_lot::RwLock;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use vllora_llm::types::models::ModelMetadata;
+use vllora_llm::types::provider::ModelPrice;
+
+/// Scores models for a named ranking, e.g. a benchmark or a leaderboard category
+#[async_trait]
+pub trait RankingSource: Send + Sync {
+    /// Score of `model` in `ranking_name`, higher is better. `None` when the model is not
+    /// ranked there.
+    async fn score(&self, ranking_name: &str, model: &ModelMetadata) -> Option<f64>;
+}
+
+/// Ranks by the model's own `benchmark_info`. A ranking name is either a path into it, such
+/// as `benchmark_score.livecodebench` or `rank.programming`, or a bare name looked up in
+/// `scores`, then `benchmark_score`, then `rank`. Positions under `rank` are ordered
+/// ascending, everything else descending.
+#[derive(Debug, Clone, Default)]
+pub struct BenchmarkRankingSource;
+
+const BARE_NAME_SECTIONS: [&str; 3] = ["scores", "benchmark_score", "rank"];
+
+impl BenchmarkRankingSource {
+    fn lookup(benchmark_info: &serde_json::Value, section: &str, name: &str) -> Option<f64> {
+        let value = benchmark_info.get(section)?.get(name)?.as_f64()?;
+        // Lower positions are better, negate them so every score sorts descending
+        Some(if section == "rank" { -value } else { value })
+    }
+}
+
+#[async_trait]
+impl RankingSource for BenchmarkRankingSource {
+    async fn score(&self, ranking_name: &str, model: &ModelMetadata) -> Option<f64> {
+        let benchmark_info = model.benchmark_info.as_ref()?;
+        match ranking_name.split_once('.') {
+            Some((section, name)) => Self::lookup(benchmark_info, section, name),
+            None => BARE_NAME_SECTIONS
+                .iter()
+                .find_map(|section| Self::lookup(benchmark_info, section, ranking_name)),
+        }
+    }
+}
+
+/// Externally maintained scores, e.g. a leaderboard refreshed by a background task.
+/// Models are matched by qualified name (`provider/model`) first, then by model name.
+#[derive(Debug, Default)]
+pub struct LeaderboardRankingSource {
+    rankings: RwLock<HashMap<String, HashMap<String, f64>>>,
+}
+
+impl LeaderboardRankingSource {
+    /// Replaces all scores of `ranking_name`
+    pub fn update(&self, ranking_name: impl Into<String>, scores: HashMap<String, f64>) {
+        self.rankings.write().insert(ranking_name.into(), scores);
+    }
+}
+
+#[async_trait]
+impl RankingSource for LeaderboardRankingSource {
+    async fn score(&self, ranking_name: &str, model: &ModelMetadata) -> Option<f64> {
+        let rankings = self.rankings.read();
+        let scores = rankings.get(ranking_name)?;
+        scores
+            .get(&model.qualified_model_name())
+            .or_else(|| scores.get(&model.model))
+            .copied()
+    }
+}
+
+fn input_price(model: &ModelMetadata) -> f64 {
+    match &model.price {
+        ModelPrice::Completion(price) => price.per_input_token,
+        _ => f64::INFINITY,
+    }
+}
+
+/// Top `top` models of `ranking_name`. Models without a score are left out; ties go to the
+/// cheaper model, then to the model name so the order is stable.
+pub async fn rank_models(
+    source: &dyn RankingSource,
+    ranking_name: &str,
+    models: Vec<ModelMetadata>,
+    top: u8,
+) -> Result<Vec<ModelMetadata>, GatewayApiError> {
+    let mut ranked = vec![];
+    for model in models {
+        if let Some(score) = source.score(ranking_name, &model).await {
+            if score.is_finite() {
+                ranked.push((score, model));
+            }
+        }
+    }
+
+    if ranked.is_empty() {
+        return Err(GatewayError::InvalidRequest(format!(
+            "No models are ranked by {ranking_name}"
+        ))
+        .into());
+    }
+
+    ranked.sort_by(|(score_a, a), (score_b, b)| {
+        score_b
+            .partial_cmp(score_a)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| {
+                input_price(a)
+                    .partial_cmp(&input_price(b))
+                    .unwrap_or(Ordering::Equal)
+            })
+            .then_with(|| a.qualified_model_name().cmp(&b.qualified_model_name()))
+    });
+
+    Ok(ranked
+        .into_iter()
+        .take(top as usize)
+        .map(|(_, model)| model)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_llm::types::models::InferenceProvider;
+    use vllora_llm::types::provider::{CompletionModelPrice, InferenceModelProvider};
+
+    fn model(
+        name: &str,
+        input_price: f64,
+        benchmark_info: Option<serde_json::Value>,
+    ) -> ModelMetadata {
+        ModelMetadata {
+            model: name.to_string(),
+            inference_provider: InferenceProvider {
+                provider: InferenceModelProvider::OpenAI,
+                model_name: name.to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            },
+            price: ModelPrice::Completion(CompletionModelPrice {
+                per_input_token: input_price,
+                per_output_token: 0.0,
+                per_cached_input_token: None,
+                per_cached_input_write_token: None,
+                valid_from: None,
+                per_batch_input_token: None,
+                per_batch_output_token: None,
+                per_service_tier: None,
+            }),
+            benchmark_info,
+            ..Default::default()
+        }
+    }
+
+    fn names(models: &[ModelMetadata]) -> Vec<&str> {
+        models.iter().map(|m| m.model.as_str()).collect()
+    }
+
+    fn models() -> Vec<ModelMetadata> {
+        vec![
+            model(
+                "coder-mid",
+                2.0,
+                Some(serde_json::json!({
+                    "benchmark_score": {"livecodebench": 60.0},
+                    "scores": {"programming": 61.0},
+                    "rank": {"programming": 3}
+                })),
+            ),
+            model("no-benchmarks", 0.1, None),
+            model(
+                "coder-best",
+                5.0,
+                Some(serde_json::json!({
+                    "benchmark_score": {"livecodebench": 71.4},
+                    "scores": {"programming": 70.0},
+                    "rank": {"programming": 1}
+                })),
+            ),
+            model(
+                "coder-tied-expensive",
+                3.0,
+                Some(serde_json::json!({
+                    "benchmark_score": {"livecodebench": 60.0, "gpqa": 80.0},
+                    "rank": {"programming": 2}
+                })),
+            ),
+            model(
+                "writer",
+                1.0,
+                Some(serde_json::json!({"benchmark_score": {"livecodebench": null}})),
+            ),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_top_models_by_benchmark() {
+        let source = BenchmarkRankingSource;
+
+        let top = rank_models(&source, "livecodebench", models(), 3)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(&top),
+            vec!["coder-best", "coder-mid", "coder-tied-expensive"]
+        );
+
+        let top = rank_models(&source, "rank.programming", models(), 2)
+            .await
+            .unwrap();
+        assert_eq!(names(&top), vec!["coder-best", "coder-tied-expensive"]);
+
+        // Bare names prefer the `scores` section
+        let top = rank_models(&source, "programming", models(), 5)
+            .await
+            .unwrap();
+        assert_eq!(
+            names(&top),
+            vec!["coder-best", "coder-mid", "coder-tied-expensive"]
+        );
+
+        assert!(rank_models(&source, "mmlu", models(), 3).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_leaderboard_source_overrides_benchmarks() {
+        let source = LeaderboardRankingSource::default();
+        source.update(
+            "coding",
+            HashMap::from([
+                ("openai/writer".to_string(), 90.0),
+                ("coder-mid".to_string(), 85.0),
+            ]),
+        );
+
+        let top = rank_models(&source, "coding", models(), 3).await.unwrap();
+        assert_eq!(names(&top), vec!["writer", "coder-mid"]);
+    }
+}
@@ -93,7 +93,8 @@ impl Responses for TracedResponsesModel {
         mut request: CreateResponse,
         outer_tx: Option<tokio::sync::mpsc::Sender<Option<ModelEvent>>>,
     ) -> LLMResult<Response> {
-        let credentials_ident = credentials_identifier_responses(&self.definition.model_params);
+        let credentials_ident = credentials_identifier_responses(&self.definition.model_params)
+            .with_billing_label(self.executor_context.billing_label.clone());
         let traced_model: TraceResponsesModelDefinition = self.definition.clone().into();
         let model = traced_model.sanitize_json()?;
         let model_str = serde_json::to_string(&model)?;
@@ -187,7 +188,8 @@ impl Responses for TracedResponsesModel {
         mut request: CreateResponse,
         outer_tx: Option<tokio::sync::mpsc::Sender<Option<ModelEvent>>>,
     ) -> LLMResult<ResponsesResultStream> {
-        let credentials_ident = credentials_identifier_responses(&self.definition.model_params);
+        let credentials_ident = credentials_identifier_responses(&self.definition.model_params)
+            .with_billing_label(self.executor_context.billing_label.clone());
         let traced_model: TraceResponsesModelDefinition = self.definition.clone().into();
         let model = traced_model.sanitize_json()?;
         let model_str = serde_json::to_string(&model)?;
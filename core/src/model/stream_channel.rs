@@ -0,0 +1,96 @@
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+/// Capacity of the event channel between a model and the consumer of its events. The
+/// channel follows the capacity of the consumer's own channel, clamped to these bounds: a
+/// fast consumer with a tiny buffer doesn't stall the model, and a slow one doesn't have
+/// the model buffering thousands of events ahead of it. Once the channel is full the model
+/// waits for the consumer.
+///
+/// ```yaml
+/// stream_channel:
+///   min_capacity: 16
+///   max_capacity: 256
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamChannelConfig {
+    pub min_capacity: usize,
+    pub max_capacity: usize,
+}
+
+impl Default for StreamChannelConfig {
+    fn default() -> Self {
+        Self {
+            min_capacity: 16,
+            max_capacity: 256,
+        }
+    }
+}
+
+impl StreamChannelConfig {
+    /// Capacity of a channel feeding a consumer whose channel holds `consumer_capacity`
+    pub fn capacity(&self, consumer_capacity: usize) -> usize {
+        let min_capacity = self.min_capacity.max(1);
+        consumer_capacity.clamp(min_capacity, self.max_capacity.max(min_capacity))
+    }
+
+    /// Channel sized for the consumer behind `consumer_tx`
+    pub fn channel_for<T, U>(&self, consumer_tx: &Sender<U>) -> (Sender<T>, Receiver<T>) {
+        channel(self.capacity(consumer_tx.max_capacity()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[test]
+    fn test_capacity_is_clamped() {
+        let config = StreamChannelConfig::default();
+        assert_eq!(config.capacity(1), 16);
+        assert_eq!(config.capacity(100), 100);
+        assert_eq!(config.capacity(10000), 256);
+    }
+
+    #[tokio::test]
+    async fn test_slow_consumer_bounds_buffered_events() {
+        let config = StreamChannelConfig {
+            min_capacity: 4,
+            max_capacity: 8,
+        };
+        let (consumer_tx, _) = channel::<usize>(10000);
+        let (tx, mut rx) = config.channel_for::<usize, _>(&consumer_tx);
+
+        let sent = Arc::new(AtomicUsize::new(0));
+        let received = Arc::new(AtomicUsize::new(0));
+        let max_buffered = Arc::new(AtomicUsize::new(0));
+
+        let producer = {
+            let sent = sent.clone();
+            let received = received.clone();
+            let max_buffered = max_buffered.clone();
+            tokio::spawn(async move {
+                for event in 0..100 {
+                    tx.send(event).await.unwrap();
+                    let buffered =
+                        sent.fetch_add(1, Ordering::SeqCst) + 1 - received.load(Ordering::SeqCst);
+                    max_buffered.fetch_max(buffered, Ordering::SeqCst);
+                }
+            })
+        };
+
+        while rx.recv().await.is_some() {
+            received.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        producer.await.unwrap();
+
+        assert_eq!(received.load(Ordering::SeqCst), 100);
+        // The producer waited for the consumer instead of buffering everything
+        assert!(max_buffered.load(Ordering::SeqCst) <= 8 + 1);
+    }
+}
@@ -0,0 +1,217 @@
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::time::Instant;
+use vllora_llm::client::completions::response_stream::ResultStream;
+use vllora_llm::error::LLMError;
+use vllora_llm::types::gateway::ChatCompletionChunk;
+
+/// Merges content deltas a model streams within `interval_ms` of each other into a single
+/// chunk, so clients aren't sent a chunk per token. Tool calls, finish reasons, usage and
+/// errors are never delayed: buffered content is flushed before them.
+///
+/// ```yaml
+/// stream_coalescing:
+///   enabled: true
+///   interval_ms: 10
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamCoalescingConfig {
+    pub enabled: bool,
+    pub interval_ms: u64,
+}
+
+impl Default for StreamCoalescingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: 10,
+        }
+    }
+}
+
+impl StreamCoalescingConfig {
+    pub fn apply(&self, stream: ResultStream) -> ResultStream {
+        if !self.enabled || self.interval_ms == 0 {
+            return stream;
+        }
+        coalesce_stream(stream, Duration::from_millis(self.interval_ms))
+    }
+}
+
+struct CoalescedStream {
+    inner: ResultStream,
+    interval: Duration,
+    buffer: Option<(ChatCompletionChunk, Instant)>,
+    pending: Option<Result<ChatCompletionChunk, LLMError>>,
+    finished: bool,
+}
+
+/// Chunks carrying nothing but content of a single choice
+fn is_content_only(chunk: &ChatCompletionChunk) -> bool {
+    chunk.usage.is_none()
+        && matches!(chunk.choices.as_slice(), [choice]
+            if choice.delta.content.is_some()
+                && choice.delta.tool_calls.is_none()
+                && choice.delta.reasoning_content.is_none()
+                && choice.finish_reason.is_none()
+                && choice.logprobs.is_none())
+}
+
+/// Appends the content of `chunk` to `buffer` when both are content of the same choice
+fn try_merge(buffer: &mut ChatCompletionChunk, chunk: &ChatCompletionChunk) -> bool {
+    if !is_content_only(chunk) {
+        return false;
+    }
+    let (buffered, choice) = (&mut buffer.choices[0], &chunk.choices[0]);
+    if buffered.index != choice.index || choice.delta.role.is_some() {
+        return false;
+    }
+    if let (Some(content), Some(delta)) = (&mut buffered.delta.content, &choice.delta.content) {
+        content.push_str(delta);
+    }
+    true
+}
+
+/// Buffers consecutive content deltas of `inner` for up to `interval` and emits them as
+/// one chunk. Any other chunk or error flushes the buffer and is passed through as is.
+pub fn coalesce_stream(inner: ResultStream, interval: Duration) -> ResultStream {
+    let state = CoalescedStream {
+        inner,
+        interval,
+        buffer: None,
+        pending: None,
+        finished: false,
+    };
+
+    ResultStream::new(Box::pin(stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(item) = state.pending.take() {
+                return Some((item, state));
+            }
+            if state.finished {
+                let (chunk, _) = state.buffer.take()?;
+                return Some((Ok(chunk), state));
+            }
+
+            let next = match &state.buffer {
+                Some((_, deadline)) => {
+                    match tokio::time::timeout_at(*deadline, state.inner.next()).await {
+                        Ok(next) => next,
+                        Err(_) => {
+                            let (chunk, _) = state.buffer.take()?;
+                            return Some((Ok(chunk), state));
+                        }
+                    }
+                }
+                None => state.inner.next().await,
+            };
+
+            match next {
+                None => state.finished = true,
+                Some(Ok(chunk)) => match &mut state.buffer {
+                    Some((buffered, _)) if try_merge(buffered, &chunk) => {}
+                    Some(_) => {
+                        state.pending = Some(Ok(chunk));
+                        let (buffered, _) = state.buffer.take()?;
+                        return Some((Ok(buffered), state));
+                    }
+                    None if is_content_only(&chunk) => {
+                        state.buffer = Some((chunk, Instant::now() + state.interval));
+                    }
+                    None => return Some((Ok(chunk), state)),
+                },
+                Some(Err(e)) => {
+                    state.pending = Some(Err(e));
+                    if let Some((buffered, _)) = state.buffer.take() {
+                        return Some((Ok(buffered), state));
+                    }
+                }
+            }
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn chunk(delta: serde_json::Value, finish_reason: Option<&str>) -> ChatCompletionChunk {
+        serde_json::from_value(json!({
+            "id": "chunk",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason,
+                "logprobs": null
+            }]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_tiny_deltas_are_coalesced_in_order() {
+        let text = "The quick brown fox jumps over the lazy dog";
+        let mut chunks = vec![chunk(json!({"role": "assistant", "content": ""}), None)];
+        chunks.extend(
+            text.chars()
+                .map(|c| chunk(json!({"content": c.to_string()}), None)),
+        );
+        chunks.push(chunk(
+            json!({"tool_calls": [{
+                "index": 0,
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "get_weather", "arguments": "{}"}
+            }]}),
+            None,
+        ));
+        chunks.push(chunk(json!({"content": "!"}), None));
+        chunks.push(chunk(json!({}), Some("stop")));
+
+        let inner = ResultStream::new(Box::pin(stream::iter(chunks.into_iter().map(Ok))));
+        let output: Vec<ChatCompletionChunk> = coalesce_stream(inner, Duration::from_secs(1))
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(output.len(), 4);
+        assert_eq!(
+            output[0].choices[0].delta.role.as_deref(),
+            Some("assistant")
+        );
+        assert_eq!(output[0].choices[0].delta.content.as_deref(), Some(text));
+        assert!(output[1].choices[0].delta.tool_calls.is_some());
+        assert_eq!(output[2].choices[0].delta.content.as_deref(), Some("!"));
+        assert_eq!(output[3].choices[0].finish_reason.as_deref(), Some("stop"));
+    }
+
+    #[tokio::test]
+    async fn test_buffered_content_is_flushed_after_interval() {
+        let (tx, rx) = tokio::sync::mpsc::channel(4);
+        let mut stream = coalesce_stream(ResultStream::create(rx), Duration::from_millis(10));
+
+        tx.send(Ok(chunk(json!({"content": "Hel"}), None)))
+            .await
+            .unwrap();
+        tx.send(Ok(chunk(json!({"content": "lo"}), None)))
+            .await
+            .unwrap();
+
+        // The model is still streaming, the buffered content is sent once the interval passes
+        let first = tokio::time::timeout(Duration::from_secs(1), stream.next())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.choices[0].delta.content.as_deref(), Some("Hello"));
+
+        drop(tx);
+        assert!(stream.next().await.is_none());
+    }
+}
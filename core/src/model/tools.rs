@@ -32,6 +32,14 @@ impl Tool for GatewayTool {
             .map(|p| serde_json::from_value(p.clone()).unwrap())
     }
 
+    fn parameters_schema(&self) -> Option<serde_json::Value> {
+        self.def.function.parameters.clone()
+    }
+
+    fn strict(&self) -> bool {
+        self.def.function.strict.unwrap_or(false)
+    }
+
     async fn run(
         &self,
         _inputs: HashMap<String, serde_json::Value>,
@@ -0,0 +1,90 @@
+use super::{GatewayPlugin, PluginContext, PluginError};
+use crate::routing::RoutingStrategy;
+use async_trait::async_trait;
+use vllora_llm::types::gateway::ChatCompletionRequestWithTools;
+
+pub const CORRELATION_ID_HEADER: &str = "X-Correlation-Id";
+
+/// Returns the caller's correlation id on the response, or a new one when the request has none
+pub struct CorrelationIdPlugin {
+    header: String,
+}
+
+impl Default for CorrelationIdPlugin {
+    fn default() -> Self {
+        Self::new(CORRELATION_ID_HEADER)
+    }
+}
+
+impl CorrelationIdPlugin {
+    pub fn new(header: impl Into<String>) -> Self {
+        Self {
+            header: header.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl GatewayPlugin for CorrelationIdPlugin {
+    fn name(&self) -> &str {
+        "correlation_id"
+    }
+
+    async fn on_request(
+        &self,
+        context: &mut PluginContext,
+        _request: &mut ChatCompletionRequestWithTools<RoutingStrategy>,
+    ) -> Result<(), PluginError> {
+        let correlation_id = context
+            .request_header(&self.header)
+            .map(|id| id.to_string())
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+        tracing::Span::current().record("correlation_id", correlation_id.as_str());
+        context.set_response_header(self.header.clone(), correlation_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn request() -> ChatCompletionRequestWithTools<RoutingStrategy> {
+        serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o-mini",
+            "messages": [{"role": "user", "content": "hi"}]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_correlation_id_is_echoed_or_generated() {
+        let plugin = CorrelationIdPlugin::default();
+
+        let mut context = PluginContext {
+            request_headers: HashMap::from([(
+                "x-correlation-id".to_string(),
+                "abc-123".to_string(),
+            )]),
+            ..Default::default()
+        };
+        plugin
+            .on_request(&mut context, &mut request())
+            .await
+            .unwrap();
+        assert_eq!(
+            context.response_headers,
+            vec![(CORRELATION_ID_HEADER.to_string(), "abc-123".to_string())]
+        );
+
+        let mut context = PluginContext::default();
+        plugin
+            .on_request(&mut context, &mut request())
+            .await
+            .unwrap();
+        let (_, generated) = &context.response_headers[0];
+        assert!(uuid::Uuid::parse_str(generated).is_ok());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::routing::RoutingStrategy;
+use actix_web::{HttpRequest, HttpResponseBuilder};
+use async_trait::async_trait;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use thiserror::Error;
+use vllora_llm::types::gateway::{
+    ChatCompletionChunk, ChatCompletionRequestWithTools, ChatCompletionResponse,
+};
+
+pub mod correlation_id;
+
+pub use correlation_id::CorrelationIdPlugin;
+
+#[derive(Error, Debug)]
+pub enum PluginError {
+    #[error("{0}")]
+    Custom(String),
+    #[error(transparent)]
+    SerdeError(#[from] serde_json::Error),
+}
+
+/// Per request state shared by all plugins of a request
+#[derive(Debug, Clone, Default)]
+pub struct PluginContext {
+    /// Incoming request headers, keyed by lower case name
+    pub request_headers: HashMap<String, String>,
+    /// Headers added to the gateway response
+    pub response_headers: Vec<(String, String)>,
+    /// Values plugins pass between their own hooks
+    pub values: HashMap<String, serde_json::Value>,
+}
+
+impl PluginContext {
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let request_headers = req
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| {
+                value
+                    .to_str()
+                    .ok()
+                    .map(|value| (name.as_str().to_string(), value.to_string()))
+            })
+            .collect();
+
+        Self {
+            request_headers,
+            ..Default::default()
+        }
+    }
+
+    pub fn request_header(&self, name: &str) -> Option<&str> {
+        self.request_headers
+            .get(&name.to_ascii_lowercase())
+            .map(String::as_str)
+    }
+
+    pub fn set_response_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        self.response_headers
+            .retain(|(existing, _)| !existing.eq_ignore_ascii_case(&name));
+        self.response_headers.push((name, value.into()));
+    }
+
+    pub fn apply_response_headers(&self, builder: &mut HttpResponseBuilder) {
+        for (name, value) in &self.response_headers {
+            builder.insert_header((name.as_str(), value.as_str()));
+        }
+    }
+}
+
+/// Hooks for transforming chat completion requests and responses around the executor.
+/// Every hook defaults to a no-op, so plugins only implement the ones they need.
+#[async_trait]
+pub trait GatewayPlugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Called once per request, before routing and execution
+    async fn on_request(
+        &self,
+        _context: &mut PluginContext,
+        _request: &mut ChatCompletionRequestWithTools<RoutingStrategy>,
+    ) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    /// Called with a buffered response before it is returned to the client
+    async fn on_response(
+        &self,
+        _context: &PluginContext,
+        _response: &mut ChatCompletionResponse,
+    ) -> Result<(), PluginError> {
+        Ok(())
+    }
+
+    /// Called with every chunk of a streamed response before it is sent to the client
+    async fn on_stream_chunk(
+        &self,
+        _context: &PluginContext,
+        _chunk: &mut ChatCompletionChunk,
+    ) -> Result<(), PluginError> {
+        Ok(())
+    }
+}
+
+/// Which built-in plugins are enabled
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PluginsConfig {
+    /// Echoes `X-Correlation-Id` back on every completion, generating one when missing
+    pub correlation_id: bool,
+}
+
+/// Plugins registered at startup, invoked in registration order. A failing or panicking
+/// plugin is logged and skipped; it never fails the request.
+#[derive(Clone, Default)]
+pub struct PluginRegistry {
+    plugins: Vec<Arc<dyn GatewayPlugin>>,
+}
+
+impl fmt::Debug for PluginRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.plugins.iter().map(|p| p.name()))
+            .finish()
+    }
+}
+
+impl PluginRegistry {
+    pub fn from_config(config: &PluginsConfig) -> Self {
+        let mut registry = Self::default();
+        if config.correlation_id {
+            registry.register(Arc::new(CorrelationIdPlugin::default()));
+        }
+        registry
+    }
+
+    pub fn register(&mut self, plugin: Arc<dyn GatewayPlugin>) {
+        self.plugins.push(plugin);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    pub async fn on_request(
+        &self,
+        context: &mut PluginContext,
+        request: &mut ChatCompletionRequestWithTools<RoutingStrategy>,
+    ) {
+        for plugin in &self.plugins {
+            isolate(
+                plugin.name(),
+                "on_request",
+                plugin.on_request(context, request),
+            )
+            .await;
+        }
+    }
+
+    pub async fn on_response(
+        &self,
+        context: &PluginContext,
+        response: &mut ChatCompletionResponse,
+    ) {
+        for plugin in &self.plugins {
+            isolate(
+                plugin.name(),
+                "on_response",
+                plugin.on_response(context, response),
+            )
+            .await;
+        }
+    }
+
+    pub async fn on_stream_chunk(&self, context: &PluginContext, chunk: &mut ChatCompletionChunk) {
+        for plugin in &self.plugins {
+            isolate(
+                plugin.name(),
+                "on_stream_chunk",
+                plugin.on_stream_chunk(context, chunk),
+            )
+            .await;
+        }
+    }
+}
+
+async fn isolate<F>(plugin: &str, hook: &str, future: F)
+where
+    F: Future<Output = Result<(), PluginError>>,
+{
+    match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::warn!("Plugin {plugin} failed in {hook}: {e}"),
+        Err(_) => tracing::error!("Plugin {plugin} panicked in {hook}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct FailingPlugin;
+
+    #[async_trait]
+    impl GatewayPlugin for FailingPlugin {
+        fn name(&self) -> &str {
+            "failing"
+        }
+
+        async fn on_request(
+            &self,
+            _context: &mut PluginContext,
+            _request: &mut ChatCompletionRequestWithTools<RoutingStrategy>,
+        ) -> Result<(), PluginError> {
+            Err(PluginError::Custom("boom".to_string()))
+        }
+
+        async fn on_stream_chunk(
+            &self,
+            _context: &PluginContext,
+            _chunk: &mut ChatCompletionChunk,
+        ) -> Result<(), PluginError> {
+            panic!("boom")
+        }
+    }
+
+    struct ModelRewritePlugin;
+
+    #[async_trait]
+    impl GatewayPlugin for ModelRewritePlugin {
+        fn name(&self) -> &str {
+            "model_rewrite"
+        }
+
+        async fn on_request(
+            &self,
+            _context: &mut PluginContext,
+            request: &mut ChatCompletionRequestWithTools<RoutingStrategy>,
+        ) -> Result<(), PluginError> {
+            request.request.model = "openai/gpt-4o-mini".to_string();
+            Ok(())
+        }
+
+        async fn on_stream_chunk(
+            &self,
+            _context: &PluginContext,
+            chunk: &mut ChatCompletionChunk,
+        ) -> Result<(), PluginError> {
+            chunk.model = "rewritten".to_string();
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_plugin_does_not_stop_others() {
+        let mut registry = PluginRegistry::default();
+        registry.register(Arc::new(FailingPlugin));
+        registry.register(Arc::new(ModelRewritePlugin));
+
+        let mut context = PluginContext::default();
+        let mut request: ChatCompletionRequestWithTools<RoutingStrategy> =
+            serde_json::from_value(serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "hi"}]
+            }))
+            .unwrap();
+        registry.on_request(&mut context, &mut request).await;
+        assert_eq!(request.request.model, "openai/gpt-4o-mini");
+
+        let mut chunk: ChatCompletionChunk = serde_json::from_value(serde_json::json!({
+            "id": "chunk",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4o",
+            "choices": []
+        }))
+        .unwrap();
+        registry.on_stream_chunk(&context, &mut chunk).await;
+        assert_eq!(chunk.model, "rewritten");
+    }
+}
@@ -1,6 +1,6 @@
 use vllora_llm::types::{
     gateway::{
-        CostCalculationResult, GatewayModelUsage, ImageCostCalculationResult,
+        CostBreakdown, CostCalculationResult, GatewayModelUsage, ImageCostCalculationResult,
         ImageGenerationModelUsage,
     },
     provider::ImageGenerationPrice,
@@ -27,6 +27,8 @@ pub fn calculate_image_price(
             per_cached_input_token: None,
             per_cached_input_write_token: None,
             is_cache_used: false,
+            billing_label: None,
+            breakdown: None,
             per_image_cost: Some(ImageCostCalculationResult::TypePrice {
                 size: size.clone(),
                 quality: usage.quality.clone(),
@@ -45,6 +47,8 @@ pub fn calculate_image_price(
             per_cached_input_write_token: None,
             is_cache_used: false,
             per_image_cost: Some(ImageCostCalculationResult::MPPrice(cost)),
+            billing_label: None,
+            breakdown: None,
         }
     } else {
         tracing::warn!("Image model pricing are not set");
@@ -57,6 +61,8 @@ pub fn calculate_image_price(
             per_cached_input_write_token: None,
             is_cache_used: false,
             per_image_cost: Some(ImageCostCalculationResult::SingleImagePrice(price)),
+            billing_label: None,
+            breakdown: None,
         }
     }
 }
@@ -75,14 +81,8 @@ pub fn calculate_tokens_cost(
         cost_per_output_token /= 100.0;
     }
 
-    let cached_tokens = usage
-        .prompt_tokens_details
-        .as_ref()
-        .map_or(0, |p| p.cached_tokens());
-    let cached_input_write_tokens = usage
-        .prompt_tokens_details
-        .as_ref()
-        .map_or(0, |p| p.cache_creation_tokens());
+    let cached_tokens = usage.cached_input_tokens();
+    let cached_input_write_tokens = usage.cache_write_tokens();
     let not_cached_input_tokens = usage
         .input_tokens
         .saturating_sub(cached_tokens)
@@ -92,11 +92,20 @@ pub fn calculate_tokens_cost(
     let cached_input_write_token_cost =
         cost_per_cached_input_write_token.unwrap_or(cost_per_input_token);
 
+    // Reasoning tokens are part of the output tokens, priced the same
+    let reasoning_tokens = usage
+        .completion_tokens_details
+        .as_ref()
+        .map_or(0, |d| d.reasoning_tokens())
+        .min(usage.output_tokens);
+    let visible_output_tokens = usage.output_tokens - reasoning_tokens;
+
     let input_cost = cost_per_input_token * not_cached_input_tokens as f64 * 1e-6;
     let cached_input_cost = cached_input_token_cost * cached_tokens as f64 * 1e-6;
     let cached_input_write_cost =
         cached_input_write_token_cost * cached_input_write_tokens as f64 * 1e-6;
     let output_cost = cost_per_output_token * usage.output_tokens as f64 * 1e-6;
+    let reasoning_cost = cost_per_output_token * reasoning_tokens as f64 * 1e-6;
 
     CostCalculationResult {
         cost: input_cost + cached_input_cost + cached_input_write_cost + output_cost,
@@ -106,13 +115,24 @@ pub fn calculate_tokens_cost(
         per_output_token: cost_per_output_token,
         per_image_cost: None,
         is_cache_used: usage.is_cache_used,
+        billing_label: None,
+        breakdown: Some(CostBreakdown {
+            input_cost,
+            output_cost: cost_per_output_token * visible_output_tokens as f64 * 1e-6,
+            cached_cost: cached_input_cost + cached_input_write_cost,
+            reasoning_cost,
+        }),
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use vllora_llm::types::gateway::{GatewayModelUsage, PromptTokensDetails};
+    use crate::credentials::billing::BillingLabelsConfig;
+    use vllora_llm::types::credentials_ident::CredentialsIdent;
+    use vllora_llm::types::gateway::{
+        ChatCompletionUsage, CompletionTokensDetails, GatewayModelUsage, PromptTokensDetails,
+    };
 
     #[test]
     fn test_calculate_tokens_cost_no_cache() {
@@ -684,4 +704,111 @@ mod tests {
         assert!(result.is_cache_used);
         assert_eq!(result.per_image_cost, None);
     }
+
+    #[test]
+    fn test_cost_records_carry_billing_label() {
+        let labels: BillingLabelsConfig = serde_json::from_value(serde_json::json!({
+            "projects": {"search": "team-search"}
+        }))
+        .unwrap();
+        let usage = GatewayModelUsage {
+            input_tokens: 1000,
+            output_tokens: 500,
+            total_tokens: 1500,
+            ..Default::default()
+        };
+
+        let ident = CredentialsIdent::Own.with_billing_label(labels.for_project("search").cloned());
+        assert_eq!(ident.to_string(), "own");
+        let result = calculate_tokens_cost(&usage, 1.0, None, None, 2.0).with_billing_label(&ident);
+        assert!((result.cost - 0.002).abs() < 1e-10);
+        assert_eq!(result.billing_label.as_deref(), Some("team-search"));
+        let record = serde_json::to_value(&result).unwrap();
+        assert_eq!(record["billing_label"], "team-search");
+
+        // Vllora credentials are charged as before and not attributed to a billing entity
+        let ident =
+            CredentialsIdent::Vllora.with_billing_label(labels.for_project("search").cloned());
+        let result = calculate_tokens_cost(&usage, 1.0, None, None, 2.0).with_billing_label(&ident);
+        assert!((result.cost - 0.002).abs() < 1e-10);
+        assert!(result.billing_label.is_none());
+
+        // Projects without a label keep unlabelled records
+        let ident = CredentialsIdent::Own.with_billing_label(labels.for_project("other").cloned());
+        let record = serde_json::to_value(
+            calculate_tokens_cost(&usage, 1.0, None, None, 2.0).with_billing_label(&ident),
+        )
+        .unwrap();
+        assert!(record.get("billing_label").is_none());
+    }
+
+    #[test]
+    fn test_cached_anthropic_response_reports_cache_reads_at_reduced_cost() {
+        let anthropic_usage: clust::messages::Usage = serde_json::from_value(serde_json::json!({
+            "input_tokens": 100,
+            "cache_read_input_tokens": 2000,
+            "cache_creation_input_tokens": 0,
+            "output_tokens": 50
+        }))
+        .unwrap();
+
+        let usage = GatewayModelUsage::from(&anthropic_usage);
+        assert_eq!(usage.input_tokens, 2100);
+        assert_eq!(usage.cached_input_tokens(), 2000);
+        assert_eq!(usage.cache_write_tokens(), 0);
+
+        let reported = ChatCompletionUsage::from(&usage);
+        assert_eq!(reported.prompt_tokens, 2100);
+        assert_eq!(reported.cached_input_tokens, 2000);
+        let reported = serde_json::to_value(&reported).unwrap();
+        assert_eq!(reported["cached_input_tokens"], 2000);
+        assert_eq!(reported["cache_write_tokens"], 0);
+
+        // $3 per 1M input tokens, $0.30 per 1M cache reads, $15 per 1M output tokens
+        let cached = calculate_tokens_cost(&usage, 3.0, Some(0.3), Some(3.75), 15.0);
+        // 100 * 3 + 2000 * 0.3 + 50 * 15 = 1650 per 1M
+        assert!((cached.cost - 0.00165).abs() < 1e-10);
+
+        let uncached = GatewayModelUsage {
+            input_tokens: 2100,
+            output_tokens: 50,
+            total_tokens: 2150,
+            ..Default::default()
+        };
+        let uncached = calculate_tokens_cost(&uncached, 3.0, Some(0.3), Some(3.75), 15.0);
+        assert!(cached.cost < uncached.cost);
+    }
+
+    #[test]
+    fn test_cost_breakdown_with_cached_and_reasoning_tokens() {
+        let usage = GatewayModelUsage {
+            input_tokens: 1000,
+            output_tokens: 500,
+            total_tokens: 1500,
+            prompt_tokens_details: Some(PromptTokensDetails::new(Some(300), Some(100), None)),
+            completion_tokens_details: Some(CompletionTokensDetails::new(
+                None,
+                None,
+                Some(200),
+                None,
+            )),
+            is_cache_used: false,
+        };
+
+        let result = calculate_tokens_cost(&usage, 1.0, Some(0.5), Some(1.25), 4.0);
+        let breakdown = result.breakdown.unwrap();
+
+        // 600 uncached input tokens at $1 per 1M
+        assert!((breakdown.input_cost - 0.0006).abs() < 1e-10);
+        // 300 cache reads at $0.50 and 100 cache writes at $1.25 per 1M
+        assert!((breakdown.cached_cost - 0.000275).abs() < 1e-10);
+        // 300 visible output tokens at $4 per 1M
+        assert!((breakdown.output_cost - 0.0012).abs() < 1e-10);
+        // 200 reasoning tokens at the output price
+        assert!((breakdown.reasoning_cost - 0.0008).abs() < 1e-10);
+        assert!((breakdown.total() - result.cost).abs() < 1e-10);
+
+        let record = serde_json::to_value(&result).unwrap();
+        assert!(record["breakdown"]["reasoning_cost"].as_f64().is_some());
+    }
 }
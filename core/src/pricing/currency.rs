@@ -0,0 +1,344 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::RwLock;
+
+pub const BASE_CURRENCY: &str = "USD";
+
+/// Limit for fetching rates from `rates_url`
+const RATES_FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+/// Time a failed rate fetch is remembered before fetching again
+const RATES_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum CurrencyError {
+    #[error("No exchange rate for {0}")]
+    UnknownCurrency(String),
+
+    #[error("Failed to fetch exchange rates: {0}")]
+    Fetch(String),
+}
+
+/// Currency costs are displayed in. Costs are always calculated and stored in USD and only
+/// converted when shown. `rates` are units of a currency per USD. When `rates_url` is set,
+/// rates are fetched from it, expecting a body like `{"rates": {"EUR": 0.92}}`, cached for
+/// `refresh_secs` and the configured `rates` are used if fetching fails. A failed fetch is
+/// retried after a minute.
+///
+/// ```yaml
+/// currency:
+///   code: EUR
+///   rates:
+///     EUR: 0.92
+///   rates_url: https://open.er-api.com/v6/latest/USD
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CurrencyConfig {
+    pub code: String,
+    pub rates: HashMap<String, f64>,
+    pub rates_url: Option<String>,
+    pub refresh_secs: u64,
+}
+
+impl Default for CurrencyConfig {
+    fn default() -> Self {
+        Self {
+            code: BASE_CURRENCY.to_string(),
+            rates: HashMap::new(),
+            rates_url: None,
+            refresh_secs: 3600,
+        }
+    }
+}
+
+#[async_trait::async_trait]
+pub trait ExchangeRateSource: Send + Sync {
+    /// Units of `currency` per USD
+    async fn rate(&self, currency: &str) -> Result<f64, CurrencyError>;
+}
+
+/// Rates given in config
+pub struct StaticRates(HashMap<String, f64>);
+
+#[async_trait::async_trait]
+impl ExchangeRateSource for StaticRates {
+    async fn rate(&self, currency: &str) -> Result<f64, CurrencyError> {
+        self.0
+            .get(currency)
+            .copied()
+            .ok_or_else(|| CurrencyError::UnknownCurrency(currency.to_string()))
+    }
+}
+
+/// Rates fetched from an HTTP endpoint returning USD based rates
+pub struct HttpRates {
+    url: String,
+    client: reqwest::Client,
+}
+
+#[derive(Deserialize)]
+struct RatesResponse {
+    rates: HashMap<String, f64>,
+}
+
+#[async_trait::async_trait]
+impl ExchangeRateSource for HttpRates {
+    async fn rate(&self, currency: &str) -> Result<f64, CurrencyError> {
+        let response = self
+            .client
+            .get(&self.url)
+            .timeout(RATES_FETCH_TIMEOUT)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| CurrencyError::Fetch(e.to_string()))?;
+        let body: RatesResponse = response
+            .json()
+            .await
+            .map_err(|e| CurrencyError::Fetch(e.to_string()))?;
+
+        body.rates
+            .get(currency)
+            .copied()
+            .ok_or_else(|| CurrencyError::UnknownCurrency(currency.to_string()))
+    }
+}
+
+/// A cost in the display currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct ConvertedCost {
+    pub amount: f64,
+    pub currency: String,
+}
+
+impl fmt::Display for ConvertedCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(6);
+        if self.currency == BASE_CURRENCY {
+            write!(f, "${:.*}", precision, self.amount)
+        } else {
+            write!(f, "{:.*} {}", precision, self.amount, self.currency)
+        }
+    }
+}
+
+pub struct CurrencyConverter {
+    code: String,
+    source: Arc<dyn ExchangeRateSource>,
+    configured_rate: Option<f64>,
+    ttl: Duration,
+    cached: RwLock<Option<(f64, Instant)>>,
+    /// Error of the last failed fetch and when it failed
+    failed: RwLock<Option<(String, Instant)>>,
+}
+
+impl CurrencyConverter {
+    pub fn from_config(config: &CurrencyConfig) -> Self {
+        let code = config.code.trim().to_uppercase();
+        let source: Arc<dyn ExchangeRateSource> = match &config.rates_url {
+            Some(url) => Arc::new(HttpRates {
+                url: url.clone(),
+                client: reqwest::Client::new(),
+            }),
+            None => Arc::new(StaticRates(config.rates.clone())),
+        };
+
+        Self {
+            configured_rate: config.rates.get(&code).copied(),
+            code,
+            source,
+            ttl: Duration::from_secs(config.refresh_secs),
+            cached: RwLock::new(None),
+            failed: RwLock::new(None),
+        }
+    }
+
+    pub fn with_source(mut self, source: Arc<dyn ExchangeRateSource>) -> Self {
+        self.source = source;
+        self
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    /// Units of the display currency per USD, refreshed once the cached rate expires. After a
+    /// failed fetch, the fallback rate is used until the next retry.
+    pub async fn rate(&self) -> Result<f64, CurrencyError> {
+        if self.code == BASE_CURRENCY {
+            return Ok(1.0);
+        }
+        if let Some((rate, fetched_at)) = *self.cached.read().await {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(rate);
+            }
+        }
+        if let Some((error, failed_at)) = self.failed.read().await.clone() {
+            if failed_at.elapsed() < RATES_RETRY_AFTER {
+                return self.fallback().await.ok_or(CurrencyError::Fetch(error));
+            }
+        }
+        self.refresh().await
+    }
+
+    /// Fetches the rate from the source, falling back to the last known or configured rate
+    pub async fn refresh(&self) -> Result<f64, CurrencyError> {
+        match self.source.rate(&self.code).await {
+            Ok(rate) => {
+                *self.cached.write().await = Some((rate, Instant::now()));
+                *self.failed.write().await = None;
+                Ok(rate)
+            }
+            Err(e) => {
+                *self.failed.write().await = Some((e.to_string(), Instant::now()));
+                self.fallback().await.ok_or(e)
+            }
+        }
+    }
+
+    async fn fallback(&self) -> Option<f64> {
+        let cached = self.cached.read().await.map(|(rate, _)| rate);
+        cached.or(self.configured_rate)
+    }
+
+    /// Converts USD costs at the current rate, keeping them in USD when no rate is available
+    pub async fn converter(&self) -> CostConverter {
+        match self.rate().await {
+            Ok(rate) => CostConverter {
+                rate,
+                currency: self.code.clone(),
+            },
+            Err(e) => {
+                tracing::warn!("Showing costs in {BASE_CURRENCY}: {e}");
+                CostConverter {
+                    rate: 1.0,
+                    currency: BASE_CURRENCY.to_string(),
+                }
+            }
+        }
+    }
+
+    /// Converts a USD cost, keeping it in USD when no rate is available
+    pub async fn convert(&self, usd: f64) -> ConvertedCost {
+        self.converter().await.convert(usd)
+    }
+}
+
+/// A rate fixed for converting several costs
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostConverter {
+    rate: f64,
+    currency: String,
+}
+
+impl CostConverter {
+    pub fn convert(&self, usd: f64) -> ConvertedCost {
+        ConvertedCost {
+            amount: usd * self.rate,
+            currency: self.currency.clone(),
+        }
+    }
+}
+
+static CURRENCY_CONVERTER: OnceLock<Arc<CurrencyConverter>> = OnceLock::new();
+
+/// Sets the process-wide display currency. Only the first call takes effect.
+pub fn init_currency(config: &CurrencyConfig) {
+    let _ = CURRENCY_CONVERTER.set(Arc::new(CurrencyConverter::from_config(config)));
+}
+
+/// Process-wide display currency, USD unless configured
+pub fn currency_converter() -> Arc<CurrencyConverter> {
+    CURRENCY_CONVERTER
+        .get_or_init(|| Arc::new(CurrencyConverter::from_config(&CurrencyConfig::default())))
+        .clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct MockRates {
+        rate: f64,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl ExchangeRateSource for MockRates {
+        async fn rate(&self, currency: &str) -> Result<f64, CurrencyError> {
+            assert_eq!(currency, "EUR");
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.rate)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_usd_cost_is_converted_with_cached_rate() {
+        let source = Arc::new(MockRates {
+            rate: 0.5,
+            calls: AtomicUsize::new(0),
+        });
+        let converter = CurrencyConverter::from_config(&CurrencyConfig {
+            code: "eur".to_string(),
+            ..Default::default()
+        })
+        .with_source(source.clone());
+
+        let cost = converter.convert(2.5).await;
+        assert_eq!(
+            cost,
+            ConvertedCost {
+                amount: 1.25,
+                currency: "EUR".to_string(),
+            }
+        );
+        assert_eq!(format!("{cost:.2}"), "1.25 EUR");
+
+        // The rate is cached until refreshed
+        converter.convert(1.0).await;
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+        converter.refresh().await.unwrap();
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+    }
+
+    struct FailingRates(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl ExchangeRateSource for FailingRates {
+        async fn rate(&self, _currency: &str) -> Result<f64, CurrencyError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err(CurrencyError::Fetch("connection refused".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_fetch_is_not_retried_right_away() {
+        let source = Arc::new(FailingRates(AtomicUsize::new(0)));
+        let converter = CurrencyConverter::from_config(&CurrencyConfig {
+            code: "EUR".to_string(),
+            rates: HashMap::from([("EUR".to_string(), 0.9)]),
+            ..Default::default()
+        })
+        .with_source(source.clone());
+
+        assert_eq!(converter.convert(1.0).await.amount, 0.9);
+        assert_eq!(converter.convert(2.0).await.amount, 1.8);
+        assert_eq!(source.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_unknown_rate_keeps_usd() {
+        let converter = CurrencyConverter::from_config(&CurrencyConfig {
+            code: "JPY".to_string(),
+            ..Default::default()
+        });
+        let cost = converter.convert(1.5).await;
+        assert_eq!(cost.currency, BASE_CURRENCY);
+        assert_eq!(format!("{cost:.2}"), "$1.50");
+    }
+}
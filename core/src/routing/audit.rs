@@ -0,0 +1,321 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::metadata::error::DatabaseError;
+use crate::metadata::models::routing_audit::DbNewRoutingAudit;
+use crate::metadata::pool::DbPool;
+use crate::metadata::services::routing_audit::RoutingAuditServiceImpl;
+use crate::metadata::DatabaseServiceTrait;
+use crate::routing::interceptor::InterceptorResult;
+use crate::routing::{RoutingResult, RoutingStrategy, Targets};
+
+#[derive(Error, Debug)]
+pub enum RoutingAuditError {
+    #[error(transparent)]
+    DatabaseError(#[from] DatabaseError),
+
+    #[error(transparent)]
+    IoError(#[from] std::io::Error),
+
+    #[error(transparent)]
+    SerializationError(#[from] serde_json::Error),
+}
+
+/// Where routing decisions are written
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RoutingAuditConfig {
+    #[default]
+    None,
+    /// Append-only JSON lines file
+    File { path: PathBuf },
+    /// `routing_audit` table in the metadata database
+    Db,
+}
+
+impl RoutingAuditConfig {
+    pub fn build_sink(&self, db_pool: &DbPool) -> Option<Arc<dyn RoutingAuditSink>> {
+        match self {
+            RoutingAuditConfig::None => None,
+            RoutingAuditConfig::File { path } => {
+                Some(Arc::new(FileRoutingAuditSink::new(path.clone())))
+            }
+            RoutingAuditConfig::Db => Some(Arc::new(DbRoutingAuditSink::new(db_pool.clone()))),
+        }
+    }
+}
+
+/// One routing decision: which router ran, why it picked its targets and what interceptors saw
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoutingAuditRecord {
+    pub id: String,
+    pub timestamp_us: i64,
+    pub project_id: Option<String>,
+    pub trace_id: Option<String>,
+    pub router_name: String,
+    pub strategy: String,
+    pub matched_route: Option<String>,
+    pub targets: Targets,
+    pub interceptors: Vec<InterceptorResult>,
+    pub final_model: Option<String>,
+}
+
+impl RoutingAuditRecord {
+    pub fn new(router_name: &str, strategy: &RoutingStrategy, result: &RoutingResult) -> Self {
+        let interceptors = result
+            .interceptor_state
+            .as_ref()
+            .map(|state| {
+                state
+                    .pre_request_results
+                    .iter()
+                    .chain(state.post_request_results.iter())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let final_model = result
+            .targets
+            .first()
+            .and_then(|target| target.get("model"))
+            .and_then(|model| model.as_str())
+            .map(|model| model.to_string());
+
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            timestamp_us: chrono::Utc::now().timestamp_micros(),
+            project_id: None,
+            trace_id: None,
+            router_name: router_name.to_string(),
+            strategy: strategy.to_string(),
+            matched_route: result.matched_route.clone(),
+            targets: result.targets.clone(),
+            interceptors,
+            final_model,
+        }
+    }
+
+    pub fn with_project_id(mut self, project_id: String) -> Self {
+        self.project_id = Some(project_id);
+        self
+    }
+
+    pub fn with_trace_id(mut self, trace_id: String) -> Self {
+        self.trace_id = Some(trace_id);
+        self
+    }
+}
+
+/// Destination for routing audit records. Writes may block, callers go through [`record`].
+pub trait RoutingAuditSink: Send + Sync {
+    fn write(&self, record: &RoutingAuditRecord) -> Result<(), RoutingAuditError>;
+}
+
+/// Writes the record on the blocking pool so the request path never waits on the sink
+pub fn record(sink: Arc<dyn RoutingAuditSink>, record: RoutingAuditRecord) {
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = sink.write(&record) {
+            tracing::error!("Failed to write routing audit record {}: {e}", record.id);
+        }
+    });
+}
+
+pub struct FileRoutingAuditSink {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileRoutingAuditSink {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+}
+
+impl RoutingAuditSink for FileRoutingAuditSink {
+    fn write(&self, record: &RoutingAuditRecord) -> Result<(), RoutingAuditError> {
+        let line = serde_json::to_string(record)?;
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        // Single write so concurrent appenders never interleave within a line
+        file.write_all(format!("{line}\n").as_bytes())?;
+        Ok(())
+    }
+}
+
+pub struct DbRoutingAuditSink {
+    service: RoutingAuditServiceImpl,
+}
+
+impl DbRoutingAuditSink {
+    pub fn new(db_pool: DbPool) -> Self {
+        Self {
+            service: RoutingAuditServiceImpl::init(db_pool),
+        }
+    }
+}
+
+impl RoutingAuditSink for DbRoutingAuditSink {
+    fn write(&self, record: &RoutingAuditRecord) -> Result<(), RoutingAuditError> {
+        self.service.insert(DbNewRoutingAudit {
+            id: record.id.clone(),
+            timestamp_us: record.timestamp_us,
+            project_id: record.project_id.clone(),
+            trace_id: record.trace_id.clone(),
+            router_name: record.router_name.clone(),
+            strategy: record.strategy.clone(),
+            matched_route: record.matched_route.clone(),
+            targets: serde_json::to_string(&record.targets)?,
+            interceptors: serde_json::to_string(&record.interceptors)?,
+            final_model: record.final_model.clone(),
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::test_utils::setup_test_database;
+    use crate::routing::interceptor::{
+        Interceptor, InterceptorContext, InterceptorError, InterceptorFactory,
+    };
+    use crate::routing::strategy::conditional::ConditionalRouter;
+    use crate::routing::{
+        ConditionOp, ConditionOpType, ConditionalRouting, InterceptorSpec, InterceptorType, Route,
+        RouteCondition, TargetSpec,
+    };
+    use std::collections::HashMap;
+    use vllora_llm::types::gateway::ChatCompletionRequest;
+
+    struct MockGuardrail;
+
+    #[async_trait::async_trait]
+    impl Interceptor for MockGuardrail {
+        fn name(&self) -> &str {
+            "guardrail"
+        }
+        async fn pre_request(
+            &self,
+            _context: &mut InterceptorContext,
+        ) -> Result<serde_json::Value, InterceptorError> {
+            Ok(serde_json::json!({"result": false}))
+        }
+        async fn post_request(
+            &self,
+            _context: &mut InterceptorContext,
+            _response: &serde_json::Value,
+        ) -> Result<serde_json::Value, InterceptorError> {
+            Ok(serde_json::json!({"result": false}))
+        }
+    }
+
+    struct MockFactory;
+
+    impl InterceptorFactory for MockFactory {
+        fn create_interceptor(
+            &self,
+            _spec: &InterceptorSpec,
+        ) -> Result<Arc<dyn Interceptor>, InterceptorError> {
+            Ok(Arc::new(MockGuardrail))
+        }
+    }
+
+    fn model_target(model: &str) -> TargetSpec {
+        TargetSpec::List(vec![HashMap::from([(
+            "model".to_string(),
+            serde_json::json!(model),
+        )])])
+    }
+
+    #[tokio::test]
+    async fn test_conditional_decision_chain_is_captured() {
+        let routing = ConditionalRouting {
+            pre_request: vec![InterceptorSpec {
+                name: "guardrail".to_string(),
+                interceptor_type: InterceptorType::Guardrail {
+                    guard_id: "toxicity".to_string(),
+                },
+                extra: HashMap::new(),
+            }],
+            routes: vec![
+                Route {
+                    name: "safe".to_string(),
+                    conditions: Some(RouteCondition::Expr(HashMap::from([(
+                        "pre_request.guardrail.result".to_string(),
+                        ConditionOp {
+                            op: HashMap::from([(ConditionOpType::Eq, serde_json::json!(true))]),
+                        },
+                    )]))),
+                    targets: Some(model_target("openai/gpt-4o")),
+                    message_mapper: None,
+                },
+                Route {
+                    name: "flagged".to_string(),
+                    conditions: None,
+                    targets: Some(model_target("openai/gpt-4o-mini")),
+                    message_mapper: None,
+                },
+            ],
+            post_request: vec![],
+        };
+        let strategy = RoutingStrategy::Conditional {
+            routing: routing.clone(),
+        };
+
+        let resolution = ConditionalRouter { routing }
+            .resolve(
+                Box::new(MockFactory),
+                &ChatCompletionRequest::default(),
+                &HashMap::new(),
+                &HashMap::new(),
+                None,
+            )
+            .await;
+        let targets = match resolution.targets {
+            Some(TargetSpec::List(targets)) => targets.clone(),
+            _ => panic!("expected a target list"),
+        };
+        let result = RoutingResult::new(targets)
+            .with_matched_route(resolution.route)
+            .with_interceptor_state(resolution.interceptor_state);
+
+        let record = RoutingAuditRecord::new("moderated", &strategy, &result)
+            .with_trace_id("trace-1".to_string());
+
+        assert_eq!(record.router_name, "moderated");
+        assert_eq!(record.strategy, "Conditional");
+        assert_eq!(record.matched_route.as_deref(), Some("flagged"));
+        assert_eq!(record.final_model.as_deref(), Some("openai/gpt-4o-mini"));
+        assert_eq!(record.interceptors.len(), 1);
+        assert_eq!(record.interceptors[0].interceptor_name, "guardrail");
+        assert_eq!(
+            record.interceptors[0].data,
+            serde_json::json!({"result": false})
+        );
+
+        let db_pool = setup_test_database();
+        DbRoutingAuditSink::new(db_pool.clone())
+            .write(&record)
+            .unwrap();
+
+        let rows = RoutingAuditServiceImpl::init(db_pool)
+            .list_by_trace("trace-1")
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].matched_route.as_deref(), Some("flagged"));
+        let interceptors: Vec<InterceptorResult> =
+            serde_json::from_str(&rows[0].interceptors).unwrap();
+        assert_eq!(interceptors[0].interceptor_name, "guardrail");
+    }
+}
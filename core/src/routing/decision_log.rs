@@ -0,0 +1,96 @@
+use std::future::Future;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::Span;
+use valuable::Valuable;
+use vllora_telemetry::events::JsonValue;
+
+/// Target of routing decision logs. It is under `vllora::user_tracing`, so decisions are
+/// printed with the default `RUST_LOG=info` and exported with the request's trace.
+pub const DECISION_LOG_TARGET: &str = "vllora::user_tracing::request_routing::decision";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DecisionLogLevel {
+    Trace,
+    Debug,
+    #[default]
+    Info,
+    Warn,
+}
+
+/// Logs every metric based routing decision to [`DECISION_LOG_TARGET`], with the candidates
+/// and their metric values, the metric used and the chosen model. The same resolution is
+/// recorded on the routing span as `router.metric_resolution`. Enabled at info by default,
+/// projects can turn it off or change its level in their own routing config.
+///
+/// ```yaml
+/// routing:
+///   decision_log:
+///     level: debug
+///   projects:
+///     support-bot:
+///       decision_log:
+///         enabled: false
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct RoutingDecisionLogConfig {
+    pub enabled: bool,
+    pub level: DecisionLogLevel,
+}
+
+impl Default for RoutingDecisionLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            level: DecisionLogLevel::default(),
+        }
+    }
+}
+
+tokio::task_local! {
+    static DECISION_LOG: RoutingDecisionLogConfig;
+}
+
+/// Runs `future` with `config` as the decision log of the routing decisions it makes.
+/// Decisions made outside of a scope, e.g. when explaining a route, are not logged.
+pub async fn scope<F: Future>(config: RoutingDecisionLogConfig, future: F) -> F::Output {
+    DECISION_LOG.scope(config, future).await
+}
+
+macro_rules! log_decision {
+    ($level:expr, $resolution:expr) => {
+        tracing::event!(
+            target: DECISION_LOG_TARGET,
+            $level,
+            best_model = $resolution["best_model"].as_str(),
+            metric = $resolution["metric"].as_str(),
+            candidates = %$resolution["candidates"],
+            metrics_duration = %$resolution["metrics_duration"],
+            excluded = %$resolution["excluded"],
+            "Routing decision"
+        )
+    };
+}
+
+/// Records a metric resolution on the current span, and logs it when the decision log is
+/// enabled
+pub fn record_metric_resolution(resolution: Value) {
+    Span::current().record(
+        "router.metric_resolution",
+        JsonValue(&resolution).as_value(),
+    );
+
+    let Ok(Some(level)) = DECISION_LOG.try_with(|config| config.enabled.then_some(config.level))
+    else {
+        return;
+    };
+    match level {
+        DecisionLogLevel::Trace => log_decision!(tracing::Level::TRACE, resolution),
+        DecisionLogLevel::Debug => log_decision!(tracing::Level::DEBUG, resolution),
+        DecisionLogLevel::Info => log_decision!(tracing::Level::INFO, resolution),
+        DecisionLogLevel::Warn => log_decision!(tracing::Level::WARN, resolution),
+    }
+}
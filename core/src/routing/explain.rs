@@ -0,0 +1,322 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use vllora_llm::types::gateway::{ChatCompletionRequest, Extra};
+
+use crate::model::ModelMetadataFactory;
+use crate::routing::interceptor::InterceptorFactory;
+use crate::routing::metrics::MetricsRepository;
+use crate::routing::strategy::metric::collect_candidates;
+use crate::routing::{
+    ConditionOp, LlmRouter, RouterError, RoutingResult, RoutingStrategy, Target, TargetSpec,
+};
+use crate::usage::Metrics;
+
+/// One condition of a route and the value it was compared against
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ConditionEvaluation {
+    pub key: String,
+    pub op: ConditionOp,
+    /// Value found for `key`, `None` when the request, its metadata or the interceptors
+    /// don't provide it
+    pub value: Option<serde_json::Value>,
+    pub matched: bool,
+}
+
+/// Why a conditional route matched or not
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RouteEvaluation {
+    pub route: String,
+    pub matched: bool,
+    /// Conditions in evaluation order. Evaluation stops at the first one deciding the route.
+    pub conditions: Vec<ConditionEvaluation>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Routing decision of a dry run
+#[derive(Serialize, Debug, Clone)]
+pub struct RoutingExplanation {
+    pub router_name: String,
+    pub strategy: String,
+    #[serde(flatten)]
+    pub result: RoutingResult,
+    /// Metrics of the candidate models, keyed by `provider/model`
+    pub metrics: BTreeMap<String, Metrics>,
+}
+
+fn target_model(target: &Target) -> Option<String> {
+    target
+        .get("model")
+        .and_then(|model| model.as_str())
+        .map(|model| model.to_string())
+}
+
+impl LlmRouter {
+    /// Models the router could pick from: its targets and those of the matched route
+    fn candidate_models(&self, matched_route: Option<&str>) -> Vec<String> {
+        let mut models: Vec<String> = self.targets.iter().filter_map(target_model).collect();
+
+        if let RoutingStrategy::Conditional { routing } = &self.strategy {
+            let route_targets = routing
+                .routes
+                .iter()
+                .find(|route| Some(route.name.as_str()) == matched_route)
+                .and_then(|route| route.targets.as_ref());
+            match route_targets {
+                Some(TargetSpec::Any { any, .. }) => models.extend(any.iter().cloned()),
+                Some(TargetSpec::List(targets)) => {
+                    models.extend(targets.iter().filter_map(target_model))
+                }
+                Some(TargetSpec::Single(model)) => models.push(model.clone()),
+                None => {}
+            }
+        }
+
+        models.sort();
+        models.dedup();
+        models
+    }
+
+    /// Dry run of [`super::RouteStrategy::route`]. Runs the strategy, including the
+    /// pre_request interceptors its conditions reference, and explains the decision without
+    /// executing the model. A conditional router without a matching route explains it
+    /// with no targets instead of failing.
+    pub async fn explain<M: MetricsRepository + Send + Sync>(
+        &self,
+        request: ChatCompletionRequest,
+        extra: Option<&Extra>,
+        model_metadata_factory: Arc<Box<dyn ModelMetadataFactory>>,
+        metadata: HashMap<String, serde_json::Value>,
+        metrics_repository: &M,
+        interceptor_factory: Box<dyn InterceptorFactory>,
+    ) -> Result<RoutingExplanation, RouterError> {
+        let result = self
+            .resolve_route(
+                request,
+                extra,
+                model_metadata_factory,
+                metadata,
+                metrics_repository,
+                interceptor_factory,
+            )
+            .await?;
+
+        let models = self.candidate_models(result.matched_route.as_deref());
+        let metrics =
+            collect_candidates(&models, self.metrics_duration.as_ref(), metrics_repository)
+                .await
+                .into_iter()
+                .collect();
+
+        Ok(RoutingExplanation {
+            router_name: self.name.clone(),
+            strategy: self.strategy.to_string(),
+            result,
+            metrics,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::services::model::ModelServiceImpl;
+    use crate::metadata::test_utils::setup_test_database;
+    use crate::model::DefaultModelMetadataFactory;
+    use crate::routing::interceptor::{Interceptor, InterceptorContext, InterceptorError};
+    use crate::routing::metrics::InMemoryMetricsRepository;
+    use crate::routing::{
+        ConditionOpType, ConditionalRouting, InterceptorSpec, InterceptorType, Route,
+        RouteCondition,
+    };
+    use crate::usage::{ModelMetrics, ProviderMetrics, TimeMetrics};
+
+    struct MockModeration;
+
+    #[async_trait::async_trait]
+    impl Interceptor for MockModeration {
+        fn name(&self) -> &str {
+            "moderation"
+        }
+        async fn pre_request(
+            &self,
+            _context: &mut InterceptorContext,
+        ) -> Result<serde_json::Value, InterceptorError> {
+            Ok(serde_json::json!({"passed": false}))
+        }
+        async fn post_request(
+            &self,
+            _context: &mut InterceptorContext,
+            _response: &serde_json::Value,
+        ) -> Result<serde_json::Value, InterceptorError> {
+            Ok(serde_json::json!({}))
+        }
+    }
+
+    struct MockFactory;
+
+    impl InterceptorFactory for MockFactory {
+        fn create_interceptor(
+            &self,
+            _spec: &InterceptorSpec,
+        ) -> Result<Arc<dyn Interceptor>, InterceptorError> {
+            Ok(Arc::new(MockModeration))
+        }
+    }
+
+    fn condition(key: &str, value: serde_json::Value) -> RouteCondition {
+        RouteCondition::Expr(HashMap::from([(
+            key.to_string(),
+            ConditionOp {
+                op: HashMap::from([(ConditionOpType::Eq, value)]),
+            },
+        )]))
+    }
+
+    fn router() -> LlmRouter {
+        let routing = ConditionalRouting {
+            pre_request: vec![InterceptorSpec {
+                name: "moderation".to_string(),
+                interceptor_type: InterceptorType::Guardrail {
+                    guard_id: "moderation".to_string(),
+                },
+                extra: HashMap::new(),
+            }],
+            routes: vec![
+                Route {
+                    name: "premium".to_string(),
+                    conditions: Some(condition(
+                        "metadata.user.tier",
+                        serde_json::json!("premium"),
+                    )),
+                    targets: Some(TargetSpec::Single("openai/gpt-4o".to_string())),
+                    message_mapper: None,
+                },
+                Route {
+                    name: "flagged".to_string(),
+                    conditions: Some(condition(
+                        "pre_request.moderation.passed",
+                        serde_json::json!(false),
+                    )),
+                    targets: Some(TargetSpec::Single("openai/gpt-4o-mini".to_string())),
+                    message_mapper: None,
+                },
+            ],
+            post_request: vec![],
+        };
+        LlmRouter::new(
+            "moderated".to_string(),
+            RoutingStrategy::Conditional { routing },
+        )
+    }
+
+    fn metrics_repository() -> InMemoryMetricsRepository {
+        let metrics = Metrics {
+            latency: Some(420.0),
+            ..Default::default()
+        };
+        let provider_metrics = ProviderMetrics {
+            models: BTreeMap::from([(
+                "gpt-4o-mini".to_string(),
+                ModelMetrics {
+                    metrics: TimeMetrics {
+                        total: metrics,
+                        last_15_minutes: Metrics::default(),
+                        last_hour: Metrics::default(),
+                        ewma: Metrics::default(),
+                    },
+                },
+            )]),
+        };
+        InMemoryMetricsRepository::new(BTreeMap::from([("openai".to_string(), provider_metrics)]))
+    }
+
+    #[tokio::test]
+    async fn test_conditional_router_explains_matched_route() {
+        let model_metadata_factory = Arc::new(Box::new(DefaultModelMetadataFactory::new(Arc::new(
+            Box::new(ModelServiceImpl::new(setup_test_database())),
+        ))) as Box<dyn ModelMetadataFactory>);
+
+        let explanation = router()
+            .explain(
+                ChatCompletionRequest::default(),
+                None,
+                model_metadata_factory,
+                HashMap::from([("user.tier".to_string(), serde_json::json!("free"))]),
+                &metrics_repository(),
+                Box::new(MockFactory),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(explanation.router_name, "moderated");
+        assert_eq!(explanation.strategy, "Conditional");
+        assert_eq!(explanation.result.matched_route.as_deref(), Some("flagged"));
+        assert_eq!(
+            target_model(&explanation.result.targets[0]).as_deref(),
+            Some("openai/gpt-4o-mini")
+        );
+
+        // The premium route was skipped because of the user tier, the flagged one matched
+        // on the moderation result
+        let routes = &explanation.result.route_evaluations;
+        assert_eq!(routes.len(), 2);
+        assert!(!routes[0].matched);
+        assert_eq!(routes[0].conditions[0].key, "metadata.user.tier");
+        assert_eq!(
+            routes[0].conditions[0].value,
+            Some(serde_json::json!("free"))
+        );
+        assert!(routes[1].matched);
+        assert_eq!(routes[1].conditions[0].key, "pre_request.moderation.passed");
+        assert_eq!(
+            routes[1].conditions[0].value,
+            Some(serde_json::json!(false))
+        );
+
+        let interceptors = explanation.result.interceptor_state.as_ref().unwrap();
+        assert_eq!(interceptors.pre_request_results.len(), 1);
+        assert_eq!(
+            explanation.metrics["openai/gpt-4o-mini"].latency,
+            Some(420.0)
+        );
+
+        let json = serde_json::to_value(&explanation).unwrap();
+        assert_eq!(json["matched_route"], "flagged");
+        assert_eq!(json["route_evaluations"][1]["matched"], true);
+    }
+
+    #[tokio::test]
+    async fn test_unmatched_conditional_router_explains_without_targets() {
+        let mut router = router();
+        if let RoutingStrategy::Conditional { routing } = &mut router.strategy {
+            routing.routes.pop();
+        }
+        let model_metadata_factory = Arc::new(Box::new(DefaultModelMetadataFactory::new(Arc::new(
+            Box::new(ModelServiceImpl::new(setup_test_database())),
+        ))) as Box<dyn ModelMetadataFactory>);
+
+        let explanation = router
+            .explain(
+                ChatCompletionRequest::default(),
+                None,
+                model_metadata_factory,
+                HashMap::new(),
+                &metrics_repository(),
+                Box::new(MockFactory),
+            )
+            .await
+            .unwrap();
+
+        assert!(explanation.result.matched_route.is_none());
+        assert!(explanation.result.targets.is_empty());
+        // Missing metadata is reported as such
+        assert_eq!(
+            explanation.result.route_evaluations[0].conditions[0].value,
+            None
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use vllora_llm::types::gateway::{
+    ChatCompletionChoice, ChatCompletionChunk, ChatCompletionChunkChoice, ChatCompletionDelta,
+    ChatCompletionMessage, ChatCompletionResponse, ChatCompletionUsage,
+};
+
+/// Finish reason of a fallback response, so clients can tell it from model output
+pub const FALLBACK_FINISH_REASON: &str = "fallback";
+
+/// Canned response returned instead of an error once every target of a route failed.
+///
+/// ```yaml
+/// routing:
+///   fallback_responses:
+///     router/support:
+///       content: "Our assistant is unavailable right now, please try again shortly."
+///     openai/gpt-4o-mini: {}  # the default "service degraded" message
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FallbackResponse {
+    /// Assistant message content, a generic "service degraded" message when unset
+    pub content: Option<String>,
+}
+
+impl FallbackResponse {
+    pub fn content(&self) -> String {
+        self.content.clone().unwrap_or_else(|| {
+            "The service is temporarily degraded, please try again later.".to_string()
+        })
+    }
+
+    /// Completion carrying the fallback content, with zero usage and cost
+    pub fn response(&self, model: &str) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: Uuid::new_v4().to_string(),
+            object: "chat.completion".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: model.to_string(),
+            choices: vec![ChatCompletionChoice {
+                index: 0,
+                message: ChatCompletionMessage::new_text("assistant".to_string(), self.content()),
+                finish_reason: Some(FALLBACK_FINISH_REASON.to_string()),
+                redaction: None,
+            }],
+            usage: ChatCompletionUsage::default(),
+            is_cache_used: None,
+            rate_limit: None,
+        }
+    }
+
+    /// Single chunk carrying the fallback content, for streamed requests
+    pub fn chunk(&self, model: &str) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: Uuid::new_v4().to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: chrono::Utc::now().timestamp(),
+            model: model.to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta::from_assistant_text(self.content()),
+                finish_reason: Some(FALLBACK_FINISH_REASON.to_string()),
+                logprobs: None,
+            }],
+            usage: None,
+            tool_event: None,
+        }
+    }
+}
@@ -7,6 +7,7 @@ use vllora_llm::types::gateway::ChatCompletionRequest;
 
 mod factory;
 pub mod guard;
+pub mod rate_limit_store;
 pub mod rate_limiter;
 pub mod transformer;
 pub mod types;
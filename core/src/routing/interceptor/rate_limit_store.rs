@@ -0,0 +1,415 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
+use thiserror::Error;
+
+use crate::routing::interceptor::rate_limiter::{
+    RateLimitResult, RateLimiterConfig, RateLimiterService,
+};
+use crate::routing::interceptor::InterceptorError;
+use crate::routing::LimitTarget;
+use crate::usage::{InMemoryStorage, LimitPeriod};
+
+#[derive(Debug, Error)]
+pub enum RateLimitStoreError {
+    #[error("Rate limit store unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Rate limit store requires the gateway to be built with the `redis` feature")]
+    RedisDisabled,
+}
+
+/// Where rate limit counters are kept. Counters are kept in memory unless `redis_url` is
+/// set, in which case they are shared by every gateway replica using the same Redis. When
+/// the store can't be reached requests are allowed with `fail_open`, or rejected otherwise.
+///
+/// ```yaml
+/// rate_limit_store:
+///   redis_url: redis://localhost:6379
+///   key_prefix: "vllora:rate_limit:"
+///   fail_open: true
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RateLimitStoreConfig {
+    pub redis_url: Option<String>,
+    pub key_prefix: String,
+    pub fail_open: bool,
+}
+
+impl Default for RateLimitStoreConfig {
+    fn default() -> Self {
+        Self {
+            redis_url: None,
+            key_prefix: "vllora:rate_limit:".to_string(),
+            fail_open: true,
+        }
+    }
+}
+
+/// Counter store rate limits are counted in
+#[async_trait::async_trait]
+pub trait RateLimitStore: Send + Sync {
+    /// Atomically adds `amount` to the `period` counter of `key` for `identifier` and returns
+    /// the new value. Counters of periods with a refresh expire when the period ends.
+    async fn increment(
+        &self,
+        period: &LimitPeriod,
+        identifier: &str,
+        key: &str,
+        amount: f64,
+    ) -> Result<f64, RateLimitStoreError>;
+}
+
+/// Counters of this gateway process only
+#[derive(Default)]
+pub struct InMemoryRateLimitStore {
+    storage: InMemoryStorage,
+}
+
+#[async_trait::async_trait]
+impl RateLimitStore for InMemoryRateLimitStore {
+    async fn increment(
+        &self,
+        period: &LimitPeriod,
+        identifier: &str,
+        key: &str,
+        amount: f64,
+    ) -> Result<f64, RateLimitStoreError> {
+        Ok(self
+            .storage
+            .increment_and_get_value(period, identifier, key, amount)
+            .await)
+    }
+}
+
+/// Counters shared through Redis. The increment and the expiry of a new counter are set by
+/// one script, so replicas never see a counter without expiry. The connection is
+/// re-established when Redis restarts or the network drops it.
+#[cfg(feature = "redis")]
+pub struct RedisRateLimitStore {
+    client: redis::Client,
+    connection: tokio::sync::OnceCell<redis::aio::ConnectionManager>,
+    key_prefix: String,
+}
+
+#[cfg(feature = "redis")]
+const INCREMENT_SCRIPT: &str = r#"
+local value = redis.call('INCRBYFLOAT', KEYS[1], ARGV[1])
+if tonumber(ARGV[2]) > 0 and redis.call('TTL', KEYS[1]) < 0 then
+    redis.call('EXPIRE', KEYS[1], ARGV[2])
+end
+return value
+"#;
+
+#[cfg(feature = "redis")]
+impl RedisRateLimitStore {
+    pub fn new(url: &str, key_prefix: String) -> Result<Self, RateLimitStoreError> {
+        Ok(Self {
+            client: redis::Client::open(url)
+                .map_err(|e| RateLimitStoreError::Unavailable(e.to_string()))?,
+            connection: tokio::sync::OnceCell::new(),
+            key_prefix,
+        })
+    }
+
+    /// Connects on first use, retrying on the next call when connecting fails. Once
+    /// connected, the connection manager reconnects whenever the connection is lost.
+    async fn connection(&self) -> Result<redis::aio::ConnectionManager, RateLimitStoreError> {
+        self.connection
+            .get_or_try_init(|| redis::aio::ConnectionManager::new(self.client.clone()))
+            .await
+            .cloned()
+            .map_err(|e| RateLimitStoreError::Unavailable(e.to_string()))
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait::async_trait]
+impl RateLimitStore for RedisRateLimitStore {
+    async fn increment(
+        &self,
+        period: &LimitPeriod,
+        identifier: &str,
+        key: &str,
+        amount: f64,
+    ) -> Result<f64, RateLimitStoreError> {
+        let mut connection = self.connection().await?;
+        let value: String = redis::Script::new(INCREMENT_SCRIPT)
+            .key(format!(
+                "{}{}",
+                self.key_prefix,
+                period.get_key(identifier, key)
+            ))
+            .arg(amount)
+            .arg(period.get_seconds_until_refresh().unwrap_or(0))
+            .invoke_async(&mut connection)
+            .await
+            .map_err(|e| RateLimitStoreError::Unavailable(e.to_string()))?;
+        value
+            .parse()
+            .map_err(|_| RateLimitStoreError::Unavailable(format!("invalid counter {value}")))
+    }
+}
+
+/// Rate limiter counting in a [`RateLimitStore`]. Request limits are counted when checked.
+/// Cost limits compare the cost counted so far, the cost of the request itself is added by
+/// [`RateLimiterService::record_cost`] once it completed, to every cost counter it was
+/// checked against. A service is created per request for that reason.
+pub struct StoreRateLimiterService {
+    store: Arc<dyn RateLimitStore>,
+    fail_open: bool,
+    /// Identifier and period of the cost counters checked for this request
+    checked_costs: Mutex<Vec<(String, LimitPeriod)>>,
+}
+
+impl StoreRateLimiterService {
+    pub fn new(store: Arc<dyn RateLimitStore>, fail_open: bool) -> Self {
+        Self {
+            store,
+            fail_open,
+            checked_costs: Mutex::new(vec![]),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl RateLimiterService for StoreRateLimiterService {
+    async fn check_rate_limit(
+        &self,
+        entity_id: &str,
+        config: &RateLimiterConfig,
+    ) -> Result<RateLimitResult, InterceptorError> {
+        let identifier = format!("rate_limit:{entity_id}");
+        let amount = match config.limit_target {
+            LimitTarget::Requests => 1.0,
+            LimitTarget::Cost => {
+                let mut checked_costs = self.checked_costs.lock().unwrap();
+                if !checked_costs
+                    .iter()
+                    .any(|(id, period)| *id == identifier && period == &config.period)
+                {
+                    checked_costs.push((identifier.clone(), config.period.clone()));
+                }
+                0.0
+            }
+        };
+        let current_usage = match self
+            .store
+            .increment(
+                &config.period,
+                &identifier,
+                config.limit_target.get_name(),
+                amount,
+            )
+            .await
+        {
+            Ok(usage) => usage,
+            Err(e) if self.fail_open => {
+                tracing::warn!("Allowing request without rate limit: {e}");
+                0.0
+            }
+            Err(e) => return Err(InterceptorError::ExecutionError(e.to_string())),
+        };
+
+        // Requests are counted including this one, costs only up to the previous request
+        let allowed = match config.limit_target {
+            LimitTarget::Requests => current_usage <= config.limit,
+            LimitTarget::Cost => current_usage < config.limit,
+        };
+        Ok(RateLimitResult {
+            allowed,
+            current_usage,
+            limit: config.limit,
+            remaining: (config.limit - current_usage).max(0.0),
+        })
+    }
+
+    async fn record_cost(&self, cost: f64) -> Result<(), InterceptorError> {
+        if cost <= 0.0 {
+            return Ok(());
+        }
+        let checked_costs = self.checked_costs.lock().unwrap().clone();
+        for (identifier, period) in checked_costs {
+            self.store
+                .increment(&period, &identifier, LimitTarget::Cost.get_name(), cost)
+                .await
+                .map_err(|e| InterceptorError::ExecutionError(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
+
+struct RateLimitStoreState {
+    store: Arc<dyn RateLimitStore>,
+    fail_open: bool,
+}
+
+static RATE_LIMIT_STORE: OnceLock<RateLimitStoreState> = OnceLock::new();
+
+/// Sets the process-wide rate limit store. Only the first call takes effect.
+pub fn init_rate_limit_store(config: &RateLimitStoreConfig) -> Result<(), RateLimitStoreError> {
+    let store: Arc<dyn RateLimitStore> = match &config.redis_url {
+        #[cfg(feature = "redis")]
+        Some(url) => Arc::new(RedisRateLimitStore::new(url, config.key_prefix.clone())?),
+        #[cfg(not(feature = "redis"))]
+        Some(_) => return Err(RateLimitStoreError::RedisDisabled),
+        None => Arc::new(InMemoryRateLimitStore::default()),
+    };
+    let _ = RATE_LIMIT_STORE.set(RateLimitStoreState {
+        store,
+        fail_open: config.fail_open,
+    });
+    Ok(())
+}
+
+/// Rate limiter counting in the process-wide store, in memory unless configured
+pub fn rate_limiter_service() -> StoreRateLimiterService {
+    let state = RATE_LIMIT_STORE.get_or_init(|| RateLimitStoreState {
+        store: Arc::new(InMemoryRateLimitStore::default()),
+        fail_open: true,
+    });
+    StoreRateLimiterService::new(state.store.clone(), state.fail_open)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::LimitEntity;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+
+    /// Counters shared by every service using it, like Redis is by replicas
+    #[derive(Default)]
+    struct MockStore {
+        counters: Mutex<HashMap<String, f64>>,
+        unavailable: AtomicBool,
+    }
+
+    #[async_trait::async_trait]
+    impl RateLimitStore for MockStore {
+        async fn increment(
+            &self,
+            period: &LimitPeriod,
+            identifier: &str,
+            key: &str,
+            amount: f64,
+        ) -> Result<f64, RateLimitStoreError> {
+            if self.unavailable.load(Ordering::SeqCst) {
+                return Err(RateLimitStoreError::Unavailable(
+                    "connection refused".into(),
+                ));
+            }
+            let mut counters = self.counters.lock().unwrap();
+            let counter = counters.entry(period.get_key(identifier, key)).or_default();
+            *counter += amount;
+            Ok(*counter)
+        }
+    }
+
+    fn config() -> RateLimiterConfig {
+        RateLimiterConfig {
+            limit: 3.0,
+            limit_target: LimitTarget::Requests,
+            limit_entity: LimitEntity::UserId,
+            period: LimitPeriod::Hour,
+            burst_protection: None,
+            action: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replicas_share_counters() {
+        let store = Arc::new(MockStore::default());
+        let replica_a = StoreRateLimiterService::new(store.clone(), false);
+        let replica_b = StoreRateLimiterService::new(store.clone(), true);
+        let config = config();
+
+        for (replica, usage) in [(&replica_a, 1.0), (&replica_b, 2.0), (&replica_a, 3.0)] {
+            let result = replica.check_rate_limit("user-1", &config).await.unwrap();
+            assert!(result.allowed);
+            assert_eq!(result.current_usage, usage);
+        }
+        let result = replica_b.check_rate_limit("user-1", &config).await.unwrap();
+        assert!(!result.allowed);
+        assert_eq!(result.remaining, 0.0);
+
+        // Other entities are counted separately
+        let result = replica_b.check_rate_limit("user-2", &config).await.unwrap();
+        assert_eq!(result.current_usage, 1.0);
+
+        // Without the store, replica A fails closed and replica B fails open
+        store.unavailable.store(true, Ordering::SeqCst);
+        assert!(replica_a.check_rate_limit("user-1", &config).await.is_err());
+        assert!(
+            replica_b
+                .check_rate_limit("user-1", &config)
+                .await
+                .unwrap()
+                .allowed
+        );
+    }
+
+    #[tokio::test]
+    async fn test_counting_recovers_after_store_errors() {
+        let store = Arc::new(MockStore::default());
+        let service = StoreRateLimiterService::new(store.clone(), false);
+        let config = config();
+
+        assert!(service.check_rate_limit("user-1", &config).await.is_ok());
+        store.unavailable.store(true, Ordering::SeqCst);
+        assert!(service.check_rate_limit("user-1", &config).await.is_err());
+        store.unavailable.store(false, Ordering::SeqCst);
+        let result = service.check_rate_limit("user-1", &config).await.unwrap();
+        assert!(result.allowed);
+        assert_eq!(result.current_usage, 2.0);
+    }
+
+    #[tokio::test]
+    async fn test_cost_limit_trips_after_recorded_costs() {
+        let store = Arc::new(MockStore::default());
+        let config = RateLimiterConfig {
+            limit: 1.0,
+            limit_target: LimitTarget::Cost,
+            ..config()
+        };
+
+        for usage in [0.0, 0.6] {
+            let request = StoreRateLimiterService::new(store.clone(), false);
+            let result = request.check_rate_limit("user-1", &config).await.unwrap();
+            assert!(result.allowed);
+            assert_eq!(result.current_usage, usage);
+            request.record_cost(0.6).await.unwrap();
+        }
+
+        let request = StoreRateLimiterService::new(store.clone(), false);
+        let result = request.check_rate_limit("user-1", &config).await.unwrap();
+        assert!(!result.allowed);
+        assert!((result.current_usage - 1.2).abs() < 1e-9);
+
+        // Costs are only added to the counters a request was checked against
+        let unchecked = StoreRateLimiterService::new(store.clone(), false);
+        unchecked.record_cost(5.0).await.unwrap();
+        let result = request.check_rate_limit("user-2", &config).await.unwrap();
+        assert_eq!(result.current_usage, 0.0);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    async fn test_redis_store_retries_connecting_after_failure() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        let store =
+            RedisRateLimitStore::new(&format!("redis://{addr}"), "test:".to_string()).unwrap();
+
+        // Redis is down, every call tries to connect again instead of keeping the failure
+        for _ in 0..2 {
+            assert!(store
+                .increment(&LimitPeriod::Hour, "rate_limit:user-1", "requests", 1.0)
+                .await
+                .is_err());
+            assert!(store.connection.get().is_none());
+        }
+    }
+}
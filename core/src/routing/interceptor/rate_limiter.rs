@@ -1,3 +1,6 @@
+use crate::routing::interceptor::rate_limit_store::{
+    InMemoryRateLimitStore, StoreRateLimiterService,
+};
 use crate::routing::interceptor::{Interceptor, InterceptorContext, InterceptorError};
 use crate::routing::{LimitEntity, LimitTarget};
 use crate::usage::LimitPeriod;
@@ -54,10 +57,25 @@ pub trait RateLimiterService: Send + Sync {
         entity_id: &str,
         config: &RateLimiterConfig,
     ) -> Result<RateLimitResult, InterceptorError>;
+
+    /// Adds the cost of the completed request to the cost limits it was checked against
+    async fn record_cost(&self, _cost: f64) -> Result<(), InterceptorError> {
+        Ok(())
+    }
 }
 
-/// In-memory rate limiter service implementation
-pub struct InMemoryRateLimiterService {}
+/// Counts the cost of a completed request against the cost limits it was checked against.
+/// The response is already produced, so failures are only logged.
+pub async fn record_rate_limit_cost(rate_limiter_service: &dyn RateLimiterService, cost: f64) {
+    if let Err(e) = rate_limiter_service.record_cost(cost).await {
+        tracing::warn!("Failed to count request cost against rate limits: {e}");
+    }
+}
+
+/// In-memory rate limiter service implementation, counting for this process only
+pub struct InMemoryRateLimiterService {
+    inner: StoreRateLimiterService,
+}
 
 impl Default for InMemoryRateLimiterService {
     fn default() -> Self {
@@ -67,7 +85,9 @@ impl Default for InMemoryRateLimiterService {
 
 impl InMemoryRateLimiterService {
     pub fn new() -> Self {
-        Self {}
+        Self {
+            inner: StoreRateLimiterService::new(Arc::new(InMemoryRateLimitStore::default()), true),
+        }
     }
 }
 
@@ -75,10 +95,14 @@ impl InMemoryRateLimiterService {
 impl RateLimiterService for InMemoryRateLimiterService {
     async fn check_rate_limit(
         &self,
-        _entity_id: &str,
-        _config: &RateLimiterConfig,
+        entity_id: &str,
+        config: &RateLimiterConfig,
     ) -> Result<RateLimitResult, InterceptorError> {
-        unimplemented!()
+        self.inner.check_rate_limit(entity_id, config).await
+    }
+
+    async fn record_cost(&self, cost: f64) -> Result<(), InterceptorError> {
+        self.inner.record_cost(cost).await
     }
 }
 
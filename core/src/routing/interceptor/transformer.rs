@@ -174,6 +174,7 @@ mod tests {
                 refusal: None,
                 tool_call_id: None,
                 cache_control: None,
+                name: None,
             }],
             ..Default::default()
         }
@@ -1,6 +1,12 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::{routing::RouterError, usage::ProviderMetrics};
+use tokio::sync::{Mutex, OnceCell};
+
+use crate::routing::RouterError;
+use crate::usage::ewma::DEFAULT_EWMA_HALF_LIFE;
+use crate::usage::{InMemoryStorage, ModelMetrics, ProviderMetrics};
 
 /// Trait for accessing metrics data needed for routing decisions
 #[async_trait::async_trait]
@@ -19,7 +25,7 @@ pub trait MetricsRepository {
         &self,
         provider: &str,
         model: &str,
-    ) -> Result<Option<crate::usage::ModelMetrics>, RouterError>;
+    ) -> Result<Option<ModelMetrics>, RouterError>;
 }
 
 /// Simple in-memory implementation of MetricsRepository
@@ -51,7 +57,7 @@ impl MetricsRepository for InMemoryMetricsRepository {
         &self,
         provider: &str,
         model: &str,
-    ) -> Result<Option<crate::usage::ModelMetrics>, RouterError> {
+    ) -> Result<Option<ModelMetrics>, RouterError> {
         Ok(self
             .metrics
             .get(provider)
@@ -59,3 +65,297 @@ impl MetricsRepository for InMemoryMetricsRepository {
             .cloned())
     }
 }
+
+/// Metrics of the gateway's in-memory usage counters, read once on first use
+pub struct StorageMetricsRepository {
+    storage: Option<Arc<Mutex<InMemoryStorage>>>,
+    metrics: OnceCell<InMemoryMetricsRepository>,
+}
+
+impl StorageMetricsRepository {
+    pub fn new(storage: Option<Arc<Mutex<InMemoryStorage>>>) -> Self {
+        Self {
+            storage,
+            metrics: OnceCell::new(),
+        }
+    }
+
+    async fn metrics(&self) -> &InMemoryMetricsRepository {
+        self.metrics
+            .get_or_init(|| async {
+                let metrics = match &self.storage {
+                    Some(storage) => storage.lock().await.get_all_counters().await,
+                    None => BTreeMap::new(),
+                };
+                InMemoryMetricsRepository::new(metrics)
+            })
+            .await
+    }
+}
+
+#[async_trait::async_trait]
+impl MetricsRepository for StorageMetricsRepository {
+    async fn get_metrics(&self) -> Result<BTreeMap<String, ProviderMetrics>, RouterError> {
+        self.metrics().await.get_metrics().await
+    }
+
+    async fn get_provider_metrics(
+        &self,
+        provider: &str,
+    ) -> Result<Option<ProviderMetrics>, RouterError> {
+        self.metrics().await.get_provider_metrics(provider).await
+    }
+
+    async fn get_model_metrics(
+        &self,
+        provider: &str,
+        model: &str,
+    ) -> Result<Option<ModelMetrics>, RouterError> {
+        self.metrics()
+            .await
+            .get_model_metrics(provider, model)
+            .await
+    }
+}
+
+/// How long metric reads are reused by routing before the repository is queried again.
+/// Metric-sorted routes then don't pay a repository round trip on every request, at the
+/// cost of routing on metrics up to `ttl_ms` old. `0` disables the cache.
+///
+/// ```yaml
+/// routing:
+///   metrics_cache:
+///     ttl_ms: 5000
+/// ```
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct MetricsCacheConfig {
+    pub ttl_ms: u64,
+}
+
+impl Default for MetricsCacheConfig {
+    fn default() -> Self {
+        Self { ttl_ms: 5000 }
+    }
+}
+
+/// Half-life of the latency, ttft and error rate moving averages routed on with the `Ewma`
+/// metrics duration. Shorter half-lives react faster to changes but are noisier.
+///
+/// ```yaml
+/// routing:
+///   ewma:
+///     half_life_secs: 300
+/// ```
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+#[serde(default)]
+pub struct EwmaConfig {
+    pub half_life_secs: u64,
+}
+
+impl Default for EwmaConfig {
+    fn default() -> Self {
+        Self {
+            half_life_secs: DEFAULT_EWMA_HALF_LIFE.as_secs(),
+        }
+    }
+}
+
+impl EwmaConfig {
+    pub fn half_life(&self) -> Duration {
+        Duration::from_secs(self.half_life_secs)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum MetricsCacheKey {
+    All,
+    Provider(String),
+    Model(String, String),
+}
+
+#[derive(Clone)]
+enum CachedRead {
+    All(BTreeMap<String, ProviderMetrics>),
+    Provider(Option<ProviderMetrics>),
+    Model(Option<ModelMetrics>),
+}
+
+/// Metric reads by provider and model, with when they were read. Reads hold the metrics of
+/// every duration, so one entry serves routes on any duration.
+#[derive(Default)]
+pub struct MetricsCache {
+    reads: std::sync::Mutex<HashMap<MetricsCacheKey, (Instant, CachedRead)>>,
+}
+
+impl MetricsCache {
+    fn get(&self, key: &MetricsCacheKey, ttl: Duration) -> Option<CachedRead> {
+        let reads = self.reads.lock().unwrap_or_else(|e| e.into_inner());
+        reads
+            .get(key)
+            .filter(|(read_at, _)| read_at.elapsed() < ttl)
+            .map(|(_, read)| read.clone())
+    }
+
+    fn insert(&self, key: MetricsCacheKey, read: CachedRead) {
+        let mut reads = self.reads.lock().unwrap_or_else(|e| e.into_inner());
+        reads.insert(key, (Instant::now(), read));
+    }
+}
+
+pub fn metrics_cache() -> &'static MetricsCache {
+    static CACHE: std::sync::OnceLock<MetricsCache> = std::sync::OnceLock::new();
+    CACHE.get_or_init(MetricsCache::default)
+}
+
+/// Serves metric reads from `cache` while they're fresher than the configured TTL, and reads
+/// `inner` otherwise
+pub struct CachedMetricsRepository<'a, M> {
+    inner: &'a M,
+    cache: &'a MetricsCache,
+    ttl: Duration,
+}
+
+impl<'a, M: MetricsRepository + Send + Sync> CachedMetricsRepository<'a, M> {
+    pub fn new(inner: &'a M, cache: &'a MetricsCache, config: &MetricsCacheConfig) -> Self {
+        Self {
+            inner,
+            cache,
+            ttl: Duration::from_millis(config.ttl_ms),
+        }
+    }
+
+    async fn read<F>(&self, key: MetricsCacheKey, read: F) -> Result<CachedRead, RouterError>
+    where
+        F: std::future::Future<Output = Result<CachedRead, RouterError>>,
+    {
+        if let Some(cached) = self.cache.get(&key, self.ttl) {
+            return Ok(cached);
+        }
+        let value = read.await?;
+        if !self.ttl.is_zero() {
+            self.cache.insert(key, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+#[async_trait::async_trait]
+impl<M: MetricsRepository + Send + Sync> MetricsRepository for CachedMetricsRepository<'_, M> {
+    async fn get_metrics(&self) -> Result<BTreeMap<String, ProviderMetrics>, RouterError> {
+        let read = self.read(MetricsCacheKey::All, async {
+            self.inner.get_metrics().await.map(CachedRead::All)
+        });
+        match read.await? {
+            CachedRead::All(metrics) => Ok(metrics),
+            _ => unreachable!("metrics cache entry of another kind"),
+        }
+    }
+
+    async fn get_provider_metrics(
+        &self,
+        provider: &str,
+    ) -> Result<Option<ProviderMetrics>, RouterError> {
+        let key = MetricsCacheKey::Provider(provider.to_string());
+        let read = self.read(key, async {
+            self.inner
+                .get_provider_metrics(provider)
+                .await
+                .map(CachedRead::Provider)
+        });
+        match read.await? {
+            CachedRead::Provider(metrics) => Ok(metrics),
+            _ => unreachable!("metrics cache entry of another kind"),
+        }
+    }
+
+    async fn get_model_metrics(
+        &self,
+        provider: &str,
+        model: &str,
+    ) -> Result<Option<ModelMetrics>, RouterError> {
+        let key = MetricsCacheKey::Model(provider.to_string(), model.to_string());
+        let read = self.read(key, async {
+            self.inner
+                .get_model_metrics(provider, model)
+                .await
+                .map(CachedRead::Model)
+        });
+        match read.await? {
+            CachedRead::Model(metrics) => Ok(metrics),
+            _ => unreachable!("metrics cache entry of another kind"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Counts the reads of an empty repository
+    #[derive(Default)]
+    struct CountingRepository(AtomicUsize);
+
+    #[async_trait::async_trait]
+    impl MetricsRepository for CountingRepository {
+        async fn get_metrics(&self) -> Result<BTreeMap<String, ProviderMetrics>, RouterError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(BTreeMap::new())
+        }
+
+        async fn get_provider_metrics(
+            &self,
+            _provider: &str,
+        ) -> Result<Option<ProviderMetrics>, RouterError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(None)
+        }
+
+        async fn get_model_metrics(
+            &self,
+            _provider: &str,
+            _model: &str,
+        ) -> Result<Option<ModelMetrics>, RouterError> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Ok(None)
+        }
+    }
+
+    async fn route(repository: &(impl MetricsRepository + Send + Sync)) {
+        let models = vec!["openai/gpt-4o-mini".to_string(), "anthropic/*".to_string()];
+        crate::routing::strategy::metric::route(
+            &models,
+            &crate::routing::strategy::metric::MetricSelector::Latency,
+            None,
+            repository,
+            None,
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_routes_within_ttl_reuse_metric_reads() {
+        let inner = CountingRepository::default();
+        let cache = MetricsCache::default();
+        let repository =
+            CachedMetricsRepository::new(&inner, &cache, &MetricsCacheConfig::default());
+
+        route(&repository).await;
+        assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+        route(&repository).await;
+        route(&repository).await;
+        assert_eq!(inner.0.load(Ordering::SeqCst), 2);
+
+        // Without a TTL every route reads the repository
+        let inner = CountingRepository::default();
+        let repository =
+            CachedMetricsRepository::new(&inner, &cache, &MetricsCacheConfig { ttl_ms: 0 });
+        route(&repository).await;
+        route(&repository).await;
+        assert_eq!(inner.0.load(Ordering::SeqCst), 4);
+    }
+}
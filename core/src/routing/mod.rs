@@ -1,19 +1,27 @@
+use crate::error::GatewayError;
 use crate::model::ModelMetadataFactory;
+use crate::routing::interceptor::InterceptorState;
 use crate::routing::metrics::MetricsRepository;
-use vllora_telemetry::events::JsonValue;
+use crate::GatewayApiError;
 // use crate::routing::strategy::script::ScriptError;
 // use crate::routing::strategy::script::ScriptStrategy;
 use crate::routing::strategy::conditional::ConditionalRouter;
 use crate::usage::LimitPeriod;
+use futures::future::join_all;
 use std::collections::HashMap;
 use std::fmt::Display;
 use std::sync::Arc;
 use thiserror::Error;
-use valuable::Valuable;
 use vllora_llm::types::gateway::{ChatCompletionRequest, Extra};
 
+pub mod audit;
+pub mod decision_log;
+pub mod explain;
+pub mod fallback_response;
 pub mod interceptor;
 pub mod metrics;
+pub mod regression;
+pub mod resolution;
 pub mod strategy;
 
 #[derive(Error, Debug)]
@@ -48,11 +56,145 @@ pub enum RouterError {
     InterceptorError(#[from] interceptor::InterceptorError),
 }
 
+/// Header that forces a request to a specific model, bypassing routing.
+pub const FORCE_MODEL_HEADER: &str = "x-vllora-force-model";
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
+pub struct RoutingConfig {
+    /// Whether requests may bypass routing via `x-vllora-force-model` or `extra.force_model`.
+    /// Disabled by default so routing policies stay enforced unless an operator opts in.
+    #[serde(default = "default_allow_force_model")]
+    pub allow_force_model: bool,
+    /// Larger-context models to retry on, in order, when a model rejects a request
+    /// for exceeding its context window. Keyed by the originally requested model.
+    #[serde(default)]
+    pub context_upgrades: HashMap<String, Vec<String>>,
+    /// Where routing decisions are recorded for auditing
+    #[serde(default)]
+    pub audit: audit::RoutingAuditConfig,
+    /// Model or router used by requests that don't name a model
+    #[serde(default)]
+    pub default_model: Option<String>,
+    /// Which target failures move a request on to the next fallback target
+    #[serde(default)]
+    pub fallback_mode: FallbackMode,
+    /// Opt-in canned responses returned when every target failed, keyed by the model or
+    /// router requested. Routes without one return the last error.
+    #[serde(default)]
+    pub fallback_responses: HashMap<String, fallback_response::FallbackResponse>,
+    /// Structured logs of metric based routing decisions
+    #[serde(default)]
+    pub decision_log: decision_log::RoutingDecisionLogConfig,
+    /// How long metric reads are reused by metric based routes
+    #[serde(default)]
+    pub metrics_cache: metrics::MetricsCacheConfig,
+    /// Half-life of the moving averages used by the `Ewma` metrics duration
+    #[serde(default)]
+    pub ewma: metrics::EwmaConfig,
+    /// Routing config of a project by slug, used instead of this one for its requests
+    ///
+    /// ```yaml
+    /// routing:
+    ///   default_model: router/shared
+    ///   projects:
+    ///     support-bot:
+    ///       default_model: router/support
+    /// ```
+    #[serde(default)]
+    pub projects: HashMap<String, RoutingConfig>,
+}
+
+#[derive(serde::Deserialize, serde::Serialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FallbackMode {
+    /// Fall back on provider failures, but return request errors (invalid or denied
+    /// requests) right away since every other target would reject them as well
+    #[default]
+    StopOnRequestError,
+    /// Try every target whatever the error
+    BestEffort,
+}
+
+impl FallbackMode {
+    pub fn falls_back_on(&self, error: &GatewayApiError) -> bool {
+        match self {
+            FallbackMode::StopOnRequestError => !error.is_request_error(),
+            FallbackMode::BestEffort => true,
+        }
+    }
+}
+
+fn default_allow_force_model() -> bool {
+    false
+}
+
+impl RoutingConfig {
+    /// Routing config of the project with `project_slug`. Projects without their own use
+    /// this one, never another project's.
+    pub fn for_project(&self, project_slug: Option<&str>) -> RoutingConfig {
+        match project_slug.and_then(|slug| self.projects.get(slug)) {
+            Some(config) => config.clone(),
+            None => RoutingConfig {
+                projects: HashMap::new(),
+                ..self.clone()
+            },
+        }
+    }
+
+    /// Next configured upgrade for `model` that has not been attempted yet
+    pub fn next_context_upgrade(&self, model: &str, attempted: &[String]) -> Option<&String> {
+        self.context_upgrades
+            .get(model)?
+            .iter()
+            .find(|upgrade| !attempted.contains(upgrade))
+    }
+
+    /// Sets the configured default model on a request without one. Returns whether it did,
+    /// requests without a model are rejected when no default is configured.
+    pub fn apply_default_model(
+        &self,
+        request: &mut ChatCompletionRequest,
+    ) -> Result<bool, GatewayError> {
+        if !request.model.is_empty() {
+            return Ok(false);
+        }
+
+        match &self.default_model {
+            Some(model) => {
+                request.model = model.clone();
+                Ok(true)
+            }
+            None => Err(GatewayError::InvalidRequest(
+                "missing field `model`".to_string(),
+            )),
+        }
+    }
+}
+
+impl Default for RoutingConfig {
+    fn default() -> Self {
+        Self {
+            allow_force_model: default_allow_force_model(),
+            context_upgrades: HashMap::new(),
+            audit: audit::RoutingAuditConfig::default(),
+            default_model: None,
+            fallback_mode: FallbackMode::default(),
+            fallback_responses: HashMap::new(),
+            decision_log: decision_log::RoutingDecisionLogConfig::default(),
+            metrics_cache: metrics::MetricsCacheConfig::default(),
+            ewma: metrics::EwmaConfig::default(),
+            projects: HashMap::new(),
+        }
+    }
+}
+
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
 pub enum MetricsDuration {
     Total,
     Last15Minutes,
     LastHour,
+    /// Exponentially weighted moving average, see [`metrics::EwmaConfig`]
+    Ewma,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug, Clone)]
@@ -65,6 +207,12 @@ pub struct LlmRouter {
     pub targets: Vec<HashMap<String, serde_json::Value>>,
     #[serde(default)]
     pub metrics_duration: Option<MetricsDuration>,
+    /// Project whose model inventory the candidate models are looked up in
+    #[serde(skip)]
+    pub project_id: Option<uuid::Uuid>,
+    /// Decision log of the project routing the request
+    #[serde(skip)]
+    pub decision_log: decision_log::RoutingDecisionLogConfig,
 }
 
 impl LlmRouter {
@@ -74,9 +222,49 @@ impl LlmRouter {
             strategy,
             targets: Vec::new(),
             metrics_duration: None,
+            project_id: None,
+            decision_log: decision_log::RoutingDecisionLogConfig::default(),
         }
     }
 
+    pub fn with_project_id(mut self, project_id: uuid::Uuid) -> Self {
+        self.project_id = Some(project_id);
+        self
+    }
+
+    pub fn with_decision_log(mut self, config: decision_log::RoutingDecisionLogConfig) -> Self {
+        self.decision_log = config;
+        self
+    }
+
+    /// Input token prices of the candidate models, keyed by both the requested and the
+    /// qualified model name. Used to break ties between equally performing models. Models
+    /// are looked up concurrently, as they are on the routing path of every request.
+    async fn candidate_prices(
+        models: &[String],
+        project_id: Option<&uuid::Uuid>,
+        model_metadata_factory: &Arc<Box<dyn ModelMetadataFactory>>,
+    ) -> HashMap<String, f64> {
+        let lookups = models
+            .iter()
+            .filter(|m| !m.ends_with("/*"))
+            .map(|model| async move {
+                let metadata = model_metadata_factory
+                    .get_model_metadata(model, false, false, project_id)
+                    .await
+                    .ok()?;
+                Some((model, metadata))
+            });
+
+        let mut prices = HashMap::new();
+        for (model, metadata) in join_all(lookups).await.into_iter().flatten() {
+            let price = metadata.price.per_input_token();
+            prices.insert(model.clone(), price);
+            prices.insert(metadata.qualified_model_name(), price);
+        }
+        prices
+    }
+
     pub fn with_targets(mut self, targets: Vec<HashMap<String, serde_json::Value>>) -> Self {
         self.targets = targets;
         self
@@ -89,14 +277,39 @@ impl LlmRouter {
 }
 
 /// Extended routing result that includes interceptor state
-#[derive(Debug, Clone)]
+#[derive(serde::Serialize, Debug, Clone)]
 pub struct RoutingResult {
     pub targets: Targets,
+    /// Name of the conditional route that matched, if any
+    pub matched_route: Option<String>,
+    pub interceptor_state: Option<InterceptorState>,
+    /// Conditional routes evaluated to pick the targets
+    pub route_evaluations: Vec<explain::RouteEvaluation>,
 }
 
 impl RoutingResult {
     pub fn new(targets: Targets) -> Self {
-        Self { targets }
+        Self {
+            targets,
+            matched_route: None,
+            interceptor_state: None,
+            route_evaluations: vec![],
+        }
+    }
+
+    pub fn with_route_evaluations(mut self, evaluations: Vec<explain::RouteEvaluation>) -> Self {
+        self.route_evaluations = evaluations;
+        self
+    }
+
+    pub fn with_matched_route(mut self, route: Option<String>) -> Self {
+        self.matched_route = route;
+        self
+    }
+
+    pub fn with_interceptor_state(mut self, state: InterceptorState) -> Self {
+        self.interceptor_state = Some(state);
+        self
     }
 }
 
@@ -117,6 +330,9 @@ pub enum RoutingStrategy {
     // },
     Optimized {
         metric: strategy::metric::MetricSelector,
+        /// Candidates exceeding these error rate or latency bounds are skipped
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ceiling: Option<strategy::metric::MetricCeiling>,
     },
     /// Conditional routing based on request or context conditions
     Conditional {
@@ -141,6 +357,7 @@ impl Default for RoutingStrategy {
     fn default() -> Self {
         Self::Optimized {
             metric: strategy::metric::MetricSelector::default(),
+            ceiling: None,
         }
     }
 }
@@ -343,9 +560,10 @@ pub trait RouteStrategy {
     ) -> Result<RoutingResult, RouterError>;
 }
 
-#[async_trait::async_trait]
-impl RouteStrategy for LlmRouter {
-    async fn route<M: MetricsRepository + Send + Sync>(
+impl LlmRouter {
+    /// Picks the targets of a request. Unlike [`RouteStrategy::route`] a conditional
+    /// strategy without a matching route resolves to no targets instead of failing.
+    pub(crate) async fn resolve_route<M: MetricsRepository + Send + Sync>(
         &self,
         request: ChatCompletionRequest,
         extra: Option<&Extra>,
@@ -355,6 +573,9 @@ impl RouteStrategy for LlmRouter {
         interceptor_factory: Box<dyn interceptor::InterceptorFactory>,
     ) -> Result<RoutingResult, RouterError> {
         // Routing logic only, no interceptors
+        let mut matched_route = None;
+        let mut interceptor_state = None;
+        let mut route_evaluations = vec![];
         let targets = match &self.strategy {
             RoutingStrategy::Fallback => self.targets.clone(),
             RoutingStrategy::Random => {
@@ -383,7 +604,7 @@ impl RouteStrategy for LlmRouter {
                 };
                 vec![target]
             }
-            RoutingStrategy::Optimized { metric } => {
+            RoutingStrategy::Optimized { metric, ceiling } => {
                 let models = self
                     .targets
                     .iter()
@@ -392,30 +613,59 @@ impl RouteStrategy for LlmRouter {
                             .and_then(|v| v.as_str().map(|s| s.to_string()))
                     })
                     .collect::<Vec<_>>();
-                let model = strategy::metric::route(
+                let prices = Self::candidate_prices(
                     &models,
-                    metric,
-                    self.metrics_duration.as_ref(),
-                    metrics_repository,
-                    None,
-                    None,
+                    self.project_id.as_ref(),
+                    &model_metadata_factory,
                 )
-                .await?;
-                vec![HashMap::from([(
-                    "model".to_string(),
-                    serde_json::Value::String(model),
-                )])]
+                .await;
+                let models = match ceiling {
+                    Some(ceiling) => match strategy::metric::route_within_ceiling(
+                        &models,
+                        metric,
+                        self.metrics_duration.as_ref(),
+                        metrics_repository,
+                        ceiling,
+                        Some(&prices),
+                    )
+                    .await?
+                    {
+                        Some(model) => vec![model],
+                        None => ceiling.fallback_models.clone(),
+                    },
+                    None => vec![
+                        strategy::metric::route(
+                            &models,
+                            metric,
+                            self.metrics_duration.as_ref(),
+                            metrics_repository,
+                            None,
+                            None,
+                            Some(&prices),
+                        )
+                        .await?,
+                    ],
+                };
+                models
+                    .into_iter()
+                    .map(|model| {
+                        HashMap::from([("model".to_string(), serde_json::Value::String(model))])
+                    })
+                    .collect()
             }
             RoutingStrategy::Conditional { routing } => {
                 let router = ConditionalRouter {
                     routing: routing.clone(),
                 };
                 let headers = HashMap::new(); // TODO: pass real headers
-                let target_opt = router
-                    .get_target(interceptor_factory, &request, &headers, &metadata, extra)
+                let resolution = router
+                    .resolve(interceptor_factory, &request, &headers, &metadata, extra)
                     .await;
+                matched_route = resolution.route;
+                interceptor_state = Some(resolution.interceptor_state);
+                route_evaluations = resolution.evaluations;
 
-                match target_opt {
+                match resolution.targets {
                     Some(TargetSpec::List(targets)) => targets.clone(),
                     Some(TargetSpec::Single(model)) => {
                         vec![HashMap::from([(
@@ -438,10 +688,8 @@ impl RouteStrategy for LlmRouter {
                                         .map_err(|e| {
                                             RouterError::MetricRouterError(e.to_string())
                                         })?;
-                                    let span = tracing::Span::current();
-                                    span.record(
-                                        "router.metric_resolution",
-                                        JsonValue(&serde_json::json!({"candidates": [], "best_model": model.qualified_model_name(), "metric": "cost"})).as_value(),
+                                    decision_log::record_metric_resolution(
+                                        serde_json::json!({"candidates": [], "best_model": model.qualified_model_name(), "metric": "cost"}),
                                     );
                                     model.qualified_model_name()
                                 }
@@ -455,6 +703,12 @@ impl RouteStrategy for LlmRouter {
                                             filters.insert(metric.clone(), value.clone());
                                         }
                                     }
+                                    let prices = Self::candidate_prices(
+                                        any,
+                                        self.project_id.as_ref(),
+                                        &model_metadata_factory,
+                                    )
+                                    .await;
                                     strategy::metric::route(
                                         any,
                                         metric,
@@ -462,6 +716,7 @@ impl RouteStrategy for LlmRouter {
                                         metrics_repository,
                                         minimize,
                                         Some(&filters),
+                                        Some(&prices),
                                     )
                                     .await?
                                 }
@@ -474,15 +729,51 @@ impl RouteStrategy for LlmRouter {
                             serde_json::Value::String(model),
                         )])]
                     }
-                    None => {
-                        return Err(RouterError::MetricRouterError(
-                            "No conditional route matched".to_string(),
-                        ))
-                    }
+                    None => vec![],
                 }
             }
         };
-        Ok(RoutingResult::new(targets))
+        let result = RoutingResult::new(targets)
+            .with_matched_route(matched_route)
+            .with_route_evaluations(route_evaluations);
+        Ok(match interceptor_state {
+            Some(state) => result.with_interceptor_state(state),
+            None => result,
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl RouteStrategy for LlmRouter {
+    async fn route<M: MetricsRepository + Send + Sync>(
+        &self,
+        request: ChatCompletionRequest,
+        extra: Option<&Extra>,
+        model_metadata_factory: Arc<Box<dyn ModelMetadataFactory>>,
+        metadata: HashMap<String, serde_json::Value>,
+        metrics_repository: &M,
+        interceptor_factory: Box<dyn interceptor::InterceptorFactory>,
+    ) -> Result<RoutingResult, RouterError> {
+        let result = decision_log::scope(
+            self.decision_log.clone(),
+            self.resolve_route(
+                request,
+                extra,
+                model_metadata_factory,
+                metadata,
+                metrics_repository,
+                interceptor_factory,
+            ),
+        )
+        .await?;
+        if matches!(self.strategy, RoutingStrategy::Conditional { .. })
+            && result.matched_route.is_none()
+        {
+            return Err(RouterError::MetricRouterError(
+                "No conditional route matched".to_string(),
+            ));
+        }
+        Ok(result)
     }
 }
 
@@ -494,6 +785,9 @@ mod tests {
     use crate::metadata::services::model::ModelServiceImpl;
     use crate::metadata::test_utils::setup_test_database;
     use crate::routing::interceptor::InterceptorFactory;
+    use vllora_llm::types::gateway::ChatCompletionRequestWithTools;
+    use vllora_llm::types::models::{InferenceProvider, ModelMetadata};
+    use vllora_llm::types::provider::{InferenceModelProvider, ModelPrice};
 
     use super::*;
 
@@ -503,9 +797,12 @@ mod tests {
             name: "dynamic".to_string(),
             strategy: RoutingStrategy::Optimized {
                 metric: strategy::metric::MetricSelector::Ttft,
+                ceiling: None,
             },
             targets: vec![],
             metrics_duration: None,
+            project_id: None,
+            decision_log: Default::default(),
         };
 
         eprintln!("{}", serde_json::to_string_pretty(&router).unwrap());
@@ -538,6 +835,8 @@ mod tests {
                 ]),
             ],
             metrics_duration: None,
+            project_id: None,
+            decision_log: Default::default(),
         };
 
         eprintln!("{}", serde_json::to_string_pretty(&router).unwrap());
@@ -573,6 +872,7 @@ mod tests {
                     },
                     last_15_minutes: Metrics::default(),
                     last_hour: Metrics::default(),
+                    ewma: Metrics::default(),
                 },
             },
         );
@@ -587,12 +887,15 @@ mod tests {
             name: "test_router".to_string(),
             strategy: RoutingStrategy::Optimized {
                 metric: strategy::metric::MetricSelector::Latency,
+                ceiling: None,
             },
             targets: vec![HashMap::from([(
                 "model".to_string(),
                 serde_json::Value::String("openai/gpt-4".to_string()),
             )])],
             metrics_duration: Some(MetricsDuration::Total),
+            project_id: None,
+            decision_log: Default::default(),
         };
 
         // Test routing
@@ -752,6 +1055,8 @@ mod tests {
             },
             targets: vec![],
             metrics_duration: None,
+            project_id: None,
+            decision_log: Default::default(),
         };
         let db_pool = setup_test_database();
         let factory = Box::new(MockFactory { result: true }) as Box<dyn InterceptorFactory>;
@@ -847,4 +1152,285 @@ mod tests {
 
         let _conditional_router: ConditionalRouting = serde_json::from_str(&json).unwrap();
     }
+
+    #[test]
+    fn test_model_less_request_uses_default_model() {
+        let config: RoutingConfig = serde_json::from_value(serde_json::json!({
+            "default_model": "router/cost-optimized"
+        }))
+        .unwrap();
+        let request: ChatCompletionRequestWithTools<RoutingStrategy> =
+            serde_json::from_value(serde_json::json!({
+                "messages": [{"role": "user", "content": "Hi"}]
+            }))
+            .unwrap();
+
+        let mut defaulted = request.request.clone();
+        assert!(config.apply_default_model(&mut defaulted).unwrap());
+        assert_eq!(defaulted.model, "router/cost-optimized");
+
+        // Requests naming a model keep it
+        let mut named = ChatCompletionRequest {
+            model: "openai/gpt-4o".to_string(),
+            ..Default::default()
+        };
+        assert!(!config.apply_default_model(&mut named).unwrap());
+        assert_eq!(named.model, "openai/gpt-4o");
+
+        // Without a default the request is still rejected
+        let mut missing = request.request;
+        assert!(matches!(
+            RoutingConfig::default().apply_default_model(&mut missing),
+            Err(GatewayError::InvalidRequest(_))
+        ));
+    }
+
+    #[test]
+    fn test_project_routing_config_is_not_used_for_other_projects() {
+        let config: RoutingConfig = serde_json::from_value(serde_json::json!({
+            "default_model": "router/shared",
+            "projects": {
+                "project-a": {
+                    "default_model": "router/project-a",
+                    "allow_force_model": true,
+                    "context_upgrades": {"openai/gpt-4o-mini": ["openai/gpt-4o"]},
+                    "decision_log": {"enabled": false}
+                }
+            }
+        }))
+        .unwrap();
+        let default_model = |project_slug: Option<&str>| {
+            let mut request = ChatCompletionRequest::default();
+            config
+                .for_project(project_slug)
+                .apply_default_model(&mut request)
+                .unwrap();
+            request.model
+        };
+
+        assert_eq!(default_model(Some("project-a")), "router/project-a");
+        assert_eq!(default_model(Some("project-b")), "router/shared");
+        assert_eq!(default_model(None), "router/shared");
+
+        let project_a = config.for_project(Some("project-a"));
+        assert!(project_a.allow_force_model);
+        assert!(project_a
+            .next_context_upgrade("openai/gpt-4o-mini", &[])
+            .is_some());
+        assert!(!project_a.decision_log.enabled);
+        let project_b = config.for_project(Some("project-b"));
+        assert!(!project_b.allow_force_model);
+        assert!(project_b.decision_log.enabled);
+        assert!(project_b
+            .next_context_upgrade("openai/gpt-4o-mini", &[])
+            .is_none());
+        assert!(project_b.projects.is_empty());
+    }
+
+    #[test]
+    fn test_fallback_stops_on_request_errors() {
+        use vllora_llm::client::error::classify_status;
+
+        let error = |status: u16| {
+            GatewayApiError::GatewayError(GatewayError::from_provider_error(
+                classify_status(status).unwrap(),
+                format!("provider returned {status}"),
+            ))
+        };
+
+        let mode = RoutingConfig::default().fallback_mode;
+        assert!(!mode.falls_back_on(&error(400)));
+        assert!(mode.falls_back_on(&error(503)));
+        assert!(mode.falls_back_on(&error(429)));
+
+        assert!(FallbackMode::BestEffort.falls_back_on(&error(400)));
+    }
+
+    /// Metadata of any OpenAI model, priced from `prices` by model name
+    struct PricedModels {
+        prices: HashMap<&'static str, f64>,
+    }
+
+    #[async_trait::async_trait]
+    impl ModelMetadataFactory for PricedModels {
+        async fn get_model_metadata(
+            &self,
+            model_name: &str,
+            _include_parameters: bool,
+            _include_benchmark: bool,
+            _project_id: Option<&uuid::Uuid>,
+        ) -> Result<ModelMetadata, GatewayApiError> {
+            let model = model_name.trim_start_matches("openai/");
+            let price = self.prices.get(model).copied().unwrap_or_default();
+            Ok(ModelMetadata {
+                model: model.to_string(),
+                inference_provider: InferenceProvider {
+                    provider: InferenceModelProvider::OpenAI,
+                    model_name: model.to_string(),
+                    endpoint: None,
+                    custom_inference_api_type: None,
+                },
+                price: ModelPrice::Completion(
+                    serde_json::from_value(serde_json::json!({
+                        "per_input_token": price,
+                        "per_output_token": price,
+                    }))
+                    .unwrap(),
+                ),
+                ..Default::default()
+            })
+        }
+
+        async fn get_cheapest_model_metadata(
+            &self,
+            _model_names: &[String],
+        ) -> Result<ModelMetadata, GatewayApiError> {
+            unimplemented!()
+        }
+
+        async fn get_models_by_name(
+            &self,
+            _model_name: &str,
+            _project_id: Option<&uuid::Uuid>,
+        ) -> Result<Vec<ModelMetadata>, GatewayApiError> {
+            unimplemented!()
+        }
+
+        async fn get_top_by_ranking(
+            &self,
+            _ranking_name: &str,
+            _top: u8,
+        ) -> Result<Vec<ModelMetadata>, GatewayApiError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_optimized_route_records_candidates_with_prices() {
+        use crate::routing::metrics::InMemoryMetricsRepository;
+        use crate::usage::{Metrics, ModelMetrics, ProviderMetrics, TimeMetrics};
+        use std::collections::BTreeMap;
+        use tracing::Instrument;
+        use vllora_telemetry::test_utils::RecordingLayer;
+
+        let equal_latency = || ModelMetrics {
+            metrics: TimeMetrics {
+                total: Metrics {
+                    requests: Some(100.0),
+                    latency: Some(800.0),
+                    ..Default::default()
+                },
+                last_15_minutes: Metrics::default(),
+                last_hour: Metrics::default(),
+                ewma: Metrics::default(),
+            },
+        };
+        let metrics_repo = InMemoryMetricsRepository::new(BTreeMap::from([(
+            "openai".to_string(),
+            ProviderMetrics {
+                models: BTreeMap::from([
+                    ("gpt-4o".to_string(), equal_latency()),
+                    ("gpt-4o-mini".to_string(), equal_latency()),
+                ]),
+            },
+        )]));
+        let model_metadata_factory = Arc::new(Box::new(PricedModels {
+            prices: HashMap::from([("gpt-4o", 2.5), ("gpt-4o-mini", 0.15)]),
+        }) as Box<dyn ModelMetadataFactory>);
+
+        let router = LlmRouter {
+            name: "optimized".to_string(),
+            strategy: RoutingStrategy::Optimized {
+                metric: strategy::metric::MetricSelector::Latency,
+                ceiling: None,
+            },
+            targets: ["openai/gpt-4o", "openai/gpt-4o-mini"]
+                .into_iter()
+                .map(|model| HashMap::from([("model".to_string(), serde_json::json!(model))]))
+                .collect(),
+            metrics_duration: Some(MetricsDuration::Total),
+            project_id: None,
+            decision_log: Default::default(),
+        };
+
+        struct DummyFactory;
+        impl interceptor::InterceptorFactory for DummyFactory {
+            fn create_interceptor(
+                &self,
+                _spec: &InterceptorSpec,
+            ) -> Result<Arc<dyn interceptor::Interceptor>, interceptor::InterceptorError>
+            {
+                Err(interceptor::InterceptorError::ExecutionError(
+                    "DummyFactory: no interceptors".to_string(),
+                ))
+            }
+        }
+
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+        let span = tracing::info_span!("route", "router.metric_resolution" = tracing::field::Empty);
+        let result = router
+            .route(
+                ChatCompletionRequest::default(),
+                None,
+                model_metadata_factory,
+                HashMap::new(),
+                &metrics_repo,
+                Box::new(DummyFactory),
+            )
+            .instrument(span)
+            .await
+            .unwrap();
+
+        // Equal latency, so the cheaper model wins
+        assert_eq!(result.targets[0]["model"], "openai/gpt-4o-mini");
+
+        let recorded = recorded.values("router.metric_resolution");
+        assert_eq!(recorded.len(), 1);
+        let resolution = &recorded[0];
+        assert!(resolution.contains("openai/gpt-4o-mini"));
+        assert!(resolution.contains("openai/gpt-4o\""));
+        assert!(resolution.contains("price"));
+        assert!(resolution.contains("0.15"));
+        assert!(resolution.contains("2.5"));
+    }
+
+    #[tokio::test]
+    async fn test_project_models_are_not_visible_to_other_projects() {
+        use crate::metadata::models::model::DbNewModel;
+        use crate::types::metadata::services::model::ModelService;
+
+        let project_a = uuid::Uuid::new_v4();
+        let project_b = uuid::Uuid::new_v4();
+        let db_pool = setup_test_database();
+        let model_service = ModelServiceImpl::new(db_pool.clone());
+        let metadata = PricedModels {
+            prices: HashMap::from([("team-model", 0.5)]),
+        }
+        .get_model_metadata("openai/team-model", false, false, None)
+        .await
+        .unwrap();
+        model_service
+            .insert_many(vec![DbNewModel {
+                project_id: Some(project_a.to_string()),
+                ..DbNewModel::from(metadata)
+            }])
+            .unwrap();
+        let model_metadata_factory = Arc::new(Box::new(DefaultModelMetadataFactory::new(Arc::new(
+            Box::new(model_service),
+        ))) as Box<dyn ModelMetadataFactory>);
+
+        let models = vec!["openai/team-model".to_string()];
+        let prices =
+            LlmRouter::candidate_prices(&models, Some(&project_a), &model_metadata_factory).await;
+        assert!((prices["openai/team-model"] - 0.5).abs() < 1e-6);
+
+        let prices =
+            LlmRouter::candidate_prices(&models, Some(&project_b), &model_metadata_factory).await;
+        assert!(prices.is_empty());
+        assert!(model_metadata_factory
+            .get_model_metadata("openai/team-model", false, false, Some(&project_b))
+            .await
+            .is_err());
+    }
 }
@@ -0,0 +1,332 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::routing::resolution::ResolutionSnapshot;
+use crate::types::traces::{LangdbSpan, Operation};
+
+/// How far the cost of a run may move from its snapshot before it's reported
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RegressionTolerance {
+    /// Allowed cost change, relative to the snapshot's cost
+    pub cost_ratio: f64,
+    /// Allowed cost change in absolute terms, for snapshots of free or very cheap runs
+    pub cost_absolute: f64,
+}
+
+impl Default for RegressionTolerance {
+    fn default() -> Self {
+        Self {
+            cost_ratio: 0.05,
+            cost_absolute: 0.000001,
+        }
+    }
+}
+
+/// Routing decisions, span structure and cost of a run, captured from a known good run and
+/// compared against later runs of the same workload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RunSnapshot {
+    /// Resolution of every request of the run, in start order
+    pub resolutions: Vec<ResolutionSnapshot>,
+    /// Operation path of every span, e.g. `run/api_invoke/model_call/openai`, depth first in
+    /// start order
+    pub structure: Vec<String>,
+    /// Total cost of the run's requests
+    pub cost: f64,
+}
+
+/// Difference between a run and its snapshot
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RunDiff {
+    /// A request of the run was executed with a different model, or a request was added or
+    /// removed
+    Model {
+        index: usize,
+        expected: Option<String>,
+        actual: Option<String>,
+    },
+    Structure {
+        expected: Vec<String>,
+        actual: Vec<String>,
+    },
+    Cost {
+        expected: f64,
+        actual: f64,
+    },
+}
+
+/// Cost recorded on a span, either the number itself or the serialized cost object
+fn span_cost(span: &LangdbSpan) -> Option<f64> {
+    match span.attribute.get("cost")? {
+        Value::Number(cost) => cost.as_f64(),
+        Value::String(cost) => {
+            let cost: Value = serde_json::from_str(cost).ok()?;
+            cost.as_f64().or_else(|| cost.get("cost")?.as_f64())
+        }
+        cost => cost.get("cost")?.as_f64(),
+    }
+}
+
+fn span_resolution(span: &LangdbSpan) -> Option<ResolutionSnapshot> {
+    match span.attribute.get("resolution")? {
+        Value::String(resolution) => serde_json::from_str(resolution).ok(),
+        resolution => serde_json::from_value(resolution.clone()).ok(),
+    }
+}
+
+impl RunSnapshot {
+    /// Snapshot of the run made of `spans`
+    pub fn from_spans(spans: &[LangdbSpan]) -> Self {
+        let mut spans: Vec<&LangdbSpan> = spans.iter().collect();
+        spans.sort_by_key(|span| span.start_time_us);
+
+        let mut children: HashMap<&str, Vec<&LangdbSpan>> = HashMap::new();
+        let mut roots = vec![];
+        for span in &spans {
+            match span.parent_span_id.as_deref() {
+                Some(parent) if spans.iter().any(|s| s.span_id == parent) => {
+                    children.entry(parent).or_default().push(span)
+                }
+                _ => roots.push(*span),
+            }
+        }
+
+        let mut structure = vec![];
+        let mut stack: Vec<(&LangdbSpan, String)> = roots
+            .into_iter()
+            .rev()
+            .map(|span| (span, span.operation_name.to_string()))
+            .collect();
+        while let Some((span, path)) = stack.pop() {
+            for child in children
+                .get(span.span_id.as_str())
+                .into_iter()
+                .flatten()
+                .rev()
+            {
+                stack.push((child, format!("{path}/{}", child.operation_name)));
+            }
+            structure.push(path);
+        }
+
+        Self {
+            resolutions: spans.iter().filter_map(|s| span_resolution(s)).collect(),
+            structure,
+            cost: spans
+                .iter()
+                .filter(|span| matches!(span.operation_name, Operation::ApiInvoke))
+                .filter_map(|span| span_cost(span))
+                .sum(),
+        }
+    }
+
+    /// Differences of `actual` from this snapshot. Costs within `tolerance` aren't reported.
+    pub fn compare(&self, actual: &RunSnapshot, tolerance: &RegressionTolerance) -> Vec<RunDiff> {
+        let mut diffs = vec![];
+
+        let requests = self.resolutions.len().max(actual.resolutions.len());
+        for index in 0..requests {
+            let expected = self.resolutions.get(index).map(|r| r.model.clone());
+            let actual = actual.resolutions.get(index).map(|r| r.model.clone());
+            if expected != actual {
+                diffs.push(RunDiff::Model {
+                    index,
+                    expected,
+                    actual,
+                });
+            }
+        }
+
+        if self.structure != actual.structure {
+            diffs.push(RunDiff::Structure {
+                expected: self.structure.clone(),
+                actual: actual.structure.clone(),
+            });
+        }
+
+        let allowed = (self.cost * tolerance.cost_ratio).max(tolerance.cost_absolute);
+        if (actual.cost - self.cost).abs() > allowed {
+            diffs.push(RunDiff::Cost {
+                expected: self.cost,
+                actual: actual.cost,
+            });
+        }
+
+        diffs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metadata::services::model::ModelServiceImpl;
+    use crate::metadata::test_utils::setup_test_database;
+    use crate::model::{DefaultModelMetadataFactory, ModelMetadataFactory};
+    use crate::routing::interceptor::{Interceptor, InterceptorError, InterceptorFactory};
+    use crate::routing::metrics::InMemoryMetricsRepository;
+    use crate::routing::resolution::ResolvedRouter;
+    use crate::routing::{InterceptorSpec, LlmRouter, RouteStrategy, RoutingStrategy};
+    use serde_json::json;
+    use std::collections::BTreeMap;
+    use std::sync::Arc;
+    use vllora_llm::types::gateway::ChatCompletionRequest;
+    use vllora_llm::types::models::{InferenceProvider, ModelMetadata};
+
+    fn span(
+        span_id: &str,
+        parent_span_id: Option<&str>,
+        operation: &str,
+        start_time_us: i64,
+        attribute: HashMap<String, Value>,
+    ) -> LangdbSpan {
+        LangdbSpan {
+            trace_id: "trace".to_string(),
+            span_id: span_id.to_string(),
+            thread_id: None,
+            parent_span_id: parent_span_id.map(ToString::to_string),
+            operation_name: operation.into(),
+            start_time_us,
+            finish_time_us: start_time_us + 10,
+            attribute,
+            child_attribute: None,
+            run_id: Some("run".to_string()),
+        }
+    }
+
+    struct NoInterceptors;
+
+    impl InterceptorFactory for NoInterceptors {
+        fn create_interceptor(
+            &self,
+            _spec: &InterceptorSpec,
+        ) -> Result<Arc<dyn Interceptor>, InterceptorError> {
+            Err(InterceptorError::ExecutionError(
+                "no interceptors".to_string(),
+            ))
+        }
+    }
+
+    fn router(targets: &[&str]) -> LlmRouter {
+        LlmRouter::new("default".to_string(), RoutingStrategy::Fallback).with_targets(
+            targets
+                .iter()
+                .map(|model| HashMap::from([("model".to_string(), json!(model))]))
+                .collect(),
+        )
+    }
+
+    /// Spans of a run whose single request was routed by `router` and cost `cost`
+    async fn run(router: &LlmRouter, cost: f64) -> Vec<LangdbSpan> {
+        let model_metadata_factory = Arc::new(Box::new(DefaultModelMetadataFactory::new(Arc::new(
+            Box::new(ModelServiceImpl::new(setup_test_database())),
+        ))) as Box<dyn ModelMetadataFactory>);
+        let routed = router
+            .route(
+                ChatCompletionRequest::default(),
+                None,
+                model_metadata_factory,
+                HashMap::new(),
+                &InMemoryMetricsRepository::new(BTreeMap::new()),
+                Box::new(NoInterceptors),
+            )
+            .await
+            .unwrap();
+
+        let target = routed.targets[0]["model"].as_str().unwrap();
+        let (provider, model_name) = target.split_once('/').unwrap();
+        let model = ModelMetadata {
+            model: model_name.to_string(),
+            inference_provider: InferenceProvider {
+                provider: provider.to_string().into(),
+                model_name: model_name.to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            },
+            ..Default::default()
+        };
+        let request = ChatCompletionRequest {
+            model: target.to_string(),
+            ..Default::default()
+        };
+        let resolved = ResolvedRouter::new(&router.name, &router.strategy);
+        let resolution = ResolutionSnapshot::new(Some(&resolved), &request, &model).unwrap();
+
+        vec![
+            span("1", None, "run", 0, HashMap::new()),
+            span(
+                "2",
+                Some("1"),
+                "api_invoke",
+                1,
+                HashMap::from([
+                    (
+                        "resolution".to_string(),
+                        json!(serde_json::to_string(&resolution).unwrap()),
+                    ),
+                    ("cost".to_string(), json!(json!({"cost": cost}).to_string())),
+                ]),
+            ),
+            span("3", Some("2"), "model_call", 2, HashMap::new()),
+            span("4", Some("3"), provider, 3, HashMap::new()),
+        ]
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_round_trips() {
+        let router = router(&["openai/gpt-4o-mini"]);
+        let snapshot = RunSnapshot::from_spans(&run(&router, 0.01).await);
+
+        assert_eq!(
+            snapshot.structure,
+            vec![
+                "run",
+                "run/api_invoke",
+                "run/api_invoke/model_call",
+                "run/api_invoke/model_call/openai"
+            ]
+        );
+        assert_eq!(snapshot.resolutions[0].model, "openai/gpt-4o-mini");
+        assert_eq!(snapshot.cost, 0.01);
+
+        let serialized = serde_json::to_string(&snapshot).unwrap();
+        let restored: RunSnapshot = serde_json::from_str(&serialized).unwrap();
+        assert_eq!(restored, snapshot);
+
+        // Cost within the tolerance
+        let rerun = RunSnapshot::from_spans(&run(&router, 0.0102).await);
+        assert!(snapshot
+            .compare(&rerun, &RegressionTolerance::default())
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_changed_routing_config_is_a_regression() {
+        let snapshot = RunSnapshot::from_spans(&run(&router(&["openai/gpt-4o-mini"]), 0.01).await);
+
+        // The router now falls back to Anthropic first, at twice the cost
+        let changed = router(&["anthropic/claude-3-5-haiku", "openai/gpt-4o-mini"]);
+        let actual = RunSnapshot::from_spans(&run(&changed, 0.02).await);
+
+        let diffs = snapshot.compare(&actual, &RegressionTolerance::default());
+        assert_eq!(
+            diffs[0],
+            RunDiff::Model {
+                index: 0,
+                expected: Some("openai/gpt-4o-mini".to_string()),
+                actual: Some("anthropic/claude-3-5-haiku".to_string()),
+            }
+        );
+        assert!(matches!(&diffs[1], RunDiff::Structure { actual, .. }
+            if actual.last().unwrap() == "run/api_invoke/model_call/anthropic"));
+        assert_eq!(
+            diffs[2],
+            RunDiff::Cost {
+                expected: 0.01,
+                actual: 0.02
+            }
+        );
+    }
+}
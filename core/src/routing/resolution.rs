@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+use vllora_llm::types::gateway::ChatCompletionRequest;
+use vllora_llm::types::models::ModelMetadata;
+
+use crate::routing::RoutingStrategy;
+
+/// Request header opting into the resolution snapshot, returned in the same response header
+pub const RESOLUTION_HEADER: &str = "x-vllora-resolution";
+
+/// Request fields that are not parameters of the call, left out of the snapshot
+const NON_PARAM_FIELDS: [&str; 4] = ["model", "messages", "tools", "stream_format"];
+
+/// Router that picked the model a request was executed on
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResolvedRouter {
+    pub name: String,
+    pub strategy: String,
+}
+
+impl ResolvedRouter {
+    pub fn new(name: &str, strategy: &RoutingStrategy) -> Self {
+        Self {
+            name: name.to_string(),
+            strategy: strategy.to_string(),
+        }
+    }
+}
+
+/// Which model and version a request hit and how it got there, so eval runs can be
+/// reproduced and diffed across config changes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResolutionSnapshot {
+    pub router_name: Option<String>,
+    pub strategy: Option<String>,
+    /// Model id the request was executed with, after routing and context upgrades
+    pub model: String,
+    /// Model name sent to the provider
+    pub inference_model_name: String,
+    pub provider: String,
+    /// Parameters sent with the request, unset ones left out
+    pub params: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ResolutionSnapshot {
+    pub fn new(
+        router: Option<&ResolvedRouter>,
+        request: &ChatCompletionRequest,
+        model: &ModelMetadata,
+    ) -> Result<Self, serde_json::Error> {
+        let params = match serde_json::to_value(request)? {
+            serde_json::Value::Object(fields) => fields
+                .into_iter()
+                .filter(|(key, value)| {
+                    !value.is_null() && !NON_PARAM_FIELDS.contains(&key.as_str())
+                })
+                .collect(),
+            _ => serde_json::Map::new(),
+        };
+
+        Ok(Self {
+            router_name: router.map(|router| router.name.clone()),
+            strategy: router.map(|router| router.strategy.clone()),
+            model: request.model.clone(),
+            inference_model_name: model.inference_provider.model_name.clone(),
+            provider: model.inference_provider.provider.to_string(),
+            params,
+        })
+    }
+
+    /// Whether the client asked for the snapshot via [`RESOLUTION_HEADER`]
+    pub fn requested(header: Option<&str>) -> bool {
+        header.is_some_and(|value| value.eq_ignore_ascii_case("true") || value == "1")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_llm::types::models::InferenceProvider;
+    use vllora_llm::types::provider::InferenceModelProvider;
+
+    #[test]
+    fn test_snapshot_contains_resolved_model_and_provider() {
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "openai/gpt-4o-mini",
+            "messages": [{"role": "user", "content": "Hello"}],
+            "temperature": 0.2,
+            "max_tokens": 64,
+        }))
+        .unwrap();
+        let model = ModelMetadata {
+            model: "gpt-4o-mini".to_string(),
+            inference_provider: InferenceProvider {
+                provider: InferenceModelProvider::OpenAI,
+                model_name: "gpt-4o-mini-2024-07-18".to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            },
+            ..Default::default()
+        };
+        let router = ResolvedRouter::new("cheapest", &RoutingStrategy::Fallback);
+
+        let snapshot = ResolutionSnapshot::new(Some(&router), &request, &model).unwrap();
+
+        assert_eq!(snapshot.router_name.as_deref(), Some("cheapest"));
+        assert_eq!(snapshot.strategy.as_deref(), Some("Fallback"));
+        assert_eq!(snapshot.model, "openai/gpt-4o-mini");
+        assert_eq!(snapshot.inference_model_name, "gpt-4o-mini-2024-07-18");
+        assert_eq!(snapshot.provider, "openai");
+        assert_eq!(snapshot.params["max_tokens"], 64);
+        assert!(snapshot.params.contains_key("temperature"));
+        assert!(!snapshot.params.contains_key("messages"));
+        assert!(!snapshot.params.contains_key("model"));
+
+        assert!(ResolutionSnapshot::requested(Some("true")));
+        assert!(!ResolutionSnapshot::requested(None));
+    }
+}
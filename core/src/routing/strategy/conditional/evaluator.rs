@@ -1,3 +1,4 @@
+use crate::routing::explain::ConditionEvaluation;
 use crate::routing::interceptor::LazyInterceptorManager;
 use crate::routing::strategy::conditional::metadata::MetadataField;
 use crate::routing::{ConditionOpType, Route, RouteCondition};
@@ -10,11 +11,22 @@ pub async fn evaluate_conditions(
     lazy_manager: &mut LazyInterceptorManager,
     metadata: &HashMap<String, serde_json::Value>,
     extra: Option<&Extra>,
+) -> Result<bool, crate::routing::interceptor::InterceptorError> {
+    evaluate_conditions_traced(condition, lazy_manager, metadata, extra, &mut vec![]).await
+}
+
+/// Same as [`evaluate_conditions`], appending every evaluated condition to `trace`.
+pub async fn evaluate_conditions_traced(
+    condition: &RouteCondition,
+    lazy_manager: &mut LazyInterceptorManager,
+    metadata: &HashMap<String, serde_json::Value>,
+    extra: Option<&Extra>,
+    trace: &mut Vec<ConditionEvaluation>,
 ) -> Result<bool, crate::routing::interceptor::InterceptorError> {
     match condition {
         RouteCondition::All { all } => {
             for expr in all {
-                if !evaluate_expr(expr, lazy_manager, metadata, extra).await? {
+                if !evaluate_expr(expr, lazy_manager, metadata, extra, trace).await? {
                     return Ok(false);
                 }
             }
@@ -22,7 +34,7 @@ pub async fn evaluate_conditions(
         }
         RouteCondition::Any { any } => {
             for expr in any {
-                if evaluate_expr(expr, lazy_manager, metadata, extra).await? {
+                if evaluate_expr(expr, lazy_manager, metadata, extra, trace).await? {
                     return Ok(true);
                 }
             }
@@ -30,7 +42,7 @@ pub async fn evaluate_conditions(
         }
         RouteCondition::Expr(map) => {
             for (k, v) in map {
-                if !evaluate_op(k, v, lazy_manager, metadata, extra).await? {
+                if !evaluate_op(k, v, lazy_manager, metadata, extra, trace).await? {
                     return Ok(false);
                 }
             }
@@ -44,11 +56,12 @@ async fn evaluate_expr(
     lazy_manager: &mut LazyInterceptorManager,
     metadata: &HashMap<String, serde_json::Value>,
     extra: Option<&Extra>,
+    trace: &mut Vec<ConditionEvaluation>,
 ) -> Result<bool, crate::routing::interceptor::InterceptorError> {
     match expr {
         crate::routing::ConditionExpr::Expr(map) => {
             for (k, v) in map {
-                if !evaluate_op(k, v, lazy_manager, metadata, extra).await? {
+                if !evaluate_op(k, v, lazy_manager, metadata, extra, trace).await? {
                     return Ok(false);
                 }
             }
@@ -63,6 +76,7 @@ async fn evaluate_op(
     lazy_manager: &mut LazyInterceptorManager,
     metadata: &HashMap<String, serde_json::Value>,
     extra: Option<&Extra>,
+    trace: &mut Vec<ConditionEvaluation>,
 ) -> Result<bool, crate::routing::interceptor::InterceptorError> {
     let get_value = |key: &str| -> Option<serde_json::Value> {
         if key.starts_with("pre_request.") {
@@ -109,17 +123,19 @@ async fn evaluate_op(
         get_value(key)
     };
 
-    let Some(value) = value else {
-        return Ok(false);
-    };
-
-    for (op_name, op_value) in &op.op {
-        if !compare_values(op_name, op_value, &value) {
-            return Ok(false);
-        }
-    }
+    let matched = value.as_ref().is_some_and(|value| {
+        op.op
+            .iter()
+            .all(|(op_name, op_value)| compare_values(op_name, op_value, value))
+    });
+    trace.push(ConditionEvaluation {
+        key: key.to_string(),
+        op: op.clone(),
+        value,
+        matched,
+    });
 
-    Ok(true)
+    Ok(matched)
 }
 
 pub fn compare_values(
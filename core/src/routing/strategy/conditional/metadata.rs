@@ -358,6 +358,12 @@ mod tests {
             guards: vec![],
             cache: None,
             variables: None,
+            force_model: None,
+            interim_usage_every: None,
+            role_policy: None,
+            endpoint: None,
+            metadata: None,
+            allowed_tools: None,
         });
 
         assert_eq!(
@@ -400,6 +406,12 @@ mod tests {
             guards: vec![],
             cache: None,
             variables: Some(variables),
+            force_model: None,
+            interim_usage_every: None,
+            role_policy: None,
+            endpoint: None,
+            metadata: None,
+            allowed_tools: None,
         });
 
         assert_eq!(
@@ -447,6 +459,12 @@ mod tests {
             guards: vec![],
             cache: None,
             variables: None,
+            force_model: None,
+            interim_usage_every: None,
+            role_policy: None,
+            endpoint: None,
+            metadata: None,
+            allowed_tools: None,
         });
 
         let metadata = manager.extract_all_metadata(extra.as_ref()).unwrap();
@@ -1,6 +1,9 @@
-use crate::routing::interceptor::{InterceptorFactory, LazyInterceptorManager};
+use crate::routing::explain::RouteEvaluation;
+use crate::routing::interceptor::{InterceptorFactory, InterceptorState, LazyInterceptorManager};
 use crate::routing::{
-    strategy::conditional::evaluator::{evaluate_conditions, referenced_pre_request_interceptors},
+    strategy::conditional::evaluator::{
+        evaluate_conditions_traced, referenced_pre_request_interceptors,
+    },
     ConditionalRouting, TargetSpec,
 };
 
@@ -8,6 +11,15 @@ pub struct ConditionalRouter {
     pub routing: ConditionalRouting,
 }
 
+/// Outcome of evaluating conditional routes, kept for auditing
+pub struct ConditionalResolution<'a> {
+    pub route: Option<String>,
+    pub targets: Option<&'a TargetSpec>,
+    pub interceptor_state: InterceptorState,
+    /// Routes evaluated until one matched, in order
+    pub evaluations: Vec<RouteEvaluation>,
+}
+
 impl ConditionalRouter {
     /// Evaluates routes in order, running only referenced pre_request interceptors lazily, and returns the first matching target.
     /// Stops at the first unmet condition for each route and moves to the next route.
@@ -20,6 +32,21 @@ impl ConditionalRouter {
         metadata: &std::collections::HashMap<String, serde_json::Value>,
         extra: Option<&vllora_llm::types::gateway::Extra>,
     ) -> Option<&TargetSpec> {
+        self.resolve(factory, request, headers, metadata, extra)
+            .await
+            .targets
+    }
+
+    /// Same as [`Self::get_target`], additionally returning the matched route name, the
+    /// interceptor results and the conditions evaluated for each route.
+    pub async fn resolve(
+        &self,
+        factory: Box<dyn InterceptorFactory>,
+        request: &vllora_llm::types::gateway::ChatCompletionRequest,
+        headers: &std::collections::HashMap<String, String>,
+        metadata: &std::collections::HashMap<String, serde_json::Value>,
+        extra: Option<&vllora_llm::types::gateway::Extra>,
+    ) -> ConditionalResolution<'_> {
         let referenced = referenced_pre_request_interceptors(&self.routing.routes);
 
         // Create interceptors map for lazy execution
@@ -47,19 +74,27 @@ impl ConditionalRouter {
         let mut lazy_manager = LazyInterceptorManager::new(interceptors, context);
 
         // Evaluate routes in order with lazy interceptor execution
+        let mut matched = None;
+        let mut evaluations = vec![];
         for route in &self.routing.routes {
-            if let Some(conditions) = &route.conditions {
-                match evaluate_conditions(conditions, &mut lazy_manager, metadata, extra).await {
-                    Ok(true) => {
-                        let span = tracing::Span::current();
-                        span.record("router.execution_route", &route.name);
-                        if let Some(targets) = &route.targets {
-                            return Some(targets);
+            let mut conditions = vec![];
+            let mut error = None;
+            let met = match &route.conditions {
+                Some(route_conditions) => match evaluate_conditions_traced(
+                    route_conditions,
+                    &mut lazy_manager,
+                    metadata,
+                    extra,
+                    &mut conditions,
+                )
+                .await
+                {
+                    Ok(met) => {
+                        if met {
+                            let span = tracing::Span::current();
+                            span.record("router.execution_route", &route.name);
                         }
-                    }
-                    Ok(false) => {
-                        // Condition not met, continue to next route
-                        continue;
+                        met
                     }
                     Err(e) => {
                         tracing::error!(
@@ -67,15 +102,35 @@ impl ConditionalRouter {
                             route.name,
                             e
                         );
-                        continue;
+                        error = Some(e.to_string());
+                        false
                     }
-                }
-            } else if let Some(targets) = &route.targets {
-                return Some(targets);
+                },
+                None => true,
+            };
+
+            // A route without targets never matches, evaluation continues with the next one
+            let targets = route.targets.as_ref().filter(|_| met);
+            evaluations.push(RouteEvaluation {
+                route: route.name.clone(),
+                matched: targets.is_some(),
+                conditions,
+                error,
+            });
+            if let Some(targets) = targets {
+                matched = Some((route.name.clone(), targets));
+                break;
             }
         }
 
-        None
+        let interceptor_state = state.read().await.clone();
+        let (route, targets) = matched.unzip();
+        ConditionalResolution {
+            route,
+            targets,
+            interceptor_state,
+            evaluations,
+        }
     }
 }
 
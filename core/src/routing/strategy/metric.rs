@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use crate::routing::decision_log::record_metric_resolution;
 use crate::routing::ConditionOpType;
 use crate::{
     routing::{
@@ -10,9 +11,6 @@ use crate::{
 };
 use futures::future;
 use rand::seq::IteratorRandom;
-use tracing::Span;
-use valuable::Valuable;
-use vllora_telemetry::events::JsonValue;
 
 #[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone, PartialEq, Eq, Hash)]
 #[serde(rename_all = "snake_case")]
@@ -66,17 +64,111 @@ fn create_default_metrics() -> Metrics {
     }
 }
 
-pub async fn route<M: MetricsRepository + Send + Sync>(
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RankedCandidate {
+    pub model: String,
+    pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<f64>,
+}
+
+/// Orders candidates best first. Ties on the metric are broken by price (cheapest first,
+/// unknown prices last) and then by model name, so the winner is always deterministic.
+pub fn rank_candidates(
+    candidates: Vec<(String, f64)>,
+    minimize: bool,
+    prices: Option<&HashMap<String, f64>>,
+) -> Vec<RankedCandidate> {
+    let mut ranked: Vec<RankedCandidate> = candidates
+        .into_iter()
+        .map(|(model, value)| RankedCandidate {
+            price: prices.and_then(|p| p.get(&model).copied()),
+            model,
+            value,
+        })
+        .collect();
+
+    ranked.sort_by(|a, b| {
+        let metric_comparison = if minimize {
+            a.value.total_cmp(&b.value)
+        } else {
+            b.value.total_cmp(&a.value)
+        };
+
+        let price_comparison = match (a.price, b.price) {
+            (Some(price_a), Some(price_b)) => price_a.total_cmp(&price_b),
+            (Some(_), None) => std::cmp::Ordering::Less,
+            (None, Some(_)) => std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+
+        metric_comparison
+            .then(price_comparison)
+            .then_with(|| a.model.cmp(&b.model))
+    });
+
+    ranked
+}
+
+/// Upper bounds a candidate must stay within to be considered by the Optimized strategy.
+/// Lets a router avoid a model that is currently failing or slow even when it ranks best
+/// on the optimized metric.
+#[derive(Debug, serde::Serialize, serde::Deserialize, Default, Clone, PartialEq)]
+pub struct MetricCeiling {
+    /// Highest error rate allowed, as a fraction of requests (0.1 = 10%)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_error_rate: Option<f64>,
+    /// Highest average latency allowed, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_latency: Option<f64>,
+    /// Models to route to, in order, when every candidate exceeds the ceiling. When empty
+    /// the best candidate is used anyway.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallback_models: Vec<String>,
+}
+
+impl MetricCeiling {
+    /// Why `metrics` exceed the ceiling, if they do. Missing metrics never exceed it.
+    pub fn exceeded_by(&self, metrics: &Metrics) -> Option<String> {
+        match (self.max_error_rate, metrics.error_rate) {
+            (Some(max), Some(error_rate)) if error_rate > max => {
+                return Some(format!("error_rate {error_rate} exceeds {max}"))
+            }
+            _ => {}
+        }
+        match (self.max_latency, metrics.latency) {
+            (Some(max), Some(latency)) if latency > max => {
+                Some(format!("latency {latency} exceeds {max}"))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ExcludedCandidate {
+    pub model: String,
+    pub reason: String,
+}
+
+fn period_metrics<'a>(
+    metrics: &'a ModelMetrics,
+    metrics_duration: Option<&MetricsDuration>,
+) -> &'a Metrics {
+    match metrics_duration {
+        Some(MetricsDuration::Total) | None => &metrics.metrics.total,
+        Some(MetricsDuration::LastHour) => &metrics.metrics.last_hour,
+        Some(MetricsDuration::Last15Minutes) => &metrics.metrics.last_15_minutes,
+        Some(MetricsDuration::Ewma) => &metrics.metrics.ewma,
+    }
+}
+
+/// Metrics of every model matching `models`, keyed by `provider/model`
+pub(crate) async fn collect_candidates<M: MetricsRepository + Send + Sync>(
     models: &[String],
-    metric: &MetricSelector,
     metrics_duration: Option<&MetricsDuration>,
     metrics_repository: &M,
-    minimize: Option<bool>,
-    filters: Option<&HashMap<MetricSelector, HashMap<ConditionOpType, serde_json::Value>>>,
-) -> Result<String, RouterError> {
-    let minimize = minimize
-        .unwrap_or(metric.get_optimization_direction() == MetricOptimizationDirection::Minimize);
-
+) -> HashMap<String, Metrics> {
     // Collect all model candidates with their metrics
     let mut candidates = HashMap::new();
 
@@ -123,13 +215,10 @@ pub async fn route<M: MetricsRepository + Send + Sync>(
     for (provider, result) in provider_results {
         if let Ok(Some(provider_metrics)) = result {
             for (model_name, model_metrics) in provider_metrics.models {
-                let period_metrics = match metrics_duration {
-                    Some(MetricsDuration::Total) | None => &model_metrics.metrics.total,
-                    Some(MetricsDuration::LastHour) => &model_metrics.metrics.last_hour,
-                    Some(MetricsDuration::Last15Minutes) => &model_metrics.metrics.last_15_minutes,
-                };
-
-                candidates.insert(format!("{provider}/{model_name}"), period_metrics.clone());
+                candidates.insert(
+                    format!("{provider}/{model_name}"),
+                    period_metrics(&model_metrics, metrics_duration).clone(),
+                );
             }
         }
     }
@@ -145,17 +234,15 @@ pub async fn route<M: MetricsRepository + Send + Sync>(
                     total: create_default_metrics(),
                     last_15_minutes: create_default_metrics(),
                     last_hour: create_default_metrics(),
+                    ewma: create_default_metrics(),
                 },
             }
         };
 
-        let period_metrics = match metrics_duration {
-            Some(MetricsDuration::Total) | None => &model_metrics.metrics.total,
-            Some(MetricsDuration::LastHour) => &model_metrics.metrics.last_hour,
-            Some(MetricsDuration::Last15Minutes) => &model_metrics.metrics.last_15_minutes,
-        };
-
-        candidates.insert(format!("{provider}/{model_name}"), period_metrics.clone());
+        candidates.insert(
+            format!("{provider}/{model_name}"),
+            period_metrics(&model_metrics, metrics_duration).clone(),
+        );
     }
 
     // Handle models without provider. Single fetch of all metrics if needed.
@@ -165,15 +252,10 @@ pub async fn route<M: MetricsRepository + Send + Sync>(
                 let mut found_model = false;
                 for (provider, provider_metrics) in &all_metrics {
                     if let Some(metrics) = provider_metrics.models.get(&model) {
-                        let period_metrics = match metrics_duration {
-                            Some(MetricsDuration::Total) | None => &metrics.metrics.total,
-                            Some(MetricsDuration::LastHour) => &metrics.metrics.last_hour,
-                            Some(MetricsDuration::Last15Minutes) => {
-                                &metrics.metrics.last_15_minutes
-                            }
-                        };
-
-                        candidates.insert(format!("{provider}/{model}"), period_metrics.clone());
+                        candidates.insert(
+                            format!("{provider}/{model}"),
+                            period_metrics(metrics, metrics_duration).clone(),
+                        );
                         found_model = true;
                     }
                 }
@@ -193,6 +275,68 @@ pub async fn route<M: MetricsRepository + Send + Sync>(
         }
     }
 
+    candidates
+}
+
+/// Picks the best candidate on `metric` and records the resolution on the current span
+fn select_best(
+    models: &[String],
+    candidates: HashMap<String, Metrics>,
+    metric: &MetricSelector,
+    minimize: bool,
+    metrics_duration: Option<&MetricsDuration>,
+    prices: Option<&HashMap<String, f64>>,
+    excluded: &[ExcludedCandidate],
+) -> String {
+    let filtered_candidates: Vec<(String, f64)> = candidates
+        .into_iter()
+        .filter_map(|(model, metrics)| metric.get_value(&metrics).map(|value| (model, value)))
+        .collect();
+
+    if filtered_candidates.is_empty() {
+        // If no candidates have metrics, select a random model from the available models
+        let mut rng = rand::rng();
+        if let Some(random_model) = models
+            .iter()
+            .filter(|m| !excluded.iter().any(|e| &e.model == *m))
+            .choose(&mut rng)
+        {
+            record_metric_resolution(
+                serde_json::json!({"candidates": [], "best_model": random_model, "metric": metric, "metrics_duration": metrics_duration, "excluded": excluded}),
+            );
+            return random_model.clone();
+        }
+    }
+    let ranked = rank_candidates(filtered_candidates, minimize, prices);
+
+    let model = match ranked.first() {
+        Some(candidate) => candidate.model.clone(),
+        None => models.first().cloned().unwrap_or_default(),
+    };
+
+    record_metric_resolution(
+        serde_json::json!({"candidates": ranked, "best_model": model, "metric": metric, "metrics_duration": metrics_duration, "excluded": excluded}),
+    );
+
+    tracing::info!("Router metric resolution: {:#?}", model);
+
+    model
+}
+
+pub async fn route<M: MetricsRepository + Send + Sync>(
+    models: &[String],
+    metric: &MetricSelector,
+    metrics_duration: Option<&MetricsDuration>,
+    metrics_repository: &M,
+    minimize: Option<bool>,
+    filters: Option<&HashMap<MetricSelector, HashMap<ConditionOpType, serde_json::Value>>>,
+    prices: Option<&HashMap<String, f64>>,
+) -> Result<String, RouterError> {
+    let minimize = minimize
+        .unwrap_or(metric.get_optimization_direction() == MetricOptimizationDirection::Minimize);
+
+    let mut candidates = collect_candidates(models, metrics_duration, metrics_repository).await;
+
     if let Some(filters) = filters {
         candidates.retain(|_model, metrics| {
             filters.iter().all(|(filter_metric, filter_value)| {
@@ -214,63 +358,94 @@ pub async fn route<M: MetricsRepository + Send + Sync>(
         });
     }
 
-    let filtered_candidates: Vec<(String, f64)> = candidates
-        .into_iter()
-        .filter_map(|(model, metrics)| metric.get_value(&metrics).map(|value| (model, value)))
-        .collect();
+    Ok(select_best(
+        models,
+        candidates,
+        metric,
+        minimize,
+        metrics_duration,
+        prices,
+        &[],
+    ))
+}
 
-    if filtered_candidates.is_empty() {
-        // If no candidates have metrics, select a random model from the available models
-        let mut rng = rand::rng();
-        if let Some(random_model) = models.iter().choose(&mut rng) {
-            let span = Span::current();
-            span.record(
-                "router.metric_resolution",
-                JsonValue(&serde_json::json!({"candidates": [], "best_model": random_model, "metric": metric, "metrics_duration": metrics_duration})).as_value(),
-            );
-            return Ok(random_model.clone());
+/// Like [`route`], but candidates exceeding `ceiling` are excluded before selection.
+///
+/// Returns `None` when every candidate exceeds the ceiling and fallback models are
+/// declared, in which case the caller routes to `ceiling.fallback_models`.
+pub async fn route_within_ceiling<M: MetricsRepository + Send + Sync>(
+    models: &[String],
+    metric: &MetricSelector,
+    metrics_duration: Option<&MetricsDuration>,
+    metrics_repository: &M,
+    ceiling: &MetricCeiling,
+    prices: Option<&HashMap<String, f64>>,
+) -> Result<Option<String>, RouterError> {
+    let minimize = metric.get_optimization_direction() == MetricOptimizationDirection::Minimize;
+
+    let candidates = collect_candidates(models, metrics_duration, metrics_repository).await;
+
+    let mut excluded = vec![];
+    let mut within_ceiling = HashMap::new();
+    for (model, metrics) in &candidates {
+        match ceiling.exceeded_by(metrics) {
+            Some(reason) => excluded.push(ExcludedCandidate {
+                model: model.clone(),
+                reason,
+            }),
+            None => {
+                within_ceiling.insert(model.clone(), metrics.clone());
+            }
         }
     }
-    // Find the best candidate
-    let best_model = filtered_candidates
-        .iter()
-        .min_by(|(model_a, value_a), (model_b, value_b)| {
-            let metric_comparison = if minimize {
-                value_a.partial_cmp(value_b).unwrap()
-            } else {
-                value_b.partial_cmp(value_a).unwrap()
-            };
+    excluded.sort_by(|a, b| a.model.cmp(&b.model));
 
-            // If metrics are equal, sort by model name for deterministic behavior
-            if metric_comparison == std::cmp::Ordering::Equal {
-                model_a.cmp(model_b)
-            } else {
-                metric_comparison
-            }
-        });
-
-    let model = match best_model {
-        Some((model, _)) => model.clone(),
-        None => models.first().cloned().unwrap_or_default(),
-    };
-
-    let span = Span::current();
-    span.record(
-        "router.metric_resolution",
-        JsonValue(&serde_json::json!({"candidates": filtered_candidates, "best_model": model, "metric": metric, "metrics_duration": metrics_duration})).as_value(),
-    );
+    if within_ceiling.is_empty() && !excluded.is_empty() {
+        if !ceiling.fallback_models.is_empty() {
+            record_metric_resolution(
+                serde_json::json!({"candidates": [], "best_model": null, "fallback_models": ceiling.fallback_models, "metric": metric, "metrics_duration": metrics_duration, "excluded": excluded}),
+            );
+            tracing::warn!(
+                "All candidates exceed the metric ceiling, routing to fallback models {:?}",
+                ceiling.fallback_models
+            );
+            return Ok(None);
+        }
 
-    tracing::info!("Router metric resolution: {:#?}", model);
+        // Nothing to fall back to, the least bad candidate is still better than failing
+        tracing::warn!("All candidates exceed the metric ceiling, using the best one anyway");
+        return Ok(Some(select_best(
+            models,
+            candidates,
+            metric,
+            minimize,
+            metrics_duration,
+            prices,
+            &excluded,
+        )));
+    }
 
-    Ok(model)
+    Ok(Some(select_best(
+        models,
+        within_ceiling,
+        metric,
+        minimize,
+        metrics_duration,
+        prices,
+        &excluded,
+    )))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::routing::decision_log::{
+        self, DecisionLogLevel, RoutingDecisionLogConfig, DECISION_LOG_TARGET,
+    };
     use crate::routing::metrics::MetricsRepository;
     use crate::usage::{ModelMetrics, TimeMetrics};
     use async_trait::async_trait;
+    use vllora_telemetry::test_utils::{RecordedEvent, RecordingLayer};
 
     fn create_model_metrics(latency: Option<f64>, ttft: Option<f64>) -> ModelMetrics {
         let metrics = Metrics {
@@ -289,7 +464,8 @@ mod tests {
             metrics: TimeMetrics {
                 total: metrics.clone(),
                 last_15_minutes: metrics.clone(),
-                last_hour: metrics,
+                last_hour: metrics.clone(),
+                ewma: metrics,
             },
         }
     }
@@ -386,6 +562,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -400,6 +577,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -468,6 +646,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -482,6 +661,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -519,6 +699,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -533,6 +714,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -561,6 +743,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -622,6 +805,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -637,6 +821,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -652,6 +837,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -689,6 +875,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -704,6 +891,7 @@ mod tests {
             &metrics_repository,
             None,
             None,
+            None,
         )
         .await
         .unwrap();
@@ -711,4 +899,282 @@ mod tests {
         // Should select "openai/gpt-4o-mini" as it has requests (100.0) vs defaults (0.0)
         assert_eq!(selected_model, "nonexistent-model".to_string());
     }
+
+    #[tokio::test]
+    async fn test_metric_router_breaks_ties_by_price_then_name() {
+        let openai_models = std::collections::BTreeMap::from([
+            (
+                "gpt-4o".to_string(),
+                create_model_metrics(Some(1000.0), Some(500.0)),
+            ),
+            (
+                "gpt-4o-mini".to_string(),
+                create_model_metrics(Some(1000.0), Some(500.0)),
+            ),
+        ]);
+        let metrics = std::collections::BTreeMap::from([(
+            "openai".to_string(),
+            crate::usage::ProviderMetrics {
+                models: openai_models,
+            },
+        )]);
+        let metrics_repository = MockMetricsRepository::new(metrics);
+
+        let models = vec![
+            "openai/gpt-4o".to_string(),
+            "openai/gpt-4o-mini".to_string(),
+        ];
+        let prices = HashMap::from([
+            ("openai/gpt-4o".to_string(), 0.0000025),
+            ("openai/gpt-4o-mini".to_string(), 0.00000015),
+        ]);
+
+        // Equal latency, so the cheaper model wins even though it sorts later by name
+        for _ in 0..5 {
+            let selected_model = super::route(
+                &models,
+                &MetricSelector::Latency,
+                None,
+                &metrics_repository,
+                None,
+                None,
+                Some(&prices),
+            )
+            .await
+            .unwrap();
+
+            assert_eq!(selected_model, "openai/gpt-4o-mini".to_string());
+        }
+
+        let ranked = rank_candidates(
+            vec![
+                ("openai/gpt-4o".to_string(), 1000.0),
+                ("openai/gpt-4o-mini".to_string(), 1000.0),
+                ("openai/o1".to_string(), 1000.0),
+            ],
+            true,
+            Some(&prices),
+        );
+
+        assert_eq!(
+            ranked,
+            vec![
+                RankedCandidate {
+                    model: "openai/gpt-4o-mini".to_string(),
+                    value: 1000.0,
+                    price: Some(0.00000015),
+                },
+                RankedCandidate {
+                    model: "openai/gpt-4o".to_string(),
+                    value: 1000.0,
+                    price: Some(0.0000025),
+                },
+                RankedCandidate {
+                    model: "openai/o1".to_string(),
+                    value: 1000.0,
+                    price: None,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_metric_router_excludes_candidates_over_error_rate_ceiling() {
+        let mut failing = create_model_metrics(Some(500.0), Some(200.0));
+        failing.metrics.total.error_rate = Some(0.4);
+        let openai_models = std::collections::BTreeMap::from([
+            ("gpt-4o-mini".to_string(), failing),
+            (
+                "gpt-4o".to_string(),
+                create_model_metrics(Some(1500.0), Some(600.0)),
+            ),
+        ]);
+        let metrics = std::collections::BTreeMap::from([(
+            "openai".to_string(),
+            crate::usage::ProviderMetrics {
+                models: openai_models,
+            },
+        )]);
+        let metrics_repository = MockMetricsRepository::new(metrics);
+
+        let models = vec![
+            "openai/gpt-4o-mini".to_string(),
+            "openai/gpt-4o".to_string(),
+        ];
+
+        // gpt-4o-mini has the lowest latency but fails 40% of requests
+        let ceiling = MetricCeiling {
+            max_error_rate: Some(0.1),
+            ..Default::default()
+        };
+        let selected_model = super::route_within_ceiling(
+            &models,
+            &MetricSelector::Latency,
+            None,
+            &metrics_repository,
+            &ceiling,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(selected_model, Some("openai/gpt-4o".to_string()));
+
+        // Both models exceed the latency ceiling, so the declared fallback is used
+        let ceiling = MetricCeiling {
+            max_latency: Some(100.0),
+            fallback_models: vec!["anthropic/claude-3-5-haiku".to_string()],
+            ..Default::default()
+        };
+        let selected_model = super::route_within_ceiling(
+            &models,
+            &MetricSelector::Latency,
+            None,
+            &metrics_repository,
+            &ceiling,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(selected_model, None);
+
+        assert_eq!(
+            ceiling.exceeded_by(&create_model_metrics(Some(1500.0), None).metrics.total),
+            Some("latency 1500 exceeds 100".to_string())
+        );
+    }
+
+    /// Routing decisions logged while routing with `config` as the decision log
+    async fn logged_decisions(config: RoutingDecisionLogConfig) -> Vec<RecordedEvent> {
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let metrics = std::collections::BTreeMap::from([(
+            "openai".to_string(),
+            crate::usage::ProviderMetrics {
+                models: std::collections::BTreeMap::from([
+                    (
+                        "gpt-4o-mini".to_string(),
+                        create_model_metrics(Some(800.0), None),
+                    ),
+                    (
+                        "gpt-4o".to_string(),
+                        create_model_metrics(Some(1500.0), None),
+                    ),
+                ]),
+            },
+        )]);
+        let models = vec![
+            "openai/gpt-4o-mini".to_string(),
+            "openai/gpt-4o".to_string(),
+        ];
+
+        let model = decision_log::scope(
+            config,
+            super::route(
+                &models,
+                &MetricSelector::Latency,
+                None,
+                &MockMetricsRepository::new(metrics),
+                None,
+                None,
+                None,
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(model, "openai/gpt-4o-mini");
+
+        recorded
+            .events()
+            .into_iter()
+            .filter(|event| event.target == DECISION_LOG_TARGET)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_optimized_route_logs_decision() {
+        let decisions = logged_decisions(RoutingDecisionLogConfig::default()).await;
+        assert_eq!(decisions.len(), 1);
+        assert_eq!(decisions[0].level, tracing::Level::INFO);
+
+        let decisions = logged_decisions(RoutingDecisionLogConfig {
+            enabled: true,
+            level: DecisionLogLevel::Debug,
+        })
+        .await;
+        assert_eq!(decisions.len(), 1);
+        let fields = &decisions[0].fields;
+        assert_eq!(decisions[0].level, tracing::Level::DEBUG);
+        assert_eq!(fields["best_model"], "openai/gpt-4o-mini");
+        assert_eq!(fields["metric"], "latency");
+        assert!(fields["candidates"].contains("openai/gpt-4o"));
+        assert!(fields["candidates"].contains("800"));
+    }
+
+    #[tokio::test]
+    async fn test_disabled_decision_log_logs_nothing() {
+        let decisions = logged_decisions(RoutingDecisionLogConfig {
+            enabled: false,
+            level: DecisionLogLevel::Info,
+        })
+        .await;
+        assert!(decisions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_latency_spike_shifts_routing_faster_under_ewma() {
+        use crate::usage::rollup::RequestSample;
+        use crate::usage::InMemoryStorage;
+        use std::time::{Duration, Instant};
+
+        let storage = InMemoryStorage::new().with_ewma_half_life(Duration::from_secs(120));
+        let start = Instant::now();
+        let at = |minute: u64| start + Duration::from_secs(minute * 60);
+        let record = |model: &str, minute: u64, latency: f64| {
+            let identifier = format!("openai:{model}");
+            let sample = RequestSample {
+                latency_ms: latency,
+                ..Default::default()
+            };
+            storage.record_request_at(&identifier, &sample, at(minute));
+            storage.record_ewma_at(&identifier, "latency", latency, at(minute));
+        };
+
+        // An hour of steady traffic where gpt-4o-mini is the faster model, after which its
+        // latency spikes for the last five minutes
+        for minute in 0..60 {
+            let mini_latency = if minute < 55 { 500.0 } else { 3000.0 };
+            record("gpt-4o-mini", minute, mini_latency);
+            record("gpt-4o", minute, 800.0);
+        }
+
+        let metrics = storage.get_all_counters_at(at(59)).await;
+        let repository = MockMetricsRepository::new(metrics);
+        let models = vec![
+            "openai/gpt-4o-mini".to_string(),
+            "openai/gpt-4o".to_string(),
+        ];
+
+        let route = |duration: MetricsDuration| {
+            let models = models.clone();
+            let repository = &repository;
+            async move {
+                super::route(
+                    &models,
+                    &MetricSelector::Latency,
+                    Some(&duration),
+                    repository,
+                    None,
+                    None,
+                    None,
+                )
+                .await
+                .unwrap()
+            }
+        };
+
+        // The spike barely moves the hourly average, the moving average already reflects it
+        assert_eq!(route(MetricsDuration::LastHour).await, "openai/gpt-4o-mini");
+        assert_eq!(route(MetricsDuration::Ewma).await, "openai/gpt-4o");
+    }
 }
@@ -1,5 +1,6 @@
 pub mod database;
 pub mod metrics_database;
+pub mod trace_context;
 
 use crate::metadata::models::trace::DbTrace;
 use std::convert::TryFrom;
@@ -11,6 +12,7 @@ use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
 use opentelemetry::propagation::Extractor;
 use opentelemetry_sdk::trace::SpanData;
+use tokio::sync::broadcast;
 use vllora_telemetry::{map_span, trace_id_uuid, ProjectTraceMap, Span};
 
 pub fn span_to_db_trace(span: &Span) -> Option<DbTrace> {
@@ -46,6 +48,7 @@ struct RunSpanBufferEntry {
 pub struct RunSpanBuffer {
     ttl: Duration,
     inner: DashMap<String, RunSpanBufferEntry>,
+    completed: broadcast::Sender<Span>,
 }
 
 impl RunSpanBuffer {
@@ -53,6 +56,18 @@ impl RunSpanBuffer {
         Self {
             ttl,
             inner: DashMap::new(),
+            completed: broadcast::channel(1024).0,
+        }
+    }
+
+    /// Receives every completed span, including those outside of a run
+    pub fn subscribe(&self) -> broadcast::Receiver<Span> {
+        self.completed.subscribe()
+    }
+
+    pub fn publish(&self, span: &Span) {
+        if self.completed.receiver_count() > 0 {
+            let _ = self.completed.send(span.clone());
         }
     }
 
@@ -178,6 +193,7 @@ impl opentelemetry_sdk::trace::SpanExporter for RunSpanBufferExporter {
     async fn export(&self, batch: Vec<SpanData>) -> opentelemetry_sdk::error::OTelSdkResult {
         for span in batch {
             if let Some(span) = map_span(span) {
+                self.buffer.publish(&span);
                 if span.run_id.is_some() {
                     self.buffer.insert(span);
                 }
@@ -0,0 +1,132 @@
+use super::HeaderExtractor;
+use actix_web::http::header::HeaderMap;
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::Context;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+/// Providers that receive the gateway's W3C trace context (`traceparent`, `tracestate`) on
+/// every call, so spans of a traced provider, e.g. a self hosted model behind the proxy
+/// provider, link into the gateway trace
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraceContextConfig {
+    pub outbound_providers: Vec<String>,
+}
+
+impl TraceContextConfig {
+    /// Trace context headers of `span` for a call to `provider`
+    pub fn outbound_headers(
+        &self,
+        provider: &str,
+        span: &tracing::Span,
+    ) -> HashMap<String, String> {
+        if !self.outbound_providers.iter().any(|p| p == provider) {
+            return HashMap::new();
+        }
+
+        let mut headers = HashMap::new();
+        TraceContextPropagator::new().inject_context(&span.context(), &mut headers);
+        headers
+    }
+}
+
+/// Caller's trace context from W3C `traceparent` / `tracestate` headers, `None` when the
+/// request carries no valid one
+pub fn remote_parent_context(headers: &HeaderMap) -> Option<Context> {
+    let context = TraceContextPropagator::new()
+        .extract_with_context(&Context::new(), &HeaderExtractor(headers));
+    let span_context = context.span().span_context().clone();
+    (span_context.is_valid() && span_context.is_remote()).then_some(context)
+}
+
+/// Makes `span` a child of the caller's span, continuing their distributed trace instead of
+/// starting a new one. Contexts that are not remote are ignored.
+pub fn continue_remote_trace(span: &tracing::Span, context: Option<&Context>) -> bool {
+    match context {
+        Some(context) if context.span().span_context().is_remote() => {
+            let _ = span.set_parent(context.clone());
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::header::{HeaderName, HeaderValue};
+    use opentelemetry::trace::{TraceId, TracerProvider as _};
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+    const PARENT_ID: &str = "00f067aa0ba902b7";
+
+    fn headers(traceparent: Option<&str>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        if let Some(traceparent) = traceparent {
+            headers.insert(
+                HeaderName::from_static("traceparent"),
+                HeaderValue::from_str(traceparent).unwrap(),
+            );
+        }
+        headers
+    }
+
+    fn with_otel_subscriber(f: impl FnOnce()) {
+        let provider = SdkTracerProvider::builder().build();
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")));
+        tracing::subscriber::with_default(subscriber, f);
+    }
+
+    #[test]
+    fn test_incoming_traceparent_becomes_parent_of_gateway_span() {
+        let headers = headers(Some(&format!("00-{TRACE_ID}-{PARENT_ID}-01")));
+        let context = remote_parent_context(&headers).unwrap();
+
+        with_otel_subscriber(|| {
+            let span = tracing::info_span!("cloud_api_invoke");
+            assert!(continue_remote_trace(&span, Some(&context)));
+
+            let gateway_context = span.context();
+            let gateway_span = gateway_context.span();
+            let span_context = gateway_span.span_context();
+            assert_eq!(
+                span_context.trace_id(),
+                TraceId::from_hex(TRACE_ID).unwrap()
+            );
+            assert_ne!(span_context.span_id().to_string(), PARENT_ID);
+
+            // Outbound calls carry the same trace, with the gateway span as parent
+            let config = TraceContextConfig {
+                outbound_providers: vec!["proxy".to_string()],
+            };
+            let outbound = config.outbound_headers("proxy", &span);
+            let traceparent = &outbound["traceparent"];
+            assert!(traceparent.contains(TRACE_ID));
+            assert!(traceparent.contains(&span_context.span_id().to_string()));
+            assert!(config.outbound_headers("openai", &span).is_empty());
+        });
+    }
+
+    #[test]
+    fn test_requests_without_trace_context_start_a_new_trace() {
+        assert!(remote_parent_context(&headers(None)).is_none());
+        assert!(remote_parent_context(&headers(Some("00-garbage-01"))).is_none());
+
+        with_otel_subscriber(|| {
+            let span = tracing::info_span!("cloud_api_invoke");
+            assert!(!continue_remote_trace(&span, None));
+            assert!(!continue_remote_trace(&span, Some(&Context::new())));
+            assert_ne!(
+                span.context().span().span_context().trace_id(),
+                TraceId::from_hex(TRACE_ID).unwrap()
+            );
+        });
+    }
+}
@@ -3,6 +3,8 @@ use validator::Validate;
 use validator::ValidationError;
 use vllora_llm::async_openai::types::embeddings::EmbeddingUsage;
 
+use crate::GatewayError;
+
 #[derive(Serialize, Deserialize, Validate, Clone, Debug)]
 #[validate(schema(function = "validate_openai_embedding_params"))]
 pub struct OpenAiEmbeddingParams {
@@ -33,6 +35,40 @@ impl EmbeddingResult {
             EmbeddingResult::Base64(response) => &response.usage,
         }
     }
+
+    /// Appends the embeddings of the next batch of inputs, continuing their indexes and
+    /// adding up usage
+    pub fn extend(&mut self, other: EmbeddingResult) -> Result<(), GatewayError> {
+        let offset = self.data_len() as u32;
+        match (self, other) {
+            (EmbeddingResult::Float(response), EmbeddingResult::Float(other)) => {
+                response
+                    .data
+                    .extend(other.data.into_iter().map(|mut embedding| {
+                        embedding.index += offset;
+                        embedding
+                    }));
+                response.usage.prompt_tokens += other.usage.prompt_tokens;
+                response.usage.total_tokens += other.usage.total_tokens;
+            }
+            (EmbeddingResult::Base64(response), EmbeddingResult::Base64(other)) => {
+                response
+                    .data
+                    .extend(other.data.into_iter().map(|mut embedding| {
+                        embedding.index += offset;
+                        embedding
+                    }));
+                response.usage.prompt_tokens += other.usage.prompt_tokens;
+                response.usage.total_tokens += other.usage.total_tokens;
+            }
+            _ => {
+                return Err(GatewayError::CustomError(
+                    "Embedding batches returned different encoding formats".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
 }
 
 impl From<vllora_llm::async_openai::types::embeddings::CreateEmbeddingResponse>
@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use vllora_llm::types::gateway::{GuardOrName, GuardWithParameters};
+
+/// Guards evaluated on every request of a project, whether or not the request lists them
+/// in `extra.guards`. Each guard runs at the stage it's defined for. `all` applies to every
+/// project, `projects` adds guards for a project by slug.
+///
+/// ```yaml
+/// default_guards:
+///   all: [moderation]
+///   projects:
+///     support-bot:
+///       - id: pii-detection
+///         parameters: { threshold: 0.8 }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DefaultGuardsConfig {
+    pub all: Vec<GuardOrName>,
+    pub projects: HashMap<String, Vec<GuardOrName>>,
+}
+
+impl DefaultGuardsConfig {
+    /// Default guards of the project with `project_slug`
+    pub fn for_project(&self, project_slug: Option<&str>) -> Vec<GuardOrName> {
+        let project = project_slug.and_then(|slug| self.projects.get(slug));
+        merge_guards(&self.all, project.map(Vec::as_slice).unwrap_or_default())
+    }
+}
+
+fn guard_id(guard: &GuardOrName) -> &str {
+    match guard {
+        GuardOrName::GuardId(id) => id,
+        GuardOrName::GuardWithParameters(GuardWithParameters { id, .. }) => id,
+    }
+}
+
+/// Default guards followed by the requested ones. A requested guard replaces the default
+/// with the same id, so a request can set the parameters of a default guard.
+pub fn merge_guards(defaults: &[GuardOrName], requested: &[GuardOrName]) -> Vec<GuardOrName> {
+    defaults
+        .iter()
+        .filter(|default| {
+            !requested
+                .iter()
+                .any(|guard| guard_id(guard) == guard_id(default))
+        })
+        .chain(requested)
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credentials::{KeyStorage, ProviderKeyResolver};
+    use crate::executor::context::ExecutorContext;
+    use crate::handler::CallbackHandlerFn;
+    use crate::metadata::services::model::ModelServiceImpl;
+    use crate::metadata::test_utils::setup_test_database;
+    use crate::model::{apply_guardrails, DefaultModelMetadataFactory, ModelMetadataFactory};
+    use crate::routing::interceptor::rate_limiter::InMemoryRateLimiterService;
+    use crate::types::guardrails::service::GuardrailsEvaluator;
+    use crate::types::guardrails::{GuardResult, GuardStage};
+    use crate::types::metadata::project::Project;
+    use crate::types::metadata::services::model::ModelService;
+    use actix_web::HttpMessage;
+    use std::sync::Arc;
+    use vllora_llm::types::credentials_ident::CredentialsIdent;
+    use vllora_llm::types::gateway::{
+        ChatCompletionMessage, CostCalculationResult, CostCalculator, CostCalculatorError, Usage,
+    };
+    use vllora_llm::types::provider::ModelPrice;
+
+    fn config() -> DefaultGuardsConfig {
+        serde_json::from_value(serde_json::json!({
+            "all": ["moderation"],
+            "projects": {
+                "support-bot": [{"id": "pii-detection", "parameters": {"threshold": 0.8}}]
+            }
+        }))
+        .unwrap()
+    }
+
+    fn ids(guards: &[GuardOrName]) -> Vec<&str> {
+        guards.iter().map(guard_id).collect()
+    }
+
+    #[test]
+    fn test_request_without_guards_gets_default_guards() {
+        let config = config();
+
+        let defaults = config.for_project(Some("support-bot"));
+        assert_eq!(
+            ids(&merge_guards(&defaults, &[])),
+            ["moderation", "pii-detection"]
+        );
+        assert_eq!(ids(&config.for_project(Some("other"))), ["moderation"]);
+        assert_eq!(ids(&config.for_project(None)), ["moderation"]);
+    }
+
+    #[test]
+    fn test_requested_guard_overrides_default() {
+        let defaults = config().for_project(Some("support-bot"));
+        let requested = vec![
+            GuardOrName::GuardWithParameters(GuardWithParameters {
+                id: "pii-detection".to_string(),
+                parameters: serde_json::json!({"threshold": 0.5}),
+            }),
+            GuardOrName::GuardId("toxicity".to_string()),
+        ];
+
+        let guards = merge_guards(&defaults, &requested);
+        assert_eq!(ids(&guards), ["moderation", "pii-detection", "toxicity"]);
+        assert!(matches!(
+            &guards[1],
+            GuardOrName::GuardWithParameters(GuardWithParameters { parameters, .. })
+                if parameters["threshold"] == 0.5
+        ));
+    }
+
+    /// Passes every guard and records the ids it evaluated
+    #[derive(Default)]
+    struct RecordedGuards(std::sync::Mutex<Vec<String>>);
+
+    #[async_trait::async_trait]
+    impl GuardrailsEvaluator for RecordedGuards {
+        async fn evaluate(
+            &self,
+            _messages: &[ChatCompletionMessage],
+            guard_id: &str,
+            _executor_context: &ExecutorContext,
+            _parameters: Option<&serde_json::Value>,
+            _guard_stage: &GuardStage,
+        ) -> Result<GuardResult, String> {
+            self.0.lock().unwrap().push(guard_id.to_string());
+            Ok(GuardResult::Boolean {
+                passed: true,
+                confidence: None,
+            })
+        }
+    }
+
+    struct NoCost;
+
+    #[async_trait::async_trait]
+    impl CostCalculator for NoCost {
+        async fn calculate_cost(
+            &self,
+            _model_price: &ModelPrice,
+            _usage: &Usage,
+            _credentials_ident: &CredentialsIdent,
+        ) -> Result<CostCalculationResult, CostCalculatorError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_guards_run_for_request_without_guards() {
+        let now = chrono::Utc::now().naive_utc();
+        let project = Project {
+            id: uuid::Uuid::new_v4(),
+            name: "Support bot".to_string(),
+            description: None,
+            created_at: now,
+            updated_at: now,
+            company_id: uuid::Uuid::nil(),
+            slug: "support-bot".to_string(),
+            settings: None,
+            is_default: false,
+            archived_at: None,
+            allowed_user_ids: None,
+            private_model_prices: None,
+        };
+        let req = actix_web::test::TestRequest::default()
+            .app_data(config())
+            .to_http_request();
+        req.extensions_mut().insert(project.clone());
+
+        let db_pool = setup_test_database();
+        let executor_context = ExecutorContext::new(
+            CallbackHandlerFn(None),
+            Arc::new(Box::new(NoCost) as Box<dyn CostCalculator>),
+            Arc::new(Box::new(DefaultModelMetadataFactory::new(Arc::new(Box::new(
+                ModelServiceImpl::new(db_pool.clone()),
+            )
+                as Box<dyn ModelService>)))
+                as Box<dyn ModelMetadataFactory>),
+            &req,
+            HashMap::new(),
+            Arc::new(Box::new(RecordedGuards::default()) as Box<dyn GuardrailsEvaluator>),
+            Arc::new(InMemoryRateLimiterService::new()),
+            project.id,
+            Arc::new(Box::new(ProviderKeyResolver::new(db_pool)) as Box<dyn KeyStorage>),
+            None,
+        )
+        .unwrap();
+
+        let evaluator = RecordedGuards::default();
+        let messages = vec![ChatCompletionMessage::new_text(
+            "user".to_string(),
+            "Hi".to_string(),
+        )];
+        apply_guardrails(
+            &messages,
+            None,
+            &evaluator,
+            &executor_context,
+            GuardStage::Input,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            *evaluator.0.lock().unwrap(),
+            ["moderation".to_string(), "pii-detection".to_string()]
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use serde::{Deserialize, Serialize};
+use vllora_llm::client::completions::json_repair::JsonRepairModel;
+use vllora_llm::client::ModelInstance;
+use vllora_llm::types::gateway::{ChatCompletionRequest, OpenaiResponseFormat};
+
+/// Repairs responses to requests asking for JSON output (`response_format` of `json_object` or
+/// `json_schema`). Responses that aren't valid JSON, or don't match the requested schema, are
+/// sent back to the model with the validation error, at most `max_repairs` times. Disabled by
+/// default, as every repair is an extra model call.
+///
+/// ```yaml
+/// json_repair:
+///   enabled: true
+///   max_repairs: 2
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct JsonRepairConfig {
+    pub enabled: bool,
+    pub max_repairs: usize,
+}
+
+impl Default for JsonRepairConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_repairs: 1,
+        }
+    }
+}
+
+impl JsonRepairConfig {
+    pub fn apply(
+        &self,
+        instance: Box<dyn ModelInstance>,
+        request: &ChatCompletionRequest,
+    ) -> Box<dyn ModelInstance> {
+        if !self.enabled {
+            return instance;
+        }
+        let schema = match &request.response_format {
+            Some(OpenaiResponseFormat::JsonObject) => None,
+            Some(OpenaiResponseFormat::JsonSchema { json_schema }) => json_schema.schema.clone(),
+            _ => return instance,
+        };
+        Box::new(JsonRepairModel::new(instance, schema, self.max_repairs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+    use tokio::sync::mpsc;
+    use tracing::Instrument;
+    use vllora_llm::client::completions::response_stream::ResultStream;
+    use vllora_llm::error::LLMResult;
+    use vllora_llm::types::gateway::{
+        ChatCompletionContent, ChatCompletionMessage, ChatCompletionMessageWithFinishReason,
+        ResponseFormatJsonSchema,
+    };
+    use vllora_llm::types::message::Message;
+    use vllora_llm::types::{ModelEvent, ModelFinishReason};
+    use vllora_telemetry::test_utils::RecordingLayer;
+
+    /// Returns `outputs` in order
+    struct ScriptedModel(Mutex<Vec<&'static str>>);
+
+    #[async_trait]
+    impl ModelInstance for ScriptedModel {
+        async fn invoke(
+            &self,
+            _input_vars: HashMap<String, Value>,
+            _tx: mpsc::Sender<Option<ModelEvent>>,
+            _previous_messages: Vec<Message>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+            let output = self.0.lock().unwrap().remove(0);
+            Ok(ChatCompletionMessageWithFinishReason::new(
+                ChatCompletionMessage {
+                    role: "assistant".to_string(),
+                    content: Some(ChatCompletionContent::Text(output.to_string())),
+                    ..Default::default()
+                },
+                ModelFinishReason::Stop,
+                "id".to_string(),
+                0,
+                "model".to_string(),
+                None,
+            ))
+        }
+
+        async fn stream(
+            &self,
+            _input_vars: HashMap<String, Value>,
+            _tx: mpsc::Sender<Option<ModelEvent>>,
+            _previous_messages: Vec<Message>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<ResultStream> {
+            unimplemented!()
+        }
+    }
+
+    fn schema_request() -> ChatCompletionRequest {
+        ChatCompletionRequest {
+            response_format: Some(OpenaiResponseFormat::JsonSchema {
+                json_schema: ResponseFormatJsonSchema {
+                    description: None,
+                    name: "city".to_string(),
+                    schema: Some(json!({
+                        "type": "object",
+                        "properties": {"city": {"type": "string"}},
+                        "required": ["city"]
+                    })),
+                    strict: None,
+                },
+            }),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_repair_attempts_are_recorded_on_model_span() {
+        let config = JsonRepairConfig {
+            enabled: true,
+            max_repairs: 2,
+        };
+        let instance = config.apply(
+            Box::new(ScriptedModel(Mutex::new(vec![
+                "The city is Paris",
+                r#"{"city": "Paris"}"#,
+            ]))),
+            &schema_request(),
+        );
+
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+        let span = vllora_telemetry::create_model_invoke_span!(
+            "{}",
+            "{}",
+            "openai",
+            "gpt-4o-mini",
+            "gpt-4o-mini",
+            "vllora"
+        );
+        let (tx, _rx) = mpsc::channel(10);
+        let response = instance
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .instrument(span)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text(
+                r#"{"city":"Paris"}"#.to_string()
+            ))
+        );
+        assert_eq!(
+            recorded.values("json_repair_attempts"),
+            vec!["1".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_disabled_or_text_requests_are_not_wrapped() {
+        let (tx, _rx) = mpsc::channel(10);
+        let disabled = JsonRepairConfig::default().apply(
+            Box::new(ScriptedModel(Mutex::new(vec!["not json"]))),
+            &schema_request(),
+        );
+        assert!(disabled
+            .invoke(HashMap::new(), tx.clone(), vec![], HashMap::new())
+            .await
+            .is_ok());
+
+        let config = JsonRepairConfig {
+            enabled: true,
+            max_repairs: 0,
+        };
+        let text = config.apply(
+            Box::new(ScriptedModel(Mutex::new(vec!["not json"]))),
+            &ChatCompletionRequest::default(),
+        );
+        assert!(text
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .is_ok());
+    }
+}
@@ -0,0 +1,43 @@
+use serde::{Deserialize, Serialize};
+use vllora_llm::client::completions::language::LanguageEnforcementModel;
+use vllora_llm::client::ModelInstance;
+
+/// Language every response has to be in, whatever the language of the request.
+/// `required_language` is a language code or locale such as `fr` or `pt-BR`. Responses
+/// detected in another language are regenerated with an instruction to reply in the required
+/// language, at most `max_regenerations` times. Streamed responses can't be regenerated, only
+/// their detected language is recorded.
+///
+/// ```yaml
+/// language_guard:
+///   required_language: fr-FR
+///   max_regenerations: 1
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LanguageGuardConfig {
+    pub required_language: Option<String>,
+    pub max_regenerations: usize,
+}
+
+impl Default for LanguageGuardConfig {
+    fn default() -> Self {
+        Self {
+            required_language: None,
+            max_regenerations: 1,
+        }
+    }
+}
+
+impl LanguageGuardConfig {
+    pub fn apply(&self, instance: Box<dyn ModelInstance>) -> Box<dyn ModelInstance> {
+        match &self.required_language {
+            Some(language) => Box::new(LanguageEnforcementModel::new(
+                instance,
+                language.clone(),
+                self.max_regenerations,
+            )),
+            None => instance,
+        }
+    }
+}
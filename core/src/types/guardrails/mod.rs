@@ -4,9 +4,13 @@ use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
 
+pub mod defaults;
 pub mod evaluator;
+pub mod json_repair;
+pub mod language;
 pub mod partner;
 pub mod service;
+pub mod streaming;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GuardModel {
@@ -86,6 +90,8 @@ pub enum GuardStage {
     Input,
     /// Applied to LLM responses before being returned to the user
     Output,
+    /// Applied to the output accumulated so far while a response streams
+    Streaming,
 }
 
 /// Enum representing what action a guard should take
@@ -173,6 +179,27 @@ pub enum Guard {
         #[serde(flatten)]
         config: GuardConfig,
     },
+    /// Guard delegated to an external guard service over HTTP
+    Http {
+        #[serde(flatten)]
+        config: GuardConfig,
+        endpoint: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        timeout_ms: Option<u64>,
+        #[serde(default)]
+        failure_mode: GuardFailureMode,
+    },
+}
+
+/// How a guard behaves when its evaluator cannot produce a decision
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardFailureMode {
+    /// Treat the guard as passed
+    FailOpen,
+    /// Surface the failure as a guard evaluation error
+    #[default]
+    FailClosed,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -225,6 +252,7 @@ impl Guard {
             Guard::WordCount { config } => &config.stage,
             Guard::Regex { config, .. } => &config.stage,
             Guard::Partner { config, .. } => &config.stage,
+            Guard::Http { config, .. } => &config.stage,
         }
     }
 
@@ -237,6 +265,7 @@ impl Guard {
             Guard::Regex { config, .. } => &config.action,
             Guard::WordCount { config } => &config.action,
             Guard::Partner { config, .. } => &config.action,
+            Guard::Http { config, .. } => &config.action,
         }
     }
 
@@ -249,6 +278,7 @@ impl Guard {
             Guard::Regex { config, .. } => &config.id,
             Guard::WordCount { config } => &config.id,
             Guard::Partner { config, .. } => &config.id,
+            Guard::Http { config, .. } => &config.id,
         }
     }
 
@@ -261,6 +291,7 @@ impl Guard {
             Guard::Regex { config, .. } => &config.name,
             Guard::WordCount { config } => &config.name,
             Guard::Partner { config, .. } => &config.name,
+            Guard::Http { config, .. } => &config.name,
         }
     }
     pub fn parameters(&self) -> Option<&Value> {
@@ -271,6 +302,7 @@ impl Guard {
             Guard::Regex { config, .. } => config.user_defined_parameters.as_ref(),
             Guard::WordCount { config } => config.user_defined_parameters.as_ref(),
             Guard::Partner { config, .. } => config.user_defined_parameters.as_ref(),
+            Guard::Http { config, .. } => config.user_defined_parameters.as_ref(),
         }
     }
     pub fn set_parameters(&mut self, parameters: Value) {
@@ -281,6 +313,7 @@ impl Guard {
             Guard::Regex { config, .. } => config.user_defined_parameters = Some(parameters),
             Guard::WordCount { config } => config.user_defined_parameters = Some(parameters),
             Guard::Partner { config, .. } => config.user_defined_parameters = Some(parameters),
+            Guard::Http { config, .. } => config.user_defined_parameters = Some(parameters),
         }
     }
 
@@ -292,6 +325,7 @@ impl Guard {
             Guard::Regex { config, .. } => &config.template_id,
             Guard::WordCount { config } => &config.template_id,
             Guard::Partner { config, .. } => &config.template_id,
+            Guard::Http { config, .. } => &config.template_id,
         }
     }
 
@@ -303,6 +337,7 @@ impl Guard {
             Guard::Regex { .. } => "regex".to_string(),
             Guard::WordCount { .. } => "word_count".to_string(),
             Guard::Partner { .. } => "partner".to_string(),
+            Guard::Http { .. } => "http".to_string(),
         }
     }
 }
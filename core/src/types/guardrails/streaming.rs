@@ -0,0 +1,225 @@
+use super::GuardError;
+use futures::{stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use vllora_llm::client::completions::response_stream::ResultStream;
+use vllora_llm::error::LLMError;
+use vllora_llm::types::gateway::{ChatCompletionChunk, ChatCompletionChunkChoice};
+
+/// Finish reason of the chunk sent when a streaming guard stops the response
+pub const GUARD_TERMINATION_FINISH_REASON: &str = "content_filter";
+
+/// How often `streaming` stage guards evaluate the output accumulated so far
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamingGuardConfig {
+    /// Output characters received between two evaluations. The output is also evaluated
+    /// when the model finishes.
+    pub check_every_chars: usize,
+}
+
+impl Default for StreamingGuardConfig {
+    fn default() -> Self {
+        Self {
+            check_every_chars: 200,
+        }
+    }
+}
+
+struct GuardedStream<F> {
+    inner: ResultStream,
+    check: F,
+    check_every_chars: usize,
+    output: String,
+    unchecked_chars: usize,
+    pending: VecDeque<Result<ChatCompletionChunk, LLMError>>,
+    terminated: bool,
+}
+
+/// Evaluates the accumulated output of `inner` with `check` every `check_every_chars`
+/// characters. When a check fails, the chunk that tripped it is withheld and the stream
+/// ends with a termination chunk followed by the guard error.
+pub fn guard_stream<F, Fut>(inner: ResultStream, check_every_chars: usize, check: F) -> ResultStream
+where
+    F: FnMut(String) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<(), GuardError>> + Send + 'static,
+{
+    let state = GuardedStream {
+        inner,
+        check,
+        check_every_chars: check_every_chars.max(1),
+        output: String::new(),
+        unchecked_chars: 0,
+        pending: VecDeque::new(),
+        terminated: false,
+    };
+
+    ResultStream::new(Box::pin(stream::unfold(state, |mut state| async move {
+        if let Some(item) = state.pending.pop_front() {
+            return Some((item, state));
+        }
+        if state.terminated {
+            return None;
+        }
+
+        let chunk = match state.inner.next().await? {
+            Ok(chunk) => chunk,
+            Err(e) => return Some((Err(e), state)),
+        };
+
+        for content in chunk
+            .choices
+            .iter()
+            .filter_map(|choice| choice.delta.content.as_ref())
+        {
+            state.output.push_str(content);
+            state.unchecked_chars += content.chars().count();
+        }
+        let finished = chunk
+            .choices
+            .iter()
+            .any(|choice| choice.finish_reason.is_some());
+
+        if state.unchecked_chars >= state.check_every_chars
+            || (finished && state.unchecked_chars > 0)
+        {
+            state.unchecked_chars = 0;
+            if let Err(e) = (state.check)(state.output.clone()).await {
+                tracing::warn!("Streaming guard stopped the response: {e}");
+                state.terminated = true;
+                state
+                    .pending
+                    .push_back(Err(LLMError::BoxedError(Box::new(e))));
+                return Some((Ok(termination_chunk(&chunk)), state));
+            }
+        }
+
+        Some((Ok(chunk), state))
+    })))
+}
+
+fn termination_chunk(chunk: &ChatCompletionChunk) -> ChatCompletionChunk {
+    ChatCompletionChunk {
+        id: chunk.id.clone(),
+        object: chunk.object.clone(),
+        created: chunk.created,
+        model: chunk.model.clone(),
+        choices: vec![ChatCompletionChunkChoice {
+            index: 0,
+            delta: Default::default(),
+            finish_reason: Some(GUARD_TERMINATION_FINISH_REASON.to_string()),
+            logprobs: None,
+        }],
+        usage: None,
+        tool_event: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::guardrails::GuardResult;
+    use std::sync::{Arc, Mutex};
+
+    fn chunk(content: &str, finish_reason: Option<&str>) -> Result<ChatCompletionChunk, LLMError> {
+        Ok(serde_json::from_value(serde_json::json!({
+            "id": "chunk",
+            "object": "chat.completion.chunk",
+            "created": 0,
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "delta": {"content": content},
+                "finish_reason": finish_reason,
+                "logprobs": null
+            }]
+        }))
+        .unwrap())
+    }
+
+    fn content(chunk: &ChatCompletionChunk) -> String {
+        chunk.choices[0].delta.content.clone().unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn test_streaming_guard_stops_stream_after_forbidden_content() {
+        let upstream = ResultStream::new(Box::pin(stream::iter(vec![
+            chunk("The launch ", None),
+            chunk("code is ", None),
+            chunk("SECRET", None),
+            chunk(" 1234", None),
+            chunk(".", Some("stop")),
+        ])));
+        let evaluated = Arc::new(Mutex::new(vec![]));
+
+        let checks = evaluated.clone();
+        let guarded = guard_stream(upstream, 10, move |output: String| {
+            checks.lock().unwrap().push(output.clone());
+            async move {
+                if output.contains("SECRET") {
+                    Err(GuardError::GuardNotPassed(
+                        "no-secrets".to_string(),
+                        GuardResult::Boolean {
+                            passed: false,
+                            confidence: None,
+                        },
+                    ))
+                } else {
+                    Ok(())
+                }
+            }
+        });
+        let items: Vec<_> = guarded.collect().await;
+
+        assert_eq!(items.len(), 4);
+        let sent: Vec<String> = items[..2]
+            .iter()
+            .map(|item| content(item.as_ref().unwrap()))
+            .collect();
+        assert_eq!(sent, vec!["The launch ", "code is "]);
+
+        let marker = items[2].as_ref().unwrap();
+        assert_eq!(
+            marker.choices[0].finish_reason.as_deref(),
+            Some(GUARD_TERMINATION_FINISH_REASON)
+        );
+        assert_eq!(content(marker), "");
+
+        match &items[3] {
+            Err(LLMError::BoxedError(e)) => {
+                assert!(matches!(
+                    e.downcast_ref::<GuardError>(),
+                    Some(GuardError::GuardNotPassed(id, _)) if id == "no-secrets"
+                ));
+            }
+            other => panic!("expected guard error, got {other:?}"),
+        }
+
+        // Evaluated whenever 10 more characters arrived, never after the guard tripped
+        assert_eq!(
+            *evaluated.lock().unwrap(),
+            vec!["The launch ", "The launch code is SECRET"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_streaming_guard_checks_remaining_output_on_finish() {
+        let upstream = ResultStream::new(Box::pin(stream::iter(vec![
+            chunk("short", None),
+            chunk(" answer", Some("stop")),
+        ])));
+        let evaluated = Arc::new(Mutex::new(vec![]));
+
+        let checks = evaluated.clone();
+        let guarded = guard_stream(upstream, 1000, move |output: String| {
+            checks.lock().unwrap().push(output);
+            async { Ok(()) }
+        });
+        let items: Vec<_> = guarded.collect().await;
+
+        assert_eq!(items.len(), 2);
+        assert!(items.iter().all(|item| item.is_ok()));
+        assert_eq!(*evaluated.lock().unwrap(), vec!["short answer"]);
+    }
+}
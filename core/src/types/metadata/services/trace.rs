@@ -1,3 +1,5 @@
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use serde::Deserialize;
 
 use crate::metadata::error::DatabaseError;
@@ -65,6 +67,57 @@ pub struct ListTracesQuery {
     pub sort_order: Option<String>,
     /// Filter by labels (from attribute.label JSON field)
     pub labels: Option<Vec<String>>,
+    /// Only return spans after this position. Applies when sorting by start time.
+    pub after: Option<TraceCursor>,
+}
+
+/// Keyset position of a span in a listing sorted by start time, with the span id breaking
+/// ties. Unlike an offset, resuming from it is not affected by spans inserted meanwhile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceCursor {
+    pub start_time_us: i64,
+    pub span_id: String,
+}
+
+impl TraceCursor {
+    pub fn new(start_time_us: i64, span_id: impl Into<String>) -> Self {
+        Self {
+            start_time_us,
+            span_id: span_id.into(),
+        }
+    }
+
+    /// Opaque representation handed out to clients as `next_cursor`
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.start_time_us, self.span_id))
+    }
+
+    pub fn decode(cursor: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+        let (start_time_us, span_id) = decoded.split_once(':')?;
+        Some(Self::new(start_time_us.parse().ok()?, span_id))
+    }
+}
+
+impl ListTracesQuery {
+    /// Whether results are ordered by start time, the only order cursors apply to
+    pub fn sorts_by_start_time(&self) -> bool {
+        self.sort_by.as_deref() != Some("finish_time")
+    }
+
+    /// Resumes the listing from a `next_cursor`. Plain numbers are still accepted as offsets
+    /// for clients of the older offset cursors.
+    pub fn with_cursor(mut self, cursor: &str) -> Result<Self, String> {
+        if let Ok(offset) = cursor.parse::<i64>() {
+            self.offset = offset;
+            return Ok(self);
+        }
+
+        let after = TraceCursor::decode(cursor).ok_or(format!("Invalid cursor: {cursor}"))?;
+        self.after = Some(after);
+        self.offset = 0;
+        Ok(self)
+    }
 }
 
 /// Query parameters for unified GET /group/spans endpoint
@@ -168,6 +221,7 @@ impl Default for ListTracesQuery {
             sort_by: None,
             sort_order: None,
             labels: None,
+            after: None,
         }
     }
 }
@@ -203,4 +257,10 @@ pub trait TraceService {
         project_slug: &str,
         query: BatchGroupSpansQuery,
     ) -> Result<BatchGroupSpansResponse, DatabaseError>;
+    /// Runs spawned by `parent_run_id`, i.e. whose spans carry it as `parent_run_id`
+    fn list_child_run_ids(
+        &self,
+        parent_run_id: &str,
+        project_id: Option<&str>,
+    ) -> Result<Vec<String>, DatabaseError>;
 }
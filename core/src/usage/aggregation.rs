@@ -0,0 +1,154 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use serde_json::Value;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, Mutex};
+use vllora_llm::types::gateway::{CostCalculationResult, GatewayModelUsage};
+use vllora_telemetry::events::SPAN_MODEL_CALL;
+use vllora_telemetry::Span;
+
+use super::rollup::{RequestSample, BUCKET_WIDTH};
+use super::InMemoryStorage;
+
+/// Attribute holding a JSON value either as is or serialized into a string
+fn json_attribute<T: serde::de::DeserializeOwned>(span: &Span, key: &str) -> Option<T> {
+    match span.attributes.get(key)? {
+        Value::String(s) => serde_json::from_str(s).ok(),
+        value => serde_json::from_value(value.clone()).ok(),
+    }
+}
+
+/// `provider:model` identifier and outcome of a completed model call span
+pub fn sample_from_span(span: &Span) -> Option<(String, RequestSample)> {
+    if span.operation_name != SPAN_MODEL_CALL {
+        return None;
+    }
+    let provider = span.attributes.get("provider_name")?.as_str()?;
+    let model = span.attributes.get("model_name")?.as_str()?;
+
+    let usage = json_attribute::<GatewayModelUsage>(span, "usage");
+    let sample = RequestSample {
+        latency_ms: span
+            .end_time_unix_nano
+            .saturating_sub(span.start_time_unix_nano) as f64
+            / 1_000_000.0,
+        // Recorded in microseconds
+        ttft_ms: json_attribute::<f64>(span, "ttft").map(|ttft_us| ttft_us / 1000.0),
+        input_tokens: usage.as_ref().map_or(0.0, |u| u.input_tokens as f64),
+        output_tokens: usage.as_ref().map_or(0.0, |u| u.output_tokens as f64),
+        cost: json_attribute::<CostCalculationResult>(span, "cost").map_or(0.0, |c| c.cost),
+        error: span
+            .attributes
+            .get("error")
+            .is_some_and(|error| !error.is_null()),
+    };
+
+    Some((format!("{provider}:{model}"), sample))
+}
+
+/// Records the samples of completed model call spans into `storage`, where routing reads
+/// its latency, ttft, tps, error rate and usage metrics from. Windowed metrics buckets are
+/// pruned as they expire.
+pub async fn aggregate_metrics(
+    mut spans: broadcast::Receiver<Span>,
+    storage: Arc<Mutex<InMemoryStorage>>,
+) {
+    let mut prune_interval = tokio::time::interval(BUCKET_WIDTH);
+    loop {
+        tokio::select! {
+            span = spans.recv() => match span {
+                Ok(span) => {
+                    if let Some((identifier, sample)) = sample_from_span(&span) {
+                        storage.lock().await.record_request(&identifier, &sample);
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Metrics aggregation skipped {skipped} spans");
+                }
+                Err(RecvError::Closed) => break,
+            },
+            _ = prune_interval.tick() => {
+                storage.lock().await.prune_rollups(Instant::now());
+            }
+        }
+    }
+}
+
+/// Starts a background task aggregating the metrics of completed spans
+pub fn start_metrics_aggregation_task(
+    spans: broadcast::Receiver<Span>,
+    storage: Arc<Mutex<InMemoryStorage>>,
+) {
+    tokio::spawn(aggregate_metrics(spans, storage));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routing::metrics::{InMemoryMetricsRepository, MetricsRepository};
+    use opentelemetry::trace::{SpanId, SpanKind, TraceId};
+    use serde_json::json;
+
+    fn model_call_span(latency_ms: u64, error: Option<&str>) -> Span {
+        let mut attributes = json!({
+            "provider_name": "openai",
+            "model_name": "gpt-4o-mini",
+            "ttft": "200000",
+            "usage": r#"{"input_tokens": 10, "output_tokens": 40, "total_tokens": 50}"#,
+        })
+        .as_object()
+        .unwrap()
+        .clone();
+        if let Some(error) = error {
+            attributes.insert("error".to_string(), json!(error));
+        }
+
+        Span {
+            trace_id: TraceId::from_u128(1),
+            span_id: SpanId::from_u64(1),
+            parent_span_id: None,
+            operation_name: SPAN_MODEL_CALL.to_string(),
+            kind: SpanKind::Internal,
+            start_time_unix_nano: 0,
+            end_time_unix_nano: latency_ms * 1_000_000,
+            attributes,
+            tenant_id: None,
+            project_id: None,
+            thread_id: None,
+            tags: Default::default(),
+            run_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_processed_spans_update_model_metrics() {
+        let storage = Arc::new(Mutex::new(InMemoryStorage::new()));
+        let (tx, rx) = broadcast::channel(16);
+        let task = tokio::spawn(aggregate_metrics(rx, storage.clone()));
+
+        tx.send(model_call_span(1000, None)).unwrap();
+        tx.send(model_call_span(3000, Some("rate limited")))
+            .unwrap();
+        drop(tx);
+        task.await.unwrap();
+
+        let repository =
+            InMemoryMetricsRepository::new(storage.lock().await.get_all_counters().await);
+        let metrics = repository
+            .get_model_metrics("openai", "gpt-4o-mini")
+            .await
+            .unwrap()
+            .unwrap()
+            .metrics;
+
+        assert_eq!(metrics.last_15_minutes.requests, Some(2.0));
+        assert_eq!(metrics.last_15_minutes.latency, Some(2000.0));
+        assert_eq!(metrics.last_15_minutes.ttft, Some(200.0));
+        assert_eq!(metrics.last_15_minutes.tps, Some(20.0));
+        assert_eq!(metrics.last_15_minutes.error_rate, Some(0.5));
+        assert_eq!(metrics.last_hour.output_tokens, Some(80.0));
+        assert_eq!(metrics.total.latency, Some(2000.0));
+        assert!(metrics.ewma.error_rate.is_some());
+    }
+}
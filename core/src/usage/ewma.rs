@@ -0,0 +1,71 @@
+use std::time::{Duration, Instant};
+
+/// Half-life used when none is configured
+pub const DEFAULT_EWMA_HALF_LIFE: Duration = Duration::from_secs(300);
+
+/// Exponentially weighted moving average of irregularly timed samples. Each sample is
+/// weighted by `0.5^(age / half_life)`, so a sample one half-life old counts half as much as
+/// a new one and the average follows recent changes without fixed window edges.
+#[derive(Debug, Clone, Copy)]
+pub struct Ewma {
+    weighted_sum: f64,
+    weight: f64,
+    updated_at: Instant,
+}
+
+impl Ewma {
+    pub fn new(value: f64, at: Instant) -> Self {
+        Self {
+            weighted_sum: value,
+            weight: 1.0,
+            updated_at: at,
+        }
+    }
+
+    pub fn record(&mut self, value: f64, at: Instant, half_life: Duration) {
+        let elapsed = at.saturating_duration_since(self.updated_at);
+        let decay = if half_life.is_zero() {
+            0.0
+        } else {
+            0.5_f64.powf(elapsed.as_secs_f64() / half_life.as_secs_f64())
+        };
+
+        self.weighted_sum = self.weighted_sum * decay + value;
+        self.weight = self.weight * decay + 1.0;
+        self.updated_at = self.updated_at.max(at);
+    }
+
+    pub fn value(&self) -> f64 {
+        self.weighted_sum / self.weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_older_samples_weigh_less() {
+        let start = Instant::now();
+        let half_life = Duration::from_secs(60);
+
+        let mut ewma = Ewma::new(100.0, start);
+        ewma.record(100.0, start + Duration::from_secs(30), half_life);
+        assert_close(ewma.value(), 100.0);
+
+        // The first sample is one half-life old and weighs half as much as the second
+        let mut ewma = Ewma::new(100.0, start);
+        ewma.record(400.0, start + half_life, half_life);
+        assert_close(ewma.value(), 300.0);
+
+        // Samples at the same time are a plain average
+        let mut ewma = Ewma::new(100.0, start);
+        ewma.record(200.0, start, half_life);
+        ewma.record(300.0, start, half_life);
+        assert_close(ewma.value(), 200.0);
+    }
+}
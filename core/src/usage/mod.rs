@@ -1,3 +1,7 @@
+pub mod aggregation;
+pub mod ewma;
+pub mod rollup;
+
 use chrono::{Months, Utc};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
@@ -5,10 +9,14 @@ use std::collections::BTreeMap;
 use std::fmt::Display;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use chrono::Datelike;
 use chrono::Timelike;
 
+use ewma::{Ewma, DEFAULT_EWMA_HALF_LIFE};
+use rollup::{MetricsRollup, RequestSample};
+
 pub fn get_hour_key(company_id: &str, key: &str) -> String {
     let hour = Utc::now().naive_utc().format("%Y-%m-%d-%H");
     format!("{company_id}:{key}:{hour}")
@@ -119,6 +127,8 @@ pub struct TimeMetrics {
     pub total: Metrics,
     pub last_15_minutes: Metrics,
     pub last_hour: Metrics,
+    /// Exponentially weighted averages of latency, ttft and error rate
+    pub ewma: Metrics,
 }
 
 #[derive(Debug, Default, Serialize, Clone)]
@@ -131,15 +141,68 @@ pub struct ProviderMetrics {
     pub models: BTreeMap<String, ModelMetrics>,
 }
 
-#[derive(Default, Clone)]
+#[derive(Clone)]
 pub struct InMemoryStorage {
     counters: Arc<RwLock<BTreeMap<String, AtomicU64>>>,
+    ewma: Arc<RwLock<BTreeMap<String, Ewma>>>,
+    ewma_half_life: Duration,
+    rollups: Arc<RwLock<BTreeMap<String, MetricsRollup>>>,
+}
+
+impl Default for InMemoryStorage {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InMemoryStorage {
     pub fn new() -> Self {
         Self {
             counters: Arc::new(RwLock::new(BTreeMap::new())),
+            ewma: Arc::new(RwLock::new(BTreeMap::new())),
+            ewma_half_life: DEFAULT_EWMA_HALF_LIFE,
+            rollups: Arc::new(RwLock::new(BTreeMap::new())),
+        }
+    }
+
+    pub fn with_ewma_half_life(mut self, half_life: Duration) -> Self {
+        self.ewma_half_life = half_life;
+        self
+    }
+
+    /// Adds a sample to the moving average of `key` (`latency`, `ttft` or `error_rate`)
+    pub fn record_ewma(&self, identifier: &str, key: &str, value: f64) {
+        self.record_ewma_at(identifier, key, value, Instant::now());
+    }
+
+    pub fn record_ewma_at(&self, identifier: &str, key: &str, value: f64, at: Instant) {
+        let mut averages = self.ewma.write();
+        averages
+            .entry(format!("{identifier}:{key}"))
+            .and_modify(|ewma| ewma.record(value, at, self.ewma_half_life))
+            .or_insert_with(|| Ewma::new(value, at));
+    }
+
+    /// Adds a completed request of `identifier` (`provider:model`) to its windowed metrics
+    /// and error rate average
+    pub fn record_request(&self, identifier: &str, sample: &RequestSample) {
+        self.record_request_at(identifier, sample, Instant::now());
+    }
+
+    pub fn record_request_at(&self, identifier: &str, sample: &RequestSample, at: Instant) {
+        self.rollups
+            .write()
+            .entry(identifier.to_string())
+            .or_default()
+            .record(sample, at);
+        let error = if sample.error { 1.0 } else { 0.0 };
+        self.record_ewma_at(identifier, "error_rate", error, at);
+    }
+
+    /// Drops windowed metrics buckets older than the longest window
+    pub fn prune_rollups(&self, now: Instant) {
+        for rollup in self.rollups.write().values_mut() {
+            rollup.prune(now);
         }
     }
 
@@ -192,6 +255,11 @@ impl InMemoryStorage {
     }
 
     pub async fn get_all_counters(&self) -> BTreeMap<String, ProviderMetrics> {
+        self.get_all_counters_at(Instant::now()).await
+    }
+
+    /// Metrics of every model, with the windowed metrics ending at `now`
+    pub async fn get_all_counters_at(&self, now: Instant) -> BTreeMap<String, ProviderMetrics> {
         let counters = self.counters.read();
         let mut providers_metrics: BTreeMap<String, ProviderMetrics> = BTreeMap::new();
 
@@ -238,6 +306,43 @@ impl InMemoryStorage {
             }
         }
 
+        for (key, rollup) in self.rollups.read().iter() {
+            let Some((provider, model)) = key.split_once(':') else {
+                continue;
+            };
+            let metrics = &mut providers_metrics
+                .entry(provider.to_string())
+                .or_default()
+                .models
+                .entry(model.to_string())
+                .or_default()
+                .metrics;
+            rollup.fill(now, metrics);
+        }
+
+        for (key, ewma) in self.ewma.read().iter() {
+            let [provider, model, metric_type] = key.splitn(3, ':').collect::<Vec<_>>()[..] else {
+                continue;
+            };
+
+            let metrics = &mut providers_metrics
+                .entry(provider.to_string())
+                .or_default()
+                .models
+                .entry(model.to_string())
+                .or_default()
+                .metrics
+                .ewma;
+
+            let v = Some(ewma.value());
+            match metric_type {
+                "latency" => metrics.latency = v,
+                "ttft" => metrics.ttft = v,
+                "error_rate" => metrics.error_rate = v,
+                _ => {}
+            }
+        }
+
         providers_metrics
     }
 }
@@ -0,0 +1,182 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use super::{Metrics, TimeMetrics};
+
+/// Width of the buckets the windowed metrics are summed from
+pub const BUCKET_WIDTH: Duration = Duration::from_secs(60);
+/// Longest window kept, older buckets are dropped
+pub const MAX_WINDOW: Duration = Duration::from_secs(3600);
+
+const LAST_15_MINUTES: Duration = Duration::from_secs(15 * 60);
+
+/// Outcome of a single completed model call
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RequestSample {
+    pub latency_ms: f64,
+    pub ttft_ms: Option<f64>,
+    pub input_tokens: f64,
+    pub output_tokens: f64,
+    pub cost: f64,
+    pub error: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Bucket {
+    requests: f64,
+    errors: f64,
+    input_tokens: f64,
+    output_tokens: f64,
+    cost: f64,
+    latency_ms: f64,
+    ttft_ms: f64,
+    ttft_samples: f64,
+}
+
+impl Bucket {
+    fn record(&mut self, sample: &RequestSample) {
+        self.requests += 1.0;
+        self.errors += if sample.error { 1.0 } else { 0.0 };
+        self.input_tokens += sample.input_tokens;
+        self.output_tokens += sample.output_tokens;
+        self.cost += sample.cost;
+        self.latency_ms += sample.latency_ms;
+        if let Some(ttft_ms) = sample.ttft_ms {
+            self.ttft_ms += ttft_ms;
+            self.ttft_samples += 1.0;
+        }
+    }
+
+    fn add(&mut self, other: &Bucket) {
+        self.requests += other.requests;
+        self.errors += other.errors;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cost += other.cost;
+        self.latency_ms += other.latency_ms;
+        self.ttft_ms += other.ttft_ms;
+        self.ttft_samples += other.ttft_samples;
+    }
+
+    /// Sets the averaged metrics: latency, ttft, tps and error rate
+    fn apply_averages(&self, metrics: &mut Metrics) {
+        if self.requests == 0.0 {
+            return;
+        }
+        metrics.latency = Some(self.latency_ms / self.requests);
+        if self.ttft_samples > 0.0 {
+            metrics.ttft = Some(self.ttft_ms / self.ttft_samples);
+        }
+        if self.latency_ms > 0.0 {
+            metrics.tps = Some(self.output_tokens / (self.latency_ms / 1000.0));
+        }
+        metrics.error_rate = Some(self.errors / self.requests);
+    }
+
+    fn apply(&self, metrics: &mut Metrics) {
+        if self.requests == 0.0 {
+            return;
+        }
+        metrics.requests = Some(self.requests);
+        metrics.input_tokens = Some(self.input_tokens);
+        metrics.output_tokens = Some(self.output_tokens);
+        metrics.total_tokens = Some(self.input_tokens + self.output_tokens);
+        metrics.llm_usage = Some(self.cost);
+        self.apply_averages(metrics);
+    }
+}
+
+/// Request metrics of a model, summed per minute for the last hour and overall
+#[derive(Debug, Clone, Default)]
+pub struct MetricsRollup {
+    buckets: VecDeque<(Instant, Bucket)>,
+    total: Bucket,
+}
+
+impl MetricsRollup {
+    pub fn record(&mut self, sample: &RequestSample, at: Instant) {
+        self.total.record(sample);
+        match self.buckets.back_mut() {
+            Some((start, bucket)) if at.saturating_duration_since(*start) < BUCKET_WIDTH => {
+                bucket.record(sample)
+            }
+            _ => {
+                let mut bucket = Bucket::default();
+                bucket.record(sample);
+                self.buckets.push_back((at, bucket));
+            }
+        }
+    }
+
+    /// Drops buckets that fell out of the longest window
+    pub fn prune(&mut self, now: Instant) {
+        while self
+            .buckets
+            .front()
+            .is_some_and(|(start, _)| now.saturating_duration_since(*start) >= MAX_WINDOW)
+        {
+            self.buckets.pop_front();
+        }
+    }
+
+    fn window(&self, now: Instant, window: Duration) -> Bucket {
+        let mut sum = Bucket::default();
+        for (_, bucket) in self
+            .buckets
+            .iter()
+            .filter(|(start, _)| now.saturating_duration_since(*start) < window)
+        {
+            sum.add(bucket);
+        }
+        sum
+    }
+
+    /// Fills the windowed metrics, and the averages of the totals. Total counts are kept by
+    /// the usage counters.
+    pub fn fill(&self, now: Instant, metrics: &mut TimeMetrics) {
+        self.window(now, LAST_15_MINUTES)
+            .apply(&mut metrics.last_15_minutes);
+        self.window(now, MAX_WINDOW).apply(&mut metrics.last_hour);
+        self.total.apply_averages(&mut metrics.total);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_windows_only_sum_recent_buckets() {
+        let start = Instant::now();
+        let mut rollup = MetricsRollup::default();
+        let sample = |latency_ms, error| RequestSample {
+            latency_ms,
+            ttft_ms: Some(100.0),
+            output_tokens: 50.0,
+            error,
+            ..Default::default()
+        };
+
+        rollup.record(&sample(1000.0, false), start);
+        rollup.record(&sample(3000.0, true), start + Duration::from_secs(30 * 60));
+        let now = start + Duration::from_secs(40 * 60);
+
+        let mut metrics = TimeMetrics::default();
+        rollup.fill(now, &mut metrics);
+        assert_eq!(metrics.last_15_minutes.requests, Some(1.0));
+        assert_eq!(metrics.last_15_minutes.latency, Some(3000.0));
+        assert_eq!(metrics.last_15_minutes.error_rate, Some(1.0));
+        assert_eq!(metrics.last_hour.requests, Some(2.0));
+        assert_eq!(metrics.last_hour.latency, Some(2000.0));
+        assert_eq!(metrics.last_hour.tps, Some(25.0));
+        assert_eq!(metrics.total.error_rate, Some(0.5));
+
+        // The first bucket is an hour old and dropped, the total keeps it
+        let now = start + Duration::from_secs(60 * 60);
+        rollup.prune(now);
+        let mut metrics = TimeMetrics::default();
+        rollup.fill(now, &mut metrics);
+        assert_eq!(metrics.last_hour.requests, Some(1.0));
+        assert_eq!(metrics.total.latency, Some(2000.0));
+    }
+}
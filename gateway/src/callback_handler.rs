@@ -10,9 +10,22 @@ use vllora_llm::types::ModelEventType;
 
 use crate::{cost::GatewayCostCalculator, usage::update_usage};
 
+/// Timing and cost of a single completed request, as recorded into the usage counters
+#[derive(Debug, Clone)]
+pub struct RequestMetrics {
+    pub model_name: String,
+    pub duration_ms: Option<u64>,
+    pub ttft_ms: Option<u64>,
+    pub output_tokens: u32,
+    pub cost: f64,
+}
+
+pub type RequestMetricsSender = tokio::sync::mpsc::UnboundedSender<RequestMetrics>;
+
 pub fn init_callback_handler(
     storage: Arc<Mutex<InMemoryStorage>>,
     calculator: GatewayCostCalculator,
+    request_metrics: Option<RequestMetricsSender>,
 ) -> CallbackHandlerFn {
     let (tx, mut rx) = tokio::sync::broadcast::channel(10000);
     let start_times = Arc::new(Mutex::new(HashMap::<String, DateTime<Utc>>::new()));
@@ -92,6 +105,8 @@ pub fn init_callback_handler(
                             };
 
                             if let Some(model) = &model_event.model {
+                                let output_tokens =
+                                    usage.as_ref().map(|u| u.output_tokens).unwrap_or_default();
                                 let result = update_usage(
                                     storage.clone(),
                                     &calculator,
@@ -108,9 +123,22 @@ pub fn init_callback_handler(
                                 )
                                 .await;
 
-                                if let Err(e) = result {
-                                    tracing::error!("Error setting model usage: {e}");
-                                };
+                                match result {
+                                    Ok(cost) => {
+                                        if let Some(tx) = &request_metrics {
+                                            let _ = tx.send(RequestMetrics {
+                                                model_name,
+                                                duration_ms: duration.map(|d| d as u64),
+                                                ttft_ms: ttft.map(|t| t as u64),
+                                                output_tokens,
+                                                cost: cost.unwrap_or_default(),
+                                            });
+                                        }
+                                    }
+                                    Err(e) => {
+                                        tracing::error!("Error setting model usage: {e}");
+                                    }
+                                }
                             }
                         }
                         ModelEventType::ImageGenerationFinish(finish_event) => {
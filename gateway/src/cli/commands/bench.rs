@@ -0,0 +1,364 @@
+use crate::callback_handler::RequestMetrics;
+use crate::config::Config;
+use crate::http::ApiServer;
+use crate::ports::{resolve_ports, Service};
+use crate::seed;
+use crate::session;
+use crate::CliError;
+use clap::Parser;
+use prettytable::{row, Table};
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use vllora_core::events::broadcast_channel_manager::BroadcastChannelManager;
+use vllora_core::metadata::pool::DbPool;
+use vllora_core::pricing::currency::{currency_converter, ConvertedCost, BASE_CURRENCY};
+use vllora_core::telemetry::RunSpanBuffer;
+use vllora_core::usage::InMemoryStorage;
+
+const DEFAULT_PROMPT: &str = "Write a haiku about latency.";
+const DEFAULT_REQUESTS: usize = 100;
+
+/// How long to wait for the gateway to accept connections or flush request metrics
+const SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Parser)]
+pub struct BenchArgs {
+    /// Model or router to send requests to (e.g., openai/gpt-4o-mini or router/my-router)
+    #[arg(long)]
+    pub model: String,
+
+    /// User message sent with every request
+    #[arg(long, default_value = DEFAULT_PROMPT)]
+    pub prompt: String,
+
+    /// Number of requests in flight at once
+    #[arg(long, default_value_t = 10)]
+    pub concurrency: usize,
+
+    /// Stop after sending this many requests (defaults to 100 when no duration is given)
+    #[arg(long, conflicts_with = "duration")]
+    pub requests: Option<usize>,
+
+    /// Stop sending new requests after this many seconds
+    #[arg(long, value_name = "SECONDS")]
+    pub duration: Option<u64>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum StopCondition {
+    Requests(usize),
+    Deadline(Instant),
+}
+
+impl StopCondition {
+    fn from_args(args: &BenchArgs) -> Self {
+        match args.duration {
+            Some(secs) => StopCondition::Deadline(Instant::now() + Duration::from_secs(secs)),
+            None => StopCondition::Requests(args.requests.unwrap_or(DEFAULT_REQUESTS)),
+        }
+    }
+
+    /// Claims the next request slot, returning false once the run is over
+    fn next(&self, claimed: &AtomicUsize) -> bool {
+        match self {
+            StopCondition::Requests(total) => claimed.fetch_add(1, Ordering::SeqCst) < *total,
+            StopCondition::Deadline(deadline) => Instant::now() < *deadline,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Percentiles {
+    pub p50: Option<u64>,
+    pub p95: Option<u64>,
+    pub p99: Option<u64>,
+}
+
+impl Percentiles {
+    fn from_values(mut values: Vec<u64>) -> Self {
+        values.sort_unstable();
+        Self {
+            p50: percentile(&values, 50.0),
+            p95: percentile(&values, 95.0),
+            p99: percentile(&values, 99.0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of already sorted values
+fn percentile(sorted: &[u64], p: f64) -> Option<u64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.saturating_sub(1)).copied()
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchReport {
+    pub model: String,
+    pub concurrency: usize,
+    pub requests: usize,
+    pub errors: usize,
+    pub error_rate: f64,
+    pub elapsed_secs: f64,
+    pub requests_per_sec: f64,
+    pub output_tokens_per_sec: f64,
+    pub latency_ms: Percentiles,
+    pub ttft_ms: Percentiles,
+    pub total_cost: f64,
+    /// `total_cost` in the configured currency, unless that is USD
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub converted_cost: Option<ConvertedCost>,
+}
+
+impl BenchReport {
+    fn new(
+        args: &BenchArgs,
+        requests: usize,
+        errors: usize,
+        elapsed: Duration,
+        metrics: Vec<RequestMetrics>,
+    ) -> Self {
+        let elapsed_secs = elapsed.as_secs_f64();
+        let per_sec = |value: f64| {
+            if elapsed_secs > 0.0 {
+                value / elapsed_secs
+            } else {
+                0.0
+            }
+        };
+        let output_tokens: u64 = metrics.iter().map(|m| m.output_tokens as u64).sum();
+
+        Self {
+            model: args.model.clone(),
+            concurrency: args.concurrency,
+            requests,
+            errors,
+            error_rate: if requests > 0 {
+                errors as f64 / requests as f64
+            } else {
+                0.0
+            },
+            elapsed_secs,
+            requests_per_sec: per_sec((requests - errors) as f64),
+            output_tokens_per_sec: per_sec(output_tokens as f64),
+            latency_ms: Percentiles::from_values(
+                metrics.iter().filter_map(|m| m.duration_ms).collect(),
+            ),
+            ttft_ms: Percentiles::from_values(metrics.iter().filter_map(|m| m.ttft_ms).collect()),
+            total_cost: metrics.iter().map(|m| m.cost).sum(),
+            converted_cost: None,
+        }
+    }
+
+    fn print(&self) {
+        let ms = |value: Option<u64>| {
+            value
+                .map(|v| format!("{v} ms"))
+                .unwrap_or_else(|| "-".to_string())
+        };
+
+        let mut table = Table::new();
+        table.add_row(row![bF=> "Metric", "p50", "p95", "p99"]);
+        table.add_row(row![
+            "Latency",
+            ms(self.latency_ms.p50),
+            ms(self.latency_ms.p95),
+            ms(self.latency_ms.p99),
+        ]);
+        table.add_row(row![
+            "TTFT",
+            ms(self.ttft_ms.p50),
+            ms(self.ttft_ms.p95),
+            ms(self.ttft_ms.p99),
+        ]);
+
+        println!(
+            "\n📊 Benchmark of {} ({} concurrent)\n",
+            self.model, self.concurrency
+        );
+        table.printstd();
+        println!(
+            "\n   Requests:   {} ({} failed, {:.1}% error rate)",
+            self.requests,
+            self.errors,
+            self.error_rate * 100.0
+        );
+        println!("   Elapsed:    {:.2}s", self.elapsed_secs);
+        println!(
+            "   Throughput: {:.2} req/s, {:.1} output tokens/s",
+            self.requests_per_sec, self.output_tokens_per_sec
+        );
+        match &self.converted_cost {
+            Some(cost) => println!("   Total cost: {cost:.6} (${:.6})", self.total_cost),
+            None => println!("   Total cost: ${:.6}", self.total_cost),
+        }
+    }
+}
+
+pub async fn handle_bench(
+    db_pool: DbPool,
+    args: BenchArgs,
+    config_path: String,
+) -> Result<(), CliError> {
+    if args.concurrency == 0 {
+        return Err(CliError::CustomError(
+            "Concurrency must be at least 1".to_string(),
+        ));
+    }
+
+    seed::seed_models(&db_pool).await?;
+    seed::seed_providers(&db_pool).await?;
+    seed::seed_database(&db_pool)?;
+
+    // Run on free ports so a gateway that is already serving is left alone
+    let mut config = Config::load(&config_path)?;
+    for service in resolve_ports(&config).await? {
+        if let Some(port) = service.suggested_port {
+            match service.service {
+                Service::Backend => config.http.port = port,
+                Service::Otel => config.otel.port = port,
+                Service::UI | Service::Distri => {}
+            }
+        }
+    }
+    let base_url = format!("http://127.0.0.1:{}", config.http.port);
+
+    // Requests go through the real gateway, so routers and interceptors run as they would
+    // in production. Timing and cost come from the same callback handler that feeds usage.
+    let (metrics_tx, mut metrics_rx) = tokio::sync::mpsc::unbounded_channel();
+    let server = ApiServer::new(config, db_pool.clone())
+        .with_request_metrics(metrics_tx)
+        .quiet()
+        .start(
+            Some(Arc::new(Mutex::new(InMemoryStorage::new()))),
+            Arc::new(BroadcastChannelManager::new(Default::default())),
+            Arc::new(RunSpanBuffer::new(Duration::from_secs(20))),
+            session::fetch_session_id(db_pool.clone()).await,
+        )
+        .await?;
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            eprintln!("Gateway error: {e}");
+        }
+    });
+    wait_for_gateway(&base_url).await?;
+
+    if !args.json {
+        println!(
+            "🚀 Sending requests to {} with concurrency {}...",
+            args.model, args.concurrency
+        );
+    }
+
+    let client = reqwest::Client::new();
+    let body = serde_json::json!({
+        "model": args.model,
+        "messages": [{"role": "user", "content": args.prompt}],
+        "stream": true,
+    });
+    let stop = StopCondition::from_args(&args);
+    let claimed = Arc::new(AtomicUsize::new(0));
+    let sent = Arc::new(AtomicUsize::new(0));
+    let errors = Arc::new(AtomicUsize::new(0));
+
+    let started_at = Instant::now();
+    let workers = (0..args.concurrency).map(|_| {
+        let client = client.clone();
+        let body = body.clone();
+        let url = format!("{base_url}/v1/chat/completions");
+        let claimed = claimed.clone();
+        let sent = sent.clone();
+        let errors = errors.clone();
+        tokio::spawn(async move {
+            while stop.next(&claimed) {
+                sent.fetch_add(1, Ordering::SeqCst);
+                if let Err(e) = send_request(&client, &url, &body).await {
+                    tracing::debug!("Bench request failed: {e}");
+                    errors.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        })
+    });
+    futures::future::join_all(workers).await;
+    let elapsed = started_at.elapsed();
+
+    let requests = sent.load(Ordering::SeqCst);
+    let errors = errors.load(Ordering::SeqCst);
+
+    // Request metrics are reported asynchronously once the stream finishes
+    let mut metrics = vec![];
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    while metrics.len() < requests - errors {
+        match tokio::time::timeout_at(deadline.into(), metrics_rx.recv()).await {
+            Ok(Some(m)) => metrics.push(m),
+            _ => break,
+        }
+    }
+
+    let mut report = BenchReport::new(&args, requests, errors, elapsed, metrics);
+    let converter = currency_converter();
+    if converter.code() != BASE_CURRENCY {
+        report.converted_cost = Some(converter.convert(report.total_cost).await);
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        report.print();
+    }
+
+    Ok(())
+}
+
+async fn wait_for_gateway(base_url: &str) -> Result<(), CliError> {
+    let address = base_url.trim_start_matches("http://");
+    let deadline = Instant::now() + SETTLE_TIMEOUT;
+    while tokio::net::TcpStream::connect(address).await.is_err() {
+        if Instant::now() >= deadline {
+            return Err(CliError::CustomError(format!(
+                "Gateway did not start listening on {base_url}"
+            )));
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    Ok(())
+}
+
+async fn send_request(
+    client: &reqwest::Client,
+    url: &str,
+    body: &serde_json::Value,
+) -> Result<(), String> {
+    let response = client
+        .post(url)
+        .json(body)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let status = response.status();
+    // Drain the stream so the request runs to completion
+    let text = response.text().await.map_err(|e| e.to_string())?;
+    if !status.is_success() {
+        return Err(format!("{status}: {text}"));
+    }
+
+    // Failures after the stream has started are sent as an error event
+    let stream_error = text
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .filter_map(|data| serde_json::from_str::<serde_json::Value>(data).ok())
+        .find_map(|event| event.get("error").cloned());
+    match stream_error {
+        Some(error) => Err(error.to_string()),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,376 @@
+use crate::config::Config;
+use crate::ports::resolve_ports;
+use crate::CliError;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::time::Duration;
+use uuid::Uuid;
+use vllora_core::metadata::pool::DbPool;
+use vllora_core::metadata::services::project::ProjectServiceImpl;
+use vllora_core::metadata::services::provider::ProvidersServiceImpl;
+use vllora_core::metadata::services::provider_credential::ProviderCredentialsServiceImpl;
+use vllora_core::types::metadata::services::project::ProjectService;
+use vllora_core::types::metadata::services::provider::ProviderService;
+use vllora_core::types::metadata::services::provider_credential::ProviderCredentialsService;
+use vllora_llm::types::credentials::Credentials;
+
+/// How long a provider may take to answer the reachability probe
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Endpoints probed for providers that don't have one configured
+const DEFAULT_ENDPOINTS: [(&str, &str); 7] = [
+    ("openai", "https://api.openai.com/v1"),
+    ("anthropic", "https://api.anthropic.com/v1"),
+    ("gemini", "https://generativelanguage.googleapis.com"),
+    ("vertex-ai", "https://aiplatform.googleapis.com"),
+    ("mistralai", "https://api.mistral.ai/v1"),
+    ("deepseek", "https://api.deepseek.com"),
+    ("openrouter", "https://openrouter.ai/api/v1"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Pass,
+    Warn,
+    Fail,
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::Pass => write!(f, "PASS"),
+            Status::Warn => write!(f, "WARN"),
+            Status::Fail => write!(f, "FAIL"),
+        }
+    }
+}
+
+struct Check {
+    status: Status,
+    name: String,
+    detail: String,
+    hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            status: Status::Pass,
+            name: name.into(),
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Warn,
+            name: name.into(),
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            status: Status::Fail,
+            name: name.into(),
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Checks the config, database, ports and provider credentials, printing a checklist.
+/// Fails when any check fails.
+pub async fn handle_doctor(config_path: String, db_file: String) -> Result<(), CliError> {
+    let mut checks = vec![];
+
+    let config = check_config(&config_path, &mut checks);
+    let db_pool = check_database(&db_file, &mut checks);
+    if let Some(config) = &config {
+        check_ports(config, &mut checks).await;
+    }
+    check_providers(config.as_ref(), db_pool, &mut checks).await;
+
+    for check in &checks {
+        println!("[{}] {}: {}", check.status, check.name, check.detail);
+        if let Some(hint) = &check.hint {
+            println!("       -> {hint}");
+        }
+    }
+
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let failed = count(Status::Fail);
+    println!(
+        "\n{} passed, {} warnings, {} failed",
+        count(Status::Pass),
+        count(Status::Warn),
+        failed
+    );
+
+    if failed > 0 {
+        return Err(CliError::CustomError(format!(
+            "{failed} doctor check(s) failed"
+        )));
+    }
+    Ok(())
+}
+
+fn check_config(config_path: &str, checks: &mut Vec<Check>) -> Option<Config> {
+    const NAME: &str = "Config";
+    if !Path::new(config_path).exists() {
+        checks.push(Check::warn(
+            NAME,
+            format!("{config_path} not found, using defaults"),
+            "Create the file or pass its path with --config",
+        ));
+        return Some(Config::default());
+    }
+
+    let config = match Config::load(config_path) {
+        Ok(config) => config,
+        Err(e) => {
+            checks.push(Check::fail(
+                NAME,
+                format!("{config_path} is invalid: {e}"),
+                "Fix the reported field, and check that referenced environment variables are set",
+            ));
+            return None;
+        }
+    };
+
+    let mut ports = BTreeMap::new();
+    for (service, port) in [
+        ("http", config.http.port),
+        ("ui", config.ui.port),
+        ("otel", config.otel.port),
+        ("distri", config.distri.port),
+    ] {
+        if let Some(other) = ports.insert(port, service) {
+            checks.push(Check::fail(
+                NAME,
+                format!("{other} and {service} are both configured on port {port}"),
+                format!("Give {service}.port a port of its own"),
+            ));
+            return Some(config);
+        }
+    }
+
+    if let Some(providers) = &config.providers {
+        for (provider, credentials) in &providers.0 {
+            if credentials.api_key.trim().is_empty() {
+                checks.push(Check::fail(
+                    NAME,
+                    format!("providers.{provider}.api_key is empty"),
+                    "Set the key, or export the environment variable the config refers to",
+                ));
+                return Some(config);
+            }
+        }
+    }
+
+    checks.push(Check::pass(NAME, format!("{config_path} is valid")));
+    Some(config)
+}
+
+fn check_database(db_file: &str, checks: &mut Vec<Check>) -> Option<DbPool> {
+    const NAME: &str = "Database";
+    let path = Path::new(db_file);
+    let dir = path.parent().unwrap_or(Path::new("."));
+
+    if !path.exists() {
+        match std::fs::create_dir_all(dir).and_then(|_| tempfile_in(dir)) {
+            Ok(()) => checks.push(Check::warn(
+                NAME,
+                format!("{db_file} doesn't exist yet"),
+                "It is created on the first start of vllora",
+            )),
+            Err(e) => checks.push(Check::fail(
+                NAME,
+                format!("{} is not writable: {e}", dir.display()),
+                format!("Grant your user write access to {}", dir.display()),
+            )),
+        }
+        return None;
+    }
+
+    if let Err(e) = OpenOptions::new().read(true).write(true).open(path) {
+        checks.push(Check::fail(
+            NAME,
+            format!("{db_file} can't be opened for reading and writing: {e}"),
+            format!("Check the owner and permissions of {db_file}"),
+        ));
+        return None;
+    }
+    // SQLite also writes its journal next to the database
+    if let Err(e) = tempfile_in(dir) {
+        checks.push(Check::fail(
+            NAME,
+            format!("{} is not writable: {e}", dir.display()),
+            format!("Grant your user write access to {}", dir.display()),
+        ));
+        return None;
+    }
+
+    checks.push(Check::pass(
+        NAME,
+        format!("{db_file} is readable and writable"),
+    ));
+    Some(vllora_core::metadata::pool::establish_connection(
+        db_file.to_string(),
+        1,
+    ))
+}
+
+fn tempfile_in(dir: &Path) -> std::io::Result<()> {
+    let probe = dir.join(format!(".vllora-doctor-{}", Uuid::new_v4()));
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&probe)?;
+    std::fs::remove_file(probe)
+}
+
+async fn check_ports(config: &Config, checks: &mut Vec<Check>) {
+    let services = match resolve_ports(config).await {
+        Ok(services) => services,
+        Err(e) => {
+            checks.push(Check::fail(
+                "Ports",
+                e.to_string(),
+                "Free some ports or configure ports in another range",
+            ));
+            return;
+        }
+    };
+
+    for service in services {
+        let name = format!("Port {}", service.service);
+        match service.suggested_port {
+            None => checks.push(Check::pass(
+                name,
+                format!("{}:{} is free", service.host, service.initial_port),
+            )),
+            Some(suggested) => checks.push(Check::warn(
+                name,
+                format!(
+                    "{}:{} is in use, vllora would start on {suggested} instead",
+                    service.host, service.initial_port
+                ),
+                format!(
+                    "Stop the process using port {} or choose another port in the config or with CLI flags",
+                    service.initial_port
+                ),
+            )),
+        }
+    }
+}
+
+async fn check_providers(
+    config: Option<&Config>,
+    db_pool: Option<DbPool>,
+    checks: &mut Vec<Check>,
+) {
+    // Provider name -> endpoint from the stored credentials, if they carry one
+    let mut configured: BTreeMap<String, Option<String>> = BTreeMap::new();
+
+    if let Some(providers) = config.and_then(|c| c.providers.as_ref()) {
+        for provider in providers.0.keys() {
+            configured.insert(provider.clone(), None);
+        }
+    }
+
+    let mut provider_endpoints = BTreeMap::new();
+    if let Some(db_pool) = db_pool {
+        let stored = ProjectServiceImpl::new(db_pool.clone())
+            .get_default(Uuid::nil())
+            .map_err(|e| e.to_string())
+            .and_then(|project| {
+                ProviderCredentialsServiceImpl::new(db_pool.clone())
+                    .get_all_provider_credentials(Some(&project.slug))
+                    .map_err(|e| e.to_string())
+            });
+        match stored {
+            Ok(stored) => {
+                for (provider, credentials) in stored {
+                    let endpoint = match credentials {
+                        Credentials::ApiKeyWithEndpoint { endpoint, .. } => Some(endpoint),
+                        _ => None,
+                    };
+                    configured.insert(provider, endpoint);
+                }
+            }
+            Err(e) => checks.push(Check::warn(
+                "Providers",
+                format!("Stored credentials could not be read: {e}"),
+                "Start vllora once to migrate the database, then run doctor again",
+            )),
+        }
+
+        if let Ok(providers) = ProvidersServiceImpl::new(db_pool).list_providers() {
+            for provider in providers {
+                if let Some(endpoint) = provider.custom_endpoint.or(provider.endpoint) {
+                    provider_endpoints.insert(provider.name, endpoint);
+                }
+            }
+        }
+    }
+
+    if configured.is_empty() {
+        checks.push(Check::warn(
+            "Providers",
+            "No provider credentials configured",
+            "Add a key in the UI or under `providers` in the config",
+        ));
+        return;
+    }
+
+    let client = match reqwest::Client::builder().timeout(PROBE_TIMEOUT).build() {
+        Ok(client) => client,
+        Err(e) => {
+            checks.push(Check::fail(
+                "Providers",
+                format!("HTTP client could not be created: {e}"),
+                "Check the TLS setup of this machine",
+            ));
+            return;
+        }
+    };
+
+    for (provider, endpoint) in configured {
+        let name = format!("Provider {provider}");
+        let endpoint = endpoint
+            .or_else(|| provider_endpoints.get(&provider).cloned())
+            .or_else(|| {
+                DEFAULT_ENDPOINTS
+                    .iter()
+                    .find(|(name, _)| *name == provider)
+                    .map(|(_, endpoint)| endpoint.to_string())
+            });
+        let Some(endpoint) = endpoint else {
+            checks.push(Check::pass(
+                name,
+                "credentials present, no endpoint to probe",
+            ));
+            continue;
+        };
+
+        // Any HTTP answer, even 401 or 404, shows the provider is reachable
+        match client.get(&endpoint).send().await {
+            Ok(response) => checks.push(Check::pass(
+                name,
+                format!(
+                    "credentials present, {endpoint} answered {}",
+                    response.status()
+                ),
+            )),
+            Err(e) => checks.push(Check::fail(
+                name,
+                format!("credentials present, {endpoint} is unreachable: {e}"),
+                "Check the network, proxy settings (HTTPS_PROXY) and the provider endpoint",
+            )),
+        }
+    }
+}
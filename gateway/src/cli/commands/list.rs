@@ -1,21 +1,71 @@
+use crate::config::Config;
 use crate::run;
 use crate::CliError;
 use ::tracing::info;
+use clap::Parser;
+use vllora_core::handler::models::ModelsFilter;
 use vllora_core::metadata::pool::DbPool;
 use vllora_core::metadata::services::model::ModelServiceImpl;
+use vllora_core::pricing::currency::CurrencyConverter;
 use vllora_core::types::metadata::services::model::ModelService;
+use vllora_llm::types::models::{ModelCapability, ModelIOFormats, ModelType};
 
-pub async fn handle_list(db_pool: DbPool) -> Result<(), CliError> {
+#[derive(Debug, Clone, Parser, Default)]
+pub struct ListArgs {
+    /// Only models with this capability (tools, reasoning)
+    #[arg(long)]
+    pub capability: Option<ModelCapability>,
+
+    /// Only models of this type (completions, embeddings, image_generation, responses)
+    #[arg(long = "type", value_name = "TYPE")]
+    pub model_type: Option<ModelType>,
+
+    /// Only models accepting this input format (text, image, audio, video)
+    #[arg(long)]
+    pub input_format: Option<ModelIOFormats>,
+
+    /// Only models producing this output format (text, image, audio, video)
+    #[arg(long)]
+    pub output_format: Option<ModelIOFormats>,
+
+    /// Only models of this provider
+    #[arg(long)]
+    pub provider: Option<String>,
+}
+
+impl From<ListArgs> for ModelsFilter {
+    fn from(args: ListArgs) -> Self {
+        Self {
+            capability: args.capability,
+            model_type: args.model_type,
+            input_format: args.input_format,
+            output_format: args.output_format,
+            provider: args.provider,
+        }
+    }
+}
+
+pub async fn handle_list(
+    db_pool: DbPool,
+    args: ListArgs,
+    config_path: String,
+) -> Result<(), CliError> {
     // Query models from database
     let model_service = ModelServiceImpl::new(db_pool.clone());
     let db_models = model_service.list(None)?;
 
-    info!("Found {} models in database\n", db_models.len());
+    // Convert DbModel to ModelMetadata, keep the ones matching the filters and display as table
+    let filter = ModelsFilter::from(args);
+    let models = filter.apply(db_models.into_iter().map(|m| m.into()).collect());
+
+    info!("Found {} models in database\n", models.len());
 
-    // Convert DbModel to ModelMetadata and display as table
-    let models: Vec<vllora_llm::types::models::ModelMetadata> =
-        db_models.into_iter().map(|m| m.into()).collect();
+    // Prices are stored in USD, the converted price of one USD is the display rate
+    let config = Config::load(&config_path)?;
+    let rate = CurrencyConverter::from_config(&config.currency)
+        .convert(1.0)
+        .await;
 
-    run::table::pretty_print_models(models);
+    run::table::pretty_print_models(models, &rate);
     Ok(())
 }
@@ -1,3 +1,5 @@
+pub mod bench;
+pub mod doctor;
 pub mod generate_models_json;
 pub mod list;
 pub mod serve;
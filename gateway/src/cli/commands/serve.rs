@@ -16,6 +16,7 @@ use vllora_core::events::broadcast_channel_manager::BroadcastChannelManager;
 use vllora_core::metadata::models::session::DbSession;
 use vllora_core::metadata::pool::DbPool;
 use vllora_core::telemetry::RunSpanBuffer;
+use vllora_core::usage::aggregation::start_metrics_aggregation_task;
 use vllora_core::usage::InMemoryStorage;
 
 embed_assets!("dist", compress = true);
@@ -186,9 +187,13 @@ pub async fn handle_serve(
         }
     });
 
+    let ewma_half_life = config.routing.ewma.half_life();
     let api_server = ApiServer::new(config, db_pool.clone());
     let server_handle = tokio::spawn(async move {
-        let storage = Arc::new(Mutex::new(InMemoryStorage::new()));
+        let storage = Arc::new(Mutex::new(
+            InMemoryStorage::new().with_ewma_half_life(ewma_half_life),
+        ));
+        start_metrics_aggregation_task(run_span_buffer.subscribe(), storage.clone());
         match api_server
             .start(
                 Some(storage),
@@ -87,6 +87,7 @@ pub async fn handle_list(
     vllora_mcp: &VlloraMcpInstance,
     limit: i64,
     offset: i64,
+    cursor: Option<String>,
     run_id: Option<String>,
     thread_id: Option<String>,
     operation_name: Option<String>,
@@ -154,6 +155,7 @@ pub async fn handle_list(
     let page = Some(SearchTracesPage {
         limit,
         offset: Some(offset),
+        cursor,
     });
 
     let params = SearchTracesParams {
@@ -23,6 +23,9 @@ pub enum TracesCommands {
         /// Offset for pagination
         #[arg(long, default_value_t = 0)]
         offset: i64,
+        /// Cursor printed by the previous page, takes precedence over --offset
+        #[arg(long)]
+        cursor: Option<String>,
         /// Filter by run ID
         #[arg(long)]
         run_id: Option<String>,
@@ -91,6 +94,7 @@ pub async fn handle_traces(db_pool: DbPool, cmd: TracesCommands) -> Result<(), C
         TracesCommands::List {
             limit,
             offset,
+            cursor,
             run_id,
             thread_id,
             operation_name,
@@ -104,6 +108,7 @@ pub async fn handle_traces(db_pool: DbPool, cmd: TracesCommands) -> Result<(), C
                 &vllora_mcp,
                 limit,
                 offset,
+                cursor,
                 run_id,
                 thread_id,
                 operation_name,
@@ -72,6 +72,13 @@ pub fn format_run_overview_table(response: &GetRunOverviewResponse) {
         }
     }
 
+    if let Some(parent_run_id) = &run.parent_run_id {
+        run_table.add_row(row!["Parent Run ID", parent_run_id]);
+    }
+    if !response.child_run_ids.is_empty() {
+        run_table.add_row(row!["Child Run IDs", response.child_run_ids.join(", ")]);
+    }
+
     println!("Run Overview:");
     run_table.printstd();
 
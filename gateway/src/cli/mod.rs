@@ -52,8 +52,8 @@ pub struct ServeArgs {
 pub enum Commands {
     /// Start the API server (default if no command specified)
     Serve(ServeArgs),
-    /// List all available models
-    List,
+    /// List available models, optionally filtered
+    List(commands::list::ListArgs),
     /// Sync models and/or providers from API to database
     Sync {
         /// Sync only models
@@ -63,6 +63,10 @@ pub enum Commands {
         #[arg(long)]
         providers: bool,
     },
+    /// Load test a model or router through the gateway
+    Bench(commands::bench::BenchArgs),
+    /// Check config, database, ports and provider credentials
+    Doctor,
     /// Traces information retrieval commands
     #[command(subcommand)]
     Traces(commands::traces::TracesCommands),
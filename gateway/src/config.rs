@@ -5,8 +5,40 @@ use std::collections::HashMap;
 use std::path::Path;
 use thiserror::Error;
 use tracing::debug;
+use vllora_core::credentials::billing::BillingLabelsConfig;
+use vllora_core::executor::chat_completion::post_processing::PostProcessingConfig;
+use vllora_core::executor::chat_completion::response_cache::ResponseCacheConfig;
+use vllora_core::executor::concurrency::AdaptiveConcurrencyConfig;
+use vllora_core::executor::endpoint::EndpointOverrideConfig;
+use vllora_core::executor::parameter_ranges::ParameterRangesConfig;
+use vllora_core::executor::policy::AccessPolicyConfig;
+use vllora_core::executor::propagation::MetadataPropagationConfig;
+use vllora_core::executor::provider_headers::ProviderHeadersConfig;
+use vllora_core::executor::queue::RequestQueueConfig;
+use vllora_core::executor::rate_limit_headers::RateLimitHeadersConfig;
+use vllora_core::executor::warm_up::WarmUpConfig;
 use vllora_core::executor::ProvidersConfig;
+use vllora_core::handler::idempotency::IdempotencyConfig;
+use vllora_core::handler::middleware::replay_protection::ReplayProtectionConfig;
+use vllora_core::handler::prompts::StoredPromptsConfig;
+use vllora_core::handler::request_limits::RequestLimitsConfig;
+use vllora_core::metadata::anonymization::TraceAnonymizationConfig;
+use vllora_core::metadata::encryption::TraceEncryptionConfig;
+use vllora_core::model::first_token_timeout::FirstTokenTimeoutConfig;
+use vllora_core::model::stream_channel::StreamChannelConfig;
+use vllora_core::model::stream_coalescing::StreamCoalescingConfig;
+use vllora_core::plugins::PluginsConfig;
+use vllora_core::pricing::currency::CurrencyConfig;
+use vllora_core::routing::interceptor::rate_limit_store::RateLimitStoreConfig;
+use vllora_core::routing::RoutingConfig;
+use vllora_core::telemetry::trace_context::TraceContextConfig;
+use vllora_core::types::guardrails::defaults::DefaultGuardsConfig;
+use vllora_core::types::guardrails::json_repair::JsonRepairConfig;
+use vllora_core::types::guardrails::language::LanguageGuardConfig;
+use vllora_core::types::guardrails::streaming::StreamingGuardConfig;
 use vllora_core::types::guardrails::Guard;
+use vllora_llm::provider::http_pool::HttpPoolConfig;
+use vllora_llm::provider::retry::RetryConfig;
 
 #[derive(Debug, Error)]
 pub enum ConfigError {
@@ -37,6 +69,70 @@ pub struct Config {
     pub providers: Option<ProvidersConfig>,
     #[serde(default)]
     pub guards: Option<HashMap<String, Guard>>,
+    #[serde(default)]
+    pub routing: RoutingConfig,
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    #[serde(default)]
+    pub policies: AccessPolicyConfig,
+    #[serde(default)]
+    pub billing_labels: BillingLabelsConfig,
+    #[serde(default)]
+    pub endpoint_overrides: EndpointOverrideConfig,
+    #[serde(default)]
+    pub trace_encryption: TraceEncryptionConfig,
+    #[serde(default)]
+    pub trace_anonymization: TraceAnonymizationConfig,
+    #[serde(default)]
+    pub currency: CurrencyConfig,
+    #[serde(default)]
+    pub concurrency: AdaptiveConcurrencyConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub warm_up: WarmUpConfig,
+    #[serde(default)]
+    pub metadata_propagation: MetadataPropagationConfig,
+    #[serde(default)]
+    pub streaming_guard: StreamingGuardConfig,
+    #[serde(default)]
+    pub stream_channel: StreamChannelConfig,
+    #[serde(default)]
+    pub stream_coalescing: StreamCoalescingConfig,
+    #[serde(default)]
+    pub language_guard: LanguageGuardConfig,
+    #[serde(default)]
+    pub json_repair: JsonRepairConfig,
+    #[serde(default)]
+    pub first_token_timeout: FirstTokenTimeoutConfig,
+    #[serde(default)]
+    pub request_queue: RequestQueueConfig,
+    #[serde(default)]
+    pub trace_context: TraceContextConfig,
+    #[serde(default)]
+    pub post_processing: PostProcessingConfig,
+    #[serde(default)]
+    pub rate_limit_headers: RateLimitHeadersConfig,
+    #[serde(default)]
+    pub request_limits: RequestLimitsConfig,
+    #[serde(default)]
+    pub http_pool: HttpPoolConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub rate_limit_store: RateLimitStoreConfig,
+    #[serde(default)]
+    pub prompts: StoredPromptsConfig,
+    #[serde(default)]
+    pub provider_headers: ProviderHeadersConfig,
+    #[serde(default)]
+    pub parameter_ranges: ParameterRangesConfig,
+    #[serde(default)]
+    pub default_guards: DefaultGuardsConfig,
+    #[serde(default)]
+    pub replay_protection: ReplayProtectionConfig,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -24,12 +24,13 @@ impl CostCalculator for GatewayCostCalculator {
         &self,
         price: &ModelPrice,
         usage: &Usage,
-        _credentials_ident: &CredentialsIdent,
+        credentials_ident: &CredentialsIdent,
     ) -> Result<CostCalculationResult, CostCalculatorError> {
         match usage {
             vllora_llm::types::gateway::Usage::ImageGenerationModelUsage(usage) => {
                 if let ModelPrice::ImageGeneration(p) = &price {
-                    Ok(calculate_image_price(p, usage, self.default_image_cost))
+                    Ok(calculate_image_price(p, usage, self.default_image_cost)
+                        .with_billing_label(credentials_ident))
                 } else {
                     Err(CostCalculatorError::CalculationError(
                         "Image model pricing are not set".to_string(),
@@ -58,7 +59,8 @@ impl CostCalculator for GatewayCostCalculator {
                     cached_input_price,
                     cached_input_write_price,
                     output_price,
-                ))
+                )
+                .with_billing_label(credentials_ident))
             }
         }
     }
@@ -19,6 +19,7 @@ use vllora_guardrails::guards::partners::openai::OpenaiGuardrailPartner;
 use vllora_guardrails::guards::traced::TracedGuard;
 use vllora_guardrails::guards::DatasetEvaluator;
 use vllora_guardrails::guards::FileDatasetLoader;
+use vllora_guardrails::guards::HttpEvaluator;
 use vllora_guardrails::guards::LlmJudgeEvaluator;
 use vllora_guardrails::guards::RegexEvaluator;
 use vllora_guardrails::guards::SchemaEvaluator;
@@ -115,6 +116,7 @@ impl GuardrailsService {
             Guard::Partner { .. } => Box::new(PartnerEvaluator::new(Box::new(
                 OpenaiGuardrailPartner::new(None).map_err(|e| e.to_string())?,
             ))) as Box<dyn Evaluator>,
+            Guard::Http { .. } => Box::new(HttpEvaluator::new()) as Box<dyn Evaluator>,
         };
 
         Ok(TracedGuard::new(evaluator))
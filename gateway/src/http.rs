@@ -1,4 +1,4 @@
-use crate::callback_handler::init_callback_handler;
+use crate::callback_handler::{init_callback_handler, RequestMetricsSender};
 use crate::config::Config;
 use crate::cost::GatewayCostCalculator;
 use crate::guardrails::GuardrailsService;
@@ -10,7 +10,6 @@ use crate::middleware::thread_service::ThreadsServiceMiddleware;
 use crate::middleware::trace_logger::TraceLogger;
 use crate::middleware::tracing_context::TracingContext;
 use actix_cors::Cors;
-use actix_web::web::JsonConfig;
 use actix_web::Scope as ActixScope;
 use actix_web::{
     body::MessageBody,
@@ -22,9 +21,11 @@ use futures::{future::try_join, Future, TryFutureExt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::signal;
 use tokio::sync::Mutex;
+use uuid::Uuid;
 use vllora_core::credentials::KeyStorage;
 use vllora_core::credentials::ProviderKeyResolver;
 use vllora_core::events::broadcast_channel_manager::BroadcastChannelManager;
@@ -32,6 +33,9 @@ use vllora_core::events::callback_handler::GatewayCallbackHandlerFn;
 use vllora_core::events::ui_broadcaster::EventsSendersContainer;
 use vllora_core::events::ui_broadcaster::EventsUIBroadcaster;
 use vllora_core::executor::chat_completion::breakpoint::BreakpointManager;
+use vllora_core::executor::concurrency::{provider_concurrency, AdaptiveConcurrencyConfig};
+use vllora_core::executor::provider_headers::ProviderHeadersError;
+use vllora_core::executor::warm_up::{warm_up, WarmUpConfig};
 use vllora_core::handler::chat::create_chat_completion;
 use vllora_core::handler::embedding::embeddings_handler;
 use vllora_core::handler::group;
@@ -41,14 +45,19 @@ use vllora_core::handler::mcp_configs;
 use vllora_core::handler::middleware::actix_otel::CloudApiInvokeMiddleware;
 use vllora_core::handler::middleware::actix_otel::RunSpanMiddleware;
 use vllora_core::handler::middleware::rate_limit::RateLimitMiddleware;
+use vllora_core::handler::middleware::replay_protection::ReplayProtectionMiddleware;
 use vllora_core::handler::middleware::run_id::RunId;
 use vllora_core::handler::middleware::thread_id::ThreadId;
 use vllora_core::handler::responses;
+use vllora_core::handler::routing::explain_routing;
 use vllora_core::handler::runs;
 use vllora_core::handler::spans;
+use vllora_core::handler::text_completions::create_text_completion;
 use vllora_core::handler::traces;
 use vllora_core::handler::CallbackHandlerFn;
 use vllora_core::mcp::server::LocalSessionManager;
+use vllora_core::metadata::anonymization::{init_trace_anonymization, TraceAnonymizationError};
+use vllora_core::metadata::encryption::{init_trace_encryption, TraceEncryptionError};
 use vllora_core::metadata::models::session::DbSession;
 use vllora_core::metadata::pool::DbPool;
 use vllora_core::metadata::project_trace::ProjectTraceTenantResolver;
@@ -59,13 +68,23 @@ use vllora_core::metadata::services::provider::ProvidersServiceImpl;
 use vllora_core::metadata::services::run::RunServiceImpl;
 use vllora_core::metadata::services::trace::TraceServiceImpl as MetadataTraceServiceImpl;
 use vllora_core::metadata::DatabaseService;
+use vllora_core::model::{DefaultModelMetadataFactory, ModelMetadataFactory};
+use vllora_core::plugins::{GatewayPlugin, PluginRegistry};
+use vllora_core::pricing::currency::init_currency;
+use vllora_core::routing::interceptor::rate_limit_store::{
+    init_rate_limit_store, RateLimitStoreError,
+};
+use vllora_core::routing::metrics::InMemoryMetricsRepository;
 use vllora_core::telemetry::database::SqliteTraceWriterTransport;
 use vllora_core::telemetry::metrics_database::SqliteMetricsWriterTransport;
 use vllora_core::telemetry::RunSpanBuffer;
 use vllora_core::types::guardrails::service::GuardrailsEvaluator;
 use vllora_core::types::guardrails::Guard;
 use vllora_core::types::metadata::services::model::ModelService;
+use vllora_core::types::metadata::services::project::ProjectService;
 use vllora_core::usage::InMemoryStorage;
+use vllora_llm::provider::http_pool::init_http_pool;
+use vllora_llm::provider::retry::{init_retry_policy, RetryPatternError};
 use vllora_llm::types::gateway::CostCalculator;
 use vllora_telemetry::MetricsServiceImpl;
 use vllora_telemetry::MetricsServiceServer;
@@ -88,17 +107,55 @@ pub enum ServerError {
     Tonic(#[from] tonic::transport::Error),
     #[error(transparent)]
     AddrParseError(#[from] std::net::AddrParseError),
+    #[error(transparent)]
+    TraceEncryption(#[from] TraceEncryptionError),
+    #[error(transparent)]
+    TraceAnonymization(#[from] TraceAnonymizationError),
+    #[error(transparent)]
+    ProviderHeaders(#[from] ProviderHeadersError),
+    #[error(transparent)]
+    RetryPattern(#[from] RetryPatternError),
+    #[error(transparent)]
+    RateLimitStore(#[from] RateLimitStoreError),
 }
 
 #[derive(Clone, Debug)]
 pub struct ApiServer {
     config: Config,
     db_pool: DbPool,
+    request_metrics: Option<RequestMetricsSender>,
+    plugins: PluginRegistry,
+    quiet: bool,
 }
 
 impl ApiServer {
     pub fn new(config: Config, db_pool: DbPool) -> Self {
-        Self { config, db_pool }
+        let plugins = PluginRegistry::from_config(&config.plugins);
+        Self {
+            config,
+            db_pool,
+            request_metrics: None,
+            plugins,
+            quiet: false,
+        }
+    }
+
+    /// Registers a plugin invoked around every chat completion, after the configured ones
+    pub fn with_plugin(mut self, plugin: Arc<dyn GatewayPlugin>) -> Self {
+        self.plugins.register(plugin);
+        self
+    }
+
+    /// Reports timing and cost of every completed request to `tx`
+    pub fn with_request_metrics(mut self, tx: RequestMetricsSender) -> Self {
+        self.request_metrics = Some(tx);
+        self
+    }
+
+    /// Skips the startup banner
+    pub fn quiet(mut self) -> Self {
+        self.quiet = true;
+        self
     }
 
     pub fn print_useful_info(&self) {
@@ -167,9 +224,29 @@ impl ApiServer {
         run_span_buffer: Arc<RunSpanBuffer>,
         session: DbSession,
     ) -> Result<impl Future<Output = Result<(), ServerError>>, ServerError> {
+        init_trace_encryption(&self.config.trace_encryption)?;
+        init_trace_anonymization(&self.config.trace_anonymization)?;
+        init_currency(&self.config.currency);
+        init_http_pool(self.config.http_pool.clone());
+        init_retry_policy(&self.config.retry)?;
+        init_rate_limit_store(&self.config.rate_limit_store)?;
+        self.config.provider_headers.validate()?;
+
+        if let Some(storage) = storage.clone().filter(|_| self.config.concurrency.enabled) {
+            Self::spawn_concurrency_refresh(storage, self.config.concurrency.clone());
+        }
+
+        if self.config.warm_up.enabled {
+            Self::spawn_warm_up(self.db_pool.clone(), self.config.warm_up.clone());
+        }
+
         let cost_calculator = GatewayCostCalculator::new();
         let callback = if let Some(storage) = &storage {
-            init_callback_handler(storage.clone(), cost_calculator.clone())
+            init_callback_handler(
+                storage.clone(),
+                cost_calculator.clone(),
+                self.request_metrics.clone(),
+            )
         } else {
             CallbackHandlerFn(None)
         };
@@ -204,6 +281,7 @@ impl ApiServer {
                 session_manager.clone(),
                 breakpoint_manager_for_closure.clone(),
                 config.clone(),
+                server_config.plugins.clone(),
             )
         })
         .bind((self.config.http.host.as_str(), self.config.http.port))?
@@ -246,11 +324,67 @@ impl ApiServer {
         let tonic_fut = tonic_server.map_err(ServerError::Tonic);
 
         // Print useful info after servers are bound and ready
-        self.print_useful_info();
+        if !self.quiet {
+            self.print_useful_info();
+        }
 
         Ok(try_join(server, tonic_fut).map_ok(|_| ()))
     }
 
+    /// Periodically adapts provider concurrency limits to the latest usage metrics
+    fn spawn_concurrency_refresh(
+        storage: Arc<Mutex<InMemoryStorage>>,
+        config: AdaptiveConcurrencyConfig,
+    ) {
+        tokio::spawn(async move {
+            let mut interval =
+                tokio::time::interval(Duration::from_secs(config.refresh_interval_secs.max(1)));
+            loop {
+                interval.tick().await;
+                let metrics = storage.lock().await.get_all_counters().await;
+                let metrics_repository = InMemoryMetricsRepository::new(metrics);
+                if let Err(e) = provider_concurrency()
+                    .refresh(&metrics_repository, &config)
+                    .await
+                {
+                    tracing::warn!("Failed to refresh provider concurrency limits: {e}");
+                }
+            }
+        });
+    }
+
+    /// Initializes provider clients for the configured models in the background, so the
+    /// server starts accepting requests right away
+    fn spawn_warm_up(db_pool: DbPool, config: WarmUpConfig) {
+        tokio::spawn(async move {
+            let project = match ProjectServiceImpl::new(db_pool.clone()).get_default(Uuid::nil()) {
+                Ok(project) => project,
+                Err(e) => {
+                    tracing::warn!("Skipping warm up, no default project: {e}");
+                    return;
+                }
+            };
+            let model_service =
+                Arc::new(Box::new(ModelServiceImpl::new(db_pool.clone())) as Box<dyn ModelService>);
+            let model_metadata_factory =
+                DefaultModelMetadataFactory::new(model_service).with_db_pool(&db_pool);
+            let key_storage = ProviderKeyResolver::new(db_pool);
+
+            let report = warm_up(
+                &config,
+                &model_metadata_factory as &dyn ModelMetadataFactory,
+                &key_storage,
+                &project,
+            )
+            .await;
+            tracing::info!(
+                "Warm up finished: {} warmed, {} failed",
+                report.warmed.len(),
+                report.failed.len()
+            );
+        });
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn create_app_entry(
         cors: Cors,
@@ -266,6 +400,7 @@ impl ApiServer {
         session_manager: Arc<LocalSessionManager>,
         breakpoint_manager: Arc<BreakpointManager>,
         config: Config,
+        plugins: PluginRegistry,
     ) -> App<
         impl ServiceFactory<
             ServiceRequest,
@@ -298,6 +433,7 @@ impl ApiServer {
             Box::new(ModelServiceImpl::new(db_pool.clone())) as Box<dyn ModelService>;
 
         let database_service = DatabaseService::new(db_pool.clone());
+        let routing_audit_sink = config.routing.audit.build_sink(&db_pool);
 
         let mcp_scope =
             vllora_core::mcp::server::service::attach_vllora_mcp::<MetadataTraceServiceImpl>(
@@ -306,7 +442,7 @@ impl ApiServer {
                 &database_service,
             );
 
-        let json_config = JsonConfig::default().limit(8 * 1024 * 1024); // 8MB in bytes
+        let json_config = config.request_limits.json_config();
 
         app.wrap(TraceLogger)
             .wrap(ThreadId)
@@ -323,6 +459,32 @@ impl ApiServer {
             .app_data(Data::new(database_service))
             .app_data(Data::from(breakpoint_manager.clone()))
             .app_data(Data::new(model_service))
+            .app_data(config.routing.clone())
+            .app_data(routing_audit_sink)
+            .app_data(config.response_cache.clone())
+            .app_data(config.idempotency.clone())
+            .app_data(config.policies.clone())
+            .app_data(config.billing_labels.clone())
+            .app_data(config.endpoint_overrides.clone())
+            .app_data(config.concurrency.clone())
+            .app_data(config.metadata_propagation.clone())
+            .app_data(config.provider_headers.clone())
+            .app_data(config.parameter_ranges.clone())
+            .app_data(config.streaming_guard.clone())
+            .app_data(config.default_guards.clone())
+            .app_data(config.stream_channel.clone())
+            .app_data(config.stream_coalescing.clone())
+            .app_data(config.language_guard.clone())
+            .app_data(config.json_repair.clone())
+            .app_data(config.first_token_timeout.clone())
+            .app_data(config.request_queue.clone())
+            .app_data(config.trace_context.clone())
+            .app_data(config.post_processing.clone())
+            .app_data(config.rate_limit_headers.clone())
+            .app_data(config.request_limits.clone())
+            .app_data(config.prompts.clone())
+            .app_data(config.replay_protection.clone())
+            .app_data(plugins)
             .app_data(Data::new(config))
             .service(
                 service
@@ -334,7 +496,8 @@ impl ApiServer {
                     .wrap(CloudApiInvokeMiddleware)
                     .wrap(RunSpanMiddleware)
                     .wrap(TracingContext)
-                    .wrap(RateLimitMiddleware),
+                    .wrap(RateLimitMiddleware)
+                    .wrap(ReplayProtectionMiddleware),
             )
             .service(
                 lucy_service
@@ -347,6 +510,7 @@ impl ApiServer {
                     .wrap(RunSpanMiddleware)
                     .wrap(TracingContext)
                     .wrap(RateLimitMiddleware)
+                    .wrap(ReplayProtectionMiddleware)
                     .wrap(LucyProjectMiddleware),
             )
             .service(
@@ -554,6 +718,7 @@ impl ApiServer {
     fn attach_gateway_routes(scope: ActixScope) -> ActixScope {
         scope
             .route("/chat/completions", web::post().to(create_chat_completion))
+            .route("/completions", web::post().to(create_text_completion))
             .route(
                 "/models",
                 web::get().to(crate::handlers::list_models_from_db),
@@ -562,5 +727,6 @@ impl ApiServer {
             .route("/embeddings", web::post().to(embeddings_handler))
             .route("/images/generations", web::post().to(create_image))
             .route("/responses", web::post().to(responses::create))
+            .route("/routing/explain", web::post().to(explain_routing))
     }
 }
@@ -84,6 +84,10 @@ async fn main() -> Result<(), CliError> {
 
     let cli = cli::Cli::parse();
 
+    if let Some(cli::Commands::Doctor) = cli.command {
+        return cli::commands::doctor::handle_doctor(cli.config, db_file_path()).await;
+    }
+
     let db_pool = get_db_pool()?;
 
     if let Some(cli::Commands::Traces(traces_cmd)) = cli.command {
@@ -91,6 +95,11 @@ async fn main() -> Result<(), CliError> {
         return Ok(());
     }
 
+    if let Some(cli::Commands::Bench(bench_args)) = cli.command {
+        cli::commands::bench::handle_bench(db_pool, bench_args, cli.config).await?;
+        return Ok(());
+    }
+
     println!("{LOGO}");
     let project_trace_senders = Arc::new(BroadcastChannelManager::new(Default::default()));
 
@@ -120,10 +129,18 @@ async fn main() -> Result<(), CliError> {
         Some(cli::Commands::Sync { models, providers }) => {
             cli::commands::sync::handle_sync(db_pool, models, providers).await
         }
-        Some(cli::Commands::List) => cli::commands::list::handle_list(db_pool).await,
+        Some(cli::Commands::List(args)) => {
+            cli::commands::list::handle_list(db_pool, args, cli.config).await
+        }
         Some(cli::Commands::Traces(_traces_cmd)) => {
             unreachable!()
         }
+        Some(cli::Commands::Bench(_bench_args)) => {
+            unreachable!()
+        }
+        Some(cli::Commands::Doctor) => {
+            unreachable!()
+        }
         Some(cli::Commands::GenerateModelsJson { output }) => {
             cli::commands::generate_models_json::handle_generate_models_json(output).await
         }
@@ -153,11 +170,16 @@ async fn main() -> Result<(), CliError> {
     }
 }
 
-fn get_db_pool() -> Result<vllora_core::metadata::pool::DbPool, CliError> {
+fn db_file_path() -> String {
     let home_dir = std::env::var("HOME").unwrap_or_else(|_| "~".to_string());
-    let vllora_dir = format!("{home_dir}/.vllora");
-    std::fs::create_dir_all(&vllora_dir).unwrap_or_default();
-    let vllora_db_file = format!("{vllora_dir}/vllora.db");
+    format!("{home_dir}/.vllora/vllora.db")
+}
+
+fn get_db_pool() -> Result<vllora_core::metadata::pool::DbPool, CliError> {
+    let vllora_db_file = db_file_path();
+    if let Some(vllora_dir) = std::path::Path::new(&vllora_db_file).parent() {
+        std::fs::create_dir_all(vllora_dir).unwrap_or_default();
+    }
     let db_pool = vllora_core::metadata::pool::establish_connection(vllora_db_file, 10);
 
     vllora_core::metadata::utils::init_db(&db_pool);
@@ -1,8 +1,10 @@
 use prettytable::{row, Table};
+use vllora_core::pricing::currency::ConvertedCost;
 use vllora_llm::types::models::ModelMetadata;
 use vllora_llm::types::provider::ModelPrice;
 
-pub fn pretty_print_models(models: Vec<ModelMetadata>) {
+/// `rate` is the price of one USD in the display currency
+pub fn pretty_print_models(models: Vec<ModelMetadata>, rate: &ConvertedCost) {
     let mut table = Table::new();
 
     // Add header row
@@ -29,8 +31,8 @@ pub fn pretty_print_models(models: Vec<ModelMetadata>) {
             )
         };
 
-        // Format prices as dollars
-        let price = get_price(model.price);
+        // Format prices in the display currency
+        let price = get_price(model.price, rate);
         table.add_row(row![model_info, provider_info, price, model.r#type,]);
     }
 
@@ -38,37 +40,51 @@ pub fn pretty_print_models(models: Vec<ModelMetadata>) {
     table.printstd();
 }
 
-fn get_price(price: ModelPrice) -> String {
+fn get_price(price: ModelPrice, rate: &ConvertedCost) -> String {
+    let money = |usd: f64, precision: usize| {
+        let cost = ConvertedCost {
+            amount: usd * rate.amount,
+            currency: rate.currency.clone(),
+        };
+        format!("{cost:.precision$}")
+    };
+
     match price {
         ModelPrice::Completion(completion_model_price) => {
             let mut lines = vec![
-                format!("Input: ${:.4}/1M", completion_model_price.per_input_token),
-                format!("Output: ${:.4}/1M", completion_model_price.per_output_token),
+                format!(
+                    "Input: {}/1M",
+                    money(completion_model_price.per_input_token, 4)
+                ),
+                format!(
+                    "Output: {}/1M",
+                    money(completion_model_price.per_output_token, 4)
+                ),
             ];
 
             if let Some(cached_input) = completion_model_price.per_cached_input_token {
-                lines.push(format!("Cached Input: ${:.4}/1M", cached_input));
+                lines.push(format!("Cached Input: {}/1M", money(cached_input, 4)));
             }
 
             if let Some(cached_write) = completion_model_price.per_cached_input_write_token {
-                lines.push(format!("Cached Write: ${:.4}/1M", cached_write));
+                lines.push(format!("Cached Write: {}/1M", money(cached_write, 4)));
             }
 
             lines.join("\n")
         }
         ModelPrice::Embedding(embedding_model_price) => {
-            format!("${:.4}/1M", embedding_model_price.per_input_token)
+            format!("{}/1M", money(embedding_model_price.per_input_token, 4))
         }
         ModelPrice::ImageGeneration(image_generation_price) => {
             if let Some(p) = image_generation_price.mp_price {
-                format!("${p:.2}/image")
+                format!("{}/image", money(p, 2))
             } else if let Some(map) = image_generation_price.type_prices {
                 let prices: Vec<String> = map
                     .iter()
                     .map(|(size, price_map)| {
                         let prices: Vec<String> = price_map
                             .iter()
-                            .map(|(_quality, &price)| format!("${price:.4}"))
+                            .map(|(_quality, &price)| money(price, 4))
                             .collect();
                         format!("{}: ({})", size, prices.join(", "))
                     })
@@ -6,6 +6,7 @@ use std::sync::Arc;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{layer::SubscriberExt, EnvFilter, Layer, Registry};
+use vllora_core::metadata::anonymization::AnonymizingSpanExporter;
 use vllora_core::metadata::pool::DbPool;
 use vllora_core::telemetry::ProjectTraceSpanExporter;
 use vllora_core::telemetry::RunSpanBuffer;
@@ -35,16 +36,22 @@ pub fn init_tracing(
         .with_filter(env_filter);
 
     // Initialize tracing (spans)
-    let otlp_span_exporter = opentelemetry_otlp::SpanExporter::builder()
-        .with_tonic()
-        .build()
-        .unwrap();
-    let project_trace_span_exporter = ProjectTraceSpanExporter::new(project_trace_senders);
-    let run_span_buffer_exporter = RunSpanBufferExporter::new(run_span_buffer);
+    // User identifiers are pseudonymized before spans reach the live UI, MCP or a collector
+    let otlp_span_exporter = AnonymizingSpanExporter::new(
+        opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .build()
+            .unwrap(),
+    );
+    let project_trace_span_exporter =
+        AnonymizingSpanExporter::new(ProjectTraceSpanExporter::new(project_trace_senders));
+    let run_span_buffer_exporter =
+        AnonymizingSpanExporter::new(RunSpanBufferExporter::new(run_span_buffer));
 
     let trace_provider = SdkTracerProvider::builder()
         .with_span_processor(BaggageSpanProcessor::new([
             "vllora.run_id",
+            "vllora.parent_run_id",
             "vllora.thread_id",
             "vllora.label",
             "vllora.tenant",
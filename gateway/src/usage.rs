@@ -24,6 +24,7 @@ pub const TOTAL_TOKENS: &str = "total_tokens";
 pub const REQUESTS: &str = "requests";
 pub const REQUESTS_DURATION: &str = "requests_duration";
 pub const TTFT: &str = "ttft";
+pub const LATENCY: &str = "latency";
 
 #[allow(clippy::too_many_arguments)]
 pub(crate) async fn update_usage(
@@ -35,7 +36,7 @@ pub(crate) async fn update_usage(
     duration: Option<u64>,
     ttft: Option<u64>,
     price: &ModelPrice,
-) -> Result<(), UsageSetError> {
+) -> Result<Option<f64>, UsageSetError> {
     if let Some(usage) = model_usage {
         let cost = calculator
             .calculate_cost(price, usage, &CredentialsIdent::Own)
@@ -92,13 +93,25 @@ pub(crate) async fn update_usage(
                     }
                 }
 
+                {
+                    let storage = storage.lock().await;
+                    if let Some(duration) = duration {
+                        storage.record_ewma(&identifier, LATENCY, duration as f64);
+                    }
+                    if let Some(ttft) = ttft {
+                        storage.record_ewma(&identifier, TTFT, ttft as f64);
+                    }
+                }
+
                 let metrics = storage.lock().await.get_all_counters().await;
 
                 tracing::debug!(target:"gateway::usage", metrics = %serde_yaml::to_string(&metrics).unwrap());
             }
             Usage::ImageGenerationModelUsage(_) => {}
         }
+
+        return Ok(Some(cost));
     }
 
-    Ok(())
+    Ok(None)
 }
@@ -0,0 +1,220 @@
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::Span;
+use vllora_core::types::guardrails::evaluator::Evaluator;
+use vllora_core::types::guardrails::{Guard, GuardFailureMode, GuardResult};
+use vllora_llm::types::gateway::ChatCompletionMessage;
+
+const DEFAULT_TIMEOUT_MS: u64 = 5000;
+
+/// Payload sent to the external guard service
+#[derive(Debug, Serialize)]
+struct HttpGuardRequest<'a> {
+    guard_id: &'a str,
+    messages: &'a [ChatCompletionMessage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    parameters: Option<&'a Value>,
+}
+
+/// Decision returned by the external guard service
+#[derive(Debug, Deserialize)]
+pub struct HttpGuardResponse {
+    pub passed: bool,
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    #[serde(default)]
+    pub details: Option<Value>,
+}
+
+impl From<HttpGuardResponse> for GuardResult {
+    fn from(response: HttpGuardResponse) -> Self {
+        match response.details {
+            Some(details) => GuardResult::Json {
+                schema: details,
+                passed: response.passed,
+            },
+            None => GuardResult::Boolean {
+                passed: response.passed,
+                confidence: response.confidence,
+            },
+        }
+    }
+}
+
+/// Evaluator that delegates the decision to an external guard service
+pub struct HttpEvaluator {
+    client: reqwest::Client,
+}
+
+impl Default for HttpEvaluator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpEvaluator {
+    pub fn new() -> Self {
+        Self {
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn call(
+        &self,
+        endpoint: &str,
+        timeout: Duration,
+        body: &HttpGuardRequest<'_>,
+    ) -> Result<GuardResult, String> {
+        let response = self
+            .client
+            .post(endpoint)
+            .timeout(timeout)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| format!("External guard request failed: {e}"))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("External guard returned status {status}"));
+        }
+
+        let response: HttpGuardResponse = response
+            .json()
+            .await
+            .map_err(|e| format!("Invalid external guard response: {e}"))?;
+
+        Ok(response.into())
+    }
+}
+
+#[async_trait::async_trait]
+impl Evaluator for HttpEvaluator {
+    async fn evaluate(
+        &self,
+        messages: &[ChatCompletionMessage],
+        guard: &Guard,
+    ) -> Result<GuardResult, String> {
+        let Guard::Http {
+            config,
+            endpoint,
+            timeout_ms,
+            failure_mode,
+        } = guard
+        else {
+            return Err("Invalid guard type for HttpEvaluator".to_string());
+        };
+
+        let span = Span::current();
+        let timeout = Duration::from_millis(timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+        let body = HttpGuardRequest {
+            guard_id: &config.id,
+            messages,
+            parameters: config.user_defined_parameters.as_ref(),
+        };
+
+        let started_at = Instant::now();
+        let result = self.call(endpoint, timeout, &body).await;
+        span.record("latency_ms", started_at.elapsed().as_millis() as u64);
+
+        match result {
+            Ok(result) => {
+                let passed = match &result {
+                    GuardResult::Boolean { passed, .. }
+                    | GuardResult::Text { passed, .. }
+                    | GuardResult::Json { passed, .. } => *passed,
+                };
+                span.record("decision", if passed { "pass" } else { "fail" });
+                Ok(result)
+            }
+            Err(e) => match failure_mode {
+                GuardFailureMode::FailOpen => {
+                    tracing::warn!("External guard {} failed open: {e}", config.id);
+                    span.record("decision", "fail_open");
+                    Ok(GuardResult::Boolean {
+                        passed: true,
+                        confidence: None,
+                    })
+                }
+                GuardFailureMode::FailClosed => {
+                    span.record("decision", "fail_closed");
+                    Err(e)
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use vllora_core::types::guardrails::{GuardAction, GuardConfig, GuardStage};
+
+    fn http_guard(failure_mode: GuardFailureMode) -> Guard {
+        Guard::Http {
+            config: GuardConfig {
+                id: "moderation".to_string(),
+                name: "Moderation".to_string(),
+                template_id: "external-http".to_string(),
+                description: None,
+                stage: GuardStage::Input,
+                action: GuardAction::Validate,
+                user_defined_parameters: None,
+            },
+            // Nothing listens on port 1, so the request fails immediately
+            endpoint: "http://127.0.0.1:1/evaluate".to_string(),
+            timeout_ms: Some(500),
+            failure_mode,
+        }
+    }
+
+    #[test]
+    fn test_response_maps_to_guard_result() {
+        let response: HttpGuardResponse =
+            serde_json::from_str(r#"{"passed": false, "confidence": 0.9}"#).unwrap();
+        assert_eq!(
+            GuardResult::from(response),
+            GuardResult::Boolean {
+                passed: false,
+                confidence: Some(0.9)
+            }
+        );
+
+        let response: HttpGuardResponse =
+            serde_json::from_str(r#"{"passed": false, "details": {"category": "hate"}}"#).unwrap();
+        assert_eq!(
+            GuardResult::from(response),
+            GuardResult::Json {
+                schema: serde_json::json!({"category": "hate"}),
+                passed: false
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_service_respects_failure_mode() {
+        let evaluator = HttpEvaluator::new();
+        let messages = vec![ChatCompletionMessage::new_text(
+            "user".to_string(),
+            "hello".to_string(),
+        )];
+
+        let result = evaluator
+            .evaluate(&messages, &http_guard(GuardFailureMode::FailOpen))
+            .await;
+        assert_eq!(
+            result,
+            Ok(GuardResult::Boolean {
+                passed: true,
+                confidence: None
+            })
+        );
+
+        let result = evaluator
+            .evaluate(&messages, &http_guard(GuardFailureMode::FailClosed))
+            .await;
+        assert!(result.is_err());
+    }
+}
@@ -1,5 +1,6 @@
 pub mod config;
 pub mod dataset;
+pub mod http;
 pub mod llm_judge;
 pub mod partner;
 pub mod partners;
@@ -13,6 +14,7 @@ pub mod tests;
 
 // Re-export evaluators
 pub use dataset::{DatasetEvaluator, FileDatasetLoader};
+pub use http::HttpEvaluator;
 pub use llm_judge::LlmJudgeEvaluator;
 pub use regex::RegexEvaluator;
 pub use schema::SchemaEvaluator;
@@ -61,7 +61,7 @@ impl GuardPartner for OpenaiGuardrailPartner {
                                             .url,
                                     }))
                                 }
-                                ContentType::InputAudio => Err(
+                                ContentType::InputAudio | ContentType::Audio => Err(
                                     GuardPartnerError::InputTypeNotSupported("audio".to_string()),
                                 ),
                                 ContentType::File => Err(GuardPartnerError::InputTypeNotSupported(
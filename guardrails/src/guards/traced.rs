@@ -40,6 +40,8 @@ impl Evaluator for TracedGuard {
             result_metadata = field::Empty,
             r#type = guard.r#type(),
             partner = field::Empty,
+            latency_ms = field::Empty,
+            decision = field::Empty,
             error = field::Empty
         );
 
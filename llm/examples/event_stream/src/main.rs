@@ -0,0 +1,56 @@
+use vllora_llm::async_openai::types::{
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use tokio_stream::StreamExt;
+
+use vllora_llm::client::completions::event_stream::StreamEvent;
+use vllora_llm::client::VlloraLLMClient;
+use vllora_llm::error::LLMResult;
+use vllora_llm::types::gateway::ToolStreamEvent;
+
+#[tokio::main]
+async fn main() -> LLMResult<()> {
+    // 1) Build a streaming request
+    let openai_req = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4.1-mini")
+        .messages([ChatCompletionRequestMessage::User(
+            ChatCompletionRequestUserMessageArgs::default()
+                .content("Explain in three sentences why the sky is blue.")
+                .build()?,
+        )])
+        .build()?;
+
+    // 2) Stream text, tool calls, reasoning and usage as one stream of events
+    let client = VlloraLLMClient::new();
+    let mut stream = client.completions().create_event_stream(openai_req).await?;
+
+    // 3) Handle everything in a single loop
+    while let Some(event) = stream.next().await {
+        match event? {
+            StreamEvent::TextDelta(text) => print!("{text}"),
+            StreamEvent::Reasoning(reasoning) => print!("[thinking] {reasoning}"),
+            StreamEvent::ToolCallDelta(tool_call) => println!(
+                "\n[tool call] {} {}",
+                tool_call.function.name, tool_call.function.arguments
+            ),
+            StreamEvent::Tool(ToolStreamEvent::ToolStart { name, input, .. }) => {
+                println!("\n[tool] {name} {input}")
+            }
+            StreamEvent::Tool(ToolStreamEvent::ToolResult { name, output, .. }) => {
+                println!("[tool result] {name}: {output}")
+            }
+            StreamEvent::Tool(ToolStreamEvent::StepStart) => println!("[continuing]"),
+            StreamEvent::Usage(usage) => println!(
+                "\n\nUsage: {} prompt + {} completion tokens",
+                usage.prompt_tokens, usage.completion_tokens
+            ),
+            StreamEvent::Done { finish_reason } => {
+                println!("Finished: {}", finish_reason.unwrap_or_default())
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use vllora_llm::async_openai::types::{
+    ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessageArgs,
+    CreateChatCompletionRequestArgs,
+};
+use tokio_stream::StreamExt;
+
+use vllora_llm::client::completions::cancellation::CancellationToken;
+use vllora_llm::client::VlloraLLMClient;
+use vllora_llm::error::LLMResult;
+
+/// Number of streamed chunks to print before cancelling
+const MAX_CHUNKS: usize = 20;
+
+#[tokio::main]
+async fn main() -> LLMResult<()> {
+    // 1) Build a request that produces a long answer
+    let openai_req = CreateChatCompletionRequestArgs::default()
+        .model("gpt-4.1-mini")
+        .messages([ChatCompletionRequestMessage::User(
+            ChatCompletionRequestUserMessageArgs::default()
+                .content("Write a 2000 word essay about the history of the printing press.")
+                .build()?,
+        )])
+        .build()?;
+
+    // 2) Create a streaming client that ends its streams when the token is cancelled
+    let token = CancellationToken::new();
+    let client = VlloraLLMClient::new();
+    let mut stream = client
+        .completions()
+        .with_cancellation_token(token.clone())
+        .create_stream(openai_req)
+        .await?;
+
+    // 3) Print chunks and cancel after MAX_CHUNKS. Cancelling from another task
+    //    (e.g. a shutdown signal in a select loop) works the same way.
+    let mut received = 0;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        for choice in chunk.choices {
+            if let Some(delta) = choice.delta.content {
+                print!("{delta}");
+            }
+        }
+
+        received += 1;
+        if received == MAX_CHUNKS {
+            token.cancel();
+        }
+    }
+
+    println!("\n\nStream cancelled after {received} chunks");
+
+    Ok(())
+}
@@ -0,0 +1,222 @@
+use std::time::Duration;
+
+use futures::StreamExt;
+
+use crate::client::completions::response_stream::ResultStream;
+use crate::error::LLMResult;
+use crate::types::gateway::{
+    ChatCompletionChunk, ChatCompletionContent, ChatCompletionMessage,
+    ChatCompletionMessageWithFinishReason, GatewayModelUsage, ToolCall,
+};
+use crate::types::ModelFinishReason;
+
+/// Builds the message a stream adds up to from its chunks. Only the first choice is kept.
+#[derive(Debug, Default)]
+pub struct StreamAssembler {
+    id: String,
+    created: u32,
+    model: String,
+    content: String,
+    tool_calls: Vec<ToolCall>,
+    finish_reason: Option<ModelFinishReason>,
+    usage: Option<GatewayModelUsage>,
+}
+
+impl StreamAssembler {
+    pub fn push(&mut self, chunk: &ChatCompletionChunk) {
+        if self.id.is_empty() {
+            self.id = chunk.id.clone();
+            self.created = chunk.created as u32;
+            self.model = chunk.model.clone();
+        }
+
+        if let Some(usage) = &chunk.usage {
+            self.usage = Some(GatewayModelUsage {
+                input_tokens: usage.prompt_tokens as u32,
+                output_tokens: usage.completion_tokens as u32,
+                total_tokens: usage.total_tokens as u32,
+                prompt_tokens_details: usage.prompt_tokens_details.clone(),
+                completion_tokens_details: usage.completion_tokens_details.clone(),
+                is_cache_used: false,
+            });
+        }
+
+        let Some(choice) = chunk.choices.iter().find(|choice| choice.index == 0) else {
+            return;
+        };
+        if let Some(content) = &choice.delta.content {
+            self.content.push_str(content);
+        }
+        for tool_call in choice.delta.tool_calls.iter().flatten() {
+            self.push_tool_call(tool_call);
+        }
+        if let Some(finish_reason) = &choice.finish_reason {
+            self.finish_reason = Some(finish_reason.as_str().into());
+        }
+    }
+
+    /// Tool call deltas with the index of a call already seen continue its arguments
+    fn push_tool_call(&mut self, delta: &ToolCall) {
+        let existing = delta.index.and_then(|index| {
+            self.tool_calls
+                .iter_mut()
+                .find(|tool_call| tool_call.index == Some(index))
+        });
+        match existing {
+            Some(tool_call) => {
+                if tool_call.id.is_empty() {
+                    tool_call.id = delta.id.clone();
+                }
+                if tool_call.function.name.is_empty() {
+                    tool_call.function.name = delta.function.name.clone();
+                }
+                tool_call
+                    .function
+                    .arguments
+                    .push_str(&delta.function.arguments);
+                if delta.extra_content.is_some() {
+                    tool_call.extra_content = delta.extra_content.clone();
+                }
+            }
+            None => self.tool_calls.push(delta.clone()),
+        }
+    }
+
+    /// Message assembled so far. Without a finish reason, e.g. when the stream was cut
+    /// short, it is reported as `incomplete`.
+    pub fn finish(self) -> ChatCompletionMessageWithFinishReason {
+        let message = ChatCompletionMessage {
+            role: "assistant".to_string(),
+            content: (!self.content.is_empty())
+                .then_some(ChatCompletionContent::Text(self.content)),
+            tool_calls: (!self.tool_calls.is_empty()).then_some(self.tool_calls),
+            ..Default::default()
+        };
+
+        ChatCompletionMessageWithFinishReason::new(
+            message,
+            self.finish_reason.unwrap_or(ModelFinishReason::Incomplete),
+            self.id,
+            self.created,
+            self.model,
+            self.usage,
+        )
+    }
+}
+
+/// Reads `stream` to its end and assembles its chunks. When `timeout` runs out first, the
+/// stream is dropped, which stops the model call, and the content received so far is
+/// returned as `incomplete`.
+pub async fn assemble_stream(
+    mut stream: ResultStream,
+    timeout: Option<Duration>,
+) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+    let deadline = timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+    let mut assembler = StreamAssembler::default();
+
+    loop {
+        let next = match deadline {
+            Some(deadline) => match tokio::time::timeout_at(deadline, stream.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    assembler.finish_reason = None;
+                    break;
+                }
+            },
+            None => stream.next().await,
+        };
+        match next {
+            Some(chunk) => assembler.push(&chunk?),
+            None => break,
+        }
+    }
+
+    Ok(assembler.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LLMError;
+    use crate::types::gateway::{ChatCompletionChunkChoice, ChatCompletionDelta, FunctionCall};
+    use futures::stream;
+
+    fn chunk(delta: ChatCompletionDelta, finish_reason: Option<&str>) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: "chatcmpl-1".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: "gpt-4o-mini".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta,
+                finish_reason: finish_reason.map(ToString::to_string),
+                logprobs: None,
+            }],
+            usage: None,
+            tool_event: None,
+        }
+    }
+
+    fn tool_call_delta(id: &str, name: &str, arguments: &str) -> ChatCompletionDelta {
+        ChatCompletionDelta {
+            tool_calls: Some(vec![ToolCall {
+                index: Some(0),
+                id: id.to_string(),
+                r#type: "function".to_string(),
+                function: FunctionCall {
+                    name: name.to_string(),
+                    arguments: arguments.to_string(),
+                },
+                extra_content: None,
+            }]),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_arguments_are_joined() {
+        let chunks: Vec<Result<ChatCompletionChunk, LLMError>> = vec![
+            Ok(chunk(tool_call_delta("call_1", "get_weather", ""), None)),
+            Ok(chunk(tool_call_delta("", "", "{\"city\":"), None)),
+            Ok(chunk(
+                tool_call_delta("", "", "\"Paris\"}"),
+                Some("tool_calls"),
+            )),
+        ];
+
+        let result = assemble_stream(ResultStream::new(Box::pin(stream::iter(chunks))), None)
+            .await
+            .unwrap();
+
+        assert_eq!(result.finish_reason(), &ModelFinishReason::ToolCalls);
+        let tool_calls = result.message().tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].id, "call_1");
+        assert_eq!(tool_calls[0].function.name, "get_weather");
+        assert_eq!(tool_calls[0].function.arguments, "{\"city\":\"Paris\"}");
+        assert!(result.message().content.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_returns_partial_content() {
+        let first = chunk(
+            ChatCompletionDelta::from_assistant_text("Once upon".to_string()),
+            None,
+        );
+        let stalled = stream::iter(vec![Ok(first)]).chain(stream::pending());
+
+        let result = assemble_stream(
+            ResultStream::new(Box::pin(stalled)),
+            Some(Duration::from_millis(50)),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.finish_reason(), &ModelFinishReason::Incomplete);
+        assert_eq!(
+            result.message().content,
+            Some(ChatCompletionContent::Text("Once upon".to_string()))
+        );
+    }
+}
@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::engine::CompletionEngineParams;
+use crate::types::gateway::{ChatCompletionMessageWithFinishReason, ChatCompletionRequest};
+use crate::types::message::Message;
+
+/// A request submitted as part of a batch job
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    /// Identifies the request's result in the job results
+    pub custom_id: String,
+    pub request: ChatCompletionRequest,
+}
+
+impl BatchRequest {
+    pub fn new(custom_id: impl Into<String>, request: impl Into<ChatCompletionRequest>) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            request: request.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchProvider {
+    Anthropic,
+    Gemini,
+}
+
+/// Handle of a submitted batch job, used to poll it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BatchJob {
+    /// Provider assigned id of the job
+    pub id: String,
+    pub provider: BatchProvider,
+    /// Model of the requests in the job
+    pub model: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchJobState {
+    Validating,
+    InProgress,
+    Completed,
+    Failed,
+    Cancelled,
+    Expired,
+}
+
+impl BatchJobState {
+    /// Whether the job stopped processing, its results don't change anymore
+    pub fn is_terminal(&self) -> bool {
+        !matches!(self, BatchJobState::Validating | BatchJobState::InProgress)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    Succeeded {
+        response: ChatCompletionMessageWithFinishReason,
+    },
+    Errored {
+        message: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub outcome: BatchOutcome,
+    /// Cost of the request at batch prices, when the client has pricing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl BatchResult {
+    pub fn succeeded(custom_id: String, response: ChatCompletionMessageWithFinishReason) -> Self {
+        Self {
+            custom_id,
+            outcome: BatchOutcome::Succeeded { response },
+            cost: None,
+        }
+    }
+
+    pub fn errored(custom_id: String, message: impl Into<String>) -> Self {
+        Self {
+            custom_id,
+            outcome: BatchOutcome::Errored {
+                message: message.into(),
+            },
+            cost: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct BatchJobStatus {
+    pub job: BatchJob,
+    pub state: BatchJobState,
+    /// Results of the job, only available once it completed
+    pub results: Vec<BatchResult>,
+}
+
+/// A batch request mapped to the engine and messages of its provider
+pub struct PreparedBatchRequest {
+    pub custom_id: String,
+    pub engine: CompletionEngineParams,
+    pub messages: Vec<Message>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_finished_states_are_terminal() {
+        assert!(!BatchJobState::Validating.is_terminal());
+        assert!(!BatchJobState::InProgress.is_terminal());
+        assert!(BatchJobState::Completed.is_terminal());
+        assert!(BatchJobState::Failed.is_terminal());
+        assert!(BatchJobState::Expired.is_terminal());
+    }
+
+    #[test]
+    fn test_job_handle_round_trips() {
+        let job = BatchJob {
+            id: "msgbatch_013Zva2CMHLNnXjNJJKqJ2EF".to_string(),
+            provider: BatchProvider::Anthropic,
+            model: "anthropic/claude-3-5-haiku-20241022".to_string(),
+        };
+
+        let json = serde_json::to_value(&job).unwrap();
+        assert_eq!(json["provider"], "anthropic");
+        assert_eq!(serde_json::from_value::<BatchJob>(json).unwrap(), job);
+    }
+}
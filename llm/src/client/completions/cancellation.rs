@@ -0,0 +1,171 @@
+use futures::{stream, StreamExt};
+use tokio::sync::mpsc;
+use tracing::Span;
+
+pub use tokio_util::sync::CancellationToken;
+
+use crate::client::completions::interim_usage::estimate_tokens;
+use crate::client::completions::response_stream::ResultStream;
+use crate::types::{LLMInterimUsageEvent, ModelEvent, ModelEventType};
+
+struct CancellableState {
+    inner: Option<ResultStream>,
+    token: CancellationToken,
+    tx: Option<mpsc::Sender<Option<ModelEvent>>>,
+    span: Span,
+    output: String,
+    completion_tokens: Option<u32>,
+}
+
+impl CancellableState {
+    /// Drops the provider stream and reports the usage accumulated so far
+    async fn cancel(&mut self) {
+        self.inner = None;
+        self.span.record("cancelled", true);
+
+        let usage = match self.completion_tokens {
+            Some(output_tokens) => LLMInterimUsageEvent {
+                output_tokens,
+                estimated: false,
+            },
+            None => LLMInterimUsageEvent {
+                output_tokens: estimate_tokens(&self.output),
+                estimated: true,
+            },
+        };
+        if let Some(tx) = &self.tx {
+            let _ = tx
+                .send(Some(ModelEvent::new(
+                    &self.span,
+                    ModelEventType::LlmInterimUsage(usage),
+                )))
+                .await;
+        }
+    }
+}
+
+/// Ends `stream` as soon as `token` is cancelled.
+///
+/// The provider stream is dropped on cancellation, which stops the underlying model
+/// call. A final `LlmInterimUsage` event with the output produced so far is sent on
+/// `tx`.
+pub fn cancellable(
+    stream: ResultStream,
+    token: CancellationToken,
+    tx: Option<mpsc::Sender<Option<ModelEvent>>>,
+    span: Span,
+) -> ResultStream {
+    let state = CancellableState {
+        inner: Some(stream),
+        token,
+        tx,
+        span,
+        output: String::new(),
+        completion_tokens: None,
+    };
+
+    ResultStream::new(Box::pin(stream::unfold(state, |mut state| async move {
+        let inner = state.inner.as_mut()?;
+        let chunk = tokio::select! {
+            biased;
+            _ = state.token.cancelled() => None,
+            chunk = inner.next() => Some(chunk),
+        };
+
+        match chunk {
+            Some(Some(chunk)) => {
+                if let Ok(chunk) = &chunk {
+                    for choice in &chunk.choices {
+                        if let Some(content) = &choice.delta.content {
+                            state.output.push_str(content);
+                        }
+                    }
+                    if let Some(usage) = &chunk.usage {
+                        state.completion_tokens = Some(usage.completion_tokens as u32);
+                    }
+                }
+                Some((chunk, state))
+            }
+            Some(None) => None,
+            None => {
+                state.cancel().await;
+                None
+            }
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::gateway::{
+        ChatCompletionChunk, ChatCompletionChunkChoice, ChatCompletionDelta,
+    };
+
+    fn chunk(content: &str) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: "test".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: "test".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta {
+                    content: Some(content.to_string()),
+                    role: Some("assistant".to_string()),
+                    tool_calls: None,
+                    reasoning_content: None,
+                },
+                finish_reason: None,
+                logprobs: None,
+            }],
+            usage: None,
+            tool_event: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_stops_provider_and_flushes_usage() {
+        let (tx_response, rx_response) = mpsc::channel(1);
+        let producer = tokio::spawn(async move {
+            let mut sent = 0;
+            while tx_response.send(Ok(chunk("abcd"))).await.is_ok() {
+                sent += 1;
+            }
+            sent
+        });
+
+        let (tx, mut rx) = mpsc::channel(10);
+        let token = CancellationToken::new();
+        let mut stream = cancellable(
+            ResultStream::create(rx_response),
+            token.clone(),
+            Some(tx),
+            Span::none(),
+        );
+
+        for _ in 0..3 {
+            assert!(stream.next().await.unwrap().is_ok());
+        }
+        token.cancel();
+        assert!(stream.next().await.is_none());
+
+        // The provider sees a closed channel and stops producing
+        let sent = producer.await.unwrap();
+        assert!(sent < 10);
+
+        let event = rx.recv().await.unwrap().unwrap();
+        match event.event {
+            ModelEventType::LlmInterimUsage(usage) => {
+                assert_eq!(
+                    usage,
+                    LLMInterimUsageEvent {
+                        output_tokens: 3,
+                        estimated: true
+                    }
+                );
+            }
+            _ => panic!("Expected interim usage event"),
+        }
+    }
+}
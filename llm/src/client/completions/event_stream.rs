@@ -0,0 +1,626 @@
+use std::collections::VecDeque;
+use std::pin::Pin;
+
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::mpsc;
+
+use crate::client::completions::response_stream::ResultStream;
+use crate::error::{LLMError, LLMResult};
+use crate::types::gateway::{ChatCompletionChunk, ChatCompletionUsage, ToolCall, ToolStreamEvent};
+use crate::types::{ModelEvent, ModelEventType};
+
+/// Everything a streamed completion produces, in the order it was produced
+#[derive(Debug, Clone)]
+pub enum StreamEvent {
+    TextDelta(String),
+    ToolCallDelta(ToolCall),
+    Reasoning(String),
+    Usage(ChatCompletionUsage),
+    /// A tool the gateway runs on behalf of the model was called or returned, or the model
+    /// continues with the results
+    Tool(ToolStreamEvent),
+    /// Last item of the stream, with the finish reason reported by the model
+    Done {
+        finish_reason: Option<String>,
+    },
+}
+
+/// Stream of [`StreamEvent`]s, see [`with_events`]
+pub struct EventStream {
+    inner: Pin<Box<dyn Stream<Item = LLMResult<StreamEvent>> + Send + 'static>>,
+}
+
+impl Stream for EventStream {
+    type Item = LLMResult<StreamEvent>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        Pin::new(&mut self.inner).poll_next(cx)
+    }
+}
+
+/// Item of a chunk stream merged with its event channel
+enum Merged {
+    Chunk(ChatCompletionChunk),
+    Reasoning(String),
+    Tool(ToolStreamEvent),
+}
+
+/// Merges a chunk stream with its event channel, keeping the order they were produced in
+struct MergeState {
+    chunks: ResultStream,
+    events: Option<mpsc::Receiver<Option<ModelEvent>>>,
+    forward: Option<mpsc::Sender<Option<ModelEvent>>>,
+    pending: VecDeque<LLMResult<Merged>>,
+    /// Set once a tool result was merged, until the model is called again
+    awaiting_step: bool,
+}
+
+impl MergeState {
+    fn new(
+        chunks: ResultStream,
+        events: Option<mpsc::Receiver<Option<ModelEvent>>>,
+        forward: Option<mpsc::Sender<Option<ModelEvent>>>,
+    ) -> Self {
+        Self {
+            chunks,
+            events,
+            forward,
+            pending: VecDeque::new(),
+            awaiting_step: false,
+        }
+    }
+
+    async fn push_event(&mut self, event: ModelEvent) {
+        let merged = match &event.event {
+            ModelEventType::LlmReasoning(reasoning) if !reasoning.content.is_empty() => {
+                Some(Merged::Reasoning(reasoning.content.clone()))
+            }
+            ModelEventType::ToolStart(tool) => Some(Merged::Tool(ToolStreamEvent::ToolStart {
+                id: tool.tool_id.clone(),
+                name: tool.tool_name.clone(),
+                input: tool.input.clone(),
+            })),
+            ModelEventType::ToolResult(tool) => {
+                self.awaiting_step = true;
+                Some(Merged::Tool(ToolStreamEvent::ToolResult {
+                    id: tool.tool_id.clone(),
+                    name: tool.tool_name.clone(),
+                    output: tool.output.clone(),
+                    is_error: tool.is_error,
+                }))
+            }
+            ModelEventType::LlmStart(_) if self.awaiting_step => {
+                self.awaiting_step = false;
+                Some(Merged::Tool(ToolStreamEvent::StepStart))
+            }
+            _ => None,
+        };
+        self.pending.extend(merged.map(Ok));
+        if let Some(forward) = &self.forward {
+            let _ = forward.send(Some(event)).await;
+        }
+    }
+
+    /// Handles the events that are already queued, without waiting for new ones
+    async fn drain_events(&mut self) {
+        let mut received = vec![];
+        if let Some(events) = &mut self.events {
+            while let Ok(event) = events.try_recv() {
+                received.extend(event);
+            }
+        }
+        for event in received {
+            self.push_event(event).await;
+        }
+    }
+
+    /// Waits for the next chunk or event, returning `false` once the chunk stream ended
+    async fn fill(&mut self) -> bool {
+        let next = match &mut self.events {
+            Some(events) => tokio::select! {
+                biased;
+                event = events.recv() => Next::Event(event),
+                chunk = self.chunks.next() => Next::Chunk(chunk),
+            },
+            None => Next::Chunk(self.chunks.next().await),
+        };
+
+        match next {
+            Next::Event(Some(Some(event))) => self.push_event(event).await,
+            Next::Event(Some(None)) => {}
+            // The event sender was dropped, keep streaming chunks only
+            Next::Event(None) => self.events = None,
+            Next::Chunk(chunk) => {
+                // Providers emit events before the chunk they belong to
+                self.drain_events().await;
+                match chunk {
+                    Some(chunk) => self.pending.push_back(chunk.map(Merged::Chunk)),
+                    None => return false,
+                }
+            }
+        }
+        true
+    }
+
+    /// Next merged item, `None` once the chunk stream ended
+    async fn next(&mut self) -> Option<LLMResult<Merged>> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            if !self.fill().await {
+                return None;
+            }
+        }
+    }
+}
+
+impl Drop for MergeState {
+    /// Keeps forwarding the events sent once nothing consumes the merged stream anymore,
+    /// such as the end of the model call
+    fn drop(&mut self) {
+        if let (Some(events), Some(forward), Ok(runtime)) = (
+            self.events.take(),
+            self.forward.take(),
+            tokio::runtime::Handle::try_current(),
+        ) {
+            runtime.spawn(forward_events(events, forward));
+        }
+    }
+}
+
+/// Passes every event of `events` on to `forward`, until either side is closed
+pub async fn forward_events(
+    mut events: mpsc::Receiver<Option<ModelEvent>>,
+    forward: mpsc::Sender<Option<ModelEvent>>,
+) {
+    while let Some(event) = events.recv().await {
+        if event.is_some() && forward.send(event).await.is_err() {
+            break;
+        }
+    }
+}
+
+enum Next {
+    Event(Option<Option<ModelEvent>>),
+    Chunk(Option<Result<ChatCompletionChunk, LLMError>>),
+}
+
+struct EventState {
+    merged: MergeState,
+    pending: VecDeque<LLMResult<StreamEvent>>,
+    finish_reason: Option<String>,
+}
+
+impl EventState {
+    fn push_chunk(&mut self, chunk: ChatCompletionChunk) {
+        for choice in chunk.choices {
+            // Reasoning is taken from the event channel when there is one, as some
+            // providers only report it there
+            if self.merged.events.is_none() {
+                if let Some(reasoning) = choice.delta.reasoning_content.filter(|r| !r.is_empty()) {
+                    self.pending
+                        .push_back(Ok(StreamEvent::Reasoning(reasoning)));
+                }
+            }
+            if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                self.pending.push_back(Ok(StreamEvent::TextDelta(content)));
+            }
+            for tool_call in choice.delta.tool_calls.unwrap_or_default() {
+                self.pending
+                    .push_back(Ok(StreamEvent::ToolCallDelta(tool_call)));
+            }
+            if choice.finish_reason.is_some() {
+                self.finish_reason = choice.finish_reason;
+            }
+        }
+        if let Some(usage) = chunk.usage {
+            self.pending.push_back(Ok(StreamEvent::Usage(usage)));
+        }
+    }
+}
+
+/// Merges a completion stream and its event channel into a single stream of
+/// [`StreamEvent`]s ending with [`StreamEvent::Done`], so one loop can handle text, tool
+/// calls, reasoning and usage alike. When the model calls tools the gateway runs, their
+/// start and result events are interleaved with the output of every model call of the
+/// loop, each continued generation starting with [`ToolStreamEvent::StepStart`].
+///
+/// `events` is the receiver of the sender the stream was created with. Events are passed
+/// on to `forward` when given, so other consumers of the event channel keep working.
+pub fn with_events(
+    chunks: ResultStream,
+    events: Option<mpsc::Receiver<Option<ModelEvent>>>,
+    forward: Option<mpsc::Sender<Option<ModelEvent>>>,
+) -> EventStream {
+    let state = EventState {
+        merged: MergeState::new(chunks, events, forward),
+        pending: VecDeque::new(),
+        finish_reason: None,
+    };
+
+    EventStream {
+        inner: Box::pin(stream::unfold(Some(state), |state| async move {
+            let mut state = state?;
+            loop {
+                if let Some(item) = state.pending.pop_front() {
+                    return Some((item, Some(state)));
+                }
+                match state.merged.next().await {
+                    Some(Ok(Merged::Chunk(chunk))) => state.push_chunk(chunk),
+                    Some(Ok(Merged::Reasoning(reasoning))) => state
+                        .pending
+                        .push_back(Ok(StreamEvent::Reasoning(reasoning))),
+                    Some(Ok(Merged::Tool(event))) => {
+                        state.pending.push_back(Ok(StreamEvent::Tool(event)))
+                    }
+                    Some(Err(e)) => state.pending.push_back(Err(e)),
+                    None => {
+                        let done = StreamEvent::Done {
+                            finish_reason: state.finish_reason.take(),
+                        };
+                        return Some((Ok(done), None));
+                    }
+                }
+            }
+        })),
+    }
+}
+
+/// Interleaves the tool calls the gateway runs while streaming with the chunks of every model
+/// call of the loop, as chunks without choices carrying a [`ToolStreamEvent`]. Other chunks
+/// are passed through unchanged.
+///
+/// `events` is the receiver of the sender the stream was created with, all its events are
+/// passed on to `forward`, including the ones sent after the last chunk.
+pub fn with_tool_events(
+    chunks: ResultStream,
+    events: mpsc::Receiver<Option<ModelEvent>>,
+    forward: mpsc::Sender<Option<ModelEvent>>,
+) -> ResultStream {
+    let merged = MergeState::new(chunks, Some(events), Some(forward));
+
+    ResultStream::new(Box::pin(stream::unfold(
+        (merged, None::<ChatCompletionChunk>),
+        |(mut merged, mut last)| async move {
+            loop {
+                let item = match merged.next().await? {
+                    Ok(Merged::Chunk(chunk)) => {
+                        last = Some(chunk.clone());
+                        Ok(chunk)
+                    }
+                    Ok(Merged::Tool(event)) => Ok(ChatCompletionChunk {
+                        choices: vec![],
+                        usage: None,
+                        tool_event: Some(event),
+                        ..last.clone().unwrap_or_else(|| ChatCompletionChunk {
+                            id: String::new(),
+                            object: "chat.completion.chunk".to_string(),
+                            created: chrono::Utc::now().timestamp(),
+                            model: String::new(),
+                            choices: vec![],
+                            usage: None,
+                            tool_event: None,
+                        })
+                    }),
+                    // Reasoning already reaches clients in the chunks
+                    Ok(Merged::Reasoning(_)) => continue,
+                    Err(e) => Err(e),
+                };
+                return Some((item, (merged, last)));
+            }
+        },
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::openai::completions::OpenAIModel;
+    use crate::provider::tests::MockStreamServer;
+    use crate::types::credentials::ApiKeyCredentials;
+    use crate::types::engine::{ExecutionOptions, OpenAiModelParams};
+    use crate::types::gateway::{
+        ChatCompletionChunkChoice, ChatCompletionDelta, FunctionCall, FunctionParameters,
+    };
+    use crate::types::instance::ModelInstance;
+    use crate::types::tools::Tool;
+    use crate::types::LLMReasoningEvent;
+    use serde_json::Value;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn chunk(delta: ChatCompletionDelta, finish_reason: Option<&str>) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: "chunk".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: "model".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta,
+                finish_reason: finish_reason.map(str::to_string),
+                logprobs: None,
+            }],
+            usage: None,
+            tool_event: None,
+        }
+    }
+
+    fn text(content: &str) -> ChatCompletionDelta {
+        ChatCompletionDelta {
+            content: Some(content.to_string()),
+            ..Default::default()
+        }
+    }
+
+    fn tool_call() -> ToolCall {
+        ToolCall {
+            index: Some(0),
+            id: "call_1".to_string(),
+            r#type: "function".to_string(),
+            function: FunctionCall {
+                name: "get_weather".to_string(),
+                arguments: "{\"city\":\"Paris\"}".to_string(),
+            },
+            extra_content: None,
+        }
+    }
+
+    fn usage() -> ChatCompletionUsage {
+        ChatCompletionUsage {
+            prompt_tokens: 5,
+            completion_tokens: 3,
+            total_tokens: 8,
+            ..Default::default()
+        }
+    }
+
+    fn describe(event: LLMResult<StreamEvent>) -> String {
+        match event.unwrap() {
+            StreamEvent::TextDelta(text) => format!("text:{text}"),
+            StreamEvent::ToolCallDelta(tool_call) => format!("tool_call:{}", tool_call.id),
+            StreamEvent::Reasoning(reasoning) => format!("reasoning:{reasoning}"),
+            StreamEvent::Usage(usage) => format!("usage:{}", usage.total_tokens),
+            StreamEvent::Tool(ToolStreamEvent::ToolStart { id, name, .. }) => {
+                format!("tool_start:{id}:{name}")
+            }
+            StreamEvent::Tool(ToolStreamEvent::ToolResult { id, output, .. }) => {
+                format!("tool_result:{id}:{output}")
+            }
+            StreamEvent::Tool(ToolStreamEvent::StepStart) => "step_start".to_string(),
+            StreamEvent::Done { finish_reason } => format!("done:{}", finish_reason.unwrap()),
+        }
+    }
+
+    fn reasoning_event(content: &str) -> Option<ModelEvent> {
+        Some(ModelEvent::new(
+            &tracing::Span::none(),
+            ModelEventType::LlmReasoning(LLMReasoningEvent {
+                content: content.to_string(),
+            }),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_events_are_yielded_in_order() {
+        let (chunk_tx, chunk_rx) = mpsc::channel(10);
+        let (event_tx, event_rx) = mpsc::channel(10);
+
+        // Reasoning is reported on the event channel before the content it precedes
+        event_tx.send(reasoning_event("Thinking")).await.unwrap();
+        chunk_tx.send(Ok(chunk(text("Hello"), None))).await.unwrap();
+        chunk_tx
+            .send(Ok(chunk(
+                ChatCompletionDelta {
+                    tool_calls: Some(vec![tool_call()]),
+                    ..Default::default()
+                },
+                None,
+            )))
+            .await
+            .unwrap();
+        let mut finish = chunk(ChatCompletionDelta::default(), Some("tool_calls"));
+        finish.usage = Some(usage());
+        chunk_tx.send(Ok(finish)).await.unwrap();
+        drop(chunk_tx);
+
+        let events: Vec<_> = with_events(ResultStream::create(chunk_rx), Some(event_rx), None)
+            .map(describe)
+            .collect()
+            .await;
+        drop(event_tx);
+
+        assert_eq!(
+            events,
+            vec![
+                "reasoning:Thinking",
+                "text:Hello",
+                "tool_call:call_1",
+                "usage:8",
+                "done:tool_calls",
+            ]
+        );
+    }
+
+    /// Tool the model calls in [`tool_loop`]
+    struct WeatherTool;
+
+    #[async_trait::async_trait]
+    impl Tool for WeatherTool {
+        fn name(&self) -> String {
+            "get_weather".to_string()
+        }
+
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn get_function_parameters(&self) -> Option<FunctionParameters> {
+            None
+        }
+
+        async fn run(
+            &self,
+            _input: HashMap<String, Value>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<Value> {
+            Ok(serde_json::json!({"forecast": "sunny"}))
+        }
+    }
+
+    /// Streams a request to an OpenAI model that has the gateway run `get_weather` before
+    /// answering, with the receiver of its events
+    async fn tool_loop(
+        server: &MockStreamServer,
+    ) -> (ResultStream, mpsc::Receiver<Option<ModelEvent>>) {
+        server.queue_events(vec![
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{"role":"assistant","tool_calls":[{"index":0,"id":"call_1","type":"function","function":{"name":"get_weather","arguments":"{\"city\":\"Paris\"}"}}]},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"chatcmpl-1","object":"chat.completion.chunk","created":1,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{},"finish_reason":"tool_calls"}]}"#.to_string(),
+        ]).await;
+        server.queue_events(vec![
+            r#"{"id":"chatcmpl-2","object":"chat.completion.chunk","created":2,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{"content":"It is sunny"},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"chatcmpl-2","object":"chat.completion.chunk","created":2,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#.to_string(),
+        ]).await;
+
+        let tools = HashMap::from([(
+            "get_weather".to_string(),
+            Arc::new(Box::new(WeatherTool) as Box<dyn Tool>),
+        )]);
+        let model = OpenAIModel::new(
+            OpenAiModelParams {
+                model: Some("gpt-4o-mini".to_string()),
+                ..Default::default()
+            },
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            tools,
+            None,
+            Some(&server.url()),
+        )
+        .unwrap();
+        let (tx, rx) = mpsc::channel(100);
+        let stream = model
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .unwrap();
+        (stream, rx)
+    }
+
+    #[tokio::test]
+    async fn test_tool_events_are_interleaved_with_content() {
+        let server = MockStreamServer::start().await.unwrap();
+        let (stream, events) = tool_loop(&server).await;
+
+        let events: Vec<_> = with_events(stream, Some(events), None)
+            .map(describe)
+            .collect()
+            .await;
+
+        assert_eq!(
+            events,
+            vec![
+                "tool_call:call_1",
+                "tool_start:call_1:get_weather",
+                r#"tool_result:call_1:{"forecast":"sunny"}"#,
+                "step_start",
+                "text:It is sunny",
+                "done:stop",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_events_are_streamed_as_chunks() {
+        let server = MockStreamServer::start().await.unwrap();
+        let (stream, events) = tool_loop(&server).await;
+        let (forward, mut forwarded) = mpsc::channel(100);
+
+        let chunks: Vec<Value> = with_tool_events(stream, events, forward)
+            .map(|chunk| serde_json::to_value(chunk.unwrap()).unwrap())
+            .collect()
+            .await;
+        let text = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        let described: Vec<String> = chunks
+            .iter()
+            .filter_map(|chunk| {
+                let tool_event = &chunk["tool_event"];
+                let delta = &chunk["choices"][0]["delta"];
+                let finish_reason = text(&chunk["choices"][0]["finish_reason"]);
+                match tool_event["type"].as_str() {
+                    Some("tool_start") => Some(format!("tool_start:{}", text(&tool_event["name"]))),
+                    Some("tool_result") => {
+                        Some(format!("tool_result:{}", text(&tool_event["output"])))
+                    }
+                    Some(event) => Some(event.to_string()),
+                    None if delta["tool_calls"].is_array() => {
+                        Some(format!("tool_call:{}", text(&delta["tool_calls"][0]["id"])))
+                    }
+                    None if delta["content"].is_string() => {
+                        Some(format!("text:{}", text(&delta["content"])))
+                    }
+                    // The end of the first call races with the start of the tool it called
+                    None if finish_reason == "tool_calls" => None,
+                    None => Some(format!("finish:{finish_reason}")),
+                }
+            })
+            .collect();
+
+        assert_eq!(
+            described,
+            vec![
+                "tool_call:call_1",
+                "tool_start:get_weather",
+                r#"tool_result:{"forecast":"sunny"}"#,
+                "step_start",
+                "text:It is sunny",
+                "finish:stop",
+            ]
+        );
+        let step_start = chunks
+            .iter()
+            .find(|chunk| chunk["tool_event"]["type"] == "step_start")
+            .unwrap();
+        assert_eq!(step_start["choices"], serde_json::json!([]));
+        assert_eq!(step_start["id"], "chatcmpl-1");
+
+        // Every event still reaches the other consumers, the end of both calls included
+        let mut stops = 0;
+        while let Some(Some(event)) = forwarded.recv().await {
+            stops += matches!(event.event, ModelEventType::LlmStop(_)) as usize;
+        }
+        assert_eq!(stops, 2);
+    }
+
+    #[tokio::test]
+    async fn test_reasoning_comes_from_chunks_without_events() {
+        let (chunk_tx, chunk_rx) = mpsc::channel(10);
+        chunk_tx
+            .send(Ok(chunk(
+                ChatCompletionDelta {
+                    reasoning_content: Some("Thinking".to_string()),
+                    ..Default::default()
+                },
+                None,
+            )))
+            .await
+            .unwrap();
+        chunk_tx
+            .send(Ok(chunk(text("Hi"), Some("stop"))))
+            .await
+            .unwrap();
+        drop(chunk_tx);
+
+        let events: Vec<_> = with_events(ResultStream::create(chunk_rx), None, None)
+            .map(describe)
+            .collect()
+            .await;
+
+        assert_eq!(events, vec!["reasoning:Thinking", "text:Hi", "done:stop"]);
+    }
+}
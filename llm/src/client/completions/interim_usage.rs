@@ -0,0 +1,168 @@
+use crate::types::{
+    LLMContentEvent, LLMInterimUsageEvent, LLMReasoningEvent, ModelEvent, ModelEventType,
+};
+
+/// Average number of characters per token used for interim estimates.
+const CHARS_PER_TOKEN: usize = 4;
+
+/// Rough output token estimate used while a response is still streaming.
+pub fn estimate_tokens(text: &str) -> u32 {
+    text.chars().count().div_ceil(CHARS_PER_TOKEN) as u32
+}
+
+/// Interleaves `LlmInterimUsage` events into a model event stream.
+///
+/// Every `every` content or reasoning chunks an estimated output token count is emitted
+/// after the chunk.
+/// On `LlmStop` a final event is emitted ahead of the stop event, carrying the provider
+/// usage when available so consumers can replace their running estimate.
+pub struct InterimUsageTracker {
+    every: u32,
+    chunks: u32,
+    output: String,
+}
+
+impl InterimUsageTracker {
+    pub fn new(every: u32) -> Self {
+        Self {
+            every: every.max(1),
+            chunks: 0,
+            output: String::new(),
+        }
+    }
+
+    /// Returns the events to forward, in order, for an incoming event
+    pub fn process(&mut self, event: ModelEvent) -> Vec<ModelEvent> {
+        match &event.event {
+            // Reasoning tokens are output tokens too
+            ModelEventType::LlmContent(LLMContentEvent { content })
+            | ModelEventType::LlmReasoning(LLMReasoningEvent { content }) => {
+                self.output.push_str(content);
+                self.chunks += 1;
+
+                if self.chunks % self.every == 0 {
+                    let interim = self.usage_event(&event, estimate_tokens(&self.output), true);
+                    vec![event, interim]
+                } else {
+                    vec![event]
+                }
+            }
+            ModelEventType::LlmStop(stop) => {
+                let final_usage = match &stop.usage {
+                    Some(usage) => self.usage_event(&event, usage.output_tokens, false),
+                    None => {
+                        let mut output = self.output.clone();
+                        if let Some(stop_output) = &stop.output {
+                            output.push_str(stop_output);
+                        }
+                        self.usage_event(&event, estimate_tokens(&output), true)
+                    }
+                };
+                vec![final_usage, event]
+            }
+            _ => vec![event],
+        }
+    }
+
+    fn usage_event(&self, source: &ModelEvent, output_tokens: u32, estimated: bool) -> ModelEvent {
+        ModelEvent {
+            event: ModelEventType::LlmInterimUsage(LLMInterimUsageEvent {
+                output_tokens,
+                estimated,
+            }),
+            ..source.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::credentials_ident::CredentialsIdent;
+    use crate::types::gateway::GatewayModelUsage;
+    use crate::types::{LLMFinishEvent, ModelFinishReason};
+    use tracing::Span;
+
+    fn content(text: &str) -> ModelEvent {
+        ModelEvent::new(
+            &Span::none(),
+            ModelEventType::LlmContent(LLMContentEvent {
+                content: text.to_string(),
+            }),
+        )
+    }
+
+    fn stop(usage: Option<GatewayModelUsage>) -> ModelEvent {
+        ModelEvent::new(
+            &Span::none(),
+            ModelEventType::LlmStop(LLMFinishEvent {
+                provider_name: "openai".to_string(),
+                model_name: "gpt-4o-mini".to_string(),
+                output: None,
+                usage,
+                finish_reason: ModelFinishReason::Stop,
+                tool_calls: vec![],
+                credentials_ident: CredentialsIdent::Own,
+                rate_limit: None,
+            }),
+        )
+    }
+
+    fn interim_usages(events: &[ModelEvent]) -> Vec<LLMInterimUsageEvent> {
+        events
+            .iter()
+            .filter_map(|e| match &e.event {
+                ModelEventType::LlmInterimUsage(usage) => Some(usage.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_interim_usage_precedes_final_provider_usage() {
+        let mut tracker = InterimUsageTracker::new(2);
+        let mut events = vec![];
+        for chunk in ["Hello ", "there, ", "how are ", "you doing"] {
+            events.extend(tracker.process(content(chunk)));
+        }
+        events.extend(tracker.process(stop(Some(GatewayModelUsage {
+            input_tokens: 10,
+            output_tokens: 7,
+            total_tokens: 17,
+            ..Default::default()
+        }))));
+
+        let usages = interim_usages(&events);
+        assert_eq!(usages.len(), 3);
+        assert!(usages[..2].iter().all(|u| u.estimated));
+        assert!(usages[0].output_tokens <= usages[1].output_tokens);
+        assert_eq!(
+            usages[2],
+            LLMInterimUsageEvent {
+                output_tokens: 7,
+                estimated: false
+            }
+        );
+
+        let types: Vec<&str> = events.iter().map(|e| e.event.as_str()).collect();
+        assert_eq!(
+            &types[types.len() - 2..],
+            &["llm_interim_usage", "llm_stop"]
+        );
+    }
+
+    #[test]
+    fn test_final_usage_is_estimated_without_provider_usage() {
+        let mut tracker = InterimUsageTracker::new(10);
+        tracker.process(content("12345678"));
+        let events = tracker.process(stop(None));
+
+        assert_eq!(
+            interim_usages(&events),
+            vec![LLMInterimUsageEvent {
+                output_tokens: 2,
+                estimated: true
+            }]
+        );
+    }
+}
@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::client::completions::response_stream::ResultStream;
+use crate::error::{LLMError, LLMResult};
+use crate::types::gateway::{ChatCompletionContent, ChatCompletionMessageWithFinishReason};
+use crate::types::instance::ModelInstance;
+use crate::types::message::{Message, MessageContentType, MessageType};
+use crate::types::ModelEvent;
+
+/// Strips a markdown code fence models often wrap JSON in
+fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    let Some(inner) = text.strip_prefix("```") else {
+        return text;
+    };
+    let inner = inner.strip_suffix("```").unwrap_or(inner);
+    // Drop the language tag, e.g. ```json
+    match inner.split_once('\n') {
+        Some((tag, body)) if !tag.trim().contains(['{', '[']) => body.trim(),
+        _ => inner.trim(),
+    }
+}
+
+/// Parses `text` as JSON and validates it against `schema`. Schemas that cannot be compiled
+/// are not validated, the output only has to be JSON.
+pub fn validate_output(text: &str, schema: Option<&Value>) -> Result<Value, String> {
+    let value = serde_json::from_str::<Value>(strip_code_fence(text))
+        .map_err(|e| format!("Output is not valid JSON: {e}"))?;
+
+    if let Some(schema) = schema {
+        match jsonschema::validator_for(schema) {
+            Ok(validator) => {
+                let errors: Vec<String> = validator
+                    .iter_errors(&value)
+                    .map(|e| match e.instance_path.to_string() {
+                        path if path.is_empty() => e.to_string(),
+                        path => format!("{path}: {e}"),
+                    })
+                    .collect();
+                if !errors.is_empty() {
+                    return Err(format!(
+                        "Output does not match the schema: {}",
+                        errors.join("; ")
+                    ));
+                }
+            }
+            Err(e) => tracing::warn!("Skipping output validation, invalid schema: {e}"),
+        }
+    }
+
+    Ok(value)
+}
+
+/// Wraps a model so its output is JSON matching `schema`. Invalid output is sent back to the
+/// model with the validation error, up to `max_repairs` times, before failing with
+/// [`LLMError::InvalidJsonOutput`]. Meant for models without native structured output, it
+/// works the same for every provider. Repair attempts are recorded as `json_repair_attempts`
+/// on the current span.
+///
+/// Streams are passed through unchanged, as their output is sent before it can be validated.
+pub struct JsonRepairModel {
+    inner: Box<dyn ModelInstance>,
+    schema: Option<Value>,
+    max_repairs: usize,
+}
+
+impl JsonRepairModel {
+    pub fn new(inner: Box<dyn ModelInstance>, schema: Option<Value>, max_repairs: usize) -> Self {
+        Self {
+            inner,
+            schema,
+            max_repairs,
+        }
+    }
+
+    fn repair_messages(previous: &[Message], output: &str, error: &str) -> [Message; 2] {
+        follow_up_messages(
+            previous,
+            output,
+            format!(
+                "Your output was invalid. {error}. Fix it and reply with only the corrected JSON."
+            ),
+        )
+    }
+}
+
+/// The model's `output` followed by a user `instruction` about it, to ask for another
+/// attempt in the same conversation
+pub(crate) fn follow_up_messages(
+    previous: &[Message],
+    output: &str,
+    instruction: String,
+) -> [Message; 2] {
+    let last = previous.last();
+    let message = |r#type: MessageType, content: String| Message {
+        model_name: last.map(|m| m.model_name.clone()).unwrap_or_default(),
+        thread_id: last.and_then(|m| m.thread_id.clone()),
+        user_id: last.map(|m| m.user_id.clone()).unwrap_or_default(),
+        content_type: MessageContentType::Text,
+        content: Some(content),
+        content_array: vec![],
+        r#type,
+        tool_call_id: None,
+        tool_calls: None,
+        created_at: None,
+        name: None,
+    };
+
+    [
+        message(MessageType::AIMessage, output.to_string()),
+        message(MessageType::HumanMessage, instruction),
+    ]
+}
+
+#[async_trait]
+impl ModelInstance for JsonRepairModel {
+    async fn invoke(
+        &self,
+        input_vars: HashMap<String, Value>,
+        tx: mpsc::Sender<Option<ModelEvent>>,
+        mut previous_messages: Vec<Message>,
+        tags: HashMap<String, String>,
+    ) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+        let mut attempts = 0;
+        loop {
+            let mut response = self
+                .inner
+                .invoke(
+                    input_vars.clone(),
+                    tx.clone(),
+                    previous_messages.clone(),
+                    tags.clone(),
+                )
+                .await?;
+
+            let output = response
+                .message()
+                .content
+                .as_ref()
+                .and_then(ChatCompletionContent::as_string)
+                .unwrap_or_default();
+            let error = match validate_output(&output, self.schema.as_ref()) {
+                Ok(value) => {
+                    response.message_mut().content =
+                        Some(ChatCompletionContent::Text(value.to_string()));
+                    return Ok(response);
+                }
+                Err(error) => error,
+            };
+
+            if attempts == self.max_repairs {
+                return Err(LLMError::InvalidJsonOutput {
+                    attempts,
+                    message: error,
+                });
+            }
+            attempts += 1;
+            tracing::Span::current().record("json_repair_attempts", attempts);
+            tracing::debug!("Repairing model output, attempt {attempts}: {error}");
+            previous_messages.extend(Self::repair_messages(&previous_messages, &output, &error));
+        }
+    }
+
+    async fn stream(
+        &self,
+        input_vars: HashMap<String, Value>,
+        tx: mpsc::Sender<Option<ModelEvent>>,
+        previous_messages: Vec<Message>,
+        tags: HashMap<String, String>,
+    ) -> LLMResult<ResultStream> {
+        self.inner
+            .stream(input_vars, tx, previous_messages, tags)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::completions::tests::{ScriptedCalls, ScriptedModel};
+    use serde_json::json;
+
+    fn model(outputs: Vec<&'static str>, max_repairs: usize) -> (JsonRepairModel, ScriptedCalls) {
+        let inner = ScriptedModel::new(outputs);
+        let calls = inner.calls.clone();
+        let schema = json!({
+            "type": "object",
+            "properties": {"city": {"type": "string"}},
+            "required": ["city"]
+        });
+        (
+            JsonRepairModel::new(Box::new(inner), Some(schema), max_repairs),
+            calls,
+        )
+    }
+
+    async fn invoke(model: &JsonRepairModel) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+        let (tx, _rx) = mpsc::channel(10);
+        model
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_invalid_output_is_repaired() {
+        let (model, calls) = model(
+            vec!["Sure! {city: Paris}", "```json\n{\"city\": \"Paris\"}\n```"],
+            2,
+        );
+
+        let response = invoke(&model).await.unwrap();
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text(
+                r#"{"city":"Paris"}"#.to_string()
+            ))
+        );
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        let repair = &calls[1];
+        assert_eq!(repair[0].r#type, MessageType::AIMessage);
+        assert_eq!(repair[0].content.as_deref(), Some("Sure! {city: Paris}"));
+        assert_eq!(repair[1].r#type, MessageType::HumanMessage);
+        assert!(repair[1]
+            .content
+            .as_ref()
+            .unwrap()
+            .contains("not valid JSON"));
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_after_max_repairs() {
+        let (model, calls) = model(vec!["{}", "{\"city\": 1}"], 1);
+
+        let error = invoke(&model).await.unwrap_err();
+        assert!(matches!(
+            error,
+            LLMError::InvalidJsonOutput { attempts: 1, ref message } if message.contains("/city")
+        ));
+        assert_eq!(calls.lock().unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::client::completions::json_repair::follow_up_messages;
+use crate::client::completions::response_stream::ResultStream;
+use crate::error::LLMResult;
+use crate::types::gateway::{ChatCompletionContent, ChatCompletionMessageWithFinishReason};
+use crate::types::instance::ModelInstance;
+use crate::types::message::Message;
+use crate::types::ModelEvent;
+
+/// Most frequent words of languages written in Latin script, which tell them apart
+const STOPWORDS: [(&str, &[&str]); 7] = [
+    (
+        "en",
+        &[
+            "the", "and", "is", "of", "to", "in", "that", "it", "you", "for", "with", "are",
+            "this", "was",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "la", "de", "que", "y", "en", "los", "es", "por", "un", "una", "las", "con",
+            "para",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "de", "et", "est", "un", "une", "des", "que", "pour", "dans", "pas",
+            "vous",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "das", "und", "ist", "nicht", "ein", "eine", "zu", "den", "mit", "ich",
+            "sie", "es",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "di", "che", "e", "la", "un", "una", "per", "non", "sono", "con", "gli", "è",
+            "della",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "a", "de", "que", "e", "do", "da", "em", "um", "uma", "não", "para", "os", "com",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "van", "is", "dat", "niet", "ik", "je", "op", "zijn", "met",
+            "voor",
+        ],
+    ),
+];
+
+/// Stopwords Latin script text needs to contain for its language to be detected
+const MIN_STOPWORDS: usize = 2;
+
+/// Stopwords the detected language needs over the runner-up, as related languages such as
+/// Spanish and Portuguese share many
+const MIN_STOPWORD_LEAD: usize = 2;
+
+const LANGUAGE_NAMES: [(&str, &str); 16] = [
+    ("en", "English"),
+    ("es", "Spanish"),
+    ("fr", "French"),
+    ("de", "German"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("nl", "Dutch"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("zh", "Chinese"),
+    ("ru", "Russian"),
+    ("ar", "Arabic"),
+    ("he", "Hebrew"),
+    ("el", "Greek"),
+    ("hi", "Hindi"),
+    ("th", "Thai"),
+];
+
+/// Language of the non-Latin scripts characters of `c` belong to
+fn script_language(c: char) -> Option<&'static str> {
+    match c {
+        '\u{3040}'..='\u{30FF}' => Some("ja"),
+        '\u{1100}'..='\u{11FF}' | '\u{AC00}'..='\u{D7AF}' => Some("ko"),
+        '\u{4E00}'..='\u{9FFF}' => Some("zh"),
+        '\u{0400}'..='\u{04FF}' => Some("ru"),
+        '\u{0600}'..='\u{06FF}' => Some("ar"),
+        '\u{0590}'..='\u{05FF}' => Some("he"),
+        '\u{0370}'..='\u{03FF}' => Some("el"),
+        '\u{0900}'..='\u{097F}' => Some("hi"),
+        '\u{0E00}'..='\u{0E7F}' => Some("th"),
+        _ => None,
+    }
+}
+
+/// Language with the highest score, unless the runner-up is within `min_lead` of it
+fn leader(
+    scores: impl IntoIterator<Item = (&'static str, usize)>,
+    min_lead: usize,
+) -> Option<(&'static str, usize)> {
+    let mut scores: Vec<_> = scores.into_iter().collect();
+    scores.sort_by(|a, b| b.1.cmp(&a.1));
+    match scores.as_slice() {
+        [] => None,
+        [top] => Some(*top),
+        [top, runner_up, ..] => (top.1 >= runner_up.1 + min_lead).then_some(*top),
+    }
+}
+
+/// Detects the language of `text` as an ISO 639-1 code. Non-Latin scripts are detected by
+/// their characters, Latin script languages by their most frequent words. Returns `None`
+/// when the text is too short or ambiguous to tell.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scripts: HashMap<&'static str, usize> = HashMap::new();
+    let mut latin = 0;
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        match script_language(c) {
+            Some(language) => *scripts.entry(language).or_default() += 1,
+            None => latin += 1,
+        }
+    }
+
+    // Japanese is written with kana and Chinese characters alike
+    if let Some(kana) = scripts.remove("ja") {
+        *scripts.entry("ja").or_default() += kana + scripts.remove("zh").unwrap_or_default();
+    }
+    let top_script = scripts.values().max().copied();
+    if top_script.is_some_and(|count| count >= latin) {
+        // Text mixing two scripts evenly is ambiguous
+        return leader(scripts, 1).map(|(language, _)| language);
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let (language, score) = leader(
+        STOPWORDS.iter().map(|(language, stopwords)| {
+            let score = words
+                .iter()
+                .filter(|word| stopwords.contains(&word.as_str()))
+                .count();
+            (*language, score)
+        }),
+        MIN_STOPWORD_LEAD,
+    )?;
+
+    (score >= MIN_STOPWORDS).then_some(language)
+}
+
+/// Language code of a locale, e.g. `pt` for `pt-BR`
+fn primary_language(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn language_name(language: &str) -> &str {
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(code, _)| *code == language)
+        .map_or(language, |(_, name)| *name)
+}
+
+/// Wraps a model so it replies in `required_language`, a language code or locale such as
+/// `fr` or `pt-BR`. Output detected in another language is sent back to the model with an
+/// instruction to reply in the required language, up to `max_regenerations` times, after
+/// which the last output is returned as is. Output whose language can't be detected is
+/// accepted. The required and detected languages are recorded as `required_language` and
+/// `detected_language` on the current span, and regenerations as `language_regenerations`.
+///
+/// Streams are passed through unchanged, as their output is sent before it can be checked.
+pub struct LanguageEnforcementModel {
+    inner: Box<dyn ModelInstance>,
+    required_language: String,
+    max_regenerations: usize,
+}
+
+impl LanguageEnforcementModel {
+    pub fn new(
+        inner: Box<dyn ModelInstance>,
+        required_language: String,
+        max_regenerations: usize,
+    ) -> Self {
+        Self {
+            inner,
+            required_language,
+            max_regenerations,
+        }
+    }
+}
+
+#[async_trait]
+impl ModelInstance for LanguageEnforcementModel {
+    async fn invoke(
+        &self,
+        input_vars: HashMap<String, Value>,
+        tx: mpsc::Sender<Option<ModelEvent>>,
+        mut previous_messages: Vec<Message>,
+        tags: HashMap<String, String>,
+    ) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+        let span = tracing::Span::current();
+        span.record("required_language", self.required_language.as_str());
+        let required = primary_language(&self.required_language);
+
+        let mut regenerations = 0;
+        loop {
+            let response = self
+                .inner
+                .invoke(
+                    input_vars.clone(),
+                    tx.clone(),
+                    previous_messages.clone(),
+                    tags.clone(),
+                )
+                .await?;
+
+            let output = response
+                .message()
+                .content
+                .as_ref()
+                .and_then(ChatCompletionContent::as_string)
+                .unwrap_or_default();
+            let detected = match detect_language(&output) {
+                Some(detected) => detected,
+                None => return Ok(response),
+            };
+            span.record("detected_language", detected);
+            if detected == required {
+                return Ok(response);
+            }
+            if regenerations == self.max_regenerations {
+                tracing::warn!(
+                    "Model replied in {detected} instead of {required} after {regenerations} regenerations"
+                );
+                return Ok(response);
+            }
+
+            regenerations += 1;
+            span.record("language_regenerations", regenerations);
+            tracing::debug!("Regenerating output in {required}, attempt {regenerations}");
+            let required_name = language_name(&required);
+            previous_messages.extend(follow_up_messages(
+                &previous_messages,
+                &output,
+                format!(
+                    "Your reply was in {}, but it must be in {required_name}. Reply again in {required_name} only.",
+                    language_name(detected)
+                ),
+            ));
+        }
+    }
+
+    async fn stream(
+        &self,
+        input_vars: HashMap<String, Value>,
+        tx: mpsc::Sender<Option<ModelEvent>>,
+        previous_messages: Vec<Message>,
+        tags: HashMap<String, String>,
+    ) -> LLMResult<ResultStream> {
+        self.inner
+            .stream(input_vars, tx, previous_messages, tags)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::completions::tests::ScriptedModel;
+    use crate::types::message::MessageType;
+
+    #[test]
+    fn test_detect_language() {
+        assert_eq!(
+            detect_language("The weather is sunny and it is warm in the city."),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("Le temps est ensoleillé et il fait chaud dans la ville."),
+            Some("fr")
+        );
+        assert_eq!(detect_language("今日はとても良い天気です。"), Some("ja"));
+        assert_eq!(detect_language("Сегодня солнечно и тепло."), Some("ru"));
+        assert_eq!(detect_language("OK"), None);
+    }
+
+    #[test]
+    fn test_ambiguous_language_is_not_detected() {
+        // Spanish and Portuguese both match "para que"
+        assert_eq!(detect_language("para que"), None);
+        // Spanish leads Portuguese and French by a single stopword
+        assert_eq!(detect_language("de la que para"), None);
+        // As many Cyrillic as Hebrew characters
+        assert_eq!(detect_language("мир שלם"), None);
+        assert_eq!(
+            detect_language("Los niños de la escuela juegan en el parque con una pelota."),
+            Some("es")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mismatched_language_is_regenerated() {
+        let inner = ScriptedModel::new(vec![
+            "The weather is sunny and it is warm in the city.",
+            "Le temps est ensoleillé et il fait chaud dans la ville.",
+        ]);
+        let calls = inner.calls.clone();
+        let model = LanguageEnforcementModel::new(Box::new(inner), "fr-FR".to_string(), 1);
+
+        let (tx, _rx) = mpsc::channel(10);
+        let response = model
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text(
+                "Le temps est ensoleillé et il fait chaud dans la ville.".to_string()
+            ))
+        );
+
+        let calls = calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        let regeneration = &calls[1];
+        assert_eq!(regeneration[0].r#type, MessageType::AIMessage);
+        assert_eq!(regeneration[1].r#type, MessageType::HumanMessage);
+        assert_eq!(
+            regeneration[1].content.as_deref(),
+            Some(
+                "Your reply was in English, but it must be in French. Reply again in French only."
+            )
+        );
+    }
+}
@@ -1,19 +1,45 @@
+pub mod assemble;
+pub mod batch;
+pub mod cancellation;
+pub mod event_stream;
+pub mod interim_usage;
+pub mod json_repair;
+pub mod language;
 pub mod response_stream;
+pub mod stop_sequence;
+pub mod stream_usage;
+
+#[cfg(test)]
+pub(crate) mod tests;
 
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 use serde_json::Value;
 
+use crate::client::completions::assemble::assemble_stream;
+use crate::client::completions::batch::{
+    BatchJob, BatchJobStatus, BatchOutcome, BatchProvider, BatchRequest, PreparedBatchRequest,
+};
+use crate::client::completions::cancellation::{cancellable, CancellationToken};
+use crate::client::completions::event_stream::{with_events, EventStream};
 use crate::client::completions::response_stream::ResultStream;
+use crate::client::completions::stop_sequence::{truncate_response, truncate_stream};
+use crate::client::completions::stream_usage::with_stream_usage;
 use crate::client::message_mapper::{MessageMapper, MessageMapperError};
 use crate::client::ModelInstance;
-use crate::error::LLMResult;
-use crate::types::engine::CompletionEngineParamsBuilder;
+use crate::error::{LLMError, LLMResult};
+use crate::provider::{anthropic, gemini};
+use crate::types::credentials_ident::CredentialsIdent;
+use crate::types::engine::{CompletionEngineParams, CompletionEngineParamsBuilder};
 use crate::types::gateway::{
     ChatCompletionMessage, ChatCompletionMessageWithFinishReason, ChatCompletionRequest,
+    CostCalculator, Usage,
 };
 use crate::types::instance::init_model_instance;
 use crate::types::message::Message;
+use crate::types::provider::ModelPrice;
 use crate::types::ModelEvent;
 use tracing::Instrument;
 
@@ -23,6 +49,8 @@ pub struct CompletionsClient {
     tx: Option<tokio::sync::mpsc::Sender<Option<ModelEvent>>>,
     tags: HashMap<String, String>,
     instance: Option<Box<dyn ModelInstance>>,
+    cancellation_token: Option<CancellationToken>,
+    pricing: Option<(ModelPrice, Arc<Box<dyn CostCalculator>>)>,
 }
 
 impl CompletionsClient {
@@ -33,6 +61,8 @@ impl CompletionsClient {
             input_variables: HashMap::new(),
             tx: None,
             tags: HashMap::new(),
+            cancellation_token: None,
+            pricing: None,
         }
     }
 
@@ -56,6 +86,23 @@ impl CompletionsClient {
         self
     }
 
+    /// Streams created by this client end when `token` is cancelled, stopping the
+    /// underlying model call
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation_token = Some(token);
+        self
+    }
+
+    /// Prices of the model, used to report the cost of batch results at batch prices
+    pub fn with_pricing(
+        mut self,
+        price: ModelPrice,
+        cost_calculator: Arc<Box<dyn CostCalculator>>,
+    ) -> Self {
+        self.pricing = Some((price, cost_calculator));
+        self
+    }
+
     fn map_messages(
         messages: &[ChatCompletionMessage],
         model: &str,
@@ -89,7 +136,7 @@ impl CompletionsClient {
             }
         };
 
-        match &self.instance {
+        let mut response = match &self.instance {
             Some(instance) => {
                 instance
                     .invoke(
@@ -112,18 +159,20 @@ impl CompletionsClient {
                     )
                     .await
             }
+        }?;
+
+        if let Some(stop) = &r.stop {
+            if let Some(sequence) = truncate_response(&mut response, stop) {
+                tracing::Span::current().record("stop_sequence", sequence);
+            }
         }
+        Ok(response)
     }
 
     pub async fn create_stream(
         &self,
         request: impl Into<ChatCompletionRequest>,
     ) -> LLMResult<ResultStream> {
-        let mut r = request.into();
-        r.stream = Some(true);
-
-        let messages = Self::map_messages(&r.messages, &r.model, r.user.clone())?;
-
         let tx = match &self.tx {
             Some(tx) => tx.clone(),
             None => {
@@ -131,8 +180,31 @@ impl CompletionsClient {
                 tx
             }
         };
+        self.stream_with_tx(request.into(), tx).await
+    }
+
+    /// Streams `request` as a single stream of text, tool call, tool result, reasoning and
+    /// usage events, ending with [`StreamEvent::Done`](event_stream::StreamEvent::Done). Model
+    /// events are still sent to the client's event sender when one is set.
+    pub async fn create_event_stream(
+        &self,
+        request: impl Into<ChatCompletionRequest>,
+    ) -> LLMResult<EventStream> {
+        let (tx, rx) = tokio::sync::mpsc::channel(10000);
+        let stream = self.stream_with_tx(request.into(), tx).await?;
+        Ok(with_events(stream, Some(rx), self.tx.clone()))
+    }
+
+    async fn stream_with_tx(
+        &self,
+        mut r: ChatCompletionRequest,
+        tx: tokio::sync::mpsc::Sender<Option<ModelEvent>>,
+    ) -> LLMResult<ResultStream> {
+        r.stream = Some(true);
+
+        let messages = Self::map_messages(&r.messages, &r.model, r.user.clone())?;
 
-        match &self.instance {
+        let stream = match &self.instance {
             Some(instance) => {
                 instance
                     .stream(
@@ -157,6 +229,114 @@ impl CompletionsClient {
                     .instrument(tracing::Span::current())
                     .await
             }
+        }?;
+        let stream = match &r.stop {
+            Some(stop) => truncate_stream(stream, stop.clone(), tracing::Span::current()),
+            None => stream,
+        };
+        let stream = with_stream_usage(stream, r.include_usage(), &r.messages);
+
+        Ok(match &self.cancellation_token {
+            Some(token) => cancellable(
+                stream,
+                token.clone(),
+                self.tx.clone(),
+                tracing::Span::current(),
+            ),
+            None => stream,
+        })
+    }
+
+    /// Streams `request` and returns the assembled message once the stream ends, including
+    /// tool calls. When `timeout` runs out first the model call is stopped and the content
+    /// received so far is returned with an `incomplete` finish reason.
+    pub async fn create_via_stream(
+        &self,
+        request: impl Into<ChatCompletionRequest>,
+        timeout: Option<Duration>,
+    ) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+        let stream = self.create_stream(request).await?;
+        assemble_stream(stream, timeout).await
+    }
+
+    /// Submits `requests` to the native batch API of the model's provider. Batch jobs
+    /// are processed asynchronously at discounted prices, poll the returned job with
+    /// [`CompletionsClient::poll_batch_job`].
+    pub async fn submit_batch_job(&self, requests: Vec<BatchRequest>) -> LLMResult<BatchJob> {
+        let model = requests
+            .first()
+            .map(|r| r.request.model.clone())
+            .ok_or_else(|| LLMError::CustomError("Batch job has no requests".to_string()))?;
+
+        let mut prepared = Vec::with_capacity(requests.len());
+        for BatchRequest { custom_id, request } in requests {
+            let messages =
+                Self::map_messages(&request.messages, &request.model, request.user.clone())?;
+            prepared.push(PreparedBatchRequest {
+                custom_id,
+                engine: self.builder.build(&request)?,
+                messages,
+            });
+        }
+
+        match &prepared[0].engine {
+            CompletionEngineParams::Anthropic { .. } => {
+                anthropic::batch::submit_batch(&model, prepared, &self.input_variables).await
+            }
+            CompletionEngineParams::Gemini { .. } => {
+                gemini::batch::submit_batch(&model, prepared, &self.input_variables).await
+            }
+            other => Err(LLMError::UnsupportedProvider(
+                other.engine_name().to_string(),
+            )),
+        }
+    }
+
+    /// Current state of a batch job. Results are returned once the job completed, with
+    /// their cost at the model's batch prices when the client has pricing.
+    pub async fn poll_batch_job(&self, job: &BatchJob) -> LLMResult<BatchJobStatus> {
+        let engine = self.builder.build(&ChatCompletionRequest {
+            model: job.model.clone(),
+            ..Default::default()
+        })?;
+
+        let mut status = match job.provider {
+            BatchProvider::Anthropic => anthropic::batch::poll_batch(job, &engine).await?,
+            BatchProvider::Gemini => gemini::batch::poll_batch(job, &engine).await?,
+        };
+
+        if let Some((price, cost_calculator)) = &self.pricing {
+            let batch_price = price.batch();
+            let credentials_ident = match &engine {
+                CompletionEngineParams::Anthropic {
+                    credentials: Some(_),
+                    ..
+                }
+                | CompletionEngineParams::Gemini {
+                    credentials: Some(_),
+                    ..
+                } => CredentialsIdent::Own,
+                _ => CredentialsIdent::Vllora,
+            };
+            for result in &mut status.results {
+                let BatchOutcome::Succeeded { response } = &result.outcome else {
+                    continue;
+                };
+                let Some(usage) = response.usage() else {
+                    continue;
+                };
+                let cost = cost_calculator
+                    .calculate_cost(
+                        &batch_price,
+                        &Usage::CompletionModelUsage(usage.clone()),
+                        &credentials_ident,
+                    )
+                    .await
+                    .map_err(|e| LLMError::CustomError(e.to_string()))?;
+                result.cost = Some(cost.cost);
+            }
         }
+
+        Ok(status)
     }
 }
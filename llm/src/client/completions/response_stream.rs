@@ -1,6 +1,10 @@
+use std::future::Future;
 use std::pin::Pin;
 
-use crate::{error::LLMError, types::gateway::ChatCompletionChunk};
+use crate::{
+    error::{LLMError, LLMResult},
+    types::gateway::ChatCompletionChunk,
+};
 use futures::{stream, Stream};
 
 /// Wrapper type around the boxed async stream of raw `Chunk` items.
@@ -36,3 +40,23 @@ impl Stream for ResultStream {
         Pin::new(&mut self.inner).poll_next(cx)
     }
 }
+
+/// Drives a provider streaming task until it completes or the consumer drops the stream.
+///
+/// Dropping the task closes the upstream connection, so the model stops generating
+/// tokens instead of streaming into a closed channel.
+pub async fn stream_until_closed(
+    tx_response: &tokio::sync::mpsc::Sender<LLMResult<ChatCompletionChunk>>,
+    task: impl Future<Output = LLMResult<()>>,
+) {
+    tokio::select! {
+        result = task => {
+            if let Err(e) = result {
+                let _ = tx_response.send(Err(e)).await;
+            }
+        }
+        _ = tx_response.closed() => {
+            tracing::Span::current().record("cancelled", true);
+        }
+    }
+}
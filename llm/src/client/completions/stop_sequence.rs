@@ -0,0 +1,226 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::{stream, StreamExt};
+
+use crate::client::completions::response_stream::ResultStream;
+use crate::types::gateway::{
+    ChatCompletionChunk, ChatCompletionChunkChoice, ChatCompletionContent, ChatCompletionDelta,
+    ChatCompletionMessageWithFinishReason,
+};
+
+/// Position and value of the earliest stop sequence in `text`
+fn find_stop<'a>(text: &str, stop: &'a [String]) -> Option<(usize, &'a str)> {
+    stop.iter()
+        .filter(|sequence| !sequence.is_empty())
+        .filter_map(|sequence| {
+            text.find(sequence.as_str())
+                .map(|pos| (pos, sequence.as_str()))
+        })
+        .min_by_key(|(pos, _)| *pos)
+}
+
+/// Removes a stop sequence, and anything after it, from the text of `response`. Some
+/// providers include the sequence that stopped the output, others don't. Returns the stop
+/// sequence found.
+pub fn truncate_response(
+    response: &mut ChatCompletionMessageWithFinishReason,
+    stop: &[String],
+) -> Option<String> {
+    let message = response.message_mut();
+    let Some(ChatCompletionContent::Text(text)) = &mut message.content else {
+        return None;
+    };
+    let (pos, sequence) = find_stop(text, stop)?;
+    text.truncate(pos);
+    Some(sequence.to_string())
+}
+
+struct StopState {
+    inner: ResultStream,
+    stop: Vec<String>,
+    /// Bytes of content held back per choice, as they may start a stop sequence
+    hold_back: usize,
+    held: HashMap<i32, String>,
+    stopped: HashSet<i32>,
+    last_chunk: Option<ChatCompletionChunk>,
+    span: tracing::Span,
+}
+
+impl StopState {
+    /// Content of `choice` that can be sent. Content after a stop sequence is dropped.
+    fn truncate_choice(&mut self, choice: &mut ChatCompletionChunkChoice) {
+        if self.stopped.contains(&choice.index) {
+            choice.delta.content = None;
+        } else if let Some(content) = choice.delta.content.take() {
+            let held = self.held.entry(choice.index).or_default();
+            held.push_str(&content);
+            let ready = match find_stop(held, &self.stop) {
+                Some((pos, sequence)) => {
+                    self.span.record("stop_sequence", sequence);
+                    self.stopped.insert(choice.index);
+                    held.truncate(pos);
+                    std::mem::take(held)
+                }
+                None => {
+                    let mut keep_from = held.len().saturating_sub(self.hold_back);
+                    while !held.is_char_boundary(keep_from) {
+                        keep_from -= 1;
+                    }
+                    let rest = held.split_off(keep_from);
+                    std::mem::replace(held, rest)
+                }
+            };
+            choice.delta.content = (!ready.is_empty()).then_some(ready);
+        }
+
+        if choice.finish_reason.is_some() {
+            if let Some(held) = self.held.remove(&choice.index).filter(|h| !h.is_empty()) {
+                choice
+                    .delta
+                    .content
+                    .get_or_insert_with(String::new)
+                    .push_str(&held);
+            }
+            if self.stopped.contains(&choice.index) {
+                choice.finish_reason = Some("stop".to_string());
+            }
+        }
+    }
+
+    /// Chunk with the content still held back when the stream ended without finishing
+    fn held_chunk(&mut self) -> Option<ChatCompletionChunk> {
+        let last = self.last_chunk.as_ref()?;
+        let mut held: Vec<_> = self.held.drain().filter(|(_, h)| !h.is_empty()).collect();
+        if held.is_empty() {
+            return None;
+        }
+        held.sort_by_key(|(index, _)| *index);
+        Some(ChatCompletionChunk {
+            choices: held
+                .into_iter()
+                .map(|(index, content)| ChatCompletionChunkChoice {
+                    index,
+                    delta: ChatCompletionDelta::from_assistant_text(content),
+                    finish_reason: None,
+                    logprobs: None,
+                })
+                .collect(),
+            usage: None,
+            ..last.clone()
+        })
+    }
+}
+
+/// Removes stop sequences, and anything after them, from the content of `inner`. Content
+/// that could be the start of a stop sequence is held back until the next chunk shows it
+/// isn't. The stop sequence found is recorded as `stop_sequence` on `span`.
+pub fn truncate_stream(
+    inner: ResultStream,
+    stop: Vec<String>,
+    span: tracing::Span,
+) -> ResultStream {
+    if stop.iter().all(|s| s.is_empty()) {
+        return inner;
+    }
+    let hold_back = stop.iter().map(|s| s.len()).max().unwrap_or(0) - 1;
+
+    let state = StopState {
+        inner,
+        stop,
+        hold_back,
+        held: HashMap::new(),
+        stopped: HashSet::new(),
+        last_chunk: None,
+        span,
+    };
+
+    ResultStream::new(Box::pin(stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        match state.inner.next().await {
+            Some(Ok(mut chunk)) => {
+                for choice in chunk.choices.iter_mut() {
+                    state.truncate_choice(choice);
+                }
+                state.last_chunk = Some(chunk.clone());
+                Some((Ok(chunk), Some(state)))
+            }
+            Some(Err(e)) => Some((Err(e), Some(state))),
+            None => {
+                let chunk = state.held_chunk()?;
+                Some((Ok(chunk), None))
+            }
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LLMError;
+
+    fn chunk(content: Option<&str>, finish_reason: Option<&str>) -> ChatCompletionChunk {
+        ChatCompletionChunk {
+            id: "chunk".to_string(),
+            object: "chat.completion.chunk".to_string(),
+            created: 0,
+            model: "model".to_string(),
+            choices: vec![ChatCompletionChunkChoice {
+                index: 0,
+                delta: ChatCompletionDelta {
+                    content: content.map(ToString::to_string),
+                    ..Default::default()
+                },
+                finish_reason: finish_reason.map(ToString::to_string),
+                logprobs: None,
+            }],
+            usage: None,
+            tool_event: None,
+        }
+    }
+
+    async fn collect(chunks: Vec<ChatCompletionChunk>, stop: &[&str]) -> (String, Vec<String>) {
+        let inner = ResultStream::new(Box::pin(stream::iter(
+            chunks.into_iter().map(Ok::<_, LLMError>),
+        )));
+        let stop = stop.iter().map(ToString::to_string).collect();
+        let chunks: Vec<_> = truncate_stream(inner, stop, tracing::Span::none())
+            .collect()
+            .await;
+
+        let mut content = String::new();
+        let mut finish_reasons = vec![];
+        for chunk in chunks {
+            for choice in chunk.unwrap().choices {
+                content.push_str(choice.delta.content.as_deref().unwrap_or_default());
+                finish_reasons.extend(choice.finish_reason);
+            }
+        }
+        (content, finish_reasons)
+    }
+
+    #[tokio::test]
+    async fn test_stop_sequence_split_across_chunks_is_removed() {
+        let chunks = vec![
+            chunk(Some("1, 2, 3"), None),
+            chunk(Some(" EN"), None),
+            chunk(Some("D 4"), None),
+            chunk(None, Some("length")),
+        ];
+        let (content, finish_reasons) = collect(chunks, &["END"]).await;
+        assert_eq!(content, "1, 2, 3 ");
+        assert_eq!(finish_reasons, vec!["stop"]);
+    }
+
+    #[tokio::test]
+    async fn test_held_back_content_is_flushed() {
+        // Looks like the start of the stop sequence, but isn't
+        let chunks = vec![chunk(Some("Hello E"), None), chunk(Some("N"), None)];
+        let (content, finish_reasons) = collect(chunks, &["END"]).await;
+        assert_eq!(content, "Hello EN");
+        assert!(finish_reasons.is_empty());
+
+        let chunks = vec![chunk(Some("Héllo wörld"), Some("stop"))];
+        let (content, _) = collect(chunks, &["§§§"]).await;
+        assert_eq!(content, "Héllo wörld");
+    }
+}
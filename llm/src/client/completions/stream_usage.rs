@@ -0,0 +1,362 @@
+use futures::{stream, StreamExt};
+
+use crate::client::completions::interim_usage::estimate_tokens;
+use crate::client::completions::response_stream::ResultStream;
+use crate::types::gateway::{ChatCompletionChunk, ChatCompletionMessage, ChatCompletionUsage};
+
+/// Estimated number of tokens of the text content of `messages`
+pub fn estimate_prompt_tokens(messages: &[ChatCompletionMessage]) -> u32 {
+    messages
+        .iter()
+        .filter_map(|m| m.content.as_ref())
+        .map(|content| match content.as_content() {
+            Some(parts) => parts
+                .iter()
+                .filter_map(|p| p.text.as_deref())
+                .map(estimate_tokens)
+                .sum(),
+            None => content
+                .as_string()
+                .as_deref()
+                .map(estimate_tokens)
+                .unwrap_or(0),
+        })
+        .sum()
+}
+
+struct UsageState {
+    inner: ResultStream,
+    prompt_tokens: u32,
+    output: String,
+    last_chunk: Option<ChatCompletionChunk>,
+    has_usage: bool,
+}
+
+impl UsageState {
+    /// Final chunk with usage estimated from the prompt and the streamed output
+    fn estimated_usage_chunk(&self) -> Option<ChatCompletionChunk> {
+        let last = self.last_chunk.as_ref()?;
+        let completion_tokens = estimate_tokens(&self.output);
+        Some(ChatCompletionChunk {
+            choices: vec![],
+            usage: Some(ChatCompletionUsage {
+                prompt_tokens: self.prompt_tokens as i32,
+                completion_tokens: completion_tokens as i32,
+                total_tokens: (self.prompt_tokens + completion_tokens) as i32,
+                ..Default::default()
+            }),
+            tool_event: None,
+            ..last.clone()
+        })
+    }
+}
+
+/// Makes usage reporting of a stream independent of the provider.
+///
+/// With `include_usage` the stream always ends with a usage chunk: the provider's when it
+/// reported one, otherwise one estimated from `messages` and the streamed output. Without it
+/// usage is removed from the stream and nothing is estimated.
+pub fn with_stream_usage(
+    stream: ResultStream,
+    include_usage: bool,
+    messages: &[ChatCompletionMessage],
+) -> ResultStream {
+    if !include_usage {
+        return ResultStream::new(Box::pin(stream.filter_map(|chunk| async move {
+            match chunk {
+                Ok(mut chunk) if chunk.usage.is_some() => {
+                    chunk.usage = None;
+                    (!chunk.choices.is_empty()).then_some(Ok(chunk))
+                }
+                chunk => Some(chunk),
+            }
+        })));
+    }
+
+    let state = UsageState {
+        inner: stream,
+        prompt_tokens: estimate_prompt_tokens(messages),
+        output: String::new(),
+        last_chunk: None,
+        has_usage: false,
+    };
+
+    ResultStream::new(Box::pin(stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        match state.inner.next().await {
+            Some(chunk) => {
+                if let Ok(chunk) = &chunk {
+                    for choice in &chunk.choices {
+                        if let Some(content) = &choice.delta.content {
+                            state.output.push_str(content);
+                        }
+                    }
+                    state.has_usage |= chunk.usage.is_some();
+                    state.last_chunk = Some(chunk.clone());
+                }
+                Some((chunk, Some(state)))
+            }
+            None if !state.has_usage => {
+                let chunk = state.estimated_usage_chunk()?;
+                Some((Ok(chunk), None))
+            }
+            None => None,
+        }
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::anthropic::AnthropicModel;
+    use crate::provider::bedrock::{get_sdk_config, BedrockModel};
+    use crate::provider::gemini::model::GeminiModel;
+    use crate::provider::openai::completions::OpenAIModel;
+    use crate::provider::tests::MockStreamServer;
+    use crate::types::credentials::{ApiKeyCredentials, AwsApiKeyCredentials, BedrockCredentials};
+    use crate::types::engine::{ExecutionOptions, GeminiModelParams, OpenAiModelParams};
+    use crate::types::gateway::ChatCompletionContent;
+    use crate::types::instance::ModelInstance;
+    use aws_smithy_types::event_stream::{Header, HeaderValue, Message};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::path::PathBuf;
+
+    /// Recorded streams of each provider, with the usage the provider reported in them
+    const PROVIDER_STREAMS: [(&str, &str, Option<(i32, i32)>); 5] = [
+        (
+            "openai",
+            "openai/tests/fixtures/chat_completions_stream",
+            Some((11, 2)),
+        ),
+        (
+            "openai",
+            "openai/tests/fixtures/chat_completions_stream_without_usage",
+            None,
+        ),
+        (
+            "anthropic",
+            "anthropic/tests/fixtures/messages_stream",
+            Some((12, 5)),
+        ),
+        (
+            "gemini",
+            "gemini/tests/fixtures/stream_generate_content",
+            Some((5, 3)),
+        ),
+        (
+            "bedrock",
+            "bedrock/tests/fixtures/converse_stream",
+            Some((12, 5)),
+        ),
+    ];
+
+    fn read_fixture_file(fixture: &str) -> String {
+        let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        path.push(format!("src/provider/{fixture}"));
+        fs::read_to_string(path).expect("Failed to read fixture file")
+    }
+
+    /// Encodes the `event:`/`data:` pairs of a recorded Converse stream as the event stream
+    /// messages Bedrock sends them in
+    fn converse_stream_body(fixture: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        let lines: Vec<&str> = fixture.lines().collect();
+        for pair in lines.windows(2) {
+            let (Some(event), Some(data)) = (
+                pair[0].strip_prefix("event: "),
+                pair[1].strip_prefix("data: "),
+            ) else {
+                continue;
+            };
+            let message = Message::new(data.as_bytes().to_vec())
+                .add_header(Header::new(
+                    ":event-type",
+                    HeaderValue::String(event.to_string().into()),
+                ))
+                .add_header(Header::new(
+                    ":content-type",
+                    HeaderValue::String("application/json".into()),
+                ))
+                .add_header(Header::new(
+                    ":message-type",
+                    HeaderValue::String("event".into()),
+                ));
+            aws_smithy_eventstream::frame::write_message_to(&message, &mut body).unwrap();
+        }
+        body
+    }
+
+    async fn provider_instance(provider: &str, url: String) -> Box<dyn ModelInstance> {
+        let credentials = ApiKeyCredentials {
+            api_key: "test".to_string(),
+        };
+        match provider {
+            "openai" => Box::new(
+                OpenAIModel::new(
+                    OpenAiModelParams {
+                        model: Some("gpt-4o-mini".to_string()),
+                        ..Default::default()
+                    },
+                    Some(&credentials),
+                    ExecutionOptions::default(),
+                    HashMap::new(),
+                    None,
+                    Some(&url),
+                )
+                .unwrap(),
+            ),
+            "anthropic" => Box::new(
+                AnthropicModel::new(
+                    serde_json::from_value(serde_json::json!({
+                        "model": "claude-3-5-haiku-20241022",
+                        "max_tokens": 64,
+                    }))
+                    .unwrap(),
+                    ExecutionOptions::default(),
+                    Some(&credentials),
+                    HashMap::new(),
+                    Some(format!("{url}/v1/messages")),
+                )
+                .unwrap(),
+            ),
+            "gemini" => Box::new(
+                GeminiModel::new(
+                    GeminiModelParams {
+                        model: Some("gemini-2.0-flash".to_string()),
+                        ..Default::default()
+                    },
+                    ExecutionOptions::default(),
+                    Some(&credentials),
+                    HashMap::new(),
+                    Some(url),
+                )
+                .unwrap(),
+            ),
+            "bedrock" => {
+                let credentials = BedrockCredentials::ApiKey(AwsApiKeyCredentials {
+                    api_key: "test".to_string(),
+                    region: Some("us-east-1".to_string()),
+                });
+                let mut model = BedrockModel::new(
+                    serde_json::from_value(serde_json::json!({
+                        "model_id": "anthropic.claude-3-haiku-20240307-v1:0",
+                    }))
+                    .unwrap(),
+                    ExecutionOptions::default(),
+                    Some(&credentials),
+                    HashMap::new(),
+                )
+                .await
+                .unwrap();
+                let config = get_sdk_config(Some(&credentials)).await.unwrap();
+                model.client = aws_sdk_bedrockruntime::Client::from_conf(
+                    aws_sdk_bedrockruntime::config::Builder::from(&config)
+                        .endpoint_url(url)
+                        .build(),
+                );
+                Box::new(model)
+            }
+            provider => unreachable!("no recorded stream for {provider}"),
+        }
+    }
+
+    /// Streams a request to the model of `provider`, answered with the recorded stream in
+    /// `fixture`
+    async fn collect(
+        provider: &str,
+        fixture: &str,
+        include_usage: bool,
+    ) -> Vec<ChatCompletionChunk> {
+        let server = MockStreamServer::start().await.unwrap();
+        let fixture = read_fixture_file(fixture);
+        match provider {
+            "bedrock" => {
+                server
+                    .set_payload(
+                        "application/vnd.amazon.eventstream",
+                        converse_stream_body(&fixture),
+                    )
+                    .await
+            }
+            _ => {
+                server
+                    .set_payload("text/event-stream", fixture.into_bytes())
+                    .await
+            }
+        }
+
+        let instance = provider_instance(provider, server.url()).await;
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let stream = instance
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .unwrap();
+
+        let messages = vec![ChatCompletionMessage {
+            role: "user".to_string(),
+            content: Some(ChatCompletionContent::Text("Say hello world".to_string())),
+            ..Default::default()
+        }];
+        with_stream_usage(stream, include_usage, &messages)
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await
+    }
+
+    fn text(chunks: &[ChatCompletionChunk]) -> String {
+        chunks
+            .iter()
+            .flat_map(|c| &c.choices)
+            .filter_map(|choice| choice.delta.content.as_deref())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_final_usage_is_always_included_when_requested() {
+        for (provider, fixture, reported) in PROVIDER_STREAMS {
+            let chunks = collect(provider, fixture, true).await;
+
+            assert_eq!(text(&chunks).trim_end(), "Hello world", "{fixture}");
+            let usages: Vec<_> = chunks.iter().filter_map(|c| c.usage.as_ref()).collect();
+            assert_eq!(usages.len(), 1, "{fixture}");
+            assert!(chunks.last().unwrap().usage.is_some(), "{fixture}");
+            let usage = usages[0];
+            match reported {
+                Some((prompt_tokens, completion_tokens)) => {
+                    assert_eq!(usage.prompt_tokens, prompt_tokens, "{fixture}");
+                    assert_eq!(usage.completion_tokens, completion_tokens, "{fixture}");
+                }
+                None => assert!(usage.completion_tokens > 0, "{fixture}"),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_usage_is_estimated() {
+        let (provider, fixture, _) = PROVIDER_STREAMS[1];
+        let chunks = collect(provider, fixture, true).await;
+
+        let usage = chunks.last().unwrap().usage.as_ref().unwrap();
+        assert_eq!(
+            usage.prompt_tokens,
+            estimate_tokens("Say hello world") as i32
+        );
+        assert_eq!(
+            usage.completion_tokens,
+            estimate_tokens("Hello world") as i32
+        );
+        assert!(chunks.last().unwrap().choices.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_usage_is_removed_when_not_requested() {
+        for (provider, fixture, _) in PROVIDER_STREAMS {
+            let chunks = collect(provider, fixture, false).await;
+
+            assert_eq!(text(&chunks).trim_end(), "Hello world", "{fixture}");
+            assert!(chunks.iter().all(|c| c.usage.is_none()), "{fixture}");
+            assert!(chunks.iter().all(|c| !c.choices.is_empty()), "{fixture}");
+        }
+    }
+}
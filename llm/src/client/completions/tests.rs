@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::Value;
+use tokio::sync::mpsc;
+
+use crate::client::completions::response_stream::ResultStream;
+use crate::error::LLMResult;
+use crate::types::gateway::{
+    ChatCompletionContent, ChatCompletionMessage, ChatCompletionMessageWithFinishReason,
+};
+use crate::types::instance::ModelInstance;
+use crate::types::message::Message;
+use crate::types::{ModelEvent, ModelFinishReason};
+
+/// Messages of every invocation of a [`ScriptedModel`]
+pub type ScriptedCalls = Arc<Mutex<Vec<Vec<Message>>>>;
+
+/// Returns `outputs` in order, recording the messages of every invocation
+pub struct ScriptedModel {
+    outputs: Mutex<Vec<&'static str>>,
+    pub calls: ScriptedCalls,
+}
+
+impl ScriptedModel {
+    pub fn new(outputs: Vec<&'static str>) -> Self {
+        Self {
+            outputs: Mutex::new(outputs),
+            calls: Arc::new(Mutex::new(vec![])),
+        }
+    }
+}
+
+#[async_trait]
+impl ModelInstance for ScriptedModel {
+    async fn invoke(
+        &self,
+        _input_vars: HashMap<String, Value>,
+        _tx: mpsc::Sender<Option<ModelEvent>>,
+        previous_messages: Vec<Message>,
+        _tags: HashMap<String, String>,
+    ) -> LLMResult<ChatCompletionMessageWithFinishReason> {
+        self.calls.lock().unwrap().push(previous_messages);
+        let output = self.outputs.lock().unwrap().remove(0);
+        Ok(ChatCompletionMessageWithFinishReason::new(
+            ChatCompletionMessage {
+                role: "assistant".to_string(),
+                content: Some(ChatCompletionContent::Text(output.to_string())),
+                ..Default::default()
+            },
+            ModelFinishReason::Stop,
+            "id".to_string(),
+            0,
+            "model".to_string(),
+            None,
+        ))
+    }
+
+    async fn stream(
+        &self,
+        _input_vars: HashMap<String, Value>,
+        _tx: mpsc::Sender<Option<ModelEvent>>,
+        _previous_messages: Vec<Message>,
+        _tags: HashMap<String, String>,
+    ) -> LLMResult<ResultStream> {
+        unimplemented!()
+    }
+}
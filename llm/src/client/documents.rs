@@ -0,0 +1,157 @@
+use base64::Engine;
+use thiserror::Error;
+
+use crate::types::gateway::File;
+
+/// Largest decoded document accepted in a file part. Matches the request size limits of
+/// providers that parse documents natively.
+pub const MAX_DOCUMENT_BYTES: usize = 32 * 1024 * 1024;
+
+/// Media types that can be sent as file parts
+pub const SUPPORTED_DOCUMENT_MEDIA_TYPES: &[&str] = &["application/pdf", "text/plain"];
+
+#[derive(Error, Debug)]
+pub enum DocumentError {
+    #[error("File part must have either file_data or file_id")]
+    MissingData,
+
+    #[error("File data must be a base64 data URL: data:<media type>;base64,<data>")]
+    InvalidDataUrl,
+
+    #[error(
+        "Unsupported document media type {0}, supported types are: {}",
+        SUPPORTED_DOCUMENT_MEDIA_TYPES.join(", ")
+    )]
+    UnsupportedMediaType(String),
+
+    #[error("Document is {size} bytes, the limit is {limit} bytes")]
+    TooLarge { size: usize, limit: usize },
+
+    #[error("Document data is not valid base64: {0}")]
+    InvalidBase64(#[from] base64::DecodeError),
+
+    #[error("{provider} does not support {what} in file parts")]
+    Unsupported { provider: String, what: String },
+}
+
+impl DocumentError {
+    pub fn unsupported(provider: &str, what: &str) -> Self {
+        Self::Unsupported {
+            provider: provider.to_string(),
+            what: what.to_string(),
+        }
+    }
+}
+
+/// Where the content of a document comes from
+#[derive(Debug, Clone, PartialEq)]
+pub enum DocumentSource {
+    /// Base64 encoded content sent with the request
+    Inline { media_type: String, data: String },
+    /// File previously uploaded to the provider
+    FileId(String),
+}
+
+/// A validated file part
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub source: DocumentSource,
+    pub filename: Option<String>,
+}
+
+impl Document {
+    /// Validates the media type and size of a file part. Inline data is expected as a
+    /// base64 data URL.
+    pub fn from_file(file: &File) -> Result<Self, DocumentError> {
+        let source = match (&file.data, &file.id) {
+            (Some(data), _) => {
+                let (media_type, data) = data
+                    .strip_prefix("data:")
+                    .and_then(|rest| rest.split_once(";base64,"))
+                    .ok_or(DocumentError::InvalidDataUrl)?;
+
+                if !SUPPORTED_DOCUMENT_MEDIA_TYPES.contains(&media_type) {
+                    return Err(DocumentError::UnsupportedMediaType(media_type.to_string()));
+                }
+
+                let size = decoded_len(data);
+                if size > MAX_DOCUMENT_BYTES {
+                    return Err(DocumentError::TooLarge {
+                        size,
+                        limit: MAX_DOCUMENT_BYTES,
+                    });
+                }
+
+                DocumentSource::Inline {
+                    media_type: media_type.to_string(),
+                    data: data.to_string(),
+                }
+            }
+            (None, Some(id)) => DocumentSource::FileId(id.clone()),
+            (None, None) => return Err(DocumentError::MissingData),
+        };
+
+        Ok(Self {
+            source,
+            filename: file.filename.clone(),
+        })
+    }
+
+    /// Media type and base64 content of an inline document
+    pub fn inline(&self) -> Option<(&str, &str)> {
+        match &self.source {
+            DocumentSource::Inline { media_type, data } => Some((media_type, data)),
+            DocumentSource::FileId(_) => None,
+        }
+    }
+
+    pub fn decode(data: &str) -> Result<Vec<u8>, DocumentError> {
+        Ok(base64::engine::general_purpose::STANDARD.decode(data)?)
+    }
+}
+
+/// Size of base64 `data` once decoded, without decoding it
+fn decoded_len(data: &str) -> usize {
+    let padding = data.bytes().rev().take_while(|b| *b == b'=').count();
+    (data.len() / 4 * 3).saturating_sub(padding)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(data: &str) -> File {
+        File {
+            data: Some(data.to_string()),
+            id: None,
+            filename: Some("report.pdf".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_pdf_data_url_is_accepted() {
+        let document =
+            Document::from_file(&file("data:application/pdf;base64,JVBERi0xLjQK")).unwrap();
+
+        assert_eq!(document.inline(), Some(("application/pdf", "JVBERi0xLjQK")));
+        assert_eq!(document.filename.as_deref(), Some("report.pdf"));
+    }
+
+    #[test]
+    fn test_unsupported_media_type_is_rejected() {
+        let result = Document::from_file(&file("data:application/zip;base64,UEsDBA=="));
+
+        assert!(matches!(
+            result,
+            Err(DocumentError::UnsupportedMediaType(media_type)) if media_type == "application/zip"
+        ));
+    }
+
+    #[test]
+    fn test_oversized_document_is_rejected() {
+        let data = "A".repeat((MAX_DOCUMENT_BYTES / 3 + 1) * 4);
+        let result = Document::from_file(&file(&format!("data:application/pdf;base64,{data}")));
+
+        assert!(matches!(result, Err(DocumentError::TooLarge { .. })));
+    }
+}
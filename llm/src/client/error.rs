@@ -1,6 +1,8 @@
+use crate::client::documents::DocumentError;
 use crate::error::ModelFinishError;
 use async_openai::error::OpenAIError;
 use aws_sdk_bedrock::error::DisplayErrorContext;
+use serde::Deserialize;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -32,6 +34,9 @@ pub enum ModelError {
     #[error(transparent)]
     Anthropic(#[from] AnthropicError),
 
+    #[error(transparent)]
+    Gemini(#[from] GeminiError),
+
     #[error("Max retries reached")]
     MaxRetriesReached,
 
@@ -55,6 +60,9 @@ pub enum ModelError {
 
     #[error("Cannot calculate input tokens")]
     CannotCalculateInputTokens,
+
+    #[error(transparent)]
+    DocumentError(#[from] DocumentError),
 }
 
 impl From<OpenAIError> for ModelError {
@@ -77,13 +85,60 @@ pub enum AuthorizationError {
 
 #[derive(Error, Debug)]
 pub enum AnthropicError {
+    #[error("Anthropic API error ({status}): {message}")]
+    ApiError {
+        status: u16,
+        r#type: String,
+        message: String,
+    },
+
     #[error(transparent)]
-    ClustError(#[from] clust::ClientError),
+    Reqwest(#[from] reqwest::Error),
 
     #[error("Error building request: {0}")]
     RequestError(String),
 }
 
+#[derive(Error, Debug)]
+pub enum GeminiError {
+    #[error("Gemini API error ({code} {status}): {message}")]
+    ApiError {
+        code: u16,
+        status: String,
+        message: String,
+    },
+}
+
+#[derive(Deserialize)]
+struct GeminiErrorResponse {
+    error: GeminiErrorBody,
+}
+
+#[derive(Deserialize)]
+struct GeminiErrorBody {
+    #[serde(default)]
+    status: String,
+    message: String,
+}
+
+impl GeminiError {
+    /// Error of a failed request, parsed from its `{"error": {...}}` body when possible
+    pub fn from_response(code: u16, body: &str) -> Self {
+        match serde_json::from_str::<GeminiErrorResponse>(body) {
+            Ok(response) => GeminiError::ApiError {
+                code,
+                status: response.error.status,
+                message: response.error.message,
+            },
+            Err(_) => GeminiError::ApiError {
+                code,
+                status: String::new(),
+                message: body.to_string(),
+            },
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum BedrockError {
     #[error("Custom Error: {0}")]
@@ -98,6 +153,9 @@ pub enum BedrockError {
     #[error("Invalid credentials: {0}")]
     AuthenticationError(String), // Adding a specific error for authentication failures
 
+    #[error("Invalid inference profile: {0}")]
+    InferenceProfileError(String),
+
     #[error("{}", DisplayErrorContext(.0))]
     SmithyError(
         #[from]
@@ -125,3 +183,425 @@ pub enum BedrockError {
         >,
     ),
 }
+
+/// Provider failure classes that clients can act on
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProviderErrorClass {
+    /// Provider throttled the request (429)
+    RateLimit { retry_after: Option<u64> },
+    /// Provider rejected the request as malformed (400)
+    InvalidRequest,
+    /// Provider failed, timed out or is overloaded (5xx)
+    ProviderUnavailable { retry_after: Option<u64> },
+    /// Prompt does not fit into the model context window
+    ContextLengthExceeded,
+}
+
+impl ModelError {
+    /// Classifies provider failures; returns `None` for gateway side errors
+    pub fn classify(&self) -> Option<ProviderErrorClass> {
+        match self {
+            ModelError::OpenAIApi(e) => classify_openai_error(e),
+            ModelError::Bedrock(e) => e.classify(),
+            ModelError::Anthropic(e) => e.classify(),
+            ModelError::Gemini(e) => e.classify(),
+            _ => None,
+        }
+    }
+}
+
+impl AnthropicError {
+    // Reference: https://docs.anthropic.com/en/api/errors
+    pub fn classify(&self) -> Option<ProviderErrorClass> {
+        match self {
+            AnthropicError::ApiError {
+                status,
+                r#type,
+                message,
+            } => match r#type.as_str() {
+                // Anthropic has no error type of its own for prompts over the context window
+                "invalid_request_error" if message.starts_with("prompt is too long") => {
+                    Some(ProviderErrorClass::ContextLengthExceeded)
+                }
+                "invalid_request_error" => Some(ProviderErrorClass::InvalidRequest),
+                "rate_limit_error" => Some(ProviderErrorClass::RateLimit { retry_after: None }),
+                "api_error" | "overloaded_error" => {
+                    Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+                }
+                _ => classify_status(*status),
+            },
+            AnthropicError::Reqwest(e) => classify_reqwest_error(e),
+            AnthropicError::RequestError(_) => None,
+        }
+    }
+}
+
+impl GeminiError {
+    // Reference: https://ai.google.dev/gemini-api/docs/troubleshooting#error-codes
+    pub fn classify(&self) -> Option<ProviderErrorClass> {
+        let GeminiError::ApiError {
+            code,
+            status,
+            message,
+        } = self;
+        match status.as_str() {
+            // Gemini reports prompts over the context window as a plain invalid argument
+            "INVALID_ARGUMENT" if message.contains("exceeds the maximum number of tokens") => {
+                Some(ProviderErrorClass::ContextLengthExceeded)
+            }
+            "INVALID_ARGUMENT" | "FAILED_PRECONDITION" => Some(ProviderErrorClass::InvalidRequest),
+            "RESOURCE_EXHAUSTED" => Some(ProviderErrorClass::RateLimit { retry_after: None }),
+            "INTERNAL" | "UNAVAILABLE" | "DEADLINE_EXCEEDED" => {
+                Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+            }
+            _ => classify_status(*code),
+        }
+    }
+}
+
+impl BedrockError {
+    pub fn classify(&self) -> Option<ProviderErrorClass> {
+        match self {
+            BedrockError::ValidationError(message) => Some(classify_validation_message(message)),
+            BedrockError::TimeoutError(_) => {
+                Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+            }
+            BedrockError::CustomError(_)
+            | BedrockError::AuthenticationError(_)
+            | BedrockError::InferenceProfileError(_) => None,
+            BedrockError::SmithyError(e) => classify_sdk_error(e, None),
+            BedrockError::ConverseError(e) => {
+                classify_sdk_error(e, e.raw_response().and_then(retry_after_header))
+            }
+            BedrockError::ResponseError(e) => {
+                classify_sdk_error(e, e.raw_response().and_then(retry_after_header))
+            }
+        }
+    }
+}
+
+fn classify_openai_error(e: &OpenAIError) -> Option<ProviderErrorClass> {
+    match e {
+        OpenAIError::ApiError(api) => {
+            let code = api.code.as_deref().unwrap_or_default();
+            let r#type = api.r#type.as_deref().unwrap_or_default();
+            if code == "context_length_exceeded" {
+                Some(ProviderErrorClass::ContextLengthExceeded)
+            } else if code == "rate_limit_exceeded" || r#type == "rate_limit_error" {
+                Some(ProviderErrorClass::RateLimit { retry_after: None })
+            } else if matches!(r#type, "server_error" | "overloaded_error" | "api_error") {
+                Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+            } else if r#type == "invalid_request_error" {
+                Some(ProviderErrorClass::InvalidRequest)
+            } else {
+                None
+            }
+        }
+        OpenAIError::Reqwest(e) => classify_reqwest_error(e),
+        _ => None,
+    }
+}
+
+pub fn classify_reqwest_error(e: &reqwest::Error) -> Option<ProviderErrorClass> {
+    if e.is_timeout() || e.is_connect() {
+        return Some(ProviderErrorClass::ProviderUnavailable { retry_after: None });
+    }
+
+    e.status()
+        .and_then(|status| classify_status(status.as_u16()))
+}
+
+/// Classifies a raw provider HTTP status code
+pub fn classify_status(status: u16) -> Option<ProviderErrorClass> {
+    match status {
+        429 => Some(ProviderErrorClass::RateLimit { retry_after: None }),
+        400 | 422 => Some(ProviderErrorClass::InvalidRequest),
+        500..=599 => Some(ProviderErrorClass::ProviderUnavailable { retry_after: None }),
+        _ => None,
+    }
+}
+
+fn classify_sdk_error<E, R>(
+    e: &aws_smithy_runtime_api::client::result::SdkError<E, R>,
+    retry_after: Option<u64>,
+) -> Option<ProviderErrorClass>
+where
+    E: aws_sdk_bedrockruntime::error::ProvideErrorMetadata,
+{
+    use aws_sdk_bedrockruntime::error::ProvideErrorMetadata;
+    use aws_smithy_runtime_api::client::result::SdkError;
+
+    match e {
+        SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) => {
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after })
+        }
+        SdkError::ServiceError(_) => match e.code() {
+            Some("ThrottlingException" | "ServiceQuotaExceededException") => {
+                Some(ProviderErrorClass::RateLimit { retry_after })
+            }
+            Some(
+                "ServiceUnavailableException"
+                | "InternalServerException"
+                | "ModelNotReadyException"
+                | "ModelTimeoutException",
+            ) => Some(ProviderErrorClass::ProviderUnavailable { retry_after }),
+            Some("ValidationException") => {
+                Some(classify_validation_message(e.message().unwrap_or_default()))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn retry_after_header(response: &aws_smithy_runtime_api::http::Response) -> Option<u64> {
+    response
+        .headers()
+        .get("retry-after")
+        .and_then(|v| v.trim().parse().ok())
+}
+
+fn classify_validation_message(message: &str) -> ProviderErrorClass {
+    // Converse has no error code for prompts over the context window, only these messages
+    let message = message.to_lowercase();
+    if message.starts_with("input is too long")
+        || message.starts_with("prompt is too long")
+        || message.contains("too many input tokens")
+    {
+        ProviderErrorClass::ContextLengthExceeded
+    } else {
+        ProviderErrorClass::InvalidRequest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_openai::error::ApiError;
+    use aws_sdk_bedrockruntime::operation::converse::ConverseError;
+    use aws_sdk_bedrockruntime::types::error::{ServiceUnavailableException, ThrottlingException};
+    use aws_smithy_runtime_api::client::result::SdkError;
+    use aws_smithy_runtime_api::http::{Response, StatusCode};
+    use aws_smithy_types::body::SdkBody;
+
+    fn openai_error(r#type: Option<&str>, code: Option<&str>, message: &str) -> ModelError {
+        ModelError::OpenAIApi(Box::new(OpenAIError::ApiError(ApiError {
+            message: message.to_string(),
+            r#type: r#type.map(|t| t.to_string()),
+            param: None,
+            code: code.map(|c| c.to_string()),
+        })))
+    }
+
+    fn converse_error(error: ConverseError, status: u16) -> ModelError {
+        let response = Response::new(StatusCode::try_from(status).unwrap(), SdkBody::empty());
+        ModelError::Bedrock(Box::new(BedrockError::ConverseError(
+            SdkError::service_error(error, response),
+        )))
+    }
+
+    #[test]
+    fn test_classify_openai_errors() {
+        assert_eq!(
+            openai_error(
+                Some("requests"),
+                Some("rate_limit_exceeded"),
+                "Rate limit reached"
+            )
+            .classify(),
+            Some(ProviderErrorClass::RateLimit { retry_after: None })
+        );
+        assert_eq!(
+            openai_error(
+                Some("invalid_request_error"),
+                Some("context_length_exceeded"),
+                "This model's maximum context length is 8192 tokens"
+            )
+            .classify(),
+            Some(ProviderErrorClass::ContextLengthExceeded)
+        );
+        assert_eq!(
+            openai_error(Some("invalid_request_error"), None, "Unrecognized request").classify(),
+            Some(ProviderErrorClass::InvalidRequest)
+        );
+        assert_eq!(
+            openai_error(Some("server_error"), None, "The server had an error").classify(),
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+        );
+    }
+
+    #[test]
+    fn test_openai_context_length_needs_its_error_code() {
+        // Other invalid requests can mention that something is too long
+        assert_eq!(
+            openai_error(
+                Some("invalid_request_error"),
+                Some("string_above_max_length"),
+                "Invalid 'tools[0].function.name': string is too long"
+            )
+            .classify(),
+            Some(ProviderErrorClass::InvalidRequest)
+        );
+    }
+
+    fn anthropic_error(status: u16, body: &str) -> ModelError {
+        let response: serde_json::Value = serde_json::from_str(body).unwrap();
+        ModelError::Anthropic(AnthropicError::ApiError {
+            status,
+            r#type: response["error"]["type"].as_str().unwrap().to_string(),
+            message: response["error"]["message"].as_str().unwrap().to_string(),
+        })
+    }
+
+    #[test]
+    fn test_classify_anthropic_errors() {
+        assert_eq!(
+            anthropic_error(
+                400,
+                r#"{"type":"error","error":{"type":"invalid_request_error","message":"prompt is too long: 208310 tokens > 200000 maximum"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::ContextLengthExceeded)
+        );
+        assert_eq!(
+            anthropic_error(
+                400,
+                r#"{"type":"error","error":{"type":"invalid_request_error","message":"tools.0.name: String should have at most 64 characters, the name is too long"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::InvalidRequest)
+        );
+        assert_eq!(
+            anthropic_error(
+                429,
+                r#"{"type":"error","error":{"type":"rate_limit_error","message":"Number of request tokens has exceeded your per-minute rate limit"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::RateLimit { retry_after: None })
+        );
+        assert_eq!(
+            anthropic_error(
+                529,
+                r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+        );
+        assert_eq!(
+            anthropic_error(
+                401,
+                r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#
+            )
+            .classify(),
+            None
+        );
+    }
+
+    fn gemini_error(code: u16, body: &str) -> ModelError {
+        ModelError::Gemini(GeminiError::from_response(code, body))
+    }
+
+    #[test]
+    fn test_classify_gemini_errors() {
+        assert_eq!(
+            gemini_error(
+                400,
+                r#"{"error":{"code":400,"message":"The input token count (1200000) exceeds the maximum number of tokens allowed (1048576).","status":"INVALID_ARGUMENT"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::ContextLengthExceeded)
+        );
+        assert_eq!(
+            gemini_error(
+                400,
+                r#"{"error":{"code":400,"message":"* GenerateContentRequest.tools[0].function_declarations[0].name: Invalid function name.","status":"INVALID_ARGUMENT"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::InvalidRequest)
+        );
+        assert_eq!(
+            gemini_error(
+                429,
+                r#"{"error":{"code":429,"message":"Resource has been exhausted (e.g. check quota).","status":"RESOURCE_EXHAUSTED"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::RateLimit { retry_after: None })
+        );
+        assert_eq!(
+            gemini_error(
+                503,
+                r#"{"error":{"code":503,"message":"The model is overloaded. Please try again later.","status":"UNAVAILABLE"}}"#
+            )
+            .classify(),
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+        );
+        // Bodies that aren't Gemini errors fall back to the status code
+        assert_eq!(
+            gemini_error(502, "<html>Bad Gateway</html>").classify(),
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+        );
+    }
+
+    #[test]
+    fn test_classify_bedrock_errors() {
+        let throttled = converse_error(
+            ConverseError::ThrottlingException(
+                ThrottlingException::builder()
+                    .message("Too many requests")
+                    .build(),
+            ),
+            429,
+        );
+        assert_eq!(
+            throttled.classify(),
+            Some(ProviderErrorClass::RateLimit { retry_after: None })
+        );
+
+        let unavailable = converse_error(
+            ConverseError::ServiceUnavailableException(
+                ServiceUnavailableException::builder()
+                    .message("Service unavailable")
+                    .build(),
+            ),
+            503,
+        );
+        assert_eq!(
+            unavailable.classify(),
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+        );
+
+        let too_long = ModelError::Bedrock(Box::new(BedrockError::ValidationError(
+            "Input is too long for requested model.".to_string(),
+        )));
+        assert_eq!(
+            too_long.classify(),
+            Some(ProviderErrorClass::ContextLengthExceeded)
+        );
+
+        let invalid = ModelError::Bedrock(Box::new(BedrockError::ValidationError(
+            "temperature: must be less than or equal to 1".to_string(),
+        )));
+        assert_eq!(invalid.classify(), Some(ProviderErrorClass::InvalidRequest));
+
+        let tool_name = ModelError::Bedrock(Box::new(BedrockError::ValidationError(
+            "The tool name is too long".to_string(),
+        )));
+        assert_eq!(
+            tool_name.classify(),
+            Some(ProviderErrorClass::InvalidRequest)
+        );
+
+        let timeout = ModelError::Bedrock(Box::new(BedrockError::TimeoutError(
+            "operation timed out".to_string(),
+        )));
+        assert_eq!(
+            timeout.classify(),
+            Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+        );
+
+        assert_eq!(
+            ModelError::ModelNotFound("gpt-unknown".to_string()).classify(),
+            None
+        );
+    }
+}
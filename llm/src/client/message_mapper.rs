@@ -61,8 +61,10 @@ impl MessageMapper {
                         }])
                     }
                 }
+                // Generated audio is not sent back to models, its transcript part is
                 ChatCompletionContent::Content(content) => content
                     .iter()
+                    .filter(|c| c.r#type != ContentType::Audio)
                     .map(|c| {
                         Ok(match c.r#type {
                             ContentType::Text => MessageContentPart {
@@ -117,6 +119,7 @@ impl MessageMapper {
                                 cache_control: c.cache_control.clone(),
                                 file: c.file.clone(),
                             },
+                            ContentType::Audio => unreachable!("audio output is filtered out"),
                         })
                     })
                     .collect::<Result<Vec<MessageContentPart>, MessageMapperError>>(),
@@ -136,6 +139,7 @@ impl MessageMapper {
             tool_calls: message.tool_calls.clone(),
             tool_call_id: message.tool_call_id.clone(),
             created_at: None,
+            name: message.name.clone(),
         })
     }
 
@@ -1,7 +1,9 @@
 pub mod completions;
+pub mod documents;
 pub mod error;
 pub mod message_mapper;
 pub mod responses;
+pub mod role_normalization;
 pub mod tools;
 
 use crate::client::responses::ResponsesClient;
@@ -16,6 +18,7 @@ use crate::types::models::InferenceProvider;
 use crate::types::provider::InferenceModelProvider;
 
 pub const DEFAULT_MAX_RETRIES: u32 = 0;
+pub const DEFAULT_MAX_TOOL_ITERATIONS: u32 = 25;
 
 #[derive(Default)]
 pub struct VlloraLLMClientBuilderParams {
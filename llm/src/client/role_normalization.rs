@@ -0,0 +1,346 @@
+use serde::{Deserialize, Serialize};
+use vllora_telemetry::events::JsonValue;
+
+use crate::types::message::{Message, MessageContentPart, MessageContentType, MessageType};
+
+/// Sent as a user turn where strict alternation needs one and the history has none
+pub const PLACEHOLDER_USER_CONTENT: &str = "Continue.";
+/// Sent as an assistant turn where strict alternation needs one and the history has none
+pub const PLACEHOLDER_ASSISTANT_CONTENT: &str = "Understood.";
+
+/// How a provider constrains the order of user and assistant turns
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RolePolicy {
+    /// Messages are sent as they are
+    #[default]
+    Passthrough,
+    /// Consecutive user or assistant messages are merged into one turn
+    Merge,
+    /// Turns are merged and strictly alternate, starting with a user turn.
+    /// Placeholder turns are inserted where merging is not possible.
+    Alternate,
+}
+
+/// What normalization changed in a conversation
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct RoleNormalization {
+    pub policy: RolePolicy,
+    pub merged: usize,
+    pub inserted: usize,
+}
+
+impl RoleNormalization {
+    pub fn is_altered(&self) -> bool {
+        self.merged > 0 || self.inserted > 0
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Turn {
+    User,
+    Assistant,
+}
+
+fn turn(message: &Message) -> Option<Turn> {
+    match message.r#type {
+        MessageType::SystemMessage => None,
+        // Tool results are sent back to providers as part of a user turn
+        MessageType::HumanMessage | MessageType::ToolResult => Some(Turn::User),
+        MessageType::AIMessage => Some(Turn::Assistant),
+    }
+}
+
+/// Whether `next` can be folded into `previous` without breaking tool call and
+/// tool result pairing
+fn can_merge(previous: &Message, next: &Message) -> bool {
+    // Messages of different speakers keep their own turn
+    if previous.name != next.name {
+        return false;
+    }
+    match (&previous.r#type, &next.r#type) {
+        (MessageType::HumanMessage, MessageType::HumanMessage) => true,
+        // Tool calls must stay last in their turn so results can follow them
+        (MessageType::AIMessage, MessageType::AIMessage) => previous.tool_calls.is_none(),
+        _ => false,
+    }
+}
+
+fn content_parts(message: &Message) -> Vec<MessageContentPart> {
+    if !message.content_array.is_empty() {
+        return message.content_array.clone();
+    }
+
+    message
+        .content
+        .iter()
+        .filter(|content| !content.is_empty())
+        .map(|content| MessageContentPart {
+            r#type: MessageContentType::Text,
+            value: content.clone(),
+            additional_options: None,
+            file: None,
+            cache_control: None,
+        })
+        .collect()
+}
+
+fn merge_into(previous: &mut Message, next: Message) {
+    if previous.content_array.is_empty() && next.content_array.is_empty() {
+        let content = [previous.content.take(), next.content]
+            .into_iter()
+            .flatten()
+            .filter(|content| !content.is_empty())
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        previous.content = Some(content);
+    } else {
+        let mut parts = content_parts(previous);
+        parts.extend(content_parts(&next));
+        previous.content = None;
+        previous.content_array = parts;
+    }
+
+    if let Some(tool_calls) = next.tool_calls {
+        previous.tool_calls = Some(tool_calls);
+    }
+}
+
+fn placeholder(template: &Message, turn: Turn) -> Message {
+    let (r#type, content) = match turn {
+        Turn::User => (MessageType::HumanMessage, PLACEHOLDER_USER_CONTENT),
+        Turn::Assistant => (MessageType::AIMessage, PLACEHOLDER_ASSISTANT_CONTENT),
+    };
+
+    Message {
+        r#type,
+        content_type: MessageContentType::Text,
+        content: Some(content.to_string()),
+        content_array: vec![],
+        tool_call_id: None,
+        tool_calls: None,
+        created_at: None,
+        name: None,
+        ..template.clone()
+    }
+}
+
+/// Rewrites the conversation to satisfy `policy`. System messages are left in place and
+/// do not count as turns.
+pub fn normalize_roles(
+    messages: Vec<Message>,
+    policy: RolePolicy,
+) -> (Vec<Message>, RoleNormalization) {
+    let mut summary = RoleNormalization {
+        policy,
+        ..Default::default()
+    };
+    if policy == RolePolicy::Passthrough {
+        return (messages, summary);
+    }
+
+    let mut normalized: Vec<Message> = Vec::with_capacity(messages.len());
+    // Index of the last non-system message in `normalized`
+    let mut last: Option<usize> = None;
+
+    for message in messages {
+        let Some(current) = turn(&message) else {
+            normalized.push(message);
+            continue;
+        };
+
+        let previous = last.map(|index| &normalized[index]);
+        match previous {
+            Some(previous) if turn(previous) == Some(current) => {
+                if can_merge(previous, &message) {
+                    let index = last.expect("previous message exists");
+                    merge_into(&mut normalized[index], message);
+                    summary.merged += 1;
+                    continue;
+                }
+
+                let repeated_tool_results = previous.r#type == MessageType::ToolResult
+                    && message.r#type == MessageType::ToolResult;
+                if policy == RolePolicy::Alternate && !repeated_tool_results {
+                    let other = match current {
+                        Turn::User => Turn::Assistant,
+                        Turn::Assistant => Turn::User,
+                    };
+                    normalized.push(placeholder(&message, other));
+                    summary.inserted += 1;
+                }
+            }
+            None if policy == RolePolicy::Alternate && current == Turn::Assistant => {
+                normalized.push(placeholder(&message, Turn::User));
+                summary.inserted += 1;
+            }
+            _ => {}
+        }
+
+        normalized.push(message);
+        last = Some(normalized.len() - 1);
+    }
+
+    (normalized, summary)
+}
+
+/// Applies `policy` and records on the current span when the conversation was altered
+pub fn normalize_for_provider(messages: Vec<Message>, policy: RolePolicy) -> Vec<Message> {
+    let (messages, normalization) = normalize_roles(messages, policy);
+    if normalization.is_altered() {
+        if let Ok(value) = serde_json::to_value(&normalization) {
+            tracing::Span::current().record("role_normalization", JsonValue(&value).as_value());
+        }
+    }
+    messages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::gateway::{FunctionCall, ToolCall};
+
+    fn message(r#type: MessageType, content: &str) -> Message {
+        Message {
+            model_name: "model".to_string(),
+            thread_id: None,
+            user_id: "user".to_string(),
+            content_type: MessageContentType::Text,
+            content: Some(content.to_string()),
+            content_array: vec![],
+            r#type,
+            tool_call_id: None,
+            tool_calls: None,
+            created_at: None,
+            name: None,
+        }
+    }
+
+    fn tool_call_message(ids: &[&str]) -> Message {
+        Message {
+            tool_calls: Some(
+                ids.iter()
+                    .map(|id| ToolCall {
+                        index: None,
+                        id: id.to_string(),
+                        r#type: "function".to_string(),
+                        function: FunctionCall {
+                            name: "get_weather".to_string(),
+                            arguments: "{}".to_string(),
+                        },
+                        extra_content: None,
+                    })
+                    .collect(),
+            ),
+            ..message(MessageType::AIMessage, "")
+        }
+    }
+
+    fn tool_result_message(id: &str) -> Message {
+        Message {
+            tool_call_id: Some(id.to_string()),
+            ..message(MessageType::ToolResult, "sunny")
+        }
+    }
+
+    fn types(messages: &[Message]) -> Vec<MessageType> {
+        messages.iter().map(|m| m.r#type.clone()).collect()
+    }
+
+    #[test]
+    fn test_consecutive_user_messages_are_merged_for_strict_provider() {
+        let messages = vec![
+            message(MessageType::SystemMessage, "Be brief"),
+            message(MessageType::HumanMessage, "Hi"),
+            message(MessageType::HumanMessage, "What's the weather?"),
+            message(MessageType::AIMessage, "Sunny"),
+        ];
+
+        let (normalized, summary) = normalize_roles(messages, RolePolicy::Alternate);
+
+        assert_eq!(
+            types(&normalized),
+            vec![
+                MessageType::SystemMessage,
+                MessageType::HumanMessage,
+                MessageType::AIMessage
+            ]
+        );
+        assert_eq!(
+            normalized[1].content.as_deref(),
+            Some("Hi\n\nWhat's the weather?")
+        );
+        assert_eq!(
+            summary,
+            RoleNormalization {
+                policy: RolePolicy::Alternate,
+                merged: 1,
+                inserted: 0
+            }
+        );
+    }
+
+    #[test]
+    fn test_passthrough_leaves_history_untouched() {
+        let messages = vec![
+            message(MessageType::HumanMessage, "Hi"),
+            message(MessageType::HumanMessage, "Hello?"),
+        ];
+
+        let (normalized, summary) = normalize_roles(messages, RolePolicy::Passthrough);
+
+        assert_eq!(normalized.len(), 2);
+        assert!(!summary.is_altered());
+    }
+
+    #[test]
+    fn test_tool_results_stay_paired_with_tool_calls() {
+        let messages = vec![
+            message(MessageType::HumanMessage, "Weather in Paris and Rome?"),
+            tool_call_message(&["call_1", "call_2"]),
+            tool_result_message("call_1"),
+            tool_result_message("call_2"),
+            message(MessageType::HumanMessage, "Thanks"),
+        ];
+
+        let (normalized, summary) = normalize_roles(messages, RolePolicy::Alternate);
+
+        assert_eq!(
+            types(&normalized),
+            vec![
+                MessageType::HumanMessage,
+                MessageType::AIMessage,
+                MessageType::ToolResult,
+                MessageType::ToolResult,
+                MessageType::AIMessage,
+                MessageType::HumanMessage
+            ]
+        );
+        assert_eq!(
+            normalized[4].content.as_deref(),
+            Some(PLACEHOLDER_ASSISTANT_CONTENT)
+        );
+        assert_eq!(summary.merged, 0);
+        assert_eq!(summary.inserted, 1);
+    }
+
+    #[test]
+    fn test_leading_assistant_gets_placeholder_user_turn() {
+        let messages = vec![
+            message(MessageType::AIMessage, "How can I help?"),
+            message(MessageType::HumanMessage, "Hi"),
+        ];
+
+        let (normalized, summary) = normalize_roles(messages, RolePolicy::Alternate);
+
+        assert_eq!(
+            types(&normalized),
+            vec![
+                MessageType::HumanMessage,
+                MessageType::AIMessage,
+                MessageType::HumanMessage
+            ]
+        );
+        assert_eq!(summary.inserted, 1);
+    }
+}
@@ -0,0 +1,68 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+/// Identifies a cached tool result: the cache scope (usually the trace of the run),
+/// the tool name and the sha256 of the canonicalized arguments.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ToolCacheKey {
+    pub scope: String,
+    pub tool_name: String,
+    pub args_sha256: String,
+}
+
+impl ToolCacheKey {
+    pub fn new(scope: impl Into<String>, tool_name: &str, args: &HashMap<String, Value>) -> Self {
+        Self {
+            scope: scope.into(),
+            tool_name: tool_name.to_string(),
+            args_sha256: args_sha256(args),
+        }
+    }
+}
+
+/// Hashes tool arguments with keys sorted so that argument order does not matter.
+pub fn args_sha256(args: &HashMap<String, Value>) -> String {
+    let sorted: BTreeMap<&String, &Value> = args.iter().collect();
+    let serialized = serde_json::to_string(&sorted).unwrap_or_default();
+    format!("{:x}", Sha256::digest(serialized.as_bytes()))
+}
+
+/// In-memory cache of deterministic tool results.
+///
+/// Only tools that opt in via [`crate::types::tools::Tool::cache_ttl`] are cached.
+#[derive(Default)]
+pub struct ToolResultCache {
+    entries: Mutex<HashMap<ToolCacheKey, (Instant, Value)>>,
+}
+
+impl ToolResultCache {
+    pub fn get(&self, key: &ToolCacheKey) -> Option<Value> {
+        let mut entries = self.entries.lock().ok()?;
+        match entries.get(key) {
+            Some((expires_at, value)) if *expires_at > Instant::now() => Some(value.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, key: ToolCacheKey, value: Value, ttl: Duration) {
+        if let Ok(mut entries) = self.entries.lock() {
+            let now = Instant::now();
+            entries.retain(|_, (expires_at, _)| *expires_at > now);
+            entries.insert(key, (now + ttl, value));
+        }
+    }
+}
+
+/// Process-wide tool result cache shared by all providers.
+pub fn tool_result_cache() -> &'static ToolResultCache {
+    static CACHE: OnceLock<ToolResultCache> = OnceLock::new();
+    CACHE.get_or_init(ToolResultCache::default)
+}
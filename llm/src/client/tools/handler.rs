@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 
+use crate::client::tools::cache::{tool_result_cache, ToolCacheKey};
+use crate::client::tools::validation::{validate_arguments, InvalidArgumentsResult};
 use crate::error::LLMError;
 use crate::error::LLMResult;
 use vllora_telemetry::events::{JsonValue, RecordResult};
@@ -8,7 +10,8 @@ use vllora_telemetry::events::{JsonValue, RecordResult};
 use crate::types::tools::Tool;
 use crate::types::{ModelEvent, ModelEventType, ModelToolCall, ToolResultEvent, ToolStartEvent};
 use opentelemetry::propagation::Injector;
-use serde_json::Value;
+use opentelemetry::trace::TraceContextExt;
+use serde::Serialize;
 use tracing::Span;
 use tracing_opentelemetry::OpenTelemetrySpanExt;
 
@@ -37,6 +40,52 @@ impl Injector for LlmToolCallCarrier<'_> {
     }
 }
 
+/// Characters of the arguments kept in an error payload
+const MAX_PAYLOAD_ARGUMENTS_CHARS: usize = 1000;
+
+/// Recorded as `error_payload` on the tools span when a tool fails
+#[derive(Debug, Serialize)]
+struct ToolErrorPayload<'a> {
+    tool_name: &'a str,
+    error_type: &'static str,
+    message: String,
+    arguments: String,
+}
+
+impl<'a> ToolErrorPayload<'a> {
+    fn new(
+        tool_name: &'a str,
+        error_type: &'static str,
+        error: &LLMError,
+        arguments: &str,
+    ) -> Self {
+        Self {
+            tool_name,
+            error_type,
+            message: error.to_string(),
+            arguments: arguments
+                .chars()
+                .take(MAX_PAYLOAD_ARGUMENTS_CHARS)
+                .collect(),
+        }
+    }
+
+    fn record(&self) {
+        if let Ok(payload) = serde_json::to_string(self) {
+            Span::current().record("error_payload", payload);
+        }
+    }
+}
+
+fn error_type(error: &LLMError) -> &'static str {
+    match error {
+        LLMError::ParseError(_) => "parse_error",
+        LLMError::ReqwestError(_) => "http_error",
+        LLMError::McpServerError(_) => "mcp_error",
+        _ => "execution_error",
+    }
+}
+
 pub async fn handle_tool_call(
     tool_use: &ModelToolCall,
     tools: &HashMap<String, Arc<Box<dyn Tool>>>,
@@ -45,7 +94,6 @@ pub async fn handle_tool_call(
 ) -> LLMResult<String> {
     let tool_name = tool_use.tool_name.clone();
     let arguments = tool_use.input.clone();
-    let arguments_value = serde_json::from_str::<HashMap<String, Value>>(&arguments)?;
     // let span = tracing::info_span!(
     //     target: target!("tool"),
     //     crate::events::SPAN_TOOL,
@@ -54,9 +102,12 @@ pub async fn handle_tool_call(
     //     output = tracing::field::Empty,
     //     error = tracing::field::Empty,
     // );
-    let tool = tools
-        .get(&tool_name)
-        .ok_or(LLMError::CustomError(format!("Tool Not Found {tool_name}")))?;
+    let Some(tool) = tools.get(&tool_name) else {
+        let error = LLMError::CustomError(format!("Tool Not Found {tool_name}"));
+        ToolErrorPayload::new(&tool_name, "tool_not_found", &error, &arguments).record();
+        return Err(error);
+    };
+    let validated_arguments = validate_arguments(tool.as_ref().as_ref(), &arguments);
 
     async {
         tx.send(Some(ModelEvent::new(
@@ -69,18 +120,71 @@ pub async fn handle_tool_call(
         )))
         .await
         .map_err(|e| LLMError::CustomError(e.to_string()))?;
+
+        let arguments_value = match validated_arguments {
+            Ok(arguments_value) => arguments_value,
+            Err(errors) => {
+                let output =
+                    serde_json::to_string(&InvalidArgumentsResult::new(&tool_name, &errors))?;
+                Span::current().record("validation_errors", serde_json::to_string(&errors)?);
+                tx.send(Some(ModelEvent::new(
+                    &Span::current(),
+                    ModelEventType::ToolResult(ToolResultEvent {
+                        tool_id: tool_use.tool_id.clone(),
+                        tool_name,
+                        is_error: true,
+                        output: output.clone(),
+                    }),
+                )))
+                .await
+                .map_err(|e| LLMError::CustomError(e.to_string()))?;
+                // Handed back as the tool result so the model can correct its call
+                return Ok(output);
+            }
+        };
+
         let span_context = Span::current().context();
         opentelemetry::global::get_text_map_propagator(|propagator| {
             propagator.inject_context(&span_context, &mut LlmToolCallCarrier::new(&mut tags))
         });
 
-        let result = tool.run(arguments_value, tags).await;
+        // Cacheable tools are scoped to the trace so results never leak across runs. Calls
+        // outside a trace would all share the invalid trace id, so they are not cached.
+        let trace_scope = {
+            let span = span_context.span();
+            let trace = span.span_context();
+            trace.is_valid().then(|| trace.trace_id().to_string())
+        };
+        let cache_entry = tool
+            .cache_ttl()
+            .zip(trace_scope)
+            .map(|(ttl, scope)| (ToolCacheKey::new(scope, &tool_name, &arguments_value), ttl));
+        let cached = cache_entry
+            .as_ref()
+            .and_then(|(key, _)| tool_result_cache().get(key));
+
+        let result = match cached {
+            Some(value) => {
+                Span::current().record("cache", "HIT");
+                Ok(value)
+            }
+            None => {
+                let result = tool.run(arguments_value, tags).await;
+                if let (Ok(value), Some((key, ttl))) = (&result, cache_entry) {
+                    tool_result_cache().insert(key, value.clone(), ttl);
+                }
+                result
+            }
+        };
         let _ = result.as_ref().map(JsonValue).record();
+        if let Err(error) = &result {
+            ToolErrorPayload::new(&tool_name, error_type(error), error, &tool_use.input).record();
+        }
         let result = result.map(|v| v.to_string());
         tx.send(Some(ModelEvent::new(
             &Span::current(),
             ModelEventType::ToolResult(ToolResultEvent {
-                tool_id: tool_name.clone(),
+                tool_id: tool_use.tool_id.clone(),
                 tool_name,
                 is_error: result.is_err(),
                 output: result
@@ -96,3 +200,282 @@ pub async fn handle_tool_call(
     // .instrument(span.or_current())
     .await
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::gateway::FunctionParameters;
+    use serde_json::Value;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use vllora_telemetry::test_utils::RecordingLayer;
+
+    struct CountingTool {
+        calls: Arc<AtomicUsize>,
+        cacheable: bool,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for CountingTool {
+        fn name(&self) -> String {
+            "counting_tool".to_string()
+        }
+
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn get_function_parameters(&self) -> Option<FunctionParameters> {
+            None
+        }
+
+        async fn run(
+            &self,
+            input: HashMap<String, Value>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(serde_json::json!({ "echo": input }))
+        }
+
+        fn cache_ttl(&self) -> Option<Duration> {
+            self.cacheable.then_some(Duration::from_secs(60))
+        }
+    }
+
+    /// Runs `f` in a span continuing a new remote trace
+    async fn in_trace<F: std::future::Future>(f: F) -> F::Output {
+        use opentelemetry::trace::{SpanContext, SpanId, TraceFlags, TraceId, TraceState};
+        use tracing::Instrument;
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let subscriber = tracing_subscriber::registry().with(
+            tracing_opentelemetry::layer()
+                .with_tracer(opentelemetry::trace::noop::NoopTracer::new()),
+        );
+        let _guard = tracing::subscriber::set_default(subscriber);
+        let remote = SpanContext::new(
+            TraceId::from_bytes(rand::random::<u128>().to_be_bytes()),
+            SpanId::from_bytes(rand::random::<u64>().to_be_bytes()),
+            TraceFlags::SAMPLED,
+            true,
+            TraceState::default(),
+        );
+        let span = tracing::info_span!("run");
+        let _ = span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote));
+        f.instrument(span).await
+    }
+
+    async fn call_twice(cacheable: bool) -> usize {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tool: Arc<Box<dyn Tool>> = Arc::new(Box::new(CountingTool {
+            calls: calls.clone(),
+            cacheable,
+        }));
+        let tools = HashMap::from([("counting_tool".to_string(), tool)]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        tokio::spawn(async move { while rx.recv().await.is_some() {} });
+
+        let tool_call = ModelToolCall {
+            tool_id: "call_1".to_string(),
+            tool_name: "counting_tool".to_string(),
+            input: format!(r#"{{"a": 1, "b": "{cacheable}"}}"#),
+            extra_content: None,
+        };
+
+        let first = handle_tool_call(&tool_call, &tools, &tx, HashMap::new())
+            .await
+            .unwrap();
+        let second = handle_tool_call(&tool_call, &tools, &tx, HashMap::new())
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+
+        calls.load(Ordering::SeqCst)
+    }
+
+    #[tokio::test]
+    async fn test_cacheable_tool_runs_once_for_identical_calls() {
+        assert_eq!(in_trace(call_twice(true)).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_non_cacheable_tool_runs_every_time() {
+        assert_eq!(in_trace(call_twice(false)).await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_calls_outside_a_trace_are_not_cached() {
+        assert_eq!(call_twice(true).await, 2);
+    }
+
+    struct WeatherTool {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for WeatherTool {
+        fn name(&self) -> String {
+            "get_weather".to_string()
+        }
+
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn get_function_parameters(&self) -> Option<FunctionParameters> {
+            None
+        }
+
+        fn parameters_schema(&self) -> Option<Value> {
+            Some(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "city": { "type": "string" },
+                    "days": { "type": "integer" }
+                },
+                "required": ["city"]
+            }))
+        }
+
+        fn strict(&self) -> bool {
+            true
+        }
+
+        async fn run(
+            &self,
+            _input: HashMap<String, Value>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(serde_json::json!({ "forecast": "sunny" }))
+        }
+    }
+
+    async fn call_weather(input: &str) -> (String, Vec<ModelEvent>, usize) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let tool: Arc<Box<dyn Tool>> = Arc::new(Box::new(WeatherTool {
+            calls: calls.clone(),
+        }));
+        let tools = HashMap::from([("get_weather".to_string(), tool)]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+
+        let tool_call = ModelToolCall {
+            tool_id: "call_1".to_string(),
+            tool_name: "get_weather".to_string(),
+            input: input.to_string(),
+            extra_content: None,
+        };
+        let output = handle_tool_call(&tool_call, &tools, &tx, HashMap::new())
+            .await
+            .unwrap();
+        drop(tx);
+
+        let mut events = vec![];
+        while let Some(Some(event)) = rx.recv().await {
+            events.push(event);
+        }
+        (output, events, calls.load(Ordering::SeqCst))
+    }
+
+    #[tokio::test]
+    async fn test_malformed_arguments_return_structured_error() {
+        let (output, events, calls) = call_weather(r#"{"city": 42, "unit": "celsius"}"#).await;
+        assert_eq!(calls, 0);
+
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["error"], "invalid_arguments");
+        assert_eq!(output["tool_name"], "get_weather");
+        let paths: Vec<&str> = output["errors"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["path"].as_str().unwrap())
+            .collect();
+        assert!(paths.contains(&"/city"));
+        assert!(paths.contains(&"/unit"));
+
+        assert!(events.iter().any(|e| matches!(
+            &e.event,
+            ModelEventType::ToolResult(ToolResultEvent { is_error: true, .. })
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_unparseable_arguments_return_structured_error() {
+        let (output, _, calls) = call_weather(r#"{"city": "Paris""#).await;
+        assert_eq!(calls, 0);
+
+        let output: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(output["error"], "invalid_arguments");
+        assert_eq!(output["errors"][0]["path"], "");
+    }
+
+    #[tokio::test]
+    async fn test_valid_arguments_run_tool() {
+        let (output, _, calls) = call_weather(r#"{"city": "Paris", "days": 3}"#).await;
+        assert_eq!(calls, 1);
+        assert_eq!(output, r#"{"forecast":"sunny"}"#);
+    }
+
+    struct FailingTool;
+
+    #[async_trait::async_trait]
+    impl Tool for FailingTool {
+        fn name(&self) -> String {
+            "search".to_string()
+        }
+
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn get_function_parameters(&self) -> Option<FunctionParameters> {
+            None
+        }
+
+        async fn run(
+            &self,
+            _input: HashMap<String, Value>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<Value> {
+            Err(LLMError::CustomError("index unavailable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failing_tool_records_structured_error_payload() {
+        use tracing::Instrument;
+
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let tool: Arc<Box<dyn Tool>> = Arc::new(Box::new(FailingTool));
+        let tools = HashMap::from([("search".to_string(), tool)]);
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        tokio::spawn(async move { while rx.recv().await.is_some() {} });
+
+        let tool_call = ModelToolCall {
+            tool_id: "call_1".to_string(),
+            tool_name: "search".to_string(),
+            input: format!(r#"{{"query": "{}"}}"#, "a".repeat(2000)),
+            extra_content: None,
+        };
+        let span = tracing::info_span!("tools", error_payload = tracing::field::Empty);
+        let result = handle_tool_call(&tool_call, &tools, &tx, HashMap::new())
+            .instrument(span)
+            .await;
+        assert!(result.is_err());
+
+        let recorded = recorded.values("error_payload");
+        assert_eq!(recorded.len(), 1);
+        let payload: Value = serde_json::from_str(&recorded[0]).unwrap();
+        assert_eq!(payload["tool_name"], "search");
+        assert_eq!(payload["error_type"], "execution_error");
+        assert_eq!(payload["message"], "Custom Error: index unavailable");
+        assert_eq!(
+            payload["arguments"].as_str().unwrap().len(),
+            MAX_PAYLOAD_ARGUMENTS_CHARS
+        );
+    }
+}
@@ -1 +1,4 @@
+pub mod cache;
 pub mod handler;
+pub mod partial;
+pub mod validation;
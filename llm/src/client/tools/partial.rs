@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+
+use serde_json::Value;
+
+use crate::types::gateway::ChatCompletionChunk;
+
+/// Best-effort parse of a JSON document cut off mid-stream. Open strings, arrays and
+/// objects are closed, and a trailing incomplete token (a partial key, literal or a
+/// dangling `,` / `:`) is dropped. Returns `None` until the input has a parsable prefix.
+pub fn parse_partial_json(input: &str) -> Option<Value> {
+    if let Ok(value) = serde_json::from_str(input) {
+        return Some(value);
+    }
+
+    let mut end = input.len();
+    loop {
+        let prefix = &input[..end];
+        if let Some(value) = complete(prefix).and_then(|c| serde_json::from_str(&c).ok()) {
+            return Some(value);
+        }
+        end = prefix.char_indices().last()?.0;
+    }
+}
+
+/// Closes whatever is still open at the end of `prefix`
+fn complete(prefix: &str) -> Option<String> {
+    let mut closers = vec![];
+    let mut in_string = false;
+    // Start of an escape sequence that is not complete yet
+    let mut escape_start: Option<usize> = None;
+
+    let mut chars = prefix.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if in_string {
+            match c {
+                '\\' => {
+                    escape_start = Some(i);
+                    match chars.next() {
+                        Some((_, 'u')) => {
+                            let mut hex = 0;
+                            while hex < 4 && chars.next_if(|(_, c)| c.is_ascii_hexdigit()).is_some()
+                            {
+                                hex += 1;
+                            }
+                            if hex == 4 {
+                                escape_start = None;
+                            }
+                        }
+                        Some(_) => escape_start = None,
+                        None => {}
+                    }
+                }
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else {
+            match c {
+                '"' => in_string = true,
+                '{' => closers.push('}'),
+                '[' => closers.push(']'),
+                '}' | ']' => {
+                    if closers.pop() != Some(c) {
+                        return None;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let mut completed = match (in_string, escape_start) {
+        (true, Some(start)) => prefix[..start].to_string(),
+        _ => prefix.to_string(),
+    };
+    if in_string {
+        completed.push('"');
+    } else {
+        let trimmed = completed.trim_end();
+        completed = trimmed.strip_suffix(',').unwrap_or(trimmed).to_string();
+    }
+    completed.extend(closers.iter().rev());
+    Some(completed)
+}
+
+/// Accumulates streamed tool call arguments per tool call index, exposing the partially
+/// formed arguments after every fragment so they can be rendered while they stream
+#[derive(Debug, Clone, Default)]
+pub struct ToolArgumentsAccumulator {
+    arguments: BTreeMap<usize, String>,
+}
+
+impl ToolArgumentsAccumulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a fragment to the arguments of tool call `index` and returns their best-effort
+    /// parse so far
+    pub fn push(&mut self, index: usize, fragment: &str) -> Option<Value> {
+        let arguments = self.arguments.entry(index).or_default();
+        arguments.push_str(fragment);
+        parse_partial_json(arguments)
+    }
+
+    /// Appends the tool call fragments of a streamed chunk. Returns the updated partial
+    /// arguments of every tool call the chunk touched.
+    pub fn push_chunk(&mut self, chunk: &ChatCompletionChunk) -> Vec<(usize, Option<Value>)> {
+        chunk
+            .choices
+            .iter()
+            .filter_map(|choice| choice.delta.tool_calls.as_ref())
+            .flatten()
+            .enumerate()
+            .map(|(position, tool_call)| {
+                let index = tool_call.index.unwrap_or(position);
+                (index, self.push(index, &tool_call.function.arguments))
+            })
+            .collect()
+    }
+
+    /// Raw arguments received so far for tool call `index`
+    pub fn arguments(&self, index: usize) -> Option<&str> {
+        self.arguments.get(&index).map(String::as_str)
+    }
+
+    /// Best-effort parse of the arguments received so far for tool call `index`
+    pub fn partial(&self, index: usize) -> Option<Value> {
+        self.arguments(index).and_then(parse_partial_json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_fragments_produce_progressively_richer_arguments() {
+        let fragments = [
+            (r#"{"loc"#, json!({})),
+            (r#"ation": "San"#, json!({"location": "San"})),
+            (r#" Fran"#, json!({"location": "San Fran"})),
+            (r#"cisco", "unit"#, json!({"location": "San Francisco"})),
+            (
+                r#"s": ["c"#,
+                json!({"location": "San Francisco", "units": ["c"]}),
+            ),
+            (
+                r#"", "f"], "days": 1"#,
+                json!({"location": "San Francisco", "units": ["c", "f"], "days": 1}),
+            ),
+            (
+                r#"0, "detailed": tr"#,
+                json!({"location": "San Francisco", "units": ["c", "f"], "days": 10}),
+            ),
+            (
+                r#"ue}"#,
+                json!({"location": "San Francisco", "units": ["c", "f"], "days": 10, "detailed": true}),
+            ),
+        ];
+
+        let mut accumulator = ToolArgumentsAccumulator::new();
+        for (fragment, expected) in fragments {
+            assert_eq!(accumulator.push(0, fragment), Some(expected), "{fragment}");
+        }
+        assert!(serde_json::from_str::<Value>(accumulator.arguments(0).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_partial_json_edge_cases() {
+        assert_eq!(parse_partial_json(""), None);
+        assert_eq!(parse_partial_json("  "), None);
+        assert_eq!(parse_partial_json("["), Some(json!([])));
+        assert_eq!(
+            parse_partial_json(r#"{"a": {"b": [1, 2"#),
+            Some(json!({"a": {"b": [1, 2]}}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"path": "C:\\dir\"#),
+            Some(json!({"path": "C:\\dir"}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"emoji": "\u26"#),
+            Some(json!({"emoji": ""}))
+        );
+        assert_eq!(
+            parse_partial_json(r#"{"text": "a \"quoted"#),
+            Some(json!({"text": "a \"quoted"}))
+        );
+    }
+
+    #[test]
+    fn test_chunks_are_tracked_per_tool_call() {
+        let chunk = |index: usize, arguments: &str| -> ChatCompletionChunk {
+            serde_json::from_value(json!({
+                "id": "chunk",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": "gpt-4o-mini",
+                "choices": [{
+                    "index": 0,
+                    "delta": {"tool_calls": [{
+                        "index": index,
+                        "id": "",
+                        "type": "function",
+                        "function": {"name": "", "arguments": arguments}
+                    }]},
+                    "finish_reason": null,
+                    "logprobs": null
+                }]
+            }))
+            .unwrap()
+        };
+
+        let mut accumulator = ToolArgumentsAccumulator::new();
+        accumulator.push_chunk(&chunk(0, r#"{"city": "Par"#));
+        let updated = accumulator.push_chunk(&chunk(1, r#"{"query": "wea"#));
+        assert_eq!(updated, vec![(1, Some(json!({"query": "wea"})))]);
+
+        accumulator.push_chunk(&chunk(0, r#"is"}"#));
+        assert_eq!(accumulator.partial(0), Some(json!({"city": "Paris"})));
+        assert_eq!(accumulator.arguments(1), Some(r#"{"query": "wea"#));
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::types::gateway::FunctionParameters;
+use crate::types::tools::Tool;
+
+/// A tool call argument that does not conform to the tool schema
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArgumentError {
+    /// JSON pointer to the offending value, empty for the arguments object itself
+    pub path: String,
+    pub message: String,
+}
+
+/// Tool result handed back to the model in place of running the tool, so it can retry
+/// the call with corrected arguments
+#[derive(Debug, Clone, Serialize)]
+pub struct InvalidArgumentsResult<'a> {
+    pub error: &'static str,
+    pub tool_name: &'a str,
+    pub errors: &'a [ArgumentError],
+}
+
+impl<'a> InvalidArgumentsResult<'a> {
+    pub fn new(tool_name: &'a str, errors: &'a [ArgumentError]) -> Self {
+        Self {
+            error: "invalid_arguments",
+            tool_name,
+            errors,
+        }
+    }
+}
+
+/// A tool whose parameters schema would be rejected by providers
+#[derive(Debug, Clone, PartialEq, Error)]
+#[error("Tool {tool_name} has an invalid parameters schema: {message}")]
+pub struct InvalidToolDefinition {
+    pub tool_name: String,
+    pub message: String,
+}
+
+/// Checks a tool's parameters schema when the tool is registered, before any request
+/// sends it to a provider.
+///
+/// The schema has to be valid JSON Schema describing an object, every `required`
+/// property has to be declared, and it has to convert to [`FunctionParameters`] the
+/// way provider request mapping does.
+pub fn validate_definition(tool: &dyn Tool) -> Result<(), InvalidToolDefinition> {
+    let Some(schema) = tool.parameters_schema() else {
+        return Ok(());
+    };
+    let invalid = |message: String| InvalidToolDefinition {
+        tool_name: tool.name(),
+        message,
+    };
+
+    jsonschema::validator_for(&schema).map_err(|e| invalid(e.to_string()))?;
+    let parameters =
+        serde_json::from_value::<FunctionParameters>(schema).map_err(|e| invalid(e.to_string()))?;
+
+    if parameters.r#type != "object" {
+        return Err(invalid(format!(
+            "type must be object, found {}",
+            parameters.r#type
+        )));
+    }
+    if let Some(missing) = parameters
+        .required
+        .iter()
+        .flatten()
+        .find(|name| !parameters.properties.contains_key(*name))
+    {
+        return Err(invalid(format!(
+            "required property {missing} is not declared in properties"
+        )));
+    }
+
+    Ok(())
+}
+
+/// Parses the model-produced arguments and validates them against the tool schema.
+///
+/// Tools whose schema cannot be compiled are not validated, they only get the
+/// arguments parsed.
+pub fn validate_arguments(
+    tool: &dyn Tool,
+    arguments: &str,
+) -> Result<HashMap<String, Value>, Vec<ArgumentError>> {
+    let value = serde_json::from_str::<Value>(arguments).map_err(|e| {
+        vec![ArgumentError {
+            path: String::new(),
+            message: format!("Arguments are not valid JSON: {e}"),
+        }]
+    })?;
+
+    let mut errors = vec![];
+    if let Some(schema) = tool.parameters_schema() {
+        match jsonschema::validator_for(&schema) {
+            Ok(validator) => {
+                errors.extend(validator.iter_errors(&value).map(|e| ArgumentError {
+                    path: e.instance_path.to_string(),
+                    message: e.to_string(),
+                }));
+            }
+            Err(e) => {
+                tracing::warn!("Skipping argument validation for {}: {e}", tool.name());
+            }
+        }
+
+        if let (true, Some(object)) = (tool.strict(), value.as_object()) {
+            let declared = schema.get("properties").and_then(Value::as_object);
+            errors.extend(
+                object
+                    .keys()
+                    .filter(|key| !declared.is_some_and(|properties| properties.contains_key(*key)))
+                    .map(|key| ArgumentError {
+                        path: format!("/{key}"),
+                        message: format!("Additional property {key} is not allowed"),
+                    }),
+            );
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    match value {
+        Value::Object(object) => Ok(object.into_iter().collect()),
+        _ => Err(vec![ArgumentError {
+            path: String::new(),
+            message: "Arguments must be a JSON object".to_string(),
+        }]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::LLMResult;
+
+    struct SchemaTool(Value);
+
+    #[async_trait::async_trait]
+    impl Tool for SchemaTool {
+        fn name(&self) -> String {
+            "lookup".to_string()
+        }
+
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn get_function_parameters(&self) -> Option<FunctionParameters> {
+            serde_json::from_value(self.0.clone()).ok()
+        }
+
+        fn parameters_schema(&self) -> Option<Value> {
+            Some(self.0.clone())
+        }
+
+        async fn run(
+            &self,
+            _input: HashMap<String, Value>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<Value> {
+            Ok(Value::Null)
+        }
+    }
+
+    fn validate(schema: Value) -> Result<(), InvalidToolDefinition> {
+        validate_definition(&SchemaTool(schema))
+    }
+
+    #[test]
+    fn test_valid_definitions() {
+        assert_eq!(
+            validate(serde_json::json!({
+                "type": "object",
+                "properties": {"query": {"type": "string"}},
+                "required": ["query"]
+            })),
+            Ok(())
+        );
+        assert_eq!(validate(serde_json::json!({"type": "object"})), Ok(()));
+    }
+
+    #[test]
+    fn test_invalid_definitions_name_the_tool() {
+        for schema in [
+            // not valid JSON Schema
+            serde_json::json!({"type": "object", "properties": {"query": {"type": 5}}}),
+            // missing type, providers can't map it
+            serde_json::json!({"properties": {}}),
+            serde_json::json!({"type": "string", "properties": {}}),
+            serde_json::json!({"type": "object", "properties": {}, "required": ["query"]}),
+        ] {
+            let error = validate(schema.clone()).unwrap_err();
+            assert_eq!(error.tool_name, "lookup", "{schema}");
+            assert!(error.to_string().starts_with("Tool lookup"));
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::client::error::ModelError;
+use crate::client::error::{classify_reqwest_error, ModelError, ProviderErrorClass};
 use crate::client::message_mapper::MessageMapperError;
 use crate::{mcp::McpServerError, types::ModelEvent};
 use thiserror::Error;
@@ -35,6 +35,12 @@ pub enum LLMError {
     UnsupportedProvider(String),
     #[error("Model stopped with error: {0}")]
     FinishError(ModelFinishError),
+    #[error("Model output is invalid after {attempts} repair attempts: {message}")]
+    InvalidJsonOutput { attempts: usize, message: String },
+    #[error("No first token received within {0:?}")]
+    FirstTokenTimeout(std::time::Duration),
+    #[error("Model kept calling tools after {0} tool iterations")]
+    MaxToolIterations(u32),
     #[error(transparent)]
     ModelError(#[from] Box<ModelError>),
     #[error(transparent)]
@@ -80,6 +86,20 @@ pub enum ModelFinishError {
     Custom(String),
 }
 
+impl LLMError {
+    /// Classifies provider failures; returns `None` for gateway side errors
+    pub fn classify(&self) -> Option<ProviderErrorClass> {
+        match self {
+            LLMError::ModelError(e) => e.classify(),
+            LLMError::ReqwestError(e) => classify_reqwest_error(e),
+            LLMError::FirstTokenTimeout(_) => {
+                Some(ProviderErrorClass::ProviderUnavailable { retry_after: None })
+            }
+            _ => None,
+        }
+    }
+}
+
 impl From<ModelError> for LLMError {
     fn from(value: ModelError) -> Self {
         LLMError::ModelError(Box::new(value))
@@ -1,41 +1,49 @@
-use crate::client::completions::response_stream::ResultStream;
+pub mod batch;
+
+use crate::client::completions::response_stream::{stream_until_closed, ResultStream};
+use crate::client::documents::{Document, DocumentError};
 use crate::client::error::AnthropicError;
 use crate::client::error::AuthorizationError;
 use crate::client::error::ModelError;
+use crate::client::role_normalization::{normalize_for_provider, RolePolicy};
 use crate::client::tools::handler::handle_tool_call;
 use crate::client::DEFAULT_MAX_RETRIES;
 use crate::error::{LLMError, LLMResult, ModelFinishError};
+use crate::provider::retry::retry_policy;
+use crate::provider::ToolIterations;
+use crate::provider::{forwarded_headers, shared_http_client};
 use crate::types::credentials::ApiKeyCredentials;
 use crate::types::credentials_ident::CredentialsIdent;
 use crate::types::engine::{render, AnthropicModelParams, ExecutionOptions};
 use crate::types::gateway::ChatCompletionChunk;
 use crate::types::gateway::ChatCompletionChunkChoice;
 use crate::types::gateway::ChatCompletionDelta;
+use crate::types::gateway::GatewayModelUsage;
 use crate::types::gateway::{
     ChatCompletionContent, ChatCompletionMessage, ChatCompletionMessageWithFinishReason,
     FunctionCall, ToolCall,
 };
-use crate::types::gateway::{GatewayModelUsage, PromptTokensDetails};
 use crate::types::instance::ModelInstance;
 use crate::types::message::InnerMessage;
 use crate::types::message::Message;
 use crate::types::message::{MessageContentType, MessageType};
 use crate::types::tools::Tool;
 use crate::types::{
-    LLMContentEvent, LLMFinishEvent, LLMFirstToken, LLMStartEvent, ModelEvent, ModelEventType,
-    ModelFinishReason, ModelToolCall, ToolStartEvent,
+    LLMContentEvent, LLMFinishEvent, LLMFirstToken, LLMReasoningEvent, LLMStartEvent, ModelEvent,
+    ModelEventType, ModelFinishReason, ModelToolCall, ToolStartEvent,
 };
 use async_trait::async_trait;
 use clust::messages::MessagesResponseBody;
 use clust::messages::{
     Content, ContentBlock, ImageContentBlock, ImageContentSource, Message as ClustMessage,
-    MessageChunk, MessagesRequestBody, MessagesRequestBuilder, StopReason, StreamError,
-    StreamOption, SystemPrompt, TextContentBlock, ToolDefinition, ToolResult,
-    ToolResultContentBlock, ToolUse, ToolUseContentBlock, Usage,
+    MessageChunk, MessagesRequestBody, MessagesRequestBuilder, StopReason, StreamOption,
+    SystemPrompt, TextContentBlock, ToolDefinition, ToolResult, ToolResultContentBlock, ToolUse,
+    ToolUseContentBlock, Usage,
 };
-use clust::Client;
+use eventsource_stream::Eventsource;
 use futures::Stream;
 use futures::StreamExt;
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::ops::Deref;
@@ -65,16 +73,88 @@ fn custom_err(e: impl ToString) -> ModelError {
     ModelError::CustomError(e.to_string())
 }
 
-pub fn anthropic_client(
-    credentials: Option<&ApiKeyCredentials>,
-) -> Result<clust::Client, ModelError> {
-    let api_key = if let Some(credentials) = credentials {
+fn anthropic_api_key(credentials: Option<&ApiKeyCredentials>) -> Result<String, ModelError> {
+    Ok(if let Some(credentials) = credentials {
         credentials.api_key.clone()
     } else {
         std::env::var("VLLORA_ANTHROPIC_API_KEY").map_err(|_| AuthorizationError::InvalidApiKey)?
-    };
-    let client = Client::from_api_key(clust::ApiKey::new(api_key));
-    Ok(client)
+    })
+}
+
+const API_URL: &str = "https://api.anthropic.com/v1/messages";
+pub(crate) const ANTHROPIC_VERSION: &str = "2023-06-01";
+
+/// Error returned by the messages API, `{"type": "error", "error": {...}}`
+#[derive(Deserialize)]
+struct ApiErrorResponse {
+    error: ApiErrorBody,
+}
+
+#[derive(Deserialize)]
+struct ApiErrorBody {
+    #[serde(rename = "type")]
+    r#type: String,
+    message: String,
+}
+
+fn api_error(status: u16, body: &str) -> AnthropicError {
+    match serde_json::from_str::<ApiErrorResponse>(body) {
+        Ok(response) => AnthropicError::ApiError {
+            status,
+            r#type: response.error.r#type,
+            message: response.error.message,
+        },
+        Err(_) => AnthropicError::ApiError {
+            status,
+            r#type: String::new(),
+            message: body.to_string(),
+        },
+    }
+}
+
+/// `%PDF-`, the signature every PDF file starts with, base64 encoded
+const PDF_BASE64_SIGNATURE: &str = "JVBERi0";
+
+/// The Anthropic client has no document block, PDF file parts are mapped to base64 image
+/// blocks and turned into document blocks when the request body is serialized. They are
+/// told apart from images by their signature.
+fn request_body(request: &MessagesRequestBody) -> Result<Value, AnthropicError> {
+    let mut body =
+        serde_json::to_value(request).map_err(|e| AnthropicError::RequestError(e.to_string()))?;
+    let messages = body.get_mut("messages").and_then(Value::as_array_mut);
+    for message in messages.into_iter().flatten() {
+        let blocks = message.get_mut("content").and_then(Value::as_array_mut);
+        for block in blocks.into_iter().flatten() {
+            let is_pdf = block["type"] == "image"
+                && block["source"]["data"]
+                    .as_str()
+                    .is_some_and(|data| data.starts_with(PDF_BASE64_SIGNATURE));
+            if is_pdf {
+                block["type"] = "document".into();
+                block["source"]["media_type"] = "application/pdf".into();
+            }
+        }
+    }
+    Ok(body)
+}
+
+/// Parses one server-sent event of a streamed message
+fn parse_chunk(event: &str, data: &str) -> Result<MessageChunk, ModelError> {
+    Ok(match event {
+        "message_start" => MessageChunk::MessageStart(serde_json::from_str(data)?),
+        "content_block_start" => MessageChunk::ContentBlockStart(serde_json::from_str(data)?),
+        "ping" => MessageChunk::Ping(serde_json::from_str(data)?),
+        "content_block_delta" => MessageChunk::ContentBlockDelta(serde_json::from_str(data)?),
+        "content_block_stop" => MessageChunk::ContentBlockStop(serde_json::from_str(data)?),
+        "message_delta" => MessageChunk::MessageDelta(serde_json::from_str(data)?),
+        "message_stop" => MessageChunk::MessageStop(serde_json::from_str(data)?),
+        "error" => return Err(api_error(200, data).into()),
+        event => {
+            return Err(ModelError::StreamError(format!(
+                "Unknown message stream event: {event}"
+            )))
+        }
+    })
 }
 
 fn tool_definition(tool: &dyn Tool) -> clust::messages::ToolDefinition {
@@ -95,7 +175,11 @@ fn tool_definition(tool: &dyn Tool) -> clust::messages::ToolDefinition {
 pub struct AnthropicModel {
     params: AnthropicModelParams,
     execution_options: ExecutionOptions,
-    client: Client,
+    api_key: String,
+    /// Client shared by all models, see [`shared_http_client`]
+    http_client: reqwest::Client,
+    /// Headers configured in `execution_options`, e.g. `anthropic-beta`, set on every call
+    headers: reqwest::header::HeaderMap,
     tools: HashMap<String, Arc<Box<dyn Tool>>>,
     credentials_ident: CredentialsIdent,
     endpoint: Option<String>,
@@ -109,11 +193,14 @@ impl AnthropicModel {
         tools: HashMap<String, Arc<Box<dyn Tool>>>,
         endpoint: Option<String>,
     ) -> Result<Self, ModelError> {
-        let client: Client = anthropic_client(credentials)?;
+        let api_key = anthropic_api_key(credentials)?;
+        let headers = forwarded_headers(&execution_options)?;
         Ok(Self {
             params,
             execution_options,
-            client,
+            api_key,
+            http_client: shared_http_client(),
+            headers,
             tools,
             credentials_ident: credentials
                 .map(|_c| CredentialsIdent::Own)
@@ -122,6 +209,52 @@ impl AnthropicModel {
         })
     }
 
+    async fn send(
+        &self,
+        request: &MessagesRequestBody,
+    ) -> Result<reqwest::Response, AnthropicError> {
+        let response = self
+            .http_client
+            .post(self.endpoint.as_deref().unwrap_or(API_URL))
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .headers(self.headers.clone())
+            .json(&request_body(request)?)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await?;
+            return Err(api_error(status.as_u16(), &body));
+        }
+        Ok(response)
+    }
+
+    async fn create_message(
+        &self,
+        request: &MessagesRequestBody,
+    ) -> Result<MessagesResponseBody, ModelError> {
+        let body = self
+            .send(request)
+            .await?
+            .text()
+            .await
+            .map_err(AnthropicError::from)?;
+        Ok(serde_json::from_str(&body)?)
+    }
+
+    async fn create_message_stream(
+        &self,
+        request: &MessagesRequestBody,
+    ) -> Result<impl Stream<Item = Result<MessageChunk, ModelError>>, ModelError> {
+        let response = self.send(request).await?;
+        Ok(response.bytes_stream().eventsource().map(|event| {
+            let event = event.map_err(|e| ModelError::StreamError(e.to_string()))?;
+            parse_chunk(&event.event, &event.data)
+        }))
+    }
+
     async fn handle_tool_calls(
         function_calls: impl Iterator<Item = &ToolUse>,
         tools: &HashMap<String, Arc<Box<dyn Tool>>>,
@@ -262,7 +395,7 @@ impl AnthropicModel {
 
     async fn process_stream(
         &self,
-        stream: impl Stream<Item = Result<MessageChunk, StreamError>>,
+        stream: impl Stream<Item = Result<MessageChunk, ModelError>>,
         tx: &tokio::sync::mpsc::Sender<Option<ModelEvent>>,
         tx_response: &tokio::sync::mpsc::Sender<LLMResult<ChatCompletionChunk>>,
         started_at: std::time::Instant,
@@ -307,6 +440,7 @@ impl AnthropicModel {
                             .to_string(),
                         choices: vec![],
                         usage: None,
+                        tool_event: None,
                     };
 
                     // let _ = tx_response.send(Ok(Chunk::Anthropic(result.clone()))).await;
@@ -330,6 +464,7 @@ impl AnthropicModel {
                                         role: Some("assistant".to_string()),
                                         content: Some(block.text.clone()),
                                         tool_calls: None,
+                                        reasoning_content: None,
                                     },
                                     finish_reason: None,
                                     logprobs: None,
@@ -340,8 +475,8 @@ impl AnthropicModel {
                                 let _ = tx
                                     .send(Some(ModelEvent::new(
                                         &tracing::Span::current(),
-                                        ModelEventType::LlmContent(LLMContentEvent {
-                                            content: format!("thinking: {}", thinking.thinking),
+                                        ModelEventType::LlmReasoning(LLMReasoningEvent {
+                                            content: thinking.thinking.clone(),
                                         }),
                                     )))
                                     .await;
@@ -351,8 +486,9 @@ impl AnthropicModel {
                                     index: 0,
                                     delta: ChatCompletionDelta {
                                         role: Some("assistant".to_string()),
-                                        content: Some(format!("thinking: {}", thinking.thinking)),
+                                        content: None,
                                         tool_calls: None,
+                                        reasoning_content: Some(thinking.thinking.clone()),
                                     },
                                     finish_reason: None,
                                     logprobs: None,
@@ -385,6 +521,7 @@ impl AnthropicModel {
                                         role: Some("assistant".to_string()),
                                         content: Some(delta.text.clone()),
                                         tool_calls: None,
+                                        reasoning_content: None,
                                     },
                                     finish_reason: None,
                                     logprobs: None,
@@ -394,23 +531,24 @@ impl AnthropicModel {
                             clust::messages::ContentBlockDelta::ThinkingDeltaContentBlock(
                                 delta,
                             ) => {
+                                // Thinking is streamed apart and kept out of the answer
                                 let _ = tx
                                     .send(Some(ModelEvent::new(
                                         &tracing::Span::current(),
-                                        ModelEventType::LlmContent(LLMContentEvent {
+                                        ModelEventType::LlmReasoning(LLMReasoningEvent {
                                             content: delta.thinking.clone(),
                                         }),
                                     )))
                                     .await;
-                                stream_content.push_str(&delta.thinking);
 
                                 let mut chunk_clone = chunk.clone();
                                 chunk_clone.choices.push(ChatCompletionChunkChoice {
                                     index: 0,
                                     delta: ChatCompletionDelta {
                                         role: Some("assistant".to_string()),
-                                        content: Some(delta.thinking.clone()),
+                                        content: None,
                                         tool_calls: None,
+                                        reasoning_content: Some(delta.thinking.clone()),
                                     },
                                     finish_reason: None,
                                     logprobs: None,
@@ -468,6 +606,7 @@ impl AnthropicModel {
                                             },
                                             extra_content: None,
                                         }]),
+                                        reasoning_content: None,
                                     },
                                     finish_reason: None,
                                     logprobs: None,
@@ -499,6 +638,7 @@ impl AnthropicModel {
                         }
                     }
                 }
+                Err(e) => return Err(e.into()),
                 last_result => {
                     tracing::error!("Error in stream: {last_result:?}");
                     break;
@@ -536,17 +676,14 @@ impl AnthropicModel {
             .await;
 
         let response = async move {
-            let result = self
-                .client
-                .create_a_message(request, self.endpoint.clone())
-                .await;
+            let result = self.create_message(&request).await;
             let _ = result
                 .as_ref()
                 .map(|response| serde_json::to_value(response).unwrap())
                 .as_ref()
                 .map(JsonValue)
                 .record();
-            let response = result.map_err(custom_err)?;
+            let response = result?;
 
             let span = Span::current();
             span.record("output", serde_json::to_string(&response)?);
@@ -573,21 +710,7 @@ impl AnthropicModel {
             clust::messages::StopReason::EndTurn | clust::messages::StopReason::StopSequence => {
                 let message_content = response.content;
 
-                let prompt_tokens_details = PromptTokensDetails::new(
-                    response.usage.cache_read_input_tokens,
-                    response.usage.cache_creation_input_tokens,
-                    None,
-                );
-                let input_tokens = response.usage.input_tokens
-                    + response.usage.cache_read_input_tokens.unwrap_or(0)
-                    + response.usage.cache_creation_input_tokens.unwrap_or(0);
-                let usage = GatewayModelUsage {
-                    input_tokens,
-                    output_tokens: response.usage.output_tokens,
-                    total_tokens: input_tokens + response.usage.output_tokens,
-                    prompt_tokens_details: Some(prompt_tokens_details),
-                    ..Default::default()
-                };
+                let usage = GatewayModelUsage::from(&response.usage);
 
                 match message_content {
                     Content::SingleText(content) => {
@@ -607,6 +730,7 @@ impl AnthropicModel {
                                     finish_reason: ModelFinishReason::Stop,
                                     tool_calls: vec![],
                                     credentials_ident: self.credentials_ident.clone(),
+                                    rate_limit: None,
                                 }),
                             )))
                             .await;
@@ -663,6 +787,7 @@ impl AnthropicModel {
                                     finish_reason: ModelFinishReason::Stop,
                                     tool_calls: vec![],
                                     credentials_ident: self.credentials_ident.clone(),
+                                    rate_limit: None,
                                 }),
                             )))
                             .await;
@@ -725,6 +850,9 @@ impl AnthropicModel {
                 let tools_span = tracing::info_span!(
                     target: target!(),
                     SPAN_TOOLS,
+                    cache=tracing::field::Empty,
+                    validation_errors=tracing::field::Empty,
+                    error_payload=tracing::field::Empty,
                     tool_calls=tool_calls_str,
                     tool.name=tool_runs.iter().map(|t| t.name.clone()).collect::<Vec<String>>().join(",")
                 );
@@ -732,12 +860,7 @@ impl AnthropicModel {
 
                 let tool = self.tools.get(&tool_runs[0].name).unwrap();
                 if tool.stop_at_call() {
-                    let usage = Some(GatewayModelUsage {
-                        input_tokens: response.usage.input_tokens,
-                        output_tokens: response.usage.output_tokens,
-                        total_tokens: response.usage.input_tokens + response.usage.output_tokens,
-                        ..Default::default()
-                    });
+                    let usage = Some(GatewayModelUsage::from(&response.usage));
                     let _ = tx
                         .send(Some(ModelEvent::new(
                             &span,
@@ -762,6 +885,7 @@ impl AnthropicModel {
                                     })
                                     .collect(),
                                 credentials_ident: self.credentials_ident.clone(),
+                                rate_limit: None,
                             }),
                         )))
                         .await;
@@ -830,6 +954,7 @@ impl AnthropicModel {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some((system_message, input_messages)) = calls.pop() {
             let input = serde_json::to_string(&input_messages)?;
             let call_span = create_model_span!(
@@ -858,11 +983,12 @@ impl AnthropicModel {
             {
                 Ok(InnerExecutionResult::Finish(message)) => return Ok(message.deref().clone()),
                 Ok(InnerExecutionResult::NextCall((system_prompt, messages))) => {
+                    tool_iterations.next(&call_span)?;
                     calls.push((system_prompt, messages));
                 }
                 Err(e) => {
                     call_span.record("error", e.to_string());
-                    if retries_left == 0 {
+                    if retries_left == 0 || !retry_policy().is_retryable(&e) {
                         return Err(e);
                     } else {
                         calls.push((system_message, input_messages));
@@ -888,6 +1014,7 @@ impl AnthropicModel {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some((system_message, input_messages)) = calls.pop() {
             let input = serde_json::to_string(&input_messages)?;
             let call_span = create_model_span!(
@@ -916,11 +1043,12 @@ impl AnthropicModel {
             {
                 Ok(InnerExecutionResult::Finish(_)) => return Ok(()),
                 Ok(InnerExecutionResult::NextCall((system_prompt, messages))) => {
+                    tool_iterations.next(&call_span)?;
                     calls.push((system_prompt, messages));
                 }
                 Err(e) => {
                     call_span.record("error", e.to_string());
-                    if retries_left == 0 {
+                    if retries_left == 0 || !retry_policy().is_retryable(&e) {
                         return Err(e);
                     } else {
                         calls.push((system_message, input_messages));
@@ -934,20 +1062,7 @@ impl AnthropicModel {
     }
 
     fn map_usage(usage: &Usage) -> GatewayModelUsage {
-        let input_tokens = usage.input_tokens
-            + usage.cache_read_input_tokens.unwrap_or(0)
-            + usage.cache_creation_input_tokens.unwrap_or(0);
-        GatewayModelUsage {
-            input_tokens,
-            output_tokens: usage.output_tokens,
-            total_tokens: usage.output_tokens + input_tokens,
-            prompt_tokens_details: Some(PromptTokensDetails::new(
-                usage.cache_read_input_tokens,
-                usage.cache_creation_input_tokens,
-                None,
-            )),
-            ..Default::default()
-        }
+        GatewayModelUsage::from(usage)
     }
 
     fn map_finish_reason(reason: &StopReason) -> ModelFinishReason {
@@ -997,11 +1112,7 @@ impl AnthropicModel {
             .await;
 
         let started_at = std::time::Instant::now();
-        let stream = self
-            .client
-            .create_a_message_stream(request, self.endpoint.clone())
-            .await
-            .map_err(custom_err)?;
+        let stream = self.create_message_stream(&request).await?;
         let (stop_reason, tool_calls, usage, response) = self
             .process_stream(stream, tx, tx_response, started_at)
             .instrument(span.clone())
@@ -1017,6 +1128,7 @@ impl AnthropicModel {
             model: response.model.to_string(),
             choices: vec![],
             usage: None,
+            tool_event: None,
         };
 
         let mut chunk_clone = chunk.clone();
@@ -1061,6 +1173,7 @@ impl AnthropicModel {
                         .iter()
                         .map(Self::map_tool_call)
                         .collect::<Result<Vec<ModelToolCall>, LLMError>>()?,
+                    rate_limit: None,
                 }),
             )))
             .await;
@@ -1085,6 +1198,9 @@ impl AnthropicModel {
                 let tools_span = tracing::info_span!(
                     target: target!(),
                     SPAN_TOOLS,
+                    cache=tracing::field::Empty,
+                    validation_errors=tracing::field::Empty,
+                    error_payload=tracing::field::Empty,
                     tool_calls=tool_calls_str,
                     tool.name=tool_calls.iter().map(|t| t.name.clone()).collect::<Vec<String>>().join(",")
                 );
@@ -1139,34 +1255,48 @@ impl AnthropicModel {
         }
     }
 
+    /// Maps the history to Anthropic turns. Histories can come from another provider, e.g.
+    /// when a tool loop fails over to Anthropic: assistant text is kept next to its tool
+    /// calls, and consecutive tool results are sent as one user turn.
     fn map_previous_messages(messages_dto: Vec<Message>) -> LLMResult<Vec<ClustMessage>> {
-        // convert serde::Map into HashMap
         let mut messages: Vec<ClustMessage> = vec![];
-
-        let mut tool_results_remaining = 0;
-        let mut tool_calls_collected = vec![];
+        let mut tool_results = vec![];
 
         for m in messages_dto.iter() {
+            if m.r#type != MessageType::ToolResult && !tool_results.is_empty() {
+                messages.push(ClustMessage::user(Content::MultipleBlocks(std::mem::take(
+                    &mut tool_results,
+                ))));
+            }
+
             match m.r#type {
                 MessageType::SystemMessage => {}
                 MessageType::AIMessage => {
                     if let Some(tool_calls) = &m.tool_calls {
-                        tool_results_remaining = tool_calls.len();
-                        tool_calls_collected = vec![];
+                        let text = m
+                            .content
+                            .as_ref()
+                            .filter(|content| !content.is_empty())
+                            .map(|content| {
+                                ContentBlock::Text(TextContentBlock::new(content.clone()))
+                            });
+                        let tool_uses = tool_calls.iter().map(|t| {
+                            // Some providers send no arguments for tools without parameters
+                            let arguments = match t.function.arguments.trim() {
+                                "" => serde_json::json!({}),
+                                arguments => serde_json::from_str::<Value>(arguments)?,
+                            };
+                            Ok::<_, LLMError>(ContentBlock::ToolUse(ToolUseContentBlock::new(
+                                ToolUse::new(t.id.clone(), t.function.name.clone(), arguments),
+                            )))
+                        });
 
                         messages.push(ClustMessage::assistant(Content::MultipleBlocks(
-                            tool_calls
-                                .iter()
-                                .map(|t| {
-                                    Ok(ContentBlock::ToolUse(ToolUseContentBlock::new(
-                                        ToolUse::new(
-                                            t.id.clone(),
-                                            t.function.name.clone(),
-                                            serde_json::from_str(&t.function.arguments)?,
-                                        ),
-                                    )))
-                                })
-                                .collect::<Result<Vec<ContentBlock>, LLMError>>()?,
+                            text.into_iter().map(Ok).chain(tool_uses).collect::<Result<
+                                Vec<ContentBlock>,
+                                LLMError,
+                            >>(
+                            )?,
                         )));
                     } else {
                         messages.push(ClustMessage::assistant(Content::SingleText(
@@ -1175,25 +1305,24 @@ impl AnthropicModel {
                     }
                 }
                 MessageType::HumanMessage => {
-                    messages.push(construct_user_message(&m.clone().into()));
+                    messages.push(construct_user_message(&m.clone().into())?);
                 }
                 MessageType::ToolResult => {
-                    tool_results_remaining -= 1;
-                    tool_calls_collected.push(ContentBlock::ToolResult(
-                        ToolResultContentBlock::new(ToolResult::success(
-                            m.tool_call_id.as_ref().expect("Missing tool call id"),
-                            m.content.clone(),
-                        )),
-                    ));
-                    if tool_results_remaining == 0 {
-                        messages.push(ClustMessage::user(Content::MultipleBlocks(
-                            tool_calls_collected.clone(),
-                        )));
-                    }
+                    let tool_call_id = m
+                        .tool_call_id
+                        .as_ref()
+                        .ok_or(LLMError::ToolCallIdNotFound)?;
+                    tool_results.push(ContentBlock::ToolResult(ToolResultContentBlock::new(
+                        ToolResult::success(tool_call_id, m.content.clone()),
+                    )));
                 }
             }
         }
 
+        if !tool_results.is_empty() {
+            messages.push(ClustMessage::user(Content::MultipleBlocks(tool_results)));
+        }
+
         Ok(messages)
     }
 }
@@ -1227,19 +1356,17 @@ impl ModelInstance for AnthropicModel {
         let tx_clone = tx.clone();
         tokio::spawn(
             async move {
-                let result = model
-                    .execute_stream(
+                stream_until_closed(
+                    &tx_response,
+                    model.execute_stream(
                         system_prompt,
                         conversational_messages,
                         &tx_clone,
                         &tx_response,
                         tags,
-                    )
-                    .await;
-
-                if let Err(e) = result {
-                    let _ = tx_response.send(Err(e)).await;
-                }
+                    ),
+                )
+                .await;
             }
             .instrument(tracing::Span::current()),
         );
@@ -1254,6 +1381,17 @@ impl AnthropicModel {
         input_variables: HashMap<String, Value>,
         previous_messages: Vec<Message>,
     ) -> LLMResult<(Option<SystemPrompt>, Vec<ClustMessage>)> {
+        // Messages must alternate between user and assistant, starting with the user. Names
+        // aren't supported, so they are folded into the content before turns are merged.
+        let previous_messages = normalize_for_provider(
+            previous_messages
+                .into_iter()
+                .map(Message::fold_name)
+                .collect(),
+            self.execution_options
+                .role_policy
+                .unwrap_or(RolePolicy::Alternate),
+        );
         let mut conversational_messages = vec![];
         let system_message = previous_messages
             .iter()
@@ -1291,7 +1429,7 @@ impl AnthropicModel {
     }
 }
 
-fn construct_user_message(m: &InnerMessage) -> ClustMessage {
+fn construct_user_message(m: &InnerMessage) -> Result<ClustMessage, ModelError> {
     let content = match m {
         InnerMessage::Text(text) => Content::SingleText(text.to_owned()),
         InnerMessage::Array(content_array) => {
@@ -1326,7 +1464,40 @@ fn construct_user_message(m: &InnerMessage) -> ClustMessage {
                         todo!()
                     }
                     MessageContentType::File => {
-                        todo!()
+                        let file = m.file.as_ref().ok_or(DocumentError::MissingData)?;
+                        match Document::from_file(file)?.inline() {
+                            // Sent as a document block, see `request_body`
+                            Some(("application/pdf", data)) => {
+                                if !data.starts_with(PDF_BASE64_SIGNATURE) {
+                                    return Err(ModelError::CustomError(
+                                        "File part of type application/pdf is not a PDF"
+                                            .to_string(),
+                                    ));
+                                }
+                                ContentBlock::Image(ImageContentBlock::from(
+                                    ImageContentSource::base64(
+                                        clust::messages::ImageMediaType::Png,
+                                        data,
+                                    ),
+                                ))
+                            }
+                            // Plain text is inlined as a text block
+                            Some(("text/plain", data)) => {
+                                let text = String::from_utf8(Document::decode(data)?)
+                                    .map_err(|e| ModelError::CustomError(e.to_string()))?;
+                                ContentBlock::Text(TextContentBlock::new(text))
+                            }
+                            Some((media_type, _)) => {
+                                return Err(
+                                    DocumentError::unsupported("Anthropic", media_type).into()
+                                )
+                            }
+                            None => {
+                                return Err(
+                                    DocumentError::unsupported("Anthropic", "file ids").into()
+                                )
+                            }
+                        }
                     }
                 };
                 blocks.push(msg)
@@ -1336,10 +1507,370 @@ fn construct_user_message(m: &InnerMessage) -> ClustMessage {
         }
     };
 
-    ClustMessage::user(content)
+    Ok(ClustMessage::user(content))
 }
 
 pub fn record_map_err(e: impl Into<LLMError> + ToString, span: tracing::Span) -> LLMError {
     span.record("error", e.to_string());
     e.into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::completions::CompletionsClient;
+    use crate::provider::tests::MockStreamServer;
+    use crate::types::credentials::Credentials;
+    use crate::types::engine::CompletionEngineParamsBuilder;
+    use crate::types::gateway::ChatCompletionRequest;
+    use crate::types::models::InferenceProvider;
+    use crate::types::provider::InferenceModelProvider;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers every messages request with `body`
+    async fn serve_message(body: &str) -> MockStreamServer {
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server
+            .set_payload("application/json", body.as_bytes().to_vec())
+            .await;
+        server
+    }
+
+    /// Tool whose result always makes the model call it again
+    struct LookupTool {
+        calls: Arc<AtomicUsize>,
+    }
+
+    #[async_trait::async_trait]
+    impl Tool for LookupTool {
+        fn name(&self) -> String {
+            "lookup".to_string()
+        }
+
+        fn description(&self) -> String {
+            String::new()
+        }
+
+        fn get_function_parameters(&self) -> Option<crate::types::gateway::FunctionParameters> {
+            None
+        }
+
+        async fn run(
+            &self,
+            _input: HashMap<String, Value>,
+            _tags: HashMap<String, String>,
+        ) -> LLMResult<Value> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(serde_json::json!("Not found, look it up again"))
+        }
+    }
+
+    #[test]
+    fn test_named_messages_are_folded_into_content() {
+        let messages: Vec<crate::types::gateway::ChatCompletionMessage> =
+            serde_json::from_value(serde_json::json!([
+                {"role": "user", "name": "alice", "content": "Hi"},
+                {"role": "user", "name": "bob", "content": "Hello"},
+            ]))
+            .unwrap();
+        let messages = messages
+            .iter()
+            .map(|m| {
+                crate::client::message_mapper::MessageMapper::map_completions_message_to_vllora_message(
+                    m, "claude-3-5-haiku-20241022", "user",
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let model = AnthropicModel::new(
+            serde_json::from_value(serde_json::json!({})).unwrap(),
+            ExecutionOptions::default(),
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let (_, messages) = model.construct_messages(HashMap::new(), messages).unwrap();
+        let messages = serde_json::to_value(messages).unwrap();
+
+        // Both speakers end up in a single user turn, each with their name
+        assert_eq!(messages.as_array().unwrap().len(), 1);
+        assert_eq!(messages[0]["role"], "user");
+        let content = messages[0]["content"].to_string();
+        assert!(content.contains("alice: Hi"));
+        assert!(content.contains("bob: Hello"));
+        assert!(messages[0].get("name").is_none());
+    }
+
+    #[test]
+    fn test_openai_tool_loop_continues_on_anthropic() {
+        // History of a tool loop started on OpenAI, sent to Anthropic after failing over
+        let messages: Vec<crate::types::gateway::ChatCompletionMessage> =
+            serde_json::from_value(serde_json::json!([
+                {"role": "user", "content": "Weather in Paris and the time?"},
+                {"role": "assistant", "content": "Let me check.", "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+                    {"id": "call_2", "type": "function", "function": {"name": "get_time", "arguments": ""}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18C"},
+                {"role": "tool", "tool_call_id": "call_2", "content": "14:00"},
+            ]))
+            .unwrap();
+        let messages = messages
+            .iter()
+            .map(|m| {
+                crate::client::message_mapper::MessageMapper::map_completions_message_to_vllora_message(
+                    m, "claude-3-5-haiku-20241022", "user",
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let model = AnthropicModel::new(
+            serde_json::from_value(serde_json::json!({})).unwrap(),
+            ExecutionOptions::default(),
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let (_, messages) = model.construct_messages(HashMap::new(), messages).unwrap();
+        let messages = serde_json::to_value(messages).unwrap();
+
+        let roles: Vec<&str> = messages
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["role"].as_str().unwrap())
+            .collect();
+        assert_eq!(roles, ["user", "assistant", "user"]);
+
+        let assistant = &messages[1]["content"];
+        assert_eq!(assistant[0]["type"], "text");
+        assert_eq!(assistant[0]["text"], "Let me check.");
+        assert_eq!(assistant[1]["type"], "tool_use");
+        assert_eq!(assistant[1]["id"], "call_1");
+        assert_eq!(assistant[1]["input"], serde_json::json!({"city": "Paris"}));
+        assert_eq!(assistant[2]["id"], "call_2");
+        assert_eq!(assistant[2]["input"], serde_json::json!({}));
+
+        let results = &messages[2]["content"];
+        assert_eq!(results.as_array().unwrap().len(), 2);
+        assert_eq!(results[0]["type"], "tool_result");
+        assert_eq!(results[0]["tool_use_id"], "call_1");
+        assert_eq!(results[1]["tool_use_id"], "call_2");
+        assert!(results[1]["content"].to_string().contains("14:00"));
+    }
+
+    #[test]
+    fn test_pdf_file_part_is_sent_as_document_block() {
+        let message = InnerMessage::Array(vec![
+            crate::types::message::MessageContentPart {
+                r#type: MessageContentType::Text,
+                value: "Summarize this report".to_string(),
+                additional_options: None,
+                file: None,
+                cache_control: None,
+            },
+            crate::types::message::MessageContentPart {
+                r#type: MessageContentType::File,
+                value: String::new(),
+                additional_options: None,
+                file: Some(crate::types::gateway::File {
+                    data: Some("data:application/pdf;base64,JVBERi0xLjQK".to_string()),
+                    id: None,
+                    filename: Some("report.pdf".to_string()),
+                }),
+                cache_control: None,
+            },
+            crate::types::message::MessageContentPart {
+                r#type: MessageContentType::ImageUrl,
+                value: "data:image/png;base64,iVBORw0KGgo=".to_string(),
+                additional_options: None,
+                file: None,
+                cache_control: None,
+            },
+        ]);
+
+        let model = AnthropicModel::new(
+            serde_json::from_value(serde_json::json!({"model": "claude-3-5-haiku-20241022"}))
+                .unwrap(),
+            ExecutionOptions::default(),
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            HashMap::new(),
+            None,
+        )
+        .unwrap();
+        let request = model
+            .build_request(None, vec![construct_user_message(&message).unwrap()], false)
+            .unwrap();
+        let body = request_body(&request).unwrap();
+
+        let content = &body["messages"][0]["content"];
+        assert_eq!(content[1]["type"], "document");
+        assert_eq!(
+            content[1]["source"],
+            serde_json::json!({
+                "type": "base64",
+                "media_type": "application/pdf",
+                "data": "JVBERi0xLjQK"
+            })
+        );
+        // Images are left as they are
+        assert_eq!(content[2]["type"], "image");
+        assert_eq!(content[2]["source"]["media_type"], "image/png");
+    }
+
+    #[test]
+    fn test_pdf_file_part_without_pdf_content_is_rejected() {
+        let message = InnerMessage::Array(vec![crate::types::message::MessageContentPart {
+            r#type: MessageContentType::File,
+            value: String::new(),
+            additional_options: None,
+            file: Some(crate::types::gateway::File {
+                data: Some("data:application/pdf;base64,aGVsbG8=".to_string()),
+                id: None,
+                filename: None,
+            }),
+            cache_control: None,
+        }]);
+
+        assert!(construct_user_message(&message).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_configured_headers_reach_anthropic() {
+        let body = r#"{"id":"msg_01","type":"message","role":"assistant","model":"claude-3-5-haiku-20241022","content":[{"type":"text","text":"Paris"}],"stop_reason":"end_turn","stop_sequence":null,"usage":{"input_tokens":12,"output_tokens":3}}"#;
+        let server = serve_message(body).await;
+
+        let builder = CompletionEngineParamsBuilder::new()
+            .with_provider(InferenceProvider {
+                provider: InferenceModelProvider::Anthropic,
+                model_name: "claude-3-5-haiku-20241022".to_string(),
+                endpoint: Some(format!("{}/v1/messages", server.url())),
+                custom_inference_api_type: None,
+            })
+            .with_model_name("claude-3-5-haiku-20241022".to_string())
+            .with_credentials(Credentials::ApiKey(ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }))
+            .with_execution_options(ExecutionOptions {
+                headers: HashMap::from([(
+                    "anthropic-beta".to_string(),
+                    "token-efficient-tools-2025-02-19".to_string(),
+                )]),
+                ..Default::default()
+            });
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "anthropic/claude-3-5-haiku-20241022",
+            "messages": [{"role": "user", "content": "What is the capital of France?"}],
+            "max_tokens": 64,
+        }))
+        .unwrap();
+
+        let response = CompletionsClient::new(builder)
+            .create(request)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text("Paris".to_string()))
+        );
+
+        let head = server.requests().await[0].to_lowercase();
+        assert!(head.contains("anthropic-beta: token-efficient-tools-2025-02-19"));
+        assert!(head.contains("x-api-key: test"));
+    }
+
+    #[tokio::test]
+    async fn test_stop_sequence_is_removed_from_anthropic_content() {
+        let body = r#"{"id":"msg_02","type":"message","role":"assistant","model":"claude-3-5-haiku-20241022","content":[{"type":"text","text":"Paris\n\nHuman:"}],"stop_reason":"stop_sequence","stop_sequence":"\n\nHuman:","usage":{"input_tokens":12,"output_tokens":4}}"#;
+        let server = serve_message(body).await;
+
+        let builder = CompletionEngineParamsBuilder::new()
+            .with_provider(InferenceProvider {
+                provider: InferenceModelProvider::Anthropic,
+                model_name: "claude-3-5-haiku-20241022".to_string(),
+                endpoint: Some(format!("{}/v1/messages", server.url())),
+                custom_inference_api_type: None,
+            })
+            .with_model_name("claude-3-5-haiku-20241022".to_string())
+            .with_credentials(Credentials::ApiKey(ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }));
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "anthropic/claude-3-5-haiku-20241022",
+            "messages": [{"role": "user", "content": "What is the capital of France?"}],
+            "max_tokens": 64,
+            "stop": ["\n\nHuman:"],
+        }))
+        .unwrap();
+
+        let response = CompletionsClient::new(builder)
+            .create(request)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text("Paris".to_string()))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tool_loop_stops_at_max_tool_iterations() {
+        let body = r#"{"id":"msg_03","type":"message","role":"assistant","model":"claude-3-5-haiku-20241022","content":[{"type":"tool_use","id":"toolu_01","name":"lookup","input":{}}],"stop_reason":"tool_use","stop_sequence":null,"usage":{"input_tokens":12,"output_tokens":8}}"#;
+        let server = serve_message(body).await;
+
+        let tool_calls = Arc::new(AtomicUsize::new(0));
+        let tool: Arc<Box<dyn Tool>> = Arc::new(Box::new(LookupTool {
+            calls: tool_calls.clone(),
+        }));
+        let model = AnthropicModel::new(
+            serde_json::from_value(serde_json::json!({
+                "model": "claude-3-5-haiku-20241022",
+                "max_tokens": 64,
+            }))
+            .unwrap(),
+            ExecutionOptions {
+                max_tool_iterations: Some(2),
+                ..Default::default()
+            },
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            HashMap::from([("lookup".to_string(), tool)]),
+            Some(format!("{}/v1/messages", server.url())),
+        )
+        .unwrap();
+        let message: crate::types::gateway::ChatCompletionMessage =
+            serde_json::from_value(serde_json::json!({"role": "user", "content": "Look it up"}))
+                .unwrap();
+        let message =
+            crate::client::message_mapper::MessageMapper::map_completions_message_to_vllora_message(
+                &message,
+                "claude-3-5-haiku-20241022",
+                "user",
+            )
+            .unwrap();
+
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let error = model
+            .invoke(HashMap::new(), tx, vec![message], HashMap::new())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, LLMError::MaxToolIterations(2)));
+        // Tool results are sent back twice, the model's third tool call ends the loop
+        assert_eq!(server.requests().await.len(), 3);
+        assert_eq!(tool_calls.load(Ordering::SeqCst), 3);
+    }
+}
@@ -0,0 +1,407 @@
+use std::collections::HashMap;
+
+use clust::messages::{Content, ContentBlock, MessagesResponseBody};
+use serde::Deserialize;
+use serde_json::Value;
+
+use super::{anthropic_api_key, custom_err, AnthropicModel, ANTHROPIC_VERSION};
+use crate::client::completions::batch::{
+    BatchJob, BatchJobState, BatchJobStatus, BatchProvider, BatchResult, PreparedBatchRequest,
+};
+use crate::error::{LLMError, LLMResult};
+use crate::types::credentials::ApiKeyCredentials;
+use crate::types::engine::CompletionEngineParams;
+use crate::types::gateway::{
+    ChatCompletionContent, ChatCompletionMessage, ChatCompletionMessageWithFinishReason,
+    FunctionCall, ToolCall,
+};
+use crate::types::ModelFinishReason;
+
+const API_BASE: &str = "https://api.anthropic.com";
+
+// Reference: https://docs.anthropic.com/en/api/creating-message-batches
+#[derive(Debug, Deserialize)]
+struct MessageBatch {
+    id: String,
+    processing_status: ProcessingStatus,
+    request_counts: RequestCounts,
+    results_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+enum ProcessingStatus {
+    InProgress,
+    Canceling,
+    Ended,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RequestCounts {
+    #[serde(default)]
+    succeeded: u32,
+    #[serde(default)]
+    errored: u32,
+    #[serde(default)]
+    canceled: u32,
+    #[serde(default)]
+    expired: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchResultLine {
+    custom_id: String,
+    result: BatchResultBody,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BatchResultBody {
+    Succeeded { message: MessagesResponseBody },
+    Errored { error: Value },
+    Canceled,
+    Expired,
+}
+
+/// Messages batches API of the account owning `credentials`
+struct BatchClient {
+    api_key: String,
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl BatchClient {
+    fn new(
+        credentials: Option<&ApiKeyCredentials>,
+        endpoint: Option<&str>,
+    ) -> Result<Self, LLMError> {
+        // Custom endpoints point at the messages API, batches live next to it
+        let base_url = endpoint
+            .map(|e| {
+                e.trim_end_matches('/')
+                    .trim_end_matches("/v1/messages")
+                    .to_string()
+            })
+            .unwrap_or_else(|| API_BASE.to_string());
+
+        Ok(Self {
+            api_key: anthropic_api_key(credentials)?,
+            base_url,
+            client: crate::provider::shared_http_client(),
+        })
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> LLMResult<String> {
+        let response = request
+            .header("x-api-key", &self.api_key)
+            .header("anthropic-version", ANTHROPIC_VERSION)
+            .send()
+            .await?;
+
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            tracing::error!(target: "anthropic", "Batch request failed: {text}");
+            return Err(LLMError::CustomError(format!(
+                "Batch request failed with status: {status}"
+            )));
+        }
+        Ok(text)
+    }
+
+    async fn create(&self, requests: Vec<Value>) -> LLMResult<MessageBatch> {
+        let url = format!("{}/v1/messages/batches", self.base_url);
+        let text = self
+            .send(
+                self.client
+                    .post(url)
+                    .json(&serde_json::json!({ "requests": requests })),
+            )
+            .await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    async fn retrieve(&self, id: &str) -> LLMResult<MessageBatch> {
+        let url = format!("{}/v1/messages/batches/{id}", self.base_url);
+        let text = self.send(self.client.get(url)).await?;
+        Ok(serde_json::from_str(&text)?)
+    }
+
+    async fn results(&self, results_url: &str) -> LLMResult<Vec<BatchResult>> {
+        let text = self.send(self.client.get(results_url)).await?;
+        text.lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| Ok::<_, LLMError>(map_result(serde_json::from_str(line)?)))
+            .collect()
+    }
+}
+
+fn mixed_providers(engine: &CompletionEngineParams) -> LLMError {
+    LLMError::CustomError(format!(
+        "Batch job mixes Anthropic and {} requests",
+        engine.engine_name()
+    ))
+}
+
+fn engine_params(
+    engine: &CompletionEngineParams,
+) -> LLMResult<(&Option<ApiKeyCredentials>, &Option<String>)> {
+    match engine {
+        CompletionEngineParams::Anthropic {
+            credentials,
+            endpoint,
+            ..
+        } => Ok((credentials, endpoint)),
+        other => Err(mixed_providers(other)),
+    }
+}
+
+fn map_state(batch: &MessageBatch) -> BatchJobState {
+    let counts = &batch.request_counts;
+    match batch.processing_status {
+        ProcessingStatus::InProgress | ProcessingStatus::Canceling => BatchJobState::InProgress,
+        ProcessingStatus::Ended if counts.succeeded > 0 => BatchJobState::Completed,
+        ProcessingStatus::Ended if counts.errored > 0 => BatchJobState::Failed,
+        ProcessingStatus::Ended if counts.expired > 0 => BatchJobState::Expired,
+        ProcessingStatus::Ended if counts.canceled > 0 => BatchJobState::Cancelled,
+        ProcessingStatus::Ended => BatchJobState::Completed,
+    }
+}
+
+fn map_result(line: BatchResultLine) -> BatchResult {
+    match line.result {
+        BatchResultBody::Succeeded { message } => {
+            BatchResult::succeeded(line.custom_id, map_message(message))
+        }
+        BatchResultBody::Errored { error } => {
+            let message = error
+                .pointer("/error/message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            BatchResult::errored(line.custom_id, message)
+        }
+        BatchResultBody::Canceled => BatchResult::errored(line.custom_id, "Request was canceled"),
+        BatchResultBody::Expired => BatchResult::errored(line.custom_id, "Request expired"),
+    }
+}
+
+fn map_message(response: MessagesResponseBody) -> ChatCompletionMessageWithFinishReason {
+    let mut text = String::new();
+    let mut tool_calls = vec![];
+    let blocks = match response.content {
+        Content::SingleText(content) => {
+            text = content;
+            vec![]
+        }
+        Content::MultipleBlocks(blocks) => blocks,
+    };
+    for block in blocks {
+        match block {
+            ContentBlock::Text(block) => text.push_str(&block.text),
+            ContentBlock::ToolUse(block) => tool_calls.push(ToolCall {
+                index: Some(tool_calls.len()),
+                id: block.tool_use.id.clone(),
+                r#type: "function".to_string(),
+                function: FunctionCall {
+                    name: block.tool_use.name.clone(),
+                    arguments: block.tool_use.input.to_string(),
+                },
+                extra_content: None,
+            }),
+            _ => {}
+        }
+    }
+
+    let finish_reason = response
+        .stop_reason
+        .as_ref()
+        .map(AnthropicModel::map_finish_reason)
+        .unwrap_or(ModelFinishReason::Stop);
+
+    ChatCompletionMessageWithFinishReason::new(
+        ChatCompletionMessage {
+            role: "assistant".to_string(),
+            content: Some(ChatCompletionContent::Text(text)),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            ..Default::default()
+        },
+        finish_reason,
+        response.id,
+        chrono::Utc::now().timestamp() as u32,
+        response.model.to_string(),
+        Some(AnthropicModel::map_usage(&response.usage)),
+    )
+}
+
+/// Submits `requests` as one message batch
+pub async fn submit_batch(
+    model: &str,
+    requests: Vec<PreparedBatchRequest>,
+    input_variables: &HashMap<String, Value>,
+) -> LLMResult<BatchJob> {
+    let first = requests
+        .first()
+        .ok_or_else(|| LLMError::CustomError("Batch job has no requests".to_string()))?;
+    let (credentials, endpoint) = engine_params(&first.engine)?;
+    let client = BatchClient::new(credentials.as_ref(), endpoint.as_deref())?;
+
+    let mut batch_requests = Vec::with_capacity(requests.len());
+    for request in requests {
+        let (params, execution_options, credentials, endpoint) = match request.engine {
+            CompletionEngineParams::Anthropic {
+                params,
+                execution_options,
+                credentials,
+                endpoint,
+            } => (params, execution_options, credentials, endpoint),
+            other => return Err(mixed_providers(&other)),
+        };
+
+        let service_tier = params.service_tier;
+        let model = AnthropicModel::new(
+            params,
+            execution_options,
+            credentials.as_ref(),
+            HashMap::new(),
+            endpoint,
+        )?;
+        let (system_message, messages) =
+            model.construct_messages(input_variables.clone(), request.messages)?;
+        let body = model
+            .build_request(system_message.as_ref(), messages, false)
+            .map_err(custom_err)?;
+
+        // Batched requests can't stream
+        let mut params = serde_json::to_value(body)?;
+        if let Some(params) = params.as_object_mut() {
+            params.remove("stream");
+            if let Some(service_tier) = service_tier {
+                params.insert(
+                    "service_tier".to_string(),
+                    serde_json::to_value(service_tier)?,
+                );
+            }
+        }
+        batch_requests.push(serde_json::json!({
+            "custom_id": request.custom_id,
+            "params": params,
+        }));
+    }
+
+    let batch = client.create(batch_requests).await?;
+    Ok(BatchJob {
+        id: batch.id,
+        provider: BatchProvider::Anthropic,
+        model: model.to_string(),
+    })
+}
+
+/// Current state of `job`, with its results once it ended
+pub async fn poll_batch(
+    job: &BatchJob,
+    engine: &CompletionEngineParams,
+) -> LLMResult<BatchJobStatus> {
+    let (credentials, endpoint) = engine_params(engine)?;
+    let client = BatchClient::new(credentials.as_ref(), endpoint.as_deref())?;
+
+    let batch = client.retrieve(&job.id).await?;
+    let state = map_state(&batch);
+    let results = match (&batch.processing_status, &batch.results_url) {
+        (ProcessingStatus::Ended, Some(results_url)) => client.results(results_url).await?,
+        _ => vec![],
+    };
+
+    Ok(BatchJobStatus {
+        job: job.clone(),
+        state,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::completions::batch::BatchOutcome;
+
+    fn batch(status: &str, succeeded: u32, errored: u32, expired: u32) -> MessageBatch {
+        serde_json::from_value(serde_json::json!({
+            "id": "msgbatch_01",
+            "type": "message_batch",
+            "processing_status": status,
+            "request_counts": {
+                "processing": 0,
+                "succeeded": succeeded,
+                "errored": errored,
+                "canceled": 0,
+                "expired": expired
+            },
+            "results_url": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_batch_state_mapping() {
+        assert_eq!(
+            map_state(&batch("in_progress", 0, 0, 0)),
+            BatchJobState::InProgress
+        );
+        assert_eq!(
+            map_state(&batch("ended", 2, 1, 0)),
+            BatchJobState::Completed
+        );
+        assert_eq!(map_state(&batch("ended", 0, 3, 0)), BatchJobState::Failed);
+        assert_eq!(map_state(&batch("ended", 0, 0, 3)), BatchJobState::Expired);
+    }
+
+    #[test]
+    fn test_result_lines_map_to_completion_responses() {
+        let succeeded: BatchResultLine = serde_json::from_value(serde_json::json!({
+            "custom_id": "request-1",
+            "result": {
+                "type": "succeeded",
+                "message": {
+                    "id": "msg_01",
+                    "type": "message",
+                    "role": "assistant",
+                    "model": "claude-3-5-haiku-20241022",
+                    "content": [{"type": "text", "text": "Paris"}],
+                    "stop_reason": "end_turn",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 12, "output_tokens": 3}
+                }
+            }
+        }))
+        .unwrap();
+
+        let result = map_result(succeeded);
+        assert_eq!(result.custom_id, "request-1");
+        let BatchOutcome::Succeeded { response } = result.outcome else {
+            panic!("Expected a succeeded result");
+        };
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text("Paris".to_string()))
+        );
+        assert_eq!(response.usage().unwrap().total_tokens, 15);
+
+        let errored: BatchResultLine = serde_json::from_value(serde_json::json!({
+            "custom_id": "request-2",
+            "result": {
+                "type": "errored",
+                "error": {
+                    "type": "error",
+                    "error": {"type": "invalid_request_error", "message": "max_tokens is required"}
+                }
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            map_result(errored).outcome,
+            BatchOutcome::Errored {
+                message: "max_tokens is required".to_string()
+            }
+        );
+    }
+}
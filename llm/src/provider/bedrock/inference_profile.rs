@@ -0,0 +1,92 @@
+use crate::client::error::BedrockError;
+
+/// Prefixes of regional cross-region inference profile ids. `us-gov.` must come before `us.`.
+const REGIONAL_PREFIXES: [&str; 4] = ["us-gov.", "us.", "eu.", "apac."];
+
+/// Global inference profiles route across all commercial regions and work from any of them
+const GLOBAL_PREFIX: &str = "global.";
+
+/// Prefix of the cross-region inference profiles available in `region`
+pub fn region_prefix(region: &str) -> Option<&'static str> {
+    if region.starts_with("us-gov-") {
+        Some("us-gov.")
+    } else if region.starts_with("us-") {
+        Some("us.")
+    } else if region.starts_with("eu-") {
+        Some("eu.")
+    } else if region.starts_with("ap-") {
+        Some("apac.")
+    } else {
+        None
+    }
+}
+
+/// Returns the id to invoke `model_id` with from `region`. Ids of regional inference
+/// profiles get the prefix of the client region, so a profile listed as `us.` is invoked
+/// as `eu.` from `eu-west-1`. Foundation model ids, ARNs and global profiles are unchanged.
+pub fn inference_profile_id(model_id: &str, region: &str) -> Result<String, BedrockError> {
+    if model_id.starts_with("arn:") || model_id.starts_with(GLOBAL_PREFIX) {
+        return Ok(model_id.to_string());
+    }
+
+    let Some(base_id) = REGIONAL_PREFIXES
+        .iter()
+        .find_map(|prefix| model_id.strip_prefix(prefix))
+    else {
+        return Ok(model_id.to_string());
+    };
+
+    let prefix = region_prefix(region).ok_or_else(|| {
+        BedrockError::InferenceProfileError(format!(
+            "Model {model_id} requires a cross-region inference profile, which is not available in region {region}. Use a region with {} profiles or a global profile.",
+            REGIONAL_PREFIXES.join(" / ")
+        ))
+    })?;
+
+    Ok(format!("{prefix}{base_id}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_prefix_follows_client_region() {
+        let model = "us.anthropic.claude-3-5-sonnet-20240620-v1:0";
+        assert_eq!(
+            inference_profile_id(model, "eu-west-1").unwrap(),
+            "eu.anthropic.claude-3-5-sonnet-20240620-v1:0"
+        );
+        assert_eq!(
+            inference_profile_id(model, "ap-northeast-1").unwrap(),
+            "apac.anthropic.claude-3-5-sonnet-20240620-v1:0"
+        );
+        assert_eq!(
+            inference_profile_id("eu.meta.llama3-2-3b-instruct-v1:0", "us-east-2").unwrap(),
+            "us.meta.llama3-2-3b-instruct-v1:0"
+        );
+        assert_eq!(
+            inference_profile_id("us.amazon.nova-pro-v1:0", "us-gov-west-1").unwrap(),
+            "us-gov.amazon.nova-pro-v1:0"
+        );
+    }
+
+    #[test]
+    fn test_models_without_regional_profile_are_unchanged() {
+        for model in [
+            "anthropic.claude-3-haiku-20240307-v1:0",
+            "global.anthropic.claude-sonnet-4-20250514-v1:0",
+            "arn:aws:bedrock:us-east-1::foundation-model/amazon.titan-text-express-v1",
+        ] {
+            assert_eq!(inference_profile_id(model, "eu-west-1").unwrap(), model);
+            assert_eq!(inference_profile_id(model, "sa-east-1").unwrap(), model);
+        }
+    }
+
+    #[test]
+    fn test_region_without_profiles_is_rejected() {
+        let err = inference_profile_id("us.amazon.nova-lite-v1:0", "sa-east-1").unwrap_err();
+        assert!(matches!(err, BedrockError::InferenceProfileError(_)));
+        assert!(err.to_string().contains("sa-east-1"));
+    }
+}
@@ -1,10 +1,15 @@
-use crate::client::completions::response_stream::ResultStream;
+use crate::client::completions::response_stream::{stream_until_closed, ResultStream};
+use crate::client::documents::{self, DocumentError, DocumentSource};
 use crate::client::error::BedrockError;
 use crate::client::error::ModelError;
+use crate::client::role_normalization::{normalize_for_provider, RolePolicy};
 use crate::client::tools::handler::handle_tool_call;
 use crate::client::ModelInstance;
 use crate::client::DEFAULT_MAX_RETRIES;
 use crate::error::{LLMError, LLMResult, ModelFinishError};
+use crate::provider::bedrock::inference_profile::inference_profile_id;
+use crate::provider::retry::with_attempts;
+use crate::provider::ToolIterations;
 use crate::types::credentials::aws::{get_shared_config, get_user_shared_config};
 use crate::types::credentials::BedrockCredentials;
 use crate::types::credentials_ident::CredentialsIdent;
@@ -55,6 +60,8 @@ use vllora_telemetry::create_model_span;
 use vllora_telemetry::events::RecordResult;
 use vllora_telemetry::events::{JsonValue, SPAN_BEDROCK, SPAN_TOOLS};
 
+pub mod inference_profile;
+
 const DEFAULT_REGION: &str = "us-east-1";
 
 macro_rules! target {
@@ -75,6 +82,46 @@ fn build_err(e: impl ToString) -> ModelError {
     ModelError::CustomError(e.to_string())
 }
 
+/// User turn answering the tool calls of the previous assistant turn
+fn tool_results_message(results: Vec<ContentBlock>) -> Result<Message, ModelError> {
+    Message::builder()
+        .set_content(Some(results))
+        .role(ConversationRole::User)
+        .build()
+        .map_err(build_err)
+}
+
+/// Appends a streamed fragment to the JSON input of a tool call
+fn append_tool_input(block: &mut ToolUseBlock, fragment: &str) -> Result<(), ModelError> {
+    match block.input {
+        Document::String(ref mut input) => {
+            input.push_str(fragment);
+            Ok(())
+        }
+        _ => Err(ModelError::StreamError(format!(
+            "Input of tool {} was already parsed",
+            block.name
+        ))),
+    }
+}
+
+/// Parses the JSON input streamed for a tool call once its block stops
+fn parse_tool_input(block: &mut ToolUseBlock) -> Result<(), ModelError> {
+    let Document::String(ref input) = block.input else {
+        return Err(ModelError::StreamError(format!(
+            "Input of tool {} was already parsed",
+            block.name
+        )));
+    };
+    block.input = serde_json::from_str(input).map_err(|e| {
+        ModelError::StreamError(format!(
+            "Invalid input streamed for tool {}: {e}",
+            block.name
+        ))
+    })?;
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct BedrockModel {
     pub client: Client,
@@ -134,7 +181,11 @@ impl BedrockModel {
     ) -> Result<Self, ModelError> {
         let client = bedrock_client(credentials).await?;
 
-        let model_id = model_params.model_id.clone().unwrap_or_default();
+        let mut model_id = model_params.model_id.clone().unwrap_or_default();
+        if let Some(region) = client.config().region() {
+            model_id = inference_profile_id(&model_id, &region.to_string())
+                .map_err(|e| ModelError::Bedrock(Box::new(e)))?;
+        }
 
         Ok(Self {
             client,
@@ -153,6 +204,16 @@ impl BedrockModel {
         input_vars: HashMap<String, Value>,
         previous_messages: Vec<LMessage>,
     ) -> LLMResult<(Vec<Message>, Vec<SystemContentBlock>)> {
+        // Converse rejects consecutive turns with the same role, and has no speaker names
+        let previous_messages = normalize_for_provider(
+            previous_messages
+                .into_iter()
+                .map(LMessage::fold_name)
+                .collect(),
+            self.execution_options
+                .role_policy
+                .unwrap_or(RolePolicy::Alternate),
+        );
         let mut conversational_messages: Vec<Message> = vec![];
         let mut system_messages = vec![];
         for m in previous_messages.iter() {
@@ -169,16 +230,21 @@ impl BedrockModel {
         Ok((conversational_messages, system_messages))
     }
 
+    /// Maps the history to Converse turns. Histories can come from another provider, e.g.
+    /// when a tool loop fails over to Bedrock: consecutive tool results are sent as one user
+    /// turn, however many tool calls preceded them.
     fn map_previous_messages(
         messages_dto: Vec<LMessage>,
         input_vars: &HashMap<String, Value>,
     ) -> Result<Vec<Message>, ModelError> {
-        // convert serde::Map into HashMap
         let mut messages: Vec<Message> = vec![];
-        let mut tool_results_expected = 0;
-        let mut tool_calls_results = vec![];
+        let mut tool_results = vec![];
         for m in messages_dto.iter() {
-            let message = match m.r#type {
+            if m.r#type != MessageType::ToolResult && !tool_results.is_empty() {
+                messages.push(tool_results_message(std::mem::take(&mut tool_results))?);
+            }
+
+            match m.r#type {
                 MessageType::AIMessage => {
                     let mut contents = vec![];
                     if let Some(content) = m.content.clone() {
@@ -186,58 +252,55 @@ impl BedrockModel {
                             contents.push(ContentBlock::Text(render(content, input_vars)));
                         }
                     }
-                    if let Some(tool_calls) = m.tool_calls.clone() {
-                        tool_results_expected = tool_calls.len();
-                        tool_calls_results = vec![];
-
-                        for tool_call in tool_calls {
-                            let doc =
-                                serde_json::from_str::<Document>(&tool_call.function.arguments)?;
-                            contents.push(ContentBlock::ToolUse(
-                                ToolUseBlock::builder()
-                                    .tool_use_id(tool_call.id.clone())
-                                    .name(tool_call.function.name.clone())
-                                    .input(doc)
-                                    .build()
-                                    .map_err(build_err)?,
-                            ));
-                        }
+                    for tool_call in m.tool_calls.iter().flatten() {
+                        // Some providers send no arguments for tools without parameters
+                        let arguments = match tool_call.function.arguments.trim() {
+                            "" => "{}",
+                            arguments => arguments,
+                        };
+                        contents.push(ContentBlock::ToolUse(
+                            ToolUseBlock::builder()
+                                .tool_use_id(tool_call.id.clone())
+                                .name(tool_call.function.name.clone())
+                                .input(serde_json::from_str::<Document>(arguments)?)
+                                .build()
+                                .map_err(build_err)?,
+                        ));
                     }
 
-                    Message::builder()
-                        .set_content(Some(contents))
-                        .role(ConversationRole::Assistant)
-                        .build()
-                        .map_err(build_err)?
+                    messages.push(
+                        Message::builder()
+                            .set_content(Some(contents))
+                            .role(ConversationRole::Assistant)
+                            .build()
+                            .map_err(build_err)?,
+                    );
+                }
+                MessageType::HumanMessage => {
+                    messages.push(construct_human_message(&m.clone().into())?)
                 }
-                MessageType::HumanMessage => construct_human_message(&m.clone().into())?,
                 MessageType::ToolResult => {
-                    tool_results_expected -= 1;
-                    let content = m.content.clone().unwrap_or_default();
-                    tool_calls_results.push(ContentBlock::ToolResult(
+                    let tool_call_id = m
+                        .tool_call_id
+                        .clone()
+                        .ok_or(ModelError::ToolCallIdNotFound)?;
+                    tool_results.push(ContentBlock::ToolResult(
                         ToolResultBlock::builder()
-                            .tool_use_id(m.tool_call_id.clone().unwrap_or_default())
-                            .content(ToolResultContentBlock::Text(content))
+                            .tool_use_id(tool_call_id)
+                            .content(ToolResultContentBlock::Text(
+                                m.content.clone().unwrap_or_default(),
+                            ))
                             .status(ToolResultStatus::Success)
                             .build()
                             .map_err(build_err)?,
                     ));
-
-                    if tool_results_expected > 0 {
-                        continue;
-                    }
-
-                    Message::builder()
-                        .set_content(Some(tool_calls_results.clone()))
-                        .role(ConversationRole::User)
-                        .build()
-                        .map_err(build_err)?
                 }
-                _ => {
-                    continue;
-                }
-            };
-            messages.push(message);
+                MessageType::SystemMessage => {}
+            }
+        }
+
+        if !tool_results.is_empty() {
+            messages.push(tool_results_message(tool_results)?);
         }
         Ok(messages)
     }
@@ -364,6 +427,7 @@ impl BedrockModel {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some(input_messages) = calls.pop() {
             let input = serde_json::json!({
                 "initial_messages": format!("{input_messages:?}"),
@@ -379,23 +443,20 @@ impl BedrockModel {
             );
 
             let builder = self.build_request(&input_messages, &system_messages)?;
-            let response = self
-                .execute_inner(builder, span.clone(), tx, tags.clone())
-                .await;
+            let response = with_attempts(&span, &mut retries_left, |span| {
+                self.execute_inner(builder.clone(), span, tx, tags.clone())
+            })
+            .await;
 
             match response {
                 Ok(InnerExecutionResult::Finish(message)) => return Ok(*message),
                 Ok(InnerExecutionResult::NextCall(messages)) => {
+                    tool_iterations.next(&span)?;
                     calls.push(messages);
                 }
                 Err(e) => {
                     span.record("error", e.to_string());
-                    if retries_left == 0 {
-                        return Err(e);
-                    } else {
-                        calls.push(input_messages);
-                    }
-                    retries_left -= 1;
+                    return Err(e);
                 }
             }
         }
@@ -453,12 +514,7 @@ impl BedrockModel {
         match response.stop_reason {
             StopReason::EndTurn | StopReason::StopSequence => match response.output {
                 Some(MessageVariant(message)) => {
-                    let usage = response.usage.as_ref().map(|usage| GatewayModelUsage {
-                        input_tokens: usage.input_tokens as u32,
-                        output_tokens: usage.output_tokens as u32,
-                        total_tokens: usage.total_tokens as u32,
-                        ..Default::default()
-                    });
+                    let usage = Self::map_usage(response.usage.as_ref());
 
                     let output = match message.content.first() {
                         Some(ContentBlock::Text(message)) => Some(message.clone()),
@@ -481,6 +537,7 @@ impl BedrockModel {
                                 finish_reason: ModelFinishReason::Stop,
                                 tool_calls: vec![],
                                 credentials_ident: self.credentials_ident.clone(),
+                                rate_limit: None,
                             }),
                         )))
                         .await;
@@ -536,6 +593,9 @@ impl BedrockModel {
                 let tools_span = tracing::info_span!(
                     target: target!(),
                     SPAN_TOOLS,
+                    cache=tracing::field::Empty,
+                    validation_errors=tracing::field::Empty,
+                    error_payload=tracing::field::Empty,
                     tool.name=field::Empty
                 );
                 tools_span.follows_from(span.id());
@@ -579,7 +639,7 @@ impl BedrockModel {
                                 })
                                 .collect();
                             let tool_calls_str = serde_json::to_string(&tool_calls)?;
-                            let tools_span = tracing::info_span!(target: target!(), SPAN_TOOLS, tool_calls=tool_calls_str, label=tool_uses.iter().map(|t| t.name.clone()).collect::<Vec<String>>().join(","));
+                            let tools_span = tracing::info_span!(target: target!(), SPAN_TOOLS, tool_calls=tool_calls_str, cache=tracing::field::Empty, validation_errors=tracing::field::Empty, error_payload=tracing::field::Empty, label=tool_uses.iter().map(|t| t.name.clone()).collect::<Vec<String>>().join(","));
 
                             tools_span.record(
                                 "tool.name",
@@ -590,13 +650,7 @@ impl BedrockModel {
                                     .join(","),
                             );
                             if tool.stop_at_call() {
-                                let usage =
-                                    response.usage.as_ref().map(|usage| GatewayModelUsage {
-                                        input_tokens: usage.input_tokens as u32,
-                                        output_tokens: usage.output_tokens as u32,
-                                        total_tokens: usage.total_tokens as u32,
-                                        ..Default::default()
-                                    });
+                                let usage = Self::map_usage(response.usage.as_ref());
 
                                 let _ = tx
                                     .send(Some(ModelEvent::new(
@@ -618,6 +672,7 @@ impl BedrockModel {
                                                 .collect::<Result<Vec<ModelToolCall>, LLMError>>(
                                             )?,
                                             credentials_ident: self.credentials_ident.clone(),
+                                            rate_limit: None,
                                         }),
                                     )))
                                     .await;
@@ -706,6 +761,7 @@ impl BedrockModel {
                 model: self.model_name.clone(),
                 choices: vec![],
                 usage: None,
+                tool_event: None,
             };
 
             match output {
@@ -733,12 +789,9 @@ impl BedrockModel {
                             let _ = tx_response.send(Ok(chunk_clone)).await;
                         }
                         Some(ContentBlockDelta::ToolUse(tool_use)) => {
-                            tool_uses.entry(a.content_block_index).and_modify(|t| {
-                                let Document::String(ref mut s) = t.input else {
-                                    unreachable!("Streaming tool input is always a string")
-                                };
-                                s.push_str(tool_use.input());
-                            });
+                            if let Some(t) = tool_uses.get_mut(&a.content_block_index) {
+                                append_tool_input(t, tool_use.input())?;
+                            }
                         }
                         _ => {
                             return Err(ModelError::CustomError(
@@ -767,11 +820,7 @@ impl BedrockModel {
                 },
                 ConverseStreamOutput::ContentBlockStop(event) => {
                     if let Some(block) = tool_uses.get_mut(&event.content_block_index) {
-                        let Document::String(ref s) = block.input else {
-                            unreachable!()
-                        };
-                        let d: Document = serde_json::from_str(s)?;
-                        block.input = d;
+                        parse_tool_input(block)?;
                     }
                 }
                 ConverseStreamOutput::MessageStart(event) => {
@@ -820,6 +869,7 @@ impl BedrockModel {
                                         })
                                         .collect(),
                                 ),
+                                reasoning_content: None,
                             },
                             finish_reason: None,
                             logprobs: None,
@@ -847,7 +897,7 @@ impl BedrockModel {
                 }
             }
         }
-        unreachable!();
+        Err(ModelError::StreamError("Stream ended before the message stopped".to_string()).into())
     }
 
     fn map_finish_reason(reason: &StopReason) -> ModelFinishReason {
@@ -861,12 +911,7 @@ impl BedrockModel {
         }
     }
     fn map_usage(usage: Option<&TokenUsage>) -> Option<GatewayModelUsage> {
-        usage.map(|u| GatewayModelUsage {
-            input_tokens: u.input_tokens as u32,
-            output_tokens: u.output_tokens as u32,
-            total_tokens: u.total_tokens as u32,
-            ..Default::default()
-        })
+        usage.map(GatewayModelUsage::from)
     }
 
     async fn execute_stream(
@@ -883,6 +928,7 @@ impl BedrockModel {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some(input_messages) = calls.pop() {
             let input = serde_json::json!({
                 "initial_messages": format!("{input_messages:?}"),
@@ -899,31 +945,32 @@ impl BedrockModel {
 
             tracing::warn!("Bedrock Model name: {}", self.model_name);
 
-            let builder = self
-                .client
-                .converse_stream()
-                .model_id(replace_version(&self.model_name))
-                .set_system(Some(system_messages.clone()))
-                .set_tool_config(self.get_tools_config()?)
-                .set_messages(Some(input_messages.clone()));
-
-            let response = self
-                .execute_stream_inner(builder, span.clone(), tx, tx_response, tags.clone())
-                .await;
+            let tool_config = self.get_tools_config()?;
+            let response = with_attempts(&span, &mut retries_left, |span| {
+                let builder = self
+                    .client
+                    .converse_stream()
+                    .model_id(replace_version(&self.model_name))
+                    .set_system(Some(system_messages.clone()))
+                    .set_tool_config(tool_config.clone())
+                    .set_messages(Some(input_messages.clone()));
+                let tags = tags.clone();
+                async move {
+                    self.execute_stream_inner(builder, span, tx, tx_response, tags)
+                        .await
+                }
+            })
+            .await;
 
             match response {
                 Ok(InnerExecutionResult::Finish(_)) => return Ok(()),
                 Ok(InnerExecutionResult::NextCall(messages)) => {
+                    tool_iterations.next(&span)?;
                     calls.push(messages);
                 }
                 Err(e) => {
                     span.record("error", e.to_string());
-                    if retries_left == 0 {
-                        return Err(e);
-                    } else {
-                        calls.push(input_messages);
-                    }
-                    retries_left -= 1;
+                    return Err(e);
                 }
             }
         }
@@ -971,6 +1018,7 @@ impl BedrockModel {
             model: self.model_name.clone(),
             choices: vec![],
             usage: None,
+            tool_event: None,
         };
 
         let mut chunk_clone = chunk.clone();
@@ -1018,6 +1066,7 @@ impl BedrockModel {
                     finish_reason: trace_finish_reason.clone(),
                     tool_calls: tool_calls.clone(),
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             )))
             .await;
@@ -1032,11 +1081,16 @@ impl BedrockModel {
                 let tools_span = tracing::info_span!(
                     target: target!(),
                     SPAN_TOOLS,
+                    cache=tracing::field::Empty,
+                    validation_errors=tracing::field::Empty,
+                    error_payload=tracing::field::Empty,
                     tool_calls=tool_calls_str,
                     tool.name=tool_uses.iter().map(|t| t.name.clone()).collect::<Vec<String>>().join(",")
                 );
 
-                let tool = self.tools.get(&tool_calls[0].tool_name).unwrap();
+                let tool = self.tools.get(&tool_calls[0].tool_name).ok_or_else(|| {
+                    ModelError::ToolNotFoundError(tool_calls[0].tool_name.clone())
+                })?;
                 if tool.stop_at_call() {
                     return Ok(InnerExecutionResult::Finish(
                         ChatCompletionMessageWithFinishReason::new(
@@ -1133,19 +1187,17 @@ impl ModelInstance for BedrockModel {
         let tx_clone = tx.clone();
         tokio::spawn(
             async move {
-                let result = model
-                    .execute_stream(
+                stream_until_closed(
+                    &tx_response,
+                    model.execute_stream(
                         initial_messages,
                         system_messages,
                         &tx_clone,
                         &tx_response,
                         tags,
-                    )
-                    .await;
-
-                if let Err(e) = result {
-                    let _ = tx_response.send(Err(e)).await;
-                }
+                    ),
+                )
+                .await;
             }
             .instrument(tracing::Span::current()),
         );
@@ -1153,6 +1205,30 @@ impl ModelInstance for BedrockModel {
     }
 }
 
+/// Bedrock only accepts alphanumerics, whitespace, hyphens, parentheses and square
+/// brackets in document names
+fn document_name(filename: Option<&str>) -> String {
+    let stem = filename
+        .map(|name| name.rsplit_once('.').map_or(name, |(stem, _)| stem))
+        .unwrap_or_default();
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, ' ' | '-' | '(' | ')' | '[' | ']') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+
+    if name.trim().is_empty() {
+        "document".to_string()
+    } else {
+        name
+    }
+}
+
 fn construct_human_message(m: &InnerMessage) -> Result<Message, ModelError> {
     let content_blocks = match &m {
         InnerMessage::Text(text) => {
@@ -1188,7 +1264,28 @@ fn construct_human_message(m: &InnerMessage) -> Result<Message, ModelError> {
                         todo!()
                     }
                     MessageContentType::File => {
-                        todo!()
+                        let file = part.file.as_ref().ok_or(DocumentError::MissingData)?;
+                        let document = documents::Document::from_file(file)?;
+                        let (media_type, data) = match &document.source {
+                            DocumentSource::Inline { media_type, data } => (media_type, data),
+                            DocumentSource::FileId(_) => {
+                                return Err(DocumentError::unsupported("Bedrock", "file ids").into())
+                            }
+                        };
+                        let format = match media_type.as_str() {
+                            "application/pdf" => aws_sdk_bedrockruntime::types::DocumentFormat::Pdf,
+                            _ => aws_sdk_bedrockruntime::types::DocumentFormat::Txt,
+                        };
+                        let block = aws_sdk_bedrockruntime::types::DocumentBlock::builder()
+                            .format(format)
+                            .name(document_name(document.filename.as_deref()))
+                            .source(aws_sdk_bedrockruntime::types::DocumentSource::Bytes(
+                                Blob::new(documents::Document::decode(data)?),
+                            ))
+                            .build()
+                            .map_err(build_err)?;
+
+                        content_blocks.push(ContentBlock::Document(block));
                     }
                 }
             }
@@ -1232,3 +1329,149 @@ fn replace_version(model: &str) -> String {
         })
         .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::role_normalization::normalize_roles;
+    use crate::types::message::MessageContentType;
+
+    fn message(r#type: MessageType, content: &str) -> LMessage {
+        LMessage {
+            model_name: "anthropic.claude-3-haiku".to_string(),
+            thread_id: None,
+            user_id: "user".to_string(),
+            content_type: MessageContentType::Text,
+            content: Some(content.to_string()),
+            content_array: vec![],
+            r#type,
+            tool_call_id: None,
+            tool_calls: None,
+            created_at: None,
+            name: None,
+        }
+    }
+
+    #[test]
+    fn test_consecutive_user_messages_become_one_turn() {
+        let history = vec![
+            message(MessageType::HumanMessage, "Hi"),
+            message(MessageType::HumanMessage, "Are you there?"),
+            message(MessageType::AIMessage, "Yes"),
+            message(MessageType::HumanMessage, "Great"),
+        ];
+
+        let (history, normalization) = normalize_roles(history, RolePolicy::Alternate);
+        let messages = BedrockModel::map_previous_messages(history, &HashMap::new()).unwrap();
+
+        assert_eq!(normalization.merged, 1);
+        assert_eq!(
+            messages
+                .iter()
+                .map(|m| m.role().clone())
+                .collect::<Vec<_>>(),
+            vec![
+                ConversationRole::User,
+                ConversationRole::Assistant,
+                ConversationRole::User
+            ]
+        );
+        assert_eq!(
+            messages[0].content(),
+            &[ContentBlock::Text("Hi\n\nAre you there?".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_openai_tool_loop_continues_on_bedrock() {
+        // History of a tool loop started on OpenAI, sent to Bedrock after failing over
+        let messages: Vec<crate::types::gateway::ChatCompletionMessage> =
+            serde_json::from_value(serde_json::json!([
+                {"role": "user", "content": "Weather in Paris and the time?"},
+                {"role": "assistant", "content": "Let me check.", "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+                    {"id": "call_2", "type": "function", "function": {"name": "get_time", "arguments": ""}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18C"},
+                {"role": "tool", "tool_call_id": "call_2", "content": "14:00"},
+                {"role": "user", "content": "Thanks"},
+            ]))
+            .unwrap();
+        let history = messages
+            .iter()
+            .map(|m| {
+                crate::client::message_mapper::MessageMapper::map_completions_message_to_vllora_message(
+                    m, "anthropic.claude-3-haiku", "user",
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let messages = BedrockModel::map_previous_messages(history, &HashMap::new()).unwrap();
+        assert_eq!(
+            messages
+                .iter()
+                .map(|m| m.role().clone())
+                .collect::<Vec<_>>(),
+            vec![
+                ConversationRole::User,
+                ConversationRole::Assistant,
+                ConversationRole::User,
+                ConversationRole::User
+            ]
+        );
+
+        let assistant = messages[1].content();
+        assert_eq!(
+            assistant[0],
+            ContentBlock::Text("Let me check.".to_string())
+        );
+        let ContentBlock::ToolUse(get_time) = &assistant[2] else {
+            panic!("expected a tool use, got {:?}", assistant[2]);
+        };
+        assert_eq!(get_time.tool_use_id, "call_2");
+        assert_eq!(get_time.name, "get_time");
+        assert!(matches!(get_time.input, Document::Object(ref o) if o.is_empty()));
+
+        // Both results are sent in the turn following the tool calls
+        let results = messages[2].content();
+        assert_eq!(results.len(), 2);
+        assert!(matches!(
+            &results[1],
+            ContentBlock::ToolResult(result) if result.tool_use_id == "call_2"
+        ));
+    }
+
+    fn tool_use_block() -> ToolUseBlock {
+        ToolUseBlock::builder()
+            .name("get_weather")
+            .tool_use_id("tooluse_1")
+            .input(String::new().into())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_streamed_tool_input_is_parsed() {
+        let mut block = tool_use_block();
+        append_tool_input(&mut block, r#"{"city": "Par"#).unwrap();
+        append_tool_input(&mut block, r#"is"}"#).unwrap();
+
+        parse_tool_input(&mut block).unwrap();
+        assert!(matches!(block.input, Document::Object(_)));
+        // Late fragments of a parsed block are rejected instead of panicking
+        assert!(append_tool_input(&mut block, "}").is_err());
+    }
+
+    #[test]
+    fn test_malformed_tool_input_is_a_stream_error() {
+        let mut block = tool_use_block();
+        append_tool_input(&mut block, r#"{"city": "Par"#).unwrap();
+
+        let error = parse_tool_input(&mut block).unwrap_err();
+        assert!(matches!(
+            error,
+            ModelError::StreamError(ref message) if message.contains("get_weather")
+        ));
+    }
+}
@@ -0,0 +1,311 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use super::model::gemini_client;
+use super::types::{
+    BatchGenerateContentRequest, BatchInputConfig, BatchOperation, GenerateContentBatch,
+    GenerateContentResponse, InlinedRequest, InlinedRequestMetadata, InlinedRequests,
+    InlinedResponse, Part,
+};
+use super::GeminiModel;
+use crate::client::completions::batch::{
+    BatchJob, BatchJobState, BatchJobStatus, BatchProvider, BatchResult, PreparedBatchRequest,
+};
+use crate::error::{LLMError, LLMResult};
+use crate::types::engine::CompletionEngineParams;
+use crate::types::gateway::{
+    ChatCompletionContent, ChatCompletionMessage, ChatCompletionMessageWithFinishReason,
+    FunctionCall, ToolCall,
+};
+use crate::types::ModelFinishReason;
+
+fn mixed_providers(engine: &CompletionEngineParams) -> LLMError {
+    LLMError::CustomError(format!(
+        "Batch job mixes Gemini and {} requests",
+        engine.engine_name()
+    ))
+}
+
+fn map_state(state: &str) -> BatchJobState {
+    let state = state
+        .trim_start_matches("BATCH_STATE_")
+        .trim_start_matches("JOB_STATE_");
+    match state {
+        "SUCCEEDED" => BatchJobState::Completed,
+        "FAILED" => BatchJobState::Failed,
+        "CANCELLED" => BatchJobState::Cancelled,
+        "EXPIRED" => BatchJobState::Expired,
+        "RUNNING" => BatchJobState::InProgress,
+        _ => BatchJobState::Validating,
+    }
+}
+
+fn map_result(index: usize, inlined: InlinedResponse) -> BatchResult {
+    // Responses are returned in request order, the key is only echoed back when set
+    let custom_id = inlined
+        .metadata
+        .map(|m| m.key)
+        .unwrap_or_else(|| index.to_string());
+
+    match (inlined.response, inlined.error) {
+        (Some(response), None) => BatchResult::succeeded(custom_id, map_response(response)),
+        (_, Some(error)) => {
+            let message = error
+                .get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .unwrap_or_else(|| error.to_string());
+            BatchResult::errored(custom_id, message)
+        }
+        (None, None) => BatchResult::errored(custom_id, "Request returned no response"),
+    }
+}
+
+fn map_response(response: GenerateContentResponse) -> ChatCompletionMessageWithFinishReason {
+    let mut text = String::new();
+    let mut tool_calls = vec![];
+    let mut finish_reason = None;
+    for candidate in response.candidates {
+        if let Some(reason) = candidate.finish_reason {
+            finish_reason = Some(reason);
+        }
+        for part in candidate.content.parts {
+            match part.part {
+                Part::Text(t) => text.push_str(&t),
+                Part::FunctionCall { name, args } => tool_calls.push(ToolCall {
+                    index: Some(tool_calls.len()),
+                    id: name.clone(),
+                    r#type: "function".to_string(),
+                    function: FunctionCall {
+                        name,
+                        arguments: serde_json::to_string(&args).unwrap_or_default(),
+                    },
+                    extra_content: None,
+                }),
+                _ => {}
+            }
+        }
+    }
+
+    let finish_reason = finish_reason
+        .map(|reason| GeminiModel::map_finish_reason(&reason, !tool_calls.is_empty()))
+        .unwrap_or(ModelFinishReason::Stop);
+
+    ChatCompletionMessageWithFinishReason::new(
+        ChatCompletionMessage {
+            role: "assistant".to_string(),
+            content: Some(ChatCompletionContent::Text(text)),
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            ..Default::default()
+        },
+        finish_reason,
+        response.response_id,
+        chrono::Utc::now().timestamp() as u32,
+        response.model_version,
+        GeminiModel::map_usage(response.usage_metadata.as_ref()),
+    )
+}
+
+fn map_operation(job: &BatchJob, operation: BatchOperation) -> BatchJobStatus {
+    let state = match (&operation.metadata, &operation.error) {
+        (_, Some(_)) => BatchJobState::Failed,
+        (Some(metadata), None) => map_state(&metadata.state),
+        (None, None) if operation.done => BatchJobState::Completed,
+        (None, None) => BatchJobState::Validating,
+    };
+
+    let output = operation
+        .response
+        .or_else(|| operation.metadata.and_then(|m| m.output));
+    let results = output
+        .and_then(|o| o.inlined_responses)
+        .map(|r| r.inlined_responses)
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+        .map(|(index, inlined)| map_result(index, inlined))
+        .collect();
+
+    BatchJobStatus {
+        job: job.clone(),
+        state,
+        results,
+    }
+}
+
+/// Submits `requests` as one batch with inlined requests. Gemini batches run a single
+/// model, so all requests must use the same one.
+pub async fn submit_batch(
+    model: &str,
+    requests: Vec<PreparedBatchRequest>,
+    input_variables: &HashMap<String, Value>,
+) -> LLMResult<BatchJob> {
+    let mut client = None;
+    let mut model_name = None;
+    let mut inlined = Vec::with_capacity(requests.len());
+    for request in requests {
+        let (params, execution_options, credentials, api_url) = match request.engine {
+            CompletionEngineParams::Gemini {
+                params,
+                execution_options,
+                credentials,
+                api_url,
+            } => (params, execution_options, credentials, api_url),
+            other => return Err(mixed_providers(&other)),
+        };
+
+        let request_model = params.model.clone().unwrap_or_default();
+        match &model_name {
+            None => model_name = Some(request_model),
+            Some(name) if *name != request_model => {
+                return Err(LLMError::CustomError(format!(
+                    "Gemini batch jobs run a single model, got {name} and {request_model}"
+                )))
+            }
+            Some(_) => {}
+        }
+        if client.is_none() {
+            client = Some(gemini_client(credentials.as_ref(), api_url.clone())?);
+        }
+
+        let gemini = GeminiModel::new(
+            params,
+            execution_options,
+            credentials.as_ref(),
+            HashMap::new(),
+            api_url,
+        )?;
+        let contents = gemini.construct_messages(input_variables.clone(), request.messages)?;
+        inlined.push(InlinedRequest {
+            request: gemini.build_request(contents)?,
+            metadata: InlinedRequestMetadata {
+                key: request.custom_id,
+            },
+        });
+    }
+
+    let (Some(client), Some(model_name)) = (client, model_name) else {
+        return Err(LLMError::CustomError(
+            "Batch job has no requests".to_string(),
+        ));
+    };
+    let operation = client
+        .batch_generate_content(
+            &model_name,
+            BatchGenerateContentRequest {
+                batch: GenerateContentBatch {
+                    display_name: format!("vllora-{}", uuid::Uuid::new_v4()),
+                    input_config: BatchInputConfig {
+                        requests: InlinedRequests { requests: inlined },
+                    },
+                },
+            },
+        )
+        .await?;
+
+    Ok(BatchJob {
+        id: operation.name,
+        provider: BatchProvider::Gemini,
+        model: model.to_string(),
+    })
+}
+
+/// Current state of `job`, with its results once it succeeded
+pub async fn poll_batch(
+    job: &BatchJob,
+    engine: &CompletionEngineParams,
+) -> LLMResult<BatchJobStatus> {
+    let CompletionEngineParams::Gemini {
+        credentials,
+        api_url,
+        ..
+    } = engine
+    else {
+        return Err(mixed_providers(engine));
+    };
+
+    let client = gemini_client(credentials.as_ref(), api_url.clone())?;
+    let operation = client.get_batch(&job.id).await?;
+    Ok(map_operation(job, operation))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::completions::batch::BatchOutcome;
+
+    fn job() -> BatchJob {
+        BatchJob {
+            id: "batches/123".to_string(),
+            provider: BatchProvider::Gemini,
+            model: "gemini/gemini-2.0-flash".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_batch_state_mapping() {
+        assert_eq!(map_state("BATCH_STATE_PENDING"), BatchJobState::Validating);
+        assert_eq!(map_state("BATCH_STATE_RUNNING"), BatchJobState::InProgress);
+        assert_eq!(map_state("JOB_STATE_SUCCEEDED"), BatchJobState::Completed);
+        assert_eq!(map_state("BATCH_STATE_FAILED"), BatchJobState::Failed);
+    }
+
+    #[test]
+    fn test_inlined_responses_map_to_completion_responses() {
+        let operation: BatchOperation = serde_json::from_value(serde_json::json!({
+            "name": "batches/123",
+            "done": true,
+            "metadata": {"state": "BATCH_STATE_SUCCEEDED"},
+            "response": {
+                "inlinedResponses": {
+                    "inlinedResponses": [
+                        {
+                            "metadata": {"key": "request-1"},
+                            "response": {
+                                "candidates": [{
+                                    "content": {"role": "model", "parts": [{"text": "Paris"}]},
+                                    "finishReason": "STOP"
+                                }],
+                                "usageMetadata": {
+                                    "promptTokenCount": 10,
+                                    "candidatesTokenCount": 2,
+                                    "totalTokenCount": 12
+                                },
+                                "modelVersion": "gemini-2.0-flash",
+                                "responseId": "resp-1"
+                            }
+                        },
+                        {
+                            "metadata": {"key": "request-2"},
+                            "error": {"code": 400, "message": "Invalid argument"}
+                        }
+                    ]
+                }
+            }
+        }))
+        .unwrap();
+
+        let status = map_operation(&job(), operation);
+        assert_eq!(status.state, BatchJobState::Completed);
+        assert_eq!(status.results.len(), 2);
+
+        let BatchOutcome::Succeeded { response } = &status.results[0].outcome else {
+            panic!("Expected a succeeded result");
+        };
+        assert_eq!(status.results[0].custom_id, "request-1");
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text("Paris".to_string()))
+        );
+        assert_eq!(*response.finish_reason(), ModelFinishReason::Stop);
+        assert_eq!(response.usage().unwrap().output_tokens, 2);
+
+        assert_eq!(
+            status.results[1].outcome,
+            BatchOutcome::Errored {
+                message: "Invalid argument".to_string()
+            }
+        );
+    }
+}
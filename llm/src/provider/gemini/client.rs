@@ -1,7 +1,8 @@
 use super::types::{
-    CountTokensRequest, CountTokensResponse, GenerateContentRequest, GenerateContentResponse,
-    ModelsResponse,
+    BatchGenerateContentRequest, BatchOperation, CountTokensRequest, CountTokensResponse,
+    GenerateContentRequest, GenerateContentResponse, ModelsResponse,
 };
+use crate::client::error::{GeminiError, ModelError};
 use crate::error::LLMError;
 use crate::error::LLMResult;
 use crate::provider::gemini::types::{CreateEmbeddingRequest, CreateEmbeddingResponse};
@@ -33,7 +34,7 @@ impl Client {
     pub fn new(api_key: String, api_url: Option<String>) -> Self {
         Self {
             api_key,
-            client: reqwest::Client::new(),
+            client: crate::provider::shared_http_client(),
             api_url: api_url.unwrap_or_else(|| API_URL.to_string()),
         }
     }
@@ -44,7 +45,17 @@ impl Client {
         payload: Option<P>,
         method: Method,
     ) -> LLMResult<T> {
-        let url = format!("{}{path}?key={}", self.api_url, self.api_key);
+        let url = format!("{}{path}", self.api_url);
+        self.request_url(url, payload, method).await
+    }
+
+    async fn request_url<T: serde::de::DeserializeOwned, P: Serialize>(
+        &self,
+        url: String,
+        payload: Option<P>,
+        method: Method,
+    ) -> LLMResult<T> {
+        let url = format!("{url}?key={}", self.api_key);
 
         let resp = match method {
             Method::Get => self.client.get(url),
@@ -73,9 +84,7 @@ impl Client {
             Span::current().record("error_payload", msg.clone());
             tracing::error!(target: "gemini", "{msg}. Payload: {p}");
 
-            return Err(LLMError::CustomError(format!(
-                "Request failed with status: {status}"
-            )));
+            return Err(ModelError::from(GeminiError::from_response(status.as_u16(), &msg)).into());
         }
 
         let text = resp.text().await?;
@@ -120,6 +129,22 @@ impl Client {
             .await
     }
 
+    pub async fn batch_generate_content(
+        &self,
+        model_name: &str,
+        payload: BatchGenerateContentRequest,
+    ) -> LLMResult<BatchOperation> {
+        let url = format!("/{model_name}:batchGenerateContent");
+        self.make_request(&url, Some(&payload), Method::Post).await
+    }
+
+    /// Batches are not nested under models, `name` is of the form `batches/{id}`
+    pub async fn get_batch(&self, name: &str) -> LLMResult<BatchOperation> {
+        let base_url = self.api_url.trim_end_matches("/models");
+        self.request_url(format!("{base_url}/{name}"), None::<Value>, Method::Get)
+            .await
+    }
+
     pub async fn embeddings(
         &self,
         model_name: &str,
@@ -175,7 +200,7 @@ impl Client {
                     Some(Err(Error::StreamEnded)) => None,
                     Some(Err(e)) => {
                         let err_str = e.to_string();
-                        let err_str = match e {
+                        let error = match e {
                             reqwest_eventsource::Error::InvalidStatusCode(_, r) => {
                                 let status = r.status();
                                 let error = r.text().await.unwrap_or(err_str);
@@ -183,15 +208,20 @@ impl Client {
                                 tracing::error!(target: "gemini", "Gemini error: {error}");
 
                                 if status == StatusCode::NOT_FOUND {
-                                    "Gemini model not found".to_string()
+                                    LLMError::CustomError("Gemini model not found".to_string())
                                 } else {
-                                    error
+                                    ModelError::from(GeminiError::from_response(
+                                        status.as_u16(),
+                                        &error,
+                                    ))
+                                    .into()
                                 }
                             }
-                            _ => err_str,
+                            reqwest_eventsource::Error::Transport(e) => LLMError::ReqwestError(e),
+                            _ => LLMError::CustomError(err_str),
                         };
 
-                        Some((Err(LLMError::CustomError(err_str)), event_source))
+                        Some((Err(error), event_source))
                     }
                     _ => None,
                 }
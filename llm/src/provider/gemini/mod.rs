@@ -1,3 +1,4 @@
+pub mod batch;
 pub mod client;
 pub mod model;
 pub mod types;
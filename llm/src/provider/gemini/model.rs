@@ -3,24 +3,29 @@ use super::types::{
     Content, FinishReason, GenerateContentRequest, GenerateContentResponse, Part,
     PartFunctionResponse, UsageMetadata,
 };
-use crate::client::completions::response_stream::ResultStream;
+use crate::client::completions::response_stream::{stream_until_closed, ResultStream};
+use crate::client::documents::{Document, DocumentError, DocumentSource};
 use crate::client::error::AuthorizationError;
 use crate::client::error::ModelError;
+use crate::client::role_normalization::normalize_for_provider;
 use crate::client::tools::handler::handle_tool_call;
 use crate::client::DEFAULT_MAX_RETRIES;
 use crate::error::LLMError;
 use crate::error::LLMResult;
 use crate::error::ModelFinishError;
 use crate::provider::gemini::types::{
-    Candidate, FunctionDeclaration, GenerationConfig, PartWithThought, Role, Tools,
+    Candidate, FunctionDeclaration, GenerationConfig, PartWithThought, Role, ThinkingConfig, Tools,
 };
+use crate::provider::retry::retry_policy;
+use crate::provider::ToolIterations;
 use crate::types::credentials::ApiKeyCredentials;
 use crate::types::credentials_ident::CredentialsIdent;
 use crate::types::engine::render;
 use crate::types::engine::{ExecutionOptions, GeminiModelParams};
 use crate::types::gateway::{
     ChatCompletionChunk, ChatCompletionChunkChoice, ChatCompletionContent, ChatCompletionDelta,
-    ChatCompletionMessage, ChatCompletionMessageWithFinishReason, GatewayModelUsage, ToolCall,
+    ChatCompletionMessage, ChatCompletionMessageWithFinishReason, CompletionTokensDetails,
+    GatewayModelUsage, PromptTokensDetails, ToolCall,
 };
 use crate::types::instance::ModelInstance;
 use crate::types::message::{AudioFormat, InnerMessage, Message, MessageContentPartOptions};
@@ -28,8 +33,8 @@ use crate::types::message::{MessageContentType, MessageType};
 use crate::types::tools::Tool;
 use crate::types::{GoogleToolCallExtra, LLMFirstToken, ToolCallExtra};
 use crate::types::{
-    LLMContentEvent, LLMFinishEvent, LLMStartEvent, ModelEvent, ModelEventType, ModelFinishReason,
-    ModelToolCall,
+    LLMContentEvent, LLMFinishEvent, LLMReasoningEvent, LLMStartEvent, ModelEvent, ModelEventType,
+    ModelFinishReason, ModelToolCall,
 };
 use async_openai::types::chat::ResponseFormat;
 use async_trait::async_trait;
@@ -141,7 +146,10 @@ impl GeminiModel {
         .await
     }
 
-    fn build_request(&self, messages: Vec<Content>) -> LLMResult<GenerateContentRequest> {
+    pub(crate) fn build_request(
+        &self,
+        messages: Vec<Content>,
+    ) -> LLMResult<GenerateContentRequest> {
         let model_params = &self.params;
         let response_schema = match &model_params.response_format {
             Some(ResponseFormat::JsonSchema { json_schema }) => {
@@ -176,6 +184,13 @@ impl GeminiModel {
                 None
             },
             response_schema,
+            thinking_config: model_params
+                .thinking
+                .as_ref()
+                .map(|thinking| ThinkingConfig {
+                    include_thoughts: Some(true),
+                    thinking_budget: Some(thinking.budget_tokens as i32),
+                }),
         };
 
         let tools = if self.tools.is_empty() {
@@ -262,11 +277,22 @@ impl GeminiModel {
                             model: model_version.clone(),
                             choices: vec![],
                             usage: None,
+                            tool_event: None,
                         };
 
                         for candidate in &res.candidates {
                             for part in &candidate.content.parts {
                                 match &part.part {
+                                    Part::Text(text) if part.is_thought() => {
+                                        let _ = tx
+                                            .send(Some(ModelEvent::new(
+                                                &Span::current(),
+                                                ModelEventType::LlmReasoning(LLMReasoningEvent {
+                                                    content: text.to_owned(),
+                                                }),
+                                            )))
+                                            .await;
+                                    }
                                     Part::Text(text) => {
                                         content.push_str(text);
                                         let _ = tx
@@ -331,6 +357,7 @@ impl GeminiModel {
                         args: args.clone(),
                     },
                     thought_signature: thought_signature.clone(),
+                    thought: None,
                 });
             }
 
@@ -412,6 +439,8 @@ impl GeminiModel {
             }
             for part in candidate.content.parts {
                 match part.part {
+                    // Thought summaries are not part of the answer
+                    Part::Text(_) if part.thought.unwrap_or(false) => {}
                     Part::Text(t) => {
                         text.push_str(&t);
                     }
@@ -437,6 +466,7 @@ impl GeminiModel {
                     parts: vec![PartWithThought {
                         part: Part::FunctionCall { name, args },
                         thought_signature: thought_signature.clone(),
+                        thought: None,
                     }],
                 });
             }
@@ -467,6 +497,9 @@ impl GeminiModel {
                 target: target!(),
                 parent: span.clone(),
                 events::SPAN_TOOLS,
+                cache=tracing::field::Empty,
+                validation_errors=tracing::field::Empty,
+                error_payload=tracing::field::Empty,
                 tool_calls=tool_calls_str,
                 tool.name=name
             );
@@ -474,12 +507,7 @@ impl GeminiModel {
             let tool = self.tools.get(&calls[0].0);
             if let Some(tool) = tool {
                 if tool.stop_at_call() {
-                    let usage = response.usage_metadata.as_ref().map(|u| GatewayModelUsage {
-                        input_tokens: u.prompt_token_count,
-                        output_tokens: (u.total_token_count - u.prompt_token_count),
-                        total_tokens: u.total_token_count,
-                        ..Default::default()
-                    });
+                    let usage = Self::map_usage(response.usage_metadata.as_ref());
                     let finish_reason = ModelFinishReason::ToolCalls;
                     let _ = tx
                         .send(Some(ModelEvent::new(
@@ -508,6 +536,7 @@ impl GeminiModel {
                                     })
                                     .collect::<Result<Vec<ModelToolCall>, LLMError>>()?,
                                 credentials_ident: self.credentials_ident.clone(),
+                                rate_limit: None,
                             }),
                         )))
                         .await;
@@ -574,12 +603,7 @@ impl GeminiModel {
 
         match finish_reason {
             Some(FinishReason::Stop) | Some(FinishReason::MaxTokens) => {
-                let usage = response.usage_metadata.as_ref().map(|u| GatewayModelUsage {
-                    input_tokens: u.prompt_token_count,
-                    output_tokens: (u.total_token_count - u.prompt_token_count),
-                    total_tokens: u.total_token_count,
-                    ..Default::default()
-                });
+                let usage = Self::map_usage(response.usage_metadata.as_ref());
 
                 let finish_reason = Self::map_finish_reason(
                     &finish_reason.expect("Finish reason is already checked"),
@@ -601,6 +625,7 @@ impl GeminiModel {
                             finish_reason: finish_reason.clone(),
                             tool_calls: vec![],
                             credentials_ident: self.credentials_ident.clone(),
+                            rate_limit: None,
                         }),
                     )))
                     .await;
@@ -640,6 +665,7 @@ impl GeminiModel {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some(call) = gemini_calls.pop() {
             let span = create_model_span!(SPAN_GEMINI, target!("chat"), &tags, retries_left);
 
@@ -655,12 +681,13 @@ impl GeminiModel {
             match result.map_err(|e| record_map_err(e, span.clone())) {
                 Ok(InnerExecutionResult::Finish(message)) => return Ok(*message),
                 Ok(InnerExecutionResult::NextCall(messages)) => {
+                    tool_iterations.next(&span)?;
                     gemini_calls.push(messages);
                     continue;
                 }
                 Err(e) => {
                     span.record("error", e.to_string());
-                    if retries_left == 0 {
+                    if retries_left == 0 || !retry_policy().is_retryable(&e) {
                         return Err(e);
                     } else {
                         gemini_calls.push(call);
@@ -714,11 +741,17 @@ impl GeminiModel {
         }
     }
 
-    fn map_usage(usage: Option<&UsageMetadata>) -> Option<GatewayModelUsage> {
+    pub(crate) fn map_usage(usage: Option<&UsageMetadata>) -> Option<GatewayModelUsage> {
         usage.map(|u| GatewayModelUsage {
             input_tokens: u.prompt_token_count,
             output_tokens: (u.total_token_count - u.prompt_token_count),
             total_tokens: u.total_token_count,
+            prompt_tokens_details: u
+                .cached_content_token_count
+                .map(|t| PromptTokensDetails::new(Some(t), None, None)),
+            completion_tokens_details: u
+                .thoughts_token_count
+                .map(|t| CompletionTokensDetails::new(None, None, Some(t), None)),
             ..Default::default()
         })
     }
@@ -779,6 +812,7 @@ impl GeminiModel {
                 model: response.model_version.clone(),
                 choices: vec![],
                 usage: None,
+                tool_event: None,
             };
 
             let mut chunk_clone = chunk.clone();
@@ -824,6 +858,7 @@ impl GeminiModel {
                     finish_reason: trace_finish_reason.clone(),
                     tool_calls: tool_calls.iter().map(Self::map_tool_call).collect(),
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             )))
             .await;
@@ -862,6 +897,9 @@ impl GeminiModel {
                 target: target!(),
                 parent: call_span.id(),
                 events::SPAN_TOOLS,
+                cache=tracing::field::Empty,
+                validation_errors=tracing::field::Empty,
+                error_payload=tracing::field::Empty,
                 tool_calls=tool_calls_str,
                 tool.name=name
             );
@@ -942,6 +980,7 @@ impl GeminiModel {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some(call) = gemini_calls.pop() {
             let span = create_model_span!(SPAN_GEMINI, target!("chat"), &tags, retries_left);
 
@@ -957,12 +996,13 @@ impl GeminiModel {
             match result.map_err(|e| record_map_err(e, span.clone())) {
                 Ok(InnerExecutionResult::Finish(_)) => return Ok(()),
                 Ok(InnerExecutionResult::NextCall(messages)) => {
+                    tool_iterations.next(&span)?;
                     gemini_calls.push(messages);
                     continue;
                 }
                 Err(e) => {
                     span.record("error", e.to_string());
-                    if retries_left == 0 {
+                    if retries_left == 0 || !retry_policy().is_retryable(&e) {
                         return Err(e);
                     } else {
                         gemini_calls.push(call);
@@ -975,97 +1015,99 @@ impl GeminiModel {
         Ok(())
     }
 
+    /// Maps the history to Gemini turns. Histories can come from another provider, e.g.
+    /// when a tool loop fails over to Gemini: function calls are named after their tool,
+    /// responses are matched to them by tool call id, and consecutive tool results are sent
+    /// as one user turn.
     fn map_previous_messages(
         messages_dto: Vec<Message>,
         input_variables: HashMap<String, Value>,
     ) -> LLMResult<Vec<Content>> {
-        // convert serde::Map into HashMap
         let mut messages = vec![];
-        let mut tool_results_remaining = 0;
-        let mut tool_calls_collected = vec![];
+        let mut tool_names: HashMap<String, String> = HashMap::new();
+        let mut tool_results: Vec<PartWithThought> = vec![];
         for m in messages_dto.iter() {
-            let request_message = {
-                match m.r#type {
-                    MessageType::SystemMessage => Some(Content::user(render(
-                        m.content.clone().unwrap_or_default(),
-                        &input_variables,
-                    ))),
-
-                    MessageType::AIMessage => {
-                        if let Some(tool_calls) = &m.tool_calls {
-                            tool_results_remaining = tool_calls.len();
-                            tool_calls_collected = vec![];
-                            Some(Content {
-                                role: Role::Model,
-                                parts: tool_calls
-                                    .iter()
-                                    .map(|c| {
-                                        let args = if c.function.arguments.is_empty() {
-                                            "{}"
-                                        } else {
-                                            &c.function.arguments
-                                        };
-                                        Ok(PartWithThought {
-                                            part: Part::FunctionCall {
-                                                name: c.id.clone(),
-                                                args: serde_json::from_str(args)?,
-                                            },
-                                            thought_signature: c.extra_content.as_ref().and_then(
-                                                |e| {
-                                                    e.google
-                                                        .as_ref()
-                                                        .map(|g| g.thought_signature.clone())
-                                                },
-                                            ),
-                                        })
-                                    })
-                                    .collect::<Result<Vec<PartWithThought>, LLMError>>()?,
+            if m.r#type != MessageType::ToolResult && !tool_results.is_empty() {
+                messages.push(Content::user_with_multiple_parts(std::mem::take(
+                    &mut tool_results,
+                )));
+            }
+
+            match m.r#type {
+                MessageType::SystemMessage => messages.push(Content::user(render(
+                    m.content.clone().unwrap_or_default(),
+                    &input_variables,
+                ))),
+                MessageType::AIMessage => {
+                    if let Some(tool_calls) = &m.tool_calls {
+                        let text = m
+                            .content
+                            .as_ref()
+                            .filter(|content| !content.is_empty())
+                            .map(|content| Ok(Part::Text(content.clone()).into()));
+                        let function_calls = tool_calls.iter().map(|c| {
+                            tool_names.insert(c.id.clone(), c.function.name.clone());
+                            // Some providers send no arguments for tools without parameters
+                            let args = match c.function.arguments.trim() {
+                                "" => HashMap::new(),
+                                arguments => serde_json::from_str(arguments)?,
+                            };
+                            Ok(PartWithThought {
+                                part: Part::FunctionCall {
+                                    name: c.function.name.clone(),
+                                    args,
+                                },
+                                thought_signature: c.extra_content.as_ref().and_then(|e| {
+                                    e.google.as_ref().map(|g| g.thought_signature.clone())
+                                }),
+                                thought: None,
                             })
-                        } else {
-                            match &m.content {
-                                Some(content) if !content.is_empty() => {
-                                    Some(Content::model(content.clone()))
-                                }
-                                _ => None,
+                        });
+                        messages.push(Content {
+                            role: Role::Model,
+                            parts: text.into_iter().chain(function_calls).collect::<Result<
+                                Vec<PartWithThought>,
+                                LLMError,
+                            >>(
+                            )?,
+                        });
+                    } else {
+                        match &m.content {
+                            Some(content) if !content.is_empty() => {
+                                messages.push(Content::model(content.clone()))
                             }
+                            _ => {}
                         }
                     }
-                    MessageType::HumanMessage => Some(construct_user_message(&m.clone().into())),
-                    MessageType::ToolResult => {
-                        tool_results_remaining -= 1;
-                        let content =
-                            serde_json::to_value(m.content.clone().unwrap_or_default()).unwrap();
-                        tool_calls_collected.push(
-                            Part::FunctionResponse {
-                                name: m.tool_call_id.clone().unwrap_or_default(),
-                                response: Some(PartFunctionResponse {
-                                    fields: HashMap::from([(
-                                        "content
-                                        "
-                                        .to_string(),
-                                        content,
-                                    )]),
-                                }),
-                            }
-                            .into(),
-                        );
-                        if tool_results_remaining == 0 {
-                            Some(Content {
-                                role: Role::User,
-                                parts: tool_calls_collected.clone(),
-                            })
-                        } else {
-                            None
+                }
+                MessageType::HumanMessage => {
+                    messages.push(construct_user_message(&m.clone().into())?)
+                }
+                MessageType::ToolResult => {
+                    let tool_call_id = m
+                        .tool_call_id
+                        .as_ref()
+                        .ok_or(LLMError::ToolCallIdNotFound)?;
+                    // Gemini names its tool calls after the tool
+                    let name = tool_names.get(tool_call_id).unwrap_or(tool_call_id).clone();
+                    let content = serde_json::to_value(m.content.clone().unwrap_or_default())?;
+                    tool_results.push(
+                        Part::FunctionResponse {
+                            name,
+                            response: Some(PartFunctionResponse {
+                                fields: HashMap::from([("content".to_string(), content)]),
+                            }),
                         }
-                    }
+                        .into(),
+                    );
                 }
-            };
-
-            if let Some(request_message) = request_message {
-                messages.push(request_message);
             }
         }
 
+        if !tool_results.is_empty() {
+            messages.push(Content::user_with_multiple_parts(tool_results));
+        }
+
         Ok(messages)
     }
 }
@@ -1098,13 +1140,11 @@ impl ModelInstance for GeminiModel {
         let tx_clone = tx.clone();
         tokio::spawn(
             async move {
-                let result = model
-                    .execute_stream(conversational_messages, tx_clone, &tx_response, tags)
-                    .await;
-
-                if let Err(e) = result {
-                    let _ = tx_response.send(Err(e)).await;
-                }
+                stream_until_closed(
+                    &tx_response,
+                    model.execute_stream(conversational_messages, tx_clone, &tx_response, tags),
+                )
+                .await;
             }
             .instrument(tracing::Span::current()),
         );
@@ -1114,11 +1154,19 @@ impl ModelInstance for GeminiModel {
 }
 
 impl GeminiModel {
-    fn construct_messages(
+    pub(crate) fn construct_messages(
         &self,
         input_variables: HashMap<String, Value>,
         previous_messages: Vec<Message>,
     ) -> LLMResult<Vec<Content>> {
+        // Gemini has no speaker names, they are kept in the content
+        let previous_messages = normalize_for_provider(
+            previous_messages
+                .into_iter()
+                .map(Message::fold_name)
+                .collect(),
+            self.execution_options.role_policy.unwrap_or_default(),
+        );
         let mut conversational_messages = vec![];
         let previous_messages = Self::map_previous_messages(previous_messages, input_variables)?;
         conversational_messages.extend(previous_messages);
@@ -1127,8 +1175,8 @@ impl GeminiModel {
     }
 }
 
-fn construct_user_message(m: &InnerMessage) -> Content {
-    match m {
+fn construct_user_message(m: &InnerMessage) -> Result<Content, ModelError> {
+    Ok(match m {
         InnerMessage::Text(text) => Content::user(text.to_string()),
         InnerMessage::Array(content_array) => {
             let mut parts = vec![];
@@ -1161,7 +1209,16 @@ fn construct_user_message(m: &InnerMessage) -> Content {
                         }
                     }
                     MessageContentType::File => {
-                        todo!()
+                        let file = m.file.as_ref().ok_or(DocumentError::MissingData)?;
+                        match Document::from_file(file)?.source {
+                            DocumentSource::Inline { media_type, data } => Part::InlineData {
+                                mime_type: media_type,
+                                data,
+                            },
+                            DocumentSource::FileId(_) => {
+                                return Err(DocumentError::unsupported("Gemini", "file ids").into())
+                            }
+                        }
                     }
                 };
                 parts.push(msg.into())
@@ -1171,7 +1228,7 @@ fn construct_user_message(m: &InnerMessage) -> Content {
                 parts,
             }
         }
-    }
+    })
 }
 
 pub fn record_map_err(e: impl Into<LLMError> + ToString, span: tracing::Span) -> LLMError {
@@ -1371,7 +1428,86 @@ fn normalize_nullable_types(schema: Value) -> Value {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::completions::CompletionsClient;
     use crate::provider::tests::MockStreamServer;
+    use crate::types::credentials::Credentials;
+    use crate::types::engine::CompletionEngineParamsBuilder;
+    use crate::types::gateway::ChatCompletionRequest;
+    use crate::types::models::InferenceProvider;
+    use crate::types::provider::InferenceModelProvider;
+
+    /// History of a tool loop started on OpenAI, as sent after failing over
+    fn openai_tool_history(model_name: &str) -> Vec<Message> {
+        let messages: Vec<crate::types::gateway::ChatCompletionMessage> =
+            serde_json::from_value(serde_json::json!([
+                {"role": "user", "content": "Weather in Paris and the time?"},
+                {"role": "assistant", "content": "Let me check.", "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+                    {"id": "call_2", "type": "function", "function": {"name": "get_time", "arguments": ""}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18C"},
+                {"role": "tool", "tool_call_id": "call_2", "content": "14:00"},
+                {"role": "user", "content": "Thanks"},
+            ]))
+            .unwrap();
+        messages
+            .iter()
+            .map(|m| {
+                crate::client::message_mapper::MessageMapper::map_completions_message_to_vllora_message(
+                    m, model_name, "user",
+                )
+                .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_openai_tool_loop_continues_on_gemini() {
+        let contents = GeminiModel::map_previous_messages(
+            openai_tool_history("gemini-2.0-flash"),
+            HashMap::new(),
+        )
+        .unwrap();
+
+        let roles: Vec<&Role> = contents.iter().map(|c| &c.role).collect();
+        assert!(matches!(
+            roles.as_slice(),
+            [Role::User, Role::Model, Role::User, Role::User]
+        ));
+
+        let calls: Vec<&Part> = contents[1].parts.iter().map(|p| &p.part).collect();
+        assert_eq!(calls[0], &Part::Text("Let me check.".to_string()));
+        assert_eq!(
+            calls[1],
+            &Part::FunctionCall {
+                name: "get_weather".to_string(),
+                args: HashMap::from([("city".to_string(), serde_json::json!("Paris"))]),
+            }
+        );
+        assert_eq!(
+            calls[2],
+            &Part::FunctionCall {
+                name: "get_time".to_string(),
+                args: HashMap::new(),
+            }
+        );
+
+        // Both results answer their calls by tool name in a single turn
+        let results: Vec<&Part> = contents[2].parts.iter().map(|p| &p.part).collect();
+        assert_eq!(results.len(), 2);
+        let Part::FunctionResponse { name, response } = results[1] else {
+            panic!("expected a function response, got {:?}", results[1]);
+        };
+        assert_eq!(name, "get_time");
+        assert_eq!(
+            response.as_ref().unwrap().fields["content"],
+            serde_json::json!("14:00")
+        );
+        assert!(matches!(
+            results[0],
+            Part::FunctionResponse { name, .. } if name == "get_weather"
+        ));
+    }
 
     fn get_instance(url: &str) -> GeminiModel {
         GeminiModel::new(
@@ -1438,4 +1574,100 @@ mod tests {
         assert_eq!(index, full_events.len());
         drop(server);
     }
+
+    #[tokio::test]
+    async fn test_thought_parts_stream_as_reasoning() {
+        let full_events = vec![
+            r#"{"candidates": [{"content": {"parts": [{"text": "Comparing the two numbers", "thought": true}],"role": "model"}}],"modelVersion": "gemini-2.5-flash","responseId": "resp-1"}"#.to_string(),
+            r#"{"candidates": [{"content": {"parts": [{"text": "9.11 is smaller"}],"role": "model"},"finishReason": "STOP"}],"usageMetadata": {"promptTokenCount": 10,"candidatesTokenCount": 4,"thoughtsTokenCount": 6,"totalTokenCount": 20},"modelVersion": "gemini-2.5-flash","responseId": "resp-1"}"#.to_string(),
+        ];
+
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server.set_events(full_events).await;
+        let instance = get_instance(&server.url());
+
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let mut stream = instance
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to stream");
+
+        let mut deltas = vec![];
+        while let Some(Ok(chunk)) = stream.next().await {
+            deltas.push(chunk.choices[0].delta.clone());
+        }
+        assert_eq!(deltas[0].content, None);
+        assert_eq!(
+            deltas[0].reasoning_content.as_deref(),
+            Some("Comparing the two numbers")
+        );
+        assert_eq!(deltas[1].content.as_deref(), Some("9.11 is smaller"));
+
+        let mut reasoning = String::new();
+        let mut content = String::new();
+        let mut usage = None;
+        while let Some(Some(event)) = rx.recv().await {
+            match event.event {
+                ModelEventType::LlmReasoning(e) => reasoning.push_str(&e.content),
+                ModelEventType::LlmContent(e) => content.push_str(&e.content),
+                ModelEventType::LlmStop(e) => {
+                    usage = e.usage;
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(reasoning, "Comparing the two numbers");
+        assert_eq!(content, "9.11 is smaller");
+        let usage = usage.expect("Expected usage on stop");
+        assert_eq!(
+            usage
+                .completion_tokens_details
+                .map(|details| details.reasoning_tokens()),
+            Some(6)
+        );
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_stop_sequence_is_removed_from_gemini_stream() {
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server.set_events(vec![
+            r#"{"candidates": [{"content": {"parts": [{"text": "Roses are red\nEN"}],"role": "model"}}],"modelVersion": "gemini-2.0-flash","responseId": "resp-2"}"#.to_string(),
+            r#"{"candidates": [{"content": {"parts": [{"text": "D"}],"role": "model"},"finishReason": "STOP"}],"usageMetadata": {"promptTokenCount": 5,"candidatesTokenCount": 6,"totalTokenCount": 11},"modelVersion": "gemini-2.0-flash","responseId": "resp-2"}"#.to_string(),
+        ]).await;
+
+        let builder = CompletionEngineParamsBuilder::new()
+            .with_provider(InferenceProvider {
+                provider: InferenceModelProvider::Gemini,
+                model_name: "gemini-2.0-flash".to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            })
+            .with_model_name("gemini-2.0-flash".to_string())
+            .with_credentials(Credentials::ApiKey(ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }))
+            .with_api_url(server.url());
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gemini-2.0-flash",
+            "messages": [{"role": "user", "content": "Write a poem"}],
+            "stop": ["END"]
+        }))
+        .unwrap();
+
+        let response = CompletionsClient::new(builder)
+            .create_via_stream(request, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text("Roses are red\n".to_string()))
+        );
+        drop(server);
+    }
 }
@@ -47,6 +47,7 @@ impl Content {
             parts: vec![PartWithThought {
                 part: part.into(),
                 thought_signature: None,
+                thought: None,
             }],
         }
     }
@@ -56,6 +57,7 @@ impl Content {
             parts: vec![PartWithThought {
                 part: part.into(),
                 thought_signature: None,
+                thought: None,
             }],
         }
     }
@@ -90,6 +92,19 @@ pub struct GenerationConfig {
     pub logprobs: Option<i32>,
     pub response_mime_type: Option<String>,
     pub response_schema: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_config: Option<ThinkingConfig>,
+}
+
+/// Thinking settings of Gemini 2.5+ models. Thought summaries are only returned with
+/// `include_thoughts`.
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ThinkingConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_thoughts: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking_budget: Option<i32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -99,6 +114,15 @@ pub struct PartWithThought {
     pub part: Part,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thought_signature: Option<String>,
+    /// Set on text parts carrying a thought summary rather than the answer
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thought: Option<bool>,
+}
+
+impl PartWithThought {
+    pub fn is_thought(&self) -> bool {
+        self.thought.unwrap_or(false)
+    }
 }
 
 impl From<Part> for PartWithThought {
@@ -106,6 +130,7 @@ impl From<Part> for PartWithThought {
         Self {
             part,
             thought_signature: None,
+            thought: None,
         }
     }
 }
@@ -138,6 +163,77 @@ pub struct PartFunctionResponse {
     pub fields: HashMap<String, Value>,
 }
 
+// Reference: https://ai.google.dev/api/batch-mode
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchGenerateContentRequest {
+    pub batch: GenerateContentBatch,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GenerateContentBatch {
+    pub display_name: String,
+    pub input_config: BatchInputConfig,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchInputConfig {
+    pub requests: InlinedRequests,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InlinedRequests {
+    pub requests: Vec<InlinedRequest>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InlinedRequest {
+    pub request: GenerateContentRequest,
+    pub metadata: InlinedRequestMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InlinedRequestMetadata {
+    pub key: String,
+}
+
+/// Long running operation of a batch, `metadata` carries its state
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BatchOperation {
+    pub name: String,
+    #[serde(default)]
+    pub done: bool,
+    pub metadata: Option<BatchMetadata>,
+    pub response: Option<BatchOutput>,
+    pub error: Option<Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchMetadata {
+    pub state: String,
+    pub output: Option<BatchOutput>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOutput {
+    pub inlined_responses: Option<InlinedResponses>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InlinedResponses {
+    #[serde(default)]
+    pub inlined_responses: Vec<InlinedResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InlinedResponse {
+    pub response: Option<GenerateContentResponse>,
+    pub error: Option<Value>,
+    pub metadata: Option<InlinedRequestMetadata>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct GenerateContentResponse {
@@ -203,6 +299,9 @@ pub struct UsageMetadata {
     pub prompt_token_count: u32,
     pub total_token_count: u32,
     pub thoughts_token_count: Option<u32>,
+    /// Tokens of the prompt served from cached content, included in `prompt_token_count`
+    #[serde(default)]
+    pub cached_content_token_count: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
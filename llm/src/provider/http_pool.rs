@@ -0,0 +1,87 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+static HTTP_POOL: OnceLock<HttpPoolConfig> = OnceLock::new();
+
+/// Connection pool of the shared provider HTTP clients, see [`super::shared_http_client`]
+///
+/// ```yaml
+/// http_pool:
+///   max_idle_per_host: 64
+///   idle_timeout_secs: 90
+///   tcp_keepalive_secs: 60
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpPoolConfig {
+    /// Idle connections kept open per provider host
+    pub max_idle_per_host: usize,
+    /// Idle connections are closed after this long, `None` keeps them open
+    pub idle_timeout_secs: Option<u64>,
+    /// Interval of TCP keep-alive probes on open connections, `None` disables them
+    pub tcp_keepalive_secs: Option<u64>,
+}
+
+impl Default for HttpPoolConfig {
+    fn default() -> Self {
+        Self {
+            max_idle_per_host: 64,
+            idle_timeout_secs: Some(90),
+            tcp_keepalive_secs: Some(60),
+        }
+    }
+}
+
+impl HttpPoolConfig {
+    /// Client builder with the pool settings applied
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        reqwest::Client::builder()
+            .pool_max_idle_per_host(self.max_idle_per_host)
+            .pool_idle_timeout(self.idle_timeout_secs.map(Duration::from_secs))
+            .tcp_keepalive(self.tcp_keepalive_secs.map(Duration::from_secs))
+    }
+}
+
+/// Sets the pool settings of provider HTTP clients. Has no effect once a client was built.
+pub fn init_http_pool(config: HttpPoolConfig) {
+    if HTTP_POOL.set(config).is_err() {
+        tracing::warn!("HTTP pool settings were already initialized");
+    }
+}
+
+/// Pool settings of provider HTTP clients
+pub fn http_pool() -> &'static HttpPoolConfig {
+    HTTP_POOL.get_or_init(HttpPoolConfig::default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::tests::MockStreamServer;
+
+    async fn connections_for(config: &HttpPoolConfig, requests: usize) -> usize {
+        let server = MockStreamServer::start().await.unwrap();
+        server.set_payload("text/plain", vec![]).await;
+        let client = config.client_builder().build().unwrap();
+        for _ in 0..requests {
+            let response = client.get(server.url()).send().await.unwrap();
+            assert!(response.status().is_success());
+            response.bytes().await.unwrap();
+        }
+        server.connections()
+    }
+
+    #[tokio::test]
+    async fn test_sequential_requests_reuse_connection() {
+        assert_eq!(connections_for(&HttpPoolConfig::default(), 5).await, 1);
+
+        // Without idle connections kept, every request opens a new one
+        let no_pool = HttpPoolConfig {
+            max_idle_per_host: 0,
+            ..Default::default()
+        };
+        assert_eq!(connections_for(&no_pool, 3).await, 3);
+    }
+}
@@ -1,9 +1,84 @@
 pub mod anthropic;
 pub mod bedrock;
 pub mod gemini;
+pub mod http_pool;
 pub mod openai;
 pub mod openai_spec_client;
 pub mod proxy;
+pub mod retry;
 
-#[cfg(test)]
-pub(crate) mod tests;
+use std::sync::OnceLock;
+
+use tracing::Span;
+
+use crate::client::error::ModelError;
+use crate::client::DEFAULT_MAX_TOOL_ITERATIONS;
+use crate::error::{LLMError, LLMResult};
+use crate::provider::http_pool::http_pool;
+use crate::types::engine::ExecutionOptions;
+
+/// HTTP client shared by provider clients, so connection pools and TLS setup are reused
+/// across model instances instead of being rebuilt for every request
+pub fn shared_http_client() -> reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT
+        .get_or_init(|| {
+            http_pool().client_builder().build().unwrap_or_else(|e| {
+                tracing::warn!("Failed to apply HTTP pool settings: {e}");
+                reqwest::Client::new()
+            })
+        })
+        .clone()
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod tests;
+
+/// Headers configured in `execution_options`, set on every provider request sent through
+/// the [`shared_http_client`]
+pub(crate) fn forwarded_headers(
+    execution_options: &ExecutionOptions,
+) -> Result<reqwest::header::HeaderMap, ModelError> {
+    let mut header_map = reqwest::header::HeaderMap::new();
+    for (name, value) in &execution_options.headers {
+        let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| ModelError::CustomError(format!("Invalid header name {name}: {e}")))?;
+        let value = reqwest::header::HeaderValue::from_str(value).map_err(|e| {
+            ModelError::CustomError(format!("Invalid value for header {name}: {e}"))
+        })?;
+        header_map.insert(name, value);
+    }
+    Ok(header_map)
+}
+
+/// Counts the tool iterations of a model call, each one sending tool results back to the
+/// model, and stops models that keep calling tools
+pub(crate) struct ToolIterations {
+    count: u32,
+    max: u32,
+}
+
+impl ToolIterations {
+    pub(crate) fn new(execution_options: &ExecutionOptions) -> Self {
+        Self {
+            count: 0,
+            max: execution_options
+                .max_tool_iterations
+                .unwrap_or(DEFAULT_MAX_TOOL_ITERATIONS),
+        }
+    }
+
+    /// Counts the iteration whose tool results were produced in `span`, recorded as its
+    /// `tool_iterations`. Fails with [`LLMError::MaxToolIterations`], recorded as the
+    /// `error` of `span`, once the maximum is used up.
+    pub(crate) fn next(&mut self, span: &Span) -> LLMResult<()> {
+        if self.count == self.max {
+            let error = LLMError::MaxToolIterations(self.max);
+            span.record("error", error.to_string());
+            return Err(error);
+        }
+        self.count += 1;
+        span.record("tool_iterations", self.count);
+        Ok(())
+    }
+}
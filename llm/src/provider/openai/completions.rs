@@ -1,6 +1,8 @@
-use crate::client::completions::response_stream::ResultStream;
+use crate::client::completions::response_stream::{stream_until_closed, ResultStream};
+use crate::client::documents::{Document, DocumentError, DocumentSource};
 use crate::client::error::AuthorizationError;
 use crate::client::error::ModelError;
+use crate::client::role_normalization::normalize_for_provider;
 use crate::client::tools::handler::handle_tool_call;
 use crate::client::DEFAULT_MAX_RETRIES;
 use crate::error::LLMError;
@@ -8,6 +10,9 @@ use crate::error::{LLMResult, ModelFinishError};
 use crate::provider::openai::azure_openai_client;
 use crate::provider::openai::is_azure_endpoint;
 use crate::provider::openai::openai_client;
+use crate::provider::retry::retry_policy;
+use crate::provider::ToolIterations;
+use crate::provider::{forwarded_headers, shared_http_client};
 use crate::types::credentials::ApiKeyCredentials;
 use crate::types::credentials_ident::CredentialsIdent;
 use crate::types::engine::{render, ExecutionOptions, OpenAiModelParams};
@@ -20,6 +25,7 @@ use crate::types::gateway::{ChatCompletionMessageWithFinishReason, GatewayModelU
 use crate::types::instance::ModelInstance;
 use crate::types::message::{ImageDetail, MessageContentType, MessageType};
 use crate::types::message::{InnerMessage, Message};
+use crate::types::rate_limit::ProviderRateLimit;
 use crate::types::tools::Tool;
 use crate::types::{
     LLMContentEvent, LLMFinishEvent, LLMFirstToken, LLMStartEvent, ModelEvent, ModelEventType,
@@ -27,7 +33,7 @@ use crate::types::{
 };
 use async_openai::config::Config;
 use async_openai::config::{AzureConfig, OpenAIConfig};
-use async_openai::error::OpenAIError;
+use async_openai::error::{ApiError, OpenAIError, StreamError};
 use async_openai::types::chat::ChatCompletionMessageToolCalls;
 use async_openai::types::chat::ChatCompletionRequestToolMessageArgs;
 use async_openai::types::chat::ChatCompletionRequestUserMessageArgs;
@@ -47,12 +53,15 @@ use async_openai::types::chat::{
     FinishReason, FunctionCall, FunctionCallStream,
 };
 use async_openai::types::chat::{
-    ChatCompletionRequestMessageContentPartImage, CreateChatCompletionStreamResponse, ImageUrl,
+    ChatCompletionRequestMessageContentPartFile, ChatCompletionRequestMessageContentPartImage,
+    CreateChatCompletionStreamResponse, FileObject, ImageUrl,
 };
 use async_openai::Client;
 use async_trait::async_trait;
+use eventsource_stream::Eventsource;
 use futures::Stream;
 use futures::StreamExt;
+use serde::Deserialize;
 use serde_json::Value;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
@@ -82,6 +91,20 @@ macro_rules! target {
     };
 }
 
+/// Error body of OpenAI compatible APIs
+#[derive(Deserialize)]
+struct ApiErrorResponse {
+    error: ApiError,
+}
+
+/// Error of a response with an error status, like `async-openai` reads it
+fn api_error(body: &[u8]) -> OpenAIError {
+    match serde_json::from_slice::<ApiErrorResponse>(body) {
+        Ok(response) => OpenAIError::ApiError(response.error),
+        Err(e) => OpenAIError::JSONDeserialize(e, String::from_utf8_lossy(body).to_string()),
+    }
+}
+
 enum InnerExecutionResult {
     Finish(Box<ChatCompletionMessageWithFinishReason>),
     NextCall(Vec<ChatCompletionRequestMessage>),
@@ -92,6 +115,10 @@ pub struct OpenAIModel<C: Config = OpenAIConfig> {
     params: OpenAiModelParams,
     execution_options: ExecutionOptions,
     client: Client<C>,
+    /// Client used for calls whose response headers are read, shared by all models
+    http_client: reqwest::Client,
+    /// Headers configured in `execution_options`, set on every chat completion request
+    headers: reqwest::header::HeaderMap,
     tools: HashMap<String, Arc<Box<dyn Tool>>>,
     credentials_ident: CredentialsIdent,
 }
@@ -115,12 +142,15 @@ impl OpenAIModel<OpenAIConfig> {
             }
         }
 
+        let headers = forwarded_headers(&execution_options)?;
         let client = client.unwrap_or(openai_client(credentials, endpoint)?);
 
         Ok(Self {
             params,
             execution_options,
             client,
+            http_client: shared_http_client(),
+            headers,
             tools,
             credentials_ident: credentials
                 .map(|_c| CredentialsIdent::Own)
@@ -154,11 +184,14 @@ impl OpenAIModel<AzureConfig> {
                 "Azure OpenAI requires an endpoint URL".to_string(),
             ));
         };
+        let headers = forwarded_headers(&execution_options)?;
 
         Ok(Self {
             params,
             execution_options,
             client,
+            http_client: shared_http_client(),
+            headers,
             tools,
             credentials_ident: credentials
                 .map(|_c| CredentialsIdent::Own)
@@ -185,6 +218,29 @@ impl OpenAIModel<AzureConfig> {
     }
 }
 
+/// Header an aggregator like OpenRouter reports the provider it routed the request to in
+pub const UPSTREAM_PROVIDER_HEADER: &str = "x-openrouter-provider";
+
+/// Provider an aggregator routed the request to, reported in the response headers
+fn upstream_provider_header(headers: &reqwest::header::HeaderMap) -> Option<String> {
+    headers
+        .get(UPSTREAM_PROVIDER_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(ToString::to_string)
+}
+
+/// Provider an aggregator routed the request to, reported in the `provider` field of the
+/// response body by aggregators that don't send [`UPSTREAM_PROVIDER_HEADER`]
+fn upstream_provider(body: &[u8]) -> Option<String> {
+    #[derive(Deserialize)]
+    struct UpstreamProvider {
+        provider: Option<String>,
+    }
+    serde_json::from_slice::<UpstreamProvider>(body)
+        .ok()?
+        .provider
+}
+
 // Common implementation for all Config types
 impl<C: Config> OpenAIModel<C> {
     pub fn map_tool_call(tool_call: &ChatCompletionMessageToolCalls) -> ModelToolCall {
@@ -242,7 +298,48 @@ impl<C: Config> OpenAIModel<C> {
             .collect()
     }
 
-    #[tracing::instrument(level = "debug", skip(self))]
+    /// Adapts parameters for reasoning models, which only accept `max_completion_tokens`
+    /// and reject sampling parameters. Returns the names of the dropped parameters.
+    fn normalize_reasoning_params(params: &mut OpenAiModelParams) -> Vec<&'static str> {
+        let mut dropped = vec![];
+        if !params.reasoning_model {
+            return dropped;
+        }
+
+        if let Some(max_tokens) = params.max_tokens.take() {
+            params.max_completion_tokens.get_or_insert(max_tokens);
+        }
+        if params.temperature.take().is_some() {
+            dropped.push("temperature");
+        }
+        if params.top_p.take().is_some() {
+            dropped.push("top_p");
+        }
+        if params.presence_penalty.take().is_some() {
+            dropped.push("presence_penalty");
+        }
+        if params.frequency_penalty.take().is_some() {
+            dropped.push("frequency_penalty");
+        }
+        if params.logprobs.take().is_some() {
+            dropped.push("logprobs");
+        }
+        if params.top_logprobs.take().is_some() {
+            dropped.push("top_logprobs");
+        }
+
+        dropped
+    }
+
+    /// Records the configured parameters the model rejects on the model `span`, they are
+    /// left out by [`Self::build_request`]
+    fn record_dropped_params(&self, span: &Span) {
+        let dropped_params = Self::normalize_reasoning_params(&mut self.params.clone());
+        if !dropped_params.is_empty() {
+            span.record("dropped_params", dropped_params.join(","));
+        }
+    }
+
     fn build_request(
         &self,
         messages: &[ChatCompletionRequestMessage],
@@ -251,33 +348,47 @@ impl<C: Config> OpenAIModel<C> {
         let mut chat_completion_tools: Vec<ChatCompletionTools> = vec![];
 
         for (name, tool) in self.tools.iter() {
+            let strict = tool.strict();
+            // Strict mode needs the schema as declared, e.g. with `additionalProperties: false`
+            let parameters = if strict {
+                tool.parameters_schema()
+            } else {
+                tool.get_function_parameters()
+                    .map(|mut s| {
+                        if s.required.is_none() {
+                            s.required = Some(vec![]);
+                        }
+
+                        serde_json::to_value(s)
+                    })
+                    .transpose()?
+            };
             chat_completion_tools.push(ChatCompletionTools::Function(ChatCompletionTool {
                 function: FunctionObject {
                     name: name.to_owned(),
                     description: Some(tool.description()),
-                    parameters: tool
-                        .get_function_parameters()
-                        .map(|mut s| {
-                            if s.required.is_none() {
-                                s.required = Some(vec![]);
-                            }
-
-                            serde_json::to_value(s)
-                        })
-                        .transpose()?,
-                    strict: Some(false),
+                    parameters,
+                    strict: Some(strict),
                 },
             }));
         }
 
         let mut builder = CreateChatCompletionRequestArgs::default();
-        let model_params = &self.params;
+        let mut model_params = self.params.clone();
+        Self::normalize_reasoning_params(&mut model_params);
+        let model_params = &model_params;
         if let Some(max_tokens) = model_params.max_tokens {
             builder.max_tokens(max_tokens);
         }
+        if let Some(max_completion_tokens) = model_params.max_completion_tokens {
+            builder.max_completion_tokens(max_completion_tokens);
+        }
         if let Some(temperature) = model_params.temperature {
             builder.temperature(temperature);
         }
+        if let Some(top_p) = model_params.top_p {
+            builder.top_p(top_p);
+        }
 
         if let Some(logprobs) = model_params.logprobs {
             builder.logprobs(logprobs);
@@ -299,6 +410,28 @@ impl<C: Config> OpenAIModel<C> {
             builder.prompt_cache_key(prompt_cache_key.clone());
         }
 
+        if let Some(prediction) = &model_params.prediction {
+            builder.prediction(prediction.clone());
+        }
+
+        if let Some(service_tier) = model_params.service_tier {
+            builder.service_tier(serde_json::from_value::<
+                async_openai::types::chat::ServiceTier,
+            >(serde_json::to_value(service_tier)?)?);
+        }
+
+        if let Some(modalities) = &model_params.modalities {
+            builder.modalities(serde_json::from_value::<
+                Vec<async_openai::types::chat::ChatCompletionModalities>,
+            >(serde_json::to_value(modalities)?)?);
+        }
+
+        if let Some(audio) = &model_params.audio {
+            builder.audio(serde_json::from_value::<
+                async_openai::types::chat::ChatCompletionAudio,
+            >(serde_json::to_value(audio)?)?);
+        }
+
         if stream {
             builder.stream_options(ChatCompletionStreamOptions {
                 include_usage: Some(true),
@@ -318,9 +451,18 @@ impl<C: Config> OpenAIModel<C> {
                 ));
         }
 
-        Ok(builder
+        let mut request = builder
             .build()
-            .map_err(|e| ModelError::OpenAIApi(Box::new(e)))?)
+            .map_err(|e| ModelError::OpenAIApi(Box::new(e)))?;
+        if let Some(logit_bias) = &model_params.logit_bias {
+            request.logit_bias = Some(serde_json::from_value(serde_json::to_value(logit_bias)?)?);
+        }
+        if let Some(reasoning_effort) = model_params.reasoning_effort {
+            request.reasoning_effort = Some(serde_json::from_value(serde_json::to_value(
+                reasoning_effort,
+            )?)?);
+        }
+        Ok(request)
     }
 
     #[tracing::instrument(level = "debug", skip_all)]
@@ -409,6 +551,7 @@ impl<C: Config> OpenAIModel<C> {
                         model: response.model.clone(),
                         choices: vec![],
                         usage: None,
+                        tool_event: None,
                     };
 
                     if response.choices.is_empty() {
@@ -496,6 +639,7 @@ impl<C: Config> OpenAIModel<C> {
                                     .tool_calls
                                     .as_ref()
                                     .map(|t| t.iter().map(|t| t.into()).collect()),
+                                reasoning_content: None,
                             },
                             finish_reason: None,
                             logprobs: chat_choice.logprobs.clone(),
@@ -539,6 +683,107 @@ impl<C: Config> OpenAIModel<C> {
         let _ = tx.send(Some(event)).await;
     }
 
+    /// Sends `request` like `client.chat().create`, which keeps the response headers to
+    /// itself, and also returns the rate limits the provider reported in them
+    async fn create_chat_completion(
+        &self,
+        request: &CreateChatCompletionRequest,
+    ) -> (
+        Result<CreateChatCompletionResponse, OpenAIError>,
+        Option<ProviderRateLimit>,
+    ) {
+        let config = self.client.config();
+        let response = match self
+            .http_client
+            .post(config.url("/chat/completions"))
+            .query(&config.query())
+            .headers(config.headers())
+            .headers(self.headers.clone())
+            .json(request)
+            .send()
+            .await
+        {
+            Ok(response) => response,
+            Err(e) => return (Err(OpenAIError::Reqwest(e)), None),
+        };
+
+        let rate_limit = ProviderRateLimit::from_headers(response.headers());
+        let provider = upstream_provider_header(response.headers());
+        let status = response.status();
+        let bytes = match response.bytes().await {
+            Ok(bytes) => bytes,
+            Err(e) => return (Err(OpenAIError::Reqwest(e)), rate_limit),
+        };
+
+        let result = if status.is_success() {
+            if let Some(provider) = provider.or_else(|| upstream_provider(&bytes)) {
+                Span::current().record("upstream_provider", provider);
+            }
+            serde_json::from_slice(&bytes).map_err(|e| {
+                OpenAIError::JSONDeserialize(e, String::from_utf8_lossy(&bytes).to_string())
+            })
+        } else {
+            Err(api_error(&bytes))
+        };
+        (result, rate_limit)
+    }
+
+    /// Streams `request` like `client.chat().create_stream`, which keeps the response headers
+    /// to itself, and records the upstream provider reported in them or in the first chunk.
+    /// Also returns the rate limits the provider reported in the headers.
+    async fn create_chat_completion_stream(
+        &self,
+        request: &CreateChatCompletionRequest,
+    ) -> Result<
+        (
+            impl Stream<Item = Result<CreateChatCompletionStreamResponse, OpenAIError>> + Unpin,
+            Option<ProviderRateLimit>,
+        ),
+        OpenAIError,
+    > {
+        let config = self.client.config();
+        let response = self
+            .http_client
+            .post(config.url("/chat/completions"))
+            .query(&config.query())
+            .headers(config.headers())
+            .headers(self.headers.clone())
+            .json(request)
+            .send()
+            .await
+            .map_err(OpenAIError::Reqwest)?;
+        if !response.status().is_success() {
+            let bytes = response.bytes().await.map_err(OpenAIError::Reqwest)?;
+            return Err(api_error(&bytes));
+        }
+
+        let rate_limit = ProviderRateLimit::from_headers(response.headers());
+        let provider = upstream_provider_header(response.headers());
+        if let Some(provider) = &provider {
+            Span::current().record("upstream_provider", provider.as_str());
+        }
+        let mut read_provider = provider.is_none();
+        let events = response
+            .bytes_stream()
+            .eventsource()
+            .take_while(|event| {
+                futures::future::ready(!matches!(event, Ok(event) if event.data == "[DONE]"))
+            })
+            .map(move |event| {
+                let event = event.map_err(|e| {
+                    OpenAIError::StreamError(Box::new(StreamError::ReqwestEventSource(e.into())))
+                })?;
+                if std::mem::take(&mut read_provider) {
+                    if let Some(provider) = upstream_provider(event.data.as_bytes()) {
+                        Span::current().record("upstream_provider", provider);
+                    }
+                }
+                serde_json::from_str::<CreateChatCompletionStreamResponse>(&event.data)
+                    .map_err(|e| OpenAIError::JSONDeserialize(e, event.data))
+            });
+        Ok((Box::pin(events), rate_limit))
+    }
+
     #[tracing::instrument(level = "debug", skip_all)]
     async fn execute_inner(
         &self,
@@ -549,6 +794,7 @@ impl<C: Config> OpenAIModel<C> {
     ) -> LLMResult<InnerExecutionResult> {
         let call = self.build_request(&messages, false)?;
         span.record("request", serde_json::to_string(&call)?);
+        self.record_dropped_params(&span);
 
         let input_messages = call.messages.clone();
         let _ = tx
@@ -562,8 +808,11 @@ impl<C: Config> OpenAIModel<C> {
             )))
             .await;
 
-        let response = async move {
-            let result = self.client.chat().create(call).await;
+        let (response, rate_limit) = async move {
+            let (result, rate_limit) = self.create_chat_completion(&call).await;
+            if let Some(rate_limit) = &rate_limit {
+                Span::current().record("rate_limit", serde_json::to_string(rate_limit)?);
+            }
             let _ = result
                 .as_ref()
                 .map(|response| serde_json::to_value(response).unwrap())
@@ -585,7 +834,7 @@ impl<C: Config> OpenAIModel<C> {
                         .as_value(),
                 );
             }
-            Ok::<_, LLMError>(response)
+            Ok::<_, LLMError>((response, rate_limit))
         }
         .instrument(span.clone().or_current())
         .await?;
@@ -617,6 +866,9 @@ impl<C: Config> OpenAIModel<C> {
                     target: target!(),
                     parent: span.clone(),
                     events::SPAN_TOOLS,
+                    cache=tracing::field::Empty,
+                    validation_errors=tracing::field::Empty,
+                    error_payload=tracing::field::Empty,
                     tool_calls=JsonValue(&serde_json::to_value(&tool_calls)?).as_value(),
                     tool.name=tool_names
                 );
@@ -644,6 +896,7 @@ impl<C: Config> OpenAIModel<C> {
                             finish_reason: finish_reason.clone(),
                             tool_calls: tool_calls.iter().map(Self::map_tool_call).collect(),
                             credentials_ident: self.credentials_ident.clone(),
+                            rate_limit: rate_limit.clone(),
                         }),
                     )))
                     .await;
@@ -704,8 +957,23 @@ impl<C: Config> OpenAIModel<C> {
                 let finish_reason = Self::map_finish_reason(
                     &finish_reason.expect("Finish reason is already checked"),
                 );
-                let message_content = first_choice.message.content;
-                if let Some(content) = &message_content {
+                // Audio output comes with its transcript instead of content
+                let message_content = match first_choice.message.audio {
+                    Some(audio) => Some(ChatCompletionContent::audio_output(
+                        audio.transcript,
+                        audio.data,
+                        self.params
+                            .audio
+                            .as_ref()
+                            .map(|audio| audio.format.clone())
+                            .unwrap_or_default(),
+                    )),
+                    None => first_choice
+                        .message
+                        .content
+                        .map(ChatCompletionContent::Text),
+                };
+                if let Some(content) = message_content {
                     let usage = Self::map_usage(response.usage.as_ref());
                     let _ = tx
                         .send(Some(ModelEvent::new(
@@ -713,11 +981,12 @@ impl<C: Config> OpenAIModel<C> {
                             ModelEventType::LlmStop(LLMFinishEvent {
                                 provider_name: SPAN_OPENAI.to_string(),
                                 model_name: self.params.model.clone().unwrap_or_default(),
-                                output: Some(content.clone()),
+                                output: content.as_string(),
                                 usage: usage.clone(),
                                 finish_reason: finish_reason.clone(),
                                 tool_calls: vec![],
                                 credentials_ident: self.credentials_ident.clone(),
+                                rate_limit: rate_limit.clone(),
                             }),
                         )))
                         .await;
@@ -726,7 +995,7 @@ impl<C: Config> OpenAIModel<C> {
                         ChatCompletionMessageWithFinishReason::new(
                             ChatCompletionMessage {
                                 role: "assistant".to_string(),
-                                content: Some(ChatCompletionContent::Text(content.to_string())),
+                                content: Some(content),
                                 ..Default::default()
                             },
                             finish_reason,
@@ -760,6 +1029,7 @@ impl<C: Config> OpenAIModel<C> {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some(messages) = openai_calls.pop() {
             let input = serde_json::to_string(&messages)?;
             let span = create_model_span!(
@@ -776,11 +1046,12 @@ impl<C: Config> OpenAIModel<C> {
             {
                 Ok(InnerExecutionResult::Finish(message)) => return Ok(*message),
                 Ok(InnerExecutionResult::NextCall(messages)) => {
+                    tool_iterations.next(&span)?;
                     openai_calls.push(messages);
                 }
                 Err(e) => {
                     span.record("error", e.to_string());
-                    if retries_left == 0 {
+                    if retries_left == 0 || !retry_policy().is_retryable(&e) {
                         return Err(e);
                     } else {
                         openai_calls.push(messages);
@@ -829,6 +1100,7 @@ impl<C: Config> OpenAIModel<C> {
     ) -> LLMResult<InnerExecutionResult> {
         let request = self.build_request(&input_messages, true)?;
         span.record("request", serde_json::to_string(&request)?);
+        self.record_dropped_params(&span);
 
         let _ = tx
             .send(Some(ModelEvent::new(
@@ -842,12 +1114,14 @@ impl<C: Config> OpenAIModel<C> {
             .await;
 
         let started_at = std::time::Instant::now();
-        let stream = self
-            .client
-            .chat()
-            .create_stream(request)
+        let (stream, rate_limit) = self
+            .create_chat_completion_stream(&request)
+            .instrument(span.clone())
             .await
             .map_err(|e| ModelError::OpenAIApi(Box::new(e)))?;
+        if let Some(rate_limit) = &rate_limit {
+            span.record("rate_limit", serde_json::to_string(rate_limit)?);
+        }
         let (finish_reason, tool_calls, usage, response) = self
             .process_stream(stream, tx, tx_response, started_at)
             .instrument(span.clone())
@@ -866,6 +1140,7 @@ impl<C: Config> OpenAIModel<C> {
                     finish_reason: model_finish_reason.clone(),
                     tool_calls: tool_calls.iter().map(Self::map_tool_call).collect(),
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit,
                 }),
             )))
             .await;
@@ -880,6 +1155,7 @@ impl<C: Config> OpenAIModel<C> {
                 model: response.model.clone(),
                 choices: vec![],
                 usage: None,
+                tool_event: None,
             };
 
             let mut chunk_clone = chunk.clone();
@@ -949,6 +1225,9 @@ impl<C: Config> OpenAIModel<C> {
                     target: target!(),
                     parent: span.clone(),
                     events::SPAN_TOOLS,
+                    cache=tracing::field::Empty,
+                    validation_errors=tracing::field::Empty,
+                    error_payload=tracing::field::Empty,
                     tool_calls=JsonValue(&serde_json::to_value(&tool_calls)?).as_value(),
                     tool_results=field::Empty,
                     tool.name=tool_names
@@ -1012,6 +1291,7 @@ impl<C: Config> OpenAIModel<C> {
             .execution_options
             .max_retries
             .unwrap_or(DEFAULT_MAX_RETRIES);
+        let mut tool_iterations = ToolIterations::new(&self.execution_options);
         while let Some(input_messages) = openai_calls.pop() {
             let input = serde_json::to_string(&input_messages)?;
             let span = create_model_span!(
@@ -1036,11 +1316,12 @@ impl<C: Config> OpenAIModel<C> {
                     break;
                 }
                 Ok(InnerExecutionResult::NextCall(messages)) => {
+                    tool_iterations.next(&span)?;
                     openai_calls.push(messages);
                 }
                 Err(e) => {
                     span.record("error", e.to_string());
-                    if retries_left == 0 {
+                    if retries_left == 0 || !retry_policy().is_retryable(&e) {
                         return Err(e);
                     } else {
                         openai_calls.push(input_messages);
@@ -1062,18 +1343,23 @@ impl<C: Config> OpenAIModel<C> {
         for m in messages_dto.iter() {
             let request_message = {
                 match m.r#type {
-                    MessageType::SystemMessage => ChatCompletionRequestMessage::System(
-                        ChatCompletionRequestSystemMessageArgs::default()
-                            .content(m.content.clone().unwrap_or_default())
-                            .build()
-                            .unwrap_or_default(),
-                    ),
+                    MessageType::SystemMessage => {
+                        let mut msg_args = ChatCompletionRequestSystemMessageArgs::default();
+                        msg_args.content(m.content.clone().unwrap_or_default());
+                        if let Some(name) = &m.name {
+                            msg_args.name(name.clone());
+                        }
+                        ChatCompletionRequestMessage::System(msg_args.build().unwrap_or_default())
+                    }
                     MessageType::AIMessage => {
                         let mut msg_args = ChatCompletionRequestAssistantMessageArgs::default();
                         msg_args.content(render(
                             m.content.clone().unwrap_or_default(),
                             &input_variables,
                         ));
+                        if let Some(name) = &m.name {
+                            msg_args.name(name.clone());
+                        }
 
                         if let Some(calls) = m.tool_calls.as_ref() {
                             msg_args.tool_calls(
@@ -1098,11 +1384,17 @@ impl<C: Config> OpenAIModel<C> {
                         )
                     }
                     MessageType::HumanMessage => {
-                        construct_user_message(&m.clone().into(), input_variables.clone())
+                        let mut message =
+                            construct_user_message(&m.clone().into(), input_variables.clone())?;
+                        if let ChatCompletionRequestMessage::User(user) = &mut message {
+                            user.name = m.name.clone();
+                        }
+                        message
                     }
+                    // Tool messages have no name field
                     MessageType::ToolResult => ChatCompletionRequestMessage::Tool(
                         ChatCompletionRequestToolMessageArgs::default()
-                            .content(m.content.clone().unwrap_or_default())
+                            .content(m.clone().fold_name().content.unwrap_or_default())
                             .tool_call_id(
                                 m.tool_call_id
                                     .clone()
@@ -1152,14 +1444,13 @@ where
         let tx_clone = tx.clone();
         tokio::spawn(
             async move {
-                let result = model
-                    .execute_stream(conversational_messages, &tx_clone, &tx_response, tags)
-                    .instrument(tracing::Span::current())
-                    .await;
-
-                if let Err(e) = result {
-                    let _ = tx_response.send(Err(e)).await;
-                }
+                stream_until_closed(
+                    &tx_response,
+                    model
+                        .execute_stream(conversational_messages, &tx_clone, &tx_response, tags)
+                        .instrument(tracing::Span::current()),
+                )
+                .await;
             }
             .instrument(tracing::Span::current()),
         );
@@ -1174,6 +1465,10 @@ impl<C: Config> OpenAIModel<C> {
         input_variables: HashMap<String, Value>,
         previous_messages: Vec<Message>,
     ) -> LLMResult<Vec<ChatCompletionRequestMessage>> {
+        let previous_messages = normalize_for_provider(
+            previous_messages,
+            self.execution_options.role_policy.unwrap_or_default(),
+        );
         let mut conversational_messages: Vec<ChatCompletionRequestMessage> = vec![];
         let previous_messages =
             Self::map_previous_messages(previous_messages, input_variables.clone())?;
@@ -1186,7 +1481,7 @@ impl<C: Config> OpenAIModel<C> {
 fn construct_user_message(
     m: &InnerMessage,
     variables: HashMap<String, Value>,
-) -> ChatCompletionRequestMessage {
+) -> Result<ChatCompletionRequestMessage, ModelError> {
     let content = match m {
         InnerMessage::Text(text) => {
             ChatCompletionRequestUserMessageContent::Text(render(text.clone(), &variables.clone()))
@@ -1226,7 +1521,21 @@ fn construct_user_message(
                         todo!()
                     }
                     MessageContentType::File => {
-                        todo!()
+                        let file = m.file.as_ref().ok_or(DocumentError::MissingData)?;
+                        let document = Document::from_file(file)?;
+                        let (file_data, file_id) = match document.source {
+                            DocumentSource::Inline { .. } => (file.data.clone(), None),
+                            DocumentSource::FileId(id) => (None, Some(id)),
+                        };
+                        ChatCompletionRequestUserMessageContentPart::File(
+                            ChatCompletionRequestMessageContentPartFile {
+                                file: FileObject {
+                                    file_data,
+                                    file_id,
+                                    filename: document.filename,
+                                },
+                            },
+                        )
                     }
                 };
                 messages.push(msg)
@@ -1234,12 +1543,12 @@ fn construct_user_message(
             ChatCompletionRequestUserMessageContent::Array(messages)
         }
     };
-    ChatCompletionRequestMessage::User(
+    Ok(ChatCompletionRequestMessage::User(
         ChatCompletionRequestUserMessageArgs::default()
             .content(content)
             .build()
             .unwrap_or_default(),
-    )
+    ))
 }
 
 pub fn record_map_err(e: impl Into<LLMError> + ToString, span: tracing::Span) -> LLMError {
@@ -1263,7 +1572,14 @@ fn map_tool_names(tool_calls: &[ChatCompletionMessageToolCalls]) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::client::completions::CompletionsClient;
     use crate::provider::tests::MockStreamServer;
+    use crate::types::credentials::Credentials;
+    use crate::types::engine::CompletionEngineParamsBuilder;
+    use crate::types::gateway::ChatCompletionRequest;
+    use crate::types::models::InferenceProvider;
+    use crate::types::provider::InferenceModelProvider;
+    use vllora_telemetry::test_utils::RecordingLayer;
 
     fn get_instance(url: &str) -> OpenAIModel {
         OpenAIModel::new(
@@ -1282,6 +1598,546 @@ mod tests {
         .expect("Failed to create instance")
     }
 
+    #[test]
+    fn test_reasoning_model_request_uses_max_completion_tokens() {
+        let instance = OpenAIModel::new(
+            OpenAiModelParams {
+                model: Some("o3-mini".to_string()),
+                max_tokens: Some(256),
+                temperature: Some(0.2),
+                top_p: Some(0.9),
+                reasoning_model: true,
+                ..Default::default()
+            },
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            None,
+        )
+        .expect("Failed to create instance");
+
+        let request = instance.build_request(&[], false).unwrap();
+        let request = serde_json::to_value(request).unwrap();
+
+        assert_eq!(request["max_completion_tokens"], 256);
+        assert!(request.get("max_tokens").is_none());
+        assert!(request.get("temperature").is_none());
+        assert!(request.get("top_p").is_none());
+    }
+
+    #[test]
+    fn test_non_reasoning_model_request_keeps_params() {
+        let instance = OpenAIModel::new(
+            OpenAiModelParams {
+                model: Some("gpt-4o".to_string()),
+                max_tokens: Some(256),
+                temperature: Some(0.2),
+                ..Default::default()
+            },
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            None,
+        )
+        .expect("Failed to create instance");
+
+        let request = instance.build_request(&[], false).unwrap();
+        let request = serde_json::to_value(request).unwrap();
+
+        assert_eq!(request["max_tokens"], 256);
+        assert!(request["temperature"].as_f64().is_some());
+        assert!(request.get("max_completion_tokens").is_none());
+    }
+
+    #[test]
+    fn test_reasoning_effort_reaches_payload() {
+        let request: crate::types::gateway::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "o3-mini",
+                "messages": [],
+                "reasoning_effort": "high",
+            }))
+            .unwrap();
+        let engine = crate::types::engine::CompletionEngineParamsBuilder::new()
+            .build(&request)
+            .unwrap();
+        let crate::types::engine::CompletionEngineParams::OpenAi { params, .. } = engine else {
+            panic!("Expected OpenAI engine params");
+        };
+
+        let instance = OpenAIModel::new(
+            params,
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            None,
+        )
+        .expect("Failed to create instance");
+        let payload = serde_json::to_value(instance.build_request(&[], false).unwrap()).unwrap();
+        assert_eq!(payload["reasoning_effort"], "high");
+
+        let invalid = serde_json::from_value::<crate::types::gateway::ChatCompletionRequest>(
+            serde_json::json!({
+                "model": "o3-mini",
+                "messages": [],
+                "reasoning_effort": "extreme",
+            }),
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_upstream_provider_is_read_from_response() {
+        assert_eq!(
+            upstream_provider(br#"{"id": "gen-1", "provider": "Anthropic", "choices": []}"#),
+            Some("Anthropic".to_string())
+        );
+        assert_eq!(upstream_provider(br#"{"id": "chatcmpl-1"}"#), None);
+    }
+
+    #[test]
+    fn test_logit_bias_reaches_payload() {
+        let request: crate::types::gateway::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "gpt-4o-mini",
+                "messages": [],
+                "logit_bias": {"50256": -100, "1734": 5},
+            }))
+            .unwrap();
+        let engine = crate::types::engine::CompletionEngineParamsBuilder::new()
+            .build(&request)
+            .unwrap();
+        let crate::types::engine::CompletionEngineParams::OpenAi { params, .. } = engine else {
+            panic!("Expected OpenAI engine params");
+        };
+
+        let instance = OpenAIModel::new(
+            params,
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            None,
+        )
+        .expect("Failed to create instance");
+        let payload = serde_json::to_value(instance.build_request(&[], false).unwrap()).unwrap();
+        assert_eq!(
+            payload["logit_bias"],
+            serde_json::json!({"50256": -100, "1734": 5})
+        );
+    }
+
+    #[test]
+    fn test_prediction_reaches_payload_and_usage() {
+        let prediction = serde_json::json!({"type": "content", "content": "fn main() {}"});
+        let request: crate::types::gateway::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [],
+                "prediction": prediction,
+            }))
+            .unwrap();
+        let engine = crate::types::engine::CompletionEngineParamsBuilder::new()
+            .build(&request)
+            .unwrap();
+        let crate::types::engine::CompletionEngineParams::OpenAi { params, .. } = engine else {
+            panic!("Expected OpenAI engine params");
+        };
+
+        let instance = OpenAIModel::new(
+            params,
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            None,
+        )
+        .expect("Failed to create instance");
+        let payload = serde_json::to_value(instance.build_request(&[], false).unwrap()).unwrap();
+        assert_eq!(payload["prediction"], prediction);
+
+        let usage: CompletionUsage = serde_json::from_value(serde_json::json!({
+            "prompt_tokens": 120,
+            "completion_tokens": 40,
+            "total_tokens": 160,
+            "completion_tokens_details": {
+                "reasoning_tokens": 0,
+                "audio_tokens": 0,
+                "accepted_prediction_tokens": 18,
+                "rejected_prediction_tokens": 4
+            }
+        }))
+        .unwrap();
+        let usage = OpenAIModel::map_usage(Some(&usage)).unwrap();
+        // Rejected prediction tokens are billed as output tokens, they are part of the count
+        assert_eq!(usage.output_tokens, 40);
+        let details = usage.completion_tokens_details.unwrap();
+        assert_eq!(details.accepted_prediction_tokens(), 18);
+        assert_eq!(details.rejected_prediction_tokens(), 4);
+    }
+
+    #[test]
+    fn test_service_tier_reaches_payload_and_price() {
+        let request: crate::types::gateway::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "gpt-4o",
+                "messages": [],
+                "service_tier": "priority",
+            }))
+            .unwrap();
+        let engine = crate::types::engine::CompletionEngineParamsBuilder::new()
+            .build(&request)
+            .unwrap();
+        let service_tier = engine.service_tier();
+        assert_eq!(
+            service_tier,
+            Some(crate::types::gateway::ServiceTier::Priority)
+        );
+        let crate::types::engine::CompletionEngineParams::OpenAi { params, .. } = engine else {
+            panic!("Expected OpenAI engine params");
+        };
+
+        let instance = OpenAIModel::new(
+            params,
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            None,
+        )
+        .expect("Failed to create instance");
+        let payload = serde_json::to_value(instance.build_request(&[], false).unwrap()).unwrap();
+        assert_eq!(payload["service_tier"], "priority");
+
+        let price: crate::types::provider::ModelPrice = serde_json::from_value(serde_json::json!({
+            "per_input_token": 2.5,
+            "per_output_token": 10.0,
+            "valid_from": null,
+            "per_service_tier": {
+                "priority": {"per_input_token": 4.25, "per_output_token": 17.0}
+            }
+        }))
+        .unwrap();
+        let price = price.for_service_tier(service_tier);
+        assert_eq!(price.per_input_token(), 4.25);
+        assert_eq!(price.per_output_token(), 17.0);
+
+        assert!(
+            serde_json::from_value::<crate::types::gateway::ChatCompletionRequest>(
+                serde_json::json!({"model": "gpt-4o", "messages": [], "service_tier": "fastest"})
+            )
+            .is_err()
+        );
+    }
+
+    /// Serves a chat completion with OpenAI rate limit headers
+    async fn serve_rate_limited_completion() -> MockStreamServer {
+        let body = r#"{"id":"chatcmpl-123","object":"chat.completion","created":1694268190,"model":"gpt-3.5-turbo-0125","choices":[{"index":0,"message":{"role":"assistant","content":"Hello"},"finish_reason":"stop"}],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#;
+        let server = serve_completion(body).await;
+        for (name, value) in [
+            ("x-ratelimit-limit-requests", "500"),
+            ("x-ratelimit-remaining-requests", "499"),
+            ("x-ratelimit-reset-requests", "120ms"),
+            ("x-ratelimit-limit-tokens", "30000"),
+            ("x-ratelimit-remaining-tokens", "29994"),
+            ("x-ratelimit-reset-tokens", "12ms"),
+        ] {
+            server.set_header(name, value).await;
+        }
+        server
+    }
+
+    /// Serves a chat completion with the given body
+    async fn serve_completion(body: &str) -> MockStreamServer {
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server
+            .set_payload("application/json", body.as_bytes().to_vec())
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_audio_output_is_surfaced_as_content_part() {
+        let request: crate::types::gateway::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "gpt-4o-audio-preview",
+                "messages": [],
+                "modalities": ["text", "audio"],
+                "audio": {"voice": "alloy", "format": "wav"},
+            }))
+            .unwrap();
+        let engine = crate::types::engine::CompletionEngineParamsBuilder::new()
+            .build(&request)
+            .unwrap();
+        let crate::types::engine::CompletionEngineParams::OpenAi { params, .. } = engine else {
+            panic!("Expected OpenAI engine params");
+        };
+
+        let body = r#"{"id":"chatcmpl-123","object":"chat.completion","created":1694268190,"model":"gpt-4o-audio-preview","choices":[{"index":0,"message":{"role":"assistant","content":null,"audio":{"id":"audio_123","expires_at":1694271790,"data":"UklGRg==","transcript":"Hello"}},"finish_reason":"stop"}],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#;
+        let server = serve_completion(body).await;
+        let instance = OpenAIModel::new(
+            params,
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            Some(&server.url()),
+        )
+        .expect("Failed to create instance");
+
+        let payload = serde_json::to_value(instance.build_request(&[], false).unwrap()).unwrap();
+        assert_eq!(payload["modalities"], serde_json::json!(["text", "audio"]));
+        assert_eq!(payload["audio"]["voice"], "alloy");
+
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let response = instance
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to invoke");
+        let Some(ChatCompletionContent::Content(parts)) = &response.message().content else {
+            panic!("Expected content parts");
+        };
+        assert_eq!(parts[0].text.as_deref(), Some("Hello"));
+        assert_eq!(parts[1].r#type, crate::types::gateway::ContentType::Audio);
+        let audio = parts[1].audio.as_ref().unwrap();
+        assert_eq!(audio.data, "UklGRg==");
+        assert_eq!(audio.format, "wav");
+
+        let request: crate::types::gateway::ChatCompletionRequest =
+            serde_json::from_value(serde_json::json!({
+                "model": "claude-sonnet-4",
+                "messages": [],
+                "modalities": ["audio"],
+                "audio": {"voice": "alloy", "format": "wav"},
+            }))
+            .unwrap();
+        let error = crate::types::engine::CompletionEngineParamsBuilder::new()
+            .with_model_provider(crate::types::provider::InferenceModelProvider::Anthropic)
+            .build(&request)
+            .unwrap_err();
+        assert!(error.to_string().contains("Audio output is not supported"));
+    }
+
+    #[tokio::test]
+    async fn test_dropped_params_are_recorded_on_model_span() {
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let body = r#"{"id":"chatcmpl-123","object":"chat.completion","created":1694268190,"model":"o3-mini","choices":[{"index":0,"message":{"role":"assistant","content":"Hello"},"finish_reason":"stop"}],"usage":{"prompt_tokens":5,"completion_tokens":1,"total_tokens":6}}"#;
+        let server = serve_completion(body).await;
+        let instance = OpenAIModel::new(
+            OpenAiModelParams {
+                model: Some("o3-mini".to_string()),
+                temperature: Some(0.2),
+                top_p: Some(0.9),
+                reasoning_model: true,
+                ..Default::default()
+            },
+            Some(&ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }),
+            ExecutionOptions::default(),
+            HashMap::new(),
+            None,
+            Some(&server.url()),
+        )
+        .expect("Failed to create instance");
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        instance
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to invoke");
+
+        assert_eq!(recorded.values("dropped_params"), ["temperature,top_p"]);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers_are_recorded_on_span() {
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let server = serve_rate_limited_completion().await;
+        let instance = get_instance(&server.url());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        instance
+            .invoke(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to invoke");
+
+        let expected = ProviderRateLimit {
+            limit_requests: Some(500),
+            remaining_requests: Some(499),
+            reset_requests: Some("120ms".to_string()),
+            limit_tokens: Some(30000),
+            remaining_tokens: Some(29994),
+            reset_tokens: Some("12ms".to_string()),
+            retry_after: None,
+        };
+        let recorded = recorded.values("rate_limit");
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(
+            serde_json::from_str::<ProviderRateLimit>(&recorded[0]).unwrap(),
+            expected
+        );
+
+        let mut stop_rate_limit = None;
+        while let Some(Some(event)) = rx.recv().await {
+            if let ModelEventType::LlmStop(e) = event.event {
+                stop_rate_limit = e.rate_limit;
+            }
+        }
+        assert_eq!(stop_rate_limit, Some(expected.clone()));
+        assert_eq!(
+            expected.response_headers()[1],
+            (
+                "x-vllora-ratelimit-remaining-requests".to_string(),
+                "499".to_string()
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_headers_of_stream_are_recorded_on_span() {
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server.set_header("x-ratelimit-limit-requests", "500").await;
+        server
+            .set_header("x-ratelimit-remaining-requests", "499")
+            .await;
+        server
+            .set_header("x-ratelimit-reset-requests", "120ms")
+            .await;
+        server.set_events(vec![
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#.to_string(),
+        ]).await;
+
+        let instance = get_instance(&server.url());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let mut stream = instance
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to stream");
+        while let Some(chunk) = stream.next().await {
+            chunk.expect("Failed to read chunk");
+        }
+
+        let expected = ProviderRateLimit {
+            limit_requests: Some(500),
+            remaining_requests: Some(499),
+            reset_requests: Some("120ms".to_string()),
+            limit_tokens: None,
+            remaining_tokens: None,
+            reset_tokens: None,
+            retry_after: None,
+        };
+        let recorded = recorded.values("rate_limit");
+        assert_eq!(recorded.len(), 1);
+        assert_eq!(
+            serde_json::from_str::<ProviderRateLimit>(&recorded[0]).unwrap(),
+            expected
+        );
+
+        let mut stop_rate_limit = None;
+        while let Some(Some(event)) = rx.recv().await {
+            if let ModelEventType::LlmStop(e) = event.event {
+                stop_rate_limit = e.rate_limit;
+            }
+        }
+        assert_eq!(stop_rate_limit, Some(expected));
+    }
+
+    #[tokio::test]
+    async fn test_upstream_provider_of_stream_is_recorded_on_span() {
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server
+            .set_header(UPSTREAM_PROVIDER_HEADER, "Anthropic")
+            .await;
+        server.set_events(vec![
+            r#"{"id":"gen-123","object":"chat.completion.chunk","created":1694268190,"model":"anthropic/claude-sonnet-4","choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"gen-123","object":"chat.completion.chunk","created":1694268190,"model":"anthropic/claude-sonnet-4","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#.to_string(),
+        ]).await;
+
+        let instance = get_instance(&server.url());
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let mut stream = instance
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to stream");
+        while let Some(chunk) = stream.next().await {
+            chunk.expect("Failed to read chunk");
+        }
+
+        assert_eq!(
+            recorded.values("upstream_provider"),
+            vec!["Anthropic".to_string()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_stream_error_status_is_classified() {
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server
+            .fail_with(
+                400,
+                serde_json::json!({
+                    "error": {
+                        "message": "This model's maximum context length is 16385 tokens.",
+                        "type": "invalid_request_error",
+                        "param": "messages",
+                        "code": "context_length_exceeded"
+                    }
+                })
+                .to_string(),
+            )
+            .await;
+
+        let instance = get_instance(&server.url());
+        let (tx, _rx) = tokio::sync::mpsc::channel(100);
+        let mut stream = instance
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to stream");
+        let error = match stream.next().await {
+            Some(Err(error)) => error,
+            other => panic!("Expected an error, got {other:?}"),
+        };
+        assert_eq!(
+            error.classify(),
+            Some(crate::client::error::ProviderErrorClass::ContextLengthExceeded)
+        );
+    }
+
     #[tokio::test]
     async fn test_stream_request() {
         // Start the mock server
@@ -1406,4 +2262,109 @@ mod tests {
             index += 1;
         }
     }
+
+    #[test]
+    fn test_named_messages_reach_openai_payload() {
+        let messages: Vec<crate::types::gateway::ChatCompletionMessage> =
+            serde_json::from_value(serde_json::json!([
+                {"role": "user", "name": "alice", "content": "Hi"},
+                {"role": "user", "name": "bob", "content": "Hello"},
+                {"role": "assistant", "name": "moderator", "content": "Welcome"},
+            ]))
+            .unwrap();
+        let messages = messages
+            .iter()
+            .map(|m| {
+                crate::client::message_mapper::MessageMapper::map_completions_message_to_vllora_message(
+                    m, "gpt-4o", "user",
+                )
+                .unwrap()
+            })
+            .collect();
+
+        let instance = get_instance("http://localhost");
+        let messages = instance
+            .construct_messages(HashMap::new(), messages)
+            .unwrap();
+        let messages = serde_json::to_value(messages).unwrap();
+
+        assert_eq!(messages[0]["name"], "alice");
+        assert_eq!(messages[0]["content"], "Hi");
+        assert_eq!(messages[1]["name"], "bob");
+        assert_eq!(messages[2]["name"], "moderator");
+    }
+
+    #[test]
+    fn test_pdf_file_part_is_sent_as_file_input() {
+        let message = InnerMessage::Array(vec![
+            crate::types::message::MessageContentPart {
+                r#type: MessageContentType::Text,
+                value: "Summarize this report".to_string(),
+                additional_options: None,
+                file: None,
+                cache_control: None,
+            },
+            crate::types::message::MessageContentPart {
+                r#type: MessageContentType::File,
+                value: String::new(),
+                additional_options: None,
+                file: Some(crate::types::gateway::File {
+                    data: Some("data:application/pdf;base64,JVBERi0xLjQK".to_string()),
+                    id: None,
+                    filename: Some("report.pdf".to_string()),
+                }),
+                cache_control: None,
+            },
+        ]);
+
+        let request = construct_user_message(&message, HashMap::new()).unwrap();
+        let value = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(value["content"][1]["type"], "file");
+        assert_eq!(
+            value["content"][1]["file"]["file_data"],
+            "data:application/pdf;base64,JVBERi0xLjQK"
+        );
+        assert_eq!(value["content"][1]["file"]["filename"], "report.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_stop_sequence_is_removed_from_openai_stream() {
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server.set_events(vec![
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{"content":"Hello world </ans"},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{"content":"wer> ignored"},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#.to_string(),
+        ]).await;
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let builder = CompletionEngineParamsBuilder::new()
+            .with_provider(InferenceProvider {
+                provider: InferenceModelProvider::OpenAI,
+                model_name: "gpt-4o-mini".to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            })
+            .with_credentials(Credentials::ApiKeyWithEndpoint {
+                api_key: "test".to_string(),
+                endpoint: server.url(),
+            });
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o-mini",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "stop": ["</answer>"]
+        }))
+        .unwrap();
+
+        let response = CompletionsClient::new(builder)
+            .create_via_stream(request, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            response.message().content,
+            Some(ChatCompletionContent::Text("Hello world ".to_string()))
+        );
+    }
 }
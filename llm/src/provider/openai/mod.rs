@@ -1,5 +1,6 @@
 use crate::{
     client::error::{AuthorizationError, ModelError},
+    provider::shared_http_client,
     types::credentials::ApiKeyCredentials,
 };
 use async_openai::{
@@ -42,7 +43,7 @@ pub fn openai_client(
         config = config.with_api_base(endpoint);
     }
 
-    Ok(Client::with_config(config))
+    Ok(Client::with_config(config).with_http_client(shared_http_client()))
 }
 
 /// Create an Azure OpenAI client from endpoint URL
@@ -57,5 +58,5 @@ pub fn azure_openai_client(
         .with_api_key(api_key)
         .with_deployment_id(deployment_id.to_string());
 
-    Client::with_config(azure_config)
+    Client::with_config(azure_config).with_http_client(shared_http_client())
 }
@@ -11,6 +11,7 @@ use crate::types::gateway::GatewayModelUsage;
 use crate::types::LLMContentEvent;
 use crate::types::LLMFinishEvent;
 use crate::types::LLMFirstToken;
+use crate::types::LLMReasoningEvent;
 use crate::types::LLMStartEvent;
 use crate::types::ModelEvent;
 use crate::types::ModelEventType;
@@ -61,6 +62,108 @@ struct FunctionCall {
     arguments: serde_json::Value,
 }
 
+/// Tools OpenAI runs on its side, whose calls come back as output items
+pub const HOSTED_TOOLS: [&str; 2] = ["file_search", "code_interpreter"];
+
+/// Types of the hosted tools declared on a request
+pub fn hosted_tool_types(request: &CreateResponse) -> Vec<String> {
+    let Some(tools) = request
+        .tools
+        .as_ref()
+        .and_then(|tools| serde_json::to_value(tools).ok())
+    else {
+        return vec![];
+    };
+
+    tools
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|tool| tool.get("type").and_then(|t| t.as_str()))
+        .filter(|tool_type| HOSTED_TOOLS.contains(tool_type))
+        .map(|tool_type| tool_type.to_string())
+        .collect()
+}
+
+/// A `file_search_call` or `code_interpreter_call` output item, normalized into tool input
+/// and output: search queries and file citations, or executed code and its results
+#[derive(Debug, Clone)]
+struct HostedToolCall {
+    id: String,
+    name: &'static str,
+    input: serde_json::Value,
+    output: serde_json::Value,
+}
+
+impl HostedToolCall {
+    fn from_output_item(item: &OutputItem) -> Option<Self> {
+        let item = serde_json::to_value(item).ok()?;
+        let id = item.get("id")?.as_str()?.to_string();
+        let field = |name: &str| item.get(name).cloned().unwrap_or(serde_json::Value::Null);
+
+        match item.get("type")?.as_str()? {
+            "file_search_call" => Some(Self {
+                id,
+                name: "file_search",
+                input: json!({ "queries": field("queries") }),
+                output: json!({ "status": field("status"), "results": field("results") }),
+            }),
+            "code_interpreter_call" => Some(Self {
+                id,
+                name: "code_interpreter",
+                input: json!({ "code": field("code"), "container_id": field("container_id") }),
+                output: json!({ "status": field("status"), "outputs": field("outputs") }),
+            }),
+            _ => None,
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        self.output.get("status").and_then(|s| s.as_str()) == Some("failed")
+    }
+
+    fn events(&self, span: &Span) -> Vec<ModelEventType> {
+        let tool_span = tracing::info_span!(
+            target: target!(),
+            parent: span.clone(),
+            events::SPAN_TOOLS,
+            tool_calls=field::Empty,
+            tool_results=field::Empty,
+            tool.name=field::Empty
+        );
+        tool_span.follows_from(span.id());
+        let _entered = tool_span.clone().entered();
+
+        let tool_calls_vec = vec![ToolCall {
+            id: self.id.clone(),
+            function: Some(FunctionCall {
+                name: self.name.to_string(),
+                arguments: self.input.clone(),
+            }),
+        }];
+        tool_span.record(
+            "tool_calls",
+            JsonValue(&serde_json::to_value(tool_calls_vec).unwrap()).as_value(),
+        );
+        tool_span.record("tool_results", JsonValue(&self.output).as_value());
+        tool_span.record("tool.name", self.name);
+
+        vec![
+            ModelEventType::ToolStart(ToolStartEvent {
+                tool_id: self.id.clone(),
+                tool_name: self.name.to_string(),
+                input: self.input.to_string(),
+            }),
+            ModelEventType::ToolResult(ToolResultEvent {
+                tool_id: self.id.clone(),
+                tool_name: self.name.to_string(),
+                is_error: self.is_error(),
+                output: self.output.to_string(),
+            }),
+        ]
+    }
+}
+
 #[derive(Clone)]
 pub struct OpenAIResponses {
     client: Client<OpenAIConfig>,
@@ -111,6 +214,11 @@ impl OpenAIResponses {
 
         let mut content = String::new();
         for output in &response.output {
+            if let Some(call) = HostedToolCall::from_output_item(output) {
+                for event in call.events(&span) {
+                    Self::send_event(tx, ModelEvent::new(&span, event)).await;
+                }
+            }
             if let OutputItem::Message(message) = output {
                 for c in &message.content {
                     match c {
@@ -137,6 +245,7 @@ impl OpenAIResponses {
                     finish_reason: finish_reason.clone(),
                     tool_calls: vec![],
                     credentials_ident: self.credentials_ident.clone(),
+                    rate_limit: None,
                 }),
             ),
         )
@@ -191,6 +300,7 @@ impl OpenAIResponses {
                         finish_reason,
                         tool_calls: vec![],
                         credentials_ident: self.credentials_ident.clone(),
+                        rate_limit: None,
                     }),
                 ),
             )
@@ -330,7 +440,9 @@ impl OpenAIResponses {
             ResponseStreamEvent::ResponseRefusalDone(_) => {}
             ResponseStreamEvent::ResponseContentPartDone(_) => {}
             ResponseStreamEvent::ResponseOutputItemDone(item) => {
-                if let OutputItem::WebSearchCall(call) = &item.item {
+                if let Some(call) = HostedToolCall::from_output_item(&item.item) {
+                    events.extend(call.events(span));
+                } else if let OutputItem::WebSearchCall(call) = &item.item {
                     // Keep the span alive until after we've recorded and sent events
                     if let Some((_, Some(tool_span))) = tool_calls.remove(&call.id) {
                         // Enter the span before recording to ensure it's active
@@ -420,7 +532,11 @@ impl OpenAIResponses {
             ResponseStreamEvent::ResponseWebSearchCallCompleted(_) => {}
             ResponseStreamEvent::ResponseReasoningSummaryPartAdded(_) => {}
             ResponseStreamEvent::ResponseReasoningSummaryPartDone(_) => {}
-            ResponseStreamEvent::ResponseReasoningSummaryTextDelta(_) => {}
+            ResponseStreamEvent::ResponseReasoningSummaryTextDelta(delta) => {
+                events.push(ModelEventType::LlmReasoning(LLMReasoningEvent {
+                    content: delta.delta.clone(),
+                }));
+            }
             ResponseStreamEvent::ResponseReasoningSummaryTextDone(_) => {}
             ResponseStreamEvent::ResponseImageGenerationCallInProgress(_) => {}
             ResponseStreamEvent::ResponseImageGenerationCallGenerating(_) => {}
@@ -592,6 +708,42 @@ mod tests {
         assert!(!response_events.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_reasoning_summary_deltas_are_reasoning_events() {
+        let response_events = parse_fixture_events(
+            r#"event: response.reasoning_summary_text.delta
+data: {"type":"response.reasoning_summary_text.delta","sequence_number":3,"item_id":"rs_1","output_index":0,"summary_index":0,"delta":"Checking the units"}
+event: response.output_text.delta
+data: {"type":"response.output_text.delta","sequence_number":4,"item_id":"msg_1","output_index":1,"content_index":0,"delta":"42","logprobs":[]}"#,
+        );
+
+        let span = tracing::Span::current();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        let mut tool_calls = HashMap::new();
+        for response_event in &response_events {
+            OpenAIResponses::match_response_event(
+                response_event,
+                &span,
+                Some(&tx),
+                &mut tool_calls,
+            )
+            .await;
+        }
+        let _ = tx.send(None).await;
+
+        let mut reasoning = vec![];
+        let mut content = vec![];
+        while let Some(Some(event)) = rx.recv().await {
+            match event.event {
+                ModelEventType::LlmReasoning(e) => reasoning.push(e.content),
+                ModelEventType::LlmContent(e) => content.push(e.content),
+                _ => {}
+            }
+        }
+        assert_eq!(reasoning, vec!["Checking the units"]);
+        assert_eq!(content, vec!["42"]);
+    }
+
     #[tokio::test]
     async fn test_match_response_event_web_search() {
         let fixture_content = read_fixture_file("web_search_example");
@@ -691,4 +843,60 @@ mod tests {
         // And the fixture itself should not be empty / malformed
         assert!(!response_events.is_empty());
     }
+
+    #[test]
+    fn test_code_interpreter_tool_reaches_payload() {
+        let request: CreateResponse = serde_json::from_value(json!({
+            "model": "gpt-4.1",
+            "input": "What is 2 + 2? Use python.",
+            "tools": [{"type": "code_interpreter", "container": {"type": "auto"}}]
+        }))
+        .unwrap();
+
+        let payload = serde_json::to_value(&request).unwrap();
+        assert_eq!(payload["tools"][0]["type"], "code_interpreter");
+        assert_eq!(payload["tools"][0]["container"]["type"], "auto");
+        assert_eq!(hosted_tool_types(&request), vec!["code_interpreter"]);
+    }
+
+    #[tokio::test]
+    async fn test_code_interpreter_output_item_is_surfaced() {
+        let event: ResponseStreamEvent = serde_json::from_value(json!({
+            "type": "response.output_item.done",
+            "sequence_number": 7,
+            "output_index": 0,
+            "item": {
+                "type": "code_interpreter_call",
+                "id": "ci_123",
+                "status": "completed",
+                "container_id": "cntr_123",
+                "code": "print(2 + 2)",
+                "outputs": [{"type": "logs", "logs": "4\n"}]
+            }
+        }))
+        .unwrap();
+
+        let span = tracing::Span::current();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(10);
+        OpenAIResponses::match_response_event(&event, &span, Some(&tx), &mut HashMap::new()).await;
+        let _ = tx.send(None).await;
+
+        let mut events = vec![];
+        while let Some(Some(event)) = rx.recv().await {
+            events.push(event.event);
+        }
+
+        match &events[..] {
+            [ModelEventType::ToolStart(start), ModelEventType::ToolResult(result)] => {
+                assert_eq!(start.tool_id, "ci_123");
+                assert_eq!(start.tool_name, "code_interpreter");
+                assert!(start.input.contains("print(2 + 2)"));
+                assert_eq!(result.tool_name, "code_interpreter");
+                assert!(!result.is_error);
+                let output: serde_json::Value = serde_json::from_str(&result.output).unwrap();
+                assert_eq!(output["outputs"][0]["logs"], "4\n");
+            }
+            other => panic!("Unexpected events: {other:?}"),
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use crate::async_openai::{config::OpenAIConfig, Client};
 
+use crate::provider::shared_http_client;
 use crate::types::credentials::ApiKeyCredentials;
 
 use crate::client::error::ModelError;
@@ -32,5 +33,5 @@ pub fn openai_spec_client(
 
     config = config.with_api_base(api_base);
 
-    Ok(Client::with_config(config))
+    Ok(Client::with_config(config).with_http_client(shared_http_client()))
 }
@@ -0,0 +1,231 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use regex::RegexSet;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{field, Span};
+use tracing_futures::Instrument;
+
+use crate::client::error::ProviderErrorClass;
+use crate::error::{LLMError, LLMResult};
+
+static RETRY_POLICY: OnceLock<RetryPolicy> = OnceLock::new();
+
+#[derive(Debug, Error)]
+#[error("Invalid retryable error pattern: {0}")]
+pub struct RetryPatternError(#[from] regex::Error);
+
+/// Provider errors retried on top of the status code classification, see [`RetryPolicy`]
+///
+/// ```yaml
+/// retry:
+///   retryable_errors:
+///     - "(?i)model is currently loading"
+///     - "upstream connect error"
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryConfig {
+    /// Regexes matched against the error message
+    pub retryable_errors: Vec<String>,
+}
+
+/// Decides which failed provider calls are retried. Throttling and provider failures are
+/// always retried, and so are errors that can't be classified. Errors classified as
+/// rejected requests are only retried when their message matches a configured pattern,
+/// which lets operators mark transient errors that providers report as 4xx.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    retryable_errors: RegexSet,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            retryable_errors: RegexSet::empty(),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_config(config: &RetryConfig) -> Result<Self, RetryPatternError> {
+        Ok(Self {
+            retryable_errors: RegexSet::new(&config.retryable_errors)?,
+        })
+    }
+
+    pub fn is_retryable(&self, error: &LLMError) -> bool {
+        match error.classify() {
+            Some(
+                ProviderErrorClass::RateLimit { .. }
+                | ProviderErrorClass::ProviderUnavailable { .. },
+            )
+            | None => true,
+            Some(
+                ProviderErrorClass::InvalidRequest | ProviderErrorClass::ContextLengthExceeded,
+            ) => self.retryable_errors.is_match(&error.to_string()),
+        }
+    }
+}
+
+/// Sets the process-wide retry policy of provider calls. Only the first call takes effect.
+pub fn init_retry_policy(config: &RetryConfig) -> Result<(), RetryPatternError> {
+    let _ = RETRY_POLICY.set(RetryPolicy::from_config(config)?);
+    Ok(())
+}
+
+/// Retry policy of provider calls
+pub fn retry_policy() -> &'static RetryPolicy {
+    RETRY_POLICY.get_or_init(RetryPolicy::default)
+}
+
+/// Runs `call` until it succeeds, fails with an error that isn't retryable or
+/// `retries_left` runs out.
+///
+/// Every attempt runs in its own `attempt` child span of the logical model call `span`,
+/// so retries don't show up as sibling model calls. `call` gets the logical span, usage
+/// and cost belong on it and are only reported by the successful attempt.
+pub(crate) async fn with_attempts<T, F, Fut>(
+    span: &Span,
+    retries_left: &mut u32,
+    mut call: F,
+) -> LLMResult<T>
+where
+    F: FnMut(Span) -> Fut,
+    Fut: Future<Output = LLMResult<T>>,
+{
+    let mut attempt = 1;
+    loop {
+        let attempt_span = tracing::info_span!(
+            target: "vllora::user_tracing::models::attempt",
+            parent: span,
+            "attempt",
+            attempt,
+            error = field::Empty,
+        );
+
+        match call(span.clone()).instrument(attempt_span.clone()).await {
+            Ok(result) => return Ok(result),
+            Err(e) => {
+                attempt_span.record("error", e.to_string());
+                if *retries_left == 0 || !retry_policy().is_retryable(&e) {
+                    return Err(e);
+                }
+                *retries_left -= 1;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::error::ModelError;
+    use async_openai::error::{ApiError, OpenAIError};
+    use vllora_telemetry::test_utils::RecordingLayer;
+
+    #[tokio::test]
+    async fn test_retry_is_an_attempt_of_the_same_model_call() {
+        let recorded = RecordingLayer::default();
+        let _guard = recorded.set_default();
+
+        let span = tracing::info_span!("bedrock", usage = field::Empty);
+        let mut retries_left = 1;
+        let mut calls = 0;
+        let result = with_attempts(&span, &mut retries_left, |span| {
+            calls += 1;
+            let failed = calls == 1;
+            async move {
+                if failed {
+                    return Err(LLMError::CustomError("throttled".to_string()));
+                }
+                span.record("usage", 42);
+                Ok(calls)
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(retries_left, 0);
+
+        let spans = recorded.spans();
+        let model_calls: Vec<_> = spans.iter().filter(|s| s.name == "bedrock").collect();
+        assert_eq!(model_calls.len(), 1);
+        assert_eq!(
+            spans
+                .iter()
+                .filter(|s| s.name == "attempt")
+                .map(|s| (s.parent, s.field("attempt")))
+                .collect::<Vec<_>>(),
+            vec![(Some("bedrock"), Some("1")), (Some("bedrock"), Some("2"))]
+        );
+    }
+
+    fn openai_error(r#type: &str, message: &str) -> LLMError {
+        ModelError::OpenAIApi(Box::new(OpenAIError::ApiError(ApiError {
+            message: message.to_string(),
+            r#type: Some(r#type.to_string()),
+            param: None,
+            code: None,
+        })))
+        .into()
+    }
+
+    #[test]
+    fn test_configured_patterns_make_rejected_requests_retryable() {
+        let policy = RetryPolicy::from_config(&RetryConfig {
+            retryable_errors: vec!["(?i)model is currently loading".to_string()],
+        })
+        .unwrap();
+
+        let loading = openai_error("invalid_request_error", "Model is currently loading");
+        let unknown_param = openai_error("invalid_request_error", "Unknown parameter: foo");
+        assert!(policy.is_retryable(&loading));
+        assert!(!policy.is_retryable(&unknown_param));
+        assert!(!RetryPolicy::default().is_retryable(&loading));
+
+        // Classification by status still applies
+        assert!(policy.is_retryable(&openai_error("rate_limit_error", "Slow down")));
+        assert!(policy.is_retryable(&LLMError::CustomError("throttled".to_string())));
+
+        assert!(RetryPolicy::from_config(&RetryConfig {
+            retryable_errors: vec!["(".to_string()],
+        })
+        .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_request_is_not_retried() {
+        let span = tracing::info_span!("openai");
+        let mut retries_left = 2;
+        let mut calls = 0;
+        let result: LLMResult<()> = with_attempts(&span, &mut retries_left, |_| {
+            calls += 1;
+            async {
+                Err(openai_error(
+                    "invalid_request_error",
+                    "Unknown parameter: foo",
+                ))
+            }
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+        assert_eq!(retries_left, 2);
+    }
+
+    #[tokio::test]
+    async fn test_last_error_is_returned_without_retries_left() {
+        let span = tracing::info_span!("bedrock");
+        let mut retries_left = 0;
+        let result: LLMResult<()> = with_attempts(&span, &mut retries_left, |_| async {
+            Err(LLMError::CustomError("throttled".to_string()))
+        })
+        .await;
+
+        assert!(result.is_err());
+    }
+}
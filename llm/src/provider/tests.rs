@@ -1,14 +1,27 @@
 use rand::Rng;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::io::AsyncWriteExt;
-use tokio::net::TcpListener;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::Mutex;
 use tokio::task::JoinHandle;
 
+#[derive(Default)]
+struct MockState {
+    events: Mutex<Vec<String>>,
+    queued: Mutex<VecDeque<Vec<String>>>,
+    headers: Mutex<Vec<(String, String)>>,
+    failure: Mutex<Option<(u16, String)>>,
+    payload: Mutex<Option<(String, Vec<u8>)>>,
+    requests: Mutex<Vec<String>>,
+    connections: AtomicUsize,
+}
+
 /// Mock server that responds with text/event-stream
 pub struct MockStreamServer {
     port: u16,
-    events: Arc<Mutex<Vec<String>>>,
+    state: Arc<MockState>,
     handle: JoinHandle<()>,
 }
 
@@ -19,139 +32,15 @@ impl MockStreamServer {
         let listener = TcpListener::bind(format!("0.0.0.0:{}", port)).await?;
         let addr = listener.local_addr()?;
         let port = addr.port();
-        let events = Arc::new(Mutex::new(Vec::new()));
+        let state = Arc::new(MockState::default());
 
-        let events_clone = events.clone();
+        let server_state = state.clone();
         let handle = tokio::spawn(async move {
             loop {
                 match listener.accept().await {
-                    Ok((mut stream, _)) => {
-                        let events = events_clone.clone();
-                        tokio::spawn(async move {
-                            use tokio::io::AsyncReadExt;
-
-                            // Read the entire request with a timeout
-                            let read_future = async {
-                                let mut request_data = Vec::new();
-                                let mut buffer = [0u8; 8192];
-
-                                // Read until we get headers
-                                loop {
-                                    let size = stream.read(&mut buffer).await?;
-                                    if size == 0 {
-                                        break;
-                                    }
-                                    request_data.extend_from_slice(&buffer[..size]);
-
-                                    // Check if we have complete headers
-                                    if let Some(headers_end) =
-                                        request_data.windows(4).rposition(|w| w == b"\r\n\r\n")
-                                    {
-                                        // Parse Content-Length
-                                        let headers_str = String::from_utf8_lossy(
-                                            &request_data[..=headers_end + 3],
-                                        );
-                                        let content_length = headers_str.lines().find_map(|line| {
-                                            if line.to_lowercase().starts_with("content-length:") {
-                                                line.split(':')
-                                                    .nth(1)
-                                                    .and_then(|s| s.trim().parse::<usize>().ok())
-                                            } else {
-                                                None
-                                            }
-                                        });
-
-                                        // Read body if needed
-                                        if let Some(body_len) = content_length {
-                                            let body_start = headers_end + 4;
-                                            let body_received =
-                                                request_data.len().saturating_sub(body_start);
-
-                                            if body_received < body_len {
-                                                let remaining = body_len - body_received;
-                                                let mut body_buffer = vec![0u8; remaining];
-                                                let mut total_read = 0;
-
-                                                // Read the remaining body bytes
-                                                while total_read < remaining {
-                                                    match stream
-                                                        .read(&mut body_buffer[total_read..])
-                                                        .await
-                                                    {
-                                                        Ok(0) => break, // Connection closed
-                                                        Ok(size) => {
-                                                            total_read += size;
-                                                        }
-                                                        Err(e) => return Err(e),
-                                                    }
-                                                }
-                                                request_data
-                                                    .extend_from_slice(&body_buffer[..total_read]);
-                                            }
-                                        }
-                                        break;
-                                    }
-                                }
-
-                                Ok::<Vec<u8>, std::io::Error>(request_data)
-                            };
-
-                            let request_data = match tokio::time::timeout(
-                                tokio::time::Duration::from_secs(5),
-                                read_future,
-                            )
-                            .await
-                            {
-                                Ok(Ok(data)) => data,
-                                Ok(Err(e)) => {
-                                    eprintln!("Error reading request: {}", e);
-                                    return;
-                                }
-                                Err(_) => {
-                                    eprintln!("Timeout reading request");
-                                    return;
-                                }
-                            };
-
-                            let request = String::from_utf8_lossy(&request_data);
-
-                            // Check if it's a POST request (for chat completions)
-                            if request.starts_with("POST") {
-                                // Send HTTP response headers
-                                let headers = "HTTP/1.1 200 OK\r\n\
-                                    Content-Type: text/event-stream\r\n\
-                                    Cache-Control: no-cache\r\n\
-                                    Connection: keep-alive\r\n\
-                                    Access-Control-Allow-Origin: *\r\n\r\n";
-
-                                if let Err(e) = stream.write_all(headers.as_bytes()).await {
-                                    eprintln!("Error writing headers: {}", e);
-                                    return;
-                                }
-
-                                // Stream events
-                                let events_guard = events.lock().await;
-                                for event in events_guard.iter() {
-                                    let sse_data = format!("data: {}\n\n", event);
-                                    if let Err(e) = stream.write_all(sse_data.as_bytes()).await {
-                                        eprintln!("Error writing event: {}", e);
-                                        break;
-                                    }
-                                    // Small delay to simulate streaming
-                                    tokio::time::sleep(tokio::time::Duration::from_millis(10))
-                                        .await;
-                                }
-
-                                // Send [DONE] to signal end of stream
-                                if let Err(e) = stream.write_all(b"data: [DONE]\n\n").await {
-                                    eprintln!("Error writing [DONE]: {}", e);
-                                }
-                            } else {
-                                // Send 404 for non-POST requests
-                                let response = "HTTP/1.1 404 Not Found\r\n\r\n";
-                                let _ = stream.write_all(response.as_bytes()).await;
-                            }
-                        });
+                    Ok((stream, _)) => {
+                        server_state.connections.fetch_add(1, Ordering::SeqCst);
+                        tokio::spawn(serve_connection(stream, server_state.clone()));
                     }
                     Err(e) => {
                         eprintln!("Error accepting connection: {}", e);
@@ -162,7 +51,7 @@ impl MockStreamServer {
 
         Ok(Self {
             port,
-            events,
+            state,
             handle,
         })
     }
@@ -174,15 +63,49 @@ impl MockStreamServer {
 
     /// Set the events to stream
     pub async fn set_events(&self, events: Vec<String>) {
-        let mut guard = self.events.lock().await;
-        *guard = events;
+        *self.state.events.lock().await = events;
+    }
+
+    /// Stream `events` in answer to the next request only, later requests get the next queued
+    /// events or the ones set with [`MockStreamServer::set_events`]
+    pub async fn queue_events(&self, events: Vec<String>) {
+        self.state.queued.lock().await.push_back(events);
+    }
+
+    /// Set a header sent along the events
+    pub async fn set_header(&self, name: &str, value: &str) {
+        self.state
+            .headers
+            .lock()
+            .await
+            .push((name.to_string(), value.to_string()));
+    }
+
+    /// Answer with `status` and the JSON `body` instead of streaming events
+    pub async fn fail_with(&self, status: u16, body: String) {
+        *self.state.failure.lock().await = Some((status, body));
+    }
+
+    /// Answer every request, whatever its method, with `payload` as is, e.g. a JSON body or a
+    /// recorded provider stream, instead of streaming events. The connection is kept alive.
+    pub async fn set_payload(&self, content_type: &str, payload: Vec<u8>) {
+        *self.state.payload.lock().await = Some((content_type.to_string(), payload));
+    }
+
+    /// Requests received so far, head and body, in order
+    pub async fn requests(&self) -> Vec<String> {
+        self.state.requests.lock().await.clone()
+    }
+
+    /// Number of connections opened to the server
+    pub fn connections(&self) -> usize {
+        self.state.connections.load(Ordering::SeqCst)
     }
 
     #[allow(dead_code)]
     /// Add an event to stream
     pub async fn add_event(&self, event: String) {
-        let mut guard = self.events.lock().await;
-        guard.push(event);
+        self.state.events.lock().await.push(event);
     }
 }
 
@@ -191,3 +114,138 @@ impl Drop for MockStreamServer {
         self.handle.abort();
     }
 }
+
+/// Answers the requests of one connection until a streamed response closes it
+async fn serve_connection(mut stream: TcpStream, state: Arc<MockState>) {
+    loop {
+        let request = match tokio::time::timeout(
+            tokio::time::Duration::from_secs(5),
+            read_request(&mut stream),
+        )
+        .await
+        {
+            Ok(Ok(Some(request))) => request,
+            Ok(Ok(None)) => return,
+            Ok(Err(e)) => {
+                eprintln!("Error reading request: {}", e);
+                return;
+            }
+            Err(_) => {
+                eprintln!("Timeout reading request");
+                return;
+            }
+        };
+        state.requests.lock().await.push(request.clone());
+
+        let extra_headers: String = state
+            .headers
+            .lock()
+            .await
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}\r\n"))
+            .collect();
+
+        if let Some((status, body)) = state.failure.lock().await.clone() {
+            let response = format!(
+                "HTTP/1.1 {status} Error\r\n\
+                Content-Type: application/json\r\n\
+                Content-Length: {}\r\n\r\n{body}",
+                body.len()
+            );
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        } else if let Some((content_type, body)) = state.payload.lock().await.clone() {
+            let headers = format!(
+                "HTTP/1.1 200 OK\r\n\
+                Content-Type: {content_type}\r\n\
+                {extra_headers}\
+                Content-Length: {}\r\n\r\n",
+                body.len()
+            );
+            if stream.write_all(headers.as_bytes()).await.is_err()
+                || stream.write_all(&body).await.is_err()
+            {
+                return;
+            }
+        } else if request.starts_with("POST") {
+            stream_events(&mut stream, &state, &extra_headers).await;
+            return;
+        } else {
+            // Send 404 for non-POST requests
+            let response = "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+            if stream.write_all(response.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Reads one request, head and body, or `None` once the client closed the connection
+async fn read_request(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut request_data = Vec::new();
+    let mut buffer = [0u8; 8192];
+
+    loop {
+        let size = stream.read(&mut buffer).await?;
+        if size == 0 {
+            return Ok(None);
+        }
+        request_data.extend_from_slice(&buffer[..size]);
+
+        // Check if we have complete headers
+        let Some(headers_end) = request_data.windows(4).position(|w| w == b"\r\n\r\n") else {
+            continue;
+        };
+        let headers_str = String::from_utf8_lossy(&request_data[..headers_end]);
+        let content_length = headers_str
+            .lines()
+            .find_map(|line| {
+                line.to_lowercase()
+                    .strip_prefix("content-length:")
+                    .and_then(|s| s.trim().parse::<usize>().ok())
+            })
+            .unwrap_or_default();
+        if request_data.len() >= headers_end + 4 + content_length {
+            return Ok(Some(String::from_utf8_lossy(&request_data).to_string()));
+        }
+    }
+}
+
+async fn stream_events(stream: &mut TcpStream, state: &MockState, extra_headers: &str) {
+    // Send HTTP response headers
+    let headers = format!(
+        "HTTP/1.1 200 OK\r\n\
+        Content-Type: text/event-stream\r\n\
+        Cache-Control: no-cache\r\n\
+        Connection: keep-alive\r\n\
+        {extra_headers}\
+        Access-Control-Allow-Origin: *\r\n\r\n"
+    );
+
+    if let Err(e) = stream.write_all(headers.as_bytes()).await {
+        eprintln!("Error writing headers: {}", e);
+        return;
+    }
+
+    // Stream events
+    let queued = state.queued.lock().await.pop_front();
+    let events = match queued {
+        Some(events) => events,
+        None => state.events.lock().await.clone(),
+    };
+    for event in events.iter() {
+        let sse_data = format!("data: {}\n\n", event);
+        if let Err(e) = stream.write_all(sse_data.as_bytes()).await {
+            eprintln!("Error writing event: {}", e);
+            break;
+        }
+        // Small delay to simulate streaming
+        tokio::time::sleep(tokio::time::Duration::from_millis(10)).await;
+    }
+
+    // Send [DONE] to signal end of stream
+    if let Err(e) = stream.write_all(b"data: [DONE]\n\n").await {
+        eprintln!("Error writing [DONE]: {}", e);
+    }
+}
@@ -22,6 +22,10 @@ impl Tool for McpTool {
         serde_json::from_value(schema.clone()).ok()
     }
 
+    fn parameters_schema(&self) -> Option<serde_json::Value> {
+        Some(self.0.schema_as_json_value())
+    }
+
     async fn run(
         &self,
         inputs: HashMap<String, serde_json::Value>,
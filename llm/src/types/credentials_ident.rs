@@ -6,13 +6,38 @@ use serde::{Deserialize, Serialize};
 pub enum CredentialsIdent {
     Vllora,
     Own,
+    /// Own credentials whose usage is charged back to a billing entity
+    OwnBilled(String),
+}
+
+impl CredentialsIdent {
+    /// Whether usage is paid with the caller's own credentials
+    pub fn is_own(&self) -> bool {
+        !matches!(self, CredentialsIdent::Vllora)
+    }
+
+    pub fn billing_label(&self) -> Option<&str> {
+        match self {
+            CredentialsIdent::OwnBilled(label) => Some(label),
+            _ => None,
+        }
+    }
+
+    /// Attributes own credentials to `billing_label`. Vllora credentials are not labelled.
+    pub fn with_billing_label(self, billing_label: Option<String>) -> Self {
+        match (self, billing_label) {
+            (CredentialsIdent::Vllora, _) => CredentialsIdent::Vllora,
+            (_, Some(label)) => CredentialsIdent::OwnBilled(label),
+            (ident, None) => ident,
+        }
+    }
 }
 
 impl Display for CredentialsIdent {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             CredentialsIdent::Vllora => write!(f, "vllora"),
-            CredentialsIdent::Own => write!(f, "own"),
+            CredentialsIdent::Own | CredentialsIdent::OwnBilled(_) => write!(f, "own"),
         }
     }
 }
@@ -1,4 +1,4 @@
-use async_openai::types::chat::ResponseFormat;
+use async_openai::types::chat::{PredictionContent, ResponseFormat};
 use clust::messages::{self as claude, StopSequence};
 use minijinja::Environment;
 use serde::de::IntoDeserializer;
@@ -8,12 +8,16 @@ use std::borrow::Cow;
 use std::{collections::HashMap, fmt::Display, ops::Deref, str::FromStr};
 use validator::Validate;
 
+use crate::client::role_normalization::RolePolicy;
 use crate::error::LLMError;
 use crate::types::credentials::BedrockCredentials;
 use crate::types::credentials::{ApiKeyCredentials, Credentials};
 use crate::types::credentials_ident::CredentialsIdent;
-use crate::types::gateway::{ChatCompletionRequest, ProviderSpecificRequest};
-use crate::types::models::{InferenceProvider, ModelType};
+use crate::types::gateway::{
+    AudioOutputOptions, ChatCompletionRequest, Modality, ProviderSpecificRequest, ReasoningEffort,
+    ServiceTier, Thinking,
+};
+use crate::types::models::{InferenceProvider, ModelCapability, ModelType};
 use crate::types::provider::{InferenceModelProvider, ModelPrice};
 use crate::types::tools::ModelTools;
 
@@ -98,6 +102,17 @@ impl CompletionModelDefinition {
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, Default)]
 pub struct ExecutionOptions {
     pub max_retries: Option<u32>,
+    /// Times tool results are sent back to the model within one call before it fails with
+    /// [`crate::error::LLMError::MaxToolIterations`]. Defaults to
+    /// [`crate::client::DEFAULT_MAX_TOOL_ITERATIONS`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tool_iterations: Option<u32>,
+    /// Overrides the provider's default role policy
+    pub role_policy: Option<RolePolicy>,
+    /// Extra HTTP headers sent with every provider call. Honoured by OpenAI compatible
+    /// providers and Anthropic.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub headers: HashMap<String, String>,
 }
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -325,6 +340,43 @@ impl CompletionEngineParams {
             Self::Proxy { params, .. } => params.model.as_deref(),
         }
     }
+
+    /// Base URL the provider client calls, `None` for the provider default
+    pub fn endpoint(&self) -> Option<&str> {
+        match self {
+            Self::OpenAi { endpoint, .. }
+            | Self::Anthropic { endpoint, .. }
+            | Self::Proxy { endpoint, .. } => endpoint.as_deref(),
+            Self::Gemini { api_url, .. } => api_url.as_deref(),
+            Self::Bedrock { .. } => None,
+        }
+    }
+
+    /// Service tier sent to the provider, after mapping it to the provider's tiers
+    pub fn service_tier(&self) -> Option<ServiceTier> {
+        match self {
+            Self::OpenAi { params, .. } | Self::Proxy { params, .. } => params.service_tier,
+            Self::Anthropic { params, .. } => params.service_tier,
+            Self::Gemini { .. } | Self::Bedrock { .. } => None,
+        }
+    }
+
+    /// Sends the calls to `endpoint` instead. Bedrock clients are resolved from their region
+    /// and don't support it.
+    pub fn with_endpoint(mut self, endpoint: String) -> Result<Self, LLMError> {
+        match &mut self {
+            Self::OpenAi { endpoint: e, .. }
+            | Self::Anthropic { endpoint: e, .. }
+            | Self::Proxy { endpoint: e, .. }
+            | Self::Gemini { api_url: e, .. } => *e = Some(endpoint),
+            Self::Bedrock { .. } => {
+                return Err(LLMError::UnsupportedProvider(
+                    "bedrock does not support endpoint overrides".to_string(),
+                ))
+            }
+        }
+        Ok(self)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -335,6 +387,8 @@ pub struct CompletionEngineParamsBuilder {
     pub provider_specific: Option<ProviderSpecificRequest>,
     pub execution_options: Option<ExecutionOptions>,
     pub api_url: Option<String>,
+    #[serde(default)]
+    pub capabilities: Vec<ModelCapability>,
 }
 
 impl Default for CompletionEngineParamsBuilder {
@@ -357,6 +411,7 @@ impl CompletionEngineParamsBuilder {
             provider_specific: None,
             execution_options: None,
             api_url: None,
+            capabilities: vec![],
         }
     }
 
@@ -408,17 +463,102 @@ impl CompletionEngineParamsBuilder {
         self
     }
 
-    pub fn build(
-        &self,
-        request: &ChatCompletionRequest,
-    ) -> Result<CompletionEngineParams, LLMError> {
-        let provider = match &self.provider.custom_inference_api_type {
+    pub fn with_capabilities(mut self, capabilities: Vec<ModelCapability>) -> Self {
+        self.capabilities = capabilities;
+        self
+    }
+
+    /// Engine the request goes to, custom providers name the API they implement
+    fn engine_provider(&self) -> &InferenceModelProvider {
+        match &self.provider.custom_inference_api_type {
             Some(CustomInferenceApiType::OpenAI) => &InferenceModelProvider::OpenAI,
             Some(CustomInferenceApiType::Anthropic) => &InferenceModelProvider::Anthropic,
             Some(CustomInferenceApiType::Bedrock) => &InferenceModelProvider::Bedrock,
             Some(CustomInferenceApiType::Gemini) => &InferenceModelProvider::Gemini,
             None => &self.provider.provider,
-        };
+        }
+    }
+
+    /// Request parameters the engine doesn't take, and the reasoning effort it does.
+    /// [`Self::build`] leaves the dropped parameters out of the engine parameters.
+    pub fn param_adjustments(&self, request: &ChatCompletionRequest) -> ParamAdjustments {
+        let provider = self.engine_provider();
+        let is_openai = matches!(
+            provider,
+            InferenceModelProvider::OpenAI | InferenceModelProvider::Proxy(_)
+        );
+        let mut adjustments = ParamAdjustments::default();
+        // Predicted outputs are OpenAI only, other engines don't map them
+        if request.prediction.is_some() && !is_openai {
+            adjustments.dropped_params.push("prediction");
+        }
+        // Token ids are tokenizer specific, only OpenAI compatible engines take a bias
+        if request.logit_bias.is_some() && !is_openai {
+            adjustments.dropped_params.push("logit_bias");
+        }
+        // Only OpenAI and Anthropic have service tiers, Anthropic lacks some of OpenAI's
+        let anthropic_service_tier = request.service_tier.and_then(ServiceTier::for_anthropic);
+        if request.service_tier.is_some()
+            && !is_openai
+            && !(provider == &InferenceModelProvider::Anthropic && anthropic_service_tier.is_some())
+        {
+            adjustments.dropped_params.push("service_tier");
+        }
+        // Gemini takes the effort as a thinking budget, unless the request sets one itself
+        if let Some(effort) = request.reasoning_effort {
+            let is_gemini = provider == &InferenceModelProvider::Gemini;
+            if is_openai || (is_gemini && self.explicit_thinking().is_none()) {
+                adjustments.reasoning_effort = Some(effort);
+            } else {
+                adjustments.dropped_params.push("reasoning_effort");
+            }
+        }
+        adjustments
+    }
+
+    fn explicit_thinking(&self) -> Option<Thinking> {
+        self.provider_specific
+            .as_ref()
+            .and_then(|ps| ps.thinking.clone())
+    }
+
+    pub fn build(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<CompletionEngineParams, LLMError> {
+        let provider = self.engine_provider();
+        let is_openai = matches!(
+            provider,
+            InferenceModelProvider::OpenAI | InferenceModelProvider::Proxy(_)
+        );
+        // Clients of reasoning models send `max_completion_tokens`, which other engines only
+        // know as their max tokens
+        let max_tokens = request.max_tokens.or(request.max_completion_tokens);
+        let anthropic_service_tier = request.service_tier.and_then(ServiceTier::for_anthropic);
+        let gemini_thinking = self.explicit_thinking().or_else(|| {
+            request.reasoning_effort.map(|effort| Thinking {
+                r#type: "enabled".to_string(),
+                budget_tokens: effort.thinking_budget(),
+            })
+        });
+        // Audio output can't be dropped silently, the caller expects audio back
+        if request.wants_audio_output() {
+            if !is_openai {
+                return Err(LLMError::CustomError(format!(
+                    "Audio output is not supported by {provider} models"
+                )));
+            }
+            if request.audio.is_none() {
+                return Err(LLMError::CustomError(
+                    "Audio output requires `audio` with a voice and format".to_string(),
+                ));
+            }
+            if request.stream.unwrap_or(false) {
+                return Err(LLMError::CustomError(
+                    "Audio output is not supported on streamed requests".to_string(),
+                ));
+            }
+        }
 
         // Fall back to existing behavior based on provider.provider
         match provider {
@@ -433,6 +573,7 @@ impl CompletionEngineParamsBuilder {
                     logprobs: None,
                     top_logprobs: None,
                     max_tokens: request.max_tokens,
+                    max_completion_tokens: request.max_completion_tokens,
                     presence_penalty: request.presence_penalty,
                     seed: request.seed,
                     stop: request.stop.clone(),
@@ -441,6 +582,12 @@ impl CompletionEngineParamsBuilder {
                     user: request.user.clone(),
                     response_format: request.response_format.clone(),
                     prompt_cache_key: request.prompt_cache_key.clone(),
+                    prediction: request.prediction.clone(),
+                    service_tier: request.service_tier.map(ServiceTier::for_openai),
+                    modalities: request.modalities.clone(),
+                    audio: request.audio.clone(),
+                    reasoning_effort: request.reasoning_effort,
+                    reasoning_model: self.capabilities.contains(&ModelCapability::Reasoning),
                 };
                 let mut custom_endpoint = None;
                 let api_key_credentials = self.credentials.clone().and_then(|cred| match cred {
@@ -502,7 +649,7 @@ impl CompletionEngineParamsBuilder {
                             .model_name
                             .clone()
                             .or_else(|| Some(request.model.clone())),
-                        max_tokens: request.max_tokens.map(|x| x as i32),
+                        max_tokens: max_tokens.map(|x| x as i32),
                         temperature: request.temperature,
                         top_p: request.top_p,
                         stop_sequences: request.stop.clone(),
@@ -524,7 +671,7 @@ impl CompletionEngineParamsBuilder {
                     endpoint: self.provider.endpoint.clone(),
                     params: AnthropicModelParams {
                         model: Some(model.clone()),
-                        max_tokens: match request.max_tokens {
+                        max_tokens: match max_tokens {
                             Some(x) => Some(clust::messages::MaxTokens::new(x, model.model)?),
                             None => None,
                         },
@@ -554,6 +701,7 @@ impl CompletionEngineParamsBuilder {
                                     budget_tokens: thinking.budget_tokens,
                                 })
                         }),
+                        service_tier: anthropic_service_tier,
                     },
                 })
             }
@@ -570,7 +718,7 @@ impl CompletionEngineParamsBuilder {
                             .model_name
                             .clone()
                             .or_else(|| Some(request.model.clone())),
-                        max_output_tokens: request.max_tokens.map(|x| x as i32),
+                        max_output_tokens: max_tokens.map(|x| x as i32),
                         temperature: request.temperature,
                         top_p: request.top_p,
                         stop_sequences: request.stop.clone(),
@@ -586,6 +734,7 @@ impl CompletionEngineParamsBuilder {
                         logprobs: None,
                         top_k: None,
                         response_format: request.response_format.clone(),
+                        thinking: gemini_thinking,
                     },
                     api_url: self.api_url.clone(),
                 })
@@ -680,7 +829,7 @@ pub struct OpenAiModelParams {
     /// Mathematically, the bias is added to the logits generated by the model prior to sampling.
     /// The exact effect will vary per model, but values between -1 and 1 should decrease or increase likelihood of selection;
     /// values like -100 or 100 should result in a ban or exclusive selection of the relevant token.
-    pub logit_bias: Option<HashMap<String, i8>>, // default: null
+    pub logit_bias: Option<HashMap<String, i32>>, // default: null
 
     /// Whether to return log probabilities of the output tokens or not. If true, returns the log probabilities of each output token returned in the `content` of `message`.
     pub logprobs: Option<bool>,
@@ -694,6 +843,11 @@ pub struct OpenAiModelParams {
     /// The total length of input tokens and generated tokens is limited by the model's context length. [Example Python code](https://cookbook.openai.com/examples/how_to_count_tokens_with_tiktoken) for counting tokens.
     pub max_tokens: Option<u32>,
 
+    /// An upper bound for the number of tokens that can be generated for a completion, including visible output tokens and reasoning tokens.
+    /// Reasoning models only accept this instead of `max_tokens`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+
     /// Number between -2.0 and 2.0. Positive values penalize new tokens based on whether they appear in the text so far, increasing the model's likelihood to talk about new topics.
     ///
     /// [See more information about frequency and presence penalties.](https://platform.openai.com/docs/api-reference/parameter-details)
@@ -736,6 +890,28 @@ pub struct OpenAiModelParams {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompt_cache_key: Option<String>,
+
+    /// Predicted output, accepted and rejected prediction tokens are reported in the usage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prediction: Option<PredictionContent>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
+
+    /// Output types, audio needs `audio` set as well
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioOutputOptions>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+
+    /// Set from the model's `reasoning` capability. Reasoning models get `max_tokens`
+    /// translated to `max_completion_tokens` and unsupported sampling parameters dropped.
+    #[serde(skip)]
+    pub reasoning_model: bool,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Validate)]
@@ -833,6 +1009,11 @@ pub struct AnthropicModelParams {
     pub top_k: Option<claude::TopK>,
 
     pub thinking: Option<claude::Thinking>,
+
+    /// Sent with batch jobs. The Messages client has no field for it, so messages use the
+    /// API default `auto` tier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, Default)]
@@ -852,12 +1033,38 @@ pub struct GeminiModelParams {
     pub response_logprobs: Option<bool>,
     pub logprobs: Option<i32>,
     pub response_format: Option<ResponseFormat>,
+    /// Thinking budget, from `provider_specific.thinking` or else the request's
+    /// `reasoning_effort`. Also returns thought summaries.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<Thinking>,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CompletionModelParams {
     pub engine: CompletionEngineParams,
     pub provider_name: String,
+    #[serde(skip)]
+    pub adjustments: ParamAdjustments,
+}
+
+/// Request parameters dropped for the engine of a model and the reasoning effort passed on
+/// to it, see [`CompletionEngineParamsBuilder::param_adjustments`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamAdjustments {
+    pub dropped_params: Vec<&'static str>,
+    pub reasoning_effort: Option<ReasoningEffort>,
+}
+
+impl ParamAdjustments {
+    /// Records the adjustments on the model call `span`
+    pub fn record(&self, span: &tracing::Span) {
+        if let Some(effort) = self.reasoning_effort {
+            span.record("reasoning_effort", effort.as_str());
+        }
+        if !self.dropped_params.is_empty() {
+            span.record("dropped_params", self.dropped_params.join(","));
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -1093,7 +1300,10 @@ impl ResponsesEngineParamsBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::provider::tests::MockStreamServer;
     use crate::types::gateway::ChatCompletionRequest;
+    use crate::types::instance::init_model_instance;
+    use crate::types::ModelEventType;
 
     #[test]
     fn test_custom_inference_api_type_parsing() {
@@ -1167,4 +1377,171 @@ mod tests {
             _ => panic!("Expected Bedrock engine params"),
         }
     }
+
+    #[test]
+    fn test_reasoning_effort_maps_to_gemini_thinking_budget() {
+        let mut builder = CompletionEngineParamsBuilder::new();
+        builder.provider.provider = InferenceModelProvider::Gemini;
+        builder.model_name = Some("gemini-2.5-flash".to_string());
+
+        let request = ChatCompletionRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages: vec![],
+            reasoning_effort: Some(ReasoningEffort::Low),
+            ..Default::default()
+        };
+
+        match builder.build(&request).unwrap() {
+            CompletionEngineParams::Gemini { params, .. } => {
+                let thinking = params.thinking.expect("Expected a thinking budget");
+                assert_eq!(thinking.budget_tokens, 1024);
+            }
+            _ => panic!("Expected Gemini engine params"),
+        }
+    }
+
+    #[test]
+    fn test_param_adjustments_depend_on_the_engine() {
+        let request = ChatCompletionRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages: vec![],
+            reasoning_effort: Some(ReasoningEffort::Low),
+            logit_bias: Some(HashMap::from([("50256".to_string(), -100)])),
+            ..Default::default()
+        };
+
+        let mut builder = CompletionEngineParamsBuilder::new();
+        builder.provider.provider = InferenceModelProvider::Gemini;
+        assert_eq!(
+            builder.param_adjustments(&request),
+            ParamAdjustments {
+                dropped_params: vec!["logit_bias"],
+                reasoning_effort: Some(ReasoningEffort::Low),
+            }
+        );
+
+        builder.provider.provider = InferenceModelProvider::Bedrock;
+        assert_eq!(
+            builder.param_adjustments(&request),
+            ParamAdjustments {
+                dropped_params: vec!["logit_bias", "reasoning_effort"],
+                reasoning_effort: None,
+            }
+        );
+
+        builder.provider.provider = InferenceModelProvider::OpenAI;
+        assert_eq!(
+            builder.param_adjustments(&request).dropped_params,
+            Vec::<&str>::new()
+        );
+    }
+
+    #[test]
+    fn test_max_completion_tokens_is_the_max_tokens_of_other_engines() {
+        let request = ChatCompletionRequest {
+            model: "gemini-2.5-flash".to_string(),
+            messages: vec![],
+            max_completion_tokens: Some(512),
+            ..Default::default()
+        };
+
+        let mut builder = CompletionEngineParamsBuilder::new();
+        builder.provider.provider = InferenceModelProvider::Gemini;
+        match builder.build(&request).unwrap() {
+            CompletionEngineParams::Gemini { params, .. } => {
+                assert_eq!(params.max_output_tokens, Some(512));
+            }
+            _ => panic!("Expected Gemini engine params"),
+        }
+
+        builder.provider.provider = InferenceModelProvider::Bedrock;
+        match builder.build(&request).unwrap() {
+            CompletionEngineParams::Bedrock { params, .. } => {
+                assert_eq!(params.max_tokens, Some(512));
+            }
+            _ => panic!("Expected Bedrock engine params"),
+        }
+
+        // `max_tokens` wins when both are set
+        let request = ChatCompletionRequest {
+            max_tokens: Some(256),
+            ..request
+        };
+        builder.provider.provider = InferenceModelProvider::Gemini;
+        match builder.build(&request).unwrap() {
+            CompletionEngineParams::Gemini { params, .. } => {
+                assert_eq!(params.max_output_tokens, Some(256));
+            }
+            _ => panic!("Expected Gemini engine params"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_endpoint_override_reaches_provider_client() {
+        let server = MockStreamServer::start()
+            .await
+            .expect("Failed to start mock server");
+        server.set_events(vec![
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{"content":"Hello"},"finish_reason":null}]}"#.to_string(),
+            r#"{"id":"chatcmpl-123","object":"chat.completion.chunk","created":1694268190,"model":"gpt-4o-mini","choices":[{"index":0,"delta":{},"finish_reason":"stop"}]}"#.to_string(),
+        ]).await;
+        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+
+        let request: ChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o-mini",
+            "messages": [{"role": "user", "content": "Hi"}]
+        }))
+        .unwrap();
+        let engine = CompletionEngineParamsBuilder::new()
+            .with_provider(InferenceProvider {
+                provider: InferenceModelProvider::OpenAI,
+                model_name: "gpt-4o-mini".to_string(),
+                endpoint: None,
+                custom_inference_api_type: None,
+            })
+            .with_credentials(Credentials::ApiKey(ApiKeyCredentials {
+                api_key: "test".to_string(),
+            }))
+            .build(&request)
+            .unwrap();
+        assert_eq!(engine.endpoint(), None);
+
+        let engine = engine.with_endpoint(server.url()).unwrap();
+        assert_eq!(engine.endpoint(), Some(server.url().as_str()));
+
+        let instance = init_model_instance(engine, HashMap::new()).await.unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        instance
+            .stream(HashMap::new(), tx, vec![], HashMap::new())
+            .await
+            .expect("Failed to stream from the overridden endpoint");
+
+        let mut content = String::new();
+        while let Some(Some(event)) = rx.recv().await {
+            if let ModelEventType::LlmContent(event) = event.event {
+                content.push_str(&event.content);
+            }
+        }
+        assert_eq!(content, "Hello");
+    }
+
+    #[test]
+    fn test_bedrock_rejects_endpoint_override() {
+        let engine = CompletionEngineParams::Bedrock {
+            credentials: None,
+            execution_options: ExecutionOptions::default(),
+            params: BedrockModelParams {
+                model_id: Some("anthropic.claude-3-haiku".to_string()),
+                max_tokens: None,
+                temperature: None,
+                top_p: None,
+                stop_sequences: None,
+                additional_parameters: HashMap::new(),
+            },
+        };
+
+        assert!(engine
+            .with_endpoint("https://bedrock.example.com".to_string())
+            .is_err());
+    }
 }
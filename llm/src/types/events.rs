@@ -232,6 +232,14 @@ pub enum CustomEventType {
     GlobalBreakpoint {
         intercept_all: bool,
     },
+    LlmInterimUsage {
+        output_tokens: u32,
+        estimated: bool,
+    },
+    /// Reasoning delta, for rendering thinking progress apart from the message
+    LlmReasoning {
+        delta: String,
+    },
 }
 
 impl Event {
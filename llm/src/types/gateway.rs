@@ -1,8 +1,11 @@
+use crate::client::role_normalization::RolePolicy;
 use crate::provider::gemini::types::Candidate;
 use crate::provider::gemini::types::Content as GeminiContent;
 use crate::types::cache::ResponseCacheOptions;
 use crate::types::credentials_ident::CredentialsIdent;
+use crate::types::prompts::PromptReference;
 use crate::types::provider::ModelPrice;
+use crate::types::rate_limit::ProviderRateLimit;
 use crate::types::tools::ModelTool;
 use crate::types::tools::Tool;
 use crate::types::ModelFinishReason;
@@ -32,6 +35,8 @@ pub use async_openai::types::chat::ResponseFormatJsonSchema;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ChatCompletionRequest {
+    /// Empty when the request doesn't name a model, the gateway may apply its default
+    #[serde(default)]
     pub model: String,
     #[serde(default)]
     pub messages: Vec<ChatCompletionMessage>,
@@ -48,11 +53,13 @@ pub struct ChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_tokens: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub presence_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub frequency_penalty: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub logit_bias: Option<HashMap<String, i8>>,
+    pub logit_bias: Option<HashMap<String, i32>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -71,8 +78,166 @@ pub struct ChatCompletionRequest {
     pub tool_choice: Option<Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream_options: Option<StreamOptions>,
+    /// End streamed responses with a usage chunk, estimated when the provider doesn't report
+    /// usage. Takes precedence over `stream_options.include_usage`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_usage: Option<bool>,
+    /// Wire format of streamed responses. When unset it is negotiated from the `Accept`
+    /// header, defaulting to server-sent events.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_format: Option<StreamFormat>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub prompt_cache_key: Option<String>,
+    /// Predicted output, e.g. the file being edited. Only OpenAI models use it, other
+    /// providers drop it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prediction: Option<async_openai::types::chat::PredictionContent>,
+    /// Provider processing tier, trading latency for cost. Providers without tiers drop it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_tier: Option<ServiceTier>,
+    /// Output types to generate, `["text", "audio"]` for spoken responses. Only OpenAI
+    /// models produce audio, other providers reject it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub modalities: Option<Vec<Modality>>,
+    /// Voice and format of audio output, required with the `audio` modality
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio: Option<AudioOutputOptions>,
+    /// Effort reasoning models spend on reasoning. OpenAI models take it as is, Gemini
+    /// models as a thinking budget, other providers drop it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reasoning_effort: Option<ReasoningEffort>,
+    /// Stored prompt whose messages go before `messages`. Chat completion APIs don't
+    /// resolve stored prompts, the gateway renders them from its own prompt templates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<PromptReference>,
+}
+
+impl ChatCompletionRequest {
+    pub fn wants_audio_output(&self) -> bool {
+        self.modalities
+            .as_ref()
+            .is_some_and(|modalities| modalities.contains(&Modality::Audio))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Modality {
+    Text,
+    Audio,
+}
+
+/// Audio output settings, e.g. `{"voice": "alloy", "format": "wav"}`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AudioOutputOptions {
+    pub voice: String,
+    pub format: String,
+}
+
+/// Wire formats of streamed chat completions. Every format carries the same chunks.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamFormat {
+    /// OpenAI style server-sent events, ending with `data: [DONE]`
+    #[default]
+    Sse,
+    /// One chunk per line
+    Ndjson,
+    /// One JSON array of all chunks
+    JsonArray,
+}
+
+impl StreamFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StreamFormat::Sse => "text/event-stream",
+            StreamFormat::Ndjson => "application/x-ndjson",
+            StreamFormat::JsonArray => "application/json",
+        }
+    }
+}
+
+/// Reasoning effort of reasoning models, trading latency for quality
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+}
+
+impl ReasoningEffort {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+        }
+    }
+
+    /// Gemini thinking budget, in tokens, of the same effort
+    pub fn thinking_budget(&self) -> u64 {
+        match self {
+            ReasoningEffort::Low => 1024,
+            ReasoningEffort::Medium => 8192,
+            ReasoningEffort::High => 24576,
+        }
+    }
+}
+
+impl Display for ReasoningEffort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Service tiers accepted on requests. OpenAI takes every tier but `standard_only`, which is
+/// sent as `default`. Anthropic only distinguishes `auto` (priority capacity when available)
+/// from `standard_only`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ServiceTier {
+    Auto,
+    Default,
+    Flex,
+    Priority,
+    Scale,
+    StandardOnly,
+}
+
+impl ServiceTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ServiceTier::Auto => "auto",
+            ServiceTier::Default => "default",
+            ServiceTier::Flex => "flex",
+            ServiceTier::Priority => "priority",
+            ServiceTier::Scale => "scale",
+            ServiceTier::StandardOnly => "standard_only",
+        }
+    }
+
+    pub fn for_openai(self) -> ServiceTier {
+        match self {
+            ServiceTier::StandardOnly => ServiceTier::Default,
+            tier => tier,
+        }
+    }
+
+    /// `None` for tiers Anthropic has no equivalent of
+    pub fn for_anthropic(self) -> Option<ServiceTier> {
+        match self {
+            ServiceTier::Auto | ServiceTier::Priority => Some(ServiceTier::Auto),
+            ServiceTier::Default | ServiceTier::StandardOnly => Some(ServiceTier::StandardOnly),
+            ServiceTier::Flex | ServiceTier::Scale => None,
+        }
+    }
+}
+
+impl Display for ServiceTier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
 impl ChatCompletionRequest {
@@ -80,6 +245,14 @@ impl ChatCompletionRequest {
         self.model = model;
         self
     }
+
+    /// Whether a streamed response ends with a usage chunk. Usage is included unless
+    /// `include_usage` or `stream_options.include_usage` turns it off.
+    pub fn include_usage(&self) -> bool {
+        self.include_usage
+            .or_else(|| self.stream_options.as_ref().map(|o| o.include_usage))
+            .unwrap_or(true)
+    }
 }
 
 impl Hash for ChatCompletionRequest {
@@ -107,9 +280,15 @@ impl From<CreateChatCompletionRequest> for ChatCompletionRequest {
             }),
             #[allow(deprecated)]
             max_tokens: request.max_tokens,
+            max_completion_tokens: request.max_completion_tokens,
             presence_penalty: request.presence_penalty,
             frequency_penalty: request.frequency_penalty,
-            logit_bias: request.logit_bias,
+            logit_bias: request.logit_bias.map(|logit_bias| {
+                logit_bias
+                    .into_iter()
+                    .map(|(token, bias)| (token, bias.into()))
+                    .collect()
+            }),
             #[allow(deprecated)]
             user: request.user,
             response_format: request.response_format,
@@ -126,6 +305,7 @@ impl From<CreateChatCompletionRequest> for ChatCompletionRequest {
                         description: function.description.clone(),
                         #[allow(deprecated)]
                         parameters: Some(function.parameters.clone()),
+                        strict: None,
                     })
                     .collect()
             }),
@@ -155,11 +335,13 @@ impl From<CreateChatCompletionRequest> for ChatCompletionRequest {
                                 name: function.function.name.clone(),
                                 description: function.function.description.clone(),
                                 parameters: function.function.parameters.clone(),
+                                strict: function.function.strict,
                             },
                             ChatCompletionTools::Custom(custom) => ChatCompletionFunction {
                                 name: custom.custom.name.clone(),
                                 description: custom.custom.description.clone(),
                                 parameters: None,
+                                strict: None,
                             },
                         },
                     })
@@ -202,7 +384,27 @@ impl From<CreateChatCompletionRequest> for ChatCompletionRequest {
             stream_options: request.stream_options.map(|stream_options| StreamOptions {
                 include_usage: stream_options.include_usage.unwrap_or(false),
             }),
+            include_usage: None,
+            stream_format: None,
             prompt_cache_key: request.prompt_cache_key,
+            prediction: request.prediction,
+            service_tier: request
+                .service_tier
+                .and_then(|tier| serde_json::to_value(tier).ok())
+                .and_then(|tier| serde_json::from_value(tier).ok()),
+            modalities: request
+                .modalities
+                .and_then(|modalities| serde_json::to_value(modalities).ok())
+                .and_then(|modalities| serde_json::from_value(modalities).ok()),
+            audio: request
+                .audio
+                .and_then(|audio| serde_json::to_value(audio).ok())
+                .and_then(|audio| serde_json::from_value(audio).ok()),
+            reasoning_effort: request
+                .reasoning_effort
+                .and_then(|effort| serde_json::to_value(effort).ok())
+                .and_then(|effort| serde_json::from_value(effort).ok()),
+            prompt: None,
         }
     }
 }
@@ -368,6 +570,32 @@ pub struct Extra {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     pub variables: Option<HashMap<String, serde_json::Value>>,
+
+    /// Sends the request directly to this model, bypassing any configured router.
+    /// Only honoured when the gateway allows forcing models.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub force_model: Option<String>,
+
+    /// Emits an estimated output token count every N streamed chunks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interim_usage_every: Option<u32>,
+
+    /// Overrides how the conversation is reshaped for the provider's role constraints.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role_policy: Option<RolePolicy>,
+
+    /// Sends the provider call to this base URL instead of the model's endpoint.
+    /// Only honoured for hosts the gateway allows.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Request metadata the gateway may forward to the provider, see metadata propagation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+    /// Restricts the tools offered to the model for this request to these names.
+    /// Forcing a tool outside the list through `tool_choice` is rejected.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allowed_tools: Option<Vec<String>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -401,6 +629,8 @@ pub struct ChatCompletionRequestWithTools<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_retries: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tool_iterations: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub extra: Option<Extra>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub fallbacks: Option<Vec<ModelNameOrTarget>>,
@@ -587,6 +817,8 @@ pub enum ContentType {
     ImageUrl,
     InputAudio,
     File,
+    /// Audio generated by the model, base64 `data` in `audio`
+    Audio,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Hash, PartialEq, Eq, Default)]
@@ -617,6 +849,22 @@ impl ChatCompletionContent {
         }
     }
 
+    /// Audio output as content parts: the transcript as text, then the audio
+    pub fn audio_output(transcript: String, data: String, format: String) -> Self {
+        Self::Content(vec![
+            Content {
+                r#type: ContentType::Text,
+                text: Some(transcript),
+                ..Default::default()
+            },
+            Content {
+                r#type: ContentType::Audio,
+                audio: Some(InputAudio { data, format }),
+                ..Default::default()
+            },
+        ])
+    }
+
     pub fn as_content(&self) -> Option<Vec<Content>> {
         match self {
             ChatCompletionContent::Content(content) => Some(content.clone()),
@@ -636,6 +884,9 @@ pub struct ChatCompletionMessage {
     pub role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<ChatCompletionContent>,
+    /// Speaker of the message, to tell participants with the same role apart
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -682,6 +933,14 @@ impl ChatCompletionMessageWithFinishReason {
     pub fn message(&self) -> &ChatCompletionMessage {
         &self.message
     }
+
+    pub fn message_mut(&mut self) -> &mut ChatCompletionMessage {
+        &mut self.message
+    }
+
+    pub fn usage(&self) -> Option<&GatewayModelUsage> {
+        self.usage.as_ref()
+    }
 }
 
 impl From<ChatCompletionMessageWithFinishReason>
@@ -886,6 +1145,9 @@ pub struct ChatCompletionFunction {
     pub name: String,
     pub description: Option<String>,
     pub parameters: Option<serde_json::Value>,
+    /// Requires tool call arguments to match `parameters` exactly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -905,6 +1167,9 @@ pub struct ChatCompletionResponse {
     pub usage: ChatCompletionUsage,
     #[serde(skip_serializing)]
     pub is_cache_used: Option<bool>,
+    /// Provider rate limits reported with this response
+    #[serde(skip)]
+    pub rate_limit: Option<ProviderRateLimit>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -912,6 +1177,16 @@ pub struct ChatCompletionChoice {
     pub index: i32,
     pub message: ChatCompletionMessage,
     pub finish_reason: Option<String>,
+    /// Set when the gateway redacted part of the message content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redaction: Option<OutputRedaction>,
+}
+
+/// Categories of the content the gateway redacted from a choice. The finish reason is
+/// left as reported by the provider.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutputRedaction {
+    pub categories: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -924,6 +1199,38 @@ pub struct ChatCompletionUsage {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub completion_tokens_details: Option<CompletionTokensDetails>,
     pub cost: f64,
+    /// Input tokens read from the provider's prompt cache, included in `prompt_tokens`
+    #[serde(default)]
+    pub cached_input_tokens: u32,
+    /// Input tokens written to the provider's prompt cache, included in `prompt_tokens`
+    #[serde(default)]
+    pub cache_write_tokens: u32,
+}
+
+impl ChatCompletionUsage {
+    /// Sets the cache token counts from `prompt_tokens_details`
+    pub fn with_cache_tokens(mut self) -> Self {
+        if let Some(details) = &self.prompt_tokens_details {
+            self.cached_input_tokens = details.cached_tokens();
+            self.cache_write_tokens = details.cache_creation_tokens();
+        }
+        self
+    }
+}
+
+impl From<&GatewayModelUsage> for ChatCompletionUsage {
+    fn from(val: &GatewayModelUsage) -> Self {
+        ChatCompletionUsage {
+            prompt_tokens: val.input_tokens as i32,
+            completion_tokens: val.output_tokens as i32,
+            total_tokens: val.total_tokens as i32,
+            prompt_tokens_details: val.prompt_tokens_details.clone(),
+            completion_tokens_details: val.completion_tokens_details.clone(),
+            cost: 0.0,
+            cached_input_tokens: val.cached_input_tokens(),
+            cache_write_tokens: val.cache_write_tokens(),
+        }
+    }
 }
 
 impl From<async_openai::types::chat::CompletionUsage> for ChatCompletionUsage {
@@ -935,7 +1242,9 @@ impl From<async_openai::types::chat::CompletionUsage> for ChatCompletionUsage {
             prompt_tokens_details: val.prompt_tokens_details.map(|p| p.into()),
             completion_tokens_details: val.completion_tokens_details.map(|c| c.into()),
             cost: 0.0,
+            ..Default::default()
         }
+        .with_cache_tokens()
     }
 }
 
@@ -946,7 +1255,9 @@ impl From<crate::provider::gemini::types::UsageMetadata> for ChatCompletionUsage
             completion_tokens: val.candidates_token_count.unwrap_or(0) as i32
                 + val.thoughts_token_count.unwrap_or(0) as i32,
             total_tokens: val.total_token_count as i32,
-            prompt_tokens_details: None,
+            prompt_tokens_details: val
+                .cached_content_token_count
+                .map(|t| PromptTokensDetails::new(Some(t), None, None)),
             completion_tokens_details: val.thoughts_token_count.as_ref().map(|t| {
                 CompletionTokensDetails {
                     reasoning_tokens: *t,
@@ -954,14 +1265,20 @@ impl From<crate::provider::gemini::types::UsageMetadata> for ChatCompletionUsage
                 }
             }),
             cost: 0.0,
+            ..Default::default()
         }
+        .with_cache_tokens()
     }
 }
 
 impl From<TokenUsage> for ChatCompletionUsage {
     fn from(val: TokenUsage) -> Self {
+        // Bedrock counts cache reads and writes apart from `input_tokens`
+        let prompt_tokens = val.input_tokens
+            + val.cache_read_input_tokens.unwrap_or(0)
+            + val.cache_write_input_tokens.unwrap_or(0);
         ChatCompletionUsage {
-            prompt_tokens: val.input_tokens,
+            prompt_tokens,
             completion_tokens: val.output_tokens,
             total_tokens: val.total_tokens,
             prompt_tokens_details: Some(PromptTokensDetails::new(
@@ -971,6 +1288,27 @@ impl From<TokenUsage> for ChatCompletionUsage {
             )),
             completion_tokens_details: None,
             cost: 0.0,
+            ..Default::default()
+        }
+        .with_cache_tokens()
+    }
+}
+
+impl From<&TokenUsage> for GatewayModelUsage {
+    fn from(val: &TokenUsage) -> Self {
+        let input_tokens = val.input_tokens
+            + val.cache_read_input_tokens.unwrap_or(0)
+            + val.cache_write_input_tokens.unwrap_or(0);
+        GatewayModelUsage {
+            input_tokens: input_tokens as u32,
+            output_tokens: val.output_tokens as u32,
+            total_tokens: val.total_tokens as u32,
+            prompt_tokens_details: Some(PromptTokensDetails::new(
+                val.cache_read_input_tokens.map(|t| t as u32),
+                val.cache_write_input_tokens.map(|t| t as u32),
+                None,
+            )),
+            ..Default::default()
         }
     }
 }
@@ -1012,6 +1350,7 @@ pub struct ChatModel {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FunctionParameters {
     pub r#type: String,
+    #[serde(default)]
     pub properties: HashMap<String, Property>,
     pub required: Option<Vec<String>>,
 }
@@ -1053,6 +1392,32 @@ pub struct ChatCompletionChunk {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(default)]
     pub usage: Option<ChatCompletionUsage>,
+    /// Step of a tool loop run by the gateway, sent in a chunk without choices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(default)]
+    pub tool_event: Option<ToolStreamEvent>,
+}
+
+/// Tool calls the gateway runs on behalf of the model while streaming, so clients can follow
+/// every step of the loop and not only the final text
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ToolStreamEvent {
+    ToolStart {
+        id: String,
+        name: String,
+        input: String,
+    },
+    /// Output of a tool started with [`ToolStreamEvent::ToolStart`]
+    ToolResult {
+        id: String,
+        name: String,
+        output: String,
+        is_error: bool,
+    },
+    /// The model is called again with the tool results, the chunks that follow belong to
+    /// its continued generation
+    StepStart,
 }
 
 impl From<clust::messages::Usage> for ChatCompletionUsage {
@@ -1072,6 +1437,28 @@ impl From<clust::messages::Usage> for ChatCompletionUsage {
             )),
             completion_tokens_details: None,
             cost: 0.0,
+            ..Default::default()
+        }
+        .with_cache_tokens()
+    }
+}
+
+impl From<&clust::messages::Usage> for GatewayModelUsage {
+    fn from(usage: &clust::messages::Usage) -> Self {
+        // Anthropic counts cache reads and writes apart from `input_tokens`
+        let input_tokens = usage.input_tokens
+            + usage.cache_read_input_tokens.unwrap_or(0)
+            + usage.cache_creation_input_tokens.unwrap_or(0);
+        GatewayModelUsage {
+            input_tokens,
+            output_tokens: usage.output_tokens,
+            total_tokens: input_tokens + usage.output_tokens,
+            prompt_tokens_details: Some(PromptTokensDetails::new(
+                usage.cache_read_input_tokens,
+                usage.cache_creation_input_tokens,
+                None,
+            )),
+            ..Default::default()
         }
     }
 }
@@ -1085,6 +1472,7 @@ impl From<DeltaUsage> for ChatCompletionUsage {
             prompt_tokens_details: None,
             completion_tokens_details: None,
             cost: 0.0,
+            ..Default::default()
         }
     }
 }
@@ -1098,6 +1486,7 @@ impl From<CreateChatCompletionStreamResponse> for ChatCompletionChunk {
             model: val.model,
             choices: val.choices.into_iter().map(|c| c.into()).collect(),
             usage: val.usage.map(|u| u.into()),
+            tool_event: None,
         }
     }
 }
@@ -1153,6 +1542,9 @@ pub struct ChatCompletionDelta {
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_calls: Option<Vec<ToolCall>>,
+    /// Reasoning (thinking) delta of reasoning models, kept out of `content`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning_content: Option<String>,
 }
 
 impl ChatCompletionDelta {
@@ -1161,6 +1553,7 @@ impl ChatCompletionDelta {
             role: Some("assistant".to_string()),
             content: Some(text),
             tool_calls: None,
+            reasoning_content: None,
         }
     }
 
@@ -1169,6 +1562,7 @@ impl ChatCompletionDelta {
             role: Some("tool".to_string()),
             content: None,
             tool_calls: Some(vec![tool_call]),
+            reasoning_content: None,
         }
     }
 }
@@ -1181,6 +1575,7 @@ impl From<ChatCompletionStreamResponseDelta> for ChatCompletionDelta {
             tool_calls: val
                 .tool_calls
                 .map(|t| t.into_iter().map(|t| t.into()).collect()),
+            reasoning_content: None,
         }
     }
 }
@@ -1189,10 +1584,15 @@ impl From<GeminiContent> for ChatCompletionDelta {
     fn from(val: GeminiContent) -> Self {
         let mut tool_calls: Option<Vec<ToolCall>> = None;
         let mut text = None;
+        let mut reasoning_content = None;
         let mut contents: Vec<Content> = vec![];
         for part in val.parts {
             let signature = part.thought_signature.clone();
+            let is_thought = part.is_thought();
             match part.part {
+                crate::provider::gemini::types::Part::Text(part_text) if is_thought => {
+                    reasoning_content = Some(part_text);
+                }
                 crate::provider::gemini::types::Part::FunctionCall { name, args } => {
                     let tool_call = ToolCall {
                         id: name.clone(),
@@ -1283,6 +1683,7 @@ impl From<GeminiContent> for ChatCompletionDelta {
             }),
             content,
             tool_calls,
+            reasoning_content,
         }
     }
 }
@@ -1369,6 +1770,20 @@ impl From<async_openai::types::responses::ResponseUsage> for GatewayModelUsage {
 }
 
 impl GatewayModelUsage {
+    /// Input tokens read from the provider's prompt cache, included in `input_tokens`
+    pub fn cached_input_tokens(&self) -> u32 {
+        self.prompt_tokens_details
+            .as_ref()
+            .map_or(0, |p| p.cached_tokens())
+    }
+
+    /// Input tokens written to the provider's prompt cache, included in `input_tokens`
+    pub fn cache_write_tokens(&self) -> u32 {
+        self.prompt_tokens_details
+            .as_ref()
+            .map_or(0, |p| p.cache_creation_tokens())
+    }
+
     pub fn add_usage(&mut self, other: &Self) {
         self.input_tokens += other.input_tokens;
         self.output_tokens += other.output_tokens;
@@ -1572,6 +1987,45 @@ pub struct CostCalculationResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub per_image_cost: Option<ImageCostCalculationResult>,
     pub is_cache_used: bool,
+    /// Billing entity own credentials usage is charged back to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub billing_label: Option<String>,
+    /// Cost per usage component, for token priced models
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breakdown: Option<CostBreakdown>,
+}
+
+/// Cost of each usage component, adding up to the total cost
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct CostBreakdown {
+    /// Input tokens that weren't read from or written to the prompt cache
+    pub input_cost: f64,
+    /// Visible output tokens, reasoning tokens excluded
+    pub output_cost: f64,
+    /// Input tokens read from or written to the prompt cache
+    pub cached_cost: f64,
+    /// Output tokens spent on reasoning
+    pub reasoning_cost: f64,
+}
+
+impl CostBreakdown {
+    pub fn total(&self) -> f64 {
+        self.input_cost + self.output_cost + self.cached_cost + self.reasoning_cost
+    }
+
+    pub fn add(&mut self, other: &Self) {
+        self.input_cost += other.input_cost;
+        self.output_cost += other.output_cost;
+        self.cached_cost += other.cached_cost;
+        self.reasoning_cost += other.reasoning_cost;
+    }
+}
+
+impl CostCalculationResult {
+    pub fn with_billing_label(mut self, credentials_ident: &CredentialsIdent) -> Self {
+        self.billing_label = credentials_ident.billing_label().map(|l| l.to_string());
+        self
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -233,11 +233,13 @@ impl ModelInstance for DummyModelInstance {
                                     content: Some(chunk.to_owned()),
                                     role: Some("assistant".to_string()),
                                     tool_calls: None,
+                                    reasoning_content: None,
                                 },
                                 finish_reason: Some("stop".to_string()),
                                 logprobs: None,
                             }],
                             usage: None,
+                            tool_event: None,
                         }))
                         .await
                         .ok();
@@ -254,6 +256,7 @@ impl ModelInstance for DummyModelInstance {
                     finish_reason: ModelFinishReason::Stop,
                     tool_calls: vec![],
                     credentials_ident: CredentialsIdent::Own,
+                    rate_limit: None,
                 }),
             )))
             .await
@@ -302,6 +305,27 @@ mod tests {
         assert_eq!("test", response.model);
     }
 
+    #[tokio::test]
+    async fn test_create_via_stream_matches_create() {
+        let client = CompletionsClient::new(CompletionEngineParamsBuilder::new())
+            .with_instance(Box::new(DummyModelInstance {}));
+        let request = ChatCompletionRequest {
+            model: "test".to_string(),
+            messages: vec![ChatCompletionMessage {
+                role: "user".to_string(),
+                content: Some(ChatCompletionContent::Text("Hello, world!".to_string())),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let created = client.create(request.clone()).await.unwrap();
+        let assembled = client.create_via_stream(request, None).await.unwrap();
+
+        assert_eq!(assembled.message(), created.message());
+        assert_eq!(assembled.finish_reason(), created.finish_reason());
+    }
+
     #[tokio::test]
     async fn test_create_stream() {
         let client = CompletionsClient::new(CompletionEngineParamsBuilder::new())
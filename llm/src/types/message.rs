@@ -55,6 +55,9 @@ pub struct Message {
     pub tool_calls: Option<Vec<ToolCall>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<String>,
+    /// Speaker of the message, for providers without a name field see [`Message::fold_name`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
 }
 
 impl Message {
@@ -65,6 +68,37 @@ impl Message {
             && self.r#type == other.r#type
             && self.tool_call_id == other.tool_call_id
             && self.tool_calls == other.tool_calls
+            && self.name == other.name
+    }
+
+    /// Moves the speaker's name into the content as a `Name: ` prefix, for providers that
+    /// don't accept a name on messages
+    pub fn fold_name(mut self) -> Self {
+        let Some(name) = self.name.take() else {
+            return self;
+        };
+        let prefix = format!("{name}: ");
+        if self.content_array.is_empty() {
+            self.content = Some(format!("{prefix}{}", self.content.unwrap_or_default()));
+        } else if let Some(part) = self
+            .content_array
+            .iter_mut()
+            .find(|part| part.r#type == MessageContentType::Text)
+        {
+            part.value.insert_str(0, &prefix);
+        } else {
+            self.content_array.insert(
+                0,
+                MessageContentPart {
+                    r#type: MessageContentType::Text,
+                    value: prefix,
+                    additional_options: None,
+                    cache_control: None,
+                    file: None,
+                },
+            );
+        }
+        self
     }
 }
 
@@ -84,6 +118,8 @@ impl<'de> Deserialize<'de> for Message {
             r#type: MessageType,
             tool_call_id: Option<String>,
             tool_calls: Option<serde_json::Value>,
+            #[serde(default)]
+            name: Option<String>,
         }
 
         let helper = Helper::deserialize(deserializer)?;
@@ -105,6 +141,7 @@ impl<'de> Deserialize<'de> for Message {
             tool_call_id: helper.tool_call_id,
             tool_calls: tool_calls.and_then(|v| serde_json::from_value(v).ok()),
             created_at: None,
+            name: helper.name,
         })
     }
 }
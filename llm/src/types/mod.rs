@@ -2,6 +2,7 @@ use crate::types::credentials_ident::CredentialsIdent;
 use crate::types::events::CustomEventType;
 use crate::types::gateway::{FunctionCall, ToolCall};
 use crate::types::gateway::{GatewayModelUsage, ImageSize};
+use crate::types::rate_limit::ProviderRateLimit;
 use chrono::{DateTime, Utc};
 use opentelemetry::trace::TraceContextExt;
 use serde::{Deserialize, Serialize};
@@ -17,7 +18,9 @@ pub mod gateway;
 pub mod instance;
 pub mod message;
 pub mod models;
+pub mod prompts;
 pub mod provider;
+pub mod rate_limit;
 pub mod tools;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -63,7 +66,9 @@ pub enum ModelEventType {
     LlmStart(LLMStartEvent),
     LlmFirstToken(LLMFirstToken),
     LlmContent(LLMContentEvent),
+    LlmReasoning(LLMReasoningEvent),
     LlmStop(LLMFinishEvent),
+    LlmInterimUsage(LLMInterimUsageEvent),
     ToolStart(ToolStartEvent),
     ToolResult(ToolResultEvent),
     ImageGenerationFinish(ImageGenerationFinishEvent),
@@ -77,7 +82,9 @@ impl ModelEventType {
             ModelEventType::RunError(_) => "run_error",
             ModelEventType::LlmStart(_) => "llm_start",
             ModelEventType::LlmContent(_) => "llm_content",
+            ModelEventType::LlmReasoning(_) => "llm_reasoning",
             ModelEventType::LlmStop(_) => "llm_stop",
+            ModelEventType::LlmInterimUsage(_) => "llm_interim_usage",
             ModelEventType::ToolStart(_) => "tool_start",
             ModelEventType::ToolResult(_) => "tool_result",
             ModelEventType::ImageGenerationFinish(_) => "image_generation_finish",
@@ -130,6 +137,13 @@ pub struct LLMContentEvent {
     pub content: String,
 }
 
+/// Reasoning (thinking) delta of a reasoning model, streamed apart from the answer.
+/// It is not part of the response content.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LLMReasoningEvent {
+    pub content: String,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 
 pub struct LLMStartEvent {
@@ -152,6 +166,19 @@ pub struct LLMFinishEvent {
     pub finish_reason: ModelFinishReason,
     pub tool_calls: Vec<ModelToolCall>,
     pub credentials_ident: CredentialsIdent,
+    /// Provider rate limits reported with this response. Not serialized, they are only
+    /// meaningful at the time of the call.
+    #[serde(skip)]
+    pub rate_limit: Option<ProviderRateLimit>,
+}
+
+/// Running output token count emitted while a response streams.
+/// `estimated` values are approximations; the last event before `LlmStop` carries
+/// the provider reported usage when the provider returns one.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LLMInterimUsageEvent {
+    pub output_tokens: u32,
+    pub estimated: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, Hash, PartialEq, Eq)]
@@ -225,6 +252,25 @@ impl std::fmt::Display for ModelFinishReason {
     }
 }
 
+impl From<&str> for ModelFinishReason {
+    fn from(value: &str) -> Self {
+        match value {
+            "stop" => ModelFinishReason::Stop,
+            "stop_sequence" => ModelFinishReason::StopSequence,
+            "length" => ModelFinishReason::Length,
+            "tool_calls" => ModelFinishReason::ToolCalls,
+            "content_filter" => ModelFinishReason::ContentFilter,
+            "guardrail" => ModelFinishReason::Guardrail,
+            "error" => ModelFinishReason::Error,
+            "in_progress" => ModelFinishReason::InProgress,
+            "incomplete" => ModelFinishReason::Incomplete,
+            "queued" => ModelFinishReason::Queued,
+            "cancelled" => ModelFinishReason::Cancelled,
+            other => ModelFinishReason::Other(other.to_string()),
+        }
+    }
+}
+
 impl From<ModelFinishReason> for async_openai::types::chat::FinishReason {
     fn from(val: ModelFinishReason) -> async_openai::types::chat::FinishReason {
         match val {
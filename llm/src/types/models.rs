@@ -199,6 +199,9 @@ impl Default for ModelMetadata {
                 per_cached_input_token: None,
                 per_cached_input_write_token: None,
                 valid_from: None,
+                per_batch_input_token: None,
+                per_batch_output_token: None,
+                per_service_tier: None,
             }),
             input_formats: Vec::new(),
             output_formats: Vec::new(),
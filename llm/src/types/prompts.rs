@@ -0,0 +1,73 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::types::engine::render;
+use crate::types::gateway::{ChatCompletionContent, ChatCompletionMessage};
+
+/// Reference to a stored prompt, e.g.
+/// `{"id": "support-agent", "variables": {"product": "vLLora"}}`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptReference {
+    pub id: String,
+    /// Version of the prompt, as stored with the provider or set on a gateway prompt template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub variables: HashMap<String, Value>,
+}
+
+/// Where a stored prompt reference was resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptMode {
+    /// Sent as is, the provider resolves the prompt it stores
+    Provider,
+    /// Rendered by the gateway from its own prompt templates
+    Local,
+}
+
+impl PromptMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptMode::Provider => "provider",
+            PromptMode::Local => "local",
+        }
+    }
+}
+
+/// Prompt template kept by the gateway. Text content is a template rendered with the
+/// variables of the referencing request.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Prompt {
+    /// Only references to this version, or without a version, resolve to the template
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    pub messages: Vec<ChatCompletionMessage>,
+}
+
+impl Prompt {
+    pub fn render(&self, variables: &HashMap<String, Value>) -> Vec<ChatCompletionMessage> {
+        self.messages
+            .iter()
+            .cloned()
+            .map(|mut message| {
+                message.content = message.content.map(|content| match content {
+                    ChatCompletionContent::Text(text) => {
+                        ChatCompletionContent::Text(render(text, variables))
+                    }
+                    ChatCompletionContent::Content(parts) => ChatCompletionContent::Content(
+                        parts
+                            .into_iter()
+                            .map(|mut part| {
+                                part.text = part.text.map(|text| render(text, variables));
+                                part
+                            })
+                            .collect(),
+                    ),
+                });
+                message
+            })
+            .collect()
+    }
+}
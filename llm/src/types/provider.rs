@@ -1,6 +1,7 @@
 use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
+use super::gateway::ServiceTier;
 use super::models::ModelType;
 use std::collections::HashMap;
 
@@ -78,6 +79,24 @@ impl ModelPrice {
             ModelPrice::ImageGeneration(_) => 0.0,
         }
     }
+
+    /// Prices charged for requests submitted through a provider batch job
+    pub fn batch(&self) -> ModelPrice {
+        match self {
+            ModelPrice::Completion(price) => ModelPrice::Completion(price.batch()),
+            other => other.clone(),
+        }
+    }
+
+    /// Prices charged for requests processed in `service_tier`
+    pub fn for_service_tier(&self, service_tier: Option<ServiceTier>) -> ModelPrice {
+        match (self, service_tier) {
+            (ModelPrice::Completion(price), Some(service_tier)) => {
+                ModelPrice::Completion(price.for_service_tier(service_tier))
+            }
+            (other, _) => other.clone(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -96,6 +115,68 @@ pub struct CompletionModelPrice {
     pub per_cached_input_write_token: Option<f64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub valid_from: Option<NaiveDate>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_batch_input_token: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_batch_output_token: Option<f64>,
+    /// Prices by service tier name, e.g. `priority` or `flex`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_service_tier: Option<HashMap<String, ServiceTierPrice>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceTierPrice {
+    pub per_input_token: f64,
+    pub per_output_token: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub per_cached_input_token: Option<f64>,
+}
+
+/// Share of the regular price charged for batch requests when a model has no batch prices
+pub const DEFAULT_BATCH_PRICE_RATIO: f64 = 0.5;
+
+impl CompletionModelPrice {
+    /// Batch prices of the model. Unset batch prices fall back to the regular prices with
+    /// the batch discount applied, cached token prices are always discounted.
+    pub fn batch(&self) -> CompletionModelPrice {
+        CompletionModelPrice {
+            per_input_token: self
+                .per_batch_input_token
+                .unwrap_or(self.per_input_token * DEFAULT_BATCH_PRICE_RATIO),
+            per_output_token: self
+                .per_batch_output_token
+                .unwrap_or(self.per_output_token * DEFAULT_BATCH_PRICE_RATIO),
+            per_cached_input_token: self
+                .per_cached_input_token
+                .map(|p| p * DEFAULT_BATCH_PRICE_RATIO),
+            per_cached_input_write_token: self
+                .per_cached_input_write_token
+                .map(|p| p * DEFAULT_BATCH_PRICE_RATIO),
+            valid_from: self.valid_from,
+            per_batch_input_token: self.per_batch_input_token,
+            per_batch_output_token: self.per_batch_output_token,
+            per_service_tier: self.per_service_tier.clone(),
+        }
+    }
+
+    /// Prices of `service_tier`, the regular prices when the model has none for it
+    pub fn for_service_tier(&self, service_tier: ServiceTier) -> CompletionModelPrice {
+        let Some(tier_price) = self
+            .per_service_tier
+            .as_ref()
+            .and_then(|prices| prices.get(service_tier.as_str()))
+        else {
+            return self.clone();
+        };
+        CompletionModelPrice {
+            per_input_token: tier_price.per_input_token,
+            per_output_token: tier_price.per_output_token,
+            per_cached_input_token: tier_price
+                .per_cached_input_token
+                .or(self.per_cached_input_token),
+            ..self.clone()
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -113,3 +194,53 @@ pub struct AvailableModel {
     pub model_type: Option<ModelType>,
     pub provider: InferenceModelProvider,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(per_batch_input_token: Option<f64>) -> CompletionModelPrice {
+        CompletionModelPrice {
+            per_input_token: 3.0,
+            per_output_token: 15.0,
+            per_cached_input_token: Some(0.3),
+            per_cached_input_write_token: None,
+            valid_from: None,
+            per_batch_input_token,
+            per_batch_output_token: None,
+            per_service_tier: None,
+        }
+    }
+
+    #[test]
+    fn test_batch_price_defaults_to_discount() {
+        let batch = price(None).batch();
+        assert_eq!(batch.per_input_token, 1.5);
+        assert_eq!(batch.per_output_token, 7.5);
+        assert_eq!(batch.per_cached_input_token, Some(0.15));
+
+        assert_eq!(price(Some(1.0)).batch().per_input_token, 1.0);
+    }
+
+    #[test]
+    fn test_service_tier_price_falls_back_to_regular_price() {
+        let price = CompletionModelPrice {
+            per_service_tier: Some(HashMap::from([(
+                "priority".to_string(),
+                ServiceTierPrice {
+                    per_input_token: 5.0,
+                    per_output_token: 25.0,
+                    per_cached_input_token: None,
+                },
+            )])),
+            ..price(None)
+        };
+
+        let priority = price.for_service_tier(ServiceTier::Priority);
+        assert_eq!(priority.per_input_token, 5.0);
+        assert_eq!(priority.per_output_token, 25.0);
+        assert_eq!(priority.per_cached_input_token, Some(0.3));
+
+        assert_eq!(price.for_service_tier(ServiceTier::Flex), price);
+    }
+}
@@ -0,0 +1,141 @@
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+
+/// Prefix of the normalized rate limit headers set on gateway responses
+pub const RATE_LIMIT_HEADER_PREFIX: &str = "x-vllora-ratelimit-";
+
+/// Rate limit state a provider reported in its response headers.
+///
+/// Understands the OpenAI style `x-ratelimit-{limit,remaining,reset}-{requests,tokens}`
+/// headers, used by most OpenAI compatible providers, and Anthropic's
+/// `anthropic-ratelimit-{requests,tokens}-{limit,remaining,reset}`. Resets are kept as
+/// reported, e.g. `6m0s` or an RFC 3339 timestamp.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProviderRateLimit {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_requests: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_requests: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_requests: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_tokens: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reset_tokens: Option<String>,
+    /// Seconds from `retry-after`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+}
+
+fn header<'a>(headers: &'a HeaderMap, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .find_map(|name| headers.get(*name))
+        .and_then(|v| v.to_str().ok())
+        .map(str::trim)
+}
+
+fn number(headers: &HeaderMap, names: &[&str]) -> Option<u64> {
+    header(headers, names).and_then(|v| v.parse().ok())
+}
+
+fn text(headers: &HeaderMap, names: &[&str]) -> Option<String> {
+    header(headers, names).map(ToString::to_string)
+}
+
+impl ProviderRateLimit {
+    /// Rate limits found in `headers`, `None` when the provider sent none
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let rate_limit = Self {
+            limit_requests: number(
+                headers,
+                &[
+                    "x-ratelimit-limit-requests",
+                    "anthropic-ratelimit-requests-limit",
+                ],
+            ),
+            remaining_requests: number(
+                headers,
+                &[
+                    "x-ratelimit-remaining-requests",
+                    "anthropic-ratelimit-requests-remaining",
+                ],
+            ),
+            reset_requests: text(
+                headers,
+                &[
+                    "x-ratelimit-reset-requests",
+                    "anthropic-ratelimit-requests-reset",
+                ],
+            ),
+            limit_tokens: number(
+                headers,
+                &[
+                    "x-ratelimit-limit-tokens",
+                    "anthropic-ratelimit-tokens-limit",
+                ],
+            ),
+            remaining_tokens: number(
+                headers,
+                &[
+                    "x-ratelimit-remaining-tokens",
+                    "anthropic-ratelimit-tokens-remaining",
+                ],
+            ),
+            reset_tokens: text(
+                headers,
+                &[
+                    "x-ratelimit-reset-tokens",
+                    "anthropic-ratelimit-tokens-reset",
+                ],
+            ),
+            retry_after: number(headers, &["retry-after"]),
+        };
+
+        (rate_limit != Self::default()).then_some(rate_limit)
+    }
+
+    /// Smallest share of the request or token quota left, from 0.0 to 1.0
+    pub fn remaining_ratio(&self) -> Option<f64> {
+        [
+            (self.remaining_requests, self.limit_requests),
+            (self.remaining_tokens, self.limit_tokens),
+        ]
+        .into_iter()
+        .filter_map(|(remaining, limit)| match (remaining, limit) {
+            (Some(remaining), Some(limit)) if limit > 0 => {
+                Some((remaining as f64 / limit as f64).min(1.0))
+            }
+            _ => None,
+        })
+        .reduce(f64::min)
+    }
+
+    /// Normalized `x-vllora-ratelimit-*` headers for the gateway response
+    pub fn response_headers(&self) -> Vec<(String, String)> {
+        let to_string = |v: &u64| v.to_string();
+        [
+            (
+                "limit-requests",
+                self.limit_requests.as_ref().map(to_string),
+            ),
+            (
+                "remaining-requests",
+                self.remaining_requests.as_ref().map(to_string),
+            ),
+            ("reset-requests", self.reset_requests.clone()),
+            ("limit-tokens", self.limit_tokens.as_ref().map(to_string)),
+            (
+                "remaining-tokens",
+                self.remaining_tokens.as_ref().map(to_string),
+            ),
+            ("reset-tokens", self.reset_tokens.clone()),
+            ("retry-after", self.retry_after.as_ref().map(to_string)),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| Some((format!("{RATE_LIMIT_HEADER_PREFIX}{name}"), value?)))
+        .collect()
+    }
+}
@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::error::LLMResult;
 use crate::types::gateway::FunctionParameters;
@@ -9,6 +10,15 @@ pub trait Tool: Send + Sync + 'static {
     fn name(&self) -> String;
     fn description(&self) -> String;
     fn get_function_parameters(&self) -> Option<FunctionParameters>;
+    /// JSON schema that tool call arguments are validated against before `run`.
+    fn parameters_schema(&self) -> Option<serde_json::Value> {
+        self.get_function_parameters()
+            .and_then(|parameters| serde_json::to_value(parameters).ok())
+    }
+    /// Strict tools reject arguments that are not declared in the schema.
+    fn strict(&self) -> bool {
+        false
+    }
     async fn run(
         &self,
         input: HashMap<String, serde_json::Value>,
@@ -17,6 +27,11 @@ pub trait Tool: Send + Sync + 'static {
     fn stop_at_call(&self) -> bool {
         false
     }
+    /// Tools without side effects can return a TTL to have identical calls
+    /// (same name and arguments) within a run served from cache.
+    fn cache_ttl(&self) -> Option<Duration> {
+        None
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
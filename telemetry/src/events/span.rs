@@ -16,6 +16,10 @@ macro_rules! create_model_span {
             raw_usage = field::Empty,
             ttft = field::Empty,
             cost = field::Empty,
+            rate_limit = field::Empty,
+            upstream_provider = field::Empty,
+            tool_iterations = field::Empty,
+            dropped_params = field::Empty,
             $($field_name = $field_value,)*
         )
     }};
@@ -124,6 +128,16 @@ macro_rules! create_model_invoke_span {
             ttft = tracing::field::Empty,
             tags = $crate::events::JsonValue(&serde_json::to_value($tags.clone()).unwrap_or_default()).as_value(),
             cache = tracing::field::Empty,
+            role_normalization = tracing::field::Empty,
+            cancelled = tracing::field::Empty,
+            service_tier = tracing::field::Empty,
+            dropped_params = tracing::field::Empty,
+            reasoning_effort = tracing::field::Empty,
+            stop_sequence = tracing::field::Empty,
+            json_repair_attempts = tracing::field::Empty,
+            required_language = tracing::field::Empty,
+            detected_language = tracing::field::Empty,
+            language_regenerations = tracing::field::Empty,
         )
     }};
 
@@ -145,6 +159,16 @@ macro_rules! create_model_invoke_span {
             ttft = tracing::field::Empty,
             tags = $crate::events::JsonValue(&serde_json::to_value($tags.clone()).unwrap_or_default()).as_value(),
             cache = tracing::field::Empty,
+            role_normalization = tracing::field::Empty,
+            cancelled = tracing::field::Empty,
+            service_tier = tracing::field::Empty,
+            dropped_params = tracing::field::Empty,
+            reasoning_effort = tracing::field::Empty,
+            stop_sequence = tracing::field::Empty,
+            json_repair_attempts = tracing::field::Empty,
+            required_language = tracing::field::Empty,
+            detected_language = tracing::field::Empty,
+            language_regenerations = tracing::field::Empty,
         )
     }};
 
@@ -166,6 +190,16 @@ macro_rules! create_model_invoke_span {
             ttft = tracing::field::Empty,
             tags = tracing::field::Empty,
             cache = tracing::field::Empty,
+            role_normalization = tracing::field::Empty,
+            cancelled = tracing::field::Empty,
+            service_tier = tracing::field::Empty,
+            dropped_params = tracing::field::Empty,
+            reasoning_effort = tracing::field::Empty,
+            stop_sequence = tracing::field::Empty,
+            json_repair_attempts = tracing::field::Empty,
+            required_language = tracing::field::Empty,
+            detected_language = tracing::field::Empty,
+            language_regenerations = tracing::field::Empty,
         )
     }};
 }
@@ -1,6 +1,7 @@
 pub mod baggage;
 pub mod events;
 pub mod metrics_service;
+pub mod test_utils;
 pub use metrics_service::{MetricsDataPoint, MetricsServiceImpl, MetricsWriterTransport};
 
 // Span creation macros are exported via #[macro_export] in events/span.rs
@@ -311,6 +312,10 @@ pub fn map_span(span: SpanData) -> Option<Span> {
         }
     }
 
+    if let Some(parent_run_id) = attributes.remove("vllora.parent_run_id") {
+        attributes.insert("parent_run_id".to_string(), parent_run_id);
+    }
+
     let tags_value = attributes.remove("tags");
     let mut tags: serde_json::Map<String, Value> = Default::default();
     if let Some(Value::String(s)) = tags_value {
@@ -439,6 +444,10 @@ impl TraceService for TraceServiceImpl {
                         }
                     }
 
+                    if let Some(parent_run_id) = attributes.remove("vllora.parent_run_id") {
+                        attributes.insert("parent_run_id".to_string(), parent_run_id);
+                    }
+
                     let tags_value = attributes.remove("tags");
                     let mut tags: serde_json::Map<String, Value> = Default::default();
                     if let Some(Value::String(s)) = tags_value {
@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::subscriber::DefaultGuard;
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+/// Span seen by a [`RecordingLayer`]
+#[derive(Debug, Clone)]
+pub struct RecordedSpan {
+    pub name: &'static str,
+    pub parent: Option<&'static str>,
+    /// Fields set when the span was created or recorded later, in order
+    pub fields: Vec<(&'static str, String)>,
+}
+
+impl RecordedSpan {
+    /// Last value recorded for `field`
+    pub fn field(&self, field: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .rev()
+            .find(|(name, _)| *name == field)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Event seen by a [`RecordingLayer`]
+#[derive(Debug, Clone)]
+pub struct RecordedEvent {
+    pub target: String,
+    pub level: Level,
+    pub fields: HashMap<&'static str, String>,
+}
+
+#[derive(Default)]
+struct Recorded {
+    spans: Vec<RecordedSpan>,
+    span_indexes: HashMap<Id, usize>,
+    events: Vec<RecordedEvent>,
+    values: Vec<(&'static str, String)>,
+}
+
+/// Layer recording spans, the values of their fields and events, for tests of what gets
+/// traced. Strings are recorded as is, other values with their `Debug` output.
+#[derive(Clone, Default)]
+pub struct RecordingLayer(Arc<Mutex<Recorded>>);
+
+impl RecordingLayer {
+    /// Records everything traced on the current thread until the guard is dropped
+    pub fn set_default(&self) -> DefaultGuard {
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(self.clone()))
+    }
+
+    /// Values of `field` on spans and events, in the order they were recorded
+    pub fn values(&self, field: &str) -> Vec<String> {
+        self.0
+            .lock()
+            .unwrap()
+            .values
+            .iter()
+            .filter(|(name, _)| *name == field)
+            .map(|(_, value)| value.clone())
+            .collect()
+    }
+
+    pub fn spans(&self) -> Vec<RecordedSpan> {
+        self.0.lock().unwrap().spans.clone()
+    }
+
+    pub fn events(&self) -> Vec<RecordedEvent> {
+        self.0.lock().unwrap().events.clone()
+    }
+}
+
+#[derive(Default)]
+struct FieldValues(Vec<(&'static str, String)>);
+
+impl Visit for FieldValues {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0.push((field.name(), format!("{value:?}")));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordingLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut values = FieldValues::default();
+        attrs.record(&mut values);
+        let parent = ctx
+            .span(id)
+            .and_then(|span| span.parent().map(|parent| parent.name()));
+
+        let mut recorded = self.0.lock().unwrap();
+        recorded.values.extend(values.0.iter().cloned());
+        let index = recorded.spans.len();
+        recorded.span_indexes.insert(id.clone(), index);
+        recorded.spans.push(RecordedSpan {
+            name: attrs.metadata().name(),
+            parent,
+            fields: values.0,
+        });
+    }
+
+    fn on_record(&self, id: &Id, record: &Record<'_>, _ctx: Context<'_, S>) {
+        let mut values = FieldValues::default();
+        record.record(&mut values);
+
+        let mut recorded = self.0.lock().unwrap();
+        recorded.values.extend(values.0.iter().cloned());
+        if let Some(index) = recorded.span_indexes.get(id).copied() {
+            recorded.spans[index].fields.extend(values.0);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut values = FieldValues::default();
+        event.record(&mut values);
+
+        let mut recorded = self.0.lock().unwrap();
+        recorded.values.extend(values.0.iter().cloned());
+        recorded.events.push(RecordedEvent {
+            target: event.metadata().target().to_string(),
+            level: *event.metadata().level(),
+            fields: values.0.into_iter().collect(),
+        });
+    }
+}